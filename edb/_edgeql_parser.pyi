@@ -3,7 +3,7 @@ import typing
 class SyntaxError(Exception): ...
 
 class ParserResult:
-    out: typing.Optional[CSTNode | list[OpaqueToken]]
+    out: typing.Optional[CSTNode | TokenBuffer | list[OpaqueToken]]
     errors: list[
         tuple[
             str,
@@ -42,14 +42,39 @@ class Entry:
 
 def normalize(text: str) -> Entry: ...
 def parse(
-    start_token_name: str, tokens: list[OpaqueToken]
+    start_token_name: str,
+    tokens: TokenBuffer | list[OpaqueToken],
+    *,
+    tolerant: bool = False,
+) -> tuple[
+    ParserResult, list[tuple[type, typing.Callable]]
+]: ...
+def parse_to_buffer(
+    start_token_name: str,
+    tokens: TokenBuffer | list[OpaqueToken],
+    *,
+    tolerant: bool = False,
 ) -> tuple[
     ParserResult, list[tuple[type, typing.Callable]]
 ]: ...
 def suggest_next_keywords(
-    start_token_name: str, tokens: list[OpaqueToken]
+    start_token_name: str, tokens: TokenBuffer | list[OpaqueToken]
 ) -> tuple[list[str], bool]: ...
 def preload_spec(spec_filepath: str) -> None: ...
+def render_errors(
+    source: str,
+    filename: str,
+    errors: list[
+        tuple[
+            str,
+            tuple[int, typing.Optional[int]],
+            typing.Optional[str],
+            typing.Optional[str],
+        ]
+    ],
+    *,
+    json: bool = False,
+) -> str: ...
 def save_spec(spec_json: str, dst: str) -> None: ...
 
 class CSTNode:
@@ -67,6 +92,7 @@ class Terminal:
     value: typing.Any
     start: int
     end: int
+    is_placeholder: bool
 
 class SourcePoint:
     line: int
@@ -94,6 +120,15 @@ class OpaqueToken:
     def span_end(self) -> int: ...
     def is_ident(self) -> bool: ...
 
+class TokenBuffer:
+    def __len__(self) -> int: ...
+    @typing.overload
+    def __getitem__(self, index: int) -> OpaqueToken: ...
+    @typing.overload
+    def __getitem__(self, index: slice) -> TokenBuffer: ...
+    def __buffer__(self, flags: int) -> memoryview: ...
+
 def tokenize(s: str) -> ParserResult: ...
+def tokenize_buffer(s: str) -> ParserResult: ...
 def unpickle_token(bytes: bytes) -> OpaqueToken: ...
 def unpack(serialized: bytes) -> Entry | list[OpaqueToken]: ...
@@ -0,0 +1,219 @@
+//! Compile-time validated EdgeQL queries
+//!
+//! ```ignore
+//! use edgeql_parser_macros::edgeql;
+//!
+//! // a checked `&'static str`
+//! const COUNT: &str = edgeql!("select count(User)");
+//!
+//! // a struct with a field per parameter and the query in `QUERY`
+//! edgeql!(pub struct GetUser = "select User filter .name = <str>$name");
+//! let args = GetUser { name: "alice".into() };
+//! client.query(GetUser::QUERY, &args);
+//! ```
+//!
+//! Queries are tokenized at compile time and fully parsed if the
+//! `EDGEQL_GRAMMAR_SPEC` variable points to the grammar spec (`grammar.bc`)
+//! generated by the EdgeDB build, errors fail the build. Cargo recompiles
+//! the queries when the variable or the spec change.
+//!
+//! Parameters must be named and cast to a type. Standard scalars are mapped
+//! as in [edgeql_parser::codegen::rust] (`uuid` needs the `uuid` crate),
+//! arrays become `Vec`s and optional parameters become `Option`s. Other
+//! types are passed as `String`: `json` as the JSON text and custom scalars
+//! (including enums) as the text of the value, the server casts them.
+use std::sync::OnceLock;
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::quote;
+use syn::parse::{Parse, ParseStream};
+use syn::{parse_macro_input, Ident, LitStr, Token, Type, Visibility};
+
+use edgeql_parser::codegen::rust::{builtin_type, field_name};
+use edgeql_parser::params::{query_params, Param};
+use edgeql_parser::parser::{self, Spec, SpecSerializable, Terminal};
+use edgeql_parser::position::{InflatedPos, Span as QuerySpan};
+use edgeql_parser::tokenizer::{Error, Tokenizer};
+
+enum Input {
+    /// `"select ..."`
+    Query(LitStr),
+    /// `pub struct Name = "select ..."`
+    Struct {
+        vis: Visibility,
+        name: Ident,
+        query: LitStr,
+    },
+}
+
+impl Parse for Input {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.peek(LitStr) {
+            return Ok(Input::Query(input.parse()?));
+        }
+        let vis = input.parse()?;
+        input.parse::<Token![struct]>()?;
+        let name = input.parse()?;
+        input.parse::<Token![=]>()?;
+        let query = input.parse()?;
+        Ok(Input::Struct { vis, name, query })
+    }
+}
+
+/// Checks the EdgeQL query at compile time
+///
+/// `edgeql!("...")` is the query as a `&'static str`, `edgeql!(pub struct
+/// Name = "...")` declares a struct with the parameters of the query as
+/// fields and the query as `Name::QUERY`.
+#[proc_macro]
+pub fn edgeql(input: TokenStream) -> TokenStream {
+    match parse_macro_input!(input as Input) {
+        Input::Query(query) => match check(&query) {
+            Ok(_) => {
+                let tracking = tracking();
+                quote!({ #tracking #query }).into()
+            }
+            Err(e) => e.into_compile_error().into(),
+        },
+        Input::Struct { vis, name, query } => {
+            let tracking = tracking();
+            match check(&query).and_then(|params| fields(&query, &params)) {
+                Ok((names, idents, types)) => quote! {
+                    #tracking
+
+                    #[derive(Debug, Clone, PartialEq)]
+                    #vis struct #name {
+                        #(pub #idents: #types,)*
+                    }
+
+                    impl #name {
+                        pub const QUERY: &'static str = #query;
+                        /// Names of the parameters, in the order of the fields
+                        pub const PARAMS: &'static [&'static str] = &[#(#names),*];
+                    }
+                }
+                .into(),
+                Err(e) => e.into_compile_error().into(),
+            }
+        }
+    }
+}
+
+/// Validates the query, returns its parameters
+fn check(query: &LitStr) -> syn::Result<Vec<Param>> {
+    let text = query.value();
+    let to_syn = |e: Error| syn::Error::new(query.span(), describe(&text, &e));
+    let params = query_params(&text).map_err(to_syn)?;
+    if let Some(spec) = spec()? {
+        let mut terminals = vec![Terminal::from_start_name("STARTBLOCK")];
+        for token in Tokenizer::new(&text).validated_values().with_eof() {
+            terminals.push(Terminal::from_token(token.map_err(to_syn)?));
+        }
+        let ctx = parser::Context::new(spec);
+        let (_, errors) = parser::parse(&terminals, &ctx);
+        if let Some(error) = errors.into_iter().next() {
+            return Err(to_syn(error));
+        }
+    }
+    Ok(params)
+}
+
+/// The grammar spec, read once per compilation
+fn spec() -> syn::Result<Option<&'static Spec>> {
+    static SPEC: OnceLock<Result<Option<Spec>, String>> = OnceLock::new();
+    let spec = SPEC.get_or_init(|| {
+        let Ok(path) = std::env::var("EDGEQL_GRAMMAR_SPEC") else {
+            return Ok(None);
+        };
+        let bytes =
+            std::fs::read(&path).map_err(|e| format!("cannot read grammar spec {path}: {e}"))?;
+        let spec = bincode::deserialize::<SpecSerializable>(&bytes)
+            .map_err(|e| format!("bad grammar spec {path}: {e}"))?;
+        Ok(Some(spec.into()))
+    });
+    spec.as_ref()
+        .map(Option::as_ref)
+        .map_err(|message| syn::Error::new(Span::call_site(), message))
+}
+
+/// Items making cargo recompile the expansion when the variable or the
+/// spec change, since reading them from a proc macro is not tracked (the
+/// tracking API of `proc_macro` is unstable)
+fn tracking() -> proc_macro2::TokenStream {
+    let path = std::env::var("EDGEQL_GRAMMAR_SPEC")
+        .ok()
+        .and_then(|path| std::fs::canonicalize(path).ok())
+        .and_then(|path| path.to_str().map(String::from));
+    let spec = path.map(|path| quote! { const _: &[u8] = include_bytes!(#path); });
+    quote! {
+        const _: Option<&str> = option_env!("EDGEQL_GRAMMAR_SPEC");
+        #spec
+    }
+}
+
+/// Error message with the position in the query, as spans inside of string
+/// literals are not available to proc macros on stable Rust
+fn describe(text: &str, error: &Error) -> String {
+    let mut message = error.message.clone();
+    if error.span != QuerySpan::default() {
+        if let Ok(pos) = InflatedPos::from_offset(text.as_bytes(), error.span.start) {
+            let pos = pos.deflate();
+            message = format!("{message} (at {}:{})", pos.line, pos.column);
+        }
+    }
+    if let Some(hint) = &error.hint {
+        message = format!("{message}\nhint: {hint}");
+    }
+    message
+}
+
+type Fields = (Vec<String>, Vec<Ident>, Vec<Type>);
+
+fn fields(query: &LitStr, params: &[Param]) -> syn::Result<Fields> {
+    let mut fields: Fields = Default::default();
+    for param in params {
+        let error = |message: String| syn::Error::new(query.span(), message);
+        if param.is_positional() {
+            return Err(error(format!(
+                "positional parameter ${} is not supported, use named ones",
+                param.name
+            )));
+        }
+        let Some(type_name) = &param.type_name else {
+            return Err(error(format!(
+                "parameter ${} must be cast to a type, e.g. <str>${}",
+                param.name, param.name
+            )));
+        };
+        let mut rust = rust_type(type_name);
+        if param.optional {
+            rust = format!("Option<{rust}>");
+        }
+        let ty = syn::parse_str::<Type>(&rust)
+            .map_err(|e| error(format!("unsupported type of ${}: {e}", param.name)))?;
+        let ident = syn::parse_str::<Ident>(&field_name(&param.name).0)
+            .map_err(|e| error(format!("unsupported name ${}: {e}", param.name)))?;
+        fields.0.push(param.name.clone());
+        fields.1.push(ident);
+        fields.2.push(ty);
+    }
+    Ok(fields)
+}
+
+/// Rust type of a parameter of the type, `String` for the types that have
+/// no Rust counterpart
+fn rust_type(type_name: &str) -> String {
+    let name = type_name.strip_prefix("std::").unwrap_or(type_name);
+    if let Some(inner) = name
+        .strip_prefix("array<")
+        .and_then(|n| n.strip_suffix('>'))
+    {
+        return format!("Vec<{}>", rust_type(inner));
+    }
+    match builtin_type(name) {
+        // custom scalars, enums and json
+        "serde_json::Value" => "String".into(),
+        rust => rust.into(),
+    }
+}
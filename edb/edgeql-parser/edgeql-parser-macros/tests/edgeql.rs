@@ -0,0 +1,38 @@
+use edgeql_parser_macros::edgeql;
+
+const COUNT: &str = edgeql!("select count(User) # all of them");
+
+edgeql!(pub struct FindUsers = "
+    select User { name }
+    filter .name ilike <str>$pattern
+        and .age >= <optional int32>$min_age
+        and .tags in array_unpack(<array<str>>$tags)
+        and .`type` != <str>$`type`
+    limit <int64>$limit
+");
+
+edgeql!(struct NoParams = "select 1");
+
+#[test]
+fn query() {
+    assert_eq!(COUNT, "select count(User) # all of them");
+    assert_eq!(NoParams::QUERY, "select 1");
+    assert!(NoParams::PARAMS.is_empty());
+}
+
+#[test]
+fn params() {
+    let find = FindUsers {
+        pattern: String::from("a%"),
+        min_age: None::<i32>,
+        tags: vec![String::from("admin")],
+        r#type: String::from("bot"),
+        limit: 10i64,
+    };
+    assert_eq!(find.clone(), find);
+    assert_eq!(
+        FindUsers::PARAMS,
+        ["pattern", "min_age", "tags", "type", "limit"]
+    );
+    assert!(FindUsers::QUERY.contains("<str>$pattern"));
+}
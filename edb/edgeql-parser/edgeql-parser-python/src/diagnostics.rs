@@ -0,0 +1,32 @@
+use edgeql_parser::diagnostics::{render, render_json, Diagnostic, Severity};
+use edgeql_parser::position::Span;
+use pyo3::prelude::*;
+
+/// Error tuple as in `ParserResult.errors`: message, span, hint and details
+type ErrorTuple = (String, (u64, Option<u64>), Option<String>, Option<String>);
+
+/// Renders errors of tokenizer or parser results, either for humans or as a
+/// JSON array
+#[pyfunction]
+#[pyo3(signature = (source, filename, errors, *, json = false))]
+pub fn render_errors(source: &str, filename: &str, errors: Vec<ErrorTuple>, json: bool) -> String {
+    let diagnostics = errors
+        .into_iter()
+        .map(|(message, (start, end), hint, details)| {
+            let span = Span {
+                start,
+                end: end.unwrap_or(start),
+            };
+            Diagnostic {
+                hint,
+                details,
+                ..Diagnostic::new(Severity::Error, message, span)
+            }
+        })
+        .collect::<Vec<_>>();
+    if json {
+        render_json(source, filename, &diagnostics)
+    } else {
+        render(source, filename, &diagnostics)
+    }
+}
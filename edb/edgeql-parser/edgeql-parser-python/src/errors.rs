@@ -4,7 +4,7 @@ use pyo3::prelude::*;
 use pyo3::types::{PyBytes, PyList};
 use pyo3::{create_exception, exceptions};
 
-use crate::tokenizer::OpaqueToken;
+use crate::tokenizer::{OpaqueToken, TokenBuffer};
 
 create_exception!(_edgeql_parser, SyntaxError, exceptions::PyException);
 
@@ -20,11 +20,16 @@ pub struct ParserResult {
 #[pymethods]
 impl ParserResult {
     fn pack(&self, py: Python) -> PyResult<Py<PyAny>> {
-        let tokens = self.out.downcast_bound::<PyList>(py)?;
-        let mut rv = Vec::with_capacity(tokens.len());
-        for token in tokens {
-            let token: &Bound<OpaqueToken> = token.downcast()?;
-            rv.push(token.borrow().inner.clone());
+        let mut rv = Vec::new();
+        if let Ok(buffer) = self.out.downcast_bound::<TokenBuffer>(py) {
+            rv.extend(buffer.get().tokens.iter().cloned());
+        } else {
+            let tokens = self.out.downcast_bound::<PyList>(py)?;
+            rv.reserve(tokens.len());
+            for token in tokens {
+                let token: &Bound<OpaqueToken> = token.downcast()?;
+                rv.push(token.borrow().inner.clone());
+            }
         }
         let mut buf = vec![0u8]; // type and version
         bincode::serialize_into(&mut buf, &rv)
@@ -1,4 +1,5 @@
 #![cfg(feature = "python_extension")]
+mod diagnostics;
 mod errors;
 mod hash;
 mod keywords;
@@ -25,10 +26,13 @@ fn _edgeql_parser(py: Python, m: &Bound<PyModule>) -> PyResult<()> {
     m.add("future_reserved_keywords", keywords.future)?;
     m.add("current_reserved_keywords", keywords.current)?;
 
+    m.add_function(wrap_pyfunction!(diagnostics::render_errors, m)?)?;
+
     m.add_class::<pynormalize::Entry>()?;
     m.add_function(wrap_pyfunction!(pynormalize::normalize, m)?)?;
 
     m.add_function(wrap_pyfunction!(parser::parse, m)?)?;
+    m.add_function(wrap_pyfunction!(parser::parse_to_buffer, m)?)?;
     m.add_function(wrap_pyfunction!(parser::suggest_next_keywords, m)?)?;
     m.add_function(wrap_pyfunction!(parser::preload_spec, m)?)?;
     m.add_function(wrap_pyfunction!(parser::save_spec, m)?)?;
@@ -41,6 +45,8 @@ fn _edgeql_parser(py: Python, m: &Bound<PyModule>) -> PyResult<()> {
 
     m.add_class::<tokenizer::OpaqueToken>()?;
     m.add_function(wrap_pyfunction!(tokenizer::tokenize, m)?)?;
+    m.add_class::<tokenizer::TokenBuffer>()?;
+    m.add_function(wrap_pyfunction!(tokenizer::tokenize_buffer, m)?)?;
     m.add_function(wrap_pyfunction!(tokenizer::unpickle_token, m)?)?;
 
     m.add_function(wrap_pyfunction!(unpack::unpack, m)?)?;
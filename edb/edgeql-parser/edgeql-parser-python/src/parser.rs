@@ -1,19 +1,24 @@
-use once_cell::sync::OnceCell;
+use std::sync::OnceLock;
 
 use edgeql_parser::parser;
+use edgeql_parser::soft_keywords::demote_soft_terminals;
 use pyo3::exceptions::{PyAssertionError, PyValueError};
 use pyo3::prelude::*;
-use pyo3::types::{PyList, PyString};
+use pyo3::types::{PyBytes, PyList, PyString, PyTuple};
 
 use crate::errors::{parser_error_into_tuple, ParserResult};
 use crate::pynormalize::TokenizerValue;
-use crate::tokenizer::OpaqueToken;
+use crate::tokenizer::{OpaqueToken, TokenBuffer};
 
+/// With `tolerant`, missing tokens of incomplete input are injected
+/// without reporting errors, see [parser::ParseMode::Tolerant].
 #[pyfunction]
+#[pyo3(signature = (start_token_name, tokens, *, tolerant = false))]
 pub fn parse(
     py: Python,
     start_token_name: &Bound<PyString>,
     tokens: Py<PyAny>,
+    tolerant: bool,
 ) -> PyResult<(ParserResult, &'static Py<PyAny>)> {
     let start_token_name = start_token_name.to_string();
 
@@ -21,7 +26,7 @@ pub fn parse(
 
     let tokens = downcast_tokens(py, &start_token_name, tokens)?;
 
-    let context = parser::Context::new(spec);
+    let context = parser::Context::with_mode(spec, parse_mode(tolerant));
     let (cst, errors) = parser::parse(&tokens, &context);
 
     let errors = PyList::new(py, errors.iter().map(|e| parser_error_into_tuple(e)))?;
@@ -34,6 +39,55 @@ pub fn parse(
     Ok((res, productions))
 }
 
+/// Parses like [parse], but returns the CST flattened into a buffer, see
+/// [parser::CSTBuffer]. The `out` of the result is a tuple of the buffer
+/// (bytes) and the list of terminals it refers to.
+#[pyfunction]
+#[pyo3(signature = (start_token_name, tokens, *, tolerant = false))]
+pub fn parse_to_buffer(
+    py: Python,
+    start_token_name: &Bound<PyString>,
+    tokens: Py<PyAny>,
+    tolerant: bool,
+) -> PyResult<(ParserResult, &'static Py<PyAny>)> {
+    let start_token_name = start_token_name.to_string();
+
+    let (spec, productions) = get_spec()?;
+
+    let tokens = downcast_tokens(py, &start_token_name, tokens)?;
+
+    let context = parser::Context::with_mode(spec, parse_mode(tolerant));
+    let (cst, errors) = parser::parse(&tokens, &context);
+
+    let errors = PyList::new(py, errors.iter().map(|e| parser_error_into_tuple(e)))?;
+
+    let out = match cst {
+        Some(cst) => {
+            let buffer = cst.to_buffer();
+            let terminals = buffer
+                .terminals
+                .iter()
+                .map(|t| Py::new(py, Terminal::new(py, t)?))
+                .collect::<PyResult<Vec<_>>>()?;
+            let bytes = PyBytes::new(py, &buffer.to_bytes());
+            PyTuple::new(
+                py,
+                [bytes.into_any(), PyList::new(py, terminals)?.into_any()],
+            )?
+            .into_any()
+            .unbind()
+        }
+        None => py.None(),
+    };
+
+    let res = ParserResult {
+        out,
+        errors: errors.into(),
+    };
+
+    Ok((res, productions))
+}
+
 #[pyfunction]
 pub fn suggest_next_keywords(
     py: Python,
@@ -85,15 +139,43 @@ pub struct Terminal {
     start: u64,
     #[pyo3(get)]
     end: u64,
+    /// Set for terminals that were not in the source, but were injected by
+    /// error recovery
+    #[pyo3(get)]
+    is_placeholder: bool,
 }
 
-static PARSER_SPECS: OnceCell<(parser::Spec, Py<PyAny>)> = OnceCell::new();
+impl Terminal {
+    fn new(py: Python, token: &parser::Terminal) -> PyResult<Terminal> {
+        Ok(Terminal {
+            text: token.text.clone(),
+            value: (token.value.as_ref())
+                .map(TokenizerValue)
+                .into_pyobject(py)?
+                .unbind(),
+            start: token.span.start,
+            end: token.span.end,
+            is_placeholder: token.is_placeholder(),
+        })
+    }
+}
+
+static PRODUCTIONS: OnceLock<Py<PyAny>> = OnceLock::new();
 
 fn downcast_tokens(
     py: Python,
     start_token_name: &str,
     token_list: Py<PyAny>,
 ) -> PyResult<Vec<parser::Terminal>> {
+    if let Ok(buffer) = token_list.downcast_bound::<TokenBuffer>(py) {
+        let tokens = &buffer.get().tokens;
+        let mut buf = Vec::with_capacity(tokens.len() + 1);
+        buf.push(parser::Terminal::from_start_name(start_token_name));
+        buf.extend(tokens.iter().cloned().map(parser::Terminal::from_token));
+        demote_soft_terminals(&mut buf);
+        return Ok(buf);
+    }
+
     let tokens = token_list.downcast_bound::<PyList>(py)?;
 
     let mut buf = Vec::with_capacity(tokens.len() + 1);
@@ -104,13 +186,22 @@ fn downcast_tokens(
 
         buf.push(parser::Terminal::from_token(token));
     }
+    demote_soft_terminals(&mut buf);
 
     Ok(buf)
 }
 
-fn get_spec() -> PyResult<&'static (parser::Spec, Py<PyAny>)> {
-    if let Some(x) = PARSER_SPECS.get() {
-        Ok(x)
+fn parse_mode(tolerant: bool) -> parser::ParseMode {
+    if tolerant {
+        parser::ParseMode::Tolerant
+    } else {
+        parser::ParseMode::Strict
+    }
+}
+
+fn get_spec() -> PyResult<(&'static parser::Spec, &'static Py<PyAny>)> {
+    if let (Some(spec), Some(productions)) = (parser::shared_spec(), PRODUCTIONS.get()) {
+        Ok((spec, productions))
     } else {
         Err(PyAssertionError::new_err(("grammar spec not loaded",)))
     }
@@ -119,20 +210,26 @@ fn get_spec() -> PyResult<&'static (parser::Spec, Py<PyAny>)> {
 /// Loads the grammar specification from file and caches it in memory.
 #[pyfunction]
 pub fn preload_spec(py: Python, spec_filepath: &Bound<PyString>) -> PyResult<()> {
-    if PARSER_SPECS.get().is_some() {
+    if PRODUCTIONS.get().is_some() {
         return Ok(());
     }
 
-    let spec_filepath = spec_filepath.to_string();
-    let bytes = std::fs::read(&spec_filepath)
-        .unwrap_or_else(|e| panic!("Cannot read grammar spec from {spec_filepath} ({e})"));
-
-    let spec: parser::Spec = bincode::deserialize::<parser::SpecSerializable>(&bytes)
-        .map_err(|e| PyValueError::new_err(format!("Bad spec: {e}")))?
-        .into();
-    let productions = load_productions(py, &spec)?;
+    let spec = match parser::shared_spec() {
+        Some(spec) => spec,
+        None => {
+            let spec_filepath = spec_filepath.to_string();
+            let bytes = std::fs::read(&spec_filepath)
+                .unwrap_or_else(|e| panic!("Cannot read grammar spec from {spec_filepath} ({e})"));
+
+            let spec: parser::Spec = bincode::deserialize::<parser::SpecSerializable>(&bytes)
+                .map_err(|e| PyValueError::new_err(format!("Bad spec: {e}")))?
+                .into();
+            parser::warmup(|| spec)
+        }
+    };
+    let productions = load_productions(py, spec)?;
 
-    let _ = PARSER_SPECS.set((spec, productions));
+    let _ = PRODUCTIONS.set(productions);
     Ok(())
 }
 
@@ -180,18 +277,7 @@ impl<'py> IntoPyObject<'py> for ParserCSTNode<'_> {
             },
             parser::CSTNode::Terminal(token) => CSTNode {
                 production: None,
-                terminal: Some(Py::new(
-                    py,
-                    Terminal {
-                        text: token.text.clone(),
-                        value: (token.value.as_ref())
-                            .map(TokenizerValue)
-                            .into_pyobject(py)?
-                            .unbind(),
-                        start: token.span.start,
-                        end: token.span.end,
-                    },
-                )?),
+                terminal: Some(Py::new(py, Terminal::new(py, token)?)?),
             },
             parser::CSTNode::Production(prod) => CSTNode {
                 production: Some(Py::new(
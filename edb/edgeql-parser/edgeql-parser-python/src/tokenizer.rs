@@ -1,8 +1,12 @@
+use std::ffi::{c_char, c_int, c_void};
+use std::ptr;
+
 use edgeql_parser::tokenizer::{Kind, Token, Tokenizer};
 use once_cell::sync::OnceCell;
-use pyo3::exceptions::PyValueError;
+use pyo3::exceptions::{PyBufferError, PyIndexError, PyValueError};
 use pyo3::prelude::*;
-use pyo3::types::{PyBytes, PyList, PyString};
+use pyo3::types::{PyBytes, PyList, PySlice, PyString};
+use pyo3::{ffi, IntoPyObjectExt};
 
 use crate::errors::{parser_error_into_tuple, ParserResult};
 
@@ -33,6 +37,131 @@ pub fn tokenize(py: Python, s: &Bound<PyString>) -> PyResult<ParserResult> {
     Ok(ParserResult { out, errors })
 }
 
+/// Same as [tokenize], but the tokens are returned as a [TokenBuffer]
+/// instead of a list of [OpaqueToken].
+#[pyfunction]
+pub fn tokenize_buffer(py: Python, s: &Bound<PyString>) -> PyResult<ParserResult> {
+    let data = s.to_string();
+
+    let mut tokens = vec![];
+    let mut errors = vec![];
+
+    for res in Tokenizer::new(&data[..]).validated_values().with_eof() {
+        match res {
+            Ok(token) => tokens.push(token.cloned()),
+            Err(e) => {
+                errors.push(parser_error_into_tuple(&e).into_pyobject(py)?);
+                break;
+            }
+        }
+    }
+
+    let out = TokenBuffer::new(tokens).into_py_any(py)?;
+    let errors = PyList::new(py, errors)?.into();
+
+    Ok(ParserResult { out, errors })
+}
+
+/// Tokens packed into a buffer of `(start, end)` byte offsets into the
+/// original string.
+///
+/// Implements the buffer protocol (format `Q`, shape `(len, 2)`), so the
+/// spans of all tokens can be read with a `memoryview` without creating an
+/// object per token. Indexing creates an [OpaqueToken] for a single token,
+/// slicing creates a new buffer. The parser reads the tokens directly.
+#[pyclass(frozen)]
+pub struct TokenBuffer {
+    pub tokens: Vec<Token<'static>>,
+    spans: Vec<u64>,
+    shape: [ffi::Py_ssize_t; 2],
+    strides: [ffi::Py_ssize_t; 2],
+}
+
+impl TokenBuffer {
+    pub fn new(tokens: Vec<Token<'static>>) -> TokenBuffer {
+        let spans = tokens
+            .iter()
+            .flat_map(|t| [t.span.start, t.span.end])
+            .collect();
+        let item = size_of::<u64>() as ffi::Py_ssize_t;
+        TokenBuffer {
+            shape: [tokens.len() as ffi::Py_ssize_t, 2],
+            strides: [2 * item, item],
+            tokens,
+            spans,
+        }
+    }
+}
+
+#[pymethods]
+impl TokenBuffer {
+    fn __len__(&self) -> usize {
+        self.tokens.len()
+    }
+
+    fn __getitem__(&self, py: Python, index: &Bound<PyAny>) -> PyResult<Py<PyAny>> {
+        if let Ok(slice) = index.downcast::<PySlice>() {
+            let indices = slice.indices(self.tokens.len() as isize)?;
+            let tokens = (0..indices.slicelength)
+                .map(|i| self.tokens[(indices.start + i as isize * indices.step) as usize].clone())
+                .collect();
+            return TokenBuffer::new(tokens).into_py_any(py);
+        }
+        let index: isize = index.extract()?;
+        let len = self.tokens.len() as isize;
+        let resolved = if index < 0 { index + len } else { index };
+        if !(0..len).contains(&resolved) {
+            return Err(PyIndexError::new_err("token index out of range"));
+        }
+        OpaqueToken {
+            inner: self.tokens[resolved as usize].clone(),
+        }
+        .into_py_any(py)
+    }
+
+    unsafe fn __getbuffer__(
+        slf: Bound<'_, Self>,
+        view: *mut ffi::Py_buffer,
+        flags: c_int,
+    ) -> PyResult<()> {
+        if view.is_null() {
+            return Err(PyBufferError::new_err("view is null"));
+        }
+        if (flags & ffi::PyBUF_WRITABLE) == ffi::PyBUF_WRITABLE {
+            return Err(PyBufferError::new_err("token buffer is read-only"));
+        }
+        let this = slf.get();
+        // the buffer is never mutated (the class is frozen), so the pointers
+        // stay valid for as long as the view holds a reference to the object
+        (*view).buf = this.spans.as_ptr() as *mut c_void;
+        (*view).len = (this.spans.len() * size_of::<u64>()) as ffi::Py_ssize_t;
+        (*view).readonly = 1;
+        (*view).itemsize = size_of::<u64>() as ffi::Py_ssize_t;
+        (*view).format = if (flags & ffi::PyBUF_FORMAT) == ffi::PyBUF_FORMAT {
+            c"Q".as_ptr() as *mut c_char
+        } else {
+            ptr::null_mut()
+        };
+        (*view).ndim = 2;
+        (*view).shape = if (flags & ffi::PyBUF_ND) == ffi::PyBUF_ND {
+            this.shape.as_ptr() as *mut ffi::Py_ssize_t
+        } else {
+            ptr::null_mut()
+        };
+        (*view).strides = if (flags & ffi::PyBUF_STRIDES) == ffi::PyBUF_STRIDES {
+            this.strides.as_ptr() as *mut ffi::Py_ssize_t
+        } else {
+            ptr::null_mut()
+        };
+        (*view).suboffsets = ptr::null_mut();
+        (*view).internal = ptr::null_mut();
+        (*view).obj = slf.into_any().into_ptr();
+        Ok(())
+    }
+
+    unsafe fn __releasebuffer__(&self, _view: *mut ffi::Py_buffer) {}
+}
+
 // An opaque wrapper around [edgeql_parser::tokenizer::Token].
 // Supports Python pickle serialization.
 #[pyclass]
@@ -0,0 +1,312 @@
+//! `administer` statements
+//!
+//! `administer vacuum(User, full := true)` runs a maintenance function of the
+//! server. [parse_administer] reads the statement into an [Administer] with
+//! its positional and named arguments, and [Administer::validate] checks the
+//! arguments against the functions the server knows, listed in [FUNCTIONS].
+//!
+//! The grammar parses the statement as `administer` followed by any function
+//! call, the checks of the arguments are done by the server when it runs the
+//! statement. This module reads the tokens of the statement so that tools can
+//! report those errors earlier; it is a check, the grammar stays the
+//! reference for what is syntactically valid.
+use crate::keywords::Keyword;
+use crate::position::Span;
+use crate::schema::{is_name_like, token_name, Expr};
+use crate::tokenizer::{Error, Kind, Token, Tokenizer};
+
+/// Functions with the number of positional arguments they take (`None` for
+/// any number) and the names of their named arguments
+pub const FUNCTIONS: &[(&str, Option<usize>, &[&str])] = &[
+    ("statistics_update", None, &[]),
+    ("vacuum", None, &["statistics_update", "full"]),
+    ("reindex", Some(1), &[]),
+    ("concurrent_index_build", Some(1), &[]),
+    ("schema_repair", Some(0), &[]),
+    ("prepare_upgrade", Some(0), &[]),
+    ("fixup_backend_upgrade", Some(0), &[]),
+    ("_remove_pointless_triggers", Some(0), &[]),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AdministerArg {
+    /// Name of a named argument: `vacuum(full := true)`
+    pub name: Option<String>,
+    pub name_span: Option<Span>,
+    pub value: Expr,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Administer {
+    /// Function name as written, e.g. `vacuum`
+    pub function: String,
+    pub function_span: Span,
+    /// Arguments in the order they are written, positional ones first
+    pub args: Vec<AdministerArg>,
+    /// From `administer` to the closing parenthesis
+    pub span: Span,
+}
+
+impl Administer {
+    /// Values of the positional arguments
+    pub fn positional(&self) -> impl Iterator<Item = &Expr> {
+        self.args
+            .iter()
+            .filter(|a| a.name.is_none())
+            .map(|a| &a.value)
+    }
+
+    /// Value of the named argument
+    pub fn named(&self, name: &str) -> Option<&Expr> {
+        self.args
+            .iter()
+            .find(|a| a.name.as_deref() == Some(name))
+            .map(|a| &a.value)
+    }
+
+    /// Checks the function and its arguments, with the error messages of
+    /// the server
+    pub fn validate(&self) -> Result<(), Error> {
+        let function = &self.function;
+        let Some((_, positional, named)) = FUNCTIONS.iter().find(|(f, _, _)| f == function) else {
+            return Err(Error::new("Unknown ADMINISTER function").with_span(self.function_span));
+        };
+        let count = self.positional().count();
+        let has_named = count < self.args.len();
+        match positional {
+            Some(0) if !self.args.is_empty() => {
+                return Err(Error::new(format!("{function}() does not take arguments"))
+                    .with_span(self.span));
+            }
+            Some(1) if count != 1 || has_named => {
+                return Err(Error::new(format!(
+                    "{function}() takes exactly one positional argument"
+                ))
+                .with_span(self.span));
+            }
+            _ => {}
+        }
+        for arg in &self.args {
+            let Some(name) = &arg.name else {
+                continue;
+            };
+            if !named.contains(&&name[..]) {
+                return Err(Error::new(format!(
+                    "unrecognized keyword argument '{name}' for {function}()"
+                ))
+                .with_span(arg.value.span));
+            }
+            let is_bool = matches!(
+                &arg.value.tokens[..],
+                [tok] if matches!(tok.kind, Kind::Keyword(Keyword("true" | "false")))
+            );
+            if !is_bool {
+                return Err(Error::new(format!(
+                    "argument '{name}' for {function}() must be a boolean literal"
+                ))
+                .with_span(arg.value.span));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parses a single `administer` statement, optionally followed by `;`
+pub fn parse_administer(text: &str) -> Result<Administer, Error> {
+    let tokens = Tokenizer::new(text)
+        .validated_values()
+        .map(|t| t.map(|t| t.cloned()))
+        .collect::<Result<Vec<_>, _>>()?;
+    let eof = Span {
+        start: text.len() as u64,
+        end: text.len() as u64,
+    };
+    let span_at = |pos: usize| tokens.get(pos).map_or(eof, |t| t.span);
+    let expected =
+        |what: &str, pos: usize| Error::new(format!("Expected {what}")).with_span(span_at(pos));
+
+    if tokens.first().map(|t| t.kind) != Some(Kind::Keyword(Keyword("administer"))) {
+        return Err(expected("'administer'", 0));
+    }
+    let mut pos = 1;
+    let mut function = String::new();
+    loop {
+        if !is_name_like(tokens.get(pos).map(|t| t.kind)) {
+            return Err(expected("a function name", pos));
+        }
+        function.push_str(token_name(&tokens[pos]));
+        pos += 1;
+        if tokens.get(pos).map(|t| t.kind) != Some(Kind::Namespace) {
+            break;
+        }
+        function.push_str("::");
+        pos += 1;
+    }
+    let function_span = tokens[1].span.extend(&tokens[pos - 1].span);
+    if tokens.get(pos).map(|t| t.kind) != Some(Kind::OpenParen) {
+        return Err(expected("'('", pos));
+    }
+    pos += 1;
+
+    let mut args = Vec::new();
+    let mut arg_start = pos;
+    let mut depth = 0usize;
+    let close = loop {
+        let Some(tok) = tokens.get(pos) else {
+            return Err(expected("')'", pos));
+        };
+        match tok.kind {
+            Kind::OpenParen | Kind::OpenBracket | Kind::OpenBrace => depth += 1,
+            Kind::CloseParen if depth == 0 => break pos,
+            Kind::CloseParen | Kind::CloseBracket | Kind::CloseBrace if depth == 0 => {
+                return Err(Error::new(format!("Unexpected '{}'", tok.text)).with_span(tok.span));
+            }
+            Kind::CloseParen | Kind::CloseBracket | Kind::CloseBrace => depth -= 1,
+            Kind::Comma if depth == 0 => {
+                if arg_start == pos {
+                    return Err(expected("an argument", pos));
+                }
+                args.push(argument(&tokens[arg_start..pos])?);
+                arg_start = pos + 1;
+            }
+            _ => {}
+        }
+        pos += 1;
+    };
+    // trailing comma is allowed
+    if arg_start < close {
+        args.push(argument(&tokens[arg_start..close])?);
+    }
+    check_order(&args)?;
+
+    pos = close + 1;
+    if tokens.get(pos).map(|t| t.kind) == Some(Kind::Semicolon) {
+        pos += 1;
+    }
+    if let Some(tok) = tokens.get(pos) {
+        return Err(Error::new(format!("Unexpected '{}'", tok.text)).with_span(tok.span));
+    }
+    Ok(Administer {
+        function,
+        function_span,
+        args,
+        span: tokens[0].span.extend(&tokens[close].span),
+    })
+}
+
+fn argument(tokens: &[Token<'static>]) -> Result<AdministerArg, Error> {
+    let (name, value) = match tokens {
+        [name, assign, value @ ..]
+            if assign.kind == Kind::Assign && is_name_like(Some(name.kind)) =>
+        {
+            if value.is_empty() {
+                return Err(Error::new("Expected an expression").with_span(assign.span));
+            }
+            (Some(name), value)
+        }
+        _ => (None, tokens),
+    };
+    Ok(AdministerArg {
+        name: name.map(|t| token_name(t).to_string()),
+        name_span: name.map(|t| t.span),
+        value: Expr {
+            tokens: value.to_vec(),
+            span: value[0].span.extend(&value[value.len() - 1].span),
+        },
+    })
+}
+
+/// Reports duplicate named arguments and positional arguments after named
+/// ones, like the grammar of function calls does
+fn check_order(args: &[AdministerArg]) -> Result<(), Error> {
+    let mut last_named: Option<&str> = None;
+    for (idx, arg) in args.iter().enumerate() {
+        match (&arg.name, arg.name_span) {
+            (Some(name), Some(span)) => {
+                if args[..idx].iter().any(|a| a.name.as_ref() == Some(name)) {
+                    return Err(
+                        Error::new(format!("duplicate named argument `{name}`")).with_span(span)
+                    );
+                }
+                last_named = Some(name);
+            }
+            _ => {
+                if let Some(last) = last_named {
+                    return Err(Error::new(format!(
+                        "positional argument after named argument `{last}`"
+                    ))
+                    .with_span(arg.value.span));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::parse_administer;
+
+    #[test]
+    fn arguments() {
+        let text = "administer vacuum(User.friends, Post, full := true);";
+        let stmt = parse_administer(text).unwrap();
+        assert_eq!(stmt.function, "vacuum");
+        let src = |span: crate::position::Span| &text[span.start as usize..span.end as usize];
+        let positional: Vec<_> = stmt.positional().map(|e| src(e.span)).collect();
+        assert_eq!(positional, ["User.friends", "Post"]);
+        assert_eq!(src(stmt.named("full").unwrap().span), "true");
+        assert_eq!(src(stmt.span), &text[..text.len() - 1]);
+        stmt.validate().unwrap();
+
+        let stmt = parse_administer("administer schema_repair()").unwrap();
+        assert!(stmt.args.is_empty());
+        stmt.validate().unwrap();
+    }
+
+    #[test]
+    fn errors() {
+        let error = |text: &str| parse_administer(text).unwrap_err().message;
+        assert_eq!(
+            error("administer vacuum(full := true, User)"),
+            "positional argument after named argument `full`"
+        );
+        assert_eq!(
+            error("administer vacuum(full := true, full := false)"),
+            "duplicate named argument `full`"
+        );
+        assert_eq!(
+            error("administer vacuum(User,, Post)"),
+            "Expected an argument"
+        );
+        assert_eq!(error("administer vacuum(User) + 1"), "Unexpected '+'");
+
+        let invalid = |text: &str| {
+            parse_administer(text)
+                .unwrap()
+                .validate()
+                .unwrap_err()
+                .message
+        };
+        assert_eq!(
+            invalid("administer shrink()"),
+            "Unknown ADMINISTER function"
+        );
+        assert_eq!(
+            invalid("administer reindex(User, Post)"),
+            "reindex() takes exactly one positional argument"
+        );
+        assert_eq!(
+            invalid("administer vacuum(User, verbose := true)"),
+            "unrecognized keyword argument 'verbose' for vacuum()"
+        );
+        assert_eq!(
+            invalid("administer vacuum(full := 1)"),
+            "argument 'full' for vacuum() must be a boolean literal"
+        );
+        assert_eq!(
+            invalid("administer prepare_upgrade(1)"),
+            "prepare_upgrade() does not take arguments"
+        );
+    }
+}
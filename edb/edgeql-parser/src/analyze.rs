@@ -0,0 +1,497 @@
+//! Helpers for `analyze` queries and their output
+//!
+//! [AnalyzedQuery] finds the `analyze` prefix of a query (or adds one) and
+//! [Analysis] is the JSON returned by the server for such a query: the
+//! query plan, where every node has the contexts (spans of the query) it was
+//! compiled from. [AnalyzedQuery::span] maps a context back to the source,
+//! so that a profiling UI can highlight hot expressions, and
+//! [Tree::covering] finds the CST node of the span.
+//!
+//! [AnalyzedQuery::cost_tree] does both for a whole plan, the resulting
+//! [CostNode] tree can be exported as JSON for editor plugins.
+use serde_json::{json, Value};
+
+use crate::keywords::Keyword;
+use crate::parser::{NodeId, Tree};
+use crate::position::{InflatedPos, Span};
+use crate::tokenizer::{Error, Kind, Tokenizer};
+
+/// A query with an optional `analyze` prefix
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnalyzedQuery<'a> {
+    pub source: &'a str,
+    /// Span of the `analyze` keyword
+    pub prefix: Option<Span>,
+    /// Offset of the statement after the prefix
+    pub statement_offset: usize,
+}
+
+/// Output of an `analyze` query
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Analysis {
+    /// Texts of the queries the contexts refer to, the analyzed query first
+    pub buffers: Vec<String>,
+    /// Plan with the nodes of the SQL query
+    pub fine_grained: Option<Plan>,
+    /// Plan grouped by the shapes and subqueries of the EdgeQL query
+    pub coarse_grained: Option<Plan>,
+}
+
+/// A node of a query plan
+///
+/// Fields missing in the output are `None` or empty, as they differ between
+/// server versions.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Plan {
+    /// Type of the node, e.g. `Seq Scan`
+    pub node_type: Option<String>,
+    pub contexts: Vec<PlanContext>,
+    pub cost: Cost,
+    pub children: Vec<Plan>,
+}
+
+/// A span of a query that a plan node was compiled from
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PlanContext {
+    /// Index in [Analysis::buffers]
+    pub buffer_idx: usize,
+    pub start: usize,
+    pub end: usize,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Cost {
+    pub startup_cost: Option<f64>,
+    pub total_cost: Option<f64>,
+    pub plan_rows: Option<f64>,
+    pub actual_startup_time: Option<f64>,
+    pub actual_total_time: Option<f64>,
+    pub actual_rows: Option<f64>,
+    pub actual_loops: Option<f64>,
+}
+
+/// A plan node linked to the source
+#[derive(Debug, Clone, PartialEq)]
+pub struct CostNode {
+    pub node_type: Option<String>,
+    /// Spans of the contexts of the node that are in the source
+    pub spans: Vec<Span>,
+    /// Innermost CST nodes covering the spans
+    pub cst_nodes: Vec<NodeId>,
+    pub cost: Cost,
+    /// See [Plan::self_time]
+    pub self_time: Option<f64>,
+    pub children: Vec<CostNode>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum AnalysisError {
+    #[error("invalid analyze output: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("invalid analyze output: expected an object")]
+    NotAnObject,
+}
+
+impl<'a> AnalyzedQuery<'a> {
+    /// Finds the `analyze` prefix of the query, fails if the query cannot be
+    /// tokenized or starts with `explain`
+    pub fn parse(source: &'a str) -> Result<AnalyzedQuery<'a>, Error> {
+        let mut tokens = Tokenizer::new(source);
+        let prefix = match tokens.next().transpose()? {
+            Some(token) if token.kind == Kind::Keyword(Keyword("analyze")) => Some(token.span),
+            Some(token) if token.kind == Kind::Keyword(Keyword("explain")) => {
+                let mut error = Error::new(format!(
+                    "Unexpected keyword '{}'",
+                    token.text.to_uppercase()
+                ))
+                .with_span(token.span);
+                error.hint = Some("Use `analyze` to show query performance details".into());
+                return Err(error);
+            }
+            _ => None,
+        };
+        let statement_offset = match prefix {
+            Some(_) => match tokens.next().transpose()? {
+                Some(token) => token.span.start as usize,
+                None => source.len(),
+            },
+            None => 0,
+        };
+        Ok(AnalyzedQuery {
+            source,
+            prefix,
+            statement_offset,
+        })
+    }
+
+    /// The query without the `analyze` prefix
+    pub fn statement(&self) -> &'a str {
+        &self.source[self.statement_offset..]
+    }
+
+    /// The query with the `analyze` prefix
+    pub fn with_prefix(&self) -> String {
+        match self.prefix {
+            Some(_) => self.source.to_string(),
+            None => format!("analyze {}", self.source.trim_start()),
+        }
+    }
+
+    /// Span of the context in the source, `None` if the buffer of the
+    /// context is not a part of the source
+    pub fn span(&self, analysis: &Analysis, context: &PlanContext) -> Option<Span> {
+        let buffer = analysis.buffers.get(context.buffer_idx)?;
+        let offset = if buffer == self.source {
+            0
+        } else {
+            self.source.find(buffer.as_str())?
+        };
+        let (start, end) = if buffer.get(context.start..context.end) == Some(context.text.as_str())
+        {
+            (context.start, context.end)
+        } else {
+            // offsets in characters
+            let byte_offset = |chars: usize| {
+                buffer
+                    .char_indices()
+                    .map(|(idx, _)| idx)
+                    .chain([buffer.len()])
+                    .nth(chars)
+            };
+            (byte_offset(context.start)?, byte_offset(context.end)?)
+        };
+        Some(Span {
+            start: (offset + start) as u64,
+            end: (offset + end) as u64,
+        })
+    }
+
+    /// Links the nodes of the plan to the source and to the CST of the
+    /// source, if one is given
+    pub fn cost_tree(&self, analysis: &Analysis, plan: &Plan, tree: Option<&Tree>) -> CostNode {
+        let spans: Vec<Span> = plan
+            .contexts
+            .iter()
+            .filter_map(|ctx| self.span(analysis, ctx))
+            .collect();
+        CostNode {
+            node_type: plan.node_type.clone(),
+            cst_nodes: tree
+                .map(|tree| spans.iter().filter_map(|s| tree.covering(*s)).collect())
+                .unwrap_or_default(),
+            spans,
+            cost: plan.cost,
+            self_time: plan.self_time(),
+            children: plan
+                .children
+                .iter()
+                .map(|child| self.cost_tree(analysis, child, tree))
+                .collect(),
+        }
+    }
+}
+
+impl CostNode {
+    /// Exports the tree as JSON, spans are converted to LSP ranges (with
+    /// zero-based lines and UTF-16 columns) of the `source`:
+    ///
+    /// ```json
+    /// {
+    ///   "node_type": "Seq Scan",
+    ///   "ranges": [{"start": {"line": 0, "character": 7}, "end": {...}}],
+    ///   "spans": [[7, 11]],
+    ///   "cst_nodes": [12],
+    ///   "cost": {"actual_total_time": 0.5, ...},
+    ///   "self_time": 0.5,
+    ///   "children": [...]
+    /// }
+    /// ```
+    pub fn to_json(&self, source: &str) -> Value {
+        let offsets: Vec<usize> = self
+            .spans
+            .iter()
+            .flat_map(|s| [s.start as usize, s.end as usize])
+            .collect();
+        let positions = InflatedPos::from_offsets(source.as_bytes(), &offsets).unwrap_or_default();
+        let position = |pos: &InflatedPos| json!({"line": pos.line, "character": pos.utf16column});
+        let cost = self.cost;
+        let cost = [
+            ("startup_cost", cost.startup_cost),
+            ("total_cost", cost.total_cost),
+            ("plan_rows", cost.plan_rows),
+            ("actual_startup_time", cost.actual_startup_time),
+            ("actual_total_time", cost.actual_total_time),
+            ("actual_rows", cost.actual_rows),
+            ("actual_loops", cost.actual_loops),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| Some((name.to_string(), json!(value?))))
+        .collect::<serde_json::Map<_, _>>();
+        json!({
+            "node_type": self.node_type,
+            "ranges": positions
+                .chunks(2)
+                .map(|pair| json!({"start": position(&pair[0]), "end": position(&pair[1])}))
+                .collect::<Vec<_>>(),
+            "spans": self.spans.iter().map(|s| [s.start, s.end]).collect::<Vec<_>>(),
+            "cst_nodes": self.cst_nodes.iter().map(|n| n.index()).collect::<Vec<_>>(),
+            "cost": cost,
+            "self_time": self.self_time,
+            "children": self.children.iter().map(|c| c.to_json(source)).collect::<Vec<_>>(),
+        })
+    }
+}
+
+impl Analysis {
+    pub fn parse(json: &str) -> Result<Analysis, AnalysisError> {
+        let value: Value = serde_json::from_str(json)?;
+        // some clients return the output as a single-element array
+        let value = match value {
+            Value::Array(mut items) if items.len() == 1 => items.remove(0),
+            value => value,
+        };
+        let Value::Object(obj) = value else {
+            return Err(AnalysisError::NotAnObject);
+        };
+        Ok(Analysis {
+            buffers: obj
+                .get("buffers")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .map(|b| match b {
+                    // buffers are `[text, ...]` in some versions
+                    Value::Array(items) => items.first().and_then(Value::as_str),
+                    b => b.as_str(),
+                })
+                .map(|b| b.unwrap_or_default().to_string())
+                .collect(),
+            fine_grained: obj.get("fine_grained").map(Plan::from_json),
+            coarse_grained: obj.get("coarse_grained").map(Plan::from_json),
+        })
+    }
+}
+
+impl Plan {
+    fn from_json(value: &Value) -> Plan {
+        let float = |name: &str| value.get(name).and_then(Value::as_f64);
+        // costs are either in the node, or in its first pipeline step
+        let cost_of = |name: &str| {
+            float(name).or_else(|| {
+                value
+                    .pointer(&format!("/pipeline/0/{name}"))
+                    .and_then(Value::as_f64)
+            })
+        };
+        let children = ["children", "plans", "subplans"]
+            .into_iter()
+            .filter_map(|name| value.get(name).and_then(Value::as_array))
+            .flatten()
+            .map(|child| match child.get("node") {
+                // coarse-grained children are wrapped
+                Some(node) => Plan::from_json(node),
+                None => Plan::from_json(child),
+            })
+            .collect();
+        Plan {
+            node_type: ["node_type", "plan_type"]
+                .into_iter()
+                .find_map(|name| value.get(name).and_then(Value::as_str))
+                .or_else(|| {
+                    value
+                        .pointer("/pipeline/0/plan_type")
+                        .and_then(Value::as_str)
+                })
+                .map(String::from),
+            contexts: value
+                .get("contexts")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .map(PlanContext::from_json)
+                .collect(),
+            cost: Cost {
+                startup_cost: cost_of("startup_cost"),
+                total_cost: cost_of("total_cost"),
+                plan_rows: cost_of("plan_rows"),
+                actual_startup_time: cost_of("actual_startup_time"),
+                actual_total_time: cost_of("actual_total_time"),
+                actual_rows: cost_of("actual_rows"),
+                actual_loops: cost_of("actual_loops"),
+            },
+            children,
+        }
+    }
+
+    /// The node and all its descendants, in pre-order
+    pub fn walk(&self) -> Vec<&Plan> {
+        let mut result = Vec::new();
+        let mut stack = vec![self];
+        while let Some(plan) = stack.pop() {
+            result.push(plan);
+            stack.extend(plan.children.iter().rev());
+        }
+        result
+    }
+
+    /// Time spent in the node itself, excluding the children
+    pub fn self_time(&self) -> Option<f64> {
+        let total = self.cost.actual_total_time? * self.cost.actual_loops.unwrap_or(1.0);
+        let children: f64 = self
+            .children
+            .iter()
+            .filter_map(|c| Some(c.cost.actual_total_time? * c.cost.actual_loops.unwrap_or(1.0)))
+            .sum();
+        Some((total - children).max(0.0))
+    }
+}
+
+impl PlanContext {
+    fn from_json(value: &Value) -> PlanContext {
+        let int = |name: &str| value.get(name).and_then(Value::as_u64).unwrap_or_default() as usize;
+        PlanContext {
+            buffer_idx: int("buffer_idx"),
+            start: int("start"),
+            end: int("end"),
+            text: value
+                .get("text")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::{Analysis, AnalyzedQuery};
+    use crate::parser::{CSTNode, Production, Tree};
+    use crate::position::Span;
+    use crate::tokenizer::{terminal, Kind};
+
+    const OUTPUT: &str = r#"{
+        "buffers": ["select User { name } filter .name = 'ü'"],
+        "fine_grained": {
+            "node_type": "Hash Join",
+            "actual_total_time": 3.0,
+            "actual_loops": 1,
+            "contexts": [{"buffer_idx": 0, "start": 0, "end": 39, "text": "whole"}],
+            "plans": [{
+                "node_type": "Seq Scan",
+                "actual_total_time": 0.5,
+                "actual_loops": 2,
+                "contexts": [
+                    {"buffer_idx": 0, "start": 21, "end": 39,
+                     "text": "filter .name = 'ü'"},
+                    {"buffer_idx": 1, "start": 0, "end": 1, "text": "x"}
+                ]
+            }]
+        },
+        "coarse_grained": {
+            "pipeline": [{"plan_type": "Result", "actual_total_time": 2.5}],
+            "children": [{"node": {"contexts": []}}]
+        }
+    }"#;
+
+    #[test]
+    fn prefix() {
+        let query = AnalyzedQuery::parse("  analyze\n  select 1").unwrap();
+        assert_eq!(query.prefix, Some(Span { start: 2, end: 9 }));
+        assert_eq!(query.statement(), "select 1");
+        assert_eq!(query.with_prefix(), "  analyze\n  select 1");
+
+        let query = AnalyzedQuery::parse(" select 1").unwrap();
+        assert_eq!(query.prefix, None);
+        assert_eq!(query.statement(), " select 1");
+        assert_eq!(query.with_prefix(), "analyze select 1");
+
+        let err = AnalyzedQuery::parse("explain select 1").unwrap_err();
+        assert_eq!(err.message, "Unexpected keyword 'EXPLAIN'");
+        assert!(err.hint.is_some());
+    }
+
+    #[test]
+    fn output() {
+        let analysis = Analysis::parse(OUTPUT).unwrap();
+        let fine = analysis.fine_grained.as_ref().unwrap();
+        assert_eq!(fine.node_type.as_deref(), Some("Hash Join"));
+        assert_eq!(fine.walk().len(), 2);
+        assert_eq!(fine.self_time(), Some(2.0));
+        let coarse = analysis.coarse_grained.as_ref().unwrap();
+        assert_eq!(coarse.node_type.as_deref(), Some("Result"));
+        assert_eq!(coarse.cost.actual_total_time, Some(2.5));
+        assert_eq!(coarse.children.len(), 1);
+
+        assert!(Analysis::parse("[]").is_err());
+        assert!(Analysis::parse("{").is_err());
+    }
+
+    #[test]
+    fn spans() {
+        let analysis = Analysis::parse(OUTPUT).unwrap();
+        let source = "analyze select User { name } filter .name = 'ü'";
+        let query = AnalyzedQuery::parse(source).unwrap();
+        let scan = &analysis.fine_grained.as_ref().unwrap().children[0];
+        // offsets in characters, the buffer is the statement after the prefix
+        let span = query.span(&analysis, &scan.contexts[0]).unwrap();
+        assert_eq!(
+            &source[span.start as usize..span.end as usize],
+            "filter .name = 'ü'"
+        );
+        // unknown buffer
+        assert_eq!(query.span(&analysis, &scan.contexts[1]), None);
+    }
+
+    #[test]
+    fn cost_tree() {
+        let analysis = Analysis::parse(OUTPUT).unwrap();
+        let source = "analyze select User { name } filter .name = 'ü'";
+        let query = AnalyzedQuery::parse(source).unwrap();
+
+        // `filter` clause as a production with a single terminal
+        let filter = terminal(
+            Kind::Keyword(crate::keywords::Keyword("filter")),
+            "filter",
+            29,
+            false,
+        );
+        let args = [CSTNode::Terminal(&filter)];
+        let root = CSTNode::Production(Production {
+            id: 1,
+            args: &args,
+            span: Some(Span { start: 29, end: 48 }),
+            inlined_ids: None,
+        });
+        let tree = Tree::new(root);
+
+        let plan = analysis.fine_grained.as_ref().unwrap();
+        let node = query.cost_tree(&analysis, plan, Some(&tree));
+        // the context of the whole statement
+        assert_eq!(node.spans, [Span { start: 8, end: 48 }]);
+        assert!(node.cst_nodes.is_empty());
+        let scan = &node.children[0];
+        assert_eq!(scan.spans, [Span { start: 29, end: 48 }]);
+        assert_eq!(scan.cst_nodes, [tree.root()]);
+
+        let exported = node.to_json(source);
+        assert_eq!(exported["self_time"], json!(2.0));
+        assert_eq!(
+            exported["children"][0],
+            json!({
+                "node_type": "Seq Scan",
+                "ranges": [{
+                    "start": {"line": 0, "character": 29},
+                    "end": {"line": 0, "character": 47},
+                }],
+                "spans": [[29, 48]],
+                "cst_nodes": [0],
+                "cost": {"actual_total_time": 0.5, "actual_loops": 2.0},
+                "self_time": 1.0,
+                "children": [],
+            })
+        );
+    }
+}
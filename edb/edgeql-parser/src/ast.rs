@@ -11,12 +11,71 @@ use indexmap::IndexMap;
 #[cfg(feature = "python")]
 use edgeql_parser_derive::IntoPython;
 
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+pub struct Base {
+    #[cfg_attr(feature = "python", py_child)]
+    pub kind: BaseKind,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+#[cfg_attr(feature = "python", py_child)]
+pub enum BaseKind {
+    GrammarEntryPoint(GrammarEntryPoint),
+    OptionValue(OptionValue),
+    Options(Options),
+    Expr(Box<Expr>),
+    SortExpr(SortExpr),
+    Alias(Alias),
+    GroupingAtom(GroupingAtom),
+    BaseObjectRef(BaseObjectRef),
+    Index(Index),
+    Slice(Slice),
+    WindowSpec(WindowSpec),
+    StrInterpFragment(StrInterpFragment),
+    TypeExpr(TypeExpr),
+    FuncParamDecl(FuncParamDecl),
+    TypeIntersection(TypeIntersection),
+    Ptr(Ptr),
+    Splat(Splat),
+    TupleElement(TupleElement),
+    Command(Command),
+    Commands(Commands),
+    ShapeOperation(ShapeOperation),
+    GroupingIdentList(GroupingIdentList),
+    GroupingElement(GroupingElement),
+    Transaction(Transaction),
+    DDL(DDL),
+    ConfigOp(ConfigOp),
+    SDL(SDL),
+    Schema(Schema),
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+pub struct GrammarEntryPoint {
+    #[cfg_attr(feature = "python", py_child)]
+    pub kind: GrammarEntryPointKind,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+#[cfg_attr(feature = "python", py_child)]
+pub enum GrammarEntryPointKind {
+    Expr(Box<Expr>),
+    Commands(Commands),
+    Query(Query),
+    CreateMigration(CreateMigration),
+    Schema(Schema),
+}
+
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct OptionValue {
     pub name: String,
     #[cfg_attr(feature = "python", py_child)]
-    pub kind: Option<OptionValueKind>,
+    pub kind: OptionValueKind,
 }
 
 #[derive(Debug, Clone)]
@@ -51,13 +110,16 @@ pub struct Expr {
 pub enum ExprKind {
     Placeholder(Placeholder),
     Anchor(Anchor),
+    Cursor(Cursor),
     DetachedExpr(DetachedExpr),
     GlobalExpr(GlobalExpr),
     Indirection(Indirection),
     BinOp(BinOp),
     FunctionCall(FunctionCall),
+    StrInterp(StrInterp),
     BaseConstant(BaseConstant),
-    Parameter(Parameter),
+    QueryParameter(QueryParameter),
+    FunctionParameter(FunctionParameter),
     UnaryOp(UnaryOp),
     IsOp(IsOp),
     Path(Path),
@@ -71,7 +133,7 @@ pub enum ExprKind {
     ShapeElement(ShapeElement),
     Shape(Shape),
     Query(Query),
-    ConfigOp(ConfigOp),
+    OptionalExpr(OptionalExpr),
 }
 
 #[derive(Debug, Clone)]
@@ -88,6 +150,21 @@ pub struct SortExpr {
     pub nones_order: Option<NonesOrder>,
 }
 
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+pub struct Alias {
+    #[cfg_attr(feature = "python", py_child)]
+    pub kind: AliasKind,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+#[cfg_attr(feature = "python", py_child)]
+pub enum AliasKind {
+    AliasedExpr(AliasedExpr),
+    ModuleAliasDecl(ModuleAliasDecl),
+}
+
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct AliasedExpr {
@@ -102,6 +179,22 @@ pub struct ModuleAliasDecl {
     pub alias: Option<String>,
 }
 
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+pub struct GroupingAtom {
+    #[cfg_attr(feature = "python", py_child)]
+    pub kind: GroupingAtomKind,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+#[cfg_attr(feature = "python", py_child)]
+pub enum GroupingAtomKind {
+    ObjectRef(ObjectRef),
+    Path(Path),
+    GroupingIdentList(GroupingIdentList),
+}
+
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct BaseObjectRef {
@@ -128,26 +221,9 @@ pub struct ObjectRef {
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct PseudoObjectRef {
-    #[cfg_attr(feature = "python", py_child)]
-    pub kind: PseudoObjectRefKind,
-}
-
-#[derive(Debug, Clone)]
-#[cfg_attr(feature = "python", derive(IntoPython))]
-#[cfg_attr(feature = "python", py_child)]
-pub enum PseudoObjectRefKind {
-    AnyType(AnyType),
-    AnyTuple(AnyTuple),
+    pub name: String,
 }
 
-#[derive(Debug, Clone)]
-#[cfg_attr(feature = "python", derive(IntoPython))]
-pub struct AnyType {}
-
-#[derive(Debug, Clone)]
-#[cfg_attr(feature = "python", derive(IntoPython))]
-pub struct AnyTuple {}
-
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct Anchor {
@@ -160,35 +236,24 @@ pub struct Anchor {
 #[cfg_attr(feature = "python", derive(IntoPython))]
 #[cfg_attr(feature = "python", py_child)]
 pub enum AnchorKind {
+    IRAnchor(IRAnchor),
     SpecialAnchor(SpecialAnchor),
 }
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
-pub struct SpecialAnchor {
-    #[cfg_attr(feature = "python", py_child)]
-    pub kind: Option<SpecialAnchorKind>,
-}
-
-#[derive(Debug, Clone)]
-#[cfg_attr(feature = "python", derive(IntoPython))]
-#[cfg_attr(feature = "python", py_child)]
-pub enum SpecialAnchorKind {
-    Source(Source),
-    Subject(Subject),
+pub struct IRAnchor {
+    pub has_dml: bool,
+    pub move_scope: bool,
 }
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
-pub struct Source {
-    pub name: String,
-}
+pub struct SpecialAnchor {}
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
-pub struct Subject {
-    pub name: String,
-}
+pub struct Cursor {}
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
@@ -238,21 +303,7 @@ pub struct BinOp {
     pub op: String,
     pub right: Box<Expr>,
     pub rebalanced: bool,
-    #[cfg_attr(feature = "python", py_child)]
-    pub kind: Option<BinOpKind>,
-}
-
-#[derive(Debug, Clone)]
-#[cfg_attr(feature = "python", derive(IntoPython))]
-#[cfg_attr(feature = "python", py_child)]
-pub enum BinOpKind {
-    SetConstructorOp(SetConstructorOp),
-}
-
-#[derive(Debug, Clone)]
-#[cfg_attr(feature = "python", derive(IntoPython))]
-pub struct SetConstructorOp {
-    pub op: String,
+    pub set_constructor: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -275,79 +326,61 @@ pub struct FunctionCall {
 #[cfg_attr(feature = "python", derive(IntoPython))]
 #[cfg_attr(feature = "python", py_union)]
 pub enum FunctionCallFunc {
-    Tuple((String, String)),
+    tuple((String, String)),
     str(String),
 }
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
-pub struct BaseConstant {
-    pub value: String,
-    #[cfg_attr(feature = "python", py_child)]
-    pub kind: BaseConstantKind,
+pub struct StrInterpFragment {
+    pub expr: Box<Expr>,
+    pub suffix: String,
 }
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
-#[cfg_attr(feature = "python", py_child)]
-pub enum BaseConstantKind {
-    StringConstant(StringConstant),
-    BaseRealConstant(BaseRealConstant),
-    BooleanConstant(BooleanConstant),
-    BytesConstant(BytesConstant),
+pub struct StrInterp {
+    pub prefix: String,
+    pub interpolations: Vec<StrInterpFragment>,
 }
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
-pub struct StringConstant {}
-
-#[derive(Debug, Clone)]
-#[cfg_attr(feature = "python", derive(IntoPython))]
-pub struct BaseRealConstant {
-    pub is_negative: bool,
+pub struct BaseConstant {
     #[cfg_attr(feature = "python", py_child)]
-    pub kind: BaseRealConstantKind,
+    pub kind: BaseConstantKind,
 }
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
 #[cfg_attr(feature = "python", py_child)]
-pub enum BaseRealConstantKind {
-    IntegerConstant(IntegerConstant),
-    FloatConstant(FloatConstant),
-    BigintConstant(BigintConstant),
-    DecimalConstant(DecimalConstant),
+pub enum BaseConstantKind {
+    Constant(Constant),
+    BytesConstant(BytesConstant),
 }
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
-pub struct IntegerConstant {}
-
-#[derive(Debug, Clone)]
-#[cfg_attr(feature = "python", derive(IntoPython))]
-pub struct FloatConstant {}
-
-#[derive(Debug, Clone)]
-#[cfg_attr(feature = "python", derive(IntoPython))]
-pub struct BigintConstant {}
-
-#[derive(Debug, Clone)]
-#[cfg_attr(feature = "python", derive(IntoPython))]
-pub struct DecimalConstant {}
+pub struct Constant {
+    pub kind: ConstantKind,
+    pub value: String,
+}
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
-pub struct BooleanConstant {}
+pub struct BytesConstant {
+    pub value: Vec<u8>,
+}
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
-pub struct BytesConstant {
-    pub value: Vec<u8>,
+pub struct QueryParameter {
+    pub name: String,
 }
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
-pub struct Parameter {
+pub struct FunctionParameter {
     pub name: String,
 }
 
@@ -363,7 +396,7 @@ pub struct UnaryOp {
 pub struct TypeExpr {
     pub name: Option<String>,
     #[cfg_attr(feature = "python", py_child)]
-    pub kind: Option<TypeExprKind>,
+    pub kind: TypeExprKind,
 }
 
 #[derive(Debug, Clone)]
@@ -385,7 +418,7 @@ pub struct TypeOf {
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct TypeExprLiteral {
-    pub val: BaseConstant,
+    pub val: Constant,
 }
 
 #[derive(Debug, Clone)]
@@ -400,13 +433,13 @@ pub struct TypeName {
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct TypeOp {
     pub left: Box<TypeExpr>,
-    pub op: String,
+    pub op: TypeOpName,
     pub right: Box<TypeExpr>,
 }
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
-pub struct FuncParam {
+pub struct FuncParamDecl {
     pub name: String,
     pub r#type: TypeExpr,
     pub typemod: TypeModifier,
@@ -431,7 +464,7 @@ pub struct TypeIntersection {
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct Ptr {
-    pub ptr: ObjectRef,
+    pub name: String,
     pub direction: Option<String>,
     pub r#type: Option<String>,
 }
@@ -449,6 +482,7 @@ pub struct Splat {
 pub struct Path {
     pub steps: Vec<PathSteps>,
     pub partial: bool,
+    pub allow_factoring: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -482,12 +516,13 @@ pub struct IfElse {
     pub condition: Box<Expr>,
     pub if_expr: Box<Expr>,
     pub else_expr: Box<Expr>,
+    pub python_style: bool,
 }
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct TupleElement {
-    pub name: ObjectRef,
+    pub name: Ptr,
     pub val: Box<Expr>,
 }
 
@@ -513,22 +548,29 @@ pub struct Array {
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct Set {
     pub elements: Vec<Box<Expr>>,
+    #[cfg_attr(feature = "python", py_child)]
+    pub kind: Option<SetKind>,
 }
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
-pub struct Command {
-    pub aliases: Option<Vec<CommandAliases>>,
-    #[cfg_attr(feature = "python", py_child)]
-    pub kind: CommandKind,
+#[cfg_attr(feature = "python", py_child)]
+pub enum SetKind {
+    EmptySet(EmptySet),
 }
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
-#[cfg_attr(feature = "python", py_union)]
-pub enum CommandAliases {
-    AliasedExpr(AliasedExpr),
-    ModuleAliasDecl(ModuleAliasDecl),
+pub struct EmptySet {
+    pub r#type: Option<TypeExpr>,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+pub struct Command {
+    pub aliases: Option<Vec<Alias>>,
+    #[cfg_attr(feature = "python", py_child)]
+    pub kind: CommandKind,
 }
 
 #[derive(Debug, Clone)]
@@ -539,12 +581,19 @@ pub enum CommandKind {
     SessionResetAliasDecl(SessionResetAliasDecl),
     SessionResetModule(SessionResetModule),
     SessionResetAllAliases(SessionResetAllAliases),
+    Query(Query),
     DDLCommand(DDLCommand),
     DescribeStmt(DescribeStmt),
     ExplainStmt(ExplainStmt),
     AdministerStmt(AdministerStmt),
 }
 
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+pub struct Commands {
+    pub commands: Vec<Command>,
+}
+
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct SessionSetAliasDecl {
@@ -592,76 +641,47 @@ pub struct ShapeElement {
 pub struct Shape {
     pub expr: Option<Box<Expr>>,
     pub elements: Vec<ShapeElement>,
+    pub allow_factoring: bool,
 }
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct Query {
-    pub aliases: Option<Vec<QueryAliases>>,
+    pub aliases: Option<Vec<Alias>>,
     #[cfg_attr(feature = "python", py_child)]
     pub kind: QueryKind,
 }
 
-#[derive(Debug, Clone)]
-#[cfg_attr(feature = "python", derive(IntoPython))]
-#[cfg_attr(feature = "python", py_union)]
-pub enum QueryAliases {
-    AliasedExpr(AliasedExpr),
-    ModuleAliasDecl(ModuleAliasDecl),
-}
-
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
 #[cfg_attr(feature = "python", py_child)]
 pub enum QueryKind {
-    PipelinedQuery(PipelinedQuery),
+    SelectQuery(SelectQuery),
     GroupQuery(GroupQuery),
+    InternalGroupQuery(InternalGroupQuery),
     InsertQuery(InsertQuery),
     UpdateQuery(UpdateQuery),
+    DeleteQuery(DeleteQuery),
     ForQuery(ForQuery),
 }
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
-pub struct PipelinedQuery {
-    pub implicit: bool,
+pub struct SelectQuery {
+    pub result_alias: Option<String>,
+    pub result: Box<Expr>,
     pub r#where: Option<Box<Expr>>,
     pub orderby: Option<Vec<SortExpr>>,
     pub offset: Option<Box<Expr>>,
     pub limit: Option<Box<Expr>>,
     pub rptr_passthrough: bool,
-    #[cfg_attr(feature = "python", py_child)]
-    pub kind: PipelinedQueryKind,
-}
-
-#[derive(Debug, Clone)]
-#[cfg_attr(feature = "python", derive(IntoPython))]
-#[cfg_attr(feature = "python", py_child)]
-pub enum PipelinedQueryKind {
-    SelectQuery(SelectQuery),
-    DeleteQuery(DeleteQuery),
-}
-
-#[derive(Debug, Clone)]
-#[cfg_attr(feature = "python", derive(IntoPython))]
-pub struct SelectQuery {
-    pub result_alias: Option<String>,
-    pub result: Box<Expr>,
+    pub implicit: bool,
 }
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct GroupingIdentList {
-    pub elements: Vec<GroupingIdentListElements>,
-}
-
-#[derive(Debug, Clone)]
-#[cfg_attr(feature = "python", derive(IntoPython))]
-#[cfg_attr(feature = "python", py_union)]
-pub enum GroupingIdentListElements {
-    ObjectRef(ObjectRef),
-    Path(Path),
-    GroupingIdentList(GroupingIdentList),
+    pub elements: Vec<GroupingAtom>,
 }
 
 #[derive(Debug, Clone)]
@@ -683,16 +703,7 @@ pub enum GroupingElementKind {
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct GroupingSimple {
-    pub element: GroupingSimpleElement,
-}
-
-#[derive(Debug, Clone)]
-#[cfg_attr(feature = "python", derive(IntoPython))]
-#[cfg_attr(feature = "python", py_union)]
-pub enum GroupingSimpleElement {
-    ObjectRef(ObjectRef),
-    Path(Path),
-    GroupingIdentList(GroupingIdentList),
+    pub element: GroupingAtom,
 }
 
 #[derive(Debug, Clone)]
@@ -705,16 +716,7 @@ pub struct GroupingSets {
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct GroupingOperation {
     pub oper: String,
-    pub elements: Vec<GroupingOperationElements>,
-}
-
-#[derive(Debug, Clone)]
-#[cfg_attr(feature = "python", derive(IntoPython))]
-#[cfg_attr(feature = "python", py_union)]
-pub enum GroupingOperationElements {
-    ObjectRef(ObjectRef),
-    Path(Path),
-    GroupingIdentList(GroupingIdentList),
+    pub elements: Vec<GroupingAtom>,
 }
 
 #[derive(Debug, Clone)]
@@ -724,20 +726,15 @@ pub struct GroupQuery {
     pub using: Option<Vec<AliasedExpr>>,
     pub by: Vec<GroupingElement>,
     pub subject: Box<Expr>,
-    #[cfg_attr(feature = "python", py_child)]
-    pub kind: Option<GroupQueryKind>,
-}
-
-#[derive(Debug, Clone)]
-#[cfg_attr(feature = "python", derive(IntoPython))]
-#[cfg_attr(feature = "python", py_child)]
-pub enum GroupQueryKind {
-    InternalGroupQuery(InternalGroupQuery),
 }
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct InternalGroupQuery {
+    pub subject_alias: Option<String>,
+    pub using: Option<Vec<AliasedExpr>>,
+    pub by: Vec<GroupingElement>,
+    pub subject: Box<Expr>,
     pub group_alias: String,
     pub grouping_alias: Option<String>,
     pub from_desugaring: bool,
@@ -767,11 +764,18 @@ pub struct UpdateQuery {
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct DeleteQuery {
     pub subject: Box<Expr>,
+    pub r#where: Option<Box<Expr>>,
+    pub orderby: Option<Vec<SortExpr>>,
+    pub offset: Option<Box<Expr>>,
+    pub limit: Option<Box<Expr>>,
 }
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct ForQuery {
+    pub from_desugaring: bool,
+    pub has_union: bool,
+    pub optional: bool,
     pub iterator: Box<Expr>,
     pub iterator_alias: String,
     pub result_alias: Option<String>,
@@ -831,6 +835,28 @@ pub struct ReleaseSavepoint {
     pub name: String,
 }
 
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+pub struct DDL {
+    #[cfg_attr(feature = "python", py_child)]
+    pub kind: DDLKind,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+#[cfg_attr(feature = "python", py_child)]
+pub enum DDLKind {
+    Position(Position),
+    DDLOperation(DDLOperation),
+    NestedQLBlock(NestedQLBlock),
+    CommittedSchema(CommittedSchema),
+    IndexType(IndexType),
+    IndexCode(IndexCode),
+    FunctionCode(FunctionCode),
+    OperatorCode(OperatorCode),
+    CastCode(CastCode),
+}
+
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct Position {
@@ -870,7 +896,32 @@ pub struct DDLCommand {
 #[cfg_attr(feature = "python", derive(IntoPython))]
 #[cfg_attr(feature = "python", py_child)]
 pub enum DDLCommandKind {
-    NamedDDL(NamedDDL),
+    DDLQuery(DDLQuery),
+    NonTransactionalDDLCommand(NonTransactionalDDLCommand),
+    ObjectDDL(ObjectDDL),
+    MigrationCommand(MigrationCommand),
+    FunctionCommand(FunctionCommand),
+    OperatorCommand(OperatorCommand),
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+pub struct DDLQuery {
+    pub query: Query,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+pub struct NonTransactionalDDLCommand {
+    #[cfg_attr(feature = "python", py_child)]
+    pub kind: NonTransactionalDDLCommandKind,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+#[cfg_attr(feature = "python", py_child)]
+pub enum NonTransactionalDDLCommandKind {
+    DatabaseCommand(DatabaseCommand),
 }
 
 #[derive(Debug, Clone)]
@@ -954,34 +1005,37 @@ pub struct SetPointerOptionality {
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
-pub struct NamedDDL {
+pub struct ObjectDDL {
     pub name: ObjectRef,
     #[cfg_attr(feature = "python", py_child)]
-    pub kind: NamedDDLKind,
+    pub kind: ObjectDDLKind,
 }
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
 #[cfg_attr(feature = "python", py_child)]
-pub enum NamedDDLKind {
-    ObjectDDL(ObjectDDL),
+pub enum ObjectDDLKind {
+    CreateObject(CreateObject),
+    AlterObject(AlterObject),
+    DropObject(DropObject),
     Rename(Rename),
-}
-
-#[derive(Debug, Clone)]
-#[cfg_attr(feature = "python", derive(IntoPython))]
-pub struct ObjectDDL {
-    #[cfg_attr(feature = "python", py_child)]
-    pub kind: ObjectDDLKind,
-}
-
-#[derive(Debug, Clone)]
-#[cfg_attr(feature = "python", derive(IntoPython))]
-#[cfg_attr(feature = "python", py_child)]
-pub enum ObjectDDLKind {
-    CreateObject(CreateObject),
-    AlterObject(AlterObject),
-    DropObject(DropObject),
+    UnqualifiedObjectCommand(UnqualifiedObjectCommand),
+    AnnotationCommand(AnnotationCommand),
+    PseudoTypeCommand(PseudoTypeCommand),
+    ScalarTypeCommand(ScalarTypeCommand),
+    PropertyCommand(PropertyCommand),
+    ObjectTypeCommand(ObjectTypeCommand),
+    AliasCommand(AliasCommand),
+    GlobalCommand(GlobalCommand),
+    PermissionCommand(PermissionCommand),
+    LinkCommand(LinkCommand),
+    ConstraintCommand(ConstraintCommand),
+    IndexCommand(IndexCommand),
+    IndexMatchCommand(IndexMatchCommand),
+    AccessPolicyCommand(AccessPolicyCommand),
+    TriggerCommand(TriggerCommand),
+    RewriteCommand(RewriteCommand),
+    CastCommand(CastCommand),
 }
 
 #[derive(Debug, Clone)]
@@ -991,7 +1045,7 @@ pub struct CreateObject {
     pub sdl_alter_if_exists: bool,
     pub create_if_not_exists: bool,
     #[cfg_attr(feature = "python", py_child)]
-    pub kind: Option<CreateObjectKind>,
+    pub kind: CreateObjectKind,
 }
 
 #[derive(Debug, Clone)]
@@ -1002,6 +1056,7 @@ pub enum CreateObjectKind {
     CreateMigration(CreateMigration),
     CreateDatabase(CreateDatabase),
     CreateExtensionPackage(CreateExtensionPackage),
+    CreateExtensionPackageMigration(CreateExtensionPackageMigration),
     CreateExtension(CreateExtension),
     CreateFuture(CreateFuture),
     CreateModule(CreateModule),
@@ -1012,6 +1067,7 @@ pub enum CreateObjectKind {
     CreateGlobal(CreateGlobal),
     CreatePermission(CreatePermission),
     CreateConcreteConstraint(CreateConcreteConstraint),
+    CreateIndexMatch(CreateIndexMatch),
     CreateConcreteIndex(CreateConcreteIndex),
     CreateAnnotationValue(CreateAnnotationValue),
     CreateAccessPolicy(CreateAccessPolicy),
@@ -1020,14 +1076,13 @@ pub enum CreateObjectKind {
     CreateFunction(CreateFunction),
     CreateOperator(CreateOperator),
     CreateCast(CreateCast),
-    CreateIndexMatch(CreateIndexMatch),
 }
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct AlterObject {
     #[cfg_attr(feature = "python", py_child)]
-    pub kind: Option<AlterObjectKind>,
+    pub kind: AlterObjectKind,
 }
 
 #[derive(Debug, Clone)]
@@ -1041,6 +1096,7 @@ pub enum AlterObjectKind {
     AlterAnnotation(AlterAnnotation),
     AlterScalarType(AlterScalarType),
     AlterProperty(AlterProperty),
+    AlterConcreteUnknownPointer(AlterConcreteUnknownPointer),
     AlterConcreteProperty(AlterConcreteProperty),
     AlterObjectType(AlterObjectType),
     AlterAlias(AlterAlias),
@@ -1065,7 +1121,7 @@ pub enum AlterObjectKind {
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct DropObject {
     #[cfg_attr(feature = "python", py_child)]
-    pub kind: Option<DropObjectKind>,
+    pub kind: DropObjectKind,
 }
 
 #[derive(Debug, Clone)]
@@ -1075,6 +1131,8 @@ pub enum DropObjectKind {
     DropMigration(DropMigration),
     DropDatabase(DropDatabase),
     DropExtensionPackage(DropExtensionPackage),
+    DropExtensionPackageMigration(DropExtensionPackageMigration),
+    AlterExtension(AlterExtension),
     DropExtension(DropExtension),
     DropFuture(DropFuture),
     DropModule(DropModule),
@@ -1092,6 +1150,7 @@ pub enum DropObjectKind {
     DropConstraint(DropConstraint),
     DropConcreteConstraint(DropConcreteConstraint),
     DropIndex(DropIndex),
+    DropIndexMatch(DropIndexMatch),
     DropConcreteIndex(DropConcreteIndex),
     DropAnnotationValue(DropAnnotationValue),
     DropAccessPolicy(DropAccessPolicy),
@@ -1100,7 +1159,6 @@ pub enum DropObjectKind {
     DropFunction(DropFunction),
     DropOperator(DropOperator),
     DropCast(DropCast),
-    DropIndexMatch(DropIndexMatch),
 }
 
 #[derive(Debug, Clone)]
@@ -1109,7 +1167,7 @@ pub struct CreateExtendingObject {
     pub r#final: bool,
     pub bases: Vec<TypeExpr>,
     #[cfg_attr(feature = "python", py_child)]
-    pub kind: Option<CreateExtendingObjectKind>,
+    pub kind: CreateExtendingObjectKind,
 }
 
 #[derive(Debug, Clone)]
@@ -1139,12 +1197,39 @@ pub struct NestedQLBlock {
     pub text: Option<String>,
 }
 
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+pub struct MigrationCommand {
+    #[cfg_attr(feature = "python", py_child)]
+    pub kind: MigrationCommandKind,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+#[cfg_attr(feature = "python", py_child)]
+pub enum MigrationCommandKind {
+    CreateMigration(CreateMigration),
+    StartMigration(StartMigration),
+    AbortMigration(AbortMigration),
+    PopulateMigration(PopulateMigration),
+    AlterCurrentMigrationRejectProposed(AlterCurrentMigrationRejectProposed),
+    DescribeCurrentMigration(DescribeCurrentMigration),
+    CommitMigration(CommitMigration),
+    AlterMigration(AlterMigration),
+    DropMigration(DropMigration),
+    ResetSchema(ResetSchema),
+    StartMigrationRewrite(StartMigrationRewrite),
+    AbortMigrationRewrite(AbortMigrationRewrite),
+    CommitMigrationRewrite(CommitMigrationRewrite),
+}
+
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct CreateMigration {
     pub body: NestedQLBlock,
     pub parent: Option<ObjectRef>,
     pub metadata_only: bool,
+    pub target_sdl: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -1213,6 +1298,57 @@ pub struct AbortMigrationRewrite {}
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct CommitMigrationRewrite {}
 
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+pub struct UnqualifiedObjectCommand {
+    #[cfg_attr(feature = "python", py_child)]
+    pub kind: UnqualifiedObjectCommandKind,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+#[cfg_attr(feature = "python", py_child)]
+pub enum UnqualifiedObjectCommandKind {
+    GlobalObjectCommand(GlobalObjectCommand),
+    ExtensionCommand(ExtensionCommand),
+    FutureCommand(FutureCommand),
+    ModuleCommand(ModuleCommand),
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+pub struct GlobalObjectCommand {
+    #[cfg_attr(feature = "python", py_child)]
+    pub kind: GlobalObjectCommandKind,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+#[cfg_attr(feature = "python", py_child)]
+pub enum GlobalObjectCommandKind {
+    DatabaseCommand(DatabaseCommand),
+    ExtensionPackageCommand(ExtensionPackageCommand),
+    ExtensionPackageMigrationCommand(ExtensionPackageMigrationCommand),
+    RoleCommand(RoleCommand),
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+pub struct DatabaseCommand {
+    pub flavor: SchemaObjectClass,
+    #[cfg_attr(feature = "python", py_child)]
+    pub kind: DatabaseCommandKind,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+#[cfg_attr(feature = "python", py_child)]
+pub enum DatabaseCommandKind {
+    CreateDatabase(CreateDatabase),
+    AlterDatabase(AlterDatabase),
+    DropDatabase(DropDatabase),
+}
+
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct CreateDatabase {
@@ -1222,11 +1358,31 @@ pub struct CreateDatabase {
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
-pub struct AlterDatabase {}
+pub struct AlterDatabase {
+    pub force: bool,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+pub struct DropDatabase {
+    pub force: bool,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+pub struct ExtensionPackageCommand {
+    pub version: Constant,
+    #[cfg_attr(feature = "python", py_child)]
+    pub kind: ExtensionPackageCommandKind,
+}
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
-pub struct DropDatabase {}
+#[cfg_attr(feature = "python", py_child)]
+pub enum ExtensionPackageCommandKind {
+    CreateExtensionPackage(CreateExtensionPackage),
+    DropExtensionPackage(DropExtensionPackage),
+}
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
@@ -1240,11 +1396,83 @@ pub struct DropExtensionPackage {}
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
-pub struct CreateExtension {}
+pub struct ExtensionPackageMigrationCommand {
+    #[cfg_attr(feature = "python", py_child)]
+    pub kind: ExtensionPackageMigrationCommandKind,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+#[cfg_attr(feature = "python", py_child)]
+pub enum ExtensionPackageMigrationCommandKind {
+    CreateExtensionPackageMigration(CreateExtensionPackageMigration),
+    DropExtensionPackageMigration(DropExtensionPackageMigration),
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+pub struct CreateExtensionPackageMigration {
+    pub from_version: Constant,
+    pub to_version: Constant,
+    pub body: NestedQLBlock,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+pub struct DropExtensionPackageMigration {
+    pub from_version: Constant,
+    pub to_version: Constant,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+pub struct ExtensionCommand {
+    #[cfg_attr(feature = "python", py_child)]
+    pub kind: ExtensionCommandKind,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+#[cfg_attr(feature = "python", py_child)]
+pub enum ExtensionCommandKind {
+    CreateExtension(CreateExtension),
+    AlterExtension(AlterExtension),
+    DropExtension(DropExtension),
+}
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
-pub struct DropExtension {}
+pub struct CreateExtension {
+    pub version: Option<Constant>,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+pub struct AlterExtension {
+    pub version: Option<Constant>,
+    pub to_version: Constant,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+pub struct DropExtension {
+    pub version: Option<Constant>,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+pub struct FutureCommand {
+    #[cfg_attr(feature = "python", py_child)]
+    pub kind: FutureCommandKind,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+#[cfg_attr(feature = "python", py_child)]
+pub enum FutureCommandKind {
+    CreateFuture(CreateFuture),
+    DropFuture(DropFuture),
+}
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
@@ -1254,6 +1482,22 @@ pub struct CreateFuture {}
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct DropFuture {}
 
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+pub struct ModuleCommand {
+    #[cfg_attr(feature = "python", py_child)]
+    pub kind: ModuleCommandKind,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+#[cfg_attr(feature = "python", py_child)]
+pub enum ModuleCommandKind {
+    CreateModule(CreateModule),
+    AlterModule(AlterModule),
+    DropModule(DropModule),
+}
+
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct CreateModule {}
@@ -1266,6 +1510,22 @@ pub struct AlterModule {}
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct DropModule {}
 
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+pub struct RoleCommand {
+    #[cfg_attr(feature = "python", py_child)]
+    pub kind: RoleCommandKind,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+#[cfg_attr(feature = "python", py_child)]
+pub enum RoleCommandKind {
+    CreateRole(CreateRole),
+    AlterRole(AlterRole),
+    DropRole(DropRole),
+}
+
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct CreateRole {
@@ -1281,6 +1541,25 @@ pub struct AlterRole {}
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct DropRole {}
 
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+pub struct AnnotationCommand {
+    #[cfg_attr(feature = "python", py_child)]
+    pub kind: AnnotationCommandKind,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+#[cfg_attr(feature = "python", py_child)]
+pub enum AnnotationCommandKind {
+    CreateAnnotation(CreateAnnotation),
+    AlterAnnotation(AlterAnnotation),
+    DropAnnotation(DropAnnotation),
+    CreateAnnotationValue(CreateAnnotationValue),
+    AlterAnnotationValue(AlterAnnotationValue),
+    DropAnnotationValue(DropAnnotationValue),
+}
+
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct CreateAnnotation {
@@ -1296,10 +1575,40 @@ pub struct AlterAnnotation {}
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct DropAnnotation {}
 
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+pub struct PseudoTypeCommand {
+    #[cfg_attr(feature = "python", py_child)]
+    pub kind: PseudoTypeCommandKind,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+#[cfg_attr(feature = "python", py_child)]
+pub enum PseudoTypeCommandKind {
+    CreatePseudoType(CreatePseudoType),
+}
+
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct CreatePseudoType {}
 
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+pub struct ScalarTypeCommand {
+    #[cfg_attr(feature = "python", py_child)]
+    pub kind: ScalarTypeCommandKind,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+#[cfg_attr(feature = "python", py_child)]
+pub enum ScalarTypeCommandKind {
+    CreateScalarType(CreateScalarType),
+    AlterScalarType(AlterScalarType),
+    DropScalarType(DropScalarType),
+}
+
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct CreateScalarType {}
@@ -1312,6 +1621,26 @@ pub struct AlterScalarType {}
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct DropScalarType {}
 
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+pub struct PropertyCommand {
+    #[cfg_attr(feature = "python", py_child)]
+    pub kind: PropertyCommandKind,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+#[cfg_attr(feature = "python", py_child)]
+pub enum PropertyCommandKind {
+    CreateProperty(CreateProperty),
+    AlterProperty(AlterProperty),
+    DropProperty(DropProperty),
+    AlterConcreteUnknownPointer(AlterConcreteUnknownPointer),
+    CreateConcreteProperty(CreateConcreteProperty),
+    AlterConcreteProperty(AlterConcreteProperty),
+    DropConcreteProperty(DropConcreteProperty),
+}
+
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct CreateProperty {}
@@ -1333,7 +1662,7 @@ pub struct CreateConcretePointer {
     pub cardinality: SchemaCardinality,
     pub bases: Vec<TypeExpr>,
     #[cfg_attr(feature = "python", py_child)]
-    pub kind: Option<CreateConcretePointerKind>,
+    pub kind: CreateConcretePointerKind,
 }
 
 #[derive(Debug, Clone)]
@@ -1358,6 +1687,10 @@ pub enum CreateConcretePointerKind {
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct CreateConcreteUnknownPointer {}
 
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+pub struct AlterConcreteUnknownPointer {}
+
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct CreateConcreteProperty {}
@@ -1370,6 +1703,22 @@ pub struct AlterConcreteProperty {}
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct DropConcreteProperty {}
 
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+pub struct ObjectTypeCommand {
+    #[cfg_attr(feature = "python", py_child)]
+    pub kind: ObjectTypeCommandKind,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+#[cfg_attr(feature = "python", py_child)]
+pub enum ObjectTypeCommandKind {
+    CreateObjectType(CreateObjectType),
+    AlterObjectType(AlterObjectType),
+    DropObjectType(DropObjectType),
+}
+
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct CreateObjectType {}
@@ -1382,6 +1731,22 @@ pub struct AlterObjectType {}
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct DropObjectType {}
 
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+pub struct AliasCommand {
+    #[cfg_attr(feature = "python", py_child)]
+    pub kind: AliasCommandKind,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+#[cfg_attr(feature = "python", py_child)]
+pub enum AliasCommandKind {
+    CreateAlias(CreateAlias),
+    AlterAlias(AlterAlias),
+    DropAlias(DropAlias),
+}
+
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct CreateAlias {}
@@ -1394,6 +1759,22 @@ pub struct AlterAlias {}
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct DropAlias {}
 
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+pub struct GlobalCommand {
+    #[cfg_attr(feature = "python", py_child)]
+    pub kind: GlobalCommandKind,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+#[cfg_attr(feature = "python", py_child)]
+pub enum GlobalCommandKind {
+    CreateGlobal(CreateGlobal),
+    AlterGlobal(AlterGlobal),
+    DropGlobal(DropGlobal),
+}
+
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct CreateGlobal {
@@ -1429,6 +1810,22 @@ pub struct SetGlobalType {
     pub reset_value: bool,
 }
 
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+pub struct PermissionCommand {
+    #[cfg_attr(feature = "python", py_child)]
+    pub kind: PermissionCommandKind,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+#[cfg_attr(feature = "python", py_child)]
+pub enum PermissionCommandKind {
+    CreatePermission(CreatePermission),
+    AlterPermission(AlterPermission),
+    DropPermission(DropPermission),
+}
+
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct CreatePermission {}
@@ -1441,6 +1838,25 @@ pub struct AlterPermission {}
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct DropPermission {}
 
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+pub struct LinkCommand {
+    #[cfg_attr(feature = "python", py_child)]
+    pub kind: LinkCommandKind,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+#[cfg_attr(feature = "python", py_child)]
+pub enum LinkCommandKind {
+    CreateLink(CreateLink),
+    AlterLink(AlterLink),
+    DropLink(DropLink),
+    CreateConcreteLink(CreateConcreteLink),
+    AlterConcreteLink(AlterConcreteLink),
+    DropConcreteLink(DropConcreteLink),
+}
+
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct CreateLink {}
@@ -1465,12 +1881,29 @@ pub struct AlterConcreteLink {}
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct DropConcreteLink {}
 
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+pub struct ConstraintCommand {
+    #[cfg_attr(feature = "python", py_child)]
+    pub kind: ConstraintCommandKind,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+#[cfg_attr(feature = "python", py_child)]
+pub enum ConstraintCommandKind {
+    CreateConstraint(CreateConstraint),
+    AlterConstraint(AlterConstraint),
+    DropConstraint(DropConstraint),
+    ConcreteConstraintOp(ConcreteConstraintOp),
+}
+
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct CreateConstraint {
     pub r#abstract: bool,
     pub subjectexpr: Option<Box<Expr>>,
-    pub params: Vec<FuncParam>,
+    pub params: Vec<FuncParamDecl>,
 }
 
 #[derive(Debug, Clone)]
@@ -1481,6 +1914,25 @@ pub struct AlterConstraint {}
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct DropConstraint {}
 
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+pub struct ConcreteConstraintOp {
+    pub args: Vec<Box<Expr>>,
+    pub subjectexpr: Option<Box<Expr>>,
+    pub except_expr: Option<Box<Expr>>,
+    #[cfg_attr(feature = "python", py_child)]
+    pub kind: ConcreteConstraintOpKind,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+#[cfg_attr(feature = "python", py_child)]
+pub enum ConcreteConstraintOpKind {
+    CreateConcreteConstraint(CreateConcreteConstraint),
+    AlterConcreteConstraint(AlterConcreteConstraint),
+    DropConcreteConstraint(DropConcreteConstraint),
+}
+
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct CreateConcreteConstraint {
@@ -1503,6 +1955,23 @@ pub struct IndexType {
     pub kwargs: IndexMap<String, Box<Expr>>,
 }
 
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+pub struct IndexCommand {
+    #[cfg_attr(feature = "python", py_child)]
+    pub kind: IndexCommandKind,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+#[cfg_attr(feature = "python", py_child)]
+pub enum IndexCommandKind {
+    CreateIndex(CreateIndex),
+    AlterIndex(AlterIndex),
+    DropIndex(DropIndex),
+    ConcreteIndexCommand(ConcreteIndexCommand),
+}
+
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct IndexCode {
@@ -1516,7 +1985,7 @@ pub struct CreateIndex {
     pub kwargs: IndexMap<String, Box<Expr>>,
     pub index_types: Vec<IndexType>,
     pub code: Option<IndexCode>,
-    pub params: Vec<FuncParam>,
+    pub params: Vec<FuncParamDecl>,
 }
 
 #[derive(Debug, Clone)]
@@ -1529,23 +1998,59 @@ pub struct DropIndex {}
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
-pub struct CreateConcreteIndex {}
+pub struct IndexMatchCommand {
+    pub valid_type: TypeExpr,
+    #[cfg_attr(feature = "python", py_child)]
+    pub kind: IndexMatchCommandKind,
+}
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
-pub struct AlterConcreteIndex {}
+#[cfg_attr(feature = "python", py_child)]
+pub enum IndexMatchCommandKind {
+    CreateIndexMatch(CreateIndexMatch),
+    DropIndexMatch(DropIndexMatch),
+}
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
-pub struct DropConcreteIndex {}
+pub struct CreateIndexMatch {}
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
-pub struct CreateIndexMatch {}
+pub struct DropIndexMatch {}
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
-pub struct DropIndexMatch {}
+pub struct ConcreteIndexCommand {
+    pub kwargs: IndexMap<String, Box<Expr>>,
+    pub expr: Box<Expr>,
+    pub except_expr: Option<Box<Expr>>,
+    pub deferred: bool,
+    #[cfg_attr(feature = "python", py_child)]
+    pub kind: ConcreteIndexCommandKind,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+#[cfg_attr(feature = "python", py_child)]
+pub enum ConcreteIndexCommandKind {
+    CreateConcreteIndex(CreateConcreteIndex),
+    AlterConcreteIndex(AlterConcreteIndex),
+    DropConcreteIndex(DropConcreteIndex),
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+pub struct CreateConcreteIndex {}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+pub struct AlterConcreteIndex {}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+pub struct DropConcreteIndex {}
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
@@ -1563,6 +2068,22 @@ pub struct AlterAnnotationValue {
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct DropAnnotationValue {}
 
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+pub struct AccessPolicyCommand {
+    #[cfg_attr(feature = "python", py_child)]
+    pub kind: AccessPolicyCommandKind,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+#[cfg_attr(feature = "python", py_child)]
+pub enum AccessPolicyCommandKind {
+    CreateAccessPolicy(CreateAccessPolicy),
+    AlterAccessPolicy(AlterAccessPolicy),
+    DropAccessPolicy(DropAccessPolicy),
+}
+
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct CreateAccessPolicy {
@@ -1587,6 +2108,22 @@ pub struct AlterAccessPolicy {}
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct DropAccessPolicy {}
 
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+pub struct TriggerCommand {
+    #[cfg_attr(feature = "python", py_child)]
+    pub kind: TriggerCommandKind,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+#[cfg_attr(feature = "python", py_child)]
+pub enum TriggerCommandKind {
+    CreateTrigger(CreateTrigger),
+    AlterTrigger(AlterTrigger),
+    DropTrigger(DropTrigger),
+}
+
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct CreateTrigger {
@@ -1594,6 +2131,7 @@ pub struct CreateTrigger {
     pub kinds: Vec<TriggerKind>,
     pub scope: TriggerScope,
     pub expr: Box<Expr>,
+    pub condition: Option<Box<Expr>>,
 }
 
 #[derive(Debug, Clone)]
@@ -1604,6 +2142,23 @@ pub struct AlterTrigger {}
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct DropTrigger {}
 
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+pub struct RewriteCommand {
+    pub kinds: Vec<RewriteKind>,
+    #[cfg_attr(feature = "python", py_child)]
+    pub kind: RewriteCommandKind,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+#[cfg_attr(feature = "python", py_child)]
+pub enum RewriteCommandKind {
+    CreateRewrite(CreateRewrite),
+    AlterRewrite(AlterRewrite),
+    DropRewrite(DropRewrite),
+}
+
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct CreateRewrite {
@@ -1628,6 +2183,23 @@ pub struct FunctionCode {
     pub from_expr: bool,
 }
 
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+pub struct FunctionCommand {
+    pub params: Vec<FuncParamDecl>,
+    #[cfg_attr(feature = "python", py_child)]
+    pub kind: FunctionCommandKind,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+#[cfg_attr(feature = "python", py_child)]
+pub enum FunctionCommandKind {
+    CreateFunction(CreateFunction),
+    AlterFunction(AlterFunction),
+    DropFunction(DropFunction),
+}
+
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct CreateFunction {
@@ -1658,6 +2230,24 @@ pub struct OperatorCode {
     pub code: Option<String>,
 }
 
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+pub struct OperatorCommand {
+    pub kind: OperatorKind,
+    pub params: Vec<FuncParamDecl>,
+    #[cfg_attr(feature = "python", py_child)]
+    pub kind1: OperatorCommandKind,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+#[cfg_attr(feature = "python", py_child)]
+pub enum OperatorCommandKind {
+    CreateOperator(CreateOperator),
+    AlterOperator(AlterOperator),
+    DropOperator(DropOperator),
+}
+
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct CreateOperator {
@@ -1684,6 +2274,24 @@ pub struct CastCode {
     pub code: String,
 }
 
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+pub struct CastCommand {
+    pub from_type: TypeExpr,
+    pub to_type: TypeExpr,
+    #[cfg_attr(feature = "python", py_child)]
+    pub kind: CastCommandKind,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+#[cfg_attr(feature = "python", py_child)]
+pub enum CastCommandKind {
+    CreateCast(CreateCast),
+    AlterCast(AlterCast),
+    DropCast(DropCast),
+}
+
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct CreateCast {
@@ -1700,6 +2308,12 @@ pub struct AlterCast {}
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct DropCast {}
 
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+pub struct OptionalExpr {
+    pub expr: Box<Expr>,
+}
+
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct ConfigOp {
@@ -1765,6 +2379,21 @@ pub struct AdministerStmt {
     pub expr: FunctionCall,
 }
 
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+pub struct SDL {
+    #[cfg_attr(feature = "python", py_child)]
+    pub kind: SDLKind,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+#[cfg_attr(feature = "python", py_child)]
+pub enum SDLKind {
+    ModuleDeclaration(ModuleDeclaration),
+    Schema(Schema),
+}
+
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct ModuleDeclaration {
@@ -1776,7 +2405,7 @@ pub struct ModuleDeclaration {
 #[cfg_attr(feature = "python", derive(IntoPython))]
 #[cfg_attr(feature = "python", py_union)]
 pub enum ModuleDeclarationDeclarations {
-    NamedDDL(DDLOperation),
+    ObjectDDL(Command),
     ModuleDeclaration(ModuleDeclaration),
 }
 
@@ -1790,7 +2419,7 @@ pub struct Schema {
 #[cfg_attr(feature = "python", derive(IntoPython))]
 #[cfg_attr(feature = "python", py_union)]
 pub enum SchemaDeclarations {
-    NamedDDL(DDLOperation),
+    ObjectDDL(Command),
     ModuleDeclaration(ModuleDeclaration),
 }
 
@@ -1828,6 +2457,26 @@ pub enum DescribeGlobal {
     Roles,
 }
 
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+#[cfg_attr(feature = "python", py_enum(qlast.ConstantKind))]
+pub enum ConstantKind {
+    STRING,
+    BOOLEAN,
+    INTEGER,
+    FLOAT,
+    BIGINT,
+    DECIMAL,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+#[cfg_attr(feature = "python", py_enum(qlast.TypeOpName))]
+pub enum TypeOpName {
+    OR,
+    AND,
+}
+
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
 #[cfg_attr(feature = "python", py_enum(qlast.ShapeOp))]
@@ -1848,6 +2497,16 @@ pub enum ShapeOrigin {
     MATERIALIZATION,
 }
 
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+#[cfg_attr(feature = "python", py_enum(qlast.BranchType))]
+pub enum BranchType {
+    EMPTY,
+    SCHEMA,
+    DATA,
+    TEMPLATE,
+}
+
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
 #[cfg_attr(feature = "python", py_enum(qlast.Language))]
@@ -1874,6 +2533,16 @@ pub enum TypeModifier {
     SingletonType,
 }
 
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+#[cfg_attr(feature = "python", py_enum(qltypes.Polymorphism))]
+pub enum Polymorphism {
+    NotUsed,
+    Simple,
+    Array,
+    Collection,
+}
+
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
 #[cfg_attr(feature = "python", py_enum(qltypes.OperatorKind))]
@@ -1948,6 +2617,15 @@ pub enum Multiplicity {
     UNKNOWN,
 }
 
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+#[cfg_attr(feature = "python", py_enum(qltypes.IndexDeferrability))]
+pub enum IndexDeferrability {
+    Prohibited,
+    Permitted,
+    Required,
+}
+
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
 #[cfg_attr(feature = "python", py_enum(qltypes.AccessPolicyAction))]
@@ -2000,6 +2678,15 @@ pub enum RewriteKind {
     Insert,
 }
 
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+#[cfg_attr(feature = "python", py_enum(qltypes.SplatStrategy))]
+pub enum SplatStrategy {
+    Default,
+    Explicit,
+    Implicit,
+}
+
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
 #[cfg_attr(feature = "python", py_enum(qltypes.DescribeLanguage))]
@@ -2018,18 +2705,22 @@ pub enum SchemaObjectClass {
     ALIAS,
     ANNOTATION,
     ARRAY_TYPE,
+    BRANCH,
     CAST,
     CONSTRAINT,
     DATABASE,
     EXTENSION,
     EXTENSION_PACKAGE,
+    EXTENSION_PACKAGE_MIGRATION,
     FUTURE,
     FUNCTION,
     GLOBAL,
     INDEX,
+    INDEX_MATCH,
     LINK,
     MIGRATION,
     MODULE,
+    MULTIRANGE_TYPE,
     OPERATOR,
     PARAMETER,
     PERMISSION,
@@ -2072,12 +2763,3 @@ pub enum ConfigScope {
     SESSION,
     GLOBAL,
 }
-
-#[derive(Debug, Clone)]
-#[cfg_attr(feature = "python", derive(IntoPython))]
-#[cfg_attr(feature = "python", py_enum(qlast.BranchType))]
-pub enum BranchType {
-    EMPTY,
-    SCHEMA,
-    DATA,
-}
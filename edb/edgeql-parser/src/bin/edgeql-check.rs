@@ -0,0 +1,231 @@
+//! Checks EdgeQL query files (`.edgeql`) and schema files (`.esdl`) for
+//! syntax errors
+//!
+//! Full parsing requires the grammar spec (`grammar.bc`) generated by the
+//! EdgeDB build. Without it, files are only tokenized and schema files are
+//! checked by the declaration-level reader of [edgeql_parser::schema].
+use std::io::Read;
+use std::process::ExitCode;
+use std::{env, fs, io};
+
+use edgeql_parser::diagnostics::{self, Diagnostic, FileReport, Severity};
+use edgeql_parser::parser::{self, Coverage, Spec, SpecSerializable, Terminal};
+use edgeql_parser::schema;
+use edgeql_parser::soft_keywords::demote_soft_terminals;
+use edgeql_parser::tokenizer::Tokenizer;
+
+const USAGE: &str = "\
+Usage: edgeql-check [OPTIONS] [FILE]...
+
+Checks EdgeQL and SDL files for syntax errors. Reads stdin if no files
+are given or the file is `-`. Files with the `.esdl` extension are parsed
+as schema.
+
+Options:
+  --format <FORMAT>       Output format: `pretty`, `json` (a versioned
+                          document with all diagnostics) or `sarif`
+                          (SARIF 2.1.0 log), the latter two are printed
+                          to stdout [default: pretty]
+  --grammar <PATH>        Grammar spec (grammar.bc) used for parsing,
+                          defaults to the EDGEQL_GRAMMAR_SPEC variable
+  --coverage              Print which grammar productions the files did
+                          not exercise to stderr, requires the grammar
+  -h, --help              Print this help
+";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Pretty,
+    Json,
+    Sarif,
+}
+
+#[derive(Debug)]
+struct Options {
+    format: Format,
+    grammar: Option<String>,
+    coverage: bool,
+    files: Vec<String>,
+}
+
+fn parse_args(args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options {
+        format: Format::Pretty,
+        grammar: env::var("EDGEQL_GRAMMAR_SPEC").ok(),
+        coverage: false,
+        files: Vec::new(),
+    };
+    let mut args = args.peekable();
+    while let Some(arg) = args.next() {
+        let (name, inline_value) = match arg.split_once('=') {
+            Some((name, value)) if name.starts_with("--") => (name.to_string(), Some(value)),
+            _ => (arg.clone(), None),
+        };
+        let mut value = || {
+            inline_value
+                .map(|v| v.to_string())
+                .or_else(|| args.next())
+                .ok_or_else(|| format!("option `{name}` requires a value"))
+        };
+        match &name[..] {
+            "-h" | "--help" => return Err(String::new()),
+            "--format" => {
+                options.format = match &value()?[..] {
+                    "pretty" => Format::Pretty,
+                    "json" => Format::Json,
+                    "sarif" => Format::Sarif,
+                    other => return Err(format!("unknown format `{other}`")),
+                }
+            }
+            "--grammar" => options.grammar = Some(value()?),
+            "--coverage" => options.coverage = true,
+            opt if opt.starts_with('-') && opt != "-" => {
+                return Err(format!("unknown option `{opt}`"))
+            }
+            _ => options.files.push(arg),
+        }
+    }
+    if options.coverage && options.grammar.is_none() {
+        return Err("option `--coverage` requires the grammar spec".into());
+    }
+    if options.files.is_empty() {
+        options.files.push("-".into());
+    }
+    Ok(options)
+}
+
+fn load_spec(path: &str) -> Result<Spec, String> {
+    let bytes = fs::read(path).map_err(|e| format!("cannot read grammar spec {path}: {e}"))?;
+    let spec = bincode::deserialize::<SpecSerializable>(&bytes)
+        .map_err(|e| format!("bad grammar spec {path}: {e}"))?;
+    Ok(spec.into())
+}
+
+fn read_source(file: &str) -> io::Result<String> {
+    if file == "-" {
+        let mut buf = String::new();
+        io::stdin().read_to_string(&mut buf)?;
+        Ok(buf)
+    } else {
+        fs::read_to_string(file)
+    }
+}
+
+fn check(
+    source: &str,
+    is_schema: bool,
+    spec: Option<&Spec>,
+    coverage: Option<&mut Coverage>,
+) -> Vec<Diagnostic> {
+    let Some(spec) = spec else {
+        if is_schema {
+            return schema::parse(source)
+                .err()
+                .into_iter()
+                .map(Into::into)
+                .collect();
+        }
+        return Tokenizer::new(source)
+            .validated_values()
+            .find_map(Result::err)
+            .into_iter()
+            .map(Into::into)
+            .collect();
+    };
+
+    let start = if is_schema {
+        "STARTSDLDOCUMENT"
+    } else {
+        "STARTBLOCK"
+    };
+    let mut terminals = vec![Terminal::from_start_name(start)];
+    for token in Tokenizer::new(source).validated_values().with_eof() {
+        match token {
+            Ok(token) => terminals.push(Terminal::from_token(token)),
+            Err(e) => return vec![e.into()],
+        }
+    }
+    demote_soft_terminals(&mut terminals);
+    let ctx = parser::Context::new(spec);
+    let (tree, errors) = parser::parse(&terminals, &ctx);
+    if let (Some(coverage), Some(tree)) = (coverage, tree) {
+        coverage.record(tree);
+    }
+    errors.into_iter().map(Into::into).collect()
+}
+
+fn main() -> ExitCode {
+    let options = match parse_args(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) if message.is_empty() => {
+            print!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        Err(message) => {
+            eprintln!("edgeql-check: {message}\n\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+    let spec = match options.grammar.as_deref().map(load_spec).transpose() {
+        Ok(spec) => spec,
+        Err(message) => {
+            eprintln!("edgeql-check: {message}");
+            return ExitCode::from(2);
+        }
+    };
+
+    let mut coverage = spec
+        .as_ref()
+        .filter(|_| options.coverage)
+        .map(Coverage::new);
+    let mut failed = false;
+    let mut json = Vec::new();
+    let mut checked = Vec::new();
+    for file in &options.files {
+        let source = match read_source(file) {
+            Ok(source) => source,
+            Err(e) => {
+                eprintln!("edgeql-check: cannot read {file}: {e}");
+                return ExitCode::from(2);
+            }
+        };
+        let filename = if file == "-" { "<stdin>" } else { file };
+        let diagnostics = check(
+            &source,
+            file.ends_with(".esdl"),
+            spec.as_ref(),
+            coverage.as_mut(),
+        );
+        failed |= diagnostics.iter().any(|d| d.severity == Severity::Error);
+        match options.format {
+            Format::Pretty => eprint!("{}", diagnostics::render(&source, filename, &diagnostics)),
+            Format::Json => {
+                json.extend(diagnostics.iter().map(|d| d.to_json(&source, filename)));
+            }
+            Format::Sarif => checked.push((filename, source, diagnostics)),
+        }
+    }
+    match options.format {
+        Format::Pretty => {}
+        Format::Json => println!("{}", diagnostics::json_document(json)),
+        Format::Sarif => {
+            let files = checked
+                .iter()
+                .map(|(uri, source, diagnostics)| FileReport {
+                    uri,
+                    source,
+                    diagnostics,
+                })
+                .collect::<Vec<_>>();
+            println!("{}", diagnostics::render_sarif("edgeql-check", &files));
+        }
+    }
+    if let (Some(coverage), Some(spec)) = (&coverage, &spec) {
+        eprint!("{}", coverage.report(spec));
+    }
+    if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
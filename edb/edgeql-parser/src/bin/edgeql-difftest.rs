@@ -0,0 +1,79 @@
+//! Replays a corpus exported from the Python grammar tests and reports the
+//! cases the Rust parser accepts or rejects differently, see
+//! [edgeql_parser::difftest]
+use std::process::ExitCode;
+use std::{env, fs};
+
+use edgeql_parser::difftest::{parse_case, read_corpus, replay};
+use edgeql_parser::parser::{Spec, SpecSerializable};
+
+const USAGE: &str = "\
+Usage: edgeql-difftest [OPTIONS] CORPUS...
+
+Parses every case of the corpus files (JSON lines written by
+`edb export-syntax-corpus`) and prints the divergences from the Python
+parser as a JSON document to stdout. Fails if there are any.
+
+Options:
+  --grammar <PATH>  Grammar spec (grammar.bc) used for parsing,
+                    defaults to the EDGEQL_GRAMMAR_SPEC variable
+  -h, --help        Print this help
+";
+
+fn load_spec(path: &str) -> Result<Spec, String> {
+    let bytes = fs::read(path).map_err(|e| format!("cannot read grammar spec {path}: {e}"))?;
+    let spec = bincode::deserialize::<SpecSerializable>(&bytes)
+        .map_err(|e| format!("bad grammar spec {path}: {e}"))?;
+    Ok(spec.into())
+}
+
+fn run(mut args: impl Iterator<Item = String>) -> Result<bool, String> {
+    let mut grammar = env::var("EDGEQL_GRAMMAR_SPEC").ok();
+    let mut files = Vec::new();
+    while let Some(arg) = args.next() {
+        match &arg[..] {
+            "-h" | "--help" => return Err(String::new()),
+            "--grammar" => {
+                grammar = Some(args.next().ok_or("option `--grammar` requires a value")?)
+            }
+            opt if opt.starts_with("--grammar=") => {
+                grammar = Some(opt["--grammar=".len()..].to_string())
+            }
+            opt if opt.starts_with('-') => return Err(format!("unknown option `{opt}`")),
+            _ => files.push(arg),
+        }
+    }
+    if files.is_empty() {
+        return Err("no corpus files given".into());
+    }
+    let spec = load_spec(&grammar.ok_or("grammar spec is required")?)?;
+
+    let mut cases = Vec::new();
+    for file in &files {
+        let text = fs::read_to_string(file).map_err(|e| format!("cannot read {file}: {e}"))?;
+        cases.extend(read_corpus(&text).map_err(|e| format!("{file}: {e}"))?);
+    }
+    let report = replay(&cases, |case| parse_case(&spec, case));
+    println!("{}", report.to_json());
+    eprintln!(
+        "edgeql-difftest: {} of {} cases diverge",
+        report.divergences.len(),
+        report.total
+    );
+    Ok(report.divergences.is_empty())
+}
+
+fn main() -> ExitCode {
+    match run(env::args().skip(1)) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(message) if message.is_empty() => {
+            print!("{USAGE}");
+            ExitCode::SUCCESS
+        }
+        Err(message) => {
+            eprintln!("edgeql-difftest: {message}\n\n{USAGE}");
+            ExitCode::from(2)
+        }
+    }
+}
@@ -0,0 +1,129 @@
+//! Formats EdgeQL query files (`.edgeql`) and schema files (`.esdl`) using
+//! [edgeql_parser::format]
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::{env, fs, io};
+
+use edgeql_parser::diagnostics::{self, Diagnostic};
+use edgeql_parser::format::format;
+
+const USAGE: &str = "\
+Usage: edgeql-fmt [OPTIONS] [PATH]...
+
+Formats EdgeQL and SDL files in place. Directories are searched for
+`.edgeql` and `.esdl` files recursively. Formats stdin to stdout if no
+paths are given or the path is `-`.
+
+Options:
+  --check     Do not write files, fail if any file is not formatted
+  -h, --help  Print this help
+";
+
+const EXTENSIONS: &[&str] = &["edgeql", "esdl"];
+
+fn collect_files(path: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    if !path.is_dir() {
+        files.push(path.to_path_buf());
+        return Ok(());
+    }
+    let mut entries = fs::read_dir(path)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<io::Result<Vec<_>>>()?;
+    entries.sort();
+    for entry in entries {
+        let is_source = entry
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| EXTENSIONS.contains(&e));
+        if entry.is_dir() || is_source {
+            collect_files(&entry, files)?;
+        }
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let mut check = false;
+    let mut paths = Vec::new();
+    for arg in env::args().skip(1) {
+        match &arg[..] {
+            "-h" | "--help" => {
+                print!("{USAGE}");
+                return ExitCode::SUCCESS;
+            }
+            "--check" => check = true,
+            opt if opt.starts_with('-') && opt != "-" => {
+                eprintln!("edgeql-fmt: unknown option `{opt}`\n\n{USAGE}");
+                return ExitCode::from(2);
+            }
+            _ => paths.push(arg),
+        }
+    }
+    if paths.is_empty() {
+        paths.push("-".into());
+    }
+
+    let mut files = Vec::new();
+    for path in &paths {
+        if path == "-" {
+            files.push(PathBuf::from("-"));
+        } else if let Err(e) = collect_files(Path::new(path), &mut files) {
+            eprintln!("edgeql-fmt: cannot read {path}: {e}");
+            return ExitCode::from(2);
+        }
+    }
+
+    let mut failed = false;
+    for file in &files {
+        let is_stdin = file.as_os_str() == "-";
+        let filename = if is_stdin {
+            "<stdin>".into()
+        } else {
+            file.display().to_string()
+        };
+        let source = if is_stdin {
+            let mut buf = String::new();
+            io::stdin().read_to_string(&mut buf).map(|_| buf)
+        } else {
+            fs::read_to_string(file)
+        };
+        let source = match source {
+            Ok(source) => source,
+            Err(e) => {
+                eprintln!("edgeql-fmt: cannot read {filename}: {e}");
+                return ExitCode::from(2);
+            }
+        };
+        let formatted = match format(&source) {
+            Ok(formatted) => formatted,
+            Err(e) => {
+                let diagnostic = Diagnostic::from(e);
+                eprint!("{}", diagnostics::render(&source, &filename, &[diagnostic]));
+                failed = true;
+                continue;
+            }
+        };
+        if check {
+            if formatted != source {
+                eprintln!("{filename} is not formatted");
+                failed = true;
+            }
+        } else if is_stdin {
+            if let Err(e) = io::stdout().write_all(formatted.as_bytes()) {
+                eprintln!("edgeql-fmt: cannot write output: {e}");
+                return ExitCode::from(2);
+            }
+        } else if formatted != source {
+            if let Err(e) = fs::write(file, formatted) {
+                eprintln!("edgeql-fmt: cannot write {filename}: {e}");
+                return ExitCode::from(2);
+            }
+        }
+    }
+    if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
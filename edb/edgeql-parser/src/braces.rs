@@ -0,0 +1,276 @@
+//! Kinds of curly braces
+//!
+//! Braces open set constructors (`{1, 2}`), shapes (`User { name }`) and
+//! blocks of DDL commands or SDL declarations (`type User { ... }`).
+//! [braces] tells them apart from tokens, and empty sets (`{}`) get their
+//! own kind with the type they are cast to, if any (`<str>{}`).
+//!
+//! A brace is a block if it is directly inside of a block or of a top-level
+//! DDL or SDL statement, and not in an expression after `:=`. Otherwise it
+//! opens a shape if it follows a name, a closing bracket, `:` or `set`, and
+//! a set otherwise.
+//!
+//! The parsed AST has its own nodes for these: `Set`, `EmptySet` (with the
+//! cast in its `type`) and `Shape`, while blocks are the commands of DDL and
+//! SDL statements. This module is a heuristic for tools working on tokens
+//! (highlighting, formatting, completions), code the rules above do not
+//! cover may get a different kind than it has in the AST.
+use crate::keywords::Keyword;
+use crate::params::cast_before;
+use crate::position::Span;
+use crate::tokenizer::{Error, Kind, Token, Tokenizer};
+
+/// Keywords starting top-level statements that contain blocks
+const BLOCK_STATEMENTS: &[&str] = &[
+    "abstract",
+    "alias",
+    "alter",
+    "create",
+    "drop",
+    "function",
+    "global",
+    "module",
+    "multi",
+    "optional",
+    "permission",
+    "required",
+    "scalar",
+    "single",
+    "start",
+    "type",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BraceKind {
+    /// Set constructor with the number of its elements
+    Set {
+        elements: usize,
+    },
+    /// `{}`, with the type of the cast before it, like `optional str` in
+    /// `<optional str>{}`
+    EmptySet {
+        cast: Option<String>,
+    },
+    Shape,
+    /// Block of DDL commands or SDL declarations
+    Block,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Brace {
+    pub kind: BraceKind,
+    /// From the opening to the closing brace, including the cast of an
+    /// empty set. Only the opening brace if it is not closed.
+    pub span: Span,
+    /// Number of brackets of any kind enclosing the brace
+    pub depth: usize,
+}
+
+struct Open {
+    /// Index of the brace in the result, `None` for other brackets
+    brace: Option<usize>,
+    /// Index of the opening token
+    token: usize,
+    commas: usize,
+}
+
+/// Returns all braces of the text, ordered by the opening brace, fails if
+/// the text cannot be tokenized
+pub fn braces(text: &str) -> Result<Vec<Brace>, Error> {
+    let tokens = Tokenizer::new(text)
+        .validated_values()
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut result: Vec<Brace> = Vec::new();
+    let mut open: Vec<Open> = Vec::new();
+    for (idx, tok) in tokens.iter().enumerate() {
+        match tok.kind {
+            Kind::OpenParen | Kind::OpenBracket => open.push(Open {
+                brace: None,
+                token: idx,
+                commas: 0,
+            }),
+            Kind::OpenBrace => {
+                let (start, assigned) = statement_start(&tokens, idx);
+                let in_commands = match open.last() {
+                    None => {
+                        keyword(tokens.get(start)).is_some_and(|kw| BLOCK_STATEMENTS.contains(&kw))
+                    }
+                    Some(parent) => parent
+                        .brace
+                        .is_some_and(|b| result[b].kind == BraceKind::Block),
+                };
+                let kind = if in_commands && !assigned {
+                    BraceKind::Block
+                } else if is_shape(&tokens, idx) {
+                    BraceKind::Shape
+                } else {
+                    BraceKind::Set { elements: 0 }
+                };
+                result.push(Brace {
+                    kind,
+                    span: tok.span,
+                    depth: open.len(),
+                });
+                open.push(Open {
+                    brace: Some(result.len() - 1),
+                    token: idx,
+                    commas: 0,
+                });
+            }
+            Kind::Comma => {
+                if let Some(parent) = open.last_mut() {
+                    parent.commas += 1;
+                }
+            }
+            Kind::CloseParen | Kind::CloseBracket | Kind::CloseBrace => {
+                let Some(Open {
+                    brace: Some(brace),
+                    token,
+                    commas,
+                }) = open.pop()
+                else {
+                    continue;
+                };
+                let brace = &mut result[brace];
+                brace.span = brace.span.extend(&tok.span);
+                if !matches!(brace.kind, BraceKind::Set { .. }) {
+                    continue;
+                }
+                brace.kind = if token + 1 == idx {
+                    let cast = cast_before(&tokens, token);
+                    if let Some((start, _)) = &cast {
+                        brace.span = tokens[*start].span.extend(&brace.span);
+                    }
+                    BraceKind::EmptySet {
+                        cast: cast.map(|(_, text)| text),
+                    }
+                } else if tokens[idx - 1].kind == Kind::Comma {
+                    BraceKind::Set { elements: commas }
+                } else {
+                    BraceKind::Set {
+                        elements: commas + 1,
+                    }
+                };
+            }
+            _ => {}
+        }
+    }
+    Ok(result)
+}
+
+/// Whether the brace at `idx` opens a shape rather than a set, assuming it
+/// is not a block
+pub(crate) fn is_shape(tokens: &[Token], idx: usize) -> bool {
+    let Some(prev) = idx.checked_sub(1).map(|i| &tokens[i]) else {
+        return false;
+    };
+    matches!(
+        prev.kind,
+        Kind::Ident
+            | Kind::BacktickName
+            | Kind::CloseParen
+            | Kind::CloseBracket
+            | Kind::Colon
+            | Kind::Keyword(Keyword("set"))
+    )
+}
+
+fn keyword(token: Option<&Token>) -> Option<&'static str> {
+    match token?.kind {
+        Kind::Keyword(Keyword(kw)) => Some(kw),
+        _ => None,
+    }
+}
+
+/// Index of the first token of the statement containing the brace at
+/// `idx` (within the enclosing bracket), and whether the brace is in an
+/// expression after `:=`
+fn statement_start(tokens: &[Token], idx: usize) -> (usize, bool) {
+    let mut start = idx;
+    let mut depth = 0usize;
+    let mut assigned = false;
+    while start > 0 {
+        match tokens[start - 1].kind {
+            Kind::CloseParen | Kind::CloseBracket | Kind::CloseBrace => depth += 1,
+            Kind::OpenParen | Kind::OpenBracket | Kind::OpenBrace if depth == 0 => break,
+            Kind::OpenParen | Kind::OpenBracket | Kind::OpenBrace => depth -= 1,
+            Kind::Semicolon if depth == 0 => break,
+            Kind::Assign if depth == 0 => assigned = true,
+            _ => {}
+        }
+        start -= 1;
+    }
+    (start, assigned)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{braces, BraceKind};
+
+    fn kinds(text: &str) -> Vec<BraceKind> {
+        braces(text).unwrap().into_iter().map(|b| b.kind).collect()
+    }
+
+    fn empty(cast: Option<&str>) -> BraceKind {
+        BraceKind::EmptySet {
+            cast: cast.map(Into::into),
+        }
+    }
+
+    #[test]
+    fn queries() {
+        assert_eq!(
+            kinds("select User { name, friends: { name } } filter .name in {'a', 'b',}"),
+            [
+                BraceKind::Shape,
+                BraceKind::Shape,
+                BraceKind::Set { elements: 2 },
+            ]
+        );
+        let text = "select {<str>{}, {}, <optional array<int64>>{}, {(1, 2)}}";
+        assert_eq!(
+            kinds(text),
+            [
+                BraceKind::Set { elements: 4 },
+                empty(Some("str")),
+                empty(None),
+                empty(Some("optional array<int64>")),
+                BraceKind::Set { elements: 1 },
+            ]
+        );
+        let span = braces(text).unwrap()[1].span;
+        assert_eq!(&text[span.start as usize..span.end as usize], "<str>{}");
+        assert_eq!(
+            kinds("update User set { name := {} }"),
+            [BraceKind::Shape, empty(None)]
+        );
+    }
+
+    #[test]
+    fn blocks() {
+        assert_eq!(
+            kinds(
+                "create type User { create property name -> str { set default := {} }; }; \
+                 select User { name }"
+            ),
+            [
+                BraceKind::Block,
+                BraceKind::Block,
+                empty(None),
+                BraceKind::Shape,
+            ]
+        );
+        assert_eq!(
+            kinds(
+                "module default { type User { multi friends: User { since: datetime; }; \
+                 names := (select .friends { name }) } }"
+            ),
+            [
+                BraceKind::Block,
+                BraceKind::Block,
+                BraceKind::Block,
+                BraceKind::Shape,
+            ]
+        );
+    }
+}
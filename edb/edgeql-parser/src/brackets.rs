@@ -0,0 +1,104 @@
+//! Nesting depth of brackets, for rainbow bracket coloring in editors
+//!
+//! Works on tokens only, so it handles any input that tokenizes, including
+//! incomplete and invalid queries. Brackets inside of strings and comments
+//! are ignored by the tokenizer.
+use crate::position::Span;
+use crate::tokenizer::{Kind, Tokenizer};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bracket {
+    /// One of the paren, bracket or brace kinds
+    pub kind: Kind,
+    pub span: Span,
+    /// Number of brackets enclosing this one, zero at top level
+    pub depth: usize,
+    /// Index of the matching bracket in the result, `None` for unmatched
+    /// opening and closing brackets and mismatched pairs like `(]`
+    pub matching: Option<usize>,
+}
+
+impl Bracket {
+    pub fn is_open(&self) -> bool {
+        matches!(
+            self.kind,
+            Kind::OpenParen | Kind::OpenBracket | Kind::OpenBrace
+        )
+    }
+}
+
+/// Returns all brackets of the text in order. Tokenization stops at the
+/// first error (e.g. unterminated string), brackets until it are returned.
+pub fn brackets(text: &str) -> Vec<Bracket> {
+    let mut result: Vec<Bracket> = Vec::new();
+    let mut open = Vec::new();
+    for token in Tokenizer::new(text).map_while(Result::ok) {
+        let closes = match token.kind {
+            Kind::OpenParen | Kind::OpenBracket | Kind::OpenBrace => {
+                open.push(result.len());
+                result.push(Bracket {
+                    kind: token.kind,
+                    span: token.span,
+                    depth: open.len() - 1,
+                    matching: None,
+                });
+                continue;
+            }
+            Kind::CloseParen => Kind::OpenParen,
+            Kind::CloseBracket => Kind::OpenBracket,
+            Kind::CloseBrace => Kind::OpenBrace,
+            _ => continue,
+        };
+        let idx = result.len();
+        let matching = open.last().copied().filter(|&o| result[o].kind == closes);
+        if let Some(o) = matching {
+            open.pop();
+            result[o].matching = Some(idx);
+        }
+        result.push(Bracket {
+            kind: token.kind,
+            span: token.span,
+            depth: open.len(),
+            matching,
+        });
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::brackets;
+
+    fn depths(text: &str) -> Vec<(usize, Option<usize>)> {
+        brackets(text)
+            .iter()
+            .map(|b| (b.depth, b.matching))
+            .collect()
+    }
+
+    #[test]
+    fn nested() {
+        let result = brackets("select User { tags := [(1, '{')] }");
+        assert_eq!(result.len(), 6);
+        assert!(result[0].is_open() && !result[5].is_open());
+        assert_eq!(
+            depths("select User { tags := [(1, '{')] }"),
+            vec![
+                (0, Some(5)),
+                (1, Some(4)),
+                (2, Some(3)),
+                (2, Some(2)),
+                (1, Some(1)),
+                (0, Some(0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn unmatched() {
+        assert_eq!(
+            depths("select (1]) ) { # }"),
+            vec![(0, Some(2)), (1, None), (0, Some(0)), (0, None), (0, None)]
+        );
+    }
+}
@@ -0,0 +1,275 @@
+//! Validation of checked-in queries from build scripts
+//!
+//! ```ignore
+//! // build.rs
+//! fn main() {
+//!     edgeql_parser::build::QueryCheck::new("queries")
+//!         .run()
+//!         .unwrap();
+//! }
+//! ```
+//!
+//! [QueryCheck::run] checks every `.edgeql` file of the directory, prints
+//! problems as cargo warnings with the file, line and column, and fails if
+//! there are syntax errors, which fails the build. Cargo reruns the build
+//! script when files of the directory change.
+//!
+//! Without a grammar spec (see `QueryCheck::with_spec`, requires the
+//! `serde` feature) queries are only tokenized and checked for unbalanced
+//! brackets. Either way, shapes are checked for duplicate pointers, which
+//! the grammar allows but the server rejects.
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::brackets::brackets;
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::parser::Spec;
+#[cfg(feature = "serde")]
+use crate::parser::{self, Terminal};
+use crate::position::InflatedPos;
+use crate::shape::parse_statement;
+#[cfg(feature = "serde")]
+use crate::soft_keywords::demote_soft_terminals;
+use crate::tokenizer::Tokenizer;
+
+pub const QUERY_EXTENSION: &str = "edgeql";
+
+#[derive(Debug, thiserror::Error)]
+pub enum BuildError {
+    #[error("cannot read queries: {0}")]
+    Io(#[from] io::Error),
+    #[error("{errors} errors in {files} EdgeQL files")]
+    Invalid { errors: usize, files: usize },
+}
+
+/// Diagnostics of a single query file
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryFile {
+    pub path: PathBuf,
+    pub source: String,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Check of a directory of queries
+#[derive(Debug)]
+pub struct QueryCheck<'a> {
+    root: PathBuf,
+    /// Only set by `with_spec`, which requires the `serde` feature
+    #[cfg_attr(not(feature = "serde"), allow(dead_code))]
+    spec: Option<&'a Spec>,
+}
+
+/// Diagnostics of a single query file without the grammar: errors of the
+/// tokenizer, unbalanced brackets and duplicate pointers of shapes
+pub fn check_query(source: &str) -> Vec<Diagnostic> {
+    if let Some(err) = Tokenizer::new(source)
+        .validated_values()
+        .find_map(Result::err)
+    {
+        return vec![err.into()];
+    }
+    let unbalanced: Vec<_> = brackets(source)
+        .into_iter()
+        .filter(|b| b.matching.is_none())
+        .map(|b| {
+            let code = if b.is_open() {
+                "unclosed-bracket"
+            } else {
+                "unexpected-closing-bracket"
+            };
+            Diagnostic::with_code(Severity::Error, code, &[], b.span)
+        })
+        .collect();
+    if !unbalanced.is_empty() {
+        return unbalanced;
+    }
+    duplicate_pointers(source)
+}
+
+/// Duplicate pointers of the shape of the main statement
+fn duplicate_pointers(source: &str) -> Vec<Diagnostic> {
+    match parse_statement(source) {
+        Ok(Some(stmt)) => stmt.shape.map_or_else(Vec::new, |s| s.duplicates()),
+        _ => Vec::new(),
+    }
+}
+
+/// Diagnostics of a single query file, fully parsed with the grammar
+#[cfg(feature = "serde")]
+pub fn parse_query(source: &str, spec: &Spec) -> Vec<Diagnostic> {
+    let mut terminals = vec![Terminal::from_start_name("STARTBLOCK")];
+    for token in Tokenizer::new(source).validated_values().with_eof() {
+        match token {
+            Ok(token) => terminals.push(Terminal::from_token(token)),
+            Err(e) => return vec![e.into()],
+        }
+    }
+    demote_soft_terminals(&mut terminals);
+    let ctx = parser::Context::new(spec);
+    let (_, errors) = parser::parse(&terminals, &ctx);
+    if !errors.is_empty() {
+        return errors.into_iter().map(Into::into).collect();
+    }
+    duplicate_pointers(source)
+}
+
+fn is_query_file(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == QUERY_EXTENSION)
+}
+
+impl<'a> QueryCheck<'a> {
+    pub fn new(root: impl Into<PathBuf>) -> QueryCheck<'a> {
+        QueryCheck {
+            root: root.into(),
+            spec: None,
+        }
+    }
+
+    /// Fully parses the queries with the grammar spec
+    #[cfg(feature = "serde")]
+    pub fn with_spec(mut self, spec: &'a Spec) -> QueryCheck<'a> {
+        self.spec = Some(spec);
+        self
+    }
+
+    /// Checks all query files of the directory (recursively), ordered by
+    /// path
+    pub fn scan(&self) -> io::Result<Vec<QueryFile>> {
+        let mut paths = Vec::new();
+        let mut dirs = vec![self.root.clone()];
+        while let Some(dir) = dirs.pop() {
+            for entry in fs::read_dir(&dir)? {
+                let path = entry?.path();
+                if path.is_dir() {
+                    dirs.push(path);
+                } else if is_query_file(&path) {
+                    paths.push(path);
+                }
+            }
+        }
+        paths.sort();
+        paths
+            .into_iter()
+            .map(|path| {
+                let source = fs::read_to_string(&path)?;
+                let diagnostics = self.check(&source);
+                Ok(QueryFile {
+                    path,
+                    source,
+                    diagnostics,
+                })
+            })
+            .collect()
+    }
+
+    fn check(&self, source: &str) -> Vec<Diagnostic> {
+        #[cfg(feature = "serde")]
+        if let Some(spec) = self.spec {
+            return parse_query(source, spec);
+        }
+        check_query(source)
+    }
+
+    /// Checks the queries and prints cargo instructions, to be called from
+    /// a build script
+    pub fn run(&self) -> Result<(), BuildError> {
+        println!("cargo:rerun-if-changed={}", self.root.display());
+        let files = self.scan()?;
+        for line in files.iter().flat_map(cargo_warnings) {
+            println!("{line}");
+        }
+        let errors = files
+            .iter()
+            .map(|f| {
+                f.diagnostics
+                    .iter()
+                    .filter(|d| d.severity == Severity::Error)
+                    .count()
+            })
+            .filter(|&n| n > 0)
+            .collect::<Vec<_>>();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(BuildError::Invalid {
+                errors: errors.iter().sum(),
+                files: errors.len(),
+            })
+        }
+    }
+}
+
+/// `cargo:warning=` lines for the diagnostics of the file, one per
+/// diagnostic and one per hint, as cargo does not support multi-line
+/// warnings
+pub fn cargo_warnings(file: &QueryFile) -> Vec<String> {
+    let mut lines = Vec::new();
+    for diagnostic in &file.diagnostics {
+        let offset = diagnostic.span.start.min(file.source.len() as u64);
+        let (line, column) = match InflatedPos::from_offset(file.source.as_bytes(), offset) {
+            Ok(pos) => (pos.line + 1, pos.column + 1),
+            Err(_) => (1, 1),
+        };
+        lines.push(format!(
+            "cargo:warning={}:{line}:{column}: {}: {}",
+            file.path.display(),
+            diagnostic.severity,
+            diagnostic.message.replace('\n', " "),
+        ));
+        if let Some(hint) = &diagnostic.hint {
+            lines.push(format!("cargo:warning=  hint: {}", hint.replace('\n', " ")));
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use super::{cargo_warnings, check_query, BuildError, QueryCheck};
+
+    #[test]
+    fn scan() {
+        let root = std::env::temp_dir().join(format!("edgeql-parser-build-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("users")).unwrap();
+        fs::write(root.join("count.edgeql"), "select count(User);").unwrap();
+        fs::write(
+            root.join("users").join("get.edgeql"),
+            "select User {\n  name,\n  friends: { name }\n",
+        )
+        .unwrap();
+        fs::write(root.join("notes.txt"), "select (").unwrap();
+
+        let check = QueryCheck::new(&root);
+        let files = check.scan().unwrap();
+        assert_eq!(files.len(), 2);
+        assert!(files[0].diagnostics.is_empty());
+        assert_eq!(
+            cargo_warnings(&files[1]),
+            [format!(
+                "cargo:warning={}:1:13: error: unclosed bracket",
+                root.join("users").join("get.edgeql").display()
+            )]
+        );
+        assert!(matches!(
+            check.run(),
+            Err(BuildError::Invalid {
+                errors: 1,
+                files: 1
+            })
+        ));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn duplicates() {
+        let errors = check_query("select User { name, friends, name }");
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].message, "`name` is selected twice");
+        assert!(check_query("select User { name, friends: { name } }").is_empty());
+    }
+}
@@ -0,0 +1,358 @@
+//! Validity of type casts, for catching obviously invalid casts of literals
+//! (e.g. `<bool>(1, 2)` or `<int64>true`) without a round trip to the server
+//!
+//! Types other than standard scalars are looked up through the [Resolver],
+//! so user-defined scalars are checked using their base types.
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::position::Span;
+use crate::resolver::{Resolver, TypeKind, STD_SCALARS};
+use crate::schema::{is_name_like, token_name};
+use crate::tokenizer::{Kind, Token};
+
+/// Types that can be cast to and from any scalar type
+pub const UNIVERSAL: &[&str] = &["std::str", "std::json"];
+
+/// Numeric types, all of them can be cast to each other
+pub const NUMERIC: &[&str] = &[
+    "std::int16",
+    "std::int32",
+    "std::int64",
+    "std::float32",
+    "std::float64",
+    "std::bigint",
+    "std::decimal",
+    "std::sequence",
+];
+
+/// Casts between scalar types, besides the [UNIVERSAL] and [NUMERIC] ones
+pub const SCALAR_CASTS: &[(&str, &str)] = &[
+    ("cal::local_datetime", "cal::local_date"),
+    ("cal::local_datetime", "cal::local_time"),
+    ("cal::local_date", "cal::local_datetime"),
+    ("std::duration", "cal::relative_duration"),
+    ("cal::relative_duration", "std::duration"),
+    ("cal::relative_duration", "cal::date_duration"),
+    ("cal::date_duration", "cal::relative_duration"),
+    ("std::uuid", "std::bytes"),
+    ("std::bytes", "std::uuid"),
+];
+
+/// Returns true if the cast between standard scalar types exists
+pub fn is_scalar_cast(from: &str, to: &str) -> bool {
+    from == to
+        || UNIVERSAL.contains(&from)
+        || UNIVERSAL.contains(&to)
+        || (NUMERIC.contains(&from) && NUMERIC.contains(&to))
+        || SCALAR_CASTS.contains(&(from, to))
+}
+
+/// Type of a cast operand or a cast target
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CastType {
+    /// Standard scalar type, e.g. `std::int64`
+    Scalar(String),
+    Enum,
+    Array,
+    Tuple,
+    /// Anything else, i.e. casts that cannot be checked
+    Unknown,
+}
+
+impl CastType {
+    fn describe(&self) -> String {
+        match self {
+            CastType::Scalar(name) => format!("'{name}'"),
+            CastType::Enum => "enum".into(),
+            CastType::Array => "array".into(),
+            CastType::Tuple => "tuple".into(),
+            CastType::Unknown => "unknown".into(),
+        }
+    }
+}
+
+/// Returns false only for casts that are known not to exist
+pub fn is_valid_cast(from: &CastType, to: &CastType) -> bool {
+    use CastType::*;
+
+    let is_universal = |t: &CastType| matches!(t, Scalar(s) if UNIVERSAL.contains(&&s[..]));
+    match (from, to) {
+        (Unknown, _) | (_, Unknown) => true,
+        (Scalar(from), Scalar(to)) => is_scalar_cast(from, to),
+        _ if is_universal(from) || is_universal(to) => true,
+        (Enum, Enum) | (Array, Array) | (Tuple, Tuple) => true,
+        _ => false,
+    }
+}
+
+/// Reports casts of literals to types they cannot be cast to
+pub fn check_casts(tokens: &[Token], resolver: &dyn Resolver) -> Vec<Diagnostic> {
+    let mut errors = Vec::new();
+    for pos in 0..tokens.len() {
+        if !is_cast_start(tokens, pos) {
+            continue;
+        }
+        let Some((to, operand)) = read_cast(tokens, pos, resolver) else {
+            continue;
+        };
+        let Some((from, end)) = operand_type(tokens, operand, resolver) else {
+            continue;
+        };
+        if !is_valid_cast(&from, &to) {
+            let span = Span {
+                start: tokens[pos].span.start,
+                end: tokens[end].span.end,
+            };
+            errors.push(Diagnostic::with_code(
+                Severity::Error,
+                "invalid-cast",
+                &[("from", from.describe()), ("to", to.describe())],
+                span,
+            ));
+        }
+    }
+    errors
+}
+
+/// `<` is a cast, unless it follows an operand, i.e. is a comparison
+pub(crate) fn is_cast_start(tokens: &[Token], pos: usize) -> bool {
+    if tokens[pos].kind != Kind::Less {
+        return false;
+    }
+    let Some(prev) = pos.checked_sub(1).map(|p| &tokens[p]) else {
+        return true;
+    };
+    let is_operand = match prev.kind {
+        Kind::Ident
+        | Kind::Str
+        | Kind::BinStr
+        | Kind::IntConst
+        | Kind::FloatConst
+        | Kind::BigIntConst
+        | Kind::DecimalConst
+        | Kind::Parameter
+        | Kind::CloseParen
+        | Kind::CloseBracket
+        | Kind::CloseBrace => true,
+        Kind::Keyword(kw) => kw.is_bool() || !kw.is_reserved(),
+        _ => false,
+    };
+    !is_operand
+}
+
+/// Reads the type of the cast starting at `pos`, returns it with the index
+/// of the first token of the operand
+pub(crate) fn read_cast(
+    tokens: &[Token],
+    pos: usize,
+    resolver: &dyn Resolver,
+) -> Option<(CastType, usize)> {
+    let mut depth = 0usize;
+    let mut end = pos;
+    loop {
+        match tokens.get(end)?.kind {
+            Kind::Less => depth += 1,
+            Kind::Greater => {
+                depth -= 1;
+                if depth == 0 {
+                    break;
+                }
+            }
+            _ => {}
+        }
+        end += 1;
+    }
+    let mut inner = &tokens[pos + 1..end];
+    if let Some(Kind::Keyword(kw)) = inner.first().map(|t| t.kind) {
+        if kw.0 == "optional" {
+            inner = &inner[1..];
+        }
+    }
+    Some((cast_target(inner, resolver), end + 1))
+}
+
+fn cast_target(tokens: &[Token], resolver: &dyn Resolver) -> CastType {
+    let mut name = String::new();
+    let mut end = 0;
+    for tok in tokens {
+        match tok.kind {
+            Kind::Namespace => name.push_str("::"),
+            kind if is_name_like(Some(kind)) => name.push_str(token_name(tok)),
+            _ => break,
+        }
+        end += 1;
+    }
+    if end < tokens.len() {
+        // parametrized types: `array<str>`, `tuple<int64, str>`
+        return match &name[..] {
+            "array" | "std::array" if tokens[end].kind == Kind::Less => CastType::Array,
+            "tuple" | "std::tuple" if tokens[end].kind == Kind::Less => CastType::Tuple,
+            _ => CastType::Unknown,
+        };
+    }
+    resolve_scalar(&name, resolver)
+}
+
+fn resolve_scalar(name: &str, resolver: &dyn Resolver) -> CastType {
+    let std_name = format!("std::{name}");
+    if STD_SCALARS.contains(&name) {
+        return CastType::Scalar(name.to_string());
+    }
+    if STD_SCALARS.contains(&&std_name[..]) {
+        return CastType::Scalar(std_name);
+    }
+    let Some(mut full) = resolver.resolve(name, "default") else {
+        return CastType::Unknown;
+    };
+    // find the standard base type of user-defined scalars
+    for _ in 0..16 {
+        match resolver.type_kind(&full) {
+            _ if STD_SCALARS.contains(&&full[..]) => return CastType::Scalar(full),
+            Some(TypeKind::Enum(_)) => return CastType::Enum,
+            Some(TypeKind::Scalar) => match resolver.scalar_base(&full) {
+                Some(base) => full = base,
+                None => return CastType::Unknown,
+            },
+            _ => return CastType::Unknown,
+        }
+    }
+    CastType::Unknown
+}
+
+/// Returns the type of the literal starting at `pos` along with the index
+/// of its last token
+fn operand_type(
+    tokens: &[Token],
+    pos: usize,
+    resolver: &dyn Resolver,
+) -> Option<(CastType, usize)> {
+    let tok = tokens.get(pos)?;
+    let scalar = |name: &str| Some((CastType::Scalar(name.to_string()), pos));
+    match tok.kind {
+        Kind::Str => scalar("std::str"),
+        Kind::BinStr => scalar("std::bytes"),
+        Kind::IntConst => scalar("std::int64"),
+        Kind::FloatConst => scalar("std::float64"),
+        Kind::BigIntConst => scalar("std::bigint"),
+        Kind::DecimalConst => scalar("std::decimal"),
+        Kind::Keyword(kw) if kw.is_bool() => scalar("std::bool"),
+        Kind::Sub => {
+            let (ty, end) = operand_type(tokens, pos + 1, resolver)?;
+            let is_numeric = matches!(&ty, CastType::Scalar(s) if NUMERIC.contains(&&s[..]));
+            is_numeric.then_some((ty, end))
+        }
+        // the result of a nested cast
+        Kind::Less => {
+            let (ty, operand) = read_cast(tokens, pos, resolver)?;
+            let (_, end) = operand_type(tokens, operand, resolver)?;
+            Some((ty, end))
+        }
+        Kind::OpenBracket => {
+            let end = matching_close(tokens, pos)?;
+            Some((CastType::Array, end))
+        }
+        Kind::OpenParen => {
+            let end = matching_close(tokens, pos)?;
+            let inner = &tokens[pos + 1..end];
+            let mut depth = 0usize;
+            let is_tuple = inner.iter().any(|t| match t.kind {
+                Kind::OpenParen | Kind::OpenBracket | Kind::OpenBrace => {
+                    depth += 1;
+                    false
+                }
+                Kind::CloseParen | Kind::CloseBracket | Kind::CloseBrace => {
+                    depth -= 1;
+                    false
+                }
+                Kind::Comma | Kind::Assign => depth == 0,
+                _ => false,
+            });
+            is_tuple.then_some((CastType::Tuple, end))
+        }
+        _ => None,
+    }
+}
+
+fn matching_close(tokens: &[Token], open: usize) -> Option<usize> {
+    let mut depth = 0usize;
+    for (idx, tok) in tokens.iter().enumerate().skip(open) {
+        match tok.kind {
+            Kind::OpenParen | Kind::OpenBracket | Kind::OpenBrace => depth += 1,
+            Kind::CloseParen | Kind::CloseBracket | Kind::CloseBrace => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(idx);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::{check_casts, is_scalar_cast};
+    use crate::resolver::SchemaResolver;
+    use crate::schema::parse;
+    use crate::tokenizer::tokens;
+
+    fn errors(query: &str) -> Vec<String> {
+        let schema = parse(
+            r#"
+            scalar type Age extending int16;
+            scalar type Adult extending Age;
+            scalar type Color extending enum<Red, Green>;
+            "#,
+        )
+        .unwrap();
+        let resolver = SchemaResolver::new(&schema);
+        check_casts(&tokens(query), &resolver)
+            .into_iter()
+            .map(|e| {
+                let span = e.span;
+                format!(
+                    "{} @{}",
+                    e.message,
+                    &query[span.start as usize..span.end as usize]
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn table() {
+        assert!(is_scalar_cast("std::int16", "std::decimal"));
+        assert!(is_scalar_cast("std::bool", "std::json"));
+        assert!(is_scalar_cast("cal::local_datetime", "cal::local_date"));
+        assert!(!is_scalar_cast("cal::local_time", "cal::local_date"));
+        assert!(!is_scalar_cast("std::bool", "std::int64"));
+    }
+
+    #[test]
+    fn valid() {
+        let query = "select (<int32>1, <str>true, <json>(1, 2), <array<str>>['a'], \
+                     <Adult>-5, <Color>'Red', <tuple<int64, str>>(1, 'a'), \
+                     <bool><str>1, 1 < 2, x < -1, <int64>$x, <User>'')";
+        assert_eq!(errors(query), Vec::<String>::new());
+    }
+
+    #[test]
+    fn invalid() {
+        assert_eq!(
+            errors("select (<bool>(1, 2), <int64>true, <optional Adult>false)"),
+            vec![
+                "cannot cast tuple to 'std::bool' @<bool>(1, 2)",
+                "cannot cast 'std::bool' to 'std::int64' @<int64>true",
+                "cannot cast 'std::bool' to 'std::int16' @<optional Adult>false",
+            ]
+        );
+        assert_eq!(
+            errors("select <array<int64>>(a := 1) ++ <Color>4.5 ++ <bool><int64>'1'"),
+            vec![
+                "cannot cast tuple to array @<array<int64>>(a := 1)",
+                "cannot cast 'std::float64' to enum @<Color>4.5",
+                "cannot cast 'std::int64' to 'std::bool' @<bool><int64>'1'",
+            ]
+        );
+    }
+}
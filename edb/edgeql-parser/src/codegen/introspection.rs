@@ -0,0 +1,325 @@
+//! Canonical schema introspection queries
+//!
+//! Client tools should use these instead of writing their own, so that all
+//! of them request metadata in the same way.
+//!
+//! When [Options::modules] is set, queries expect an argument
+//! `$modules: array<str>` with the names of modules to introspect.
+
+/// What metadata should be returned by the queries
+#[derive(Debug, Clone)]
+pub struct Options {
+    pub properties: bool,
+    pub links: bool,
+    pub constraints: bool,
+    pub annotations: bool,
+    pub indexes: bool,
+    /// Include types from the standard library and extensions
+    pub builtin: bool,
+    /// Only introspect modules passed in the `$modules` argument
+    pub modules: bool,
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options {
+            properties: true,
+            links: true,
+            constraints: true,
+            annotations: true,
+            indexes: true,
+            builtin: false,
+            modules: false,
+        }
+    }
+}
+
+struct Shape(Vec<Element>);
+
+struct Element {
+    name: &'static str,
+    shape: Option<Shape>,
+    filter: Option<&'static str>,
+}
+
+impl Shape {
+    fn new() -> Shape {
+        Shape(Vec::new())
+    }
+
+    fn field(mut self, name: &'static str) -> Shape {
+        self.0.push(Element {
+            name,
+            shape: None,
+            filter: None,
+        });
+        self
+    }
+
+    fn nested(mut self, name: &'static str, shape: Shape) -> Shape {
+        self.0.push(Element {
+            name,
+            shape: Some(shape),
+            filter: None,
+        });
+        self
+    }
+
+    fn filtered(mut self, name: &'static str, shape: Shape, filter: &'static str) -> Shape {
+        self.0.push(Element {
+            name,
+            shape: Some(shape),
+            filter: Some(filter),
+        });
+        self
+    }
+
+    fn render(&self, buf: &mut String, indent: usize) {
+        buf.push_str("{\n");
+        for el in &self.0 {
+            push_indent(buf, indent + 1);
+            buf.push_str(el.name);
+            if let Some(shape) = &el.shape {
+                buf.push_str(": ");
+                shape.render(buf, indent + 1);
+            }
+            if let Some(filter) = el.filter {
+                buf.push_str(" filter ");
+                buf.push_str(filter);
+            }
+            buf.push_str(",\n");
+        }
+        push_indent(buf, indent);
+        buf.push('}');
+    }
+}
+
+fn push_indent(buf: &mut String, indent: usize) {
+    for _ in 0..indent {
+        buf.push_str("    ");
+    }
+}
+
+fn name_only() -> Shape {
+    Shape::new().field("name")
+}
+
+fn annotations() -> Shape {
+    Shape::new().field("name").field("@value")
+}
+
+fn constraints(opts: &Options) -> Shape {
+    let mut shape = Shape::new()
+        .field("name")
+        .filtered(
+            "params",
+            Shape::new().field("name").field("@value"),
+            ".name != '__subject__'",
+        )
+        .field("subjectexpr")
+        .field("except_expr")
+        .field("errmessage")
+        .field("delegated");
+    if opts.annotations {
+        shape = shape.nested("annotations", annotations());
+    }
+    shape
+}
+
+fn pointer(opts: &Options) -> Shape {
+    let mut shape = Shape::new()
+        .field("name")
+        .field("cardinality")
+        .field("required")
+        .field("readonly")
+        .field("expr")
+        .field("default")
+        .nested("target", name_only());
+    if opts.constraints {
+        shape = shape.nested("constraints", constraints(opts));
+    }
+    if opts.annotations {
+        shape = shape.nested("annotations", annotations());
+    }
+    shape
+}
+
+fn query(object: &str, shape: Shape, filter: Option<&str>, opts: &Options) -> String {
+    let mut buf = format!("select {object} ");
+    shape.render(&mut buf, 0);
+
+    let mut filters: Vec<_> = filter.into_iter().collect();
+    if !opts.builtin {
+        filters.push("not .builtin");
+    }
+    if opts.modules {
+        filters.push("any(.name like array_unpack(<array<str>>$modules) ++ '::%')");
+    }
+    if !filters.is_empty() {
+        buf.push_str("\nfilter ");
+        buf.push_str(&filters.join(" and "));
+    }
+    buf.push_str("\norder by .name");
+    buf
+}
+
+/// Query returning object types with their pointers
+pub fn object_types(opts: &Options) -> String {
+    let mut shape = Shape::new()
+        .field("name")
+        .field("is_abstract")
+        .nested("bases", name_only())
+        .nested("ancestors", name_only());
+    if opts.properties {
+        shape = shape.nested("properties", pointer(opts));
+    }
+    if opts.links {
+        let mut link = pointer(opts);
+        if opts.properties {
+            link = link.filtered(
+                "properties",
+                pointer(opts),
+                ".name not in {'source', 'target'}",
+            );
+        }
+        shape = shape.nested("links", link);
+    }
+    if opts.constraints {
+        shape = shape.nested("constraints", constraints(opts));
+    }
+    if opts.indexes {
+        shape = shape.nested(
+            "indexes",
+            Shape::new()
+                .field("name")
+                .field("expr")
+                .field("except_expr"),
+        );
+    }
+    if opts.annotations {
+        shape = shape.nested("annotations", annotations());
+    }
+    query("schema::ObjectType", shape, None, opts)
+}
+
+/// Query returning scalar types, including enum values
+pub fn scalar_types(opts: &Options) -> String {
+    let mut shape = Shape::new()
+        .field("name")
+        .field("is_abstract")
+        .field("enum_values")
+        .nested("bases", name_only());
+    if opts.constraints {
+        shape = shape.nested("constraints", constraints(opts));
+    }
+    if opts.annotations {
+        shape = shape.nested("annotations", annotations());
+    }
+    query("schema::ScalarType", shape, None, opts)
+}
+
+/// Query returning abstract constraints
+pub fn abstract_constraints(opts: &Options) -> String {
+    let mut shape = Shape::new()
+        .field("name")
+        .field("expr")
+        .field("errmessage")
+        .filtered(
+            "params",
+            Shape::new()
+                .field("name")
+                .field("kind")
+                .field("typemod")
+                .nested("type", name_only()),
+            ".name != '__subject__'",
+        );
+    if opts.annotations {
+        shape = shape.nested("annotations", annotations());
+    }
+    // concrete constraints are returned as a part of their subject
+    query("schema::Constraint", shape, Some(".abstract"), opts)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{abstract_constraints, object_types, scalar_types, Options};
+    use crate::preparser::full_statement;
+    use crate::tokenizer::Tokenizer;
+
+    fn assert_valid(query: &str) {
+        for token in Tokenizer::new(query).validated_values() {
+            token.unwrap();
+        }
+        let text = format!("{query};");
+        assert_eq!(full_statement(text.as_bytes(), None), Ok(text.len()));
+    }
+
+    #[test]
+    fn minimal() {
+        let opts = Options {
+            properties: false,
+            links: false,
+            constraints: false,
+            annotations: false,
+            indexes: false,
+            builtin: true,
+            modules: false,
+        };
+        assert_eq!(
+            object_types(&opts),
+            "\
+select schema::ObjectType {
+    name,
+    is_abstract,
+    bases: {
+        name,
+    },
+    ancestors: {
+        name,
+    },
+}
+order by .name"
+        );
+        assert_eq!(
+            abstract_constraints(&opts),
+            "\
+select schema::Constraint {
+    name,
+    expr,
+    errmessage,
+    params: {
+        name,
+        kind,
+        typemod,
+        type: {
+            name,
+        },
+    } filter .name != '__subject__',
+}
+filter .abstract
+order by .name"
+        );
+    }
+
+    #[test]
+    fn full() {
+        let opts = Options {
+            modules: true,
+            ..Options::default()
+        };
+        let query = object_types(&opts);
+        assert_valid(&query);
+        assert!(query.contains("links: {"));
+        assert!(query.contains("} filter .name not in {'source', 'target'},"));
+        assert!(query.ends_with(
+            "\nfilter not .builtin and \
+            any(.name like array_unpack(<array<str>>$modules) ++ '::%')\n\
+            order by .name"
+        ));
+
+        assert_valid(&scalar_types(&opts));
+        let query = abstract_constraints(&opts);
+        assert_valid(&query);
+        assert!(query.contains("\nfilter .abstract and not .builtin and any("));
+    }
+}
@@ -0,0 +1,4 @@
+//! Generation of EdgeQL queries and client code
+pub mod introspection;
+pub mod rust;
+pub mod typescript;
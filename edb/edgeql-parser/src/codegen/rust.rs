@@ -0,0 +1,490 @@
+//! Rust type definitions for the object and scalar types of a schema
+//!
+//! Every EdgeQL module becomes a Rust module. Object types become structs
+//! (with inherited pointers included), enum scalars become enums and other
+//! scalars become type aliases. All types derive `serde::Serialize` and
+//! `serde::Deserialize`, so that query results in JSON format can be read
+//! directly.
+//!
+//! Cardinality of pointers is reflected in field types: `multi` pointers are
+//! `Vec<T>`, optional ones are `Option<T>`. Single links are boxed as types
+//! may refer to each other. Computed pointers are only included when their
+//! type is specified explicitly.
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::Write;
+
+use crate::schema::qualify::qualify;
+use crate::schema::{DeclKind, Declaration, Document, TypeRef};
+
+const RUST_KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true", "type",
+    "unsafe", "use", "where", "while", "abstract", "become", "box", "do", "final", "macro",
+    "override", "priv", "try", "typeof", "unsized", "virtual", "yield",
+];
+
+/// Returns the source code of Rust modules for all types of the document
+pub fn generate(doc: &Document) -> String {
+    let doc = qualify(doc);
+    let mut types = HashMap::new();
+    let mut order = Vec::new();
+    collect_types(&doc.declarations, &mut types, &mut order);
+
+    let mut root = ModuleTree::default();
+    for name in &order {
+        let item = match types[name].kind {
+            DeclKind::ObjectType => object_type(name, &types),
+            _ => scalar_type(name, &types),
+        };
+        let (module, _) = split_name(name);
+        root.module(module).items.push(item);
+    }
+
+    let mut buf = String::from("// Generated from EdgeQL schema, do not edit\n");
+    root.render(&mut buf, 0);
+    buf
+}
+
+type Types<'a> = HashMap<&'a str, &'a Declaration>;
+
+fn collect_types<'a>(decls: &'a [Declaration], types: &mut Types<'a>, order: &mut Vec<&'a str>) {
+    for decl in decls {
+        let Some(name) = decl.name_text() else {
+            continue;
+        };
+        match decl.kind {
+            DeclKind::Module => collect_types(&decl.body, types, order),
+            DeclKind::ObjectType | DeclKind::ScalarType if decl.verb.is_none() => {
+                if types.insert(name, decl).is_none() {
+                    order.push(name);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[derive(Default)]
+struct ModuleTree {
+    items: Vec<String>,
+    children: BTreeMap<String, ModuleTree>,
+}
+
+impl ModuleTree {
+    fn module(&mut self, path: Option<&str>) -> &mut ModuleTree {
+        let mut node = self;
+        for part in path.into_iter().flat_map(|p| p.split("::")) {
+            node = node.children.entry(field_name(part).0).or_default();
+        }
+        node
+    }
+
+    fn render(&self, buf: &mut String, depth: usize) {
+        let indent = "    ".repeat(depth);
+        let mut separate = false;
+        for item in &self.items {
+            if separate {
+                buf.push('\n');
+            }
+            separate = true;
+            for line in item.lines() {
+                if line.is_empty() {
+                    buf.push('\n');
+                } else {
+                    writeln!(buf, "{indent}{line}").unwrap();
+                }
+            }
+        }
+        for (name, child) in &self.children {
+            if separate {
+                buf.push('\n');
+            }
+            separate = true;
+            writeln!(buf, "{indent}pub mod {name} {{").unwrap();
+            child.render(buf, depth + 1);
+            writeln!(buf, "{indent}}}").unwrap();
+        }
+    }
+}
+
+fn split_name(name: &str) -> (Option<&str>, &str) {
+    match name.rsplit_once("::") {
+        Some((module, short)) => (Some(module), short),
+        None => (None, name),
+    }
+}
+
+fn object_type(name: &str, types: &Types) -> String {
+    let (module, short) = split_name(name);
+    let mut pointers = Vec::new();
+    let mut seen_types = HashSet::new();
+    let mut seen_pointers = HashSet::new();
+    inherited_pointers(
+        types[name],
+        types,
+        &mut seen_types,
+        &mut seen_pointers,
+        &mut pointers,
+    );
+
+    let mut buf = String::new();
+    buf.push_str("#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]\n");
+    writeln!(buf, "pub struct {} {{", type_name(short)).unwrap();
+    buf.push_str("    pub id: uuid::Uuid,\n");
+    let mut fields = HashSet::from(["id".to_string()]);
+    for ptr in pointers {
+        let Some(ptr_name) = ptr.name_text() else {
+            continue;
+        };
+        if ptr_name == "id" {
+            // already emitted, whatever its declared type
+            continue;
+        }
+        let Some(target) = pointer_type(ptr, module, types) else {
+            continue;
+        };
+        let (field, renamed) = field_name(ptr_name);
+        let unique = unique_name(&field, &mut fields);
+        let (field, renamed) = (unique.clone(), renamed || unique != field);
+        if renamed {
+            writeln!(buf, "    #[serde(rename = {ptr_name:?})]").unwrap();
+        }
+        writeln!(buf, "    pub {field}: {target},").unwrap();
+    }
+    buf.push_str("}\n");
+    buf
+}
+
+fn inherited_pointers<'a>(
+    decl: &'a Declaration,
+    types: &Types<'a>,
+    seen_types: &mut HashSet<*const Declaration>,
+    seen_pointers: &mut HashSet<&'a str>,
+    pointers: &mut Vec<&'a Declaration>,
+) {
+    if !seen_types.insert(decl) {
+        return;
+    }
+    for ptr in decl.body.iter().filter(|d| d.is_pointer()) {
+        if let Some(name) = ptr.name_text() {
+            if seen_pointers.insert(name) {
+                pointers.push(ptr);
+            }
+        }
+    }
+    for base in &decl.extending {
+        if let Some(base) = types.get(&base.name.text[..]) {
+            inherited_pointers(base, types, seen_types, seen_pointers, pointers);
+        }
+    }
+}
+
+fn pointer_type(ptr: &Declaration, module: Option<&str>, types: &Types) -> Option<String> {
+    let target = match &ptr.target[..] {
+        [target] => target,
+        _ => return None,
+    };
+    let is_object = types
+        .get(&target.name.text[..])
+        .is_some_and(|t| t.kind == DeclKind::ObjectType);
+    let inner = rust_type(target, module, types);
+    let result = if ptr.is_multi() {
+        format!("Vec<{inner}>")
+    } else {
+        let inner = if is_object {
+            format!("Box<{inner}>")
+        } else {
+            inner
+        };
+        if ptr.has_modifier("required") {
+            inner
+        } else {
+            format!("Option<{inner}>")
+        }
+    };
+    Some(result)
+}
+
+fn rust_type(ty: &TypeRef, module: Option<&str>, types: &Types) -> String {
+    let name = &ty.name.text[..];
+    if types.contains_key(name) {
+        return type_path(name, module);
+    }
+    let builtin = name.strip_prefix("std::").unwrap_or(name);
+    match builtin {
+        "array" if ty.args.len() == 1 => format!("Vec<{}>", rust_type(&ty.args[0], module, types)),
+        "tuple" => {
+            let args: Vec<_> = ty
+                .args
+                .iter()
+                .map(|a| rust_type(a, module, types))
+                .collect();
+            if args.len() == 1 {
+                format!("({},)", args[0])
+            } else {
+                format!("({})", args.join(", "))
+            }
+        }
+        _ => builtin_type(builtin).to_string(),
+    }
+}
+
+/// Rust type for a standard scalar type, `name` is without the `std::`
+/// module
+pub fn builtin_type(name: &str) -> &'static str {
+    match name {
+        "str" => "String",
+        "bool" => "bool",
+        "int16" => "i16",
+        "int32" => "i32",
+        "int64" | "sequence" => "i64",
+        "float32" => "f32",
+        "float64" => "f64",
+        "uuid" => "uuid::Uuid",
+        "bytes" => "Vec<u8>",
+        // decimals and date/time types are formatted as strings in JSON
+        "bigint"
+        | "decimal"
+        | "datetime"
+        | "duration"
+        | "cal::local_datetime"
+        | "cal::local_date"
+        | "cal::local_time"
+        | "cal::relative_duration"
+        | "cal::date_duration" => "String",
+        _ => "serde_json::Value",
+    }
+}
+
+/// Path to the type `name` from inside of the module `from`
+fn type_path(name: &str, from: Option<&str>) -> String {
+    let (module, short) = split_name(name);
+    let short = type_name(short);
+    if module == from {
+        return short;
+    }
+    let depth = from.map_or(0, |m| m.split("::").count());
+    let mut path = "super::".repeat(depth);
+    for part in module.into_iter().flat_map(|m| m.split("::")) {
+        path.push_str(&field_name(part).0);
+        path.push_str("::");
+    }
+    path.push_str(&short);
+    path
+}
+
+fn scalar_type(name: &str, types: &Types) -> String {
+    let (module, short) = split_name(name);
+    let decl = types[name];
+    let mut buf = String::new();
+    let enum_values = decl
+        .extending
+        .iter()
+        .find(|b| b.name.text == "enum" || b.name.text == "std::enum");
+    if let Some(enum_type) = enum_values {
+        buf.push_str(
+            "#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]\n",
+        );
+        writeln!(buf, "pub enum {} {{", type_name(short)).unwrap();
+        let mut variants = HashSet::new();
+        for value in &enum_type.args {
+            let value = &value.name.text;
+            let variant = unique_name(&type_name(value), &mut variants);
+            if &variant != value {
+                writeln!(buf, "    #[serde(rename = {value:?})]").unwrap();
+            }
+            writeln!(buf, "    {variant},").unwrap();
+        }
+        buf.push_str("}\n");
+    } else {
+        let base = match &decl.extending[..] {
+            [base, ..] => rust_type(base, module, types),
+            [] => "serde_json::Value".to_string(),
+        };
+        writeln!(buf, "pub type {} = {base};", type_name(short)).unwrap();
+    }
+    buf
+}
+
+/// Appends a number to `name` if it is already used, e.g. by a name that
+/// only differs in punctuation
+fn unique_name(name: &str, used: &mut HashSet<String>) -> String {
+    let mut unique = name.to_string();
+    let mut n = 1;
+    while !used.insert(unique.clone()) {
+        n += 1;
+        unique = format!("{name}{n}");
+    }
+    unique
+}
+
+/// Converts a name into an UpperCamelCase identifier
+fn type_name(name: &str) -> String {
+    let mut result = String::with_capacity(name.len());
+    let mut upper = true;
+    for c in name.chars() {
+        if c.is_alphanumeric() {
+            if upper {
+                result.extend(c.to_uppercase());
+            } else {
+                result.push(c);
+            }
+            upper = false;
+        } else {
+            upper = true;
+        }
+    }
+    if result.is_empty() || result.starts_with(|c: char| c.is_ascii_digit()) {
+        result.insert(0, '_');
+    }
+    result
+}
+
+/// Converts a name into a valid field identifier, returns whether it
+/// differs from the original name
+pub fn field_name(name: &str) -> (String, bool) {
+    let mut result: String = name
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if result.is_empty() || result.starts_with(|c: char| c.is_ascii_digit()) {
+        result.insert(0, '_');
+    }
+    if RUST_KEYWORDS.contains(&&result[..]) {
+        if matches!(&result[..], "crate" | "self" | "Self" | "super") {
+            result.push('_');
+        } else {
+            result.insert_str(0, "r#");
+            return (result, false);
+        }
+    }
+    let renamed = result != name;
+    (result, renamed)
+}
+
+#[cfg(test)]
+mod test {
+    use super::generate;
+    use crate::schema::parse;
+
+    #[test]
+    fn types() {
+        let doc = parse(
+            r#"
+            module default {
+                abstract type Named {
+                    required name: str;
+                }
+                type User extending Named {
+                    multi friends: User;
+                    profile: other::Profile;
+                    required status: Status;
+                    property `type`: array<int32>;
+                    property `last-seen`: datetime;
+                    property name_len := len(.name);
+                }
+                scalar type Status extending enum<Active, `on hold`>;
+            }
+            module other {
+                type Profile {
+                    required owner: default::User;
+                    tags: tuple<str, int64>;
+                }
+                scalar type Tag extending str;
+            }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            generate(&doc),
+            r#"// Generated from EdgeQL schema, do not edit
+pub mod default {
+    #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+    pub struct Named {
+        pub id: uuid::Uuid,
+        pub name: String,
+    }
+
+    #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+    pub struct User {
+        pub id: uuid::Uuid,
+        pub friends: Vec<User>,
+        pub profile: Option<Box<super::other::Profile>>,
+        pub status: Status,
+        pub r#type: Option<Vec<i32>>,
+        #[serde(rename = "last-seen")]
+        pub last_seen: Option<String>,
+        pub name: String,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+    pub enum Status {
+        Active,
+        #[serde(rename = "on hold")]
+        OnHold,
+    }
+}
+
+pub mod other {
+    #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+    pub struct Profile {
+        pub id: uuid::Uuid,
+        pub owner: Box<super::default::User>,
+        pub tags: Option<(String, i64)>,
+    }
+
+    pub type Tag = String;
+}
+"#
+        );
+    }
+
+    #[test]
+    fn collisions() {
+        let doc = parse(
+            "
+            module default {
+                type Item {
+                    required id: uuid;
+                    `a-b`: str;
+                    a_b: int64;
+                }
+                scalar type Kind extending enum<`a-b`, a_b, AB>;
+            }
+            ",
+        )
+        .unwrap();
+        assert_eq!(
+            generate(&doc),
+            r#"// Generated from EdgeQL schema, do not edit
+pub mod default {
+    #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+    pub struct Item {
+        pub id: uuid::Uuid,
+        #[serde(rename = "a-b")]
+        pub a_b: Option<String>,
+        #[serde(rename = "a_b")]
+        pub a_b2: Option<i64>,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+    pub enum Kind {
+        #[serde(rename = "a-b")]
+        AB,
+        #[serde(rename = "a_b")]
+        AB2,
+        #[serde(rename = "AB")]
+        AB3,
+    }
+}
+"#
+        );
+    }
+}
@@ -0,0 +1,384 @@
+//! TypeScript interfaces for results of queries
+//!
+//! Types of elements are taken from the [Resolver]. Computed elements are
+//! typed when the expression is a literal or a path, otherwise they are
+//! `unknown`.
+use std::fmt::Write;
+
+use crate::diagnostics::json_string;
+use crate::resolver::{closest_names, suggestion_hint, Pointer, Resolver, TypeKind, TypeName};
+use crate::schema::token_name;
+use crate::shape::{parse_statement, Shape, ShapeElement};
+use crate::tokenizer::{Error, Kind};
+
+/// Returns `export interface {name} { ... }` describing a single object
+/// returned by the query
+pub fn generate(query: &str, name: &str, resolver: &dyn Resolver) -> Result<String, Error> {
+    let Some(stmt) = parse_statement(query)? else {
+        return Err(Error::new("Query has no statement"));
+    };
+    let module = stmt.module.as_deref().unwrap_or("default");
+    let subject = match (&stmt.subject, stmt.subject_span) {
+        (Some(subject), Some(span)) => match resolver.resolve(subject, module) {
+            Some(full) => full,
+            None => {
+                let mut error =
+                    Error::new(format!("object type '{subject}' does not exist")).with_span(span);
+                error.hint = suggestion_hint(&resolver.suggest_types(subject));
+                return Err(error);
+            }
+        },
+        _ => return Err(Error::new("Query does not select from an object type")),
+    };
+
+    let gen = Generator { resolver, module };
+    let mut buf = format!("export interface {name} ");
+    match (&stmt.shape, stmt.keyword) {
+        (Some(shape), "select") => gen.shape(&mut buf, &subject, shape, None, 0)?,
+        _ => buf.push_str("{\n  id: string;\n}"),
+    }
+    buf.push('\n');
+    Ok(buf)
+}
+
+struct Generator<'a> {
+    resolver: &'a dyn Resolver,
+    module: &'a str,
+}
+
+impl Generator<'_> {
+    fn shape(
+        &self,
+        buf: &mut String,
+        type_name: &str,
+        shape: &Shape,
+        link: Option<&Pointer>,
+        indent: usize,
+    ) -> Result<(), Error> {
+        let pad = "  ".repeat(indent + 1);
+        buf.push_str("{\n");
+        for el in &shape.elements {
+            if el.is_splat() {
+                for ptr in self.resolver.pointers(type_name) {
+                    if ptr.is_link && el.name == "*" {
+                        continue;
+                    }
+                    let ts = self.pointer_type(&ptr);
+                    writeln!(buf, "{pad}{}: {ts};", field_name(&ptr.name)).unwrap();
+                }
+                continue;
+            }
+            let ts = self.element_type(type_name, el, link, indent + 1)?;
+            let name = if el.link_property {
+                field_name(&format!("@{}", el.name))
+            } else {
+                field_name(&el.name)
+            };
+            writeln!(buf, "{pad}{name}: {ts};").unwrap();
+        }
+        buf.push_str(&"  ".repeat(indent));
+        buf.push('}');
+        Ok(())
+    }
+
+    fn element_type(
+        &self,
+        type_name: &str,
+        el: &ShapeElement,
+        link: Option<&Pointer>,
+        indent: usize,
+    ) -> Result<String, Error> {
+        if let Some(expr) = &el.expr {
+            let literal = match &expr.tokens[..] {
+                [tok] => match tok.kind {
+                    Kind::Str => Some("string"),
+                    Kind::IntConst | Kind::FloatConst => Some("number"),
+                    Kind::BigIntConst => Some("bigint"),
+                    Kind::DecimalConst => Some("string"),
+                    Kind::Keyword(kw) if kw.is_bool() => Some("boolean"),
+                    _ => None,
+                },
+                [dot, name] if dot.kind == Kind::Dot => {
+                    if let Some(ptr) = self.resolver.pointer(type_name, token_name(name)) {
+                        return Ok(self.pointer_type(&ptr));
+                    }
+                    None
+                }
+                _ => None,
+            };
+            // a literal is always exactly one value
+            return Ok(match literal {
+                Some(inner) => cardinality(inner.to_string(), el.is_multi(), true),
+                None => cardinality(
+                    "unknown".to_string(),
+                    el.is_multi(),
+                    el.has_modifier("required"),
+                ),
+            });
+        }
+
+        if el.backlink {
+            let inner = match (&el.shape, &el.type_filter) {
+                (Some(shape), Some(source)) => {
+                    let source = self.resolve_type(source, el)?;
+                    let mut buf = String::new();
+                    self.shape(&mut buf, &source, shape, None, indent)?;
+                    buf
+                }
+                _ => "{ id: string }".to_string(),
+            };
+            return Ok(format!("{}[]", parenthesize(inner)));
+        }
+
+        let source = match &el.type_filter {
+            Some(filter) => self.resolve_type(filter, el)?,
+            None => type_name.to_string(),
+        };
+        let pointer = if el.link_property {
+            link.and_then(|l| l.properties.iter().find(|p| p.name == el.name).cloned())
+        } else {
+            self.resolver.pointer(&source, &el.name)
+        };
+        let Some(mut pointer) = pointer else {
+            let (message, suggestions) = if el.link_property {
+                let names = link.iter().flat_map(|l| &l.properties).map(|p| &p.name[..]);
+                (
+                    format!("link has no property '{}'", el.name),
+                    closest_names(&el.name, names),
+                )
+            } else {
+                (
+                    format!(
+                        "object type '{source}' has no link or property '{}'",
+                        el.name
+                    ),
+                    self.resolver.suggest_pointers(&source, &el.name),
+                )
+            };
+            let mut error = Error::new(message).with_span(el.name_span);
+            error.hint = suggestion_hint(&suggestions);
+            return Err(error);
+        };
+        if el.type_filter.is_some() {
+            pointer.required = false;
+        }
+
+        match (&el.shape, &pointer.target) {
+            (Some(shape), Some(target)) if pointer.is_link => {
+                let mut buf = String::new();
+                self.shape(&mut buf, &target.name, shape, Some(&pointer), indent)?;
+                Ok(cardinality(buf, pointer.multi, pointer.required))
+            }
+            _ => Ok(self.pointer_type(&pointer)),
+        }
+    }
+
+    fn resolve_type(&self, name: &str, el: &ShapeElement) -> Result<String, Error> {
+        self.resolver.resolve(name, self.module).ok_or_else(|| {
+            let mut error =
+                Error::new(format!("object type '{name}' does not exist")).with_span(el.span);
+            error.hint = suggestion_hint(&self.resolver.suggest_types(name));
+            error
+        })
+    }
+
+    fn pointer_type(&self, ptr: &Pointer) -> String {
+        let inner = match &ptr.target {
+            Some(_) if ptr.is_link => "{ id: string }".to_string(),
+            Some(target) => self.scalar_type(target),
+            None => "unknown".to_string(),
+        };
+        cardinality(inner, ptr.multi, ptr.required)
+    }
+
+    fn scalar_type(&self, ty: &TypeName) -> String {
+        match &ty.name[..] {
+            "array" | "std::array" if ty.args.len() == 1 => {
+                format!("{}[]", parenthesize(self.scalar_type(&ty.args[0])))
+            }
+            "tuple" | "std::tuple" => {
+                let args: Vec<_> = ty.args.iter().map(|a| self.scalar_type(a)).collect();
+                format!("[{}]", args.join(", "))
+            }
+            name => match self.resolver.type_kind(name) {
+                Some(TypeKind::Enum(values)) => {
+                    let values: Vec<_> = values.iter().map(|v| string_literal(v)).collect();
+                    values.join(" | ")
+                }
+                Some(TypeKind::Scalar) => match builtin_type(name) {
+                    Some(ts) => ts.to_string(),
+                    None => match self.resolver.scalar_base(name) {
+                        Some(base) if base != name => self.scalar_type(&TypeName {
+                            name: base,
+                            args: Vec::new(),
+                        }),
+                        _ => "unknown".to_string(),
+                    },
+                },
+                _ => "unknown".to_string(),
+            },
+        }
+    }
+}
+
+fn builtin_type(name: &str) -> Option<&'static str> {
+    let ts = match name {
+        "std::str" | "std::uuid" | "std::decimal" => "string",
+        "std::bool" => "boolean",
+        "std::int16" | "std::int32" | "std::int64" | "std::float32" | "std::float64"
+        | "std::sequence" => "number",
+        "std::bigint" => "bigint",
+        "std::bytes" => "Uint8Array",
+        "std::datetime" => "Date",
+        "std::json" => "unknown",
+        // represented by classes of the client library
+        "std::duration" => "Duration",
+        "cal::local_datetime" => "LocalDateTime",
+        "cal::local_date" => "LocalDate",
+        "cal::local_time" => "LocalTime",
+        "cal::relative_duration" => "RelativeDuration",
+        "cal::date_duration" => "DateDuration",
+        _ => return None,
+    };
+    Some(ts)
+}
+
+fn cardinality(inner: String, multi: bool, required: bool) -> String {
+    if multi {
+        format!("{}[]", parenthesize(inner))
+    } else if required {
+        inner
+    } else {
+        format!("{inner} | null")
+    }
+}
+
+/// Wraps a union type in parenthesis
+fn parenthesize(ts: String) -> String {
+    let mut depth = 0;
+    let mut is_union = false;
+    for c in ts.chars() {
+        match c {
+            '{' | '[' | '(' => depth += 1,
+            '}' | ']' | ')' => depth -= 1,
+            '|' if depth == 0 => is_union = true,
+            _ => {}
+        }
+    }
+    if is_union {
+        format!("({ts})")
+    } else {
+        ts
+    }
+}
+
+fn field_name(name: &str) -> String {
+    let plain = name.starts_with(|c: char| c.is_alphabetic() || c == '_' || c == '$')
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '$');
+    if plain {
+        name.to_string()
+    } else {
+        string_literal(name)
+    }
+}
+
+/// JSON escaping is valid in TypeScript, unlike the escaping of Rust
+fn string_literal(value: &str) -> String {
+    let mut buf = String::new();
+    json_string(&mut buf, value);
+    buf
+}
+
+#[cfg(test)]
+mod test {
+    use super::generate;
+    use crate::resolver::SchemaResolver;
+    use crate::schema::parse;
+
+    fn resolver() -> SchemaResolver {
+        SchemaResolver::new(
+            &parse(
+                "
+                module default {
+                    type User {
+                        required name: str;
+                        nick: str;
+                        multi friends: User {
+                            since: datetime;
+                        };
+                        required status: Status;
+                        tags: array<str>;
+                        `last-seen`: datetime;
+                    }
+                    type Admin extending User {
+                        required level: int32;
+                    }
+                    type Post {
+                        required author: User;
+                    }
+                    scalar type Status extending enum<Active, Inactive>;
+                }
+                ",
+            )
+            .unwrap(),
+        )
+    }
+
+    #[test]
+    fn shape() {
+        let query = "
+            select User {
+                name,
+                nick,
+                friends: { name, @since } filter .name != '',
+                status,
+                tags,
+                `last-seen`,
+                [is Admin].level,
+                <author[is Post]: { id },
+                is_admin := true,
+                name2 := .name,
+            }
+        ";
+        assert_eq!(
+            generate(query, "GetUser", &resolver()).unwrap(),
+            r#"export interface GetUser {
+  name: string;
+  nick: string | null;
+  friends: {
+    name: string;
+    "@since": Date | null;
+  }[];
+  status: "Active" | "Inactive";
+  tags: string[] | null;
+  "last-seen": Date | null;
+  level: number | null;
+  author: {
+    id: string;
+  }[];
+  is_admin: boolean;
+  name2: string;
+}
+"#
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let err = generate("select User { nme }", "X", &resolver()).unwrap_err();
+        assert_eq!(
+            err.message,
+            "object type 'default::User' has no link or property 'nme'"
+        );
+        assert_eq!(err.hint.unwrap(), "did you mean 'name'?");
+        let err = generate("select Usr { name }", "X", &resolver()).unwrap_err();
+        assert_eq!(err.message, "object type 'Usr' does not exist");
+        assert_eq!(err.hint.unwrap(), "did you mean 'default::User'?");
+        assert_eq!(
+            generate("insert User { name := 'x' }", "X", &resolver()).unwrap(),
+            "export interface X {\n  id: string;\n}\n"
+        );
+    }
+}
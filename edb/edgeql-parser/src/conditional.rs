@@ -0,0 +1,302 @@
+//! Chains of conditional expressions
+//!
+//! The grammar parses `a if c1 else b if c2 else c` as nested conditionals,
+//! `a if c1 else (b if c2 else c)`, the same way as `if c1 then a else if
+//! c2 then b else c`. [conditionals] finds the conditionals of a query and
+//! flattens every chain into a single [Conditional] with a [Branch] per
+//! condition, which is what formatters and linters usually need. The nested
+//! form is kept with [ChainOptions::keep_nested].
+//!
+//! Conditionals are found from tokens: a chain ends at brackets, commas,
+//! `:=`, set operators (which bind looser than `if`) and keywords of
+//! clauses and statements. Conditionals in parentheses are separate chains.
+use std::ops::Range;
+
+use crate::keywords::Keyword;
+use crate::position::Span;
+use crate::schema::Expr;
+use crate::tokenizer::{Error, Kind, Token, Tokenizer};
+
+/// Keywords ending the expression a conditional is a part of
+const BOUNDARIES: &[&str] = &[
+    "select",
+    "insert",
+    "update",
+    "delete",
+    "group",
+    "for",
+    "with",
+    "filter",
+    "order",
+    "by",
+    "limit",
+    "offset",
+    "union",
+    "except",
+    "intersect",
+    "set",
+    "using",
+    "unless",
+    "on",
+];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ChainOptions {
+    /// Keep a conditional per `else`, with the rest of the chain in
+    /// [Conditional::nested]
+    pub keep_nested: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    /// `value if condition else ...`
+    IfElse,
+    /// `if condition then value else ...`
+    IfThenElse,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Branch {
+    pub style: Style,
+    pub condition: Expr,
+    pub value: Expr,
+    /// From the value (or `if` of [Style::IfThenElse]) to the end of the
+    /// branch, without the `else`
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conditional {
+    /// Branches in the order they are checked
+    pub branches: Vec<Branch>,
+    /// Expression after the last `else`
+    pub otherwise: Expr,
+    /// The conditional after the `else`, if the chain is kept nested, in
+    /// which case it is also the [Conditional::otherwise]
+    pub nested: Option<Box<Conditional>>,
+    pub span: Span,
+}
+
+/// Conditionals of the query, ordered by their position, fails if the
+/// query cannot be tokenized
+///
+/// Nested conditionals that are a part of a chain are only returned within
+/// the chain.
+pub fn conditionals(text: &str, options: ChainOptions) -> Result<Vec<Conditional>, Error> {
+    let tokens = Tokenizer::new(text)
+        .validated_values()
+        .map(|t| t.map(|t| t.cloned()))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut segments: Vec<Range<usize>> = Vec::new();
+    for (idx, tok) in tokens.iter().enumerate() {
+        if tok.kind == Kind::Keyword(Keyword("if")) {
+            let segment = segment(&tokens, idx);
+            if !segments.contains(&segment) {
+                segments.push(segment);
+            }
+        }
+    }
+    Ok(segments
+        .into_iter()
+        .filter_map(|segment| parse(&tokens, segment, options))
+        .collect())
+}
+
+fn keyword(token: &Token) -> Option<&'static str> {
+    match token.kind {
+        Kind::Keyword(Keyword(kw)) => Some(kw),
+        _ => None,
+    }
+}
+
+fn is_boundary(token: &Token) -> bool {
+    matches!(token.kind, Kind::Comma | Kind::Semicolon | Kind::Assign)
+        || keyword(token).is_some_and(|kw| BOUNDARIES.contains(&kw))
+}
+
+/// Tokens of the expression the `if` at `idx` is a part of
+fn segment(tokens: &[Token], idx: usize) -> Range<usize> {
+    let mut depth = 0usize;
+    let mut start = idx;
+    while start > 0 {
+        let tok = &tokens[start - 1];
+        match tok.kind {
+            Kind::CloseParen | Kind::CloseBracket | Kind::CloseBrace => depth += 1,
+            Kind::OpenParen | Kind::OpenBracket | Kind::OpenBrace if depth == 0 => break,
+            Kind::OpenParen | Kind::OpenBracket | Kind::OpenBrace => depth -= 1,
+            _ if depth == 0 && is_boundary(tok) => break,
+            _ => {}
+        }
+        start -= 1;
+    }
+    let mut end = idx;
+    while let Some(tok) = tokens.get(end) {
+        match tok.kind {
+            Kind::OpenParen | Kind::OpenBracket | Kind::OpenBrace => depth += 1,
+            Kind::CloseParen | Kind::CloseBracket | Kind::CloseBrace if depth == 0 => break,
+            Kind::CloseParen | Kind::CloseBracket | Kind::CloseBrace => depth -= 1,
+            _ if depth == 0 && is_boundary(tok) => break,
+            _ => {}
+        }
+        end += 1;
+    }
+    start..end
+}
+
+/// Indexes of the tokens of the range that are not in brackets
+fn top_level(tokens: &[Token], range: Range<usize>) -> impl Iterator<Item = usize> + '_ {
+    let mut depth = 0usize;
+    range.filter(move |&idx| match tokens[idx].kind {
+        Kind::OpenParen | Kind::OpenBracket | Kind::OpenBrace => {
+            depth += 1;
+            depth == 1
+        }
+        Kind::CloseParen | Kind::CloseBracket | Kind::CloseBrace => {
+            depth = depth.saturating_sub(1);
+            false
+        }
+        _ => depth == 0,
+    })
+}
+
+/// Index of the `else` (and `then`) of the `if` at `start`
+fn matching_else(tokens: &[Token], start: usize, end: usize) -> Option<(Option<usize>, usize)> {
+    let mut open = 0usize;
+    let mut then = None;
+    for idx in top_level(tokens, start..end) {
+        match keyword(&tokens[idx]) {
+            Some("if") => open += 1,
+            Some("then") if open == 1 && then.is_none() => then = Some(idx),
+            Some("else") => {
+                open -= 1;
+                if open == 0 {
+                    return Some((then, idx));
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+fn parse(
+    tokens: &[Token<'static>],
+    range: Range<usize>,
+    options: ChainOptions,
+) -> Option<Conditional> {
+    let if_idx =
+        top_level(tokens, range.clone()).find(|&idx| keyword(&tokens[idx]) == Some("if"))?;
+    let (branch, rest) = if if_idx == range.start {
+        let (then, else_idx) = matching_else(tokens, if_idx, range.end)?;
+        let then = then?;
+        if then == if_idx + 1 || else_idx == then + 1 {
+            return None;
+        }
+        let branch = Branch {
+            style: Style::IfThenElse,
+            condition: expr(tokens, if_idx + 1..then),
+            value: expr(tokens, then + 1..else_idx),
+            span: tokens[if_idx].span.extend(&tokens[else_idx - 1].span),
+        };
+        (branch, else_idx + 1..range.end)
+    } else {
+        let (_, else_idx) = matching_else(tokens, if_idx, range.end)?;
+        if else_idx == if_idx + 1 {
+            return None;
+        }
+        let branch = Branch {
+            style: Style::IfElse,
+            condition: expr(tokens, if_idx + 1..else_idx),
+            value: expr(tokens, range.start..if_idx),
+            span: tokens[range.start].span.extend(&tokens[else_idx - 1].span),
+        };
+        (branch, else_idx + 1..range.end)
+    };
+    if rest.is_empty() {
+        return None;
+    }
+    let otherwise = expr(tokens, rest.clone());
+    let span = tokens[range.start].span.extend(&otherwise.span);
+    let mut conditional = Conditional {
+        branches: vec![branch],
+        otherwise,
+        nested: None,
+        span,
+    };
+    if let Some(nested) = parse(tokens, rest, options) {
+        if options.keep_nested {
+            conditional.nested = Some(Box::new(nested));
+        } else {
+            conditional.branches.extend(nested.branches);
+            conditional.otherwise = nested.otherwise;
+        }
+    }
+    Some(conditional)
+}
+
+fn expr(tokens: &[Token<'static>], range: Range<usize>) -> Expr {
+    let tokens = tokens[range].to_vec();
+    let span = tokens[0].span.extend(&tokens[tokens.len() - 1].span);
+    Expr { tokens, span }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{conditionals, ChainOptions, Conditional, Style};
+
+    fn text<'a>(source: &'a str, conditional: &Conditional) -> Vec<&'a str> {
+        let slice =
+            move |span: crate::position::Span| &source[span.start as usize..span.end as usize];
+        let mut parts: Vec<_> = conditional
+            .branches
+            .iter()
+            .flat_map(|b| [slice(b.value.span), slice(b.condition.span)])
+            .collect();
+        parts.push(slice(conditional.otherwise.span));
+        parts
+    }
+
+    #[test]
+    fn flattened() {
+        let source = "select (a if x > 1 else b ++ 'c' if y else c, 'd' if z else 'e') \
+                      filter .name = ('f' if (u if v else w) else 'g')";
+        let chains = conditionals(source, ChainOptions::default()).unwrap();
+        assert_eq!(chains.len(), 4);
+        assert_eq!(
+            text(source, &chains[0]),
+            ["a", "x > 1", "b ++ 'c'", "y", "c"]
+        );
+        assert!(chains[0].nested.is_none());
+        let span = chains[0].branches[1].span;
+        assert_eq!(
+            &source[span.start as usize..span.end as usize],
+            "b ++ 'c' if y"
+        );
+        assert_eq!(text(source, &chains[1]), ["'d'", "z", "'e'"]);
+        assert_eq!(text(source, &chains[2]), ["'f'", "(u if v else w)", "'g'"]);
+        assert_eq!(text(source, &chains[3]), ["u", "v", "w"]);
+
+        let source = "select if a then 1 else if b then 2 else 3 union 4";
+        let chains = conditionals(source, ChainOptions::default()).unwrap();
+        assert_eq!(chains.len(), 1);
+        assert_eq!(text(source, &chains[0]), ["1", "a", "2", "b", "3"]);
+        assert!(chains[0]
+            .branches
+            .iter()
+            .all(|b| b.style == Style::IfThenElse));
+    }
+
+    #[test]
+    fn nested() {
+        let source = "select 1 if a else 2 if b else 3";
+        let options = ChainOptions { keep_nested: true };
+        let chains = conditionals(source, options).unwrap();
+        assert_eq!(chains.len(), 1);
+        assert_eq!(text(source, &chains[0]), ["1", "a", "2 if b else 3"]);
+        let nested = chains[0].nested.as_ref().unwrap();
+        assert_eq!(text(source, nested), ["2", "b", "3"]);
+        assert_eq!(nested.span, chains[0].otherwise.span);
+
+        assert!(conditionals("select 1 if a", options).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,550 @@
+//! Values of `configure` statements
+//!
+//! [parse_configure] reads `configure <scope> set <name> := <value>`
+//! statements of a script into [ConfigSetting]s. Literal values are decoded
+//! into [ConfigValue]s, memory sizes (`<cfg::memory>'1GiB'`) and durations
+//! (`<duration>'1 hour'`) are parsed the way the server does and kept as
+//! bytes and microseconds, so the same setting written in different units
+//! compares equal and can be printed in the canonical form.
+use std::fmt::Write;
+
+use crate::helpers::quote_string;
+use crate::keywords::Keyword;
+use crate::position::Span;
+use crate::schema::{is_name_like, token_name};
+use crate::tokenizer::{Error, Kind, Token, Tokenizer, Value};
+
+/// Units of `cfg::memory` with their sizes, largest first
+pub const MEMORY_UNITS: &[(&str, u64)] = &[
+    ("PiB", 1 << 50),
+    ("TiB", 1 << 40),
+    ("GiB", 1 << 30),
+    ("MiB", 1 << 20),
+    ("KiB", 1 << 10),
+    ("B", 1),
+];
+
+/// Units of `std::duration` with their lengths in microseconds
+const DURATION_UNITS: &[(&str, &[&str], i64)] = &[
+    ("hours", &["h", "hr", "hrs", "hour", "hours"], 3_600_000_000),
+    (
+        "minutes",
+        &["m", "min", "mins", "minute", "minutes"],
+        60_000_000,
+    ),
+    (
+        "milliseconds",
+        &[
+            "ms",
+            "millisecon",
+            "millisecons",
+            "millisecond",
+            "milliseconds",
+        ],
+        1_000,
+    ),
+    ("microseconds", &["us", "microsecond", "microseconds"], 1),
+    (
+        "seconds",
+        &["s", "sec", "secs", "second", "seconds"],
+        1_000_000,
+    ),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ConfigValueError {
+    #[error("unable to parse memory size: '{0}'")]
+    InvalidMemory(String),
+    #[error("invalid input syntax for type std::duration: unable to parse '{0}'")]
+    InvalidDuration(String),
+    #[error(
+        "invalid input syntax for type std::duration: \
+         the '{0}' component has been specified more than once"
+    )]
+    DuplicateComponent(&'static str),
+    #[error("value out of range")]
+    OutOfRange,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigValue {
+    /// `<cfg::memory>'...'`, in bytes
+    Memory(u64),
+    /// `<duration>'...'`, in microseconds
+    Duration(i64),
+    Str(String),
+    Int(i64),
+    Bool(bool),
+}
+
+impl ConfigValue {
+    /// Literal of the value in the canonical form, e.g.
+    /// `<cfg::memory>'1GiB'` for `<cfg::memory>'1024MiB'`
+    pub fn to_edgeql(&self) -> String {
+        match self {
+            ConfigValue::Memory(bytes) => format!("<cfg::memory>'{}'", format_memory(*bytes)),
+            ConfigValue::Duration(us) => format!("<std::duration>'{}'", format_duration(*us)),
+            ConfigValue::Str(value) => quote_string(value),
+            ConfigValue::Int(value) => value.to_string(),
+            ConfigValue::Bool(value) => value.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigSetting {
+    /// `instance`, `session`, `current branch` or `current database`
+    pub scope: String,
+    /// Possibly qualified name of the setting
+    pub name: String,
+    /// The decoded value, `None` if it is not a literal
+    pub value: Option<ConfigValue>,
+    pub value_span: Span,
+    /// Span of the statement, without the `;`
+    pub span: Span,
+}
+
+/// Parses `configure ... set` statements of a script, skipping other
+/// statements. Fails on literals that the server would reject.
+pub fn parse_configure(text: &str) -> Result<Vec<ConfigSetting>, Error> {
+    let tokens = Tokenizer::new(text)
+        .validated_values()
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut settings = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (idx, tok) in tokens.iter().enumerate() {
+        match tok.kind {
+            Kind::OpenParen | Kind::OpenBracket | Kind::OpenBrace => depth += 1,
+            Kind::CloseParen | Kind::CloseBracket | Kind::CloseBrace => {
+                depth = depth.saturating_sub(1);
+            }
+            Kind::Semicolon if depth == 0 => {
+                settings.extend(setting(&tokens[start..idx])?);
+                start = idx + 1;
+            }
+            _ => {}
+        }
+    }
+    settings.extend(setting(&tokens[start..])?);
+    Ok(settings)
+}
+
+fn setting(tokens: &[Token]) -> Result<Option<ConfigSetting>, Error> {
+    let keyword = |idx: usize| match tokens.get(idx).map(|t| t.kind) {
+        Some(Kind::Keyword(Keyword(kw))) => Some(kw),
+        _ => None,
+    };
+    if keyword(0) != Some("configure") {
+        return Ok(None);
+    }
+    let (scope, mut pos) = match (keyword(1), keyword(2)) {
+        (Some("current"), Some(kw @ ("branch" | "database"))) => (format!("current {kw}"), 3),
+        (Some(kw @ ("instance" | "session" | "system")), _) => (kw.to_string(), 2),
+        _ => return Ok(None),
+    };
+    if keyword(pos) != Some("set") {
+        return Ok(None);
+    }
+    pos += 1;
+    let mut name = String::new();
+    loop {
+        let Some(tok) = tokens.get(pos).filter(|t| is_name_like(Some(t.kind))) else {
+            let span = tokens.get(pos).map_or(tokens[pos - 1].span, |t| t.span);
+            return Err(Error::new("Expected a setting name").with_span(span));
+        };
+        name.push_str(token_name(tok));
+        pos += 1;
+        if tokens.get(pos).map(|t| t.kind) != Some(Kind::Namespace) {
+            break;
+        }
+        name.push_str("::");
+        pos += 1;
+    }
+    if tokens.get(pos).map(|t| t.kind) != Some(Kind::Assign) {
+        let span = tokens.get(pos).map_or(tokens[pos - 1].span, |t| t.span);
+        return Err(Error::new("Expected ':='").with_span(span));
+    }
+    let value_tokens = &tokens[pos + 1..];
+    let (Some(first), Some(last)) = (value_tokens.first(), value_tokens.last()) else {
+        return Err(Error::new("Expected a value").with_span(tokens[pos].span));
+    };
+    Ok(Some(ConfigSetting {
+        scope,
+        name,
+        value: value(value_tokens)?,
+        value_span: first.span.extend(&last.span),
+        span: tokens[0].span.extend(&last.span),
+    }))
+}
+
+/// Decodes a literal value, possibly cast to `cfg::memory` or `duration`
+fn value(tokens: &[Token]) -> Result<Option<ConfigValue>, Error> {
+    let string = |tok: &Token| match &tok.value {
+        Some(Value::String(s)) if tok.kind == Kind::Str => Some(s.clone()),
+        _ => None,
+    };
+    let value = match tokens {
+        [tok] => match (tok.kind, &tok.value) {
+            (Kind::Str, _) => string(tok).map(ConfigValue::Str),
+            (_, Some(Value::Int(value))) => Some(ConfigValue::Int(*value)),
+            (Kind::Keyword(Keyword(kw @ ("true" | "false"))), _) => {
+                Some(ConfigValue::Bool(kw == "true"))
+            }
+            _ => None,
+        },
+        [open, name @ .., close, literal]
+            if open.kind == Kind::Less && close.kind == Kind::Greater =>
+        {
+            let Some(text) = string(literal) else {
+                return Ok(None);
+            };
+            let type_name: String = name.iter().map(|t| &t.text[..]).collect();
+            let value = match &type_name[..] {
+                "cfg::memory" | "std::cfg::memory" => parse_memory(&text).map(ConfigValue::Memory),
+                "duration" | "std::duration" => parse_duration(&text).map(ConfigValue::Duration),
+                _ => return Ok(None),
+            };
+            let mut error = match value {
+                Ok(value) => return Ok(Some(value)),
+                Err(err) => Error::new(err).with_span(literal.span),
+            };
+            error.hint = memory_suggestion(&text)
+                .filter(|_| type_name.ends_with("memory"))
+                .map(|s| format!("use '{s}'"));
+            return Err(error);
+        }
+        _ => None,
+    };
+    Ok(value)
+}
+
+/// Parses a memory size, like `512MiB`, into bytes
+pub fn parse_memory(text: &str) -> Result<u64, ConfigValueError> {
+    if text == "0" {
+        return Ok(0);
+    }
+    let digits = text
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len());
+    let (number, unit) = text.split_at(digits);
+    let size = MEMORY_UNITS.iter().find(|(u, _)| *u == unit);
+    match size {
+        Some((_, size)) if !number.is_empty() => number
+            .parse::<u64>()
+            .ok()
+            .and_then(|n| n.checked_mul(*size))
+            .ok_or(ConfigValueError::OutOfRange),
+        _ => Err(ConfigValueError::InvalidMemory(text.into())),
+    }
+}
+
+/// Formats bytes with the largest unit that divides them, like the server
+pub fn format_memory(bytes: u64) -> String {
+    let (unit, size) = MEMORY_UNITS
+        .iter()
+        .find(|(_, size)| bytes >= *size && bytes % size == 0)
+        .copied()
+        .unwrap_or(("B", 1));
+    format!("{}{unit}", bytes / size)
+}
+
+/// The memory size with the unit spelled the way the server accepts, for
+/// sizes like `1GB` or `64kb`
+fn memory_suggestion(text: &str) -> Option<String> {
+    let digits = text.find(|c: char| !c.is_ascii_digit())?;
+    let (number, unit) = text.split_at(digits);
+    let unit = match &unit.trim().to_ascii_lowercase()[..] {
+        "b" => "B",
+        "k" | "kb" | "kib" => "KiB",
+        "m" | "mb" | "mib" => "MiB",
+        "g" | "gb" | "gib" => "GiB",
+        "t" | "tb" | "tib" => "TiB",
+        "p" | "pb" | "pib" => "PiB",
+        _ => return None,
+    };
+    (!number.is_empty()).then(|| format!("{number}{unit}"))
+}
+
+/// Parses a duration into microseconds
+///
+/// Accepts the same formats as the server: a number of seconds,
+/// `HH:MM:SS.ffffff`, ISO 8601 with hours, minutes and seconds (`PT1H30M`)
+/// and units (`1 hour 30 minutes`, `1h30m`).
+pub fn parse_duration(text: &str) -> Result<i64, ConfigValueError> {
+    if let Ok(seconds) = text.trim().parse::<i64>() {
+        return seconds
+            .checked_mul(1_000_000)
+            .ok_or(ConfigValueError::OutOfRange);
+    }
+    if let Some(value) = clock_duration(text) {
+        return value;
+    }
+    if let Some(value) = iso_duration(text) {
+        return value;
+    }
+    unit_duration(text)
+}
+
+/// Formats microseconds as ISO 8601, like the server
+pub fn format_duration(us: i64) -> String {
+    let neg = if us < 0 { "-" } else { "" };
+    let abs = us.unsigned_abs();
+    let (seconds, usecs) = (abs / 1_000_000, abs % 1_000_000);
+    let (minutes, seconds) = (seconds / 60, seconds % 60);
+    let (hours, minutes) = (minutes / 60, minutes % 60);
+    let mut result = String::from("PT");
+    if hours > 0 {
+        write!(result, "{neg}{hours}H").unwrap();
+    }
+    if minutes > 0 {
+        write!(result, "{neg}{minutes}M").unwrap();
+    }
+    if usecs > 0 {
+        let fraction = format!("{usecs:06}");
+        write!(result, "{neg}{seconds}.{}S", fraction.trim_end_matches('0')).unwrap();
+    } else if seconds > 0 {
+        write!(result, "{neg}{seconds}S").unwrap();
+    }
+    if result == "PT" {
+        result.push_str("0S");
+    }
+    result
+}
+
+fn is_digits(text: &str) -> bool {
+    !text.is_empty() && text.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// `[+-]HH:[MM][:SS[.ffffff]]`, `None` if the text is not in this format
+fn clock_duration(text: &str) -> Option<Result<i64, ConfigValueError>> {
+    let text = text.trim();
+    let (negative, text) = match text.strip_prefix('-') {
+        Some(text) => (true, text),
+        None => (false, text.strip_prefix('+').unwrap_or(text)),
+    };
+    let (hours, rest) = text.split_once(':')?;
+    let (minutes, seconds) = match rest.split_once(':') {
+        Some((minutes, seconds)) => (minutes, Some(seconds)),
+        None => (rest, None),
+    };
+    let (seconds, fraction) = match seconds.map(|s| s.split_once('.')) {
+        Some(Some((seconds, fraction))) => (Some(seconds), fraction),
+        Some(None) => (seconds, ""),
+        None => (None, ""),
+    };
+    let valid = is_digits(hours)
+        && (minutes.is_empty() || is_digits(minutes))
+        && seconds.is_none_or(is_digits)
+        && (fraction.is_empty() || is_digits(fraction));
+    if !valid {
+        return None;
+    }
+    Some(clock_value(
+        negative,
+        hours,
+        minutes,
+        seconds.unwrap_or(""),
+        fraction,
+    ))
+}
+
+fn clock_value(
+    negative: bool,
+    hours: &str,
+    minutes: &str,
+    seconds: &str,
+    fraction: &str,
+) -> Result<i64, ConfigValueError> {
+    let field = |text: &str, max: u64| match text {
+        "" => Ok(0),
+        _ => text
+            .parse::<u64>()
+            .ok()
+            .filter(|&v| v <= max)
+            .ok_or(ConfigValueError::OutOfRange),
+    };
+    let mut value = field(hours, i32::MAX as u64)? * 3_600_000_000;
+    value += field(minutes, 59)? * 60_000_000;
+    value += field(seconds, 59)? * 1_000_000;
+    // microseconds are rounded to the nearest
+    value += format!("{:0<6}", &fraction[..fraction.len().min(6)])
+        .parse::<u64>()
+        .unwrap();
+    if fraction.as_bytes().get(6).is_some_and(|&d| d >= b'5') {
+        value += 1;
+    }
+    let value = i64::try_from(value).map_err(|_| ConfigValueError::OutOfRange)?;
+    Ok(if negative { -value } else { value })
+}
+
+/// `PT[n H][n M][n[.f] S]`, `None` if the text is not in this format
+fn iso_duration(text: &str) -> Option<Result<i64, ConfigValueError>> {
+    let mut rest = text.strip_prefix("PT")?;
+    let mut value = 0i64;
+    for (designator, unit) in [('H', 3_600_000_000), ('M', 60_000_000), ('S', 1_000_000)] {
+        let len = rest
+            .find(|c: char| {
+                !(c.is_ascii_digit() || "+-".contains(c) || (designator == 'S' && c == '.'))
+            })
+            .unwrap_or(rest.len());
+        if !rest[len..].starts_with(designator) {
+            continue;
+        }
+        let number = &rest[..len];
+        rest = &rest[len + 1..];
+        let (negative, number) = match number.strip_prefix('-') {
+            Some(number) => (true, number),
+            None => (false, number.strip_prefix('+').unwrap_or(number)),
+        };
+        let (whole, fraction) = match number.split_once('.') {
+            Some((whole, fraction)) => (whole, Some(fraction)),
+            None => (number, None),
+        };
+        if !is_digits(whole) || !fraction.is_none_or(is_digits) {
+            return None;
+        }
+        let micros = fraction.map_or(0, |f| {
+            format!("{:0<6}", &f[..f.len().min(6)])
+                .parse::<i64>()
+                .unwrap()
+        });
+        let component = whole
+            .parse::<i64>()
+            .ok()
+            .and_then(|n| n.checked_mul(unit))
+            .and_then(|n| n.checked_add(micros));
+        let component = component.map(|c| if negative { -c } else { c });
+        let Some(sum) = component.and_then(|c| value.checked_add(c)) else {
+            return Some(Err(ConfigValueError::OutOfRange));
+        };
+        value = sum;
+    }
+    rest.is_empty().then_some(Ok(value))
+}
+
+/// Numbers with units, like `1 hour 30 minutes` or `90s`, the unit of the
+/// last number defaults to seconds
+fn unit_duration(text: &str) -> Result<i64, ConfigValueError> {
+    let mut rest = text.trim_start();
+    let mut seen = Vec::new();
+    let mut value = 0i64;
+    while !rest.is_empty() {
+        let invalid = || {
+            let line = rest.lines().next().unwrap_or(rest);
+            ConfigValueError::InvalidDuration(line.into())
+        };
+        let sign = usize::from(rest.starts_with(['+', '-']));
+        let digits = rest[sign..]
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len() - sign);
+        if digits == 0 {
+            return Err(invalid());
+        }
+        let number = &rest[..sign + digits];
+        let after = rest[sign + digits..].trim_start();
+        let unit_len = after
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(after.len());
+        let unit = after[..unit_len].to_ascii_lowercase();
+        let next = &after[unit_len..];
+        let at_boundary =
+            next.is_empty() || next.starts_with(|c: char| c.is_ascii_digit() || c.is_whitespace());
+        let kind = if unit.is_empty() && after.is_empty() {
+            DURATION_UNITS
+                .iter()
+                .find(|(kind, _, _)| *kind == "seconds")
+        } else if at_boundary {
+            DURATION_UNITS
+                .iter()
+                .find(|(_, names, _)| names.contains(&&unit[..]))
+        } else {
+            None
+        };
+        let Some(&(kind, _, size)) = kind else {
+            return Err(invalid());
+        };
+        if seen.contains(&kind) {
+            return Err(ConfigValueError::DuplicateComponent(kind));
+        }
+        seen.push(kind);
+        value = number
+            .parse::<i64>()
+            .ok()
+            .and_then(|n| n.checked_mul(size))
+            .and_then(|n| n.checked_add(value))
+            .ok_or(ConfigValueError::OutOfRange)?;
+        rest = next.trim_start();
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{format_duration, format_memory, parse_configure, parse_duration, parse_memory};
+    use super::{ConfigValue, ConfigValueError};
+
+    #[test]
+    fn units() {
+        assert_eq!(parse_memory("1024MiB"), Ok(1 << 30));
+        assert_eq!(format_memory(1 << 30), "1GiB");
+        assert_eq!(format_memory(1536), "1536B");
+        assert_eq!(parse_memory("0"), Ok(0));
+        assert_eq!(
+            parse_memory("1GB"),
+            Err(ConfigValueError::InvalidMemory("1GB".into()))
+        );
+
+        for text in [
+            "5400",
+            "1:30:00",
+            "PT1H30M",
+            "1 hour 30 minutes",
+            "1h30m",
+            "90 min",
+        ] {
+            assert_eq!(parse_duration(text), Ok(5_400_000_000), "{text}");
+        }
+        assert_eq!(parse_duration("0:00:01.5"), Ok(1_500_000));
+        assert_eq!(
+            parse_duration("1 hour 2 hours"),
+            Err(ConfigValueError::DuplicateComponent("hours"))
+        );
+        assert_eq!(
+            parse_duration("1 fortnight"),
+            Err(ConfigValueError::InvalidDuration("1 fortnight".into()))
+        );
+        assert_eq!(format_duration(5_400_000_000), "PT1H30M");
+        assert_eq!(format_duration(-1_500_000), "PT-1.5S");
+        assert_eq!(format_duration(0), "PT0S");
+    }
+
+    #[test]
+    fn statements() {
+        let text = "
+            configure instance set shared_buffers := <cfg::memory>'1024MiB';
+            configure current branch set query_execution_timeout := <duration>'90s';
+            configure session set apply_access_policies := false;
+            configure instance insert cfg::Auth { priority := 0 };
+        ";
+        let settings = parse_configure(text).unwrap();
+        assert_eq!(settings.len(), 3);
+        assert_eq!(settings[0].scope, "instance");
+        assert_eq!(settings[0].value, Some(ConfigValue::Memory(1 << 30)));
+        assert_eq!(
+            settings[0].value.as_ref().unwrap().to_edgeql(),
+            "<cfg::memory>'1GiB'"
+        );
+        assert_eq!(settings[1].scope, "current branch");
+        assert_eq!(
+            settings[1].value.as_ref().unwrap().to_edgeql(),
+            "<std::duration>'PT1M30S'"
+        );
+        assert_eq!(settings[2].value, Some(ConfigValue::Bool(false)));
+
+        let error = parse_configure("configure instance set shared_buffers := <cfg::memory>'1GB'")
+            .unwrap_err();
+        assert_eq!(error.message, "unable to parse memory size: '1GB'");
+        assert_eq!(error.hint.as_deref(), Some("use '1GiB'"));
+    }
+}
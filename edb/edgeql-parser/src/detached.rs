@@ -0,0 +1,386 @@
+//! Scopes of paths and checks of `detached`
+//!
+//! Paths starting with the same name refer to the same set within a query:
+//! in `select User { n := count(User) }` the inner `User` is the user being
+//! selected, so `n` is always 1, while `count(detached User)` counts all
+//! users. [analyze] tells which scope every path binds to and warns about
+//! uses of `detached` that are likely mistakes:
+//!
+//! * `detached` of an alias that is also used without it, as the detached
+//!   uses refer to a separate set
+//! * `detached` of a name that is not used elsewhere, where it has no effect
+//!
+//! Scopes are approximated from tokens: the query, every parenthesized
+//! subquery and every shape opens a scope. A path binds to the outermost
+//! enclosing scope that directly contains a path starting with the same
+//! name. Names that are also keywords (like `config`) are not recognized
+//! as paths.
+use crate::braces::is_shape;
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::equivalence::in_with_block;
+use crate::keywords::Keyword;
+use crate::position::Span;
+use crate::schema::{is_name_like, token_name};
+use crate::tokenizer::{Error, Kind, Token, Tokenizer};
+
+const STATEMENTS: &[&str] = &[
+    "delete", "for", "group", "insert", "select", "update", "with",
+];
+
+/// Modifiers of shape elements, skipped when looking for element names
+const ELEMENT_MODIFIERS: &[&str] = &["required", "optional", "single", "multi"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScopeKind {
+    Query,
+    /// Parenthesized statement, like `(select ...)`
+    Subquery,
+    Shape,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scope {
+    pub kind: ScopeKind,
+    /// Whole query, or from the opening to the closing bracket
+    pub span: Span,
+    /// Index of the enclosing scope, `None` for the query
+    pub parent: Option<usize>,
+}
+
+/// First name of a path, like `User` in `User.friends.name`
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathRoot {
+    /// Name as written, possibly qualified (`default::User`)
+    pub name: String,
+    /// Span of the name, without `detached`
+    pub span: Span,
+    /// Index of the innermost scope the path is written in
+    pub scope: usize,
+    /// Index of the scope the path binds to, `None` for detached paths,
+    /// which are independent of all other paths
+    pub binding: Option<usize>,
+    pub detached: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Analysis {
+    pub scopes: Vec<Scope>,
+    /// Roots of all paths, in the order they are written
+    pub paths: Vec<PathRoot>,
+    pub warnings: Vec<Diagnostic>,
+}
+
+/// Finds the scopes of the paths of the query and checks uses of
+/// `detached`, fails if the query cannot be tokenized
+pub fn analyze(text: &str) -> Result<Analysis, Error> {
+    let tokens = Tokenizer::new(text)
+        .validated_values()
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut analysis = Analysis::default();
+    let Some(first) = tokens.first() else {
+        return Ok(analysis);
+    };
+    let last = tokens.last().unwrap_or(first);
+    analysis.scopes.push(Scope {
+        kind: ScopeKind::Query,
+        span: first.span.extend(&last.span),
+        parent: None,
+    });
+
+    let mut aliases = Vec::new();
+    let mut detached_spans = Vec::new();
+    // for every open bracket, the scope it opened
+    let mut brackets: Vec<Option<usize>> = Vec::new();
+    let mut current = 0;
+    let mut idx = 0;
+    while idx < tokens.len() {
+        let tok = &tokens[idx];
+        match tok.kind {
+            Kind::OpenParen | Kind::OpenBracket | Kind::OpenBrace => {
+                let kind = match tok.kind {
+                    Kind::OpenParen if starts_statement(tokens.get(idx + 1)) => {
+                        Some(ScopeKind::Subquery)
+                    }
+                    Kind::OpenBrace if is_shape(&tokens, idx) => Some(ScopeKind::Shape),
+                    _ => None,
+                };
+                let opened = kind.map(|kind| {
+                    analysis.scopes.push(Scope {
+                        kind,
+                        span: tok.span,
+                        parent: Some(current),
+                    });
+                    analysis.scopes.len() - 1
+                });
+                current = opened.unwrap_or(current);
+                brackets.push(opened);
+            }
+            Kind::CloseParen | Kind::CloseBracket | Kind::CloseBrace => {
+                if let Some(Some(scope)) = brackets.pop() {
+                    let scope = &mut analysis.scopes[scope];
+                    scope.span = scope.span.extend(&tok.span);
+                    current = scope.parent.unwrap_or(0);
+                }
+            }
+            // module names may be keywords, like `default::User`
+            _ if is_name(tok.kind)
+                || (is_name_like(Some(tok.kind))
+                    && tokens.get(idx + 1).map(|t| t.kind) == Some(Kind::Namespace)) =>
+            {
+                let (name, span, end) = read_name(&tokens, idx);
+                let prev = idx.checked_sub(1).map(|i| &tokens[i]);
+                let next = tokens.get(end).map(|t| t.kind);
+                if next == Some(Kind::Assign) || keyword(prev) == Some("for") {
+                    let is_alias = keyword(prev).is_some_and(|k| STATEMENTS.contains(&k))
+                        || (prev.is_some_and(|t| t.kind == Kind::Comma)
+                            && in_with_block(&tokens, idx - 1));
+                    if is_alias {
+                        aliases.push(name);
+                    }
+                } else if is_root(&tokens, idx, end)
+                    && !is_element_name(&tokens, idx, &brackets, &analysis.scopes)
+                {
+                    let detached = keyword(prev) == Some("detached");
+                    if detached {
+                        detached_spans.push(prev.unwrap().span.combine(span));
+                    }
+                    analysis.paths.push(PathRoot {
+                        name,
+                        span,
+                        scope: current,
+                        binding: None,
+                        detached,
+                    });
+                }
+                idx = end;
+                continue;
+            }
+            _ => {}
+        }
+        idx += 1;
+    }
+
+    bind(&mut analysis);
+    check(&mut analysis, &aliases, &detached_spans);
+    Ok(analysis)
+}
+
+impl Analysis {
+    /// Scopes from the innermost one to the query
+    pub fn ancestors(&self, scope: usize) -> impl Iterator<Item = usize> + '_ {
+        std::iter::successors(Some(scope), |&s| self.scopes[s].parent)
+    }
+}
+
+fn bind(analysis: &mut Analysis) {
+    for idx in 0..analysis.paths.len() {
+        let path = &analysis.paths[idx];
+        if path.detached {
+            continue;
+        }
+        let binding = analysis
+            .ancestors(path.scope)
+            .filter(|&scope| {
+                analysis
+                    .paths
+                    .iter()
+                    .any(|p| !p.detached && p.scope == scope && p.name == path.name)
+            })
+            .last();
+        analysis.paths[idx].binding = binding;
+    }
+}
+
+fn check(analysis: &mut Analysis, aliases: &[String], detached_spans: &[Span]) {
+    let detached = analysis.paths.iter().filter(|p| p.detached);
+    for (path, &span) in detached.zip(detached_spans) {
+        let attached = analysis
+            .paths
+            .iter()
+            .find(|p| !p.detached && p.name == path.name);
+        let warning = match attached {
+            Some(attached) if aliases.contains(&path.name) => Diagnostic::with_code(
+                Severity::Warning,
+                "mixed-detached",
+                &[("name", path.name.clone())],
+                span,
+            )
+            .with_label(attached.span)
+            .with_hint(),
+            Some(_) => continue,
+            None => Diagnostic::with_code(
+                Severity::Warning,
+                "useless-detached",
+                &[("name", path.name.clone())],
+                span,
+            )
+            .with_hint()
+            .with_suggestion(
+                Span {
+                    start: span.start,
+                    end: path.span.start,
+                },
+                "",
+            ),
+        };
+        analysis.warnings.push(warning);
+    }
+}
+
+fn keyword(token: Option<&Token>) -> Option<&'static str> {
+    match token?.kind {
+        Kind::Keyword(Keyword(kw)) => Some(kw),
+        _ => None,
+    }
+}
+
+fn is_name(kind: Kind) -> bool {
+    matches!(kind, Kind::Ident | Kind::BacktickName)
+}
+
+fn starts_statement(token: Option<&Token>) -> bool {
+    keyword(token).is_some_and(|k| STATEMENTS.contains(&k))
+}
+
+/// Reads a possibly qualified name, returns it with its span and the index
+/// of the token after it
+fn read_name(tokens: &[Token], start: usize) -> (String, Span, usize) {
+    let mut name = token_name(&tokens[start]).to_string();
+    let mut span = tokens[start].span;
+    let mut pos = start + 1;
+    while tokens.get(pos).map(|t| t.kind) == Some(Kind::Namespace)
+        && is_name_like(tokens.get(pos + 1).map(|t| t.kind))
+    {
+        name.push_str("::");
+        name.push_str(token_name(&tokens[pos + 1]));
+        span = span.extend(&tokens[pos + 1].span);
+        pos += 2;
+    }
+    (name, span, pos)
+}
+
+/// Whether the name at `start..end` starts a path, rather than being a
+/// path step, a function, a type or a module
+fn is_root(tokens: &[Token], start: usize, end: usize) -> bool {
+    let prev = start.checked_sub(1).map(|i| &tokens[i]);
+    let next = tokens.get(end).map(|t| t.kind);
+    let is_step = prev.is_some_and(|t| {
+        matches!(
+            t.kind,
+            Kind::Dot | Kind::BackwardLink | Kind::OptionalLink | Kind::At | Kind::Namespace
+        )
+    });
+    let is_type = matches!(
+        keyword(prev),
+        Some("is" | "module" | "global" | "introspect")
+    ) || (prev.is_some_and(|t| t.kind == Kind::Less)
+        && matches!(next, Some(Kind::Greater | Kind::Less | Kind::Comma)));
+    let is_call = matches!(next, Some(Kind::OpenParen | Kind::Colon | Kind::Namespace));
+    !is_step && !is_type && !is_call
+}
+
+/// Whether the name at `idx` is the name of a shape element, like `name`
+/// in `{ required name := ... }` or `<author` in `{ <author: { ... } }`
+fn is_element_name(
+    tokens: &[Token],
+    idx: usize,
+    brackets: &[Option<usize>],
+    scopes: &[Scope],
+) -> bool {
+    let in_shape = matches!(brackets.last(), Some(Some(scope))
+        if scopes[*scope].kind == ScopeKind::Shape);
+    if !in_shape {
+        return false;
+    }
+    let mut pos = idx;
+    while let Some(prev) = pos.checked_sub(1).map(|i| &tokens[i]) {
+        let is_modifier = keyword(Some(prev)).is_some_and(|k| ELEMENT_MODIFIERS.contains(&k));
+        if !(is_modifier || matches!(prev.kind, Kind::At | Kind::Less)) {
+            return matches!(prev.kind, Kind::OpenBrace | Kind::Comma);
+        }
+        pos -= 1;
+    }
+    false
+}
+
+#[cfg(test)]
+mod test {
+    use super::{analyze, Analysis, ScopeKind};
+
+    fn roots(analysis: &Analysis) -> Vec<(&str, usize, Option<usize>)> {
+        analysis
+            .paths
+            .iter()
+            .map(|p| (&p.name[..], p.scope, p.binding))
+            .collect()
+    }
+
+    #[test]
+    fn scopes() {
+        let analysis = analyze(
+            "select User { name, friends: { name }, n := count(User), \
+             all := count(detached User), \
+             same := (select User filter .name = 'x') } \
+             filter .name in {'a', 'b'}",
+        )
+        .unwrap();
+        let kinds: Vec<_> = analysis.scopes.iter().map(|s| s.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                ScopeKind::Query,
+                ScopeKind::Shape,
+                ScopeKind::Shape,
+                ScopeKind::Subquery,
+            ]
+        );
+        assert_eq!(analysis.scopes[3].parent, Some(1));
+        assert_eq!(
+            roots(&analysis),
+            [
+                ("User", 0, Some(0)),
+                ("User", 1, Some(0)),
+                ("User", 1, None),
+                ("User", 3, Some(0)),
+            ]
+        );
+        assert!(analysis.paths[2].detached);
+        assert!(analysis.warnings.is_empty());
+
+        let analysis = analyze(
+            "with module default, u := (select default::User) \
+             for x in {1, 2} union (select (x, u.name, <str>x, Post))",
+        )
+        .unwrap();
+        let names: Vec<_> = analysis.paths.iter().map(|p| &p.name[..]).collect();
+        assert_eq!(names, ["default::User", "x", "u", "x", "Post"]);
+    }
+
+    #[test]
+    fn detached_alias() {
+        let text = "with u := (select User limit 1) select (u, detached u)";
+        let analysis = analyze(text).unwrap();
+        assert_eq!(analysis.warnings.len(), 1);
+        let warning = &analysis.warnings[0];
+        assert_eq!(
+            warning.message,
+            "alias `u` is used both with and without `detached`"
+        );
+        let span = warning.span;
+        assert_eq!(&text[span.start as usize..span.end as usize], "detached u");
+        assert_eq!(warning.labels[0].span.start, 40);
+    }
+
+    #[test]
+    fn redundant() {
+        let analysis = analyze("select detached User { name }").unwrap();
+        assert_eq!(analysis.warnings.len(), 1);
+        let warning = &analysis.warnings[0];
+        assert_eq!(warning.message, "`detached` has no effect");
+        assert_eq!(warning.code.as_deref(), Some("useless-detached"));
+        let suggestion = &warning.suggestions[0];
+        assert_eq!((suggestion.span.start, suggestion.span.end), (7, 16));
+        assert_eq!(analysis.paths.len(), 1);
+        assert_eq!(analysis.paths[0].binding, None);
+    }
+}
@@ -0,0 +1,707 @@
+//! Rendering of errors, for command-line tools and CI integration
+//!
+//! Errors of the tokenizer, the parser and the checks of this crate are
+//! converted into a [Diagnostic], which can be rendered for humans with
+//! [render] (a source excerpt with the span underlined) or for tools with
+//! [render_json] (a versioned JSON format, see [JSON_VERSION]) and
+//! [render_sarif] (for code scanning platforms).
+use std::borrow::Cow;
+use std::fmt::{self, Write};
+
+use unicode_width::UnicodeWidthStr;
+
+use crate::messages::{default_template, format_message, MessageProvider};
+use crate::position::{InflatedPos, Span};
+use crate::tokenizer::{Error, ErrorKind};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// Stable identifier of the kind of the diagnostic, if it has one
+    pub code: Option<String>,
+    pub message: String,
+    /// Values of the placeholders of the message of the [code](Self::code),
+    /// see [messages](crate::messages)
+    pub args: Vec<(String, String)>,
+    /// The primary span
+    pub span: Span,
+    /// Secondary spans, e.g. the previous definition of a duplicate name
+    pub labels: Vec<Label>,
+    pub hint: Option<String>,
+    pub details: Option<String>,
+    pub suggestions: Vec<Suggestion>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+}
+
+/// A fix of the diagnostic: the text of the span is to be replaced
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suggestion {
+    pub message: String,
+    pub span: Span,
+    pub replacement: String,
+}
+
+impl Suggestion {
+    /// Source with the span replaced
+    pub fn apply(&self, source: &str) -> String {
+        let mut result = source[..self.span.start as usize].to_string();
+        result.push_str(&self.replacement);
+        result.push_str(&source[self.span.end as usize..]);
+        result
+    }
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl From<Error> for Diagnostic {
+    fn from(err: Error) -> Diagnostic {
+        let mut suggestions = Vec::new();
+        if err.kind == Some(ErrorKind::MissingSemicolon) {
+            suggestions.push(Suggestion {
+                message: "insert ';'".into(),
+                span: err.span,
+                replacement: ";".into(),
+            });
+        }
+        Diagnostic {
+            hint: err.hint,
+            details: err.details,
+            suggestions,
+            ..Diagnostic::new(Severity::Error, err.message, err.span)
+        }
+    }
+}
+
+/// Line and column of a byte offset, both one-based
+fn line_column(source: &str, offset: u64) -> (u64, u64) {
+    let offset = offset.min(source.len() as u64);
+    match InflatedPos::from_offset(source.as_bytes(), offset) {
+        Ok(pos) => (pos.line + 1, pos.column + 1),
+        Err(_) => (1, 1),
+    }
+}
+
+/// Renders diagnostics of the `source` read from `filename`:
+///
+/// ```text
+/// error: Unexpected keyword 'FROM'
+///   --> query.edgeql:1:10
+///   |
+/// 1 | select 1 from User;
+///   |          ^^^^
+///   = hint: ...
+/// ```
+pub fn render(source: &str, filename: &str, diagnostics: &[Diagnostic]) -> String {
+    let mut buf = String::new();
+    for diagnostic in diagnostics {
+        render_one(&mut buf, source, filename, diagnostic);
+    }
+    buf
+}
+
+fn render_one(buf: &mut String, source: &str, filename: &str, diagnostic: &Diagnostic) {
+    let start = (diagnostic.span.start as usize).min(source.len());
+    let end = (diagnostic.span.end as usize).clamp(start, source.len());
+    let (line, column) = line_column(source, start as u64);
+
+    let line_start = source[..start].rfind(['\r', '\n']).map_or(0, |i| i + 1);
+    let line_end = source[start..]
+        .find(['\r', '\n'])
+        .map_or(source.len(), |i| start + i);
+    let text = &source[line_start..line_end];
+    // multi-line spans are underlined until the end of the first line
+    let underlined = &source[start..end.min(line_end)];
+
+    let gutter = line.to_string().len();
+    let pad = "";
+    match &diagnostic.code {
+        Some(code) => writeln!(
+            buf,
+            "{}[{code}]: {}",
+            diagnostic.severity, diagnostic.message
+        ),
+        None => writeln!(buf, "{}: {}", diagnostic.severity, diagnostic.message),
+    }
+    .unwrap();
+    writeln!(buf, "{pad:gutter$} --> {filename}:{line}:{column}").unwrap();
+    writeln!(buf, "{pad:gutter$} |").unwrap();
+    writeln!(buf, "{line} | {text}").unwrap();
+    writeln!(
+        buf,
+        "{pad:gutter$} | {pad:indent$}{}",
+        "^".repeat(underlined.width().max(1)),
+        indent = (column - 1) as usize,
+    )
+    .unwrap();
+    if let Some(hint) = &diagnostic.hint {
+        writeln!(buf, "{pad:gutter$} = hint: {hint}").unwrap();
+    }
+    if let Some(details) = &diagnostic.details {
+        for details_line in details.lines() {
+            writeln!(buf, "{pad:gutter$} = {details_line}").unwrap();
+        }
+    }
+    for label in &diagnostic.labels {
+        let (line, column) = line_column(source, label.span.start);
+        writeln!(
+            buf,
+            "{pad:gutter$} = note: {} at {filename}:{line}:{column}",
+            label.message
+        )
+        .unwrap();
+    }
+    for suggestion in &diagnostic.suggestions {
+        writeln!(
+            buf,
+            "{pad:gutter$} = suggestion: {}: `{}`",
+            suggestion.message, suggestion.replacement
+        )
+        .unwrap();
+    }
+    buf.push('\n');
+}
+
+/// Version of the JSON format of [Diagnostic::to_json] and [render_json],
+/// incremented on incompatible changes
+///
+/// The format is described by `diagnostics.schema.json` in the crate root.
+pub const JSON_VERSION: u32 = 1;
+
+impl Diagnostic {
+    /// Diagnostic without a code, for errors of the tokenizer and the
+    /// grammar, see [messages](crate::messages)
+    pub fn new(severity: Severity, message: impl Into<String>, span: Span) -> Diagnostic {
+        Diagnostic {
+            severity,
+            code: None,
+            message: message.into(),
+            args: Vec::new(),
+            span,
+            labels: Vec::new(),
+            hint: None,
+            details: None,
+            suggestions: Vec::new(),
+        }
+    }
+
+    /// Diagnostic with the message of the code in the
+    /// [catalog](crate::messages::CATALOG)
+    pub fn with_code(
+        severity: Severity,
+        code: &str,
+        args: &[(&str, String)],
+        span: Span,
+    ) -> Diagnostic {
+        debug_assert!(default_template(code).is_some(), "unknown code {code}");
+        let args: Vec<_> = args
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.clone()))
+            .collect();
+        let message = format_message(default_template(code).unwrap_or(code), &args);
+        Diagnostic {
+            code: Some(code.into()),
+            args,
+            ..Diagnostic::new(severity, message, span)
+        }
+    }
+
+    /// Adds an argument, for parts of the message that are only known
+    /// after creating the diagnostic
+    pub fn with_arg(mut self, name: &str, value: impl Into<String>) -> Diagnostic {
+        self.args.push((name.into(), value.into()));
+        self
+    }
+
+    /// Sets the hint to the `<code>.hint` message of the catalog
+    pub fn with_hint(mut self) -> Diagnostic {
+        self.hint = Some(self.part("hint"));
+        self
+    }
+
+    /// Sets the details to the `<code>.details` message of the catalog
+    pub fn with_details(mut self) -> Diagnostic {
+        self.details = Some(self.part("details"));
+        self
+    }
+
+    /// Adds a label with the `<code>.label` message of the catalog
+    pub fn with_label(mut self, span: Span) -> Diagnostic {
+        let message = self.part("label");
+        self.labels.push(Label { span, message });
+        self
+    }
+
+    /// Adds a suggestion with the `<code>.suggestion` message of the catalog
+    pub fn with_suggestion(mut self, span: Span, replacement: impl Into<String>) -> Diagnostic {
+        let message = self.part("suggestion");
+        self.suggestions.push(Suggestion {
+            message,
+            span,
+            replacement: replacement.into(),
+        });
+        self
+    }
+
+    /// The `<code>.<part>` message of the catalog
+    fn part(&self, part: &str) -> String {
+        let key = format!("{}.{part}", self.code.as_deref().unwrap_or_default());
+        debug_assert!(default_template(&key).is_some(), "unknown message {key}");
+        format_message(default_template(&key).unwrap_or(&key), &self.args)
+    }
+
+    /// Renders the message, the hint, the details, the labels and the
+    /// suggestions again with the templates of the provider, where it has
+    /// one for the code of the diagnostic
+    pub fn localize(&mut self, provider: &dyn MessageProvider) {
+        let Some(code) = &self.code else {
+            return;
+        };
+        let render = |part: Option<&str>| {
+            let key = match part {
+                Some(part) => Cow::Owned(format!("{code}.{part}")),
+                None => Cow::Borrowed(&code[..]),
+            };
+            let template = provider.template(&key)?;
+            Some(format_message(&template, &self.args))
+        };
+        let message = render(None);
+        let hint = render(Some("hint")).filter(|_| self.hint.is_some());
+        let details = render(Some("details")).filter(|_| self.details.is_some());
+        let label = render(Some("label"));
+        let suggestion = render(Some("suggestion"));
+
+        if let Some(message) = message {
+            self.message = message;
+        }
+        if hint.is_some() {
+            self.hint = hint;
+        }
+        if details.is_some() {
+            self.details = details;
+        }
+        if let Some(label) = label {
+            for l in &mut self.labels {
+                l.message.clone_from(&label);
+            }
+        }
+        if let Some(suggestion) = suggestion {
+            for s in &mut self.suggestions {
+                s.message.clone_from(&suggestion);
+            }
+        }
+    }
+
+    /// Converts the diagnostic to a JSON object:
+    ///
+    /// ```json
+    /// {"code": null, "severity": "error", "message": "...",
+    ///  "file": "query.edgeql",
+    ///  "spans": [{"start": {"line": 1, "column": 10, "offset": 9},
+    ///             "end": {"line": 1, "column": 14, "offset": 13},
+    ///             "label": null, "primary": true}],
+    ///  "hint": null, "details": null,
+    ///  "suggestions": [{"message": "...", "start": {...}, "end": {...},
+    ///                   "replacement": "..."}]}
+    /// ```
+    ///
+    /// The primary span goes first. Lines and columns are one-based,
+    /// offsets are in bytes.
+    pub fn to_json(&self, source: &str, filename: &str) -> String {
+        let mut buf = String::new();
+        let position = |buf: &mut String, offset: u64| {
+            let (line, column) = line_column(source, offset);
+            write!(
+                buf,
+                r#"{{"line":{line},"column":{column},"offset":{offset}}}"#
+            )
+            .unwrap();
+        };
+        let range = |buf: &mut String, span: Span| {
+            buf.push_str(r#""start":"#);
+            position(buf, span.start);
+            buf.push_str(r#","end":"#);
+            position(buf, span.end);
+        };
+        buf.push_str(r#"{"code":"#);
+        json_option(&mut buf, self.code.as_deref());
+        write!(buf, r#","severity":"{}","message":"#, self.severity).unwrap();
+        json_string(&mut buf, &self.message);
+        buf.push_str(r#","file":"#);
+        json_string(&mut buf, filename);
+
+        buf.push_str(r#","spans":[{"#);
+        range(&mut buf, self.span);
+        buf.push_str(r#","label":null,"primary":true}"#);
+        for label in &self.labels {
+            buf.push_str(",{");
+            range(&mut buf, label.span);
+            buf.push_str(r#","label":"#);
+            json_string(&mut buf, &label.message);
+            buf.push_str(r#","primary":false}"#);
+        }
+        buf.push(']');
+
+        buf.push_str(r#","hint":"#);
+        json_option(&mut buf, self.hint.as_deref());
+        buf.push_str(r#","details":"#);
+        json_option(&mut buf, self.details.as_deref());
+
+        buf.push_str(r#","suggestions":["#);
+        for (idx, suggestion) in self.suggestions.iter().enumerate() {
+            if idx > 0 {
+                buf.push(',');
+            }
+            buf.push_str(r#"{"message":"#);
+            json_string(&mut buf, &suggestion.message);
+            buf.push(',');
+            range(&mut buf, suggestion.span);
+            buf.push_str(r#","replacement":"#);
+            json_string(&mut buf, &suggestion.replacement);
+            buf.push('}');
+        }
+        buf.push_str("]}");
+        buf
+    }
+}
+
+/// Renders diagnostics of a single file as a JSON document, see
+/// [json_document]
+pub fn render_json(source: &str, filename: &str, diagnostics: &[Diagnostic]) -> String {
+    json_document(diagnostics.iter().map(|d| d.to_json(source, filename)))
+}
+
+/// Wraps objects created by [Diagnostic::to_json] (possibly of different
+/// files) into a document: `{"version": 1, "diagnostics": [...]}`
+pub fn json_document(diagnostics: impl IntoIterator<Item = String>) -> String {
+    let mut buf = format!(r#"{{"version":{JSON_VERSION},"diagnostics":["#);
+    for (idx, diagnostic) in diagnostics.into_iter().enumerate() {
+        if idx > 0 {
+            buf.push(',');
+        }
+        buf.push_str(&diagnostic);
+    }
+    buf.push_str("]}");
+    buf
+}
+
+/// Diagnostics of a single file, for [render_sarif]
+#[derive(Debug, Clone, Copy)]
+pub struct FileReport<'a> {
+    /// Path or URI of the file, relative paths are relative to the root of
+    /// the repository
+    pub uri: &'a str,
+    pub source: &'a str,
+    pub diagnostics: &'a [Diagnostic],
+}
+
+/// Renders diagnostics as a SARIF 2.1.0 log with a single run of the tool,
+/// e.g. for GitHub code scanning
+///
+/// Diagnostics without a code are reported with the severity as the rule
+/// id. Columns are in UTF-16 code units, as SARIF requires by default.
+pub fn render_sarif(tool_name: &str, files: &[FileReport]) -> String {
+    let rule_id = |d: &Diagnostic| d.code.clone().unwrap_or_else(|| d.severity.to_string());
+    let mut rules: Vec<String> = Vec::new();
+    for file in files {
+        for diagnostic in file.diagnostics {
+            let id = rule_id(diagnostic);
+            if !rules.contains(&id) {
+                rules.push(id);
+            }
+        }
+    }
+
+    let mut buf = String::from(
+        r#"{"$schema":"https://json.schemastore.org/sarif-2.1.0.json","version":"2.1.0","#,
+    );
+    buf.push_str(r#""runs":[{"tool":{"driver":{"name":"#);
+    json_string(&mut buf, tool_name);
+    buf.push_str(r#","rules":["#);
+    for (idx, rule) in rules.iter().enumerate() {
+        if idx > 0 {
+            buf.push(',');
+        }
+        buf.push_str(r#"{"id":"#);
+        json_string(&mut buf, rule);
+        buf.push('}');
+    }
+    buf.push_str(r#"]}},"columnKind":"utf16CodeUnits","results":["#);
+
+    let mut first = true;
+    for file in files {
+        let location = |buf: &mut String, span: Span| {
+            buf.push_str(r#"{"physicalLocation":{"artifactLocation":{"uri":"#);
+            json_string(buf, file.uri);
+            buf.push_str(r#"},"region":"#);
+            sarif_region(buf, file.source, span);
+            buf.push_str("}}");
+        };
+        for diagnostic in file.diagnostics {
+            if !first {
+                buf.push(',');
+            }
+            first = false;
+
+            let id = rule_id(diagnostic);
+            buf.push_str(r#"{"ruleId":"#);
+            json_string(&mut buf, &id);
+            write!(
+                buf,
+                r#","ruleIndex":{},"level":"{}","message":{{"text":"#,
+                rules.iter().position(|r| r == &id).unwrap(),
+                diagnostic.severity,
+            )
+            .unwrap();
+            let mut text = diagnostic.message.clone();
+            for extra in [&diagnostic.hint, &diagnostic.details]
+                .into_iter()
+                .flatten()
+            {
+                text.push('\n');
+                text.push_str(extra);
+            }
+            json_string(&mut buf, &text);
+            buf.push_str(r#"},"locations":["#);
+            location(&mut buf, diagnostic.span);
+            buf.push(']');
+
+            if !diagnostic.labels.is_empty() {
+                buf.push_str(r#","relatedLocations":["#);
+                for (idx, label) in diagnostic.labels.iter().enumerate() {
+                    if idx > 0 {
+                        buf.push(',');
+                    }
+                    location(&mut buf, label.span);
+                    // add id and message into the location object
+                    buf.pop();
+                    write!(buf, r#","id":{idx},"message":{{"text":"#).unwrap();
+                    json_string(&mut buf, &label.message);
+                    buf.push_str("}}");
+                }
+                buf.push(']');
+            }
+
+            if !diagnostic.suggestions.is_empty() {
+                buf.push_str(r#","fixes":["#);
+                for (idx, suggestion) in diagnostic.suggestions.iter().enumerate() {
+                    if idx > 0 {
+                        buf.push(',');
+                    }
+                    buf.push_str(r#"{"description":{"text":"#);
+                    json_string(&mut buf, &suggestion.message);
+                    buf.push_str(r#"},"artifactChanges":[{"artifactLocation":{"uri":"#);
+                    json_string(&mut buf, file.uri);
+                    buf.push_str(r#"},"replacements":[{"deletedRegion":"#);
+                    sarif_region(&mut buf, file.source, suggestion.span);
+                    buf.push_str(r#","insertedContent":{"text":"#);
+                    json_string(&mut buf, &suggestion.replacement);
+                    buf.push_str("}}]}]}");
+                }
+                buf.push(']');
+            }
+            buf.push('}');
+        }
+    }
+    buf.push_str("]}]}");
+    buf
+}
+
+fn sarif_region(buf: &mut String, source: &str, span: Span) {
+    let position = |offset: u64| {
+        let offset = offset.min(source.len() as u64);
+        InflatedPos::from_offset(source.as_bytes(), offset)
+            .map_or((1, 1), |pos| (pos.line + 1, pos.utf16column + 1))
+    };
+    let (start_line, start_column) = position(span.start);
+    let (end_line, end_column) = position(span.end);
+    write!(
+        buf,
+        r#"{{"startLine":{start_line},"startColumn":{start_column},"#
+    )
+    .unwrap();
+    write!(buf, r#""endLine":{end_line},"endColumn":{end_column}}}"#).unwrap();
+}
+
+fn json_option(buf: &mut String, value: Option<&str>) {
+    match value {
+        Some(value) => json_string(buf, value),
+        None => buf.push_str("null"),
+    }
+}
+
+pub(crate) fn json_string(buf: &mut String, value: &str) {
+    buf.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => buf.push_str("\\\""),
+            '\\' => buf.push_str("\\\\"),
+            '\n' => buf.push_str("\\n"),
+            '\r' => buf.push_str("\\r"),
+            '\t' => buf.push_str("\\t"),
+            c if c.is_control() => write!(buf, "\\u{:04x}", c as u32).unwrap(),
+            c => buf.push(c),
+        }
+    }
+    buf.push('"');
+}
+
+#[cfg(test)]
+mod test {
+    use super::{render, render_json, render_sarif, Diagnostic, FileReport, Label, Suggestion};
+    use crate::position::Span;
+    use crate::tokenizer::Error;
+
+    fn diagnostic(source: &str, text: &str, hint: Option<&str>) -> Diagnostic {
+        let start = source.find(text).unwrap() as u64;
+        let mut err = Error::new(format!("Unexpected '{text}'")).with_span(Span {
+            start,
+            end: start + text.len() as u64,
+        });
+        err.hint = hint.map(|h| h.to_string());
+        err.into()
+    }
+
+    #[test]
+    fn pretty() {
+        let source = "select 1;\nselect User {\n  name from\n};";
+        let result = render(
+            source,
+            "query.edgeql",
+            &[diagnostic(source, "from", Some("remove it"))],
+        );
+        assert_eq!(
+            result.lines().collect::<Vec<_>>(),
+            vec![
+                "error: Unexpected 'from'",
+                "  --> query.edgeql:3:8",
+                "  |",
+                "3 |   name from",
+                "  |        ^^^^",
+                "  = hint: remove it",
+                "",
+            ]
+        );
+
+        let mut diagnostic = diagnostic(source, "User", None);
+        diagnostic.code = Some("E0002".into());
+        diagnostic.labels.push(Label {
+            span: Span { start: 0, end: 6 },
+            message: "first statement".into(),
+        });
+        diagnostic.suggestions.push(Suggestion {
+            message: "use a link".into(),
+            span: diagnostic.span,
+            replacement: ".friends".into(),
+        });
+        let result = render(source, "query.edgeql", &[diagnostic]);
+        assert_eq!(
+            result.lines().collect::<Vec<_>>(),
+            vec![
+                "error[E0002]: Unexpected 'User'",
+                "  --> query.edgeql:2:8",
+                "  |",
+                "2 | select User {",
+                "  |        ^^^^",
+                "  = note: first statement at query.edgeql:1:1",
+                "  = suggestion: use a link: `.friends`",
+                "",
+            ]
+        );
+    }
+
+    #[test]
+    fn json() {
+        let source = "select\n\"x\" ;";
+        let mut diagnostic = diagnostic(source, "\"x\"", None);
+        diagnostic.code = Some("E0001".into());
+        diagnostic.suggestions.push(Suggestion {
+            message: "remove the space".into(),
+            span: Span { start: 10, end: 11 },
+            replacement: "".into(),
+        });
+        let result = render_json(source, "a.edgeql", &[diagnostic]);
+        assert_eq!(
+            result,
+            r#"{"version":1,"diagnostics":[{"code":"E0001","severity":"error","#.to_owned()
+                + r#""message":"Unexpected '\"x\"'","file":"a.edgeql","spans":[{"#
+                + r#""start":{"line":2,"column":1,"offset":7},"#
+                + r#""end":{"line":2,"column":4,"offset":10},"label":null,"primary":true}],"#
+                + r#""hint":null,"details":null,"suggestions":[{"message":"remove the space","#
+                + r#""start":{"line":2,"column":4,"offset":10},"#
+                + r#""end":{"line":2,"column":5,"offset":11},"replacement":""}]}]}"#
+        );
+    }
+
+    #[test]
+    fn sarif() {
+        let source = "select 'ä' 1;";
+        let mut first = diagnostic(source, "1", Some("remove it"));
+        first.labels.push(Label {
+            span: Span { start: 7, end: 11 },
+            message: "string".into(),
+        });
+        first.suggestions.push(Suggestion {
+            message: "remove".into(),
+            span: first.span,
+            replacement: "".into(),
+        });
+        let mut second = diagnostic(source, ";", None);
+        second.code = Some("E0001".into());
+        let diagnostics = [first, second];
+        let result = render_sarif(
+            "edgeql-check",
+            &[FileReport {
+                uri: "queries/a.edgeql",
+                source,
+                diagnostics: &diagnostics,
+            }],
+        );
+        let region = r#""region":{"startLine":1,"startColumn":12,"endLine":1,"endColumn":13}"#;
+        let expected = [
+            r#"{"$schema":"https://json.schemastore.org/sarif-2.1.0.json","version":"2.1.0","#,
+            r#""runs":[{"tool":{"driver":{"name":"edgeql-check","#,
+            r#""rules":[{"id":"error"},{"id":"E0001"}]}},"columnKind":"utf16CodeUnits","#,
+            r#""results":[{"ruleId":"error","ruleIndex":0,"level":"error","#,
+            r#""message":{"text":"Unexpected '1'\nremove it"},"#,
+            r#""locations":[{"physicalLocation":{"artifactLocation":{"uri":"queries/a.edgeql"},"#,
+            region,
+            r#"}}],"relatedLocations":[{"physicalLocation":{"#,
+            r#""artifactLocation":{"uri":"queries/a.edgeql"},"#,
+            r#""region":{"startLine":1,"startColumn":8,"endLine":1,"endColumn":11}},"#,
+            r#""id":0,"message":{"text":"string"}}],"#,
+            r#""fixes":[{"description":{"text":"remove"},"artifactChanges":[{"#,
+            r#""artifactLocation":{"uri":"queries/a.edgeql"},"replacements":[{"deletedRegion":"#,
+            r#"{"startLine":1,"startColumn":12,"endLine":1,"endColumn":13},"#,
+            r#""insertedContent":{"text":""}}]}]}]},"#,
+            r#"{"ruleId":"E0001","ruleIndex":1,"level":"error","#,
+            r#""message":{"text":"Unexpected ';'"},"locations":[{"physicalLocation":{"#,
+            r#""artifactLocation":{"uri":"queries/a.edgeql"},"#,
+            r#""region":{"startLine":1,"startColumn":13,"endLine":1,"endColumn":14}}}]}]}]}"#,
+        ];
+        assert_eq!(result, expected.concat());
+    }
+}
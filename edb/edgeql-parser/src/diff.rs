@@ -0,0 +1,201 @@
+//! Token-level differences between two versions of a query
+//!
+//! [diff_tokens] compares the tokens (ignoring whitespace and comments) and
+//! returns the minimal edit script turning the old text into the new one.
+//! Each [Edit] covers a run of consecutive tokens and has spans in both
+//! texts, so it can be used to update an editor buffer or to highlight the
+//! changes of a query under review.
+use crate::position::Span;
+use crate::tokenizer::{Error, Kind, Token, Tokenizer};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditKind {
+    Insert,
+    Delete,
+    Replace,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Edit {
+    pub kind: EditKind,
+    /// Tokens removed from the old text, an empty span at the insertion
+    /// point for [EditKind::Insert]
+    pub old: Span,
+    /// Tokens added in the new text, an empty span at the position of the
+    /// removed tokens for [EditKind::Delete]
+    pub new: Span,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// Returns the edits turning tokens of `old` into tokens of `new`, fails if
+/// either text cannot be tokenized
+pub fn diff_tokens(old: &str, new: &str) -> Result<Vec<Edit>, Error> {
+    let old_tokens = Tokenizer::new(old).collect::<Result<Vec<_>, _>>()?;
+    let new_tokens = Tokenizer::new(new).collect::<Result<Vec<_>, _>>()?;
+    let key = |t: &Token| (t.kind, t.text.to_string());
+    let a: Vec<(Kind, String)> = old_tokens.iter().map(key).collect();
+    let b: Vec<(Kind, String)> = new_tokens.iter().map(key).collect();
+
+    // empty span right before the token at `idx`
+    let point = |tokens: &[Token], idx: usize, text: &str| {
+        let offset = tokens.get(idx).map_or(text.len() as u64, |t| t.span.start);
+        Span {
+            start: offset,
+            end: offset,
+        }
+    };
+    let span = |tokens: &[Token], start: usize, end: usize| {
+        tokens[start].span.extend(&tokens[end - 1].span)
+    };
+
+    let mut edits = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    let ops = shortest_script(&a, &b);
+    let mut ops = ops.iter().peekable();
+    while let Some(op) = ops.next() {
+        if *op == Op::Equal {
+            i += 1;
+            j += 1;
+            continue;
+        }
+        let (start_i, start_j) = (i, j);
+        let mut op = Some(op);
+        while let Some(current) = op {
+            match current {
+                Op::Delete => i += 1,
+                Op::Insert => j += 1,
+                Op::Equal => unreachable!(),
+            }
+            op = ops.next_if(|op| **op != Op::Equal);
+        }
+        let edit = match (i > start_i, j > start_j) {
+            (true, false) => Edit {
+                kind: EditKind::Delete,
+                old: span(&old_tokens, start_i, i),
+                new: point(&new_tokens, start_j, new),
+            },
+            (false, true) => Edit {
+                kind: EditKind::Insert,
+                old: point(&old_tokens, start_i, old),
+                new: span(&new_tokens, start_j, j),
+            },
+            _ => Edit {
+                kind: EditKind::Replace,
+                old: span(&old_tokens, start_i, i),
+                new: span(&new_tokens, start_j, j),
+            },
+        };
+        edits.push(edit);
+    }
+    Ok(edits)
+}
+
+/// Myers' difference algorithm, returns operations in order
+fn shortest_script<T: PartialEq>(a: &[T], b: &[T]) -> Vec<Op> {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let max = n + m;
+    let offset = max + 1;
+    let mut v = vec![0isize; 2 * max as usize + 3];
+    // `v` before each round, for backtracking
+    let mut trace = Vec::new();
+    'outer: for d in 0..=max {
+        trace.push(v.clone());
+        for k in (-d..=d).step_by(2) {
+            let idx = (k + offset) as usize;
+            let mut x = if k == -d || (k != d && v[idx - 1] < v[idx + 1]) {
+                v[idx + 1]
+            } else {
+                v[idx - 1] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[idx] = x;
+            if x >= n && y >= m {
+                break 'outer;
+            }
+        }
+    }
+
+    let mut ops = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for (d, v) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        let k = x - y;
+        let idx = (k + offset) as usize;
+        let prev_k = if k == -d || (k != d && v[idx - 1] < v[idx + 1]) {
+            k + 1
+        } else {
+            k - 1
+        };
+        let prev_x = v[(prev_k + offset) as usize];
+        let prev_y = prev_x - prev_k;
+        while x > prev_x && y > prev_y {
+            ops.push(Op::Equal);
+            x -= 1;
+            y -= 1;
+        }
+        if d > 0 {
+            ops.push(if x == prev_x { Op::Insert } else { Op::Delete });
+        }
+        x = prev_x;
+        y = prev_y;
+    }
+    ops.reverse();
+    ops
+}
+
+#[cfg(test)]
+mod test {
+    use super::{diff_tokens, EditKind};
+
+    fn edits(old: &str, new: &str) -> Vec<(EditKind, String, String)> {
+        diff_tokens(old, new)
+            .unwrap()
+            .into_iter()
+            .map(|e| {
+                (
+                    e.kind,
+                    old[e.old.start as usize..e.old.end as usize].to_string(),
+                    new[e.new.start as usize..e.new.end as usize].to_string(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn edits_with_spans() {
+        use EditKind::*;
+
+        assert!(edits("select  User { name }", "select User {\n  name # x\n}").is_empty());
+        assert_eq!(
+            edits(
+                "select User { name } filter .age > 10",
+                "select User { name, email } filter .age >= 18"
+            ),
+            [
+                (Insert, "".into(), ", email".into()),
+                (Replace, "> 10".into(), ">= 18".into()),
+            ]
+        );
+        assert_eq!(
+            edits("select User { name } limit 1", "select User"),
+            [(Delete, "{ name } limit 1".into(), "".into())]
+        );
+        assert_eq!(
+            edits("", "select 1"),
+            [(Insert, "".into(), "select 1".into())]
+        );
+        let insert = diff_tokens("select User", "select User { id }").unwrap();
+        assert_eq!((insert[0].old.start, insert[0].old.end), (11, 11));
+        assert!(diff_tokens("select 'x", "select 1").is_err());
+    }
+}
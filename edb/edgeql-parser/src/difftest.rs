@@ -0,0 +1,223 @@
+//! Differential testing against the grammar tests of the Python parser
+//!
+//! The syntax tests (`tests/test_edgeql_syntax.py`,
+//! `tests/test_schema_syntax.py`) are exported by `edb export-syntax-corpus`
+//! as a corpus of JSON lines, one case per line:
+//!
+//! ```json
+//! {"name": "test_edgeql_syntax_case_01", "start": "STARTBLOCK",
+//!  "source": "select 1;", "accept": true, "error": null}
+//! ```
+//!
+//! [replay] parses every case with the Rust parser and reports the cases
+//! it accepts or rejects differently. Note that some errors of the Python
+//! tests are raised when building the AST rather than by the grammar, such
+//! cases are expected to diverge as well.
+use std::fmt::Write;
+
+use crate::diagnostics::json_string;
+use crate::parser::{self, Spec, Terminal};
+use crate::soft_keywords::demote_soft_terminals;
+use crate::tokenizer::{Error, Tokenizer};
+
+/// Start tokens of the grammar that a case may use
+const START_TOKENS: &[&str] = &[
+    "STARTBLOCK",
+    "STARTEXTENSION",
+    "STARTFRAGMENT",
+    "STARTMIGRATION",
+    "STARTSDLDOCUMENT",
+];
+
+/// A test of the Python parser
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct Case {
+    pub name: String,
+    /// Name of the start token, e.g. `STARTBLOCK`
+    pub start: String,
+    pub source: String,
+    /// Whether the Python parser accepts the source
+    pub accept: bool,
+    /// Expected error (a regular expression) of rejected cases
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("corpus line {line}: {source}")]
+pub struct CorpusError {
+    pub line: usize,
+    #[source]
+    pub source: serde_json::Error,
+}
+
+/// A case the Rust parser disagrees on
+#[derive(Debug)]
+pub struct Divergence<'a> {
+    pub case: &'a Case,
+    /// Errors of the Rust parser, empty if it accepted a rejected case
+    pub errors: Vec<Error>,
+}
+
+/// Result of [replay]
+#[derive(Debug)]
+pub struct Report<'a> {
+    pub total: usize,
+    pub divergences: Vec<Divergence<'a>>,
+}
+
+/// Reads a corpus of JSON lines, blank lines are skipped
+pub fn read_corpus(text: &str) -> Result<Vec<Case>, CorpusError> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(idx, line)| {
+            serde_json::from_str(line).map_err(|source| CorpusError {
+                line: idx + 1,
+                source,
+            })
+        })
+        .collect()
+}
+
+/// Parses the source of the case with the Rust parser
+pub fn parse_case(spec: &Spec, case: &Case) -> Vec<Error> {
+    if !START_TOKENS.contains(&&case.start[..]) {
+        return vec![Error::new(format!("unknown start token {}", case.start))];
+    }
+    let mut terminals = vec![Terminal::from_start_name(&case.start)];
+    for token in Tokenizer::new(&case.source).validated_values().with_eof() {
+        match token {
+            Ok(token) => terminals.push(Terminal::from_token(token)),
+            Err(e) => return vec![e],
+        }
+    }
+    demote_soft_terminals(&mut terminals);
+    let ctx = parser::Context::new(spec);
+    let (_, errors) = parser::parse(&terminals, &ctx);
+    errors
+}
+
+/// Runs every case through `parse` (usually [parse_case]) and collects
+/// the ones where the result differs from the Python parser
+pub fn replay<'a>(cases: &'a [Case], mut parse: impl FnMut(&Case) -> Vec<Error>) -> Report<'a> {
+    let divergences = cases
+        .iter()
+        .filter_map(|case| {
+            let errors = parse(case);
+            (errors.is_empty() != case.accept).then_some(Divergence { case, errors })
+        })
+        .collect();
+    Report {
+        total: cases.len(),
+        divergences,
+    }
+}
+
+impl Report<'_> {
+    /// Renders the report as a JSON document:
+    ///
+    /// ```json
+    /// {"total": 2, "divergences": [
+    ///   {"name": "test_x", "start": "STARTBLOCK",
+    ///    "expected": "reject", "actual": "accept", "expectedError": "...",
+    ///    "errors": []}
+    /// ]}
+    /// ```
+    pub fn to_json(&self) -> String {
+        let mut buf = String::new();
+        write!(buf, r#"{{"total":{},"divergences":["#, self.total).unwrap();
+        for (idx, divergence) in self.divergences.iter().enumerate() {
+            if idx > 0 {
+                buf.push(',');
+            }
+            let case = divergence.case;
+            let (expected, actual) = if case.accept {
+                ("accept", "reject")
+            } else {
+                ("reject", "accept")
+            };
+            buf.push_str(r#"{"name":"#);
+            json_string(&mut buf, &case.name);
+            buf.push_str(r#","start":"#);
+            json_string(&mut buf, &case.start);
+            write!(buf, r#","expected":"{expected}","actual":"{actual}","#).unwrap();
+            buf.push_str(r#""expectedError":"#);
+            match &case.error {
+                Some(error) => json_string(&mut buf, error),
+                None => buf.push_str("null"),
+            }
+            buf.push_str(r#","errors":["#);
+            for (idx, error) in divergence.errors.iter().enumerate() {
+                if idx > 0 {
+                    buf.push(',');
+                }
+                buf.push_str(r#"{"message":"#);
+                json_string(&mut buf, &error.message);
+                write!(
+                    buf,
+                    r#","start":{},"end":{}}}"#,
+                    error.span.start, error.span.end
+                )
+                .unwrap();
+            }
+            buf.push_str("]}");
+        }
+        buf.push_str("]}");
+        buf
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{replay, Case};
+    use crate::position::Span;
+    use crate::tokenizer::Error;
+
+    fn case(name: &str, source: &str, accept: bool) -> Case {
+        Case {
+            name: name.into(),
+            start: "STARTBLOCK".into(),
+            source: source.into(),
+            accept,
+            error: (!accept).then(|| "Unexpected".into()),
+        }
+    }
+
+    #[test]
+    fn divergences() {
+        let cases = [
+            case("test_ok", "select 1", true),
+            case("test_fails", "select (", false),
+            case("test_accepted", "select 1 1", false),
+            case("test_rejected", "select \"\\q\"", true),
+        ];
+        // a fake parser rejecting unbalanced parentheses and `"`
+        let report = replay(&cases, |case| {
+            let bad = case.source.contains('(') || case.source.contains('"');
+            bad.then(|| Error::new("Unexpected \"q\"").with_span(Span { start: 7, end: 12 }))
+                .into_iter()
+                .collect()
+        });
+        assert_eq!(report.total, 4);
+        let names: Vec<_> = report
+            .divergences
+            .iter()
+            .map(|d| &d.case.name[..])
+            .collect();
+        assert_eq!(names, ["test_accepted", "test_rejected"]);
+        assert_eq!(
+            report.to_json(),
+            concat!(
+                r#"{"total":4,"divergences":["#,
+                r#"{"name":"test_accepted","start":"STARTBLOCK","#,
+                r#""expected":"reject","actual":"accept","#,
+                r#""expectedError":"Unexpected","errors":[]},"#,
+                r#"{"name":"test_rejected","start":"STARTBLOCK","#,
+                r#""expected":"accept","actual":"reject","expectedError":null,"#,
+                r#""errors":[{"message":"Unexpected \"q\"","start":7,"end":12}]}"#,
+                r#"]}"#,
+            )
+        );
+    }
+}
@@ -0,0 +1,525 @@
+//! Documentation of a schema in Markdown or HTML
+//!
+//! Every object and scalar type gets a section listing its bases,
+//! constraints, properties and links. Comments directly above a declaration
+//! and its `title`, `description` and `deprecated` annotations become the
+//! prose of the section, other annotations are listed by name. Comments and
+//! descriptions are expected to be Markdown already, so they are emitted as
+//! is into Markdown output (and escaped in HTML).
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+
+use crate::schema::qualify::qualify;
+use crate::schema::{
+    annotations, Annotated, AnnotationKind, DeclKind, Declaration, Document, TypeRef,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Markdown,
+    /// HTML fragment, to be embedded into a page
+    Html,
+}
+
+/// Renders documentation of all types of the document, `text` is the source
+/// the document was parsed from
+pub fn generate(text: &str, doc: &Document, format: Format) -> String {
+    let doc = qualify(doc);
+    let annotated = annotations(&doc);
+    let mut ctx = Context {
+        text,
+        annotations: annotated
+            .iter()
+            .map(|a| (a.decl as *const Declaration, a))
+            .collect(),
+        object_types: HashSet::new(),
+        blocks: Vec::new(),
+    };
+    collect_object_types(&doc.declarations, &mut ctx.object_types);
+    ctx.declarations(&doc.declarations, None);
+
+    let mut buf = String::new();
+    for block in &ctx.blocks {
+        match format {
+            Format::Markdown => block.markdown(&mut buf),
+            Format::Html => block.html(&mut buf),
+        }
+    }
+    buf
+}
+
+enum Inline {
+    Text(String),
+    Code(String),
+    Strong(String),
+    /// Markdown text from comments and annotations
+    Prose(String),
+}
+
+enum Block {
+    Heading(usize, Vec<Inline>),
+    Paragraph(Vec<Inline>),
+    List(Vec<Vec<Inline>>),
+}
+
+struct Context<'a> {
+    text: &'a str,
+    annotations: HashMap<*const Declaration, &'a Annotated<'a>>,
+    object_types: HashSet<&'a str>,
+    blocks: Vec<Block>,
+}
+
+fn collect_object_types<'a>(decls: &'a [Declaration], types: &mut HashSet<&'a str>) {
+    for decl in decls {
+        match (decl.kind, decl.name_text()) {
+            (DeclKind::Module, _) => collect_object_types(&decl.body, types),
+            (DeclKind::ObjectType, Some(name)) => {
+                types.insert(name);
+            }
+            _ => {}
+        }
+    }
+}
+
+impl<'a> Context<'a> {
+    fn declarations(&mut self, decls: &'a [Declaration], module: Option<&str>) {
+        let mut heading = module.map(|m| Block::Heading(1, text_code("Module ", m)));
+        for decl in decls {
+            let kind = match decl.kind {
+                DeclKind::Module => {
+                    if let Some(name) = decl.name_text() {
+                        self.declarations(&decl.body, Some(name));
+                    }
+                    continue;
+                }
+                _ if decl.verb.is_some() => continue,
+                DeclKind::ObjectType if decl.has_modifier("abstract") => "Abstract type ",
+                DeclKind::ObjectType => "Type ",
+                DeclKind::ScalarType if decl.has_modifier("abstract") => "Abstract scalar type ",
+                DeclKind::ScalarType => "Scalar type ",
+                _ => continue,
+            };
+            let Some(name) = decl.name_text() else {
+                continue;
+            };
+            self.blocks.extend(heading.take());
+            self.blocks.push(Block::Heading(2, text_code(kind, name)));
+            self.type_section(decl);
+        }
+    }
+
+    fn type_section(&mut self, decl: &'a Declaration) {
+        let annotated = self.annotations.get(&(decl as *const _)).copied();
+        self.prose(decl, annotated);
+        if !decl.extending.is_empty() {
+            let mut line = vec![Inline::Text("Extends ".into())];
+            for (idx, base) in decl.extending.iter().enumerate() {
+                if idx > 0 {
+                    line.push(Inline::Text(", ".into()));
+                }
+                line.push(Inline::Code(type_text(base)));
+            }
+            line.push(Inline::Text(".".into()));
+            self.blocks.push(Block::Paragraph(line));
+        }
+        let constraints = self.constraints(decl);
+        if !constraints.is_empty() {
+            self.blocks
+                .push(Block::Paragraph(vec![Inline::Text("Constraints:".into())]));
+            self.blocks.push(Block::List(
+                constraints
+                    .into_iter()
+                    .map(|c| vec![Inline::Code(c)])
+                    .collect(),
+            ));
+        }
+
+        let (links, properties): (Vec<_>, Vec<_>) = decl
+            .body
+            .iter()
+            .filter(|d| d.is_pointer() && d.verb.is_none() && d.name.is_some())
+            .partition(|d| self.is_link(d));
+        for (title, pointers) in [("Properties", properties), ("Links", links)] {
+            if pointers.is_empty() {
+                continue;
+            }
+            self.blocks
+                .push(Block::Heading(3, vec![Inline::Text(title.into())]));
+            let items = pointers.into_iter().map(|p| self.pointer(p)).collect();
+            self.blocks.push(Block::List(items));
+        }
+    }
+
+    fn is_link(&self, ptr: &Declaration) -> bool {
+        match ptr.kind {
+            DeclKind::Link => true,
+            DeclKind::Property => false,
+            _ => ptr
+                .target
+                .iter()
+                .any(|t| self.object_types.contains(&t.name.text[..])),
+        }
+    }
+
+    /// Paragraphs of the comment, title, description and other annotations
+    fn prose(&mut self, decl: &Declaration, annotated: Option<&Annotated>) {
+        let title = annotated.and_then(|a| a.title());
+        if let Some(title) = title {
+            self.blocks
+                .push(Block::Paragraph(vec![Inline::Strong(title.into())]));
+        }
+        if let Some(comment) = decl.doc_comment(self.text) {
+            self.blocks
+                .push(Block::Paragraph(vec![Inline::Prose(comment)]));
+        }
+        let Some(annotated) = annotated else {
+            return;
+        };
+        if let Some(description) = annotated.description() {
+            self.blocks
+                .push(Block::Paragraph(vec![Inline::Prose(description.into())]));
+        }
+        for annotation in &annotated.annotations {
+            let label = match annotation.kind {
+                AnnotationKind::Title | AnnotationKind::Description => continue,
+                AnnotationKind::Deprecated => "Deprecated".into(),
+                AnnotationKind::Custom => annotation.name.clone(),
+            };
+            let value = match &annotation.value {
+                Some(value) => Inline::Prose(value.clone()),
+                None => Inline::Code(self.source(annotation.expr.span).into()),
+            };
+            self.blocks.push(Block::Paragraph(vec![
+                Inline::Strong(format!("{label}:")),
+                Inline::Text(" ".into()),
+                value,
+            ]));
+        }
+    }
+
+    fn pointer(&self, ptr: &Declaration) -> Vec<Inline> {
+        let mut line = vec![Inline::Code(ptr.name_text().unwrap_or_default().into())];
+        let mut details = Vec::new();
+        if !ptr.target.is_empty() {
+            let target = ptr.target.iter().map(type_text).collect::<Vec<_>>();
+            details.push(Inline::Code(target.join(" | ")));
+        }
+        if ptr.has_modifier("required") {
+            details.push(Inline::Text("required".into()));
+        }
+        if ptr.is_multi() {
+            details.push(Inline::Text("multi".into()));
+        }
+        if ptr.expr.is_some() {
+            details.push(Inline::Text("computed".into()));
+        }
+        if !details.is_empty() {
+            line.push(Inline::Text(" (".into()));
+            for (idx, detail) in details.into_iter().enumerate() {
+                if idx > 0 {
+                    line.push(Inline::Text(", ".into()));
+                }
+                line.push(detail);
+            }
+            line.push(Inline::Text(")".into()));
+        }
+
+        let annotated = self.annotations.get(&(ptr as *const _)).copied();
+        let description = annotated
+            .and_then(|a| a.description().or(a.title()))
+            .map(String::from)
+            .or_else(|| ptr.doc_comment(self.text));
+        if let Some(description) = description {
+            line.push(Inline::Text(": ".into()));
+            line.push(Inline::Prose(description));
+        }
+        if annotated.is_some_and(|a| a.get(AnnotationKind::Deprecated).is_some()) {
+            line.push(Inline::Text(" ".into()));
+            line.push(Inline::Strong("Deprecated.".into()));
+        }
+        let constraints = self.constraints(ptr);
+        if !constraints.is_empty() {
+            line.push(Inline::Text(" Constraints: ".into()));
+            for (idx, constraint) in constraints.into_iter().enumerate() {
+                if idx > 0 {
+                    line.push(Inline::Text(", ".into()));
+                }
+                line.push(Inline::Code(constraint));
+            }
+            line.push(Inline::Text(".".into()));
+        }
+        line
+    }
+
+    /// Constraints as written, e.g. `max_len_value(100) on (.name)`
+    fn constraints(&self, decl: &Declaration) -> Vec<String> {
+        let mut result = Vec::new();
+        for constraint in &decl.body {
+            if constraint.kind != DeclKind::Constraint || constraint.verb.is_some() {
+                continue;
+            }
+            let Some(name) = constraint.name_text() else {
+                continue;
+            };
+            let mut text = String::new();
+            if constraint.has_modifier("delegated") {
+                text.push_str("delegated ");
+            }
+            text.push_str(name);
+            if let Some(args) = &constraint.args {
+                text.push_str(self.source(args.span));
+            }
+            for clause in &constraint.clauses {
+                write!(
+                    text,
+                    " {} {}",
+                    clause.keyword,
+                    self.source(clause.expr.span)
+                )
+                .unwrap();
+            }
+            result.push(text);
+        }
+        result
+    }
+
+    fn source(&self, span: crate::position::Span) -> &'a str {
+        &self.text[span.start as usize..span.end as usize]
+    }
+}
+
+fn text_code(text: &str, code: &str) -> Vec<Inline> {
+    vec![Inline::Text(text.into()), Inline::Code(code.into())]
+}
+
+fn type_text(ty: &TypeRef) -> String {
+    if ty.args.is_empty() {
+        return ty.name.text.clone();
+    }
+    let args = ty.args.iter().map(type_text).collect::<Vec<_>>();
+    format!("{}<{}>", ty.name.text, args.join(", "))
+}
+
+impl Block {
+    fn markdown(&self, buf: &mut String) {
+        match self {
+            Block::Heading(level, inlines) => {
+                buf.extend(std::iter::repeat_n('#', *level));
+                buf.push(' ');
+                markdown_inlines(inlines, buf);
+                buf.push('\n');
+            }
+            Block::Paragraph(inlines) => {
+                markdown_inlines(inlines, buf);
+                buf.push('\n');
+            }
+            Block::List(items) => {
+                for item in items {
+                    buf.push_str("- ");
+                    let start = buf.len();
+                    markdown_inlines(item, buf);
+                    // continuation lines of multi-line prose
+                    let item = buf.split_off(start).replace('\n', "\n  ");
+                    buf.push_str(&item);
+                    buf.push('\n');
+                }
+            }
+        }
+        buf.push('\n');
+    }
+
+    fn html(&self, buf: &mut String) {
+        match self {
+            Block::Heading(level, inlines) => {
+                write!(buf, "<h{level}>").unwrap();
+                html_inlines(inlines, buf);
+                writeln!(buf, "</h{level}>").unwrap();
+            }
+            Block::Paragraph(inlines) => {
+                buf.push_str("<p>");
+                html_inlines(inlines, buf);
+                buf.push_str("</p>\n");
+            }
+            Block::List(items) => {
+                buf.push_str("<ul>\n");
+                for item in items {
+                    buf.push_str("<li>");
+                    html_inlines(item, buf);
+                    buf.push_str("</li>\n");
+                }
+                buf.push_str("</ul>\n");
+            }
+        }
+    }
+}
+
+fn markdown_inlines(inlines: &[Inline], buf: &mut String) {
+    for inline in inlines {
+        match inline {
+            Inline::Text(text) => markdown_escape(text, buf),
+            Inline::Code(code) => {
+                // a longer run of backticks than any one inside of the code
+                let mut fence = String::from("`");
+                while code.contains(&fence[..]) {
+                    fence.push('`');
+                }
+                let pad = if fence.len() > 1 { " " } else { "" };
+                write!(buf, "{fence}{pad}{code}{pad}{fence}").unwrap();
+            }
+            Inline::Strong(text) => {
+                buf.push_str("**");
+                markdown_escape(text, buf);
+                buf.push_str("**");
+            }
+            Inline::Prose(text) => buf.push_str(text.trim()),
+        }
+    }
+}
+
+fn markdown_escape(text: &str, buf: &mut String) {
+    for c in text.chars() {
+        if matches!(c, '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#') {
+            buf.push('\\');
+        }
+        buf.push(c);
+    }
+}
+
+fn html_inlines(inlines: &[Inline], buf: &mut String) {
+    for inline in inlines {
+        match inline {
+            Inline::Text(text) => html_escape(text, buf),
+            Inline::Code(code) => {
+                buf.push_str("<code>");
+                html_escape(code, buf);
+                buf.push_str("</code>");
+            }
+            Inline::Strong(text) => {
+                buf.push_str("<strong>");
+                html_escape(text, buf);
+                buf.push_str("</strong>");
+            }
+            Inline::Prose(text) => {
+                for (idx, line) in text.trim().lines().enumerate() {
+                    if idx > 0 {
+                        buf.push_str("<br>\n");
+                    }
+                    html_escape(line, buf);
+                }
+            }
+        }
+    }
+}
+
+fn html_escape(text: &str, buf: &mut String) {
+    for c in text.chars() {
+        match c {
+            '&' => buf.push_str("&amp;"),
+            '<' => buf.push_str("&lt;"),
+            '>' => buf.push_str("&gt;"),
+            '"' => buf.push_str("&quot;"),
+            c => buf.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{generate, Format};
+    use crate::schema::parse;
+
+    const SCHEMA: &str = r#"
+        module default {
+            # Anything with a name
+            abstract type Named {
+                required name: str {
+                    constraint max_len_value(100);
+                };
+            }
+
+            type User extending Named {
+                annotation title := 'User account';
+                annotation description := 'A person who can *log in*.';
+                annotation std::deprecated := 'Use `Account`';
+                # Users this one follows
+                multi follows: User;
+                link avatar -> Image;
+                property score := count(.follows);
+                constraint exclusive on ((.name, .avatar));
+            }
+
+            type Image;
+
+            scalar type Color extending enum<Red, Green>;
+        }
+    "#;
+
+    #[test]
+    fn markdown() {
+        let doc = parse(SCHEMA).unwrap();
+        assert_eq!(
+            generate(SCHEMA, &doc, Format::Markdown),
+            "\
+# Module `default`
+
+## Abstract type `default::Named`
+
+Anything with a name
+
+### Properties
+
+- `name` (`str`, required) Constraints: `max_len_value(100)`.
+
+## Type `default::User`
+
+**User account**
+
+A person who can *log in*.
+
+**Deprecated:** Use `Account`
+
+Extends `default::Named`.
+
+Constraints:
+
+- `exclusive on ((.name, .avatar))`
+
+### Properties
+
+- `score` (computed)
+
+### Links
+
+- `follows` (`default::User`, multi): Users this one follows
+- `avatar` (`default::Image`)
+
+## Type `default::Image`
+
+## Scalar type `default::Color`
+
+Extends `enum<Red, Green>`.
+
+"
+        );
+    }
+
+    #[test]
+    fn html() {
+        let doc = parse("type A {\n  # 1 < 2\n  # & more\n  required x: str;\n}").unwrap();
+        assert_eq!(
+            generate(
+                "type A {\n  # 1 < 2\n  # & more\n  required x: str;\n}",
+                &doc,
+                Format::Html
+            ),
+            "\
+<h2>Type <code>A</code></h2>
+<h3>Properties</h3>
+<ul>
+<li><code>x</code> (<code>str</code>, required): 1 &lt; 2<br>
+&amp; more</li>
+</ul>
+"
+        );
+    }
+}
@@ -0,0 +1,280 @@
+//! Syntactic equivalence of queries
+//!
+//! Two queries are [equivalent] if they are the same after normalization:
+//!
+//! * whitespace, comments and trailing semicolons are ignored
+//! * keywords are compared case-insensitively, names with and without
+//!   backticks are the same, literals are compared by value (`'a'` is `"a"`)
+//! * redundant parentheses are removed: `((a + b))` is `(a + b)` and `(a)`
+//!   is `a` where it is not a function call or a required parenthesis
+//! * aliases bound by `with`, `for` and `select x := ...` are renamed in the
+//!   order they are bound
+//!
+//! The check is conservative: queries reported equivalent always are, but
+//! some equivalent queries are not detected. In particular, an alias is not
+//! renamed if its name is also used in a place that may refer to something
+//! else (a shape element, a function call).
+use std::collections::HashMap;
+
+use crate::keywords::Keyword;
+use crate::schema::is_name_like;
+use crate::tokenizer::{Error, Kind, Token, Tokenizer, Value};
+
+/// Keywords after which parentheses around a single token can be dropped
+const OPTIONAL_PAREN_AFTER: &[&str] = &[
+    "and", "by", "distinct", "else", "exists", "filter", "if", "ilike", "in", "like", "limit",
+    "not", "offset", "or", "select", "union",
+];
+
+/// Keywords starting the statement after a `with` block
+const STATEMENTS: &[&str] = &["delete", "for", "group", "insert", "select", "update"];
+
+#[derive(Debug, PartialEq)]
+enum Item {
+    Kind(Kind),
+    Name(String),
+    Alias(usize),
+    Value(Kind, Value),
+}
+
+/// Returns true if the two queries are the same after normalization, fails
+/// if either cannot be tokenized
+pub fn equivalent(a: &str, b: &str) -> Result<bool, Error> {
+    Ok(normalize(a)? == normalize(b)?)
+}
+
+fn normalize(text: &str) -> Result<Vec<Item>, Error> {
+    let mut tokens = Tokenizer::new(text)
+        .validated_values()
+        .collect::<Result<Vec<_>, _>>()?;
+    while tokens.last().is_some_and(|t| t.kind == Kind::Semicolon) {
+        tokens.pop();
+    }
+    let tokens = remove_parens(tokens);
+    let aliases = aliases(&tokens);
+
+    let items = tokens
+        .iter()
+        .enumerate()
+        .map(|(idx, tok)| match tok.kind {
+            _ if is_name_like(Some(tok.kind)) => match (aliases.get(&idx), tok.kind) {
+                (Some(&alias), _) => Item::Alias(alias),
+                (None, Kind::Keyword(Keyword(name))) => Item::Name(name.into()),
+                (None, _) => Item::Name(name_text(tok).into()),
+            },
+            Kind::Keyword(_) => Item::Kind(tok.kind),
+            _ => match &tok.value {
+                Some(value) => Item::Value(tok.kind, value.clone()),
+                None => Item::Kind(tok.kind),
+            },
+        })
+        .collect();
+    Ok(items)
+}
+
+fn name_text<'t>(tok: &'t Token) -> &'t str {
+    match &tok.value {
+        Some(Value::String(s)) => s,
+        _ => &tok.text,
+    }
+}
+
+fn remove_parens(tokens: Vec<Token>) -> Vec<Token> {
+    let mut closing = vec![None; tokens.len()];
+    let mut stack = Vec::new();
+    for (idx, tok) in tokens.iter().enumerate() {
+        match tok.kind {
+            Kind::OpenParen => stack.push(idx),
+            Kind::CloseParen => {
+                if let Some(open) = stack.pop() {
+                    closing[open] = Some(idx);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut removed = vec![false; tokens.len()];
+    for open in 0..tokens.len() {
+        let Some(close) = closing[open] else {
+            continue;
+        };
+        let prev = (0..open).rev().find(|&i| !removed[i]).map(|i| &tokens[i]);
+        let is_call = prev.is_some_and(|p| p.kind == Kind::Ident);
+        let redundant = if closing[open + 1] == Some(close - 1) {
+            // `((...))`
+            !is_call
+        } else if close == open + 2 {
+            // `(a)`
+            let optional = match prev.map(|p| p.kind) {
+                None => true,
+                Some(Kind::Ident | Kind::Dot | Kind::Greater) => false,
+                Some(Kind::Keyword(Keyword(kw))) => OPTIONAL_PAREN_AFTER.contains(&kw),
+                Some(_) => true,
+            };
+            optional && is_atom(&tokens[open + 1])
+        } else {
+            false
+        };
+        if redundant {
+            removed[open] = true;
+            removed[close] = true;
+        }
+    }
+    tokens
+        .into_iter()
+        .zip(removed)
+        .filter(|(_, removed)| !removed)
+        .map(|(tok, _)| tok)
+        .collect()
+}
+
+fn is_atom(tok: &Token) -> bool {
+    match tok.kind {
+        Kind::Ident
+        | Kind::Parameter
+        | Kind::Str
+        | Kind::BinStr
+        | Kind::IntConst
+        | Kind::FloatConst
+        | Kind::BigIntConst
+        | Kind::DecimalConst => true,
+        Kind::Keyword(kw) => kw.is_bool(),
+        _ => false,
+    }
+}
+
+/// Returns canonical alias numbers by index of the token
+fn aliases(tokens: &[Token]) -> HashMap<usize, usize> {
+    let kind = |idx: usize| tokens.get(idx).map(|t| t.kind);
+    let is_kw =
+        |idx: usize, kw: &str| matches!(kind(idx), Some(Kind::Keyword(Keyword(k))) if k == kw);
+
+    // names in the order they are bound
+    let mut bound: Vec<&str> = Vec::new();
+    let mut bindings = Vec::new();
+    for idx in 0..tokens.len() {
+        if !is_name_like(kind(idx)) || idx == 0 {
+            continue;
+        }
+        let is_binding = if kind(idx + 1) == Some(Kind::Assign) {
+            STATEMENTS.iter().any(|s| is_kw(idx - 1, s))
+                || is_kw(idx - 1, "with")
+                || (kind(idx - 1) == Some(Kind::Comma) && in_with_block(tokens, idx - 1))
+        } else {
+            is_kw(idx - 1, "for") && is_kw(idx + 1, "in")
+        };
+        if is_binding {
+            let name = name_text(&tokens[idx]);
+            if !bound.contains(&name) {
+                bound.push(name);
+            }
+            bindings.push(idx);
+        }
+    }
+
+    let mut result = HashMap::new();
+    for (alias, name) in bound.iter().enumerate() {
+        let mut uses = Vec::new();
+        let mut ambiguous = false;
+        for (idx, tok) in tokens.iter().enumerate() {
+            if !is_name_like(Some(tok.kind)) || name_text(tok) != *name {
+                continue;
+            }
+            if bindings.contains(&idx) {
+                uses.push(idx);
+                continue;
+            }
+            let prev = idx.checked_sub(1).and_then(kind);
+            match (prev, kind(idx + 1)) {
+                // path steps and module names
+                (Some(Kind::Dot | Kind::BackwardLink | Kind::OptionalLink | Kind::At), _)
+                | (Some(Kind::Namespace), _)
+                | (_, Some(Kind::Namespace)) => {}
+                // shape elements, calls, types
+                (Some(Kind::OpenBrace | Kind::Comma | Kind::Less), _)
+                | (_, Some(Kind::OpenParen | Kind::Colon | Kind::Assign)) => ambiguous = true,
+                _ => uses.push(idx),
+            }
+        }
+        if !ambiguous {
+            result.extend(uses.into_iter().map(|idx| (idx, alias)));
+        }
+    }
+    result
+}
+
+/// Whether the comma at `idx` separates the bindings of a `with` block
+pub(crate) fn in_with_block(tokens: &[Token], idx: usize) -> bool {
+    let mut depth = 0;
+    for tok in tokens[..idx].iter().rev() {
+        match tok.kind {
+            Kind::CloseParen | Kind::CloseBracket | Kind::CloseBrace => depth += 1,
+            Kind::OpenParen | Kind::OpenBracket | Kind::OpenBrace if depth == 0 => return false,
+            Kind::OpenParen | Kind::OpenBracket | Kind::OpenBrace => depth -= 1,
+            Kind::Keyword(Keyword(kw)) if depth == 0 && STATEMENTS.contains(&kw) => return false,
+            Kind::Keyword(Keyword("with")) if depth == 0 => return true,
+            _ => {}
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod test {
+    use super::equivalent;
+
+    fn eq(a: &str, b: &str) -> bool {
+        equivalent(a, b).unwrap()
+    }
+
+    #[test]
+    fn formatting() {
+        assert!(eq(
+            "SELECT User { name } FILTER .name = 'x';",
+            "select User {\n  # comment\n  name\n}\nfilter .`name` = \"x\""
+        ));
+        assert!(eq("select 1.0", "select 1.00"));
+        assert!(!eq("select User", "select user"));
+        assert!(!eq("select 1", "select 2"));
+        assert!(equivalent("select 'oops", "select 1").is_err());
+    }
+
+    #[test]
+    fn parens() {
+        assert!(eq("select ((a + b)) * c", "select (a + b) * c"));
+        assert!(eq("select (a) + (1)", "select a + 1"));
+        assert!(eq("select (((x)))", "select x"));
+        assert!(!eq("select (a + b) * c", "select a + b * c"));
+        // calls and tuples
+        assert!(!eq("select f((a, b))", "select f(a, b)"));
+        assert!(!eq("select (a,)", "select a"));
+        assert!(eq("select f((a))", "select f(a)"));
+    }
+
+    #[test]
+    fn aliases() {
+        assert!(eq(
+            "with a := 1, b := (select User) select b filter .x = a",
+            "with x := 1, y := (select User) select y filter .x = x"
+        ));
+        assert!(eq(
+            "for x in {1, 2} union (select x)",
+            "for n in {1, 2} union (select n)"
+        ));
+        assert!(eq(
+            "select u := User filter u.name = 'x'",
+            "select user := User filter user.name = 'x'"
+        ));
+        // different order of use
+        assert!(!eq(
+            "with a := 1, b := 2 select a - b",
+            "with a := 1, b := 2 select b - a"
+        ));
+        // `x` and `y` are also properties of the shape
+        assert!(!eq(
+            "with x := 1 select User { x }",
+            "with y := 1 select User { y }"
+        ));
+    }
+}
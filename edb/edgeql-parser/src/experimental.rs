@@ -0,0 +1,102 @@
+//! Sandbox for syntax proposals
+//!
+//! Proposed syntax is implemented behind cargo features which are not enabled
+//! by default, so the stable grammar is not affected until a proposal is
+//! accepted. To add a proposal:
+//!
+//! 1. add a feature to `Cargo.toml` (not to `default`),
+//! 2. list the proposal in [PROPOSALS],
+//! 3. gate its lexing rules in [peek_token] and any other code (AST, value
+//!    decoding) with `#[cfg(feature = "...")]`.
+//!
+//! When a proposal is accepted, its rules move to the stable tokenizer and the
+//! feature is removed.
+use crate::tokenizer::{Error, Kind, Tokenizer};
+
+/// A syntax proposal implemented behind a cargo feature
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Proposal {
+    pub name: &'static str,
+    /// Cargo feature enabling the proposal
+    pub feature: &'static str,
+    /// Whether the feature is enabled in this build
+    pub enabled: bool,
+    pub description: &'static str,
+}
+
+pub const PROPOSALS: &[Proposal] = &[
+    Proposal {
+        name: "heredoc-strings",
+        feature: "future-syntax",
+        enabled: cfg!(feature = "future-syntax"),
+        description: "Raw multi-line strings quoted by `\"\"\"` or `'''` \
+                      with the indentation stripped",
+    },
+    Proposal {
+        name: "role-grants",
+        feature: "future-syntax",
+        enabled: cfg!(feature = "future-syntax"),
+        description: "`grant <permission> to <role>` and \
+                      `revoke <permission> from <role>` statements",
+    },
+];
+
+/// Proposals enabled in this build
+pub fn enabled() -> impl Iterator<Item = &'static Proposal> {
+    PROPOSALS.iter().filter(|p| p.enabled)
+}
+
+/// Lexing rules of the enabled proposals
+///
+/// Tried by the tokenizer before the stable rules, `tail` is the rest of the
+/// input starting at the token. Returns `None` if no proposal applies, which
+/// is always the case if none is enabled.
+#[cfg_attr(not(feature = "future-syntax"), allow(unused_variables))]
+pub(crate) fn peek_token(
+    tokenizer: &Tokenizer,
+    tail: &str,
+) -> Option<Result<(Kind, usize), Error>> {
+    #[cfg(feature = "future-syntax")]
+    for delim in ["\"\"\"", "'''"] {
+        if tail.starts_with(delim) {
+            return Some(tokenizer.parse_heredoc(delim));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::{enabled, PROPOSALS};
+    use crate::tokenizer::Tokenizer;
+
+    fn tokens(text: &str) -> Vec<String> {
+        Tokenizer::new(text)
+            .map(|t| t.unwrap().text.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn proposals() {
+        for proposal in PROPOSALS {
+            assert!(!proposal.feature.is_empty());
+        }
+        if cfg!(not(feature = "future-syntax")) {
+            assert_eq!(enabled().count(), 0);
+        }
+    }
+
+    #[test]
+    fn heredoc() {
+        let text = r#"select """a""" ++ '''b'''"#;
+        if cfg!(feature = "future-syntax") {
+            assert_eq!(tokens(text), ["select", r#""""a""""#, "++", "'''b'''"]);
+        } else {
+            // stable grammar: adjacent empty and non-empty strings
+            assert_eq!(
+                tokens(text),
+                ["select", r#""""#, r#""a""#, r#""""#, "++", "''", "'b'", "''"]
+            );
+        }
+    }
+}
@@ -0,0 +1,215 @@
+//! Formatter of EdgeQL queries and SDL files
+//!
+//! Formatting is conservative and works on tokens, so the output always
+//! tokenizes the same as the input (except for the case of keywords):
+//!
+//! * reserved keywords are lowercased
+//! * lines are indented according to nesting of brackets
+//! * runs of whitespace are collapsed into a single space, and the space
+//!   inside of parenthesis and square brackets, and before commas and
+//!   semicolons is removed; commas are followed by a space
+//! * trailing whitespace and repeated blank lines are removed
+//!
+//! Line breaks and comments of the input are kept.
+use crate::tokenizer::{Error, Kind, Tokenizer};
+
+/// Number of spaces per indentation level
+pub const INDENT: usize = 4;
+
+/// Formats the text, fails if the text cannot be tokenized
+pub fn format(text: &str) -> Result<String, Error> {
+    let mut tokens = Vec::new();
+    for token in Tokenizer::new(text) {
+        tokens.push(token?);
+    }
+
+    let mut out = Writer {
+        buf: String::new(),
+        indents: Vec::new(),
+    };
+    let mut prev: Option<Kind> = None;
+    let mut prev_end = 0;
+    for (idx, token) in tokens.iter().enumerate() {
+        let gap = &text[prev_end..token.span.start as usize];
+        let closers = tokens[idx..]
+            .iter()
+            .take_while(|t| is_close(t.kind))
+            .count();
+        let spaced = !gap.is_empty() || prev == Some(Kind::Comma);
+        if !out.gap(gap, closers) && spaced && prev.is_some() {
+            let tight = matches!(prev, Some(Kind::OpenParen | Kind::OpenBracket))
+                || matches!(
+                    token.kind,
+                    Kind::Comma | Kind::Semicolon | Kind::CloseParen | Kind::CloseBracket
+                );
+            if !tight {
+                out.buf.push(' ');
+            }
+        }
+
+        match token.kind {
+            Kind::Keyword(kw) if kw.is_reserved() => {
+                out.buf.push_str(&token.text.to_lowercase());
+            }
+            _ => out.buf.push_str(&token.text),
+        }
+        if is_open(token.kind) {
+            // a line opening several brackets adds one level only
+            let level = out.line_indent() + 1;
+            out.indents.push(level);
+        } else if is_close(token.kind) {
+            out.indents.pop();
+        }
+        prev = Some(token.kind);
+        prev_end = token.span.end as usize;
+    }
+    out.gap(&text[prev_end..], 0);
+    out.buf.truncate(out.buf.trim_end().len());
+    if !out.buf.is_empty() {
+        out.buf.push('\n');
+    }
+    Ok(out.buf)
+}
+
+struct Writer {
+    buf: String,
+    /// Indentation levels of unclosed brackets
+    indents: Vec<usize>,
+}
+
+impl Writer {
+    fn current_indent(&self) -> usize {
+        self.indents.last().copied().unwrap_or(0)
+    }
+
+    /// Indentation of the line being written
+    fn line_indent(&self) -> usize {
+        let line = &self.buf[self.buf.rfind('\n').map_or(0, |i| i + 1)..];
+        (line.len() - line.trim_start_matches(' ').len()) / INDENT
+    }
+
+    fn trim_end(&mut self) {
+        let len = self.buf.trim_end_matches([' ', '\t']).len();
+        self.buf.truncate(len);
+    }
+
+    fn new_line(&mut self, blank: bool, indent: usize) {
+        self.trim_end();
+        if self.buf.is_empty() {
+            return;
+        }
+        self.buf.push('\n');
+        if blank {
+            self.buf.push('\n');
+        }
+        self.buf.extend(std::iter::repeat_n(' ', indent * INDENT));
+    }
+
+    /// Writes comments and line breaks of the whitespace between tokens,
+    /// the next token starts with `closers` closing brackets. Returns true if
+    /// the next token starts a new line.
+    fn gap(&mut self, gap: &str, closers: usize) -> bool {
+        let mut lines = gap.split('\n').peekable();
+        let trailing = lines.next().unwrap_or("").trim();
+        if !trailing.is_empty() {
+            if !self.buf.is_empty() {
+                self.buf.push(' ');
+            }
+            self.buf.push_str(trailing);
+        }
+        let mut new_line = false;
+        let mut blank = false;
+        while let Some(line) = lines.next() {
+            new_line = true;
+            let line = line.trim();
+            // the last one is the start of the line of the next token
+            if line.is_empty() && lines.peek().is_none() {
+                break;
+            }
+            if line.is_empty() {
+                blank = true;
+                continue;
+            }
+            // comments are indented as the contents of the bracket
+            self.new_line(blank, self.current_indent());
+            self.buf.push_str(line);
+            blank = false;
+        }
+        if new_line {
+            let depth = self.indents.len().saturating_sub(closers);
+            let indent = depth.checked_sub(1).map_or(0, |level| self.indents[level]);
+            self.new_line(blank, indent);
+        }
+        new_line
+    }
+}
+
+fn is_open(kind: Kind) -> bool {
+    matches!(kind, Kind::OpenParen | Kind::OpenBracket | Kind::OpenBrace)
+}
+
+fn is_close(kind: Kind) -> bool {
+    matches!(
+        kind,
+        Kind::CloseParen | Kind::CloseBracket | Kind::CloseBrace
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::format;
+
+    fn check(input: &str, expected: &str) {
+        let result = format(input).unwrap();
+        assert_eq!(result, expected);
+        assert_eq!(format(&result).unwrap(), result, "not idempotent");
+    }
+
+    #[test]
+    fn indent() {
+        check(
+            "SELECT User {\nname,\n  friends: {\n name\n}  ,\n} FILTER .name = 'x'  ;",
+            "select User {\n    name,\n    friends: {\n        name\n    },\n} filter .name = 'x';\n",
+        );
+        check(
+            "select (\n  1, [\n2,\n  3]\n)",
+            "select (\n    1, [\n        2,\n        3]\n)\n",
+        );
+        // several brackets opened on a single line add one level
+        check(
+            "select { x := ({\n1\n}) }",
+            "select { x := ({\n    1\n}) }\n",
+        );
+    }
+
+    #[test]
+    fn comments() {
+        check(
+            "# header\n\n\n\nselect User {   # trailing\n  # inner\n  name  # name\n  # end\n};\n  # footer\n\n",
+            "# header\n\nselect User { # trailing\n    # inner\n    name # name\n    # end\n};\n# footer\n",
+        );
+    }
+
+    #[test]
+    fn spacing() {
+        check(
+            "select  ( 1 ,2 )  ++ [ 'a' ,  \"b\"  ]\t;",
+            "select (1, 2) ++ ['a', \"b\"];\n",
+        );
+        // tokens without whitespace between them are kept together
+        check("select<str>$x++'a{ }'", "select<str>$x++'a{ }'\n");
+        // unreserved keywords may be identifiers, their case is kept
+        check("SELECT Type", "select Type\n");
+    }
+
+    #[test]
+    fn multiline_string() {
+        check("select {\n'a\n  b'\n}", "select {\n    'a\n  b'\n}\n");
+    }
+
+    #[test]
+    fn error() {
+        assert!(format("select 'a").is_err());
+        assert_eq!(format("").unwrap(), "");
+    }
+}
@@ -0,0 +1,369 @@
+//! Validation of function calls against known function signatures
+//!
+//! The [FunctionCatalog] trait is implemented by tools that know functions of
+//! the schema, [SchemaCatalog] implements it for an SDL document.
+use std::collections::HashMap;
+
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::position::Span;
+use crate::schema::qualify::qualify;
+use crate::schema::{token_name, DeclKind, Declaration, Document};
+use crate::signature_help::{function_name_start, read_arguments, Argument};
+use crate::tokenizer::{Kind, Token};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParameterKind {
+    Positional,
+    /// `variadic x: T`, takes all remaining positional arguments
+    Variadic,
+    /// `named only x: T`, can only be passed as `x := value`
+    NamedOnly,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Parameter {
+    pub name: String,
+    pub kind: ParameterKind,
+    pub has_default: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signature {
+    /// Fully-qualified name of the function
+    pub name: String,
+    pub params: Vec<Parameter>,
+}
+
+impl Signature {
+    /// Creates a signature from tokens of a parameter list of a function
+    /// declaration, without the parenthesis
+    pub fn from_params(name: &str, tokens: &[Token]) -> Signature {
+        let mut params = Vec::new();
+        for param in split_top_level(tokens) {
+            let mut kind = ParameterKind::Positional;
+            let mut rest = param;
+            loop {
+                match rest.first().map(|t| t.kind) {
+                    Some(Kind::Keyword(kw)) if kw.0 == "variadic" => {
+                        kind = ParameterKind::Variadic;
+                    }
+                    // combined into a single token by validation
+                    Some(Kind::Keyword(kw)) if kw.0 == "named only" => {
+                        kind = ParameterKind::NamedOnly;
+                    }
+                    _ => break,
+                }
+                rest = &rest[1..];
+            }
+            let Some(name) = rest.first() else {
+                continue;
+            };
+            params.push(Parameter {
+                name: token_name(name).to_string(),
+                kind,
+                has_default: rest.iter().any(|t| t.kind == Kind::Eq),
+            });
+        }
+        Signature {
+            name: name.to_string(),
+            params,
+        }
+    }
+
+    fn positional(&self) -> impl Iterator<Item = &Parameter> {
+        self.params
+            .iter()
+            .filter(|p| p.kind == ParameterKind::Positional)
+    }
+
+    fn variadic(&self) -> Option<&Parameter> {
+        self.params
+            .iter()
+            .find(|p| p.kind == ParameterKind::Variadic)
+    }
+}
+
+pub trait FunctionCatalog {
+    /// All overloads of the function with the fully-qualified `name`
+    fn overloads(&self, name: &str) -> Vec<Signature>;
+
+    /// Resolves a function name as it is written inside of the `module`
+    fn resolve(&self, name: &str, module: &str) -> Option<String> {
+        if name.contains("::") {
+            return Some(name.to_string()).filter(|n| !self.overloads(n).is_empty());
+        }
+        [format!("{module}::{name}"), format!("std::{name}")]
+            .into_iter()
+            .find(|full| !self.overloads(full).is_empty())
+    }
+}
+
+/// Catalog of functions declared in an SDL document
+#[derive(Debug)]
+pub struct SchemaCatalog {
+    functions: HashMap<String, Vec<Signature>>,
+}
+
+impl SchemaCatalog {
+    pub fn new(doc: &Document) -> SchemaCatalog {
+        fn collect(decls: &[Declaration], functions: &mut HashMap<String, Vec<Signature>>) {
+            for decl in decls {
+                match decl.kind {
+                    DeclKind::Module => collect(&decl.body, functions),
+                    DeclKind::Function => {
+                        let Some(name) = decl.name_text() else {
+                            continue;
+                        };
+                        let name = if name.contains("::") {
+                            name.to_string()
+                        } else {
+                            format!("default::{name}")
+                        };
+                        let params = decl.args.as_ref().map_or(&[][..], |a| &a.tokens[..]);
+                        let signature = Signature::from_params(&name, params);
+                        functions.entry(name).or_default().push(signature);
+                    }
+                    _ => {}
+                }
+            }
+        }
+        let mut functions = HashMap::new();
+        collect(&qualify(doc).declarations, &mut functions);
+        SchemaCatalog { functions }
+    }
+}
+
+impl FunctionCatalog for SchemaCatalog {
+    fn overloads(&self, name: &str) -> Vec<Signature> {
+        self.functions.get(name).cloned().unwrap_or_default()
+    }
+}
+
+/// Checks arguments of calls of functions known to the catalog. Names are
+/// resolved relative to the `default` module.
+pub fn check_calls(tokens: &[Token], catalog: &dyn FunctionCatalog) -> Vec<Diagnostic> {
+    let end_of_input = tokens.last().map_or(0, |t| t.span.end);
+    let mut errors = Vec::new();
+    for (open, tok) in tokens.iter().enumerate() {
+        if tok.kind != Kind::OpenParen {
+            continue;
+        }
+        let Some(start) = function_name_start(tokens, open) else {
+            continue;
+        };
+        let name = tokens[start..open]
+            .iter()
+            .map(|t| match t.kind {
+                Kind::Namespace => "::",
+                _ => token_name(t),
+            })
+            .collect::<String>();
+        let Some(full_name) = catalog.resolve(&name, "default") else {
+            continue;
+        };
+        let name_span = tokens[start].span.extend(&tokens[open - 1].span);
+        let arguments = read_arguments(tokens, open, end_of_input);
+
+        let overloads = catalog.overloads(&full_name);
+        let mut results = overloads
+            .iter()
+            .map(|sig| check_arguments(sig, &name, name_span, &arguments))
+            .collect::<Vec<_>>();
+        if results.iter().any(|errs| errs.is_empty()) {
+            continue;
+        }
+        if results.len() == 1 {
+            errors.append(&mut results[0]);
+        } else {
+            let problems = results
+                .iter()
+                .flatten()
+                .map(|e| e.message.clone())
+                .collect::<Vec<_>>()
+                .join("\n");
+            let args = [("name", name.clone()), ("problems", problems)];
+            errors.push(error("no-matching-overload", &args, name_span).with_details());
+        }
+    }
+    errors
+}
+
+fn check_arguments(
+    signature: &Signature,
+    name: &str,
+    name_span: Span,
+    arguments: &[Argument],
+) -> Vec<Diagnostic> {
+    let mut errors = Vec::new();
+    let mut positional = 0;
+    let mut named: Vec<&str> = Vec::new();
+    let max_positional = signature.positional().count();
+    for (idx, arg) in arguments.iter().enumerate() {
+        let Some(arg_name) = &arg.name else {
+            if !named.is_empty() {
+                errors.push(error("positional-after-named", &[], arg.span));
+            } else if positional >= max_positional && signature.variadic().is_none() {
+                let code = if max_positional == 1 {
+                    "unexpected-single-argument"
+                } else {
+                    "unexpected-argument"
+                };
+                let args = [
+                    ("position", (idx + 1).to_string()),
+                    ("name", name.into()),
+                    ("max", max_positional.to_string()),
+                ];
+                errors.push(error(code, &args, arg.span));
+            }
+            positional += 1;
+            continue;
+        };
+        let param = signature.params.iter().find(|p| &p.name == arg_name);
+        let code = match param.map(|p| p.kind) {
+            _ if named.contains(&&arg_name[..]) => "duplicate-argument",
+            Some(ParameterKind::NamedOnly) => {
+                named.push(arg_name);
+                continue;
+            }
+            Some(ParameterKind::Positional) => "positional-by-name",
+            Some(ParameterKind::Variadic) => "variadic-by-name",
+            None => "unknown-argument",
+        };
+        named.push(arg_name);
+        let args = [("name", name.into()), ("arg", arg_name.clone())];
+        errors.push(error(code, &args, arg.span));
+    }
+    for param in signature.positional().skip(positional) {
+        if !param.has_default {
+            let args = [("arg", param.name.clone())];
+            errors.push(error("missing-argument", &args, name_span));
+        }
+    }
+    for param in &signature.params {
+        if param.kind == ParameterKind::NamedOnly
+            && !param.has_default
+            && !named.contains(&&param.name[..])
+        {
+            let args = [("arg", param.name.clone())];
+            errors.push(error("missing-named-argument", &args, name_span));
+        }
+    }
+    errors
+}
+
+fn error(code: &str, args: &[(&str, String)], span: Span) -> Diagnostic {
+    Diagnostic::with_code(Severity::Error, code, args, span)
+}
+
+fn split_top_level<'a, 't>(tokens: &'a [Token<'t>]) -> Vec<&'a [Token<'t>]> {
+    let mut result = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (idx, tok) in tokens.iter().enumerate() {
+        match tok.kind {
+            Kind::OpenParen | Kind::OpenBracket | Kind::OpenBrace | Kind::Less => depth += 1,
+            Kind::CloseParen | Kind::CloseBracket | Kind::CloseBrace | Kind::Greater => {
+                depth = depth.saturating_sub(1)
+            }
+            Kind::Comma if depth == 0 => {
+                result.push(&tokens[start..idx]);
+                start = idx + 1;
+            }
+            _ => {}
+        }
+    }
+    if start < tokens.len() {
+        result.push(&tokens[start..]);
+    }
+    result.retain(|param| {
+        param
+            .first()
+            .is_some_and(|t| matches!(t.kind, Kind::Ident | Kind::Keyword(_)))
+    });
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::{check_calls, ParameterKind, SchemaCatalog, Signature};
+    use crate::schema::parse;
+    use crate::tokenizer::tokens;
+
+    const SCHEMA: &str = r#"
+        function greet(name: str, punctuation: str = '!',
+                       named only lang: str = 'en') -> str using (name);
+        function total(variadic values: int64) -> int64 using (sum(values));
+        function pick(x: int64) -> int64 using (x);
+        function pick(x: str, named only strict: bool) -> str using (x);
+    "#;
+
+    fn errors(query: &str) -> Vec<String> {
+        let catalog = SchemaCatalog::new(&parse(SCHEMA).unwrap());
+        check_calls(&tokens(query), &catalog)
+            .into_iter()
+            .map(|e| {
+                let span = e.span;
+                format!(
+                    "{} @{}",
+                    e.message,
+                    &query[span.start as usize..span.end as usize]
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn signature() {
+        let params = tokens("named only x: array<tuple<a: str, b: int64>> = [], variadic y: str");
+        let sig = Signature::from_params("default::f", &params);
+        assert_eq!(sig.params.len(), 2);
+        assert_eq!(sig.params[0].kind, ParameterKind::NamedOnly);
+        assert!(sig.params[0].has_default);
+        assert_eq!(sig.params[1].name, "y");
+        assert_eq!(sig.params[1].kind, ParameterKind::Variadic);
+    }
+
+    #[test]
+    fn valid() {
+        assert!(errors("select greet('a'), greet('a', '?', lang := 'de')").is_empty());
+        assert!(errors("select default::total(1, 2, 3), total()").is_empty());
+        assert!(errors("select pick(1), pick('x', strict := true)").is_empty());
+        assert!(errors("select unknown(1, 2, x := 3)").is_empty());
+    }
+
+    #[test]
+    fn arguments() {
+        assert_eq!(
+            errors("select greet('a', '?', 'x', lang := 'de', lang := 'fr')"),
+            vec![
+                "unexpected argument 3, function `greet` takes 2 positional arguments @'x'",
+                "argument `lang` is passed more than once @lang := 'fr'",
+            ]
+        );
+        assert_eq!(
+            errors("select greet(lang := 'de', 'a')"),
+            vec!["positional argument follows named argument @'a'"]
+        );
+        assert_eq!(
+            errors("select greet(name := 'a', tone := 1)"),
+            vec![
+                "argument `name` is positional and cannot be passed by name @name := 'a'",
+                "function `greet` has no named-only argument `tone` @tone := 1",
+                "missing argument `name` @greet",
+            ]
+        );
+        assert_eq!(
+            errors("select total(values := 1)"),
+            vec!["variadic argument `values` cannot be passed by name @values := 1"]
+        );
+    }
+
+    #[test]
+    fn overloads() {
+        let errors = errors("select pick(1, 2)");
+        assert_eq!(
+            errors,
+            vec!["no overload of function `pick` matches the arguments @pick"]
+        );
+    }
+}
@@ -0,0 +1,553 @@
+//! Translation of GraphQL queries into EdgeQL (experimental)
+//!
+//! Follows conventions of the server's GraphQL extension: top-level fields
+//! select object types and accept `filter`, `order`, `first` and `offset`
+//! arguments, nested fields are links or properties. The result is a free
+//! object with an element for each top-level field.
+//!
+//! ```
+//! # use edgeql_parser::graphql::{translate, Mapping};
+//! let query = translate("{ User(first: 2) { name } }", &Mapping::default(), None).unwrap();
+//! assert!(query.contains("select default::User {"));
+//! ```
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use crate::helpers::{quote_name, quote_string};
+use crate::position::Span;
+
+pub mod parser;
+
+use parser::{Document, Field, Operation, OperationKind, Selection, Type, Value};
+
+/// Error of parsing or translating a GraphQL query
+#[derive(Debug, thiserror::Error)]
+#[error("{}", .message)]
+pub struct Error {
+    pub message: String,
+    pub span: Span,
+}
+
+impl Error {
+    fn new(message: impl Into<String>, span: Span) -> Error {
+        Error {
+            message: message.into(),
+            span,
+        }
+    }
+}
+
+/// Rules for mapping GraphQL names to the schema
+#[derive(Debug, Clone)]
+pub struct Mapping {
+    /// Module of types whose names are not in `types`
+    pub module: String,
+    /// GraphQL type (or top-level field) name to a fully-qualified name of
+    /// an object type
+    pub types: HashMap<String, String>,
+}
+
+impl Default for Mapping {
+    fn default() -> Mapping {
+        Mapping {
+            module: "default".into(),
+            types: HashMap::new(),
+        }
+    }
+}
+
+impl Mapping {
+    fn type_name(&self, name: &str) -> String {
+        match self.types.get(name) {
+            Some(full) => full.clone(),
+            None => format!("{}::{}", quote_name(&self.module), quote_name(name)),
+        }
+    }
+}
+
+/// Translates a query into EdgeQL. If the document contains several
+/// operations, `operation` selects which one to translate.
+pub fn translate(query: &str, mapping: &Mapping, operation: Option<&str>) -> Result<String, Error> {
+    let doc = parser::parse(query)?;
+    let op = match operation {
+        Some(name) => doc
+            .operations
+            .iter()
+            .find(|op| op.name.as_deref() == Some(name))
+            .ok_or_else(|| Error::new(format!("Unknown operation {name:?}"), Span::default()))?,
+        None => match &doc.operations[..] {
+            [op] => op,
+            [] => return Err(Error::new("Document has no operations", Span::default())),
+            [_, second, ..] => {
+                return Err(Error::new(
+                    "Document has several operations, operation name is required",
+                    second.span,
+                ));
+            }
+        },
+    };
+    if op.kind != OperationKind::Query {
+        return Err(Error::new("Only queries are supported", op.span));
+    }
+    let mut translator = Translator {
+        doc: &doc,
+        op,
+        mapping,
+        fragment_depth: 0,
+    };
+    translator.operation()
+}
+
+struct Translator<'a> {
+    doc: &'a Document,
+    op: &'a Operation,
+    mapping: &'a Mapping,
+    fragment_depth: usize,
+}
+
+impl Translator<'_> {
+    fn operation(&mut self) -> Result<String, Error> {
+        let mut buf = String::from("select {\n");
+        for sel in &self.op.selection {
+            let field = match sel {
+                Selection::Field(field) => field,
+                Selection::FragmentSpread { span, .. } | Selection::InlineFragment { span, .. } => {
+                    return Err(Error::new(
+                        "Fragments are not supported at the top level",
+                        *span,
+                    ));
+                }
+            };
+            check_directives(field)?;
+            let key = field.alias.as_ref().unwrap_or(&field.name);
+            if field.name == "__typename" {
+                writeln!(buf, "    {} := 'Query',", quote_name(key)).unwrap();
+                continue;
+            }
+            let type_name = self.mapping.type_name(&field.name);
+            let mut elements = Vec::new();
+            self.selection(&field.selection, Some(&field.name), &mut elements)?;
+            writeln!(buf, "    {} := (", quote_name(key)).unwrap();
+            write!(buf, "        select {type_name}").unwrap();
+            render_shape(&mut buf, &elements, 2);
+            for clause in self.clauses(field)? {
+                write!(buf, "\n        {clause}").unwrap();
+            }
+            buf.push_str("\n    ),\n");
+        }
+        buf.push('}');
+        Ok(buf)
+    }
+
+    fn selection(
+        &mut self,
+        selection: &[Selection],
+        current_type: Option<&str>,
+        elements: &mut Vec<String>,
+    ) -> Result<(), Error> {
+        for sel in selection {
+            match sel {
+                Selection::Field(field) => {
+                    check_directives(field)?;
+                    elements.push(self.field(field, None)?);
+                }
+                Selection::InlineFragment {
+                    type_condition,
+                    directives,
+                    selection,
+                    span,
+                } => {
+                    if !directives.is_empty() {
+                        return Err(Error::new("Directives are not supported", *span));
+                    }
+                    self.fragment(selection, type_condition.as_deref(), current_type, elements)?;
+                }
+                Selection::FragmentSpread {
+                    name,
+                    directives,
+                    span,
+                } => {
+                    if !directives.is_empty() {
+                        return Err(Error::new("Directives are not supported", *span));
+                    }
+                    let Some(fragment) = self.doc.fragments.iter().find(|f| &f.name == name) else {
+                        return Err(Error::new(format!("Unknown fragment {name:?}"), *span));
+                    };
+                    if self.fragment_depth > self.doc.fragments.len() {
+                        return Err(Error::new(format!("Fragment {name:?} is recursive"), *span));
+                    }
+                    self.fragment_depth += 1;
+                    // type of nested fields is unknown, so fragments spread
+                    // there are assumed to match it
+                    let condition = current_type.map(|_| &fragment.type_condition[..]);
+                    self.fragment(&fragment.selection, condition, current_type, elements)?;
+                    self.fragment_depth -= 1;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn fragment(
+        &mut self,
+        selection: &[Selection],
+        condition: Option<&str>,
+        current_type: Option<&str>,
+        elements: &mut Vec<String>,
+    ) -> Result<(), Error> {
+        match condition {
+            Some(cond) if Some(cond) != current_type => {
+                let prefix = format!("[is {}].", self.mapping.type_name(cond));
+                for sel in selection {
+                    match sel {
+                        Selection::Field(field) => {
+                            check_directives(field)?;
+                            elements.push(self.field(field, Some(&prefix))?);
+                        }
+                        Selection::FragmentSpread { span, .. }
+                        | Selection::InlineFragment { span, .. } => {
+                            return Err(Error::new(
+                                "Nested fragments in type conditions are not supported",
+                                *span,
+                            ));
+                        }
+                    }
+                }
+                Ok(())
+            }
+            _ => self.selection(selection, current_type, elements),
+        }
+    }
+
+    fn field(&mut self, field: &Field, prefix: Option<&str>) -> Result<String, Error> {
+        let prefix = prefix.unwrap_or("");
+        let name = quote_name(&field.name);
+        if field.name == "__typename" {
+            let key = field.alias.as_ref().unwrap_or(&field.name);
+            return Ok(format!("{} := .__type__.name", quote_name(key)));
+        }
+        let mut nested = Vec::new();
+        self.selection(&field.selection, None, &mut nested)?;
+        let clauses = self.clauses(field)?;
+        let mut text = String::new();
+        match &field.alias {
+            Some(alias) if !field.selection.is_empty() || !clauses.is_empty() => {
+                write!(text, "{} := (select .{prefix}{name}", quote_name(alias)).unwrap();
+                if !nested.is_empty() {
+                    render_inline_shape(&mut text, &nested);
+                }
+                for clause in &clauses {
+                    write!(text, " {clause}").unwrap();
+                }
+                text.push(')');
+            }
+            Some(alias) => write!(text, "{} := .{prefix}{name}", quote_name(alias)).unwrap(),
+            None => {
+                write!(text, "{prefix}{name}").unwrap();
+                if !nested.is_empty() {
+                    text.push(':');
+                    render_inline_shape(&mut text, &nested);
+                }
+                for clause in &clauses {
+                    write!(text, " {clause}").unwrap();
+                }
+            }
+        }
+        Ok(text)
+    }
+
+    fn clauses(&self, field: &Field) -> Result<Vec<String>, Error> {
+        let mut filter = None;
+        let mut order = None;
+        let mut offset = None;
+        let mut limit = None;
+        for (name, value) in &field.arguments {
+            match &name[..] {
+                "filter" => filter = Some(self.filter(value, "", field.span)?),
+                "order" => order = Some(self.order(value, field.span)?),
+                "first" => limit = Some(self.value(value, Some("int64"), field.span)?),
+                "offset" => offset = Some(self.value(value, Some("int64"), field.span)?),
+                _ => {
+                    return Err(Error::new(
+                        format!("Unsupported argument {name:?}"),
+                        field.span,
+                    ));
+                }
+            }
+        }
+        let mut clauses = Vec::new();
+        clauses.extend(filter.map(|f| format!("filter {f}")));
+        clauses.extend(order.map(|o| format!("order by {o}")));
+        clauses.extend(offset.map(|o| format!("offset {o}")));
+        clauses.extend(limit.map(|l| format!("limit {l}")));
+        Ok(clauses)
+    }
+
+    fn filter(&self, value: &Value, path: &str, span: Span) -> Result<String, Error> {
+        let Value::Object(fields) = value else {
+            return Err(Error::new("Filter must be an object", span));
+        };
+        let mut conditions = Vec::new();
+        for (name, value) in fields {
+            let condition = match &name[..] {
+                "and" | "or" => {
+                    let Value::List(items) = value else {
+                        return Err(Error::new(format!("{name:?} expects a list"), span));
+                    };
+                    let parts = items
+                        .iter()
+                        .map(|item| self.filter(item, path, span))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    format!("({})", parts.join(&format!(" {name} ")))
+                }
+                "not" => format!("not ({})", self.filter(value, path, span)?),
+                op if !path.is_empty() && is_operator(op) => {
+                    self.comparison(op, value, path, span)?
+                }
+                field => {
+                    let path = format!("{path}.{}", quote_name(field));
+                    self.filter(value, &path, span)?
+                }
+            };
+            conditions.push(condition);
+        }
+        match conditions.len() {
+            0 => Ok("true".into()),
+            1 => Ok(conditions.pop().unwrap()),
+            _ => Ok(conditions.join(" and ")),
+        }
+    }
+
+    fn comparison(&self, op: &str, value: &Value, path: &str, span: Span) -> Result<String, Error> {
+        let sign = match op {
+            "eq" => "=",
+            "neq" => "!=",
+            "gt" => ">",
+            "gte" => ">=",
+            "lt" => "<",
+            "lte" => "<=",
+            "like" => "like",
+            "ilike" => "ilike",
+            "exists" => {
+                return match value {
+                    Value::Boolean(true) => Ok(format!("exists {path}")),
+                    Value::Boolean(false) => Ok(format!("not exists {path}")),
+                    _ => Err(Error::new("\"exists\" expects a boolean", span)),
+                };
+            }
+            "in" => {
+                let set = match value {
+                    Value::List(items) => {
+                        let items = items
+                            .iter()
+                            .map(|item| self.value(item, None, span))
+                            .collect::<Result<Vec<_>, _>>()?;
+                        format!("{{{}}}", items.join(", "))
+                    }
+                    Value::Variable(_) => {
+                        format!("array_unpack({})", self.value(value, None, span)?)
+                    }
+                    _ => return Err(Error::new("\"in\" expects a list", span)),
+                };
+                return Ok(format!("{path} in {set}"));
+            }
+            _ => unreachable!("not an operator"),
+        };
+        Ok(format!("{path} {sign} {}", self.value(value, None, span)?))
+    }
+
+    fn order(&self, value: &Value, span: Span) -> Result<String, Error> {
+        let Value::Object(fields) = value else {
+            return Err(Error::new("Order must be an object", span));
+        };
+        let mut items = Vec::new();
+        for (name, options) in fields {
+            let mut item = format!(".{}", quote_name(name));
+            let Value::Object(options) = options else {
+                return Err(Error::new("Order direction must be an object", span));
+            };
+            for (option, value) in options {
+                let text = match (&option[..], value) {
+                    ("dir", Value::Enum(dir)) if dir == "ASC" => "asc",
+                    ("dir", Value::Enum(dir)) if dir == "DESC" => "desc",
+                    ("nulls", Value::Enum(n)) if n == "SMALLEST" => "empty first",
+                    ("nulls", Value::Enum(n)) if n == "BIGGEST" => "empty last",
+                    _ => {
+                        return Err(Error::new(format!("Invalid order option {option:?}"), span));
+                    }
+                };
+                item.push(' ');
+                item.push_str(text);
+            }
+            items.push(item);
+        }
+        Ok(items.join(" then "))
+    }
+
+    fn value(&self, value: &Value, cast: Option<&str>, span: Span) -> Result<String, Error> {
+        let text = match value {
+            Value::Variable(name) => {
+                let Some(var) = self.op.variables.iter().find(|v| &v.name == name) else {
+                    return Err(Error::new(format!("Unknown variable ${name}"), span));
+                };
+                let optional = if matches!(var.ty, Type::NonNull(_)) || var.default.is_some() {
+                    ""
+                } else {
+                    "optional "
+                };
+                let ty = cast.map_or_else(|| self.scalar_type(&var.ty), |c| c.into());
+                format!("<{optional}{ty}>${name}")
+            }
+            Value::Int(v) => v.to_string(),
+            Value::Float(v) => format!("{v:?}"),
+            Value::String(v) | Value::Enum(v) => quote_string(v),
+            Value::Boolean(v) => v.to_string(),
+            Value::Null => "{}".into(),
+            Value::List(items) => {
+                let items = items
+                    .iter()
+                    .map(|item| self.value(item, None, span))
+                    .collect::<Result<Vec<_>, _>>()?;
+                format!("[{}]", items.join(", "))
+            }
+            Value::Object(_) => return Err(Error::new("Unexpected object value", span)),
+        };
+        Ok(text)
+    }
+
+    fn scalar_type(&self, ty: &Type) -> String {
+        match ty {
+            Type::NonNull(inner) => self.scalar_type(inner),
+            Type::List(inner) => format!("array<{}>", self.scalar_type(inner)),
+            Type::Named(name) => match &name[..] {
+                "String" => "str".into(),
+                "Int" => "int64".into(),
+                "Float" => "float64".into(),
+                "Boolean" => "bool".into(),
+                "ID" => "uuid".into(),
+                _ => self.mapping.type_name(name),
+            },
+        }
+    }
+}
+
+fn is_operator(name: &str) -> bool {
+    matches!(
+        name,
+        "eq" | "neq" | "gt" | "gte" | "lt" | "lte" | "like" | "ilike" | "exists" | "in"
+    )
+}
+
+fn check_directives(field: &Field) -> Result<(), Error> {
+    match field.directives.first() {
+        Some(directive) => Err(Error::new("Directives are not supported", directive.span)),
+        None => Ok(()),
+    }
+}
+
+fn render_shape(buf: &mut String, elements: &[String], indent: usize) {
+    let pad = "    ".repeat(indent);
+    buf.push_str(" {\n");
+    for el in elements {
+        writeln!(buf, "{pad}    {el},").unwrap();
+    }
+    buf.push_str(&pad);
+    buf.push('}');
+}
+
+fn render_inline_shape(buf: &mut String, elements: &[String]) {
+    write!(buf, " {{ {} }}", elements.join(", ")).unwrap();
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::{translate, Mapping};
+
+    #[test]
+    fn query() {
+        let query = r#"
+            query Users($name: String!, $limit: Int) {
+                users: User(
+                    filter: {name: {like: $name}, friends: {age: {gte: 18}}},
+                    order: {name: {dir: DESC, nulls: BIGGEST}},
+                    first: $limit,
+                ) {
+                    __typename
+                    name
+                    friends(first: 2) { name }
+                    best: friends(order: {age: {dir: ASC}}) { name }
+                    nick: name
+                    ... on Admin { level }
+                    ...Fields
+                }
+            }
+            fragment Fields on User { bio }
+        "#;
+        assert_eq!(
+            translate(query, &Mapping::default(), None).unwrap(),
+            r#"select {
+    users := (
+        select default::User {
+            __typename := .__type__.name,
+            name,
+            friends: { name } limit 2,
+            best := (select .friends { name } order by .age asc),
+            nick := .name,
+            [is default::Admin].level,
+            bio,
+        }
+        filter .name like <str>$name and .friends.age >= 18
+        order by .name desc empty last
+        limit <optional int64>$limit
+    ),
+}"#
+        );
+    }
+
+    #[test]
+    fn mapping() {
+        let mapping = Mapping {
+            module: "app".into(),
+            types: HashMap::from([("Person".into(), "people::Person".into())]),
+        };
+        let query = r#"{
+            Person(filter: {or: [{id: {in: ["a", "b"]}}, {not: {name: {exists: true}}}]}) { id }
+            Post { title }
+        }"#;
+        assert_eq!(
+            translate(query, &mapping, None).unwrap(),
+            r#"select {
+    Person := (
+        select people::Person {
+            id,
+        }
+        filter (.id in {"a", "b"} or not (exists .name))
+    ),
+    Post := (
+        select app::Post {
+            title,
+        }
+    ),
+}"#
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let mapping = Mapping::default();
+        let err = translate("mutation { insert_User { id } }", &mapping, None).unwrap_err();
+        assert_eq!(err.message, "Only queries are supported");
+        let err = translate("{ User(where: 1) { id } }", &mapping, None).unwrap_err();
+        assert_eq!(err.message, "Unsupported argument \"where\"");
+        let err = translate("{ User { ...F } }", &mapping, None).unwrap_err();
+        assert_eq!(err.message, "Unknown fragment \"F\"");
+        let err = translate("query A { a } query B { b }", &mapping, None).unwrap_err();
+        assert_eq!(
+            err.message,
+            "Document has several operations, operation name is required"
+        );
+        assert!(translate("query A { a } query B { b }", &mapping, Some("B")).is_ok());
+    }
+}
@@ -0,0 +1,662 @@
+//! Minimal parser of GraphQL executable documents
+//!
+//! Supports operations, variables, fields with arguments and aliases,
+//! directives, fragments and inline fragments. Type system definitions
+//! are not supported.
+use crate::graphql::Error;
+use crate::position::Span;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Document {
+    pub operations: Vec<Operation>,
+    pub fragments: Vec<Fragment>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OperationKind {
+    Query,
+    Mutation,
+    Subscription,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Operation {
+    pub kind: OperationKind,
+    pub name: Option<String>,
+    pub variables: Vec<VariableDefinition>,
+    pub selection: Vec<Selection>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct VariableDefinition {
+    pub name: String,
+    pub ty: Type,
+    pub default: Option<Value>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Type {
+    Named(String),
+    List(Box<Type>),
+    NonNull(Box<Type>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Selection {
+    Field(Field),
+    FragmentSpread {
+        name: String,
+        directives: Vec<Directive>,
+        span: Span,
+    },
+    InlineFragment {
+        type_condition: Option<String>,
+        directives: Vec<Directive>,
+        selection: Vec<Selection>,
+        span: Span,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    pub alias: Option<String>,
+    pub name: String,
+    pub arguments: Vec<(String, Value)>,
+    pub directives: Vec<Directive>,
+    pub selection: Vec<Selection>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Directive {
+    pub name: String,
+    pub arguments: Vec<(String, Value)>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fragment {
+    pub name: String,
+    pub type_condition: String,
+    pub selection: Vec<Selection>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Variable(String),
+    Int(i64),
+    Float(f64),
+    String(String),
+    Boolean(bool),
+    Null,
+    Enum(String),
+    List(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Punct(&'static str),
+    Name(String),
+    Int(i64),
+    Float(f64),
+    Str(String),
+}
+
+struct Lexer<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl Lexer<'_> {
+    fn error(&self, message: impl Into<String>, start: usize) -> Error {
+        Error::new(
+            message,
+            Span {
+                start: start as u64,
+                end: self.pos.max(start + 1) as u64,
+            },
+        )
+    }
+
+    fn skip_ignored(&mut self) {
+        let bytes = self.text.as_bytes();
+        while let Some(&b) = bytes.get(self.pos) {
+            match b {
+                b' ' | b'\t' | b'\n' | b'\r' | b',' => self.pos += 1,
+                b'#' => {
+                    while bytes.get(self.pos).is_some_and(|&b| b != b'\n') {
+                        self.pos += 1;
+                    }
+                }
+                // byte order mark
+                0xEF if self.text[self.pos..].starts_with('\u{FEFF}') => self.pos += 3,
+                _ => break,
+            }
+        }
+    }
+
+    fn next(&mut self) -> Result<Option<(Token, Span)>, Error> {
+        self.skip_ignored();
+        let start = self.pos;
+        let rest = &self.text[self.pos..];
+        let Some(c) = rest.chars().next() else {
+            return Ok(None);
+        };
+        let token = match c {
+            '!' | '$' | '&' | '(' | ')' | ':' | '=' | '@' | '[' | ']' | '{' | '|' | '}' => {
+                self.pos += 1;
+                const PUNCT: &[&str] = &[
+                    "!", "$", "&", "(", ")", ":", "=", "@", "[", "]", "{", "|", "}",
+                ];
+                Token::Punct(PUNCT.iter().find(|p| rest.starts_with(**p)).unwrap())
+            }
+            '.' if rest.starts_with("...") => {
+                self.pos += 3;
+                Token::Punct("...")
+            }
+            '"' if rest.starts_with("\"\"\"") => self.block_string()?,
+            '"' => self.string()?,
+            '-' | '0'..='9' => self.number()?,
+            c if c == '_' || c.is_ascii_alphabetic() => {
+                let len = rest
+                    .find(|c: char| !(c == '_' || c.is_ascii_alphanumeric()))
+                    .unwrap_or(rest.len());
+                self.pos += len;
+                Token::Name(rest[..len].to_string())
+            }
+            c => {
+                self.pos += c.len_utf8();
+                return Err(self.error(format!("Unexpected character {c:?}"), start));
+            }
+        };
+        let span = Span {
+            start: start as u64,
+            end: self.pos as u64,
+        };
+        Ok(Some((token, span)))
+    }
+
+    fn number(&mut self) -> Result<Token, Error> {
+        let start = self.pos;
+        let rest = &self.text[self.pos..];
+        let len = rest
+            .find(|c: char| !(c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E')))
+            .unwrap_or(rest.len());
+        self.pos += len;
+        let text = &rest[..len];
+        if text.contains(['.', 'e', 'E']) {
+            text.parse()
+                .map(Token::Float)
+                .map_err(|_| self.error(format!("Invalid number {text:?}"), start))
+        } else {
+            text.parse()
+                .map(Token::Int)
+                .map_err(|_| self.error(format!("Invalid number {text:?}"), start))
+        }
+    }
+
+    fn string(&mut self) -> Result<Token, Error> {
+        let start = self.pos;
+        self.pos += 1;
+        let mut value = String::new();
+        let mut chars = self.text[self.pos..].char_indices();
+        while let Some((idx, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.pos += idx + 1;
+                    return Ok(Token::Str(value));
+                }
+                '\n' | '\r' => break,
+                '\\' => {
+                    let escaped = match chars.next().map(|(_, c)| c) {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('/') => '/',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('u') => {
+                            let hex: String = chars.by_ref().take(4).map(|(_, c)| c).collect();
+                            u32::from_str_radix(&hex, 16)
+                                .ok()
+                                .and_then(char::from_u32)
+                                .ok_or_else(|| self.error("Invalid unicode escape", start))?
+                        }
+                        _ => return Err(self.error("Invalid escape sequence", start)),
+                    };
+                    value.push(escaped);
+                }
+                c => value.push(c),
+            }
+        }
+        self.pos = self.text.len();
+        Err(self.error("Unterminated string", start))
+    }
+
+    fn block_string(&mut self) -> Result<Token, Error> {
+        let start = self.pos;
+        let body_start = self.pos + 3;
+        let Some(len) = self.text[body_start..].find("\"\"\"") else {
+            self.pos = self.text.len();
+            return Err(self.error("Unterminated string", start));
+        };
+        self.pos = body_start + len + 3;
+        let raw = self.text[body_start..body_start + len].replace("\\\"\"\"", "\"\"\"");
+
+        // remove common indentation and blank first and last lines
+        let lines: Vec<&str> = raw.lines().collect();
+        let indent = lines
+            .iter()
+            .skip(1)
+            .filter(|l| !l.trim().is_empty())
+            .map(|l| l.len() - l.trim_start().len())
+            .min()
+            .unwrap_or(0);
+        let mut result: Vec<&str> = lines
+            .iter()
+            .enumerate()
+            .map(|(i, l)| {
+                if i == 0 {
+                    l
+                } else {
+                    l.get(indent..).unwrap_or("")
+                }
+            })
+            .collect();
+        while result.first().is_some_and(|l| l.trim().is_empty()) {
+            result.remove(0);
+        }
+        while result.last().is_some_and(|l| l.trim().is_empty()) {
+            result.pop();
+        }
+        Ok(Token::Str(result.join("\n")))
+    }
+}
+
+struct Parser {
+    tokens: Vec<(Token, Span)>,
+    pos: usize,
+    end: Span,
+}
+
+/// Parses a GraphQL document
+pub fn parse(text: &str) -> Result<Document, Error> {
+    let mut lexer = Lexer { text, pos: 0 };
+    let mut tokens = Vec::new();
+    while let Some(token) = lexer.next()? {
+        tokens.push(token);
+    }
+    let end = Span {
+        start: text.len() as u64,
+        end: text.len() as u64,
+    };
+    let mut parser = Parser {
+        tokens,
+        pos: 0,
+        end,
+    };
+    let mut doc = Document::default();
+    while parser.peek().is_some() {
+        parser.definition(&mut doc)?;
+    }
+    Ok(doc)
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(t, _)| t)
+    }
+
+    fn span(&self) -> Span {
+        self.tokens.get(self.pos).map_or(self.end, |(_, s)| *s)
+    }
+
+    fn prev_span(&self) -> Span {
+        self.tokens
+            .get(self.pos.wrapping_sub(1))
+            .map_or(self.end, |(_, s)| *s)
+    }
+
+    fn is_punct(&self, punct: &str) -> bool {
+        matches!(self.peek(), Some(Token::Punct(p)) if *p == punct)
+    }
+
+    fn is_name(&self, name: &str) -> bool {
+        matches!(self.peek(), Some(Token::Name(n)) if n == name)
+    }
+
+    fn unexpected(&self) -> Error {
+        let message = match self.peek() {
+            None => "Unexpected end of document".to_string(),
+            Some(Token::Punct(p)) => format!("Unexpected {p:?}"),
+            Some(Token::Name(n)) => format!("Unexpected name {n:?}"),
+            Some(Token::Str(_)) => "Unexpected string".to_string(),
+            Some(Token::Int(_) | Token::Float(_)) => "Unexpected number".to_string(),
+        };
+        Error::new(message, self.span())
+    }
+
+    fn expect(&mut self, punct: &str) -> Result<(), Error> {
+        if self.is_punct(punct) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.unexpected())
+        }
+    }
+
+    fn consume(&mut self, punct: &str) -> bool {
+        let found = self.is_punct(punct);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn name(&mut self) -> Result<String, Error> {
+        match self.peek() {
+            Some(Token::Name(name)) => {
+                let name = name.clone();
+                self.pos += 1;
+                Ok(name)
+            }
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    fn definition(&mut self, doc: &mut Document) -> Result<(), Error> {
+        let start = self.span();
+        if self.is_punct("{") {
+            let selection = self.selection_set()?;
+            doc.operations.push(Operation {
+                kind: OperationKind::Query,
+                name: None,
+                variables: Vec::new(),
+                selection,
+                span: start.extend(&self.prev_span()),
+            });
+            return Ok(());
+        }
+        let keyword = self.name()?;
+        let kind = match &keyword[..] {
+            "query" => OperationKind::Query,
+            "mutation" => OperationKind::Mutation,
+            "subscription" => OperationKind::Subscription,
+            "fragment" => {
+                let name = self.name()?;
+                if !self.is_name("on") {
+                    return Err(self.unexpected());
+                }
+                self.pos += 1;
+                let type_condition = self.name()?;
+                self.directives()?;
+                let selection = self.selection_set()?;
+                doc.fragments.push(Fragment {
+                    name,
+                    type_condition,
+                    selection,
+                    span: start.extend(&self.prev_span()),
+                });
+                return Ok(());
+            }
+            _ => {
+                self.pos -= 1;
+                return Err(self.unexpected());
+            }
+        };
+        let name = match self.peek() {
+            Some(Token::Name(_)) => Some(self.name()?),
+            _ => None,
+        };
+        let mut variables = Vec::new();
+        if self.consume("(") {
+            while !self.consume(")") {
+                let var_start = self.span();
+                self.expect("$")?;
+                let name = self.name()?;
+                self.expect(":")?;
+                let ty = self.type_ref()?;
+                let default = if self.consume("=") {
+                    Some(self.value(true)?)
+                } else {
+                    None
+                };
+                self.directives()?;
+                variables.push(VariableDefinition {
+                    name,
+                    ty,
+                    default,
+                    span: var_start.extend(&self.prev_span()),
+                });
+            }
+        }
+        self.directives()?;
+        let selection = self.selection_set()?;
+        doc.operations.push(Operation {
+            kind,
+            name,
+            variables,
+            selection,
+            span: start.extend(&self.prev_span()),
+        });
+        Ok(())
+    }
+
+    fn type_ref(&mut self) -> Result<Type, Error> {
+        let ty = if self.consume("[") {
+            let inner = self.type_ref()?;
+            self.expect("]")?;
+            Type::List(Box::new(inner))
+        } else {
+            Type::Named(self.name()?)
+        };
+        if self.consume("!") {
+            Ok(Type::NonNull(Box::new(ty)))
+        } else {
+            Ok(ty)
+        }
+    }
+
+    fn selection_set(&mut self) -> Result<Vec<Selection>, Error> {
+        self.expect("{")?;
+        let mut selection = Vec::new();
+        while !self.consume("}") {
+            selection.push(self.selection()?);
+        }
+        Ok(selection)
+    }
+
+    fn selection(&mut self) -> Result<Selection, Error> {
+        let start = self.span();
+        if self.consume("...") {
+            if self.is_punct("{") || self.is_punct("@") || self.is_name("on") {
+                let type_condition = if self.is_name("on") {
+                    self.pos += 1;
+                    Some(self.name()?)
+                } else {
+                    None
+                };
+                let directives = self.directives()?;
+                let selection = self.selection_set()?;
+                return Ok(Selection::InlineFragment {
+                    type_condition,
+                    directives,
+                    selection,
+                    span: start.extend(&self.prev_span()),
+                });
+            }
+            let name = self.name()?;
+            let directives = self.directives()?;
+            return Ok(Selection::FragmentSpread {
+                name,
+                directives,
+                span: start.extend(&self.prev_span()),
+            });
+        }
+
+        let mut name = self.name()?;
+        let mut alias = None;
+        if self.consume(":") {
+            alias = Some(name);
+            name = self.name()?;
+        }
+        let arguments = self.arguments(false)?;
+        let directives = self.directives()?;
+        let selection = if self.is_punct("{") {
+            self.selection_set()?
+        } else {
+            Vec::new()
+        };
+        Ok(Selection::Field(Field {
+            alias,
+            name,
+            arguments,
+            directives,
+            selection,
+            span: start.extend(&self.prev_span()),
+        }))
+    }
+
+    fn arguments(&mut self, constant: bool) -> Result<Vec<(String, Value)>, Error> {
+        let mut arguments = Vec::new();
+        if self.consume("(") {
+            while !self.consume(")") {
+                let name = self.name()?;
+                self.expect(":")?;
+                arguments.push((name, self.value(constant)?));
+            }
+        }
+        Ok(arguments)
+    }
+
+    fn directives(&mut self) -> Result<Vec<Directive>, Error> {
+        let mut directives = Vec::new();
+        while self.is_punct("@") {
+            let start = self.span();
+            self.pos += 1;
+            let name = self.name()?;
+            let arguments = self.arguments(false)?;
+            directives.push(Directive {
+                name,
+                arguments,
+                span: start.extend(&self.prev_span()),
+            });
+        }
+        Ok(directives)
+    }
+
+    fn value(&mut self, constant: bool) -> Result<Value, Error> {
+        let Some(token) = self.peek().cloned() else {
+            return Err(self.unexpected());
+        };
+        let value = match token {
+            Token::Punct("$") if !constant => {
+                self.pos += 1;
+                return Ok(Value::Variable(self.name()?));
+            }
+            Token::Punct("[") => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                while !self.consume("]") {
+                    items.push(self.value(constant)?);
+                }
+                return Ok(Value::List(items));
+            }
+            Token::Punct("{") => {
+                self.pos += 1;
+                let mut fields = Vec::new();
+                while !self.consume("}") {
+                    let name = self.name()?;
+                    self.expect(":")?;
+                    fields.push((name, self.value(constant)?));
+                }
+                return Ok(Value::Object(fields));
+            }
+            Token::Punct(_) => return Err(self.unexpected()),
+            Token::Int(v) => Value::Int(v),
+            Token::Float(v) => Value::Float(v),
+            Token::Str(v) => Value::String(v),
+            Token::Name(name) => match &name[..] {
+                "true" => Value::Boolean(true),
+                "false" => Value::Boolean(false),
+                "null" => Value::Null,
+                _ => Value::Enum(name),
+            },
+        };
+        self.pos += 1;
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse, OperationKind, Selection, Type, Value};
+
+    #[test]
+    fn query() {
+        let doc = parse(
+            r#"
+            # comment
+            query Users($name: String!, $ids: [ID!] = []) {
+                all: User(filter: {name: {eq: $name}}, first: 10) {
+                    name
+                    ... on Admin { level }
+                    ...Fields @include(if: true)
+                }
+            }
+            fragment Fields on User { description: bio, note(text: """
+                multi
+                  line
+            """) }
+            "#,
+        )
+        .unwrap();
+        let op = &doc.operations[0];
+        assert_eq!(op.kind, OperationKind::Query);
+        assert_eq!(op.name.as_deref(), Some("Users"));
+        assert_eq!(
+            op.variables[0].ty,
+            Type::NonNull(Box::new(Type::Named("String".into())))
+        );
+        assert_eq!(op.variables[1].default, Some(Value::List(Vec::new())));
+
+        let Selection::Field(field) = &op.selection[0] else {
+            panic!("not a field");
+        };
+        assert_eq!(field.alias.as_deref(), Some("all"));
+        assert_eq!(field.name, "User");
+        assert_eq!(field.arguments[1], ("first".into(), Value::Int(10)));
+        assert_eq!(field.selection.len(), 3);
+        assert!(matches!(
+            &field.selection[1],
+            Selection::InlineFragment { type_condition: Some(t), .. } if t == "Admin"
+        ));
+
+        let fragment = &doc.fragments[0];
+        let Selection::Field(note) = &fragment.selection[1] else {
+            panic!("not a field");
+        };
+        assert_eq!(note.arguments[0].1, Value::String("multi\n  line".into()));
+    }
+
+    #[test]
+    fn errors() {
+        let err = parse("{ user(id: ) }").unwrap_err();
+        assert_eq!(err.message, "Unexpected \")\"");
+        assert_eq!((err.span.start, err.span.end), (11, 12));
+        assert_eq!(
+            parse("{ name(x: \"abc) }").unwrap_err().message,
+            "Unterminated string"
+        );
+        assert_eq!(
+            parse("query { a").unwrap_err().message,
+            "Unexpected end of document"
+        );
+    }
+}
@@ -1,25 +1,50 @@
-pub fn unquote_bytes(value: &str) -> Result<Vec<u8>, String> {
+use super::strings::UnquoteError;
+
+/// Decodes a bytes literal (`b'...'`, `rb'...'` or `br'...'`) as written in
+/// the source
+///
+/// Only ascii characters are allowed in bytes literals, other bytes must be
+/// written as `\x` escapes (which are not processed in raw literals).
+///
+/// # Examples
+/// ```
+/// use edgeql_parser::helpers::unquote_bytes;
+/// assert_eq!(unquote_bytes(r"b'a\x00\xff'").unwrap(), b"a\x00\xff");
+/// assert_eq!(unquote_bytes(r"rb'a\x00'").unwrap(), br"a\x00");
+/// assert!(unquote_bytes("b'\u{e9}'").is_err());
+/// ```
+pub fn unquote_bytes(value: &str) -> Result<Vec<u8>, UnquoteError> {
     let idx = value
         .find(['\'', '"'])
-        .ok_or_else(|| "invalid bytes literal: missing quotes".to_string())?;
+        .ok_or_else(|| UnquoteError::new("invalid bytes literal: missing quotes"))?;
     let prefix = &value[..idx];
+    let body = &value[idx + 1..value.len() - 1];
+    if let Some(c) = body.chars().find(|c| !c.is_ascii()) {
+        return Err(UnquoteError::new(format!(
+            "invalid bytes literal: character {c:?} is unexpected, \
+             only ascii chars are allowed in bytes literals"
+        )));
+    }
     match prefix {
-        "br" | "rb" => Ok(value.as_bytes()[3..value.len() - 1].to_vec()),
-        "b" => Ok(unquote_bytes_inner(&value[2..value.len() - 1])?),
-        _ => Err(
-            format_args!("prefix {prefix:?} is not allowed for bytes, allowed: `b`, `rb`",)
-                .to_string(),
-        ),
+        "br" | "rb" => Ok(body.as_bytes().to_vec()),
+        "b" => unquote_bytes_inner(body).map_err(|e| e.shifted(idx + 1)),
+        _ => Err(UnquoteError::new(format!(
+            "prefix {prefix:?} is not allowed for bytes, allowed: `b`, `rb`",
+        ))),
     }
 }
 
-fn unquote_bytes_inner(s: &str) -> Result<Vec<u8>, String> {
+fn unquote_bytes_inner(s: &str) -> Result<Vec<u8>, UnquoteError> {
     let mut res = Vec::with_capacity(s.len());
     let mut bytes = s.as_bytes().iter();
     while let Some(&c) = bytes.next() {
         match c {
             b'\\' => {
-                match *bytes.next().expect("slash cant be at the end") {
+                let start = s.len() - bytes.as_slice().len() - 1;
+                let c = *bytes.next().ok_or_else(|| {
+                    UnquoteError::escape("quoted bytes cannot end in slash".into(), start..s.len())
+                })?;
+                match c {
                     c @ b'"' | c @ b'\\' | c @ b'/' | c @ b'\'' => res.push(c),
                     b'b' => res.push(b'\x08'),
                     b'f' => res.push(b'\x0C'),
@@ -27,15 +52,19 @@ fn unquote_bytes_inner(s: &str) -> Result<Vec<u8>, String> {
                     b'r' => res.push(b'\r'),
                     b't' => res.push(b'\t'),
                     b'x' => {
-                        let tail = &s[s.len() - bytes.as_slice().len()..];
+                        let tail = &s[start + 2..];
                         let hex = tail.get(0..2);
                         let code = hex
-                            .and_then(|s| u8::from_str_radix(s, 16).ok())
+                            .filter(|h| h.bytes().all(|b| b.is_ascii_hexdigit()))
+                            .and_then(|h| u8::from_str_radix(h, 16).ok())
                             .ok_or_else(|| {
-                                format!(
-                                    "invalid bytes literal: \
-                                invalid escape sequence '\\x{}'",
-                                    hex.unwrap_or(tail).escape_debug()
+                                UnquoteError::escape(
+                                    format!(
+                                        "invalid bytes literal: \
+                                        invalid escape sequence '\\x{}'",
+                                        hex.unwrap_or(tail).escape_debug()
+                                    ),
+                                    start..start + 2 + tail.len().min(2),
                                 )
                             })?;
                         res.push(code);
@@ -52,19 +81,13 @@ fn unquote_bytes_inner(s: &str) -> Result<Vec<u8>, String> {
                         }
                     }
                     c => {
-                        let ch = if c < 0x7f {
-                            c as char
-                        } else {
-                            // recover the unicode byte
-                            s[s.len() - bytes.as_slice().len() - 1..]
-                                .chars()
-                                .next()
-                                .unwrap()
-                        };
-                        return Err(format!(
-                            "invalid bytes literal: \
-                            invalid escape sequence '\\{}'",
-                            ch.escape_debug()
+                        return Err(UnquoteError::escape(
+                            format!(
+                                "invalid bytes literal: \
+                                invalid escape sequence '\\{}'",
+                                (c as char).escape_debug()
+                            ),
+                            start..start + 2,
                         ));
                     }
                 }
@@ -76,6 +99,37 @@ fn unquote_bytes_inner(s: &str) -> Result<Vec<u8>, String> {
     Ok(res)
 }
 
+#[test]
+fn bytes_errors() {
+    let err = unquote_bytes(r"b'ab\x4'").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid bytes literal: invalid escape sequence '\\x4'"
+    );
+    assert_eq!(err.escape_range(), Some(4..7));
+    let err = unquote_bytes(r"b'\x+1'").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid bytes literal: invalid escape sequence '\\x+1'"
+    );
+    assert_eq!(err.escape_range(), Some(2..6));
+    let err = unquote_bytes(r"b'\u0041'").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid bytes literal: invalid escape sequence '\\u'"
+    );
+    assert_eq!(err.escape_range(), Some(2..4));
+    assert_eq!(
+        unquote_bytes("rb'\u{2665}'").unwrap_err().to_string(),
+        "invalid bytes literal: character '\u{2665}' is unexpected, \
+         only ascii chars are allowed in bytes literals"
+    );
+    assert_eq!(
+        unquote_bytes("'abc'").unwrap_err().to_string(),
+        "prefix \"\" is not allowed for bytes, allowed: `b`, `rb`"
+    );
+}
+
 #[test]
 fn simple_bytes() {
     assert_eq!(unquote_bytes_inner(r"\x09").unwrap(), b"\x09");
@@ -2,14 +2,34 @@ use std::borrow::Cow;
 use std::char;
 use std::error::Error;
 use std::fmt::{self, Write};
+use std::ops::Range;
+use std::str::Chars;
 
 use crate::keywords;
 
-/// Error returned from `unquote_string` function
+/// Error returned from `unquote_string` and `unquote_bytes` functions
 ///
-/// Opaque for now
+/// Opaque for now, except for the location of an invalid escape sequence
 #[derive(Debug)]
-pub struct UnquoteError(String);
+pub struct UnquoteError {
+    message: String,
+    escape: Option<Range<usize>>,
+}
+
+/// Error of a `\u` or `\U` escape which is not a unicode scalar value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum UnicodeEscapeError {
+    #[error(
+        "invalid string literal: invalid escape sequence, \
+         U+{0:04X} is a surrogate code point"
+    )]
+    Surrogate(u32),
+    #[error(
+        "invalid string literal: invalid escape sequence, \
+         U+{0:04X} is beyond the maximum code point U+10FFFF"
+    )]
+    TooLarge(u32),
+}
 
 /// Converts the string into edgeql-compatible name (of a column or a property)
 ///
@@ -64,32 +84,127 @@ pub fn quote_string(s: &str) -> String {
 }
 
 pub fn unquote_string(value: &str) -> Result<Cow<str>, UnquoteError> {
+    #[cfg(feature = "future-syntax")]
+    if let Some(body) = heredoc_body(value) {
+        return Ok(dedent(body).into());
+    }
     if value.starts_with('r') {
         Ok(value[2..value.len() - 1].into())
+    } else if let Some(stripped) = value.strip_prefix('{') {
+        // SQL block, see `Tokenizer::with_sql_blocks`
+        Ok(stripped[..stripped.len() - 1].into())
     } else if let Some(stripped) = value.strip_prefix('$') {
         let msize = 2 + stripped
             .find('$')
-            .ok_or_else(|| "invalid dollar-quoted string".to_string())
-            .map_err(UnquoteError)?;
+            .ok_or_else(|| UnquoteError::new("invalid dollar-quoted string"))?;
         Ok(value[msize..value.len() - msize].into())
     } else {
         let end_trim = if value.ends_with("\\(") { 2 } else { 1 };
 
         Ok(_unquote_string(&value[1..value.len() - end_trim])
-            .map_err(UnquoteError)?
+            .map_err(|e| e.shifted(1))?
             .into())
     }
 }
 
-fn _unquote_string(s: &str) -> Result<String, String> {
+/// Decodes the code point of a `\u` or `\U` escape
+///
+/// # Examples
+/// ```
+/// use edgeql_parser::helpers::{unicode_escape, UnicodeEscapeError};
+/// assert_eq!(unicode_escape(0x2665), Ok('\u{2665}'));
+/// assert_eq!(unicode_escape(0xDC00), Err(UnicodeEscapeError::Surrogate(0xDC00)));
+/// assert_eq!(unicode_escape(0x110000), Err(UnicodeEscapeError::TooLarge(0x110000)));
+/// ```
+pub fn unicode_escape(code: u32) -> Result<char, UnicodeEscapeError> {
+    match code {
+        0xD800..=0xDFFF => Err(UnicodeEscapeError::Surrogate(code)),
+        0x110000.. => Err(UnicodeEscapeError::TooLarge(code)),
+        _ => Ok(char::from_u32(code).expect("scalar value")),
+    }
+}
+
+/// Returns the raw contents of a heredoc string literal (`"""xx"""` or
+/// `'''xx'''`), i.e. the text between the delimiters as written in the source
+#[cfg(feature = "future-syntax")]
+pub fn heredoc_body(value: &str) -> Option<&str> {
+    ["\"\"\"", "'''"]
+        .iter()
+        .find_map(|delim| value.strip_prefix(delim)?.strip_suffix(delim))
+}
+
+/// Removes indentation from the contents of a heredoc string
+///
+/// The line break right after the opening delimiter and the line of the
+/// closing delimiter (if it contains only whitespace) are removed, then the
+/// whitespace prefix common to all non-blank lines is stripped. Lines
+/// consisting of whitespace only become empty. No escapes are processed.
+///
+/// # Examples
+/// ```
+/// use edgeql_parser::helpers::dedent;
+/// assert_eq!(dedent("\n    Hello\n      world\n    "), "Hello\n  world");
+/// assert_eq!(dedent("no indent"), "no indent");
+/// ```
+#[cfg(feature = "future-syntax")]
+pub fn dedent(body: &str) -> String {
+    let body = body
+        .strip_prefix("\r\n")
+        .or_else(|| body.strip_prefix('\n'))
+        .unwrap_or(body);
+    let body = match body.rfind('\n') {
+        Some(idx) if body[idx + 1..].trim_matches([' ', '\t']).is_empty() => {
+            body[..idx].strip_suffix('\r').unwrap_or(&body[..idx])
+        }
+        _ => body,
+    };
+    let is_blank = |line: &str| line.trim_matches([' ', '\t', '\r']).is_empty();
+    let mut prefix: Option<&str> = None;
+    for line in body.split('\n').filter(|line| !is_blank(line)) {
+        let indent = &line[..line.len() - line.trim_start_matches([' ', '\t']).len()];
+        prefix = Some(match prefix {
+            None => indent,
+            Some(prefix) => {
+                let common = prefix
+                    .bytes()
+                    .zip(indent.bytes())
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                &prefix[..common]
+            }
+        });
+    }
+    let prefix = prefix.unwrap_or("");
+    let mut result = String::with_capacity(body.len());
+    for (idx, line) in body.split('\n').enumerate() {
+        if idx > 0 {
+            result.push('\n');
+        }
+        if !is_blank(line) {
+            result.push_str(&line[prefix.len()..]);
+        } else if line.ends_with('\r') {
+            result.push('\r');
+        }
+    }
+    result
+}
+
+fn _unquote_string(s: &str) -> Result<String, UnquoteError> {
     let mut res = String::with_capacity(s.len());
     let mut chars = s.chars();
     while let Some(c) = chars.next() {
         match c {
             '\\' => {
-                let c = chars
-                    .next()
-                    .ok_or_else(|| "quoted string cannot end in slash".to_string())?;
+                let start = s.len() - chars.as_str().len() - 1;
+                // the escape sequence up to `n` chars after the current one
+                let escape = |chars: &Chars, n: usize| {
+                    let rest = chars.as_str();
+                    let len: usize = rest.chars().take(n).map(char::len_utf8).sum();
+                    start..s.len() - rest.len() + len
+                };
+                let c = chars.next().ok_or_else(|| {
+                    UnquoteError::escape("quoted string cannot end in slash".into(), start..s.len())
+                })?;
                 match c {
                     c @ '"' | c @ '\\' | c @ '/' | c @ '\'' => res.push(c),
                     'b' => res.push('\u{0008}'),
@@ -102,53 +217,55 @@ fn _unquote_string(s: &str) -> Result<String, String> {
                         let code = hex
                             .and_then(|s| u8::from_str_radix(s, 16).ok())
                             .ok_or_else(|| {
-                                format!(
-                                    "invalid string literal: \
-                                invalid escape sequence '\\x{}'",
-                                    hex.unwrap_or(chars.as_str()).escape_debug()
+                                UnquoteError::escape(
+                                    format!(
+                                        "invalid string literal: \
+                                    invalid escape sequence '\\x{}'",
+                                        hex.unwrap_or(chars.as_str()).escape_debug()
+                                    ),
+                                    escape(&chars, 2),
                                 )
                             })?;
                         if code > 0x7f || code == 0 {
-                            return Err(format!(
-                                "invalid string literal: \
-                                 invalid escape sequence '\\x{code:x}' \
-                                 (only non-null ascii allowed)"
+                            return Err(UnquoteError::escape(
+                                format!(
+                                    "invalid string literal: \
+                                     invalid escape sequence '\\x{code:x}' \
+                                     (only non-null ascii allowed)"
+                                ),
+                                escape(&chars, 2),
                             ));
                         }
                         res.push(code as char);
                         chars.nth(1);
                     }
-                    'u' => {
-                        let hex = chars.as_str().get(0..4);
-                        let ch = hex
+                    'u' | 'U' => {
+                        let digits = if c == 'u' { 4 } else { 8 };
+                        let hex = chars.as_str().get(0..digits);
+                        let ch = match hex
                             .and_then(|s| u32::from_str_radix(s, 16).ok())
-                            .and_then(char::from_u32)
-                            .and_then(|c| if c == '\0' { None } else { Some(c) })
-                            .ok_or_else(|| {
-                                format!(
-                                    "invalid string literal: \
-                                    invalid escape sequence '\\u{}'",
-                                    hex.unwrap_or(chars.as_str()).escape_debug()
-                                )
-                            })?;
-                        res.push(ch);
-                        chars.nth(3);
-                    }
-                    'U' => {
-                        let hex = chars.as_str().get(0..8);
-                        let ch = hex
-                            .and_then(|s| u32::from_str_radix(s, 16).ok())
-                            .and_then(char::from_u32)
-                            .and_then(|c| if c == '\0' { None } else { Some(c) })
-                            .ok_or_else(|| {
-                                format!(
-                                    "invalid string literal: \
-                                    invalid escape sequence '\\U{}'",
-                                    hex.unwrap_or(chars.as_str()).escape_debug()
-                                )
-                            })?;
+                            .map(unicode_escape)
+                        {
+                            Some(Ok(ch)) if ch != '\0' => ch,
+                            Some(Err(e)) => {
+                                return Err(UnquoteError::escape(
+                                    e.to_string(),
+                                    escape(&chars, digits),
+                                ));
+                            }
+                            _ => {
+                                return Err(UnquoteError::escape(
+                                    format!(
+                                        "invalid string literal: \
+                                        invalid escape sequence '\\{c}{}'",
+                                        hex.unwrap_or(chars.as_str()).escape_debug()
+                                    ),
+                                    escape(&chars, digits),
+                                ));
+                            }
+                        };
                         res.push(ch);
-                        chars.nth(7);
+                        chars.nth(digits - 1);
                     }
                     '\r' | '\n' => {
                         let nleft = chars.as_str().trim_start().len();
@@ -158,10 +275,13 @@ fn _unquote_string(s: &str) -> Result<String, String> {
                         }
                     }
                     c => {
-                        return Err(format!(
-                            "invalid string literal: \
-                             invalid escape sequence '\\{}'",
-                            c.escape_debug()
+                        return Err(UnquoteError::escape(
+                            format!(
+                                "invalid string literal: \
+                                 invalid escape sequence '\\{}'",
+                                c.escape_debug()
+                            ),
+                            escape(&chars, 0),
                         ));
                     }
                 }
@@ -185,18 +305,41 @@ fn unquote_unicode_string() {
 #[test]
 fn unquote_string_error() {
     assert_eq!(
-        _unquote_string(r"\x00").unwrap_err(),
+        _unquote_string(r"\x00").unwrap_err().to_string(),
         "invalid string literal: \
              invalid escape sequence '\\x0' (only non-null ascii allowed)"
     );
     assert_eq!(
-        _unquote_string(r"\u0000").unwrap_err(),
+        _unquote_string(r"\u0000").unwrap_err().to_string(),
         "invalid string literal: invalid escape sequence '\\u0000'"
     );
     assert_eq!(
-        _unquote_string(r"\U00000000").unwrap_err(),
+        _unquote_string(r"\U00000000").unwrap_err().to_string(),
         "invalid string literal: invalid escape sequence '\\U00000000'"
     );
+    assert_eq!(
+        _unquote_string(r"\uDFFF").unwrap_err().to_string(),
+        "invalid string literal: invalid escape sequence, \
+             U+DFFF is a surrogate code point"
+    );
+    assert_eq!(
+        _unquote_string(r"\U00110000").unwrap_err().to_string(),
+        "invalid string literal: invalid escape sequence, \
+             U+110000 is beyond the maximum code point U+10FFFF"
+    );
+    assert_eq!(_unquote_string(r"\U0010FFFF").unwrap(), "\u{10FFFF}");
+}
+
+#[test]
+fn unquote_string_error_range() {
+    let range = |s| unquote_string(s).unwrap_err().escape_range();
+    assert_eq!(range(r#""ab\qcd""#), Some(3..5));
+    assert_eq!(range(r#""\x0g tail""#), Some(1..5));
+    assert_eq!(range(r#""\x8""#), Some(1..4));
+    assert_eq!(range(r#"'é\uD800é'"#), Some(3..9));
+    assert_eq!(range(r#""\U0011ffff""#), Some(1..11));
+    assert_eq!(range(r#""\é""#), Some(1..4));
+    assert_eq!(range("$abc"), None);
 }
 
 #[test]
@@ -260,9 +403,38 @@ fn complex_strings() {
     );
 }
 
+impl UnquoteError {
+    pub(super) fn new(message: impl Into<String>) -> UnquoteError {
+        UnquoteError {
+            message: message.into(),
+            escape: None,
+        }
+    }
+
+    pub(super) fn escape(message: String, escape: Range<usize>) -> UnquoteError {
+        UnquoteError {
+            message,
+            escape: Some(escape),
+        }
+    }
+
+    pub(super) fn shifted(mut self, offset: usize) -> UnquoteError {
+        self.escape = self
+            .escape
+            .map(|range| range.start + offset..range.end + offset);
+        self
+    }
+
+    /// Byte range of the invalid escape sequence within the quoted literal,
+    /// if the error was caused by one
+    pub fn escape_range(&self) -> Option<Range<usize>> {
+        self.escape.clone()
+    }
+}
+
 impl fmt::Display for UnquoteError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        self.0.fmt(f)
+        self.message.fmt(f)
     }
 }
 impl Error for UnquoteError {}
@@ -0,0 +1,535 @@
+//! Chains of indexing operations, like `x['a'][0]['b']` or `arr[1:-1]`
+//!
+//! Chains are recognized on tokens, so they can be inspected (e.g. by JSON
+//! path tooling) and re-rendered without the full grammar. They are a view
+//! of the tokens for tooling, the parser does not use them. Only paths,
+//! calls, literals and bracketed expressions are recognized as the indexed
+//! operand, so e.g. indexing a set constructor is not found.
+//!
+//! [check_slices] reports constant indexes out of the bounds of array and
+//! string literals, and slices with constant bounds that are always empty.
+//! This is a lint: the grammar accepts such indexes, which fail (or return
+//! nothing) only when the query runs.
+use std::fmt::Write;
+
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::position::Span;
+use crate::schema::{is_name_like, Expr};
+use crate::tokenizer::{Kind, Token};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexChain {
+    /// Span of the expression being indexed
+    pub base: Span,
+    pub steps: Vec<IndexStep>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum IndexStep {
+    /// `[expr]`
+    Index { index: Expr, span: Span },
+    /// `[start:stop]`, either bound is optional
+    Slice {
+        start: Option<Expr>,
+        stop: Option<Expr>,
+        span: Span,
+    },
+}
+
+impl IndexStep {
+    pub fn span(&self) -> Span {
+        match self {
+            IndexStep::Index { span, .. } | IndexStep::Slice { span, .. } => *span,
+        }
+    }
+}
+
+impl IndexChain {
+    /// Renders the chain in canonical form, `source` is the text the tokens
+    /// were produced from
+    pub fn to_edgeql(&self, source: &str) -> String {
+        let mut buf = text(source, self.base).to_string();
+        for step in &self.steps {
+            match step {
+                IndexStep::Index { index, .. } => {
+                    write!(buf, "[{}]", text(source, index.span)).unwrap();
+                }
+                IndexStep::Slice { start, stop, .. } => {
+                    buf.push('[');
+                    if let Some(start) = start {
+                        buf.push_str(text(source, start.span));
+                    }
+                    buf.push(':');
+                    if let Some(stop) = stop {
+                        buf.push_str(text(source, stop.span));
+                    }
+                    buf.push(']');
+                }
+            }
+        }
+        buf
+    }
+
+    /// Returns string literal keys and integer indexes of the chain, if it
+    /// consists only of them, i.e. it is a JSON path
+    pub fn json_path(&self) -> Option<Vec<PathKey>> {
+        self.steps
+            .iter()
+            .map(|step| {
+                let IndexStep::Index { index, .. } = step else {
+                    return None;
+                };
+                constant_key(index)
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathKey {
+    Key(String),
+    Position(i64),
+}
+
+fn constant_key(index: &Expr) -> Option<PathKey> {
+    match &index.tokens[..] {
+        [tok] => literal_key(tok, false),
+        [minus, tok] if minus.kind == Kind::Sub => literal_key(tok, true),
+        _ => None,
+    }
+}
+
+/// Value of an integer constant, possibly negative, like `-1`
+fn int_constant(expr: &Expr) -> Option<i64> {
+    match constant_key(expr)? {
+        PathKey::Position(value) => Some(value),
+        PathKey::Key(_) => None,
+    }
+}
+
+fn literal_key(tok: &Token, negative: bool) -> Option<PathKey> {
+    use crate::tokenizer::Value;
+
+    match (&tok.kind, &tok.value) {
+        (Kind::Str, Some(Value::String(s))) if !negative => Some(PathKey::Key(s.clone())),
+        (Kind::IntConst, Some(Value::Int(v))) => {
+            Some(PathKey::Position(if negative { -v } else { *v }))
+        }
+        _ => None,
+    }
+}
+
+fn text(source: &str, span: Span) -> &str {
+    &source[span.start as usize..span.end as usize]
+}
+
+/// Finds all indexing chains in the tokens, in order of their start
+pub fn find_chains(tokens: &[Token]) -> Vec<IndexChain> {
+    let mut chains = Vec::new();
+    let mut pos = 0;
+    while pos < tokens.len() {
+        if tokens[pos].kind == Kind::OpenBracket && pos > 0 && is_indexable(tokens, pos) {
+            if let Some(chain) = read_chain(tokens, pos) {
+                chains.push(chain);
+            }
+        }
+        pos += 1;
+    }
+    // a chain is found at each of its brackets, keep only the full one
+    let mut result: Vec<IndexChain> = Vec::new();
+    for chain in chains {
+        let is_suffix = result
+            .iter()
+            .any(|c| c.span.end == chain.span.end && c.span.start <= chain.span.start);
+        if !is_suffix {
+            result.push(chain);
+        }
+    }
+    result
+}
+
+/// Reports casts of query parameters followed by indexing: `<json>$x['a']`
+/// indexes the parameter before it is cast, which fails with an error about
+/// a missing cast.
+pub fn check_casts(tokens: &[Token]) -> Vec<Diagnostic> {
+    let mut errors = Vec::new();
+    for chain in find_chains(tokens) {
+        let Some(pos) = tokens.iter().position(|t| t.span == chain.base) else {
+            continue;
+        };
+        let is_param = tokens[pos].kind == Kind::Parameter;
+        let after_cast = pos > 0 && tokens[pos - 1].kind == Kind::Greater;
+        if is_param && after_cast {
+            let error = Diagnostic::with_code(
+                Severity::Error,
+                "index-before-cast",
+                &[("param", tokens[pos].text.to_string())],
+                chain.span,
+            )
+            .with_hint();
+            errors.push(error);
+        }
+    }
+    errors
+}
+
+/// Reports constant indexes out of the bounds of array and string literals,
+/// and slices that are always empty: slices with constant bounds of the
+/// same sign where the start is not before the stop, like `x[3:1]` or
+/// `x[-1:-2]`, and slices of literals that select nothing, like
+/// `[1, 2][2:]`
+pub fn check_slices(tokens: &[Token]) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    for chain in find_chains(tokens) {
+        let Some(pos) = tokens.iter().position(|t| t.span.start == chain.base.start) else {
+            continue;
+        };
+        // only the first step indexes the literal
+        let mut length = literal_length(tokens, pos, chain.base);
+        for step in &chain.steps {
+            match step {
+                IndexStep::Index { index, span } => {
+                    let out_of_bounds = int_constant(index)
+                        .zip(length)
+                        .filter(|&(index, (_, len))| index >= len || index < -len);
+                    if let Some((index, (kind, len))) = out_of_bounds {
+                        diagnostics.push(Diagnostic::with_code(
+                            Severity::Error,
+                            "index-out-of-bounds",
+                            &[
+                                ("index", index.to_string()),
+                                ("kind", kind.to_string()),
+                                ("len", len.to_string()),
+                            ],
+                            *span,
+                        ));
+                    }
+                }
+                IndexStep::Slice { start, stop, span } => {
+                    let start = start.as_ref().map(int_constant);
+                    let stop = stop.as_ref().map(int_constant);
+                    if is_empty_slice(start, stop, length.map(|(_, len)| len)) {
+                        diagnostics.push(Diagnostic::with_code(
+                            Severity::Warning,
+                            "empty-slice",
+                            &[],
+                            *span,
+                        ));
+                    }
+                }
+            }
+            length = None;
+        }
+    }
+    diagnostics
+}
+
+/// Whether a slice selects nothing, bounds are `None` if they are omitted
+/// and `Some(None)` if they are not constant
+fn is_empty_slice(
+    start: Option<Option<i64>>,
+    stop: Option<Option<i64>>,
+    length: Option<i64>,
+) -> bool {
+    if let Some(len) = length {
+        let normalize = |bound: i64| {
+            if bound < 0 {
+                (bound + len).max(0)
+            } else {
+                bound.min(len)
+            }
+        };
+        let start = match start {
+            None => 0,
+            Some(Some(start)) => normalize(start),
+            Some(None) => return false,
+        };
+        let stop = match stop {
+            None => len,
+            Some(Some(stop)) => normalize(stop),
+            Some(None) => return false,
+        };
+        return start >= stop;
+    }
+    match (start, stop) {
+        (_, Some(Some(0))) => true,
+        (Some(Some(start)), Some(Some(stop))) => (start < 0) == (stop < 0) && start >= stop,
+        _ => false,
+    }
+}
+
+/// Kind and length of the array or string literal the base of a chain
+/// consists of, possibly in parentheses
+fn literal_length(tokens: &[Token], start: usize, base: Span) -> Option<(&'static str, i64)> {
+    use crate::tokenizer::Value;
+
+    let end = tokens[start..]
+        .iter()
+        .position(|t| t.span.end == base.end)?
+        + start;
+    let (mut first, mut last) = (start, end);
+    while tokens[first].kind == Kind::OpenParen
+        && tokens[last].kind == Kind::CloseParen
+        && matching_open(tokens, last) == Some(first)
+    {
+        first += 1;
+        last -= 1;
+    }
+    if first == last {
+        return match (&tokens[first].kind, &tokens[first].value) {
+            (Kind::Str, Some(Value::String(s))) => Some(("string", s.chars().count() as i64)),
+            _ => None,
+        };
+    }
+    let is_array = tokens[first].kind == Kind::OpenBracket
+        && tokens[last].kind == Kind::CloseBracket
+        && matching_open(tokens, last) == Some(first)
+        && (first == 0 || !is_indexable(tokens, first));
+    if !is_array {
+        return None;
+    }
+    if first + 1 == last {
+        return Some(("array", 0));
+    }
+    let mut depth = 0usize;
+    let mut elements = 1;
+    for tok in &tokens[first + 1..last] {
+        match tok.kind {
+            Kind::OpenBracket | Kind::OpenParen | Kind::OpenBrace => depth += 1,
+            Kind::CloseBracket | Kind::CloseParen | Kind::CloseBrace => depth -= 1,
+            Kind::Comma if depth == 0 => elements += 1,
+            _ => {}
+        }
+    }
+    if tokens[last - 1].kind == Kind::Comma {
+        elements -= 1;
+    }
+    Some(("array", elements))
+}
+
+fn is_indexable(tokens: &[Token], bracket: usize) -> bool {
+    // type intersection: `.friends[is User]`
+    if matches!(tokens.get(bracket + 1).map(|t| t.kind), Some(Kind::Keyword(kw)) if kw.0 == "is") {
+        return false;
+    }
+    let prev = &tokens[bracket - 1];
+    match prev.kind {
+        Kind::CloseParen | Kind::CloseBracket | Kind::Str | Kind::Parameter => true,
+        Kind::Ident => true,
+        kind @ Kind::Keyword(_) => is_name_like(Some(kind)),
+        _ => false,
+    }
+}
+
+/// Returns the index of the first token of the operand ending at `end`
+fn operand_start(tokens: &[Token], end: usize) -> usize {
+    let mut pos = end;
+    loop {
+        if matches!(tokens[pos].kind, Kind::CloseParen | Kind::CloseBracket) {
+            let Some(open) = matching_open(tokens, pos) else {
+                return pos;
+            };
+            pos = open;
+            let is_call = tokens[pos].kind == Kind::OpenParen
+                && pos > 0
+                && is_name_like(Some(tokens[pos - 1].kind));
+            let is_index =
+                tokens[pos].kind == Kind::OpenBracket && pos > 0 && is_indexable(tokens, pos);
+            if is_call || is_index {
+                pos -= 1;
+                continue;
+            }
+            return pos;
+        }
+        // paths: `a.b.c`, `mod::name`, `.<back`
+        let is_step = pos >= 1
+            && matches!(
+                tokens[pos - 1].kind,
+                Kind::Dot | Kind::Namespace | Kind::OptionalLink | Kind::BackwardLink
+            );
+        if !is_step {
+            return pos;
+        }
+        let prev = &tokens.get(pos.wrapping_sub(2)).map(|t| t.kind);
+        if pos >= 2
+            && (is_name_like(*prev) || matches!(prev, Some(Kind::CloseParen | Kind::CloseBracket)))
+        {
+            pos -= 2;
+        } else {
+            return pos - 1;
+        }
+    }
+}
+
+fn matching_open(tokens: &[Token], close: usize) -> Option<usize> {
+    let mut depth = 0usize;
+    for i in (0..=close).rev() {
+        match tokens[i].kind {
+            Kind::CloseBracket | Kind::CloseParen | Kind::CloseBrace => depth += 1,
+            Kind::OpenBracket | Kind::OpenParen | Kind::OpenBrace => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+fn read_chain(tokens: &[Token], first_bracket: usize) -> Option<IndexChain> {
+    let base_start = operand_start(tokens, first_bracket - 1);
+    let base = tokens[base_start]
+        .span
+        .extend(&tokens[first_bracket - 1].span);
+    let mut steps = Vec::new();
+    let mut pos = first_bracket;
+    while tokens.get(pos).map(|t| t.kind) == Some(Kind::OpenBracket) {
+        let close = matching_bracket(tokens, pos)?;
+        let span = tokens[pos].span.extend(&tokens[close].span);
+        let inner = &tokens[pos + 1..close];
+        let colon = top_level_colon(inner);
+        let step = match colon {
+            Some(colon) => IndexStep::Slice {
+                start: expr(&inner[..colon]),
+                stop: expr(&inner[colon + 1..]),
+                span,
+            },
+            None => IndexStep::Index {
+                index: expr(inner)?,
+                span,
+            },
+        };
+        steps.push(step);
+        pos = close + 1;
+    }
+    let span = base.extend(&steps.last()?.span());
+    Some(IndexChain { base, steps, span })
+}
+
+fn matching_bracket(tokens: &[Token], open: usize) -> Option<usize> {
+    let mut depth = 0usize;
+    for (i, tok) in tokens.iter().enumerate().skip(open) {
+        match tok.kind {
+            Kind::OpenBracket | Kind::OpenParen | Kind::OpenBrace => depth += 1,
+            Kind::CloseBracket | Kind::CloseParen | Kind::CloseBrace => {
+                depth -= 1;
+                if depth == 0 {
+                    return (tok.kind == Kind::CloseBracket).then_some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+fn top_level_colon(tokens: &[Token]) -> Option<usize> {
+    let mut depth = 0usize;
+    for (i, tok) in tokens.iter().enumerate() {
+        match tok.kind {
+            Kind::OpenBracket | Kind::OpenParen | Kind::OpenBrace => depth += 1,
+            Kind::CloseBracket | Kind::CloseParen | Kind::CloseBrace => {
+                depth = depth.saturating_sub(1)
+            }
+            Kind::Colon if depth == 0 => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+fn expr(tokens: &[Token]) -> Option<Expr> {
+    let span = tokens.first()?.span.extend(&tokens.last()?.span);
+    Some(Expr {
+        tokens: tokens.iter().map(|t| t.clone().cloned()).collect(),
+        span,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{check_casts, check_slices, find_chains, IndexStep, PathKey};
+    use crate::tokenizer::tokens;
+
+    #[test]
+    fn chains() {
+        let text = "select (User.data['a'][0] ++ to_json('[]')['x'], .tags[1:-1], .x[is Y])";
+        let chains = find_chains(&tokens(text));
+        assert_eq!(chains.len(), 3);
+
+        let first = &chains[0];
+        assert_eq!(first.to_edgeql(text), "User.data['a'][0]");
+        assert_eq!(
+            first.json_path(),
+            Some(vec![PathKey::Key("a".into()), PathKey::Position(0)])
+        );
+
+        assert_eq!(chains[1].to_edgeql(text), "to_json('[]')['x']");
+
+        let slice = &chains[2];
+        assert_eq!(slice.to_edgeql(text), ".tags[1:-1]");
+        assert!(matches!(
+            &slice.steps[0],
+            IndexStep::Slice {
+                start: Some(_),
+                stop: Some(_),
+                ..
+            }
+        ));
+        assert_eq!(slice.json_path(), None);
+    }
+
+    #[test]
+    fn casts() {
+        let text = "select <json>$data['a']['b']";
+        let errors = check_casts(&tokens(text));
+        assert_eq!(errors.len(), 1);
+        let span = errors[0].span;
+        assert_eq!(
+            &text[span.start as usize..span.end as usize],
+            "$data['a']['b']"
+        );
+        assert_eq!(
+            errors[0].hint.as_deref(),
+            Some("wrap the cast in parenthesis: `(<...>$data)[...]`")
+        );
+        assert!(check_casts(&tokens("select (<json>$data)['a']")).is_empty());
+    }
+
+    #[test]
+    fn slices() {
+        let check = |text: &str| -> Vec<String> {
+            check_slices(&tokens(text))
+                .into_iter()
+                .map(|d| {
+                    let span = d.span;
+                    format!(
+                        "{} @{}",
+                        d.message,
+                        &text[span.start as usize..span.end as usize]
+                    )
+                })
+                .collect()
+        };
+        assert_eq!(
+            check("select (.tags[3:1], .tags[-1:-3], .tags[:0], .tags[-1:2], .tags[1:])"),
+            [
+                "slice is always empty @[3:1]",
+                "slice is always empty @[-1:-3]",
+                "slice is always empty @[:0]",
+            ]
+        );
+        assert_eq!(
+            check("select ([1, 2, 3][3], ([1, [2, 3]])[-2], 'abc'[-4], [1, 2][2:], 'ab'[-5:1])"),
+            [
+                "index 3 is out of bounds for array of length 3 @[3]",
+                "index -4 is out of bounds for string of length 3 @[-4]",
+                "slice is always empty @[2:]",
+            ]
+        );
+        assert!(check("select ([1, 2][1:][0], 'ab'[$i], .tags[$a:$b], [1][0])").is_empty());
+    }
+}
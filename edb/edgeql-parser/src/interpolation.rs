@@ -0,0 +1,170 @@
+//! Interpolated string literals: `"Hello, \(.name)!"`
+//!
+//! The tokenizer splits them into `StrInterpStart`, `StrInterpCont` and
+//! `StrInterpEnd` tokens around the expressions, and the grammar builds
+//! `StrInterp` of the python AST from them. This module reads the same
+//! structure from tokens, for tools that do not run the grammar.
+use crate::position::Span;
+use crate::schema::Expr;
+use crate::tokenizer::{Error, Kind, Token, Tokenizer, Value};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StrInterp {
+    /// Literal text before the first interpolation
+    pub prefix: String,
+    pub interpolations: Vec<StrInterpFragment>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StrInterpFragment {
+    pub expr: Expr,
+    /// Literal text after the expression till the next interpolation or the
+    /// end of the string
+    pub suffix: String,
+    pub span: Span,
+}
+
+impl StrInterp {
+    /// Returns literal text with interpolated expressions replaced by the
+    /// result of `render`
+    pub fn render(&self, mut render: impl FnMut(&Expr) -> String) -> String {
+        let mut buf = self.prefix.clone();
+        for fragment in &self.interpolations {
+            buf.push_str(&render(&fragment.expr));
+            buf.push_str(&fragment.suffix);
+        }
+        buf
+    }
+}
+
+/// Tokenizes the text and returns all interpolated strings in it
+pub fn parse(text: &str) -> Result<Vec<StrInterp>, Error> {
+    let tokens = Tokenizer::new(text)
+        .validated_values()
+        .collect::<Result<Vec<_>, _>>()?;
+    find_interpolations(&tokens)
+}
+
+/// Returns interpolated strings in the tokens, nested ones are only
+/// available through expressions of the outer ones
+pub fn find_interpolations(tokens: &[Token]) -> Result<Vec<StrInterp>, Error> {
+    let mut result = Vec::new();
+    let mut pos = 0;
+    while pos < tokens.len() {
+        match tokens[pos].kind {
+            Kind::StrInterpStart => {
+                let (interp, end) = read_interp(tokens, pos)?;
+                result.push(interp);
+                pos = end;
+            }
+            Kind::StrInterpCont | Kind::StrInterpEnd => {
+                return Err(Error::new("unexpected end of string interpolation")
+                    .with_span(tokens[pos].span));
+            }
+            _ => pos += 1,
+        }
+    }
+    Ok(result)
+}
+
+/// Reads interpolation starting at `start`, returns it and the index of the
+/// token after it
+fn read_interp(tokens: &[Token], start: usize) -> Result<(StrInterp, usize), Error> {
+    let prefix = string_value(&tokens[start]);
+    let mut interpolations = Vec::new();
+    let mut pos = start + 1;
+    loop {
+        let expr_start = pos;
+        let mut depth = 0usize;
+        let end = loop {
+            let Some(tok) = tokens.get(pos) else {
+                return Err(
+                    Error::new("unterminated string interpolation").with_span(tokens[start].span)
+                );
+            };
+            match tok.kind {
+                Kind::StrInterpStart => depth += 1,
+                Kind::StrInterpCont if depth == 0 => break pos,
+                Kind::StrInterpEnd if depth == 0 => break pos,
+                Kind::StrInterpEnd => depth -= 1,
+                _ => {}
+            }
+            pos += 1;
+        };
+        let expr_tokens = &tokens[expr_start..end];
+        let (Some(first), Some(last)) = (expr_tokens.first(), expr_tokens.last()) else {
+            return Err(
+                Error::new("empty expression in string interpolation").with_span(tokens[end].span)
+            );
+        };
+        let expr = Expr {
+            tokens: expr_tokens.iter().map(|t| t.clone().cloned()).collect(),
+            span: first.span.extend(&last.span),
+        };
+        interpolations.push(StrInterpFragment {
+            span: expr.span.extend(&tokens[end].span),
+            expr,
+            suffix: string_value(&tokens[end]),
+        });
+        pos = end + 1;
+        if tokens[end].kind == Kind::StrInterpEnd {
+            let span = tokens[start].span.extend(&tokens[end].span);
+            let interp = StrInterp {
+                prefix,
+                interpolations,
+                span,
+            };
+            return Ok((interp, pos));
+        }
+    }
+}
+
+fn string_value(token: &Token) -> String {
+    match &token.value {
+        Some(Value::String(s)) => s.clone(),
+        _ => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::parse;
+
+    #[test]
+    fn parts() {
+        let text = r#"select "Hello, \(.first_name) \(.last_name)!""#;
+        let interps = parse(text).unwrap();
+        assert_eq!(interps.len(), 1);
+        let interp = &interps[0];
+        assert_eq!(interp.prefix, "Hello, ");
+        assert_eq!(interp.interpolations.len(), 2);
+        assert_eq!(interp.interpolations[0].suffix, " ");
+        assert_eq!(interp.interpolations[1].suffix, "!");
+        let span = interp.interpolations[1].expr.span;
+        assert_eq!(&text[span.start as usize..span.end as usize], ".last_name");
+        assert_eq!(
+            interp.render(|e| format!("{{{}}}", e.tokens.len())),
+            "Hello, {2} {2}!"
+        );
+        assert_eq!(&text[interp.span.start as usize..], &text[7..]);
+    }
+
+    #[test]
+    fn nested() {
+        let text = r#"select 'a\("b\(1 + 2)c")d', "\n\(x)""#;
+        let interps = parse(text).unwrap();
+        assert_eq!(interps.len(), 2);
+        let outer = &interps[0];
+        assert_eq!(outer.prefix, "a");
+        assert_eq!(outer.interpolations[0].suffix, "d");
+        assert_eq!(outer.interpolations[0].expr.tokens.len(), 5);
+        assert_eq!(interps[1].prefix, "\n");
+    }
+
+    #[test]
+    fn empty() {
+        let err = parse(r#"select "x\()""#).unwrap_err();
+        assert_eq!(err.message, "empty expression in string interpolation");
+    }
+}
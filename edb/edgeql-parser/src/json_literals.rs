@@ -0,0 +1,146 @@
+//! Validation of string literals cast to `json`
+//!
+//! [check_json] parses string literals cast to `json` (like `<json>'{"a":
+//! 1}'`) and reports the parse errors that the server would report when the
+//! query runs. The span of an error points to the character inside of the
+//! literal, unless the literal has escape sequences, in which case it
+//! covers the whole literal.
+use crate::casts::is_cast_start;
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::keywords::Keyword;
+use crate::position::Span;
+use crate::tokenizer::{Kind, Token, Value};
+
+/// Reports string literals cast to `json` that are not valid JSON
+pub fn check_json(tokens: &[Token]) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    for pos in 0..tokens.len() {
+        let Some(literal) = json_cast(tokens, pos) else {
+            continue;
+        };
+        let Some(Value::String(value)) = &literal.value else {
+            continue;
+        };
+        let Err(err) = serde_json::from_str::<serde_json::Value>(value) else {
+            continue;
+        };
+        let position = format!(" at line {} column {}", err.line(), err.column());
+        let message = err.to_string();
+        let message = message.strip_suffix(&position).unwrap_or(&message);
+        let offset = offset(value, err.line(), err.column());
+        let span = inner_span(literal, value, offset).unwrap_or(literal.span);
+        diagnostics.push(Diagnostic::with_code(
+            Severity::Error,
+            "invalid-json",
+            &[("error", message.into())],
+            span,
+        ));
+    }
+    diagnostics
+}
+
+/// The string literal of `<json>'...'` or `<optional std::json>'...'`
+/// starting at `pos`
+fn json_cast<'a, 't>(tokens: &'a [Token<'t>], pos: usize) -> Option<&'a Token<'t>> {
+    if !is_cast_start(tokens, pos) {
+        return None;
+    }
+    let mut rest = &tokens[pos + 1..];
+    if rest.first()?.kind == Kind::Keyword(Keyword("optional")) {
+        rest = &rest[1..];
+    }
+    let rest = match rest {
+        [std, ns, json, ..]
+            if std.text == "std" && ns.kind == Kind::Namespace && json.text == "json" =>
+        {
+            &rest[3..]
+        }
+        [json, ..] if json.kind == Kind::Ident && json.text == "json" => &rest[1..],
+        _ => return None,
+    };
+    match rest {
+        [close, literal, ..] if close.kind == Kind::Greater && literal.kind == Kind::Str => {
+            Some(literal)
+        }
+        _ => None,
+    }
+}
+
+/// Byte offset in the text of a 1-based line and column
+fn offset(text: &str, line: usize, column: usize) -> usize {
+    let line_start = text
+        .split_inclusive('\n')
+        .take(line.saturating_sub(1))
+        .map(str::len)
+        .sum::<usize>();
+    (line_start + column.saturating_sub(1)).min(text.len())
+}
+
+/// Span of the character at `offset` of the value in the source, `None` if
+/// the value is not the same as the source text of the literal
+fn inner_span(literal: &Token, value: &str, offset: usize) -> Option<Span> {
+    let text = &literal.text[..];
+    let (raw, text) = match text.strip_prefix('r') {
+        Some(rest) => (true, rest),
+        None => (text.starts_with('$'), text),
+    };
+    let quote = if text.starts_with("'''") || text.starts_with("\"\"\"") {
+        3
+    } else if text.starts_with('$') {
+        text[1..].find('$')? + 2
+    } else {
+        1
+    };
+    let prefix = literal.text.len() - text.len() + quote;
+    let content = literal.text.get(prefix..prefix + value.len())?;
+    if content != value || (!raw && content.contains('\\')) {
+        return None;
+    }
+    let len = value[offset..].chars().next().map_or(0, char::len_utf8);
+    let start = literal.span.start + (prefix + offset) as u64;
+    Some(Span {
+        start,
+        end: start + len as u64,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::check_json;
+    use crate::tokenizer::tokens;
+
+    fn errors(query: &str) -> Vec<String> {
+        let tokens = tokens(query);
+        check_json(&tokens)
+            .into_iter()
+            .map(|e| {
+                let span = e.span;
+                format!(
+                    "{} @{}",
+                    e.message,
+                    &query[span.start as usize..span.end as usize]
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn literals() {
+        assert!(errors(r#"select (<json>'{"a": [1, 2]}', <json>'1', <str>'{')"#).is_empty());
+        assert_eq!(
+            errors(r#"select <json>'{"a": [1, 2}', <optional std::json>r'{"b": tru}'"#),
+            [
+                "invalid JSON: expected `,` or `]` @}",
+                "invalid JSON: expected ident @}",
+            ]
+        );
+        assert_eq!(
+            errors("select <json>$${\"a\":\n  x}$$"),
+            ["invalid JSON: expected value @x"]
+        );
+        assert_eq!(
+            errors(r#"select <json>'{"a\u0041": }'"#),
+            [r#"invalid JSON: expected value @'{"a\u0041": }'"#]
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use phf::phf_set;
+use phf::{phf_map, phf_set};
 
 pub const UNRESERVED_KEYWORDS: phf::Set<&str> = phf_set!(
     "abort",
@@ -249,6 +249,58 @@ impl Keyword {
     pub fn is_bool(&self) -> bool {
         self.0 == "true" || self.0 == "false"
     }
+
+    /// Set of keywords this one belongs to, `None` for unknown keywords
+    pub fn category(&self) -> Option<Category> {
+        if CURRENT_RESERVED_KEYWORDS.contains(self.0) {
+            Some(Category::Reserved)
+        } else if FUTURE_RESERVED_KEYWORDS.contains(self.0) {
+            Some(Category::FutureReserved)
+        } else if PARTIAL_RESERVED_KEYWORDS.contains(self.0) {
+            Some(Category::PartialReserved)
+        } else if UNRESERVED_KEYWORDS.contains(self.0) {
+            Some(Category::Unreserved)
+        } else if COMBINED_KEYWORDS.contains(self.0) {
+            Some(Category::Combined)
+        } else {
+            None
+        }
+    }
+
+    /// Short description and documentation page of the keyword, for hover
+    /// and help texts
+    pub fn docs(&self) -> Option<&'static Docs> {
+        KEYWORD_DOCS.get(self.0)
+    }
+}
+
+/// Keyword sets, see [Keyword::category]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Category {
+    /// [CURRENT_RESERVED_KEYWORDS]
+    Reserved,
+    /// [FUTURE_RESERVED_KEYWORDS]
+    FutureReserved,
+    /// [PARTIAL_RESERVED_KEYWORDS]
+    PartialReserved,
+    /// [UNRESERVED_KEYWORDS]
+    Unreserved,
+    /// [COMBINED_KEYWORDS]
+    Combined,
+}
+
+impl Category {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Category::Reserved => "reserved",
+            Category::FutureReserved => "future_reserved",
+            Category::PartialReserved => "partial_reserved",
+            Category::Unreserved => "unreserved",
+            Category::Combined => "combined",
+        }
+    }
 }
 
 impl From<Keyword> for &'static str {
@@ -256,3 +308,290 @@ impl From<Keyword> for &'static str {
         value.0
     }
 }
+
+/// Base of the [Docs::url]
+pub const DOCS_URL: &str = "https://docs.geldata.com/";
+
+/// Short description and documentation page of a keyword or a token, see
+/// [Keyword::docs] and [token_docs](crate::tokenizer::token_docs)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Docs {
+    /// Lowercase phrase without a trailing period
+    pub description: &'static str,
+    /// Path of the page, relative to [DOCS_URL]
+    pub page: &'static str,
+}
+
+impl Docs {
+    pub(crate) const fn new(description: &'static str, page: &'static str) -> Self {
+        Docs { description, page }
+    }
+
+    pub fn url(&self) -> String {
+        format!("{DOCS_URL}{}", self.page)
+    }
+}
+
+/// Documentation of every keyword, see [Keyword::docs]
+const KEYWORD_DOCS: phf::Map<&str, Docs> = phf_map!(
+    "abort" => Docs::new("aborts a migration that is in progress", "reference/datamodel/migrations"),
+    "abstract" => Docs::new("declares a type, link, property or constraint that cannot be instantiated directly", "reference/datamodel/inheritance"),
+    "access" => Docs::new("part of `access policy`, rules restricting access to objects", "reference/datamodel/access_policies"),
+    "after" => Docs::new("part of `after insert/update/delete` trigger timing", "reference/datamodel/triggers"),
+    "alias" => Docs::new("declares an expression alias, or a module alias in `with`", "reference/datamodel/aliases"),
+    "allow" => Docs::new("access policy action granting access", "reference/datamodel/access_policies"),
+    "all" => Docs::new("part of `union all`, `reset all` and similar commands", "reference/reference/edgeql/select"),
+    "annotation" => Docs::new("declares or sets an annotation, a piece of metadata", "reference/datamodel/annotations"),
+    "applied" => Docs::new("part of `create applied migration`, recording a migration that is already applied", "reference/datamodel/migrations"),
+    "as" => Docs::new("introduces an alias, a result format or a type", "reference/reference/edgeql/with"),
+    "asc" => Docs::new("sorts in ascending order in `order by`", "reference/reference/edgeql/select"),
+    "assignment" => Docs::new("declares a cast usable in an assignment", "reference/reference/edgeql/casts"),
+    "before" => Docs::new("part of trigger and migration clauses", "reference/datamodel/triggers"),
+    "blobal" => Docs::new("not used by the grammar, kept as a keyword for future use", "reference/reference/edgeql/lexical"),
+    "branch" => Docs::new("creates, drops or alters a branch of the database", "reference/datamodel/branches"),
+    "cardinality" => Docs::new("sets the cardinality of a link or a property", "reference/reference/edgeql/cardinality"),
+    "cast" => Docs::new("declares a cast between two types", "reference/reference/edgeql/casts"),
+    "committed" => Docs::new("part of the `read committed` isolation level", "reference/reference/edgeql/tx_start"),
+    "config" => Docs::new("part of `configure` commands and `config` objects", "reference/stdlib/cfg"),
+    "conflict" => Docs::new("handles a conflict of `insert` with an exclusive constraint", "reference/reference/edgeql/insert"),
+    "constraint" => Docs::new("declares a constraint restricting values", "reference/datamodel/constraints"),
+    "cube" => Docs::new("grouping by all combinations of the given keys", "reference/reference/edgeql/group"),
+    "current" => Docs::new("refers to the current database, branch or migration", "reference/reference/edgeql/describe"),
+    "data" => Docs::new("part of `with data`, copying data of a branch", "reference/datamodel/branches"),
+    "database" => Docs::new("creates or drops a database", "reference/datamodel/branches"),
+    "ddl" => Docs::new("part of `describe ... as ddl`", "reference/reference/edgeql/describe"),
+    "declare" => Docs::new("declares a savepoint in a transaction", "reference/reference/edgeql/tx_sp_declare"),
+    "default" => Docs::new("declares the default value of a property or a link", "reference/datamodel/properties"),
+    "deferrable" => Docs::new("transaction mode allowing to defer serialization errors", "reference/reference/edgeql/tx_start"),
+    "deferred" => Docs::new("declares a deferred delete policy of a link", "reference/datamodel/links"),
+    "delegated" => Docs::new("declares a constraint that is checked by the descendants", "reference/datamodel/constraints"),
+    "desc" => Docs::new("sorts in descending order in `order by`", "reference/reference/edgeql/select"),
+    "deny" => Docs::new("access policy action denying access", "reference/datamodel/access_policies"),
+    "each" => Docs::new("part of `for each` trigger scope", "reference/datamodel/triggers"),
+    "empty" => Docs::new("part of `empty first` and `empty last` in `order by`", "reference/reference/edgeql/select"),
+    "expression" => Docs::new("declares the expression of an alias or a rewrite", "reference/datamodel/aliases"),
+    "extension" => Docs::new("creates or drops an extension", "reference/datamodel/extensions"),
+    "final" => Docs::new("reserved for final types", "reference/datamodel/inheritance"),
+    "first" => Docs::new("part of `empty first` in `order by`", "reference/reference/edgeql/select"),
+    "force" => Docs::new("part of `drop branch ... force` and `alter branch ... force`, which close the other connections to the branch", "reference/datamodel/branches"),
+    "from" => Docs::new("part of `using sql function ... from`, reading from functions", "reference/datamodel/functions"),
+    "function" => Docs::new("declares a function", "reference/datamodel/functions"),
+    "future" => Docs::new("enables a future behavior of the language", "reference/datamodel/future"),
+    "implicit" => Docs::new("declares a cast that is applied implicitly", "reference/reference/edgeql/casts"),
+    "index" => Docs::new("declares an index", "reference/datamodel/indexes"),
+    "infix" => Docs::new("declares an infix operator", "reference/reference/edgeql/functions"),
+    "inheritable" => Docs::new("declares an annotation that is inherited by the subtypes", "reference/datamodel/annotations"),
+    "instance" => Docs::new("refers to the configuration of the whole instance", "reference/stdlib/cfg"),
+    "into" => Docs::new("not used by the grammar, kept as a keyword for future use", "reference/reference/edgeql/lexical"),
+    "isolation" => Docs::new("sets the isolation level of a transaction", "reference/reference/edgeql/tx_start"),
+    "json" => Docs::new("part of `describe ... as json`", "reference/reference/edgeql/describe"),
+    "last" => Docs::new("part of `empty last` in `order by`", "reference/reference/edgeql/select"),
+    "link" => Docs::new("declares a link to other objects", "reference/datamodel/links"),
+    "migration" => Docs::new("creates, applies or inspects a migration", "reference/datamodel/migrations"),
+    "multi" => Docs::new("declares a link or a property with many values", "reference/reference/edgeql/cardinality"),
+    "named" => Docs::new("part of `named only`, a keyword-only function parameter", "reference/datamodel/functions"),
+    "object" => Docs::new("declares an object type", "reference/datamodel/objects"),
+    "of" => Docs::new("part of `set of` in function signatures", "reference/datamodel/functions"),
+    "only" => Docs::new("part of `named only` and `read only`", "reference/datamodel/functions"),
+    "onto" => Docs::new("part of `create migration ... onto`", "reference/datamodel/migrations"),
+    "operator" => Docs::new("declares an operator", "reference/reference/edgeql/functions"),
+    "optionality" => Docs::new("part of `set optionality`", "reference/datamodel/properties"),
+    "order" => Docs::new("sorts the results, as in `order by`", "reference/reference/edgeql/select"),
+    "orphan" => Docs::new("part of `on source delete delete target if orphan`", "reference/datamodel/links"),
+    "overloaded" => Docs::new("overrides an inherited link, property or constraint", "reference/datamodel/inheritance"),
+    "owned" => Docs::new("refers to locally declared items of a type", "reference/datamodel/inheritance"),
+    "package" => Docs::new("part of `extension package`", "reference/datamodel/extensions"),
+    "permission" => Docs::new("declares a permission", "reference/datamodel/permissions"),
+    "policy" => Docs::new("declares an access policy", "reference/datamodel/access_policies"),
+    "populate" => Docs::new("fills a migration with the proposed DDL", "reference/datamodel/migrations"),
+    "postfix" => Docs::new("declares a postfix operator", "reference/reference/edgeql/functions"),
+    "prefix" => Docs::new("declares a prefix operator", "reference/reference/edgeql/functions"),
+    "property" => Docs::new("declares a property of scalar values", "reference/datamodel/properties"),
+    "proposed" => Docs::new("part of `describe current migration`, proposed DDL", "reference/datamodel/migrations"),
+    "pseudo" => Docs::new("declares a pseudo type", "reference/stdlib/abstract"),
+    "read" => Docs::new("part of `read only` and `read write` transaction modes", "reference/reference/edgeql/tx_start"),
+    "reject" => Docs::new("rejects the proposed DDL of a migration", "reference/datamodel/migrations"),
+    "release" => Docs::new("releases a savepoint", "reference/reference/edgeql/tx_sp_release"),
+    "rename" => Docs::new("renames a schema item", "reference/datamodel/migrations"),
+    "repeatable" => Docs::new("part of the `repeatable read` isolation level", "reference/reference/edgeql/tx_start"),
+    "required" => Docs::new("declares a link or a property that must have a value", "reference/datamodel/properties"),
+    "reset" => Docs::new("resets a configuration, an alias or a schema item", "reference/reference/edgeql/sess_reset_alias"),
+    "restrict" => Docs::new("declares a link delete policy that prevents deletion", "reference/datamodel/links"),
+    "rewrite" => Docs::new("declares a mutation rewrite", "reference/datamodel/mutation_rewrites"),
+    "role" => Docs::new("creates, alters or drops a role", "reference/stdlib/sys"),
+    "roles" => Docs::new("refers to roles in `describe roles`", "reference/reference/edgeql/describe"),
+    "rollup" => Docs::new("grouping by the prefixes of the given keys", "reference/reference/edgeql/group"),
+    "savepoint" => Docs::new("declares or rolls back to a savepoint", "reference/reference/edgeql/tx_sp_declare"),
+    "scalar" => Docs::new("declares a scalar type", "reference/datamodel/primitives"),
+    "schema" => Docs::new("refers to the schema in `describe schema`", "reference/reference/edgeql/describe"),
+    "sdl" => Docs::new("part of `describe ... as sdl`", "reference/reference/edgeql/describe"),
+    "serializable" => Docs::new("the serializable isolation level", "reference/reference/edgeql/tx_start"),
+    "session" => Docs::new("refers to the session configuration", "reference/stdlib/cfg"),
+    "source" => Docs::new("the object a link starts from", "reference/datamodel/links"),
+    "superuser" => Docs::new("declares a role with all privileges", "reference/stdlib/sys"),
+    "system" => Docs::new("refers to the system configuration", "reference/reference/edgeql/describe"),
+    "target" => Docs::new("the object a link points to", "reference/datamodel/links"),
+    "template" => Docs::new("part of `create branch ... from` as a template", "reference/datamodel/branches"),
+    "ternary" => Docs::new("declares a ternary operator", "reference/reference/edgeql/functions"),
+    "text" => Docs::new("part of `describe ... as text`", "reference/reference/edgeql/describe"),
+    "then" => Docs::new("part of `if ... then ... else`", "reference/stdlib/generic"),
+    "to" => Docs::new("part of `rename to`, `rollback to savepoint` and ranges", "reference/reference/edgeql/tx_sp_rollback"),
+    "transaction" => Docs::new("starts, commits or rolls back a transaction", "reference/reference/edgeql/tx_start"),
+    "trigger" => Docs::new("declares a trigger", "reference/datamodel/triggers"),
+    "type" => Docs::new("declares an object or a scalar type", "reference/datamodel/objects"),
+    "unless" => Docs::new("part of `unless conflict` in `insert`", "reference/reference/edgeql/insert"),
+    "using" => Docs::new("gives the expression of a computed item, index or function", "reference/datamodel/computeds"),
+    "verbose" => Docs::new("part of `describe ... verbose`", "reference/reference/edgeql/describe"),
+    "version" => Docs::new("part of `describe ... version`", "reference/reference/edgeql/describe"),
+    "view" => Docs::new("reserved for views", "reference/datamodel/aliases"),
+    "write" => Docs::new("part of the `read write` transaction mode", "reference/reference/edgeql/tx_start"),
+    "except" => Docs::new("set difference of two sets, or the `except (...)` condition of an index", "reference/stdlib/set"),
+    "intersect" => Docs::new("set intersection of two sets", "reference/stdlib/set"),
+    "union" => Docs::new("merges two sets into one", "reference/stdlib/set"),
+    "anyarray" => Docs::new("reserved for future use", "reference/reference/edgeql/lexical"),
+    "begin" => Docs::new("reserved for future use", "reference/reference/edgeql/lexical"),
+    "case" => Docs::new("reserved for future use", "reference/reference/edgeql/lexical"),
+    "check" => Docs::new("reserved for future use", "reference/reference/edgeql/lexical"),
+    "deallocate" => Docs::new("reserved for future use", "reference/reference/edgeql/lexical"),
+    "discard" => Docs::new("reserved for future use", "reference/reference/edgeql/lexical"),
+    "end" => Docs::new("reserved for future use", "reference/reference/edgeql/lexical"),
+    "explain" => Docs::new("reserved for future use", "reference/reference/edgeql/lexical"),
+    "fetch" => Docs::new("reserved for future use", "reference/reference/edgeql/lexical"),
+    "get" => Docs::new("reserved for future use", "reference/reference/edgeql/lexical"),
+    "global" => Docs::new("declares or refers to a global variable", "reference/datamodel/globals"),
+    "grant" => Docs::new("reserved for future use", "reference/reference/edgeql/lexical"),
+    "import" => Docs::new("reserved for future use", "reference/reference/edgeql/lexical"),
+    "listen" => Docs::new("reserved for future use", "reference/reference/edgeql/lexical"),
+    "load" => Docs::new("reserved for future use", "reference/reference/edgeql/lexical"),
+    "lock" => Docs::new("reserved for future use", "reference/reference/edgeql/lexical"),
+    "match" => Docs::new("part of `create index match`, used by the standard library", "reference/datamodel/indexes"),
+    "move" => Docs::new("reserved for future use", "reference/reference/edgeql/lexical"),
+    "notify" => Docs::new("reserved for future use", "reference/reference/edgeql/lexical"),
+    "on" => Docs::new("part of `on target delete` and `on source delete`", "reference/datamodel/links"),
+    "over" => Docs::new("reserved for future use", "reference/reference/edgeql/lexical"),
+    "prepare" => Docs::new("reserved for future use", "reference/reference/edgeql/lexical"),
+    "partition" => Docs::new("reserved for future use", "reference/reference/edgeql/lexical"),
+    "raise" => Docs::new("reserved for future use", "reference/reference/edgeql/lexical"),
+    "refresh" => Docs::new("reserved for future use", "reference/reference/edgeql/lexical"),
+    "revoke" => Docs::new("reserved for future use", "reference/reference/edgeql/lexical"),
+    "single" => Docs::new("declares a link or a property with at most one value", "reference/reference/edgeql/cardinality"),
+    "when" => Docs::new("sets the condition of a trigger or an access policy, `when (...)` or `reset when`", "reference/datamodel/triggers"),
+    "window" => Docs::new("reserved for future use", "reference/reference/edgeql/lexical"),
+    "never" => Docs::new("reserved for future use", "reference/reference/edgeql/lexical"),
+    "__source__" => Docs::new("the source object of a link in link property expressions", "reference/datamodel/linkprops"),
+    "__subject__" => Docs::new("the object a constraint or a policy is applied to", "reference/datamodel/constraints"),
+    "__type__" => Docs::new("link to the type of an object", "reference/datamodel/introspection/objects"),
+    "__std__" => Docs::new("refers to the `std` module, even if shadowed", "reference/datamodel/modules"),
+    "__edgedbsys__" => Docs::new("internal system module", "reference/datamodel/modules"),
+    "__edgedbtpl__" => Docs::new("internal template module", "reference/datamodel/modules"),
+    "__new__" => Docs::new("the object after the change in triggers and rewrites", "reference/datamodel/triggers"),
+    "__old__" => Docs::new("the object before the change in triggers and rewrites", "reference/datamodel/triggers"),
+    "__specified__" => Docs::new("tells which fields were given in mutation rewrites", "reference/datamodel/mutation_rewrites"),
+    "__default__" => Docs::new("the default value of a property in rewrites", "reference/datamodel/mutation_rewrites"),
+    "administer" => Docs::new("runs an administrative command", "reference/stdlib/sys"),
+    "alter" => Docs::new("changes a schema item", "reference/datamodel/migrations"),
+    "analyze" => Docs::new("shows the query plan and performance of a query", "reference/reference/edgeql/analyze"),
+    "and" => Docs::new("logical conjunction", "reference/stdlib/bool"),
+    "anytuple" => Docs::new("generic type matching any tuple", "reference/stdlib/abstract"),
+    "anytype" => Docs::new("generic type matching any type", "reference/stdlib/abstract"),
+    "anyobject" => Docs::new("generic type matching any object", "reference/stdlib/abstract"),
+    "by" => Docs::new("part of `order by` and `group ... by`", "reference/reference/edgeql/group"),
+    "commit" => Docs::new("commits the current transaction", "reference/reference/edgeql/tx_commit"),
+    "configure" => Docs::new("changes a configuration setting", "reference/stdlib/cfg"),
+    "create" => Docs::new("creates a schema item", "reference/datamodel/migrations"),
+    "delete" => Docs::new("deletes objects", "reference/reference/edgeql/delete"),
+    "describe" => Docs::new("describes a schema item or the schema", "reference/reference/edgeql/describe"),
+    "detached" => Docs::new("refers to a set independently of the enclosing path", "reference/reference/edgeql/paths"),
+    "distinct" => Docs::new("removes duplicates from a set", "reference/stdlib/set"),
+    "do" => Docs::new("part of `for ... do` in triggers", "reference/datamodel/triggers"),
+    "drop" => Docs::new("removes a schema item", "reference/datamodel/migrations"),
+    "else" => Docs::new("part of `if ... else` and `unless conflict ... else`", "reference/stdlib/generic"),
+    "exists" => Docs::new("tells whether a set is not empty", "reference/stdlib/set"),
+    "extending" => Docs::new("declares the base types of a type", "reference/datamodel/inheritance"),
+    "false" => Docs::new("the boolean false", "reference/stdlib/bool"),
+    "filter" => Docs::new("selects the elements matching a condition", "reference/reference/edgeql/select"),
+    "for" => Docs::new("iterates over a set", "reference/reference/edgeql/for"),
+    "group" => Docs::new("groups objects by keys", "reference/reference/edgeql/group"),
+    "if" => Docs::new("conditional expression", "reference/stdlib/generic"),
+    "ilike" => Docs::new("case-insensitive pattern match", "reference/stdlib/string"),
+    "in" => Docs::new("tells whether an element is in a set", "reference/stdlib/set"),
+    "insert" => Docs::new("creates a new object", "reference/reference/edgeql/insert"),
+    "introspect" => Docs::new("returns the type of an expression", "reference/stdlib/type"),
+    "is" => Docs::new("type check of objects", "reference/stdlib/type"),
+    "like" => Docs::new("case-sensitive pattern match", "reference/stdlib/string"),
+    "limit" => Docs::new("limits the number of results", "reference/reference/edgeql/select"),
+    "module" => Docs::new("declares a module or sets the default one", "reference/datamodel/modules"),
+    "not" => Docs::new("logical negation", "reference/stdlib/bool"),
+    "offset" => Docs::new("skips a number of results", "reference/reference/edgeql/select"),
+    "optional" => Docs::new("declares an optional parameter, link or property", "reference/datamodel/functions"),
+    "or" => Docs::new("logical disjunction", "reference/stdlib/bool"),
+    "rollback" => Docs::new("rolls back the current transaction or to a savepoint", "reference/reference/edgeql/tx_rollback"),
+    "select" => Docs::new("retrieves or computes a set of values", "reference/reference/edgeql/select"),
+    "set" => Docs::new("sets a value in `update`, a config or a session alias", "reference/reference/edgeql/update"),
+    "start" => Docs::new("starts a transaction or a migration", "reference/reference/edgeql/tx_start"),
+    "true" => Docs::new("the boolean true", "reference/stdlib/bool"),
+    "typeof" => Docs::new("the type of an expression, usable as a type", "reference/stdlib/type"),
+    "update" => Docs::new("modifies objects", "reference/reference/edgeql/update"),
+    "variadic" => Docs::new("declares a function parameter taking many arguments", "reference/datamodel/functions"),
+    "with" => Docs::new("declares aliases and module settings of a query", "reference/reference/edgeql/with"),
+    "named only" => Docs::new("declares a keyword-only function parameter", "reference/datamodel/functions"),
+    "set annotation" => Docs::new("sets an annotation of a schema item", "reference/datamodel/annotations"),
+    "set type" => Docs::new("changes the type of a property or a link", "reference/datamodel/properties"),
+    "extension package" => Docs::new("declares a package of an extension", "reference/datamodel/extensions"),
+    "order by" => Docs::new("sorts the results", "reference/reference/edgeql/select"),
+);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn docs_complete() {
+        let sets = [
+            &UNRESERVED_KEYWORDS,
+            &PARTIAL_RESERVED_KEYWORDS,
+            &FUTURE_RESERVED_KEYWORDS,
+            &CURRENT_RESERVED_KEYWORDS,
+            &COMBINED_KEYWORDS,
+        ];
+        for keyword in sets.into_iter().flat_map(|set| set.iter()) {
+            let docs = lookup_all(keyword).unwrap().docs();
+            assert!(docs.is_some(), "no docs for {keyword:?}");
+        }
+        assert_eq!(KEYWORD_DOCS.len(), sets.iter().map(|s| s.len()).sum());
+
+        let select = Keyword("select").docs().unwrap();
+        assert_eq!(
+            select.url(),
+            "https://docs.geldata.com/reference/reference/edgeql/select"
+        );
+        assert_eq!(Keyword("selec").docs(), None);
+    }
+
+    #[test]
+    fn categories() {
+        assert_eq!(Keyword("select").category(), Some(Category::Reserved));
+        assert_eq!(Keyword("over").category(), Some(Category::FutureReserved));
+        assert_eq!(Keyword("union").category(), Some(Category::PartialReserved));
+        assert_eq!(Keyword("abort").category(), Some(Category::Unreserved));
+        assert_eq!(Keyword("order by").category(), Some(Category::Combined));
+        assert_eq!(Keyword("selec").category(), None);
+    }
+
+    #[test]
+    fn grammar_keywords() {
+        // reserved keywords that are already used by productions
+        for keyword in ["when", "match", "except", "orphan", "applied"] {
+            let docs = Keyword(keyword).docs().unwrap();
+            assert!(
+                !docs.description.contains("future use"),
+                "{keyword:?} is used by the grammar"
+            );
+        }
+        assert!(Keyword("blobal")
+            .docs()
+            .unwrap()
+            .description
+            .starts_with("not used by the grammar"));
+    }
+}
@@ -1,11 +1,62 @@
+pub mod administer;
+pub mod analyze;
 pub mod ast;
+pub mod braces;
+pub mod brackets;
+pub mod build;
+pub mod casts;
+pub mod codegen;
+pub mod conditional;
+pub mod config_values;
+pub mod detached;
+pub mod diagnostics;
+pub mod diff;
+#[cfg(feature = "difftest")]
+pub mod difftest;
+pub mod docgen;
+pub mod equivalence;
+pub mod experimental;
 pub mod expr;
+pub mod format;
+pub mod functions;
+#[cfg(feature = "graphql")]
+pub mod graphql;
 pub mod hash;
 pub mod helpers;
+pub mod indexing;
+pub mod interpolation;
+pub mod json_literals;
 pub mod keywords;
+pub mod lint;
+pub mod messages;
+pub mod minify;
+pub mod options;
+pub mod pagination;
+pub mod params;
 pub mod parser;
 pub mod position;
+pub mod pragmas;
 pub mod preparser;
+pub mod prune;
+pub mod redact;
+pub mod required;
+pub mod resolver;
+pub mod roles;
+pub mod row_filters;
+pub mod schema;
 pub mod schema_file;
+pub mod script;
+pub mod session;
+pub mod shape;
+pub mod signature_help;
+pub mod soft_keywords;
+pub mod template;
+pub mod temporal;
+pub mod token_stream;
 pub mod tokenizer;
+pub mod tree_sitter;
+pub mod tuples;
+pub mod uuids;
 pub mod validation;
+#[cfg(feature = "watch")]
+pub mod watch;
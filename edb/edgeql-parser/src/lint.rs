@@ -0,0 +1,505 @@
+//! Lints of `with` bindings
+//!
+//! [with_bindings] warns about aliases of `with` blocks that are:
+//!
+//! * never used (`unused-alias`), with a suggestion to remove the binding
+//! * used only once (`single-use-alias`), with a suggestion to inline the
+//!   expression
+//! * shadowing an alias of an enclosing `with` block (`shadowed-alias`),
+//!   with a suggestion to rename the inner alias
+//!
+//! Every suggestion replaces a single span, so when a fix edits several
+//! places the text between them is a part of the replacement.
+//!
+//! Aliases are found from tokens. A name in braces that may be either a
+//! shape element or a use of the alias (like `x` in `{ x }`) counts as a
+//! use. Inlining is suggested only if it cannot change the result: the
+//! expression is not a data modification and none of its paths start with
+//! a name used elsewhere in the query, as an alias is independent of the
+//! paths of the query while an inlined expression is not.
+use std::ops::Range;
+
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::keywords::Keyword;
+use crate::position::Span;
+use crate::schema::{is_name_like, token_name};
+use crate::tokenizer::{Error, Kind, Token, Tokenizer};
+
+/// Keywords starting the statement after the bindings
+const STATEMENTS: &[&str] = &["delete", "for", "group", "insert", "select", "update"];
+
+const MODIFICATIONS: &[&str] = &["delete", "insert", "update"];
+
+/// A `with` block
+#[derive(Debug)]
+struct Block {
+    /// Index of the `with` keyword
+    start: usize,
+    /// Index of the first token of the statement
+    body: usize,
+    /// Index of the token after the last one the aliases are visible in
+    end: usize,
+    /// Token ranges of all items, including `module` declarations
+    items: Vec<Range<usize>>,
+    bindings: Vec<Binding>,
+}
+
+#[derive(Debug)]
+struct Binding {
+    name: String,
+    /// Index of the name
+    idx: usize,
+    /// Index in [Block::items]
+    item: usize,
+    /// Tokens after `:=`
+    expr: Range<usize>,
+}
+
+/// A name referring to an alias
+#[derive(Debug, Clone, Copy)]
+struct Use {
+    idx: usize,
+    /// Name might also be a shape element, like in `{ x }`
+    ambiguous: bool,
+}
+
+/// Checks the aliases of all `with` blocks of the query, fails if the query
+/// cannot be tokenized
+///
+/// Warnings are ordered by the position of the alias.
+pub fn with_bindings(text: &str) -> Result<Vec<Diagnostic>, Error> {
+    let tokens = Tokenizer::new(text)
+        .validated_values()
+        .collect::<Result<Vec<_>, _>>()?;
+    let blocks: Vec<Block> = (0..tokens.len())
+        .filter(|&idx| tokens[idx].kind == Kind::Keyword(Keyword("with")))
+        .filter_map(|idx| read_block(&tokens, idx))
+        .collect();
+
+    let mut warnings = Vec::new();
+    for block in &blocks {
+        for binding in &block.bindings {
+            let span = tokens[binding.idx].span;
+            let uses = uses(&tokens, &blocks, block, binding);
+            if let Some(outer) = shadowed(&blocks, block, binding) {
+                let mut warning = Diagnostic::with_code(
+                    Severity::Warning,
+                    "shadowed-alias",
+                    &[("name", binding.name.clone())],
+                    span,
+                )
+                .with_label(tokens[outer.idx].span);
+                if !uses.iter().any(|u| u.ambiguous) {
+                    let name = fresh_name(&tokens, &binding.name);
+                    let edits = std::iter::once(binding.idx)
+                        .chain(uses.iter().map(|u| u.idx))
+                        .map(|idx| (tokens[idx].span, name.clone()))
+                        .collect();
+                    let (renamed, replacement) = rewrite(text, edits);
+                    warning = warning
+                        .with_arg("new_name", name)
+                        .with_suggestion(renamed, replacement);
+                }
+                warnings.push(warning);
+            }
+
+            match &uses[..] {
+                [] => {
+                    let (removed, replacement) =
+                        rewrite(text, vec![removal(&tokens, block, binding.item)]);
+                    let warning = Diagnostic::with_code(
+                        Severity::Warning,
+                        "unused-alias",
+                        &[("name", binding.name.clone())],
+                        span,
+                    )
+                    .with_suggestion(removed, replacement);
+                    warnings.push(warning);
+                }
+                [single] if !single.ambiguous && can_inline(&tokens, binding) => {
+                    let (inlined_span, replacement) = rewrite(
+                        text,
+                        vec![
+                            removal(&tokens, block, binding.item),
+                            (tokens[single.idx].span, inlined(text, &tokens, binding)),
+                        ],
+                    );
+                    let warning = Diagnostic::with_code(
+                        Severity::Warning,
+                        "single-use-alias",
+                        &[("name", binding.name.clone())],
+                        span,
+                    )
+                    .with_label(tokens[single.idx].span)
+                    .with_suggestion(inlined_span, replacement);
+                    warnings.push(warning);
+                }
+                _ => {}
+            }
+        }
+    }
+    warnings.sort_by_key(|w| w.span.start);
+    Ok(warnings)
+}
+
+fn is_name(token: &Token) -> bool {
+    token.kind == Kind::BacktickName || is_name_like(Some(token.kind))
+}
+
+fn is_open(kind: Kind) -> bool {
+    matches!(kind, Kind::OpenParen | Kind::OpenBracket | Kind::OpenBrace)
+}
+
+fn is_close(kind: Kind) -> bool {
+    matches!(
+        kind,
+        Kind::CloseParen | Kind::CloseBracket | Kind::CloseBrace
+    )
+}
+
+fn read_block(tokens: &[Token], start: usize) -> Option<Block> {
+    let mut items = Vec::new();
+    let mut bindings = Vec::new();
+    let mut pos = start + 1;
+    let body = loop {
+        let end = item_end(tokens, pos)?;
+        if end > pos + 2 && is_name(&tokens[pos]) && tokens[pos + 1].kind == Kind::Assign {
+            bindings.push(Binding {
+                name: token_name(&tokens[pos]).to_string(),
+                idx: pos,
+                item: items.len(),
+                expr: pos + 2..end,
+            });
+        }
+        items.push(pos..end);
+        if tokens[end].kind != Kind::Comma {
+            break end;
+        }
+        pos = end + 1;
+    };
+    Some(Block {
+        start,
+        body,
+        end: scope_end(tokens, start),
+        items,
+        bindings,
+    })
+}
+
+/// Index of the comma or the statement keyword after the item at `pos`,
+/// `None` if the block is not complete
+fn item_end(tokens: &[Token], pos: usize) -> Option<usize> {
+    let mut depth = 0usize;
+    for (idx, tok) in tokens.iter().enumerate().skip(pos) {
+        match tok.kind {
+            kind if is_open(kind) => depth += 1,
+            kind if is_close(kind) => depth = depth.checked_sub(1)?,
+            Kind::Semicolon => return None,
+            Kind::Comma if depth == 0 => return Some(idx),
+            Kind::Keyword(Keyword(kw)) if depth == 0 && STATEMENTS.contains(&kw) => {
+                return Some(idx)
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// End of the statement starting at `start`: the closing bracket if the
+/// statement is in brackets, or the end of the query
+fn scope_end(tokens: &[Token], start: usize) -> usize {
+    let in_brackets = start > 0 && is_open(tokens[start - 1].kind);
+    let mut depth = 0usize;
+    for (idx, tok) in tokens.iter().enumerate().skip(start) {
+        match tok.kind {
+            kind if is_open(kind) => depth += 1,
+            kind if is_close(kind) => match depth.checked_sub(1) {
+                Some(d) => depth = d,
+                None if in_brackets => return idx,
+                None => {}
+            },
+            Kind::Semicolon if depth == 0 => return idx,
+            _ => {}
+        }
+    }
+    tokens.len()
+}
+
+/// The nearest binding of the same name of a block enclosing `block`
+fn shadowed<'b>(blocks: &'b [Block], block: &Block, binding: &Binding) -> Option<&'b Binding> {
+    blocks
+        .iter()
+        .filter(|outer| outer.start < block.start && block.start < outer.end)
+        .flat_map(|outer| &outer.bindings)
+        .filter(|b| b.name == binding.name && b.expr.end <= block.start)
+        .max_by_key(|b| b.idx)
+}
+
+/// Uses of the alias, excluding those of aliases shadowing it
+fn uses(tokens: &[Token], blocks: &[Block], block: &Block, binding: &Binding) -> Vec<Use> {
+    let shadowing: Vec<Range<usize>> = blocks
+        .iter()
+        .filter(|inner| binding.expr.end < inner.start && inner.start < block.end)
+        .flat_map(|inner| {
+            inner
+                .bindings
+                .iter()
+                .filter(|b| b.name == binding.name)
+                .map(move |b| b.expr.end..inner.end)
+        })
+        .collect();
+    (binding.expr.end..block.end)
+        .filter(|&idx| is_name(&tokens[idx]) && token_name(&tokens[idx]) == binding.name)
+        .filter(|idx| !shadowing.iter().any(|range| range.contains(idx)))
+        .filter_map(|idx| reference(tokens, idx))
+        .collect()
+}
+
+/// Whether the name at `idx` may refer to an alias, rather than being a
+/// path step, a function, a type, a module or a name being defined
+fn reference(tokens: &[Token], idx: usize) -> Option<Use> {
+    let prev = idx.checked_sub(1).map(|i| tokens[i].kind);
+    let next = tokens.get(idx + 1).map(|t| t.kind);
+    let is_step = matches!(
+        prev,
+        Some(Kind::Dot | Kind::BackwardLink | Kind::OptionalLink | Kind::At | Kind::Namespace)
+    );
+    let is_type = matches!(
+        prev,
+        Some(Kind::Keyword(Keyword(
+            "is" | "module" | "global" | "introspect"
+        )))
+    ) || (prev == Some(Kind::Less)
+        && matches!(next, Some(Kind::Greater | Kind::Less | Kind::Comma)));
+    let is_defined = prev == Some(Kind::Keyword(Keyword("for")))
+        || matches!(
+            next,
+            Some(Kind::OpenParen | Kind::Colon | Kind::Namespace | Kind::Assign)
+        );
+    if is_step || is_type || is_defined {
+        return None;
+    }
+    let ambiguous = match prev {
+        Some(Kind::OpenBrace) => true,
+        Some(Kind::Comma) => innermost_bracket(tokens, idx) == Some(Kind::OpenBrace),
+        _ => false,
+    };
+    Some(Use { idx, ambiguous })
+}
+
+fn innermost_bracket(tokens: &[Token], idx: usize) -> Option<Kind> {
+    let mut depth = 0usize;
+    for tok in tokens[..idx].iter().rev() {
+        match tok.kind {
+            kind if is_close(kind) => depth += 1,
+            kind if is_open(kind) && depth == 0 => return Some(kind),
+            kind if is_open(kind) => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Whether inlining the expression of the binding keeps the result, see
+/// the module docs
+fn can_inline(tokens: &[Token], binding: &Binding) -> bool {
+    let expr = &tokens[binding.expr.clone()];
+    let modifies = expr
+        .iter()
+        .any(|t| matches!(t.kind, Kind::Keyword(Keyword(kw)) if MODIFICATIONS.contains(&kw)));
+    if modifies {
+        return false;
+    }
+    let roots: Vec<&str> = binding
+        .expr
+        .clone()
+        .filter(|&idx| is_name(&tokens[idx]) && reference(tokens, idx).is_some())
+        .map(|idx| token_name(&tokens[idx]))
+        .collect();
+    !tokens.iter().enumerate().any(|(idx, tok)| {
+        !binding.expr.contains(&idx) && is_name(tok) && roots.contains(&token_name(tok))
+    })
+}
+
+/// Text of the expression, parenthesized unless it is a single token or
+/// already in parentheses
+fn inlined(text: &str, tokens: &[Token], binding: &Binding) -> String {
+    let expr = &tokens[binding.expr.clone()];
+    let first = &expr[0];
+    let last = &expr[expr.len() - 1];
+    let source = &text[first.span.start as usize..last.span.end as usize];
+    let parenthesized =
+        first.kind == Kind::OpenParen && closing_paren(expr) == Some(expr.len() - 1);
+    if expr.len() == 1 || parenthesized {
+        source.to_string()
+    } else {
+        format!("({source})")
+    }
+}
+
+/// Index of the bracket closing the one opened by the first token
+fn closing_paren(expr: &[Token]) -> Option<usize> {
+    let mut depth = 0usize;
+    for (idx, tok) in expr.iter().enumerate() {
+        if is_open(tok.kind) {
+            depth += 1;
+        } else if is_close(tok.kind) {
+            depth -= 1;
+            if depth == 0 {
+                return Some(idx);
+            }
+        }
+    }
+    None
+}
+
+/// Edit removing the item of the block, along with a separating comma, or
+/// the whole block if it is the only item
+fn removal(tokens: &[Token], block: &Block, item: usize) -> (Span, String) {
+    let range = &block.items[item];
+    let span = if block.items.len() == 1 {
+        Span {
+            start: tokens[block.start].span.start,
+            end: tokens[block.body].span.start,
+        }
+    } else if let Some(next) = block.items.get(item + 1) {
+        Span {
+            start: tokens[range.start].span.start,
+            end: tokens[next.start].span.start,
+        }
+    } else {
+        Span {
+            start: tokens[range.start - 1].span.start,
+            end: tokens[range.end - 1].span.end,
+        }
+    };
+    (span, String::new())
+}
+
+/// Name not used by any token of the query, like `x2` for `x`
+fn fresh_name(tokens: &[Token], name: &str) -> String {
+    (2..)
+        .map(|n| format!("{name}{n}"))
+        .find(|candidate| !tokens.iter().any(|t| token_name(t) == candidate))
+        .unwrap()
+}
+
+/// Combines edits into a single replacement of the text from the first to
+/// the last of them
+fn rewrite(text: &str, mut edits: Vec<(Span, String)>) -> (Span, String) {
+    edits.sort_by_key(|(span, _)| span.start);
+    let start = edits[0].0.start;
+    let mut end = start;
+    let mut replacement = String::new();
+    for (span, new) in edits {
+        replacement.push_str(&text[end as usize..span.start as usize]);
+        replacement.push_str(&new);
+        end = span.end;
+    }
+    (Span { start, end }, replacement)
+}
+
+#[cfg(test)]
+mod test {
+    use super::with_bindings;
+
+    /// Codes and aliases of the warnings, with the query after applying the
+    /// first suggestion of every warning
+    fn lint(text: &str) -> Vec<(String, String, Option<String>)> {
+        with_bindings(text)
+            .unwrap()
+            .into_iter()
+            .map(|w| {
+                let span = w.span;
+                let name = text[span.start as usize..span.end as usize].to_string();
+                let fixed = w.suggestions.first().map(|s| s.apply(text));
+                (w.code.unwrap(), name, fixed)
+            })
+            .collect()
+    }
+
+    fn warning(code: &str, name: &str, fixed: Option<&str>) -> (String, String, Option<String>) {
+        (code.into(), name.into(), fixed.map(Into::into))
+    }
+
+    #[test]
+    fn unused() {
+        assert_eq!(
+            lint("with a := 1 select 2"),
+            [warning("unused-alias", "a", Some("select 2"))]
+        );
+        assert_eq!(
+            lint("with module default, a := 1, b := User select (b, count(b))"),
+            [warning(
+                "unused-alias",
+                "a",
+                Some("with module default, b := User select (b, count(b))")
+            )]
+        );
+        assert_eq!(
+            lint("with b := User, a := 1 select b { x := b.a }"),
+            [warning(
+                "unused-alias",
+                "a",
+                Some("with b := User select b { x := b.a }")
+            )]
+        );
+        // shape element or a use of the alias
+        assert!(lint("with a := 1, b := User select (b { a }, b)").is_empty());
+    }
+
+    #[test]
+    fn single_use() {
+        assert_eq!(
+            lint("with x := <str>$x, y := x ++ '!' select (y, y)"),
+            [warning(
+                "single-use-alias",
+                "x",
+                Some("with y := (<str>$x) ++ '!' select (y, y)")
+            )]
+        );
+        assert_eq!(
+            lint("with u := (select User filter .name = 'a') select u.name"),
+            [warning(
+                "single-use-alias",
+                "u",
+                Some("select (select User filter .name = 'a').name")
+            )]
+        );
+        // inlining would bind `User` to the selected users
+        assert!(lint("with u := (select User limit 1) select User { n := u.name }").is_empty());
+        assert!(lint("with u := (insert User { name := 'a' }) select u").is_empty());
+    }
+
+    #[test]
+    fn shadowed() {
+        let text = "with a := 1 select (a, a, (with a := 2 select a + a))";
+        let warnings = with_bindings(text).unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(
+            warnings[0].message,
+            "alias `a` shadows an alias of the outer query"
+        );
+        assert_eq!(warnings[0].labels[0].span.start, 5);
+        assert_eq!(
+            warnings[0].suggestions[0].apply(text),
+            "with a := 1 select (a, a, (with a2 := 2 select a2 + a2))"
+        );
+        // the outer alias is used once, the inner one is not a use of it
+        assert_eq!(
+            lint("with a := 1 select (a, (with a := 2 select (a, a)))"),
+            [
+                warning(
+                    "single-use-alias",
+                    "a",
+                    Some("select (1, (with a := 2 select (a, a)))")
+                ),
+                warning(
+                    "shadowed-alias",
+                    "a",
+                    Some("with a := 1 select (a, (with a2 := 2 select (a2, a2)))")
+                ),
+            ]
+        );
+    }
+}
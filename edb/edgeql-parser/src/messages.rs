@@ -0,0 +1,361 @@
+//! Catalog of diagnostic messages
+//!
+//! Messages of diagnostics with a code are not written where the diagnostic
+//! is created, but taken from [CATALOG] by [Diagnostic::with_code], with
+//! placeholders like `{name}` replaced by the arguments of the diagnostic
+//! ([Diagnostic::args]). A [MessageProvider] can replace the templates, e.g.
+//! to translate or reword them, and [Diagnostic::localize] renders the
+//! message again with the templates of the provider.
+//!
+//! The hint, the details, the labels and the suggestions of a diagnostic
+//! are parts of its message, with templates under `<code>.hint`,
+//! `<code>.details`, `<code>.label` and `<code>.suggestion`, added by
+//! [Diagnostic::with_hint] and the like.
+//!
+//! Every diagnostic of the checks and lints of this crate has a code.
+//! Errors of the tokenizer, of the grammar and of the functions reading
+//! statements from tokens (like [parse_statement]) have none: they are
+//! syntax errors with the messages of the server, converted to diagnostics
+//! as they are.
+//!
+//! [Diagnostic::with_code]: crate::diagnostics::Diagnostic::with_code
+//! [Diagnostic::args]: crate::diagnostics::Diagnostic::args
+//! [Diagnostic::localize]: crate::diagnostics::Diagnostic::localize
+//! [Diagnostic::with_hint]: crate::diagnostics::Diagnostic::with_hint
+//! [parse_statement]: crate::shape::parse_statement
+use std::borrow::Cow;
+
+/// Codes of diagnostics with the templates of their messages, by code,
+/// and the templates of the parts of the diagnostics, by `<code>.<part>`
+pub const CATALOG: &[(&str, &str)] = &[
+    (
+        "abstract-delegated",
+        "abstract constraints cannot be delegated",
+    ),
+    (
+        "assigned-and-selected",
+        "`{name}` is both selected and assigned",
+    ),
+    ("assigned-and-selected.label", "first assigned here"),
+    (
+        "conflicting-modifiers",
+        "'{other}' and '{keyword}' cannot be used together",
+    ),
+    (
+        "delegated-in-concrete-type",
+        "delegated constraints are only valid in abstract types",
+    ),
+    (
+        "delegated-not-constraint",
+        "`delegated` is only valid for constraints",
+    ),
+    (
+        "duplicate-argument",
+        "argument `{arg}` is passed more than once",
+    ),
+    ("duplicate-assignment", "`{name}` is assigned twice"),
+    ("duplicate-assignment.label", "first assigned here"),
+    ("duplicate-enum-member", "duplicate enum member '{name}'"),
+    ("duplicate-modifier", "'{keyword}' is specified twice"),
+    ("duplicate-selection", "`{name}` is selected twice"),
+    ("duplicate-selection.label", "first selected here"),
+    ("empty-slice", "slice is always empty"),
+    (
+        "expression-without-on",
+        "`constraint expression` requires an `on (...)` clause",
+    ),
+    (
+        "global-in-constraint",
+        "globals cannot be used in constraints",
+    ),
+    (
+        "global-in-constraint.hint",
+        "constraint expressions must be immutable",
+    ),
+    (
+        "index-before-cast",
+        "indexing is applied before the type cast",
+    ),
+    (
+        "index-before-cast.hint",
+        "wrap the cast in parenthesis: `(<...>{param})[...]`",
+    ),
+    (
+        "index-out-of-bounds",
+        "index {index} is out of bounds for {kind} of length {len}",
+    ),
+    (
+        "input-too-long",
+        "input is too long: {len} bytes, at most {max} are allowed",
+    ),
+    ("invalid-cast", "cannot cast {from} to {to}"),
+    ("invalid-enum-member", "invalid enum member '{name}'"),
+    ("invalid-json", "invalid JSON: {error}"),
+    (
+        "invalid-temporal",
+        "invalid input syntax for type {type}: '{text}'",
+    ),
+    ("invalid-temporal.hint", "use a value like '{example}'"),
+    ("invalid-uuid", "invalid UUID: {error}"),
+    (
+        "misplaced-modifier",
+        "'{keyword}' is only allowed on {kinds}",
+    ),
+    (
+        "misplaced-subject",
+        "`__subject__` can only be used in constraints, indexes and rewrites",
+    ),
+    ("missing-argument", "missing argument `{arg}`"),
+    ("missing-enum-member", "enum member is missing"),
+    (
+        "missing-named-argument",
+        "missing named-only argument `{arg}`",
+    ),
+    (
+        "missing-required",
+        "missing value for required {kind} '{name}' of object type '{type}'",
+    ),
+    (
+        "missing-required.hint",
+        "set it in the shape: `{name} := ...`",
+    ),
+    (
+        "mixed-detached",
+        "alias `{name}` is used both with and without `detached`",
+    ),
+    (
+        "mixed-detached.hint",
+        "the detached uses refer to a separate set",
+    ),
+    ("mixed-detached.label", "used without `detached`"),
+    (
+        "mutable-function-in-constraint",
+        "function `{name}` is {volatility} and cannot be used in constraints",
+    ),
+    (
+        "mutable-function-in-constraint.hint",
+        "constraint expressions must be immutable",
+    ),
+    (
+        "nested-abstract",
+        "'abstract' is only allowed on declarations of modules",
+    ),
+    (
+        "no-matching-overload",
+        "no overload of function `{name}` matches the arguments",
+    ),
+    ("no-matching-overload.details", "{problems}"),
+    ("non-canonical-uuid", "UUID is not in the canonical form"),
+    ("non-canonical-uuid.suggestion", "use lowercase with dashes"),
+    (
+        "positional-after-named",
+        "positional argument follows named argument",
+    ),
+    (
+        "positional-by-name",
+        "argument `{arg}` is positional and cannot be passed by name",
+    ),
+    (
+        "query-too-deep",
+        "query is nested too deeply: {depth} levels, at most {max} are allowed",
+    ),
+    (
+        "query-too-large",
+        "query is too large: {nodes} nodes, at most {max} are allowed",
+    ),
+    (
+        "recursive-computed-pointer",
+        "{name} is defined recursively",
+    ),
+    (
+        "recursive-computed-pointer.details",
+        "computed pointer cycle: {path}",
+    ),
+    ("recursive-definition", "{name} is defined recursively"),
+    ("recursive-definition.details", "definition cycle: {path}"),
+    ("recursive-inheritance", "{name} is defined recursively"),
+    ("recursive-inheritance.details", "inheritance cycle: {path}"),
+    (
+        "selected-and-assigned",
+        "`{name}` is both selected and assigned",
+    ),
+    ("selected-and-assigned.label", "first selected here"),
+    (
+        "shadowed-alias",
+        "alias `{name}` shadows an alias of the outer query",
+    ),
+    ("shadowed-alias.label", "shadowed alias"),
+    (
+        "shadowed-alias.suggestion",
+        "rename the alias to `{new_name}`",
+    ),
+    ("single-use-alias", "alias `{name}` is used only once"),
+    ("single-use-alias.label", "the only use"),
+    ("single-use-alias.suggestion", "inline the expression"),
+    (
+        "soft-keyword",
+        "'{keyword}' is a keyword in some statements and may become reserved",
+    ),
+    ("soft-keyword.suggestion", "quote the name"),
+    (
+        "token-too-long",
+        "token is too long: {len} bytes, at most {max} are allowed",
+    ),
+    (
+        "top-level-overloaded",
+        "'overloaded' is only allowed on pointers of types and links",
+    ),
+    ("unclosed-bracket", "unclosed bracket"),
+    (
+        "unexpected-argument",
+        "unexpected argument {position}, function `{name}` takes {max} positional arguments",
+    ),
+    ("unexpected-closing-bracket", "unexpected closing bracket"),
+    (
+        "unexpected-single-argument",
+        "unexpected argument {position}, function `{name}` takes 1 positional argument",
+    ),
+    (
+        "unknown-argument",
+        "function `{name}` has no named-only argument `{arg}`",
+    ),
+    ("unused-alias", "alias `{name}` is never used"),
+    ("unused-alias.suggestion", "remove the alias"),
+    ("useless-detached", "`detached` has no effect"),
+    (
+        "useless-detached.hint",
+        "`{name}` is not used elsewhere in the query",
+    ),
+    ("useless-detached.suggestion", "remove `detached`"),
+    (
+        "variadic-by-name",
+        "variadic argument `{arg}` cannot be passed by name",
+    ),
+];
+
+/// Source of the templates of messages
+pub trait MessageProvider {
+    /// Template of the message of diagnostics with the code, `None` to keep
+    /// the message of the [CATALOG]
+    fn template(&self, code: &str) -> Option<Cow<'_, str>>;
+}
+
+/// Provider of the templates of the [CATALOG]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DefaultMessages;
+
+impl MessageProvider for DefaultMessages {
+    fn template(&self, code: &str) -> Option<Cow<'_, str>> {
+        default_template(code).map(Cow::Borrowed)
+    }
+}
+
+/// Template of the [CATALOG] for the code
+pub fn default_template(code: &str) -> Option<&'static str> {
+    CATALOG
+        .iter()
+        .find(|(c, _)| *c == code)
+        .map(|(_, template)| *template)
+}
+
+/// Whether the code is one of a diagnostic of the [CATALOG], rather than
+/// of a part of its message
+pub fn is_code(code: &str) -> bool {
+    !code.contains('.') && default_template(code).is_some()
+}
+
+/// Replaces `{name}` placeholders of the template with the arguments,
+/// placeholders without an argument are kept as is
+pub fn format_message(template: &str, args: &[(String, String)]) -> String {
+    let mut result = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let value = rest.find('}').and_then(|end| {
+            let name = &rest[1..end];
+            let (_, value) = args.iter().find(|(n, _)| n == name)?;
+            Some((value, end))
+        });
+        match value {
+            Some((value, end)) => {
+                result.push_str(value);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                result.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use std::borrow::Cow;
+
+    use super::{format_message, is_code, MessageProvider, CATALOG};
+    use crate::diagnostics::{Diagnostic, Severity};
+    use crate::position::Span;
+
+    struct French;
+
+    impl MessageProvider for French {
+        fn template(&self, code: &str) -> Option<Cow<'_, str>> {
+            match code {
+                "unused-alias" => Some("l'alias `{name}` n'est jamais utilisé".into()),
+                "single-use-alias.label" => Some("la seule utilisation".into()),
+                _ => None,
+            }
+        }
+    }
+
+    #[test]
+    fn catalog() {
+        let mut codes: Vec<_> = CATALOG.iter().map(|(code, _)| *code).collect();
+        codes.sort();
+        codes.dedup();
+        assert_eq!(codes.len(), CATALOG.len());
+        for code in codes {
+            if let Some((base, _)) = code.split_once('.') {
+                assert!(is_code(base), "{code} is a part of an unknown code");
+            }
+        }
+
+        let args = [("name".to_string(), "x".to_string())];
+        assert_eq!(format_message("{name} {other} {", &args), "x {other} {");
+    }
+
+    #[test]
+    fn localize() {
+        let span = Span { start: 5, end: 6 };
+        let mut diagnostic = Diagnostic::with_code(
+            Severity::Warning,
+            "unused-alias",
+            &[("name", "x".into())],
+            span,
+        );
+        assert_eq!(diagnostic.message, "alias `x` is never used");
+        diagnostic.localize(&French);
+        assert_eq!(diagnostic.message, "l'alias `x` n'est jamais utilisé");
+
+        let mut other = Diagnostic::with_code(Severity::Warning, "empty-slice", &[], span);
+        other.localize(&French);
+        assert_eq!(other.message, "slice is always empty");
+
+        let mut labeled = Diagnostic::with_code(
+            Severity::Warning,
+            "single-use-alias",
+            &[("name", "x".into())],
+            span,
+        )
+        .with_label(span)
+        .with_suggestion(span, "1");
+        assert_eq!(labeled.labels[0].message, "the only use");
+        labeled.localize(&French);
+        assert_eq!(labeled.labels[0].message, "la seule utilisation");
+        assert_eq!(labeled.suggestions[0].message, "inline the expression");
+    }
+}
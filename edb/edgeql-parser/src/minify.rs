@@ -0,0 +1,230 @@
+//! Removal of comments and whitespace
+//!
+//! The minified text tokenizes exactly as the original: a space is kept
+//! between two tokens only where they would otherwise merge (like
+//! `select User`) or change meaning. A [SourceMap] translates offsets in the
+//! minified text back to the original text, e.g. to report errors returned
+//! by the server for the minified query.
+//!
+//! [canonical_text] is similar, but keeps the text readable: tokens are
+//! separated by a single space and reserved keywords are lowercased, so
+//! that queries differing only in formatting have the same text, e.g. for
+//! keys of query caches.
+use crate::position::Span;
+use crate::tokenizer::{Error, Kind, Token, Tokenizer};
+
+/// Result of [minify]
+#[derive(Debug, Clone)]
+pub struct Minified {
+    pub text: String,
+    pub map: SourceMap,
+}
+
+/// Maps offsets of minified text to the original one
+#[derive(Debug, Clone, Default)]
+pub struct SourceMap {
+    /// Spans of every token in the minified text and offset of the token in
+    /// the original text, ordered by offset
+    tokens: Vec<(Span, u64)>,
+}
+
+impl SourceMap {
+    /// Returns the offset in the original text. Offsets of the spaces
+    /// inserted between tokens map to the end of the preceding token.
+    pub fn original_offset(&self, offset: u64) -> u64 {
+        let idx = self
+            .tokens
+            .partition_point(|(span, _)| span.start <= offset);
+        let Some((span, original)) = idx.checked_sub(1).map(|i| self.tokens[i]) else {
+            return 0;
+        };
+        original + offset.min(span.end) - span.start
+    }
+
+    pub fn original_span(&self, span: Span) -> Span {
+        let start = self.original_offset(span.start);
+        let end = if span.end > span.start {
+            // the end is exclusive, map the last byte of the span
+            self.original_offset(span.end - 1) + 1
+        } else {
+            start
+        };
+        Span { start, end }
+    }
+}
+
+/// Removes comments and redundant whitespace, fails if the source cannot be
+/// tokenized
+pub fn minify(source: &str) -> Result<Minified, Error> {
+    let mut text = String::with_capacity(source.len());
+    let mut map = SourceMap::default();
+    // last two tokens written and their offsets in `text`
+    let mut last: Vec<(Token, usize)> = Vec::new();
+    for token in Tokenizer::new(source) {
+        let token = token?;
+        if let Some((prev, _)) = last.last() {
+            if prev.span.end < token.span.start && merges(&text, &last, &token) {
+                text.push(' ');
+            }
+        }
+        let start = text.len();
+        text.push_str(&token.text);
+        map.tokens.push((
+            Span {
+                start: start as u64,
+                end: text.len() as u64,
+            },
+            token.span.start,
+        ));
+        if last.len() == 2 {
+            last.remove(0);
+        }
+        last.push((token, start));
+    }
+    Ok(Minified { text, map })
+}
+
+/// Re-emits the query in a canonical formatting, fails if the source
+/// cannot be tokenized:
+///
+/// * comments are removed and tokens are separated by exactly one space,
+///   except inside of brackets, around path steps (`.`, `.<`, `::`),
+///   in function calls and indexing (`len(x)`, `x[0]`) and before `,`,
+///   `:` and `;`
+/// * reserved keywords are lowercased
+/// * trailing semicolons are removed
+pub fn canonical_text(source: &str) -> Result<String, Error> {
+    let mut tokens = Tokenizer::new(source).collect::<Result<Vec<_>, _>>()?;
+    while tokens.last().is_some_and(|t| t.kind == Kind::Semicolon) {
+        tokens.pop();
+    }
+    let mut text = String::with_capacity(source.len());
+    let mut last: Vec<(Token, usize)> = Vec::new();
+    for mut token in tokens {
+        if let Kind::Keyword(kw) = token.kind {
+            if kw.is_reserved() {
+                token.text = token.text.to_lowercase().into();
+            }
+        }
+        if let Some((prev, _)) = last.last() {
+            if !is_tight(prev.kind, token.kind) || merges(&text, &last, &token) {
+                text.push(' ');
+            }
+        }
+        let start = text.len();
+        text.push_str(&token.text);
+        if last.len() == 2 {
+            last.remove(0);
+        }
+        last.push((token, start));
+    }
+    Ok(text)
+}
+
+fn is_tight(prev: Kind, next: Kind) -> bool {
+    use Kind::*;
+
+    // path steps, function calls and indexing
+    let attached = matches!(
+        next,
+        Dot | BackwardLink | OptionalLink | OpenParen | OpenBracket
+    ) && matches!(
+        prev,
+        Ident | BacktickName | IntConst | CloseParen | CloseBracket
+    );
+    attached
+        || matches!(
+            prev,
+            OpenParen | OpenBracket | Dot | BackwardLink | OptionalLink | Namespace | At
+        )
+        || matches!(
+            next,
+            CloseParen | CloseBracket | Comma | Semicolon | Colon | Namespace
+        )
+}
+
+/// Whether the token would tokenize differently if written right after the
+/// text without a space. The preceding token is checked too, as e.g. `1.`
+/// followed by `2` becomes a single token.
+fn merges(text: &str, last: &[(Token, usize)], token: &Token) -> bool {
+    let joined = format!("{}{}", &text[last[0].1..], token.text);
+    let expected = last.iter().map(|(t, _)| t).chain([token]);
+    let mut tokens = Tokenizer::new(&joined);
+    for expected in expected {
+        match tokens.next() {
+            Some(Ok(tok)) if tok.kind == expected.kind && tok.text == expected.text => {}
+            _ => return true,
+        }
+    }
+    tokens.next().is_some()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{canonical_text, minify};
+    use crate::position::Span;
+
+    #[test]
+    fn minified() {
+        let source = "SELECT  User { # comment\n  name,\n  friends: { name }\n}\n\
+                      FILTER .name = 'x y'  AND .age >= - 1 ;";
+        let min = minify(source).unwrap();
+        assert_eq!(
+            min.text,
+            "SELECT User{name,friends:{name}}FILTER.name='x y'AND.age>=-1;"
+        );
+        // separated tokens that would merge
+        assert_eq!(minify("select 1 . 2").unwrap().text, "select 1 .2");
+        assert_eq!(minify("select a < = b").unwrap().text, "select a< =b");
+        assert_eq!(
+            minify("select <int64>$x ++ 'a' ++ r'b'").unwrap().text,
+            "select<int64>$x++'a'++r'b'"
+        );
+        assert_eq!(minify("# only comment\n").unwrap().text, "");
+        assert!(minify("select 'oops").is_err());
+    }
+
+    #[test]
+    fn source_map() {
+        let source = "select  User {\n  # comment\n  name\n}";
+        let min = minify(source).unwrap();
+        assert_eq!(min.text, "select User{name}");
+        let name = Span { start: 12, end: 16 };
+        assert_eq!(&min.text[12..16], "name");
+        let original = min.map.original_span(name);
+        assert_eq!(
+            &source[original.start as usize..original.end as usize],
+            "name"
+        );
+        // inserted space maps to the end of `select`
+        assert_eq!(min.map.original_offset(6), 6);
+        assert_eq!(min.map.original_offset(7), 8);
+        let all = min.map.original_span(Span { start: 0, end: 17 });
+        assert_eq!((all.start, all.end), (0, source.len() as u64));
+    }
+
+    #[test]
+    fn canonical() {
+        let canonical = |s| canonical_text(s).unwrap();
+        assert_eq!(
+            canonical(
+                "SELECT  User {\n  name, # comment\n  friends: { name }\n}\nFILTER .name = 'x y' ;"
+            ),
+            "select User { name, friends: { name } } filter .name = 'x y'"
+        );
+        assert_eq!(
+            canonical("select User{name,friends:{name}}filter.name='x y';;"),
+            canonical(
+                "SELECT  User {\n  name, # comment\n  friends: { name }\n}\nFILTER .name = 'x y' ;"
+            ),
+        );
+        assert_eq!(
+            canonical("select std :: len( .<friends[ is User ] )"),
+            "select std::len(.<friends[is User])"
+        );
+        // kept apart where the tokens would merge
+        assert_eq!(canonical("select (1, 2) . 0"), "select (1, 2).0");
+        assert_eq!(canonical("select 1 . 2"), "select 1 .2");
+        assert!(canonical_text("select 'oops").is_err());
+    }
+}
@@ -0,0 +1,144 @@
+use std::fmt;
+
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::messages::{default_template, format_message};
+use crate::position::Span;
+use crate::tokenizer::Error;
+
+/// Limits for parsing untrusted input
+///
+/// All limits are disabled by default. Lengths are in bytes and are checked
+/// by the tokenizer, see
+/// [Tokenizer::with_options](crate::tokenizer::Tokenizer::with_options).
+/// Sizes of the tree are checked by the parser while it builds the tree, see
+/// [Context::with_options](crate::parser::Context::with_options).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct ParserOptions {
+    /// Maximum length of the whole source text
+    pub max_input_len: Option<usize>,
+    /// Maximum length of a single token, including quotes of strings
+    pub max_token_len: Option<usize>,
+    /// Maximum number of nodes of the tree, both tokens and productions
+    pub max_nodes: Option<usize>,
+    /// Maximum nesting of nodes of the tree, a token has a depth of one
+    pub max_depth: Option<usize>,
+}
+
+/// Error of exceeding one of the [ParserOptions] limits
+///
+/// The messages are the ones of the codes of the errors in the
+/// [catalog](crate::messages::CATALOG).
+#[derive(Debug, Clone, PartialEq)]
+pub enum LimitError {
+    InputTooLong {
+        len: usize,
+        max: usize,
+    },
+    TokenTooLong {
+        len: usize,
+        max: usize,
+        span: Span,
+    },
+    /// `span` is the token at which the tree got too large
+    QueryTooLarge {
+        nodes: usize,
+        max: usize,
+        span: Span,
+    },
+    /// `span` is the token at which the tree got too deep
+    QueryTooDeep {
+        depth: usize,
+        max: usize,
+        span: Span,
+    },
+}
+
+impl ParserOptions {
+    pub fn check_input(&self, text: &str) -> Result<(), LimitError> {
+        match self.max_input_len {
+            Some(max) if text.len() > max => Err(LimitError::InputTooLong {
+                len: text.len(),
+                max,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn check_token(&self, span: Span) -> Result<(), LimitError> {
+        let len = (span.end - span.start) as usize;
+        match self.max_token_len {
+            Some(max) if len > max => Err(LimitError::TokenTooLong { len, max, span }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks the number of nodes and the depth of a tree parsed up to the
+    /// token at `span`
+    pub fn check_tree(&self, nodes: usize, depth: usize, span: Span) -> Result<(), LimitError> {
+        match (self.max_nodes, self.max_depth) {
+            (Some(max), _) if nodes > max => Err(LimitError::QueryTooLarge { nodes, max, span }),
+            (_, Some(max)) if depth > max => Err(LimitError::QueryTooDeep { depth, max, span }),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl LimitError {
+    /// Code of the error with the arguments of its message
+    fn code_args(&self) -> (&'static str, [(&'static str, String); 2]) {
+        let (code, args) = match self {
+            LimitError::InputTooLong { len, max } => {
+                ("input-too-long", [("len", len), ("max", max)])
+            }
+            LimitError::TokenTooLong { len, max, .. } => {
+                ("token-too-long", [("len", len), ("max", max)])
+            }
+            LimitError::QueryTooLarge { nodes, max, .. } => {
+                ("query-too-large", [("nodes", nodes), ("max", max)])
+            }
+            LimitError::QueryTooDeep { depth, max, .. } => {
+                ("query-too-deep", [("depth", depth), ("max", max)])
+            }
+        };
+        (code, args.map(|(name, value)| (name, value.to_string())))
+    }
+
+    pub fn span(&self) -> Span {
+        match self {
+            LimitError::InputTooLong { .. } => Span::default(),
+            LimitError::TokenTooLong { span, .. }
+            | LimitError::QueryTooLarge { span, .. }
+            | LimitError::QueryTooDeep { span, .. } => *span,
+        }
+    }
+}
+
+impl fmt::Display for LimitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (code, args) = self.code_args();
+        let args: Vec<_> = args
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value))
+            .collect();
+        f.write_str(&format_message(
+            default_template(code).unwrap_or(code),
+            &args,
+        ))
+    }
+}
+
+impl std::error::Error for LimitError {}
+
+impl From<LimitError> for Error {
+    fn from(err: LimitError) -> Error {
+        let span = err.span();
+        Error::new(&err).with_span(span)
+    }
+}
+
+impl From<LimitError> for Diagnostic {
+    fn from(err: LimitError) -> Diagnostic {
+        let (code, args) = err.code_args();
+        Diagnostic::with_code(Severity::Error, code, &args, err.span())
+    }
+}
@@ -0,0 +1,343 @@
+//! Pagination of select queries
+//!
+//! [paginate] adds `offset` and `limit` to the main `select` of a query, or
+//! combines them with the existing ones, so that the page is taken from the
+//! results of the original query:
+//!
+//! ```text
+//! select User order by .name offset 10 limit 100
+//! select User order by .name offset 30 limit 20     # page: offset 20, limit 20
+//! ```
+//!
+//! Cursor-based pages (`filter .id > <uuid>$after`) require the results to be
+//! ordered by `.id`, `order by .id` is added if the query is not ordered.
+use std::ops::Range;
+
+use crate::position::Span;
+use crate::shape::statement;
+use crate::tokenizer::{self, Kind, Token, Tokenizer};
+
+#[derive(Debug, thiserror::Error)]
+pub enum PaginateError {
+    #[error("{0}")]
+    Tokenizer(tokenizer::Error),
+    #[error("only select queries can be paginated")]
+    NotSelect,
+    #[error("{0:?} is not a valid parameter name")]
+    InvalidParameter(String),
+    #[error("cursor pagination requires the query to be ordered by `.id`")]
+    NotOrderedById(Span),
+    #[error("cursor pagination cannot be combined with `{0}` of the query")]
+    CursorWithClause(&'static str, Span),
+}
+
+/// Page to return
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Page<'a> {
+    /// Skips `offset` results and returns at most `limit`
+    Offset { offset: u64, limit: u64 },
+    /// Returns at most `limit` results after the object, whose id is passed
+    /// in the `param` query parameter
+    After { param: &'a str, limit: u64 },
+}
+
+/// Top-level clauses of a select, as ranges of token indexes including the
+/// keywords
+#[derive(Debug, Default)]
+pub(crate) struct Clauses {
+    pub filter: Option<Range<usize>>,
+    pub order_by: Option<Range<usize>>,
+    pub offset: Option<Range<usize>>,
+    pub limit: Option<Range<usize>>,
+}
+
+/// Adds the page to the main select of the query
+pub fn paginate(query: &str, page: &Page) -> Result<String, PaginateError> {
+    let tokens = Tokenizer::new(query)
+        .validated_values()
+        .map(|t| t.map(|t| t.cloned()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(PaginateError::Tokenizer)?;
+    let stmt = statement(&tokens)
+        .map_err(PaginateError::Tokenizer)?
+        .filter(|s| s.keyword == "select")
+        .ok_or(PaginateError::NotSelect)?;
+    let start = tokens
+        .iter()
+        .position(|t| t.span.start == stmt.span.start)
+        .unwrap();
+    let (clauses, end) = find_clauses(&tokens, start + 1);
+    if end == start + 1 {
+        return Err(PaginateError::NotSelect);
+    }
+
+    let text = |range: &Option<Range<usize>>, keyword_len: usize| {
+        range.as_ref().filter(|r| r.len() > keyword_len).map(|r| {
+            &query[tokens[r.start + keyword_len].span.start as usize
+                ..tokens[r.end - 1].span.end as usize]
+        })
+    };
+    let mut filter = text(&clauses.filter, 1).map(String::from);
+    let mut order_by = text(&clauses.order_by, 2).map(String::from);
+    let offset;
+    let limit;
+    match *page {
+        Page::Offset {
+            offset: page_offset,
+            limit: page_limit,
+        } => {
+            offset = match text(&clauses.offset, 1) {
+                Some(expr) => match int_value(expr) {
+                    Some(value) => value.saturating_add(page_offset).to_string(),
+                    None => format!("({expr}) + {page_offset}"),
+                },
+                None => page_offset.to_string(),
+            };
+            limit = match text(&clauses.limit, 1) {
+                Some(expr) => match int_value(expr) {
+                    Some(value) => value
+                        .saturating_sub(page_offset)
+                        .min(page_limit)
+                        .to_string(),
+                    None => format!("max({{0, min({{({expr}) - {page_offset}, {page_limit}}})}})"),
+                },
+                None => page_limit.to_string(),
+            };
+        }
+        Page::After {
+            param,
+            limit: page_limit,
+        } => {
+            if param.is_empty()
+                || param.starts_with(|c: char| c.is_ascii_digit())
+                || !param.chars().all(|c| c.is_alphanumeric() || c == '_')
+            {
+                return Err(PaginateError::InvalidParameter(param.into()));
+            }
+            for (name, range) in [("offset", &clauses.offset), ("limit", &clauses.limit)] {
+                if let Some(range) = range {
+                    return Err(PaginateError::CursorWithClause(
+                        name,
+                        clause_span(&tokens, range),
+                    ));
+                }
+            }
+            let op = match &clauses.order_by {
+                None => {
+                    order_by = Some(".id".into());
+                    ">"
+                }
+                Some(range) => match id_order(&tokens[range.start + 2..range.end]) {
+                    Some(op) => op,
+                    None => return Err(PaginateError::NotOrderedById(clause_span(&tokens, range))),
+                },
+            };
+            let cursor = format!(".id {op} <uuid>${param}");
+            filter = Some(match filter {
+                Some(expr) => format!("({expr}) and {cursor}"),
+                None => cursor,
+            });
+            offset = String::new();
+            limit = page_limit.to_string();
+        }
+    }
+
+    let first_clause = [
+        &clauses.filter,
+        &clauses.order_by,
+        &clauses.offset,
+        &clauses.limit,
+    ]
+    .into_iter()
+    .flatten()
+    .map(|r| r.start)
+    .min();
+    let prefix_end = match first_clause {
+        Some(idx) => tokens[idx].span.start,
+        None => tokens[end - 1].span.end,
+    };
+    let mut result = query[..prefix_end as usize].trim_end().to_string();
+    for (keyword, value) in [
+        ("filter", filter),
+        ("order by", order_by),
+        ("offset", Some(offset)),
+        ("limit", Some(limit)),
+    ] {
+        if let Some(value) = value.filter(|v| !v.is_empty()) {
+            result.push(' ');
+            result.push_str(keyword);
+            result.push(' ');
+            result.push_str(&value);
+        }
+    }
+    result.push_str(&query[tokens[end - 1].span.end as usize..]);
+    Ok(result)
+}
+
+/// Finds top-level clauses of a select, starting after the `select` keyword.
+/// Returns the clauses and the index after the last token of the statement.
+pub(crate) fn find_clauses(tokens: &[Token], start: usize) -> (Clauses, usize) {
+    let mut starts = Vec::new();
+    let mut depth = 0usize;
+    let mut pos = start;
+    while let Some(tok) = tokens.get(pos) {
+        match tok.kind {
+            Kind::OpenParen | Kind::OpenBracket | Kind::OpenBrace => depth += 1,
+            Kind::CloseParen | Kind::CloseBracket | Kind::CloseBrace => {
+                depth = depth.saturating_sub(1)
+            }
+            Kind::Semicolon if depth == 0 => break,
+            Kind::Keyword(kw) if depth == 0 => match kw.0 {
+                "filter" | "offset" | "limit" => starts.push((kw.0, pos)),
+                "order" if is_keyword(tokens.get(pos + 1), "by") => starts.push((kw.0, pos)),
+                _ => {}
+            },
+            _ => {}
+        }
+        pos += 1;
+    }
+    let mut clauses = Clauses::default();
+    for (i, &(keyword, clause_start)) in starts.iter().enumerate() {
+        let clause_end = starts.get(i + 1).map_or(pos, |&(_, next)| next);
+        let clause = match keyword {
+            "filter" => &mut clauses.filter,
+            "order" => &mut clauses.order_by,
+            "offset" => &mut clauses.offset,
+            _ => &mut clauses.limit,
+        };
+        *clause = Some(clause_start..clause_end);
+    }
+    (clauses, pos)
+}
+
+/// Returns the comparison operator for the cursor if the tokens of
+/// `order by` are `.id`, `.id asc` or `.id desc`
+fn id_order(tokens: &[Token]) -> Option<&'static str> {
+    let [dot, id, rest @ ..] = tokens else {
+        return None;
+    };
+    if dot.kind != Kind::Dot || id.kind != Kind::Ident || id.text != "id" {
+        return None;
+    }
+    match rest {
+        [] => Some(">"),
+        [dir] if is_keyword(Some(dir), "asc") => Some(">"),
+        [dir] if is_keyword(Some(dir), "desc") => Some("<"),
+        _ => None,
+    }
+}
+
+fn is_keyword(token: Option<&Token>, keyword: &str) -> bool {
+    matches!(token.map(|t| t.kind), Some(Kind::Keyword(kw)) if kw.0 == keyword)
+}
+
+fn int_value(expr: &str) -> Option<u64> {
+    expr.replace('_', "").parse().ok()
+}
+
+fn clause_span(tokens: &[Token], range: &Range<usize>) -> Span {
+    tokens[range.start].span.extend(&tokens[range.end - 1].span)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{paginate, Page, PaginateError};
+
+    fn offset(query: &str, offset: u64, limit: u64) -> String {
+        paginate(query, &Page::Offset { offset, limit }).unwrap()
+    }
+
+    fn after(query: &str) -> Result<String, PaginateError> {
+        paginate(
+            query,
+            &Page::After {
+                param: "after",
+                limit: 10,
+            },
+        )
+    }
+
+    #[test]
+    fn offset_limit() {
+        assert_eq!(
+            offset("select User { name }", 20, 10),
+            "select User { name } offset 20 limit 10"
+        );
+        assert_eq!(
+            offset("select User filter .active order by .name;", 0, 10),
+            "select User filter .active order by .name offset 0 limit 10;"
+        );
+        // the page is taken from the results of the original query
+        assert_eq!(
+            offset("select User order by .name offset 10 limit 100", 20, 20),
+            "select User order by .name offset 30 limit 20"
+        );
+        assert_eq!(
+            offset("select User limit 25", 20, 10),
+            "select User offset 20 limit 5"
+        );
+        assert_eq!(
+            offset("select User limit 5", 20, 10),
+            "select User offset 20 limit 0"
+        );
+        assert_eq!(
+            offset("select User offset $skip limit $n", 20, 10),
+            "select User offset ($skip) + 20 \
+             limit max({0, min({($n) - 20, 10})})"
+        );
+        // nested clauses are kept
+        assert_eq!(
+            offset(
+                "with x := (select Post limit 1) \
+                 select User { posts: { title } limit 3 } filter .x = x",
+                0,
+                10
+            ),
+            "with x := (select Post limit 1) \
+             select User { posts: { title } limit 3 } filter .x = x \
+             offset 0 limit 10"
+        );
+        assert!(matches!(
+            paginate(
+                "insert User",
+                &Page::Offset {
+                    offset: 0,
+                    limit: 1
+                }
+            ),
+            Err(PaginateError::NotSelect)
+        ));
+    }
+
+    #[test]
+    fn cursor() {
+        assert_eq!(
+            after("select User { name }").unwrap(),
+            "select User { name } filter .id > <uuid>$after \
+             order by .id limit 10"
+        );
+        assert_eq!(
+            after("select User filter .active or .admin order by .id desc").unwrap(),
+            "select User filter (.active or .admin) and .id < <uuid>$after \
+             order by .id desc limit 10"
+        );
+        assert!(matches!(
+            after("select User order by .name"),
+            Err(PaginateError::NotOrderedById(_))
+        ));
+        assert!(matches!(
+            after("select User limit 5"),
+            Err(PaginateError::CursorWithClause("limit", _))
+        ));
+        assert!(matches!(
+            paginate(
+                "select User",
+                &Page::After {
+                    param: "a; delete User",
+                    limit: 1
+                }
+            ),
+            Err(PaginateError::InvalidParameter(_))
+        ));
+    }
+}
@@ -0,0 +1,141 @@
+//! Query parameters and the types they are cast to
+//!
+//! Parameters must be cast when they are used, like `<str>$name` or
+//! `<optional array<int64>>$ids`, so their types are known from tokens.
+use crate::position::Span;
+use crate::tokenizer::{Error, Kind, Token, Tokenizer, Value};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Param {
+    /// Name without the `$`, digits for positional parameters
+    pub name: String,
+    /// Type of the cast, e.g. `str` or `array<int64>`, `None` if the
+    /// parameter is not cast
+    pub type_name: Option<String>,
+    /// Cast is `<optional T>`
+    pub optional: bool,
+    /// Span of the first occurrence
+    pub span: Span,
+}
+
+impl Param {
+    pub fn is_positional(&self) -> bool {
+        self.name.bytes().all(|b| b.is_ascii_digit())
+    }
+}
+
+/// Parameters of the query in order of their first occurrence, fails if the
+/// query cannot be tokenized or a parameter is cast to different types
+pub fn query_params(text: &str) -> Result<Vec<Param>, Error> {
+    let tokens = Tokenizer::new(text)
+        .validated_values()
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut params: Vec<Param> = Vec::new();
+    for (idx, token) in tokens.iter().enumerate() {
+        if token.kind != Kind::Parameter {
+            continue;
+        }
+        let Some(Value::String(name)) = &token.value else {
+            continue;
+        };
+        let (type_name, optional) = match cast_before(&tokens, idx) {
+            Some((_, cast)) => match cast.strip_prefix("optional ") {
+                Some(inner) => (Some(inner.to_string()), true),
+                None => (Some(cast), false),
+            },
+            None => (None, false),
+        };
+        match params.iter().find(|p| p.name == *name) {
+            Some(prev) if prev.type_name != type_name || prev.optional != optional => {
+                return Err(Error::new(format!(
+                    "parameter ${name} is cast to different types: {} and {}",
+                    describe(prev.type_name.as_deref(), prev.optional),
+                    describe(type_name.as_deref(), optional),
+                ))
+                .with_span(token.span));
+            }
+            Some(_) => {}
+            None => params.push(Param {
+                name: name.clone(),
+                type_name,
+                optional,
+                span: token.span,
+            }),
+        }
+    }
+    Ok(params)
+}
+
+/// Index of the `<` of the cast right before the token at `idx` and the
+/// text of the cast, with a single space after `optional` and no other
+/// whitespace
+pub(crate) fn cast_before(tokens: &[Token], idx: usize) -> Option<(usize, String)> {
+    if idx == 0 || tokens[idx - 1].kind != Kind::Greater {
+        return None;
+    }
+    let mut depth = 0usize;
+    let mut start = idx - 1;
+    loop {
+        match tokens[start].kind {
+            Kind::Greater => depth += 1,
+            Kind::Less => depth -= 1,
+            _ => {}
+        }
+        if depth == 0 {
+            break;
+        }
+        start = start.checked_sub(1)?;
+    }
+    let mut text = String::new();
+    for token in &tokens[start + 1..idx - 1] {
+        text.push_str(&token.text);
+        if matches!(token.kind, Kind::Keyword(kw) if kw.0 == "optional") {
+            text.push(' ');
+        }
+    }
+    Some((start, text))
+}
+
+fn describe(type_name: Option<&str>, optional: bool) -> String {
+    match (type_name, optional) {
+        (Some(name), true) => format!("<optional {name}>"),
+        (Some(name), false) => format!("<{name}>"),
+        (None, _) => "no cast".into(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::query_params;
+
+    #[test]
+    fn params() {
+        let params = query_params(
+            "select User filter .name = <str>$name \
+             and .id in array_unpack(<optional array < uuid >>$ids) \
+             and .name != <str>$name and .nick = $`nick name` limit <int64>$0",
+        )
+        .unwrap();
+        let described: Vec<_> = params
+            .iter()
+            .map(|p| (&p.name[..], p.type_name.as_deref(), p.optional))
+            .collect();
+        assert_eq!(
+            described,
+            [
+                ("name", Some("str"), false),
+                ("ids", Some("array<uuid>"), true),
+                ("nick name", None, false),
+                ("0", Some("int64"), false),
+            ]
+        );
+        assert!(params[3].is_positional());
+        assert_eq!(params[0].span.start, 32);
+
+        let err = query_params("select <str>$x ++ <int32>$x").unwrap_err();
+        assert_eq!(
+            err.message,
+            "parameter $x is cast to different types: <str> and <int32>"
+        );
+    }
+}
@@ -0,0 +1,126 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use sha2::{Digest, Sha256};
+
+use super::{Ast, AstOwned, ParseMode, Parser};
+use crate::options::ParserOptions;
+use crate::tokenizer::{Error, ErrorKind, Kind};
+
+/// A parse memoized by [ParserCache]
+#[derive(Debug)]
+pub struct ParseResult {
+    pub ast: Option<AstOwned>,
+    pub errors: Vec<Error>,
+}
+
+/// Everything a result depends on, besides the grammar
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct Key {
+    start: Kind,
+    /// See [Parser::config]
+    config: (ParseMode, ParserOptions, ParserOptions),
+    /// SHA-256 of the source
+    source: [u8; 32],
+}
+
+/// Results of parsing by the hash of the source, for servers that parse the
+/// same queries again and again
+///
+/// At most `capacity` results are kept, the least recently used one is
+/// evicted first. The cache is [Sync], so a server can share one between
+/// its threads, each parsing with its own [Parser]. Results of parsers with
+/// different modes or [ParserOptions] are kept apart, but all the parsers
+/// must use the same grammar spec.
+pub struct ParserCache {
+    capacity: usize,
+    state: Mutex<State>,
+}
+
+#[derive(Default)]
+struct State {
+    entries: HashMap<Key, (Arc<ParseResult>, u64)>,
+    /// Keys by the time of their last use
+    recent: BTreeMap<u64, Key>,
+    clock: u64,
+}
+
+impl ParserCache {
+    pub fn new(capacity: usize) -> Self {
+        ParserCache {
+            capacity,
+            state: Mutex::default(),
+        }
+    }
+
+    /// Parses `source` with the parser (see [Parser::parse]), unless its
+    /// result is cached. Cancelled parses are not cached.
+    pub fn parse(&self, parser: &mut Parser, start: Kind, source: &str) -> Arc<ParseResult> {
+        let key = Key {
+            start,
+            config: parser.config(),
+            source: Sha256::digest(source.as_bytes()).into(),
+        };
+        if let Some(result) = self.state().get(&key) {
+            return result;
+        }
+        let (node, errors) = parser.parse(start, source);
+        let result = Arc::new(ParseResult {
+            ast: node.map(|node| Ast::new(node, source).into_owned()),
+            errors,
+        });
+        if !result
+            .errors
+            .iter()
+            .any(|e| e.kind == Some(ErrorKind::Cancelled))
+        {
+            self.state().insert(key, result.clone(), self.capacity);
+        }
+        result
+    }
+
+    pub fn len(&self) -> usize {
+        self.state().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&self) {
+        *self.state() = State::default();
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        // the state is consistent between the calls, even if one panicked
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl State {
+    fn get(&mut self, key: &Key) -> Option<Arc<ParseResult>> {
+        let (result, used) = self.entries.get_mut(key)?;
+        self.recent.remove(used);
+        self.clock += 1;
+        *used = self.clock;
+        self.recent.insert(self.clock, *key);
+        Some(result.clone())
+    }
+
+    fn insert(&mut self, key: Key, result: Arc<ParseResult>, capacity: usize) {
+        if capacity == 0 {
+            return;
+        }
+        self.clock += 1;
+        if let Some((_, used)) = self.entries.insert(key, (result, self.clock)) {
+            self.recent.remove(&used);
+        }
+        self.recent.insert(self.clock, key);
+        while self.entries.len() > capacity {
+            let Some((_, oldest)) = self.recent.pop_first() else {
+                break;
+            };
+            self.entries.remove(&oldest);
+        }
+    }
+}
@@ -0,0 +1,159 @@
+use std::fmt::Write;
+
+use super::cst::CSTNode;
+use super::spec::Spec;
+
+/// Records which productions of the grammar occur in parsed trees, to find
+/// the parts of the grammar a corpus does not exercise
+#[derive(Debug, Clone)]
+pub struct Coverage {
+    /// Number of occurrences by production id
+    counts: Vec<u64>,
+}
+
+impl Coverage {
+    pub fn new(spec: &Spec) -> Coverage {
+        Coverage {
+            counts: vec![0; spec.production_names.len()],
+        }
+    }
+
+    /// Counts productions of the tree. Productions that were inlined into
+    /// their argument are counted too.
+    pub fn record(&mut self, root: CSTNode) {
+        // an explicit stack, since the trees can be very deep
+        let mut stack = vec![root];
+        while let Some(node) = stack.pop() {
+            let CSTNode::Production(prod) = node else {
+                continue;
+            };
+            for id in std::iter::once(&prod.id).chain(prod.inlined_ids.unwrap_or_default()) {
+                if let Some(count) = self.counts.get_mut(*id) {
+                    *count += 1;
+                }
+            }
+            stack.extend(prod.args.iter());
+        }
+    }
+
+    pub fn count(&self, production_id: usize) -> u64 {
+        self.counts.get(production_id).copied().unwrap_or(0)
+    }
+
+    /// Number of productions that occurred at least once
+    pub fn covered(&self) -> usize {
+        self.counts.iter().filter(|c| **c > 0).count()
+    }
+
+    pub fn total(&self) -> usize {
+        self.counts.len()
+    }
+
+    /// Renders a summary and, for every non-terminal that is not fully
+    /// covered, the productions that never occurred:
+    ///
+    /// ```text
+    /// Grammar coverage: 2 of 3 productions (66.7%)
+    ///
+    /// Expr: 1 of 2
+    ///     reduce_Expr_PLUS_Expr
+    /// ```
+    pub fn report(&self, spec: &Spec) -> String {
+        let mut buf = String::new();
+        let percent = if self.total() == 0 {
+            100.0
+        } else {
+            self.covered() as f64 * 100.0 / self.total() as f64
+        };
+        writeln!(
+            buf,
+            "Grammar coverage: {} of {} productions ({percent:.1}%)",
+            self.covered(),
+            self.total()
+        )
+        .unwrap();
+
+        // non-terminals in the order of their first production
+        let mut non_terms: Vec<(&str, Vec<usize>)> = Vec::new();
+        for (id, (non_term, _)) in spec.production_names.iter().enumerate() {
+            match non_terms.iter_mut().find(|(n, _)| n == non_term) {
+                Some((_, ids)) => ids.push(id),
+                None => non_terms.push((non_term, vec![id])),
+            }
+        }
+        for (non_term, ids) in non_terms {
+            let uncovered: Vec<_> = ids.iter().filter(|id| self.count(**id) == 0).collect();
+            if uncovered.is_empty() {
+                continue;
+            }
+            let covered = ids.len() - uncovered.len();
+            writeln!(buf, "\n{non_term}: {covered} of {}", ids.len()).unwrap();
+            for id in uncovered {
+                writeln!(buf, "    {}", spec.production_names[*id].1).unwrap();
+            }
+        }
+        buf
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use indexmap::IndexMap;
+
+    use super::Coverage;
+    use crate::parser::cst::{CSTNode, Production, Terminal};
+    use crate::parser::spec::Spec;
+    use crate::position::Span;
+    use crate::tokenizer::Kind;
+
+    fn spec(names: &[(&str, &str)]) -> Spec {
+        Spec {
+            actions: Vec::new(),
+            goto: Vec::new(),
+            inlines: IndexMap::new(),
+            production_names: names
+                .iter()
+                .map(|(n, p)| (n.to_string(), p.to_string()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn report() {
+        let spec = spec(&[
+            ("Expr", "reduce_Ident"),
+            ("Expr", "reduce_Expr_PLUS_Expr"),
+            ("Stmt", "reduce_Expr"),
+            ("Stmt", "reduce_Empty"),
+        ]);
+        let a = Terminal::from_token(crate::tokenizer::Token {
+            kind: Kind::Ident,
+            text: "a".into(),
+            value: None,
+            span: Span { start: 0, end: 1 },
+        });
+        let args = [CSTNode::Terminal(&a)];
+        // `Stmt -> Expr` was inlined into the `Expr`
+        let root = CSTNode::Production(Production {
+            id: 0,
+            args: &args,
+            span: None,
+            inlined_ids: Some(&[2]),
+        });
+        let mut coverage = Coverage::new(&spec);
+        coverage.record(root);
+        coverage.record(root);
+        assert_eq!(coverage.count(0), 2);
+        assert_eq!(coverage.count(2), 2);
+        assert_eq!(coverage.count(1), 0);
+        assert_eq!(coverage.covered(), 2);
+        assert_eq!(
+            coverage.report(&spec),
+            "Grammar coverage: 2 of 4 productions (50.0%)\n\
+             \n\
+             Expr: 1 of 2\n    reduce_Expr_PLUS_Expr\n\
+             \n\
+             Stmt: 1 of 2\n    reduce_Empty\n"
+        );
+    }
+}
@@ -22,7 +22,7 @@ pub struct Terminal {
     pub text: String,
     pub value: Option<Value>,
     pub span: Span,
-    pub(super) is_placeholder: bool,
+    pub(crate) is_placeholder: bool,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -53,6 +53,12 @@ impl std::fmt::Display for Terminal {
 }
 
 impl Terminal {
+    /// Returns true if the terminal was not in the input, but was injected
+    /// during error recovery (or by [`ParseMode::Tolerant`](super::ParseMode))
+    pub fn is_placeholder(&self) -> bool {
+        self.is_placeholder
+    }
+
     pub fn from_token(token: Token) -> Self {
         Terminal {
             kind: token.kind,
@@ -76,3 +82,118 @@ impl Terminal {
         }
     }
 }
+
+/// Kind of a node in a [CSTBuffer]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u64)]
+pub enum BufferTag {
+    /// `[tag]`
+    Empty = 0,
+    /// `[tag, terminal index, start, end]`
+    Terminal = 1,
+    /// Terminal injected by error recovery, encoded as [BufferTag::Terminal]
+    Placeholder = 2,
+    /// `[tag, production id, number of args, start, end]`, followed by the
+    /// args. Start and end are `u64::MAX` for productions without a span.
+    Production = 3,
+}
+
+/// The CST flattened in pre-order into a sequence of words, for passing the
+/// tree across FFI without allocating an object per node.
+///
+/// Terminals are referred to by their index in `terminals`, so that the
+/// text and values are converted only once.
+#[derive(Debug, Default, Clone)]
+pub struct CSTBuffer<'a> {
+    pub words: Vec<u64>,
+    pub terminals: Vec<&'a Terminal>,
+}
+
+impl<'a> CSTNode<'a> {
+    pub fn to_buffer(&self) -> CSTBuffer<'a> {
+        let mut buf = CSTBuffer::default();
+        // an explicit stack, since the trees can be very deep
+        let mut stack = vec![*self];
+        while let Some(node) = stack.pop() {
+            match node {
+                CSTNode::Empty => buf.words.push(BufferTag::Empty as u64),
+                CSTNode::Terminal(terminal) => {
+                    let tag = if terminal.is_placeholder {
+                        BufferTag::Placeholder
+                    } else {
+                        BufferTag::Terminal
+                    };
+                    buf.words.extend([
+                        tag as u64,
+                        buf.terminals.len() as u64,
+                        terminal.span.start,
+                        terminal.span.end,
+                    ]);
+                    buf.terminals.push(terminal);
+                }
+                CSTNode::Production(prod) => {
+                    let span = prod.span.map_or((u64::MAX, u64::MAX), |s| (s.start, s.end));
+                    buf.words.extend([
+                        BufferTag::Production as u64,
+                        prod.id as u64,
+                        prod.args.len() as u64,
+                        span.0,
+                        span.1,
+                    ]);
+                    stack.extend(prod.args.iter().rev());
+                }
+            }
+        }
+        buf
+    }
+}
+
+impl CSTBuffer<'_> {
+    /// The words as little-endian bytes
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.words.iter().flat_map(|w| w.to_le_bytes()).collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{BufferTag, CSTNode, Production};
+    use crate::position::Span;
+    use crate::tokenizer::{terminal, Kind};
+
+    #[test]
+    fn buffer() {
+        let a = terminal(Kind::Ident, "a", 0, false);
+        let b = terminal(Kind::Ident, "", 2, true);
+        let inner = [CSTNode::Terminal(&b), CSTNode::Empty];
+        let args = [
+            CSTNode::Terminal(&a),
+            CSTNode::Production(Production {
+                id: 7,
+                args: &inner,
+                span: None,
+                inlined_ids: None,
+            }),
+        ];
+        let root = CSTNode::Production(Production {
+            id: 3,
+            args: &args,
+            span: Some(Span { start: 0, end: 2 }),
+            inlined_ids: None,
+        });
+        let buf = root.to_buffer();
+        let (t, p, e) = (
+            BufferTag::Terminal as u64,
+            BufferTag::Production as u64,
+            BufferTag::Empty as u64,
+        );
+        let m = u64::MAX;
+        assert_eq!(
+            buf.words,
+            vec![p, 3, 2, 0, 2, t, 0, 0, 1, p, 7, 2, m, m, 2, 1, 2, 2, e]
+        );
+        assert_eq!(buf.terminals.len(), 2);
+        assert!(buf.terminals[1].is_placeholder());
+        assert_eq!(buf.to_bytes().len(), buf.words.len() * 8);
+    }
+}
@@ -1,9 +1,9 @@
 use crate::tokenizer::Kind;
 use crate::{keywords::Keyword, position::Span};
 
-use super::{CSTNode, Context, Error, Parser, StackNode, Terminal};
+use super::{CSTNode, Context, Error, StackNode, Terminal, Track};
 
-impl Parser<'_> {
+impl Track<'_> {
     pub(super) fn custom_error(&self, ctx: &Context, token: &Terminal) -> Option<Error> {
         let ltok = self.get_from_top(0).unwrap();
 
@@ -18,6 +18,7 @@ impl Parser<'_> {
                     span: Span::default(),
                     hint: Some("Use `analyze` to show query performance details".to_string()),
                     details: None,
+                    kind: None,
                 }
             });
         }
@@ -62,6 +63,7 @@ impl Parser<'_> {
                     span: super::get_span_of_nodes(&[last.value]).unwrap_or_default(),
                     hint: None,
                     details: None,
+                    kind: None,
                 });
             }
 
@@ -104,6 +106,7 @@ impl Parser<'_> {
                     span,
                     hint: None,
                     details: None,
+                    kind: None,
                 });
             },
 
@@ -114,6 +117,7 @@ impl Parser<'_> {
                         span: Span { start: token.span.start - 1, end: token.span.start },
                         hint: None,
                         details: None,
+                        kind: None,
                     })
                 }
             }
@@ -283,6 +287,26 @@ impl Parser<'_> {
     }
 }
 
+impl<'s> Track<'s> {
+    /// When the error occurred within a shape, returns the stack node from
+    /// which the parsing of the offending shape element started: either the
+    /// comma before the element or the opening brace of the shape.
+    pub(super) fn shape_element_start(&self, ctx: &Context) -> Option<&'s StackNode<'s>> {
+        let (i, rule) = self.get_rule(ctx)?;
+        if !matches!(rule, ParserRule::Shape) {
+            return None;
+        }
+        let mut node = self.stack_top;
+        for _ in 0..i {
+            if Cond::Terminal(Kind::Comma).check(node, ctx) {
+                return Some(node);
+            }
+            node = node.parent?;
+        }
+        Some(node)
+    }
+}
+
 fn unexpected_reserved_keyword(text: &str, span: Span) -> Error {
     let text_upper = text.to_uppercase();
     Error {
@@ -297,6 +321,7 @@ fn unexpected_reserved_keyword(text: &str, span: Span) -> Error {
             "Use a different identifier or quote the name \
             with backticks: `{text}`"
         )),
+        kind: None,
     }
 }
 
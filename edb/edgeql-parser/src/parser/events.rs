@@ -0,0 +1,94 @@
+use super::{Action, Context, Terminal, CANCELLED, UNEXPECTED};
+use crate::position::Span;
+use crate::tokenizer::{Error, ErrorKind, Kind};
+
+/// Events of [parse_events]
+///
+/// An LR parser recognizes a production only after all of its children, so
+/// nodes are reported bottom-up: [Event::Node] is emitted when a production
+/// is reduced and adopts the last `args` siblings (tokens or nodes) as its
+/// children. In builders of rowan-like trees, this is `start_node_at` of a
+/// checkpoint directly followed by `finish_node`.
+#[derive(Debug, Clone)]
+pub enum Event<'a> {
+    Token(&'a Terminal),
+    Node {
+        production_id: usize,
+        args: usize,
+    },
+    /// The parse stops after the first error
+    Error(Error),
+}
+
+/// The second EOI, see `parse`, it is not a part of the tree. Its span is
+/// the end of the input, which is only needed for errors.
+static EOI: Terminal = Terminal {
+    kind: Kind::EOI,
+    text: String::new(),
+    value: None,
+    span: Span { start: 0, end: 0 },
+    is_placeholder: false,
+};
+
+/// Parses `input`, reporting the nodes to `sink` instead of building the CST
+///
+/// Only the parser stack is kept in memory, so consumers can build their own
+/// structures or stream analyses over very large scripts. There is no error
+/// recovery: parsing stops at the first unexpected token, use [parse] for
+/// diagnostics. Productions are never inlined, so the events may describe a
+/// deeper tree than [parse] returns (see [Spec::inlines]).
+///
+/// Returns `true` if the whole input was parsed.
+///
+/// [parse]: super::parse
+/// [Spec::inlines]: super::Spec::inlines
+pub fn parse_events<'a>(
+    input: &'a [Terminal],
+    ctx: &'a Context,
+    mut sink: impl FnMut(Event<'a>),
+) -> bool {
+    let end = input.last().map(|t| t.span.end).unwrap_or_default();
+    let mut stack = vec![0];
+    for token in input.iter().chain([&EOI]) {
+        let is_eoi = std::ptr::eq(token, &EOI);
+        let span = if is_eoi {
+            Span { start: end, end }
+        } else {
+            token.span
+        };
+        if ctx.is_aborted() {
+            let error = Error::new(CANCELLED)
+                .with_span(span)
+                .with_kind(ErrorKind::Cancelled);
+            sink(Event::Error(error));
+            return false;
+        }
+        loop {
+            let state = *stack.last().unwrap();
+            let Some(action) = ctx.spec.actions[state].get(&token.kind) else {
+                let error = Error::new(format!("{UNEXPECTED} {token}")).with_span(span);
+                sink(Event::Error(error));
+                return false;
+            };
+            match action {
+                Action::Shift(next) => {
+                    if !is_eoi {
+                        sink(Event::Token(token));
+                    }
+                    stack.push(*next);
+                    break;
+                }
+                Action::Reduce(reduce) => {
+                    stack.truncate(stack.len() - reduce.cnt);
+                    let state = *stack.last().unwrap();
+                    stack.push(*ctx.spec.goto[state].get(&reduce.non_term).unwrap());
+                    sink(Event::Node {
+                        production_id: reduce.production_id,
+                        args: reduce.cnt,
+                    });
+                }
+            }
+        }
+    }
+    true
+}
@@ -1,30 +1,166 @@
+mod cache;
+mod coverage;
 mod cst;
 mod custom_errors;
+mod events;
+mod owned;
+mod reusable;
+#[cfg(feature = "serde")]
+mod snapshot;
 mod spec;
-
-pub use cst::{CSTNode, Production, Terminal};
+#[cfg(feature = "rowan")]
+pub mod syntax;
+mod tree;
+
+pub use cache::{ParseResult, ParserCache};
+pub use coverage::Coverage;
+pub use cst::{BufferTag, CSTBuffer, CSTNode, Production, Terminal};
+pub use events::{parse_events, Event};
+pub use owned::{Ast, AstNode, AstOwned};
+pub use reusable::Parser;
+#[cfg(feature = "serde")]
+pub use snapshot::{read_snapshot, write_snapshot, SnapshotError, SNAPSHOT_MAGIC};
 pub use spec::{Action, Reduce, Spec, SpecSerializable};
+pub use tree::{NodeId, Tree};
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::thread::JoinHandle;
+use std::time::Instant;
 
 use append_only_vec::AppendOnlyVec;
 
 use crate::keywords::{self, Keyword};
+use crate::options::{LimitError, ParserOptions};
 use crate::position::Span;
-use crate::tokenizer::{Error, Kind, Value};
+use crate::tokenizer::{Error, ErrorKind, Kind, Value};
 
 pub struct Context<'s> {
     spec: &'s Spec,
+    mode: ParseMode,
     arena: bumpalo::Bump,
     terminal_arena: AppendOnlyVec<Terminal>,
+    cancel: Option<CancelToken>,
+    deadline: Option<Instant>,
+    options: ParserOptions,
+}
+
+/// A flag that aborts a running parse, see [Context::with_cancel]
+///
+/// Clones share the flag, so one can be kept by the parsing thread and the
+/// other by the code that receives newer edits.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        CancelToken::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum ParseMode {
+    /// Every recovery action is reported as an error
+    #[default]
+    Strict,
+    /// For input that is still being typed (completion, signature help).
+    ///
+    /// Missing tokens (dangling operators, unclosed shapes, missing
+    /// expressions) are injected as placeholder terminals without reporting
+    /// errors. More of them are allowed at the end of input.
+    Tolerant,
 }
 
 impl<'s> Context<'s> {
     pub fn new(spec: &'s Spec) -> Self {
+        Context::with_mode(spec, ParseMode::Strict)
+    }
+
+    pub fn with_mode(spec: &'s Spec, mode: ParseMode) -> Self {
         Context {
             spec,
+            mode,
             arena: bumpalo::Bump::new(),
             terminal_arena: AppendOnlyVec::new(),
+            cancel: None,
+            deadline: None,
+            options: ParserOptions::default(),
         }
     }
+
+    /// Aborts parsing once `cancel` is cancelled. The parse then returns no
+    /// node and a single [CANCELLED] error of [ErrorKind::Cancelled].
+    pub fn with_cancel(mut self, cancel: CancelToken) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+
+    /// Aborts parsing once `deadline` has passed, same as a cancelled
+    /// [Context::with_cancel].
+    pub fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Aborts parsing once the tree exceeds [ParserOptions::max_nodes] or
+    /// [ParserOptions::max_depth]. The parse then returns no node and a
+    /// single error of the [LimitError].
+    pub fn with_options(mut self, options: &ParserOptions) -> Self {
+        self.options = *options;
+        self
+    }
+
+    fn is_aborted(&self) -> bool {
+        self.cancel.as_ref().is_some_and(|c| c.is_cancelled())
+            || self.deadline.is_some_and(|d| Instant::now() >= d)
+    }
+}
+
+/// Message of the error returned by an aborted parse, see
+/// [Context::with_cancel] and [Context::with_deadline]
+pub const CANCELLED: &str = "Parsing was cancelled";
+
+static SHARED_SPEC: OnceLock<Spec> = OnceLock::new();
+
+/// Builds the grammar spec shared by the whole process
+///
+/// `load` is called only by the first caller, concurrent callers wait until
+/// it returns. Servers should call this at startup, so the first request
+/// does not pay for building the automaton.
+pub fn warmup(load: impl FnOnce() -> Spec) -> &'static Spec {
+    warmup_cell(&SHARED_SPEC, load)
+}
+
+/// Calls [warmup] on a new thread, so that a short-lived process (e.g. a
+/// command-line tool) can read its input while the spec is being built
+pub fn warmup_in_background(
+    load: impl FnOnce() -> Spec + Send + 'static,
+) -> JoinHandle<&'static Spec> {
+    spawn_warmup(&SHARED_SPEC, load)
+}
+
+fn warmup_cell(cell: &OnceLock<Spec>, load: impl FnOnce() -> Spec) -> &Spec {
+    cell.get_or_init(load)
+}
+
+fn spawn_warmup(
+    cell: &'static OnceLock<Spec>,
+    load: impl FnOnce() -> Spec + Send + 'static,
+) -> JoinHandle<&'static Spec> {
+    std::thread::spawn(move || warmup_cell(cell, load))
+}
+
+/// The spec built by [warmup], if it was called already
+pub fn shared_spec() -> Option<&'static Spec> {
+    SHARED_SPEC.get()
 }
 
 /// This is a const just so we remember to update it everywhere
@@ -36,14 +172,19 @@ pub fn parse<'a>(input: &'a [Terminal], ctx: &'a Context) -> (Option<CSTNode<'a>
         parent: None,
         state: 0,
         value: CSTNode::Empty,
+        size: 0,
+        depth: 0,
+        tree_size: 0,
+        tree_depth: 0,
     });
-    let initial_track = Parser {
+    let initial_track = Track {
         stack_top,
         error_cost: 0,
         node_count: 0,
         can_recover: true,
         errors: Vec::new(),
         has_custom_error: false,
+        skipping_shape_element: None,
     };
 
     // Append EIO token.
@@ -65,6 +206,8 @@ pub fn parse<'a>(input: &'a [Terminal], ctx: &'a Context) -> (Option<CSTNode<'a>
     });
     let input = input.iter().chain([eoi]);
 
+    let tolerant = ctx.mode == ParseMode::Tolerant;
+
     let mut parsers = vec![initial_track];
     let mut prev_span: Option<Span> = None;
     let mut new_parsers = Vec::with_capacity(parsers.len() + 5);
@@ -72,7 +215,19 @@ pub fn parse<'a>(input: &'a [Terminal], ctx: &'a Context) -> (Option<CSTNode<'a>
     for token in input {
         // println!("token {:?}", token);
 
+        if ctx.is_aborted() {
+            let error = Error::new(CANCELLED)
+                .with_span(token.span)
+                .with_kind(ErrorKind::Cancelled);
+            return (None, vec![error]);
+        }
+
         while let Some(mut parser) = parsers.pop() {
+            if parser.skip_shape_element(token) {
+                new_parsers.push(parser);
+                continue;
+            }
+
             let res = parser.act(ctx, token);
 
             if res.is_ok() {
@@ -91,8 +246,30 @@ pub fn parse<'a>(input: &'a [Terminal], ctx: &'a Context) -> (Option<CSTNode<'a>
                     }
                 };
 
+                // option 0: a statement starting after an unterminated one.
+                //   Assume a missing semicolon, instead of trying to treat
+                //   the rest of the input as a part of the first statement.
+                //   Custom errors (option 2) are more specific, so they win.
+                if parser.error_cost == 0 && !parser.has_custom_error {
+                    let recover = parser
+                        .recover_missing_semicolon(ctx, token, prev_span)
+                        .filter(|_| parser.custom_error(ctx, token).is_none());
+                    if let Some(recover) = recover {
+                        // println!("   --> [missing semicolon]");
+
+                        // insert into parsers, to retry the original token
+                        parsers.push(recover);
+                        continue;
+                    }
+                }
+
                 // option 1: inject a token
-                if parser.error_cost <= ERROR_COST_INJECT_MAX && !parser.has_custom_error {
+                let inject_max = if tolerant && token.kind == Kind::EOI {
+                    ERROR_COST_INJECT_MAX_TOLERANT
+                } else {
+                    ERROR_COST_INJECT_MAX
+                };
+                if parser.error_cost <= inject_max && !parser.has_custom_error {
                     let possible_actions = &ctx.spec.actions[parser.stack_top.state];
                     for token_kind in possible_actions.keys() {
                         if parser.can_act(ctx, token_kind).is_none() {
@@ -105,12 +282,17 @@ pub fn parse<'a>(input: &'a [Terminal], ctx: &'a Context) -> (Option<CSTNode<'a>
                             new_token_for_injection(*token_kind, &prev_span, token.span, ctx);
 
                         let cost = injection_cost(token_kind);
-                        let error = Error::new(format!("Missing {injection}")).with_span(gap_span);
-                        inject.push_error(error, cost);
+                        if tolerant {
+                            // placeholder is the expected outcome, not an error
+                            inject.error_cost += cost;
+                            inject.node_count = 0;
+                        } else {
+                            let error =
+                                Error::new(format!("Missing {injection}")).with_span(gap_span);
+                            inject.push_error(error, cost);
+                        }
 
-                        if inject.error_cost <= ERROR_COST_INJECT_MAX
-                            && inject.act(ctx, injection).is_ok()
-                        {
+                        if inject.error_cost <= inject_max && inject.act(ctx, injection).is_ok() {
                             // println!("   --> [inject {injection}]");
 
                             // insert into parsers, to retry the original token
@@ -140,7 +322,30 @@ pub fn parse<'a>(input: &'a [Terminal], ctx: &'a Context) -> (Option<CSTNode<'a>
                     continue;
                 }
 
-                // option 3: skip the token
+                // option 3: skip the whole shape element
+                //   Skipping up to the next comma produces a single error and
+                //   keeps the rest of the shape intact.
+                if parser.error_cost == 0 && !parser.has_custom_error {
+                    if let Some(start) = parser.shape_element_start(ctx) {
+                        let mut recover = parser.clone();
+                        recover.stack_top = start;
+                        recover.skipping_shape_element = Some(0);
+                        // pushed directly, so it is not merged with
+                        // "Unexpected" errors of previous elements
+                        let error =
+                            Error::new(format!("{UNEXPECTED} {token}")).with_span(token.span);
+                        recover.errors.push(error);
+                        recover.error_cost += ERROR_COST_SKIP;
+                        recover.node_count = 0;
+
+                        // println!("   --> [skip shape element]");
+
+                        // insert into parsers, to retry the original token
+                        parsers.push(recover);
+                    }
+                }
+
+                // option 4: skip the token
                 let mut skip = parser;
                 let error = Error::new(format!("{UNEXPECTED} {token}")).with_span(token.span);
                 skip.push_error(error, ERROR_COST_SKIP);
@@ -158,7 +363,7 @@ pub fn parse<'a>(input: &'a [Terminal], ctx: &'a Context) -> (Option<CSTNode<'a>
 
         // has any parser recovered?
         if new_parsers.len() > 1 {
-            new_parsers.sort_by_key(Parser::adjusted_cost);
+            new_parsers.sort_by_key(Track::adjusted_cost);
 
             if new_parsers[0].has_custom_error {
                 // if we have a custom error, just keep that
@@ -186,6 +391,16 @@ pub fn parse<'a>(input: &'a [Terminal], ctx: &'a Context) -> (Option<CSTNode<'a>
         std::mem::swap(&mut parsers, &mut new_parsers);
         prev_span = Some(token.span);
 
+        // abort only if every track exceeds a limit, as any of the others
+        // may still end up as the result
+        let exceeded: Option<Vec<_>> = parsers
+            .iter()
+            .map(|p| p.limit_error(ctx, token.span))
+            .collect();
+        if let Some(error) = exceeded.and_then(|e| e.into_iter().next()) {
+            return (None, vec![error.into()]);
+        }
+
         // for (index, parser) in parsers.iter().enumerate() {
         //     print!(
         //         "p{index} {:06} {:5}:",
@@ -231,14 +446,19 @@ pub fn suggest_next_keyword<'a>(input: &'a [Terminal], ctx: &'a Context) -> (Vec
         parent: None,
         state: 0,
         value: CSTNode::Empty,
+        size: 0,
+        depth: 0,
+        tree_size: 0,
+        tree_depth: 0,
     });
-    let mut parser = Parser {
+    let mut parser = Track {
         stack_top,
         error_cost: 0,
         node_count: 0,
         can_recover: true,
         errors: Vec::new(),
         has_custom_error: false,
+        skipping_shape_element: None,
     };
 
     // parse tokens
@@ -281,7 +501,7 @@ pub fn suggest_next_keyword<'a>(input: &'a [Terminal], ctx: &'a Context) -> (Vec
     (keywords, can_be_ident)
 }
 
-fn starts_with_unexpected_error(a: &Parser) -> bool {
+fn starts_with_unexpected_error(a: &Track) -> bool {
     a.errors
         .first()
         .is_none_or(|x| x.message.starts_with(UNEXPECTED))
@@ -336,10 +556,21 @@ struct StackNode<'p> {
 
     state: usize,
     value: CSTNode<'p>,
+
+    /// number of nodes of the tree of the value
+    size: usize,
+    /// depth of the tree of the value
+    depth: u32,
+
+    /// sum of `size` and maximum of `depth` of this node and its parents,
+    /// which are the nodes the tree will be built of
+    tree_size: usize,
+    tree_depth: u32,
 }
 
+/// One of the candidate parses explored during error recovery
 #[derive(Clone)]
-struct Parser<'s> {
+struct Track<'s> {
     stack_top: &'s StackNode<'s>,
 
     /// sum of cost of every error recovery action
@@ -356,9 +587,13 @@ struct Parser<'s> {
     /// A flag that is used to make the parser prefer custom errors over other
     /// recovery paths
     has_custom_error: bool,
+
+    /// When set, tokens of an invalid shape element are being skipped. The
+    /// value is the depth of brackets opened since the start of skipping.
+    skipping_shape_element: Option<u16>,
 }
 
-impl<'s> Parser<'s> {
+impl<'s> Track<'s> {
     fn act(&mut self, ctx: &'s Context, token: &'s Terminal) -> Result<(), ()> {
         // self.print_stack();
         // println!("INPUT: {}", token.text);
@@ -374,7 +609,7 @@ impl<'s> Parser<'s> {
                     // println!("   --> [shift {next}]");
 
                     // push on stack
-                    self.push_on_stack(ctx, *next, CSTNode::Terminal(token));
+                    self.push_on_stack(ctx, *next, CSTNode::Terminal(token), 1, 1);
                     return Ok(());
                 }
                 Action::Reduce(reduce) => {
@@ -385,10 +620,21 @@ impl<'s> Parser<'s> {
     }
 
     fn reduce(&mut self, ctx: &'s Context, reduce: &'s Reduce) {
+        // size and depth of the production, or of the arg it is inlined to
+        let inline_position = ctx.spec.inlines.get(&reduce.production_id);
+        let (mut size, mut depth) = (1, 0);
+        let mut inlined = (0, 0);
+        let mut position = reduce.cnt;
         let args = ctx.arena.alloc_slice_fill_with(reduce.cnt, |_| {
-            let v = self.stack_top.value;
-            self.stack_top = self.stack_top.parent.unwrap();
-            v
+            let top = self.stack_top;
+            position -= 1;
+            if inline_position.is_some_and(|p| *p as usize == position) {
+                inlined = (top.size, top.depth);
+            }
+            size += top.size;
+            depth = depth.max(top.depth);
+            self.stack_top = top.parent.unwrap();
+            top.value
         });
         args.reverse();
 
@@ -424,7 +670,11 @@ impl<'s> Parser<'s> {
             }
         }
 
-        self.push_on_stack(ctx, next, value);
+        let (size, depth) = match inline_position {
+            Some(_) => inlined,
+            None => (size, depth + 1),
+        };
+        self.push_on_stack(ctx, next, value, size, depth);
 
         // println!(
         //     "   --> [reduce {} ::= ({} popped) at {}/{}]",
@@ -433,15 +683,103 @@ impl<'s> Parser<'s> {
         // self.print_stack();
     }
 
-    pub fn push_on_stack(&mut self, ctx: &'s Context, state: usize, value: CSTNode<'s>) {
+    /// Consumes the token if it belongs to an invalid shape element that is
+    /// being skipped. The comma after the element is consumed too, while the
+    /// closing brace of the shape is left to be parsed.
+    fn skip_shape_element(&mut self, token: &Terminal) -> bool {
+        let Some(depth) = &mut self.skipping_shape_element else {
+            return false;
+        };
+        match token.kind {
+            Kind::OpenBrace | Kind::OpenParen | Kind::OpenBracket => *depth += 1,
+            Kind::CloseBrace | Kind::CloseParen | Kind::CloseBracket if *depth > 0 => *depth -= 1,
+            Kind::Comma if *depth == 0 => {
+                self.skipping_shape_element = None;
+                return true;
+            }
+            Kind::CloseBrace
+            | Kind::CloseParen
+            | Kind::CloseBracket
+            | Kind::Semicolon
+            | Kind::EOI => {
+                self.skipping_shape_element = None;
+                return false;
+            }
+            _ => {}
+        }
+        if let Some(error) = self.errors.last_mut() {
+            error.span.end = token.span.end;
+        }
+        true
+    }
+
+    /// Injects a semicolon, if the token is a reserved keyword that could
+    /// start a new statement after it.
+    fn recover_missing_semicolon(
+        &self,
+        ctx: &'s Context,
+        token: &Terminal,
+        prev_span: Option<Span>,
+    ) -> Option<Self> {
+        let Kind::Keyword(kw) = token.kind else {
+            return None;
+        };
+        if !kw.is_reserved() || self.can_act(ctx, &Kind::Semicolon).is_none() {
+            return None;
+        }
+        let mut recover = self.clone();
+        let semicolon = new_token_for_injection(Kind::Semicolon, &prev_span, token.span, ctx);
+        recover.act(ctx, semicolon).ok()?;
+        recover.can_act(ctx, &token.kind)?;
+
+        // point at the end of the previous statement, where semicolon goes
+        let end = prev_span.map_or(token.span.start, |s| s.end);
+        let error = Error {
+            message: "Missing ';'".to_string(),
+            span: Span { start: end, end },
+            hint: Some(format!(
+                "statements must be separated by semicolons, \
+                insert ';' before {token}"
+            )),
+            details: None,
+            kind: Some(ErrorKind::MissingSemicolon),
+        };
+        recover.push_error(error, injection_cost(&Kind::Semicolon));
+        Some(recover)
+    }
+
+    pub fn push_on_stack(
+        &mut self,
+        ctx: &'s Context,
+        state: usize,
+        value: CSTNode<'s>,
+        size: usize,
+        depth: u32,
+    ) {
+        let parent = self.stack_top;
         let node = StackNode {
-            parent: Some(self.stack_top),
+            parent: Some(parent),
             state,
             value,
+            size,
+            depth,
+            tree_size: parent.tree_size + size,
+            tree_depth: parent.tree_depth.max(depth),
         };
         self.stack_top = ctx.arena.alloc(node);
     }
 
+    /// The limit of [Context::with_options] exceeded by the tree, if any
+    ///
+    /// Only nodes that are on the stack are counted, so nodes discarded by
+    /// error recovery or replaced by inlining are not.
+    fn limit_error(&self, ctx: &Context, span: Span) -> Option<LimitError> {
+        let top = self.stack_top;
+        ctx.options
+            .check_tree(top.tree_size, top.tree_depth as usize, span)
+            .err()
+    }
+
     pub fn finish(&self, _ctx: &'s Context) -> Option<CSTNode<'s>> {
         if !self.can_recover || self.has_custom_error {
             return None;
@@ -623,6 +961,8 @@ fn get_span_of_nodes(nodes: &[CSTNode]) -> Option<Span> {
 const PARSER_COUNT_MAX: usize = 10;
 
 const ERROR_COST_INJECT_MAX: u16 = 15;
+/// Completing partially typed input at its end may require more tokens
+const ERROR_COST_INJECT_MAX_TOLERANT: u16 = 30;
 const ERROR_COST_SKIP: u16 = 3;
 const ERROR_COST_CUSTOM_ERROR: u16 = 3;
 
@@ -660,3 +1000,371 @@ fn injection_cost(kind: &Kind) -> u16 {
         _ => 100, // forbidden
     }
 }
+
+#[cfg(test)]
+mod test {
+    use indexmap::IndexMap;
+
+    use std::sync::{Arc, OnceLock};
+
+    use super::{
+        parse, parse_events, spawn_warmup, warmup_cell, Action, CancelToken, Context, Event,
+        Parser, ParserCache, Reduce, Spec, CANCELLED,
+    };
+    use crate::diagnostics::Diagnostic;
+    use crate::keywords::Keyword;
+    use crate::options::{LimitError, ParserOptions};
+    use crate::position::Span;
+    use crate::tokenizer::{terminal, ErrorKind, Kind};
+
+    fn empty_spec() -> Spec {
+        Spec {
+            actions: Vec::new(),
+            goto: Vec::new(),
+            inlines: IndexMap::new(),
+            production_names: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn cancel() {
+        let spec = empty_spec();
+        let ident = terminal(Kind::Ident, "a", 0, false);
+        let cancel = CancelToken::new();
+        let ctx = Context::new(&spec).with_cancel(cancel.clone());
+        cancel.cancel();
+        let (node, errors) = parse(std::slice::from_ref(&ident), &ctx);
+        assert!(node.is_none());
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].message, CANCELLED);
+        assert_eq!(errors[0].span, ident.span);
+
+        let ctx = Context::new(&spec).with_deadline(std::time::Instant::now());
+        let (node, _) = parse(std::slice::from_ref(&ident), &ctx);
+        assert!(node.is_none());
+    }
+
+    #[test]
+    fn warmup_once() {
+        // not the spec shared by the process, which other tests may build
+        static SPEC: OnceLock<Spec> = OnceLock::new();
+        let spec = |names: &[&str]| Spec {
+            actions: Vec::new(),
+            goto: Vec::new(),
+            inlines: IndexMap::new(),
+            production_names: names
+                .iter()
+                .map(|n| (n.to_string(), String::new()))
+                .collect(),
+        };
+        let threads: Vec<_> = (0..4)
+            .map(|_| std::thread::spawn(move || warmup_cell(&SPEC, || spec(&["Expr"]))))
+            .collect();
+        let built: Vec<_> = threads.into_iter().map(|t| t.join().unwrap()).collect();
+        assert!(built.iter().all(|&p| std::ptr::eq(p, built[0])));
+
+        let spec = warmup_cell(&SPEC, || unreachable!());
+        assert_eq!(spec.production_names.len(), 1);
+        let background = spawn_warmup(&SPEC, || unreachable!());
+        assert!(std::ptr::eq(background.join().unwrap(), spec));
+    }
+
+    #[test]
+    fn reusable_parser() {
+        fn assert_send<T: Send>() {}
+        assert_send::<Parser>();
+
+        // the spec is never reached, parsing is cancelled on the first token
+        let spec = empty_spec();
+        let cancel = CancelToken::new();
+        cancel.cancel();
+        let mut parser = Parser::new(Context::new(&spec).with_cancel(cancel));
+        for source in ["select 1", "select {a, b}", ""] {
+            let (node, errors) = parser.parse(Kind::StartBlock, source);
+            assert!(node.is_none());
+            assert_eq!(errors[0].message, CANCELLED);
+        }
+        let (node, errors) = parser.parse(Kind::StartBlock, "select 'x");
+        assert!(node.is_none());
+        assert_eq!(errors[0].message, "unterminated string, quoted by `'`");
+    }
+
+    /// Root ::= STARTBLOCK Expr EOI
+    /// Expr ::= IDENT | Expr '+' IDENT
+    fn sum_spec() -> Spec {
+        let reduce = |production_id, non_term: &str, cnt| {
+            Action::Reduce(Reduce {
+                production_id,
+                non_term: non_term.into(),
+                cnt,
+            })
+        };
+        Spec {
+            actions: vec![
+                IndexMap::from([(Kind::StartBlock, Action::Shift(1))]),
+                IndexMap::from([(Kind::Ident, Action::Shift(2))]),
+                IndexMap::from([
+                    (Kind::EOI, reduce(1, "Expr", 1)),
+                    (Kind::Add, reduce(1, "Expr", 1)),
+                ]),
+                IndexMap::from([(Kind::EOI, Action::Shift(4)), (Kind::Add, Action::Shift(5))]),
+                IndexMap::from([(Kind::EOI, reduce(0, "Root", 3))]),
+                IndexMap::from([(Kind::Ident, Action::Shift(6))]),
+                IndexMap::from([
+                    (Kind::EOI, reduce(2, "Expr", 3)),
+                    (Kind::Add, reduce(2, "Expr", 3)),
+                ]),
+                IndexMap::from([(Kind::EOI, Action::Shift(8))]),
+                IndexMap::new(),
+            ],
+            goto: vec![
+                IndexMap::from([("Root".into(), 7)]),
+                IndexMap::from([("Expr".into(), 3)]),
+                IndexMap::new(),
+                IndexMap::new(),
+                IndexMap::new(),
+                IndexMap::new(),
+                IndexMap::new(),
+                IndexMap::new(),
+                IndexMap::new(),
+            ],
+            inlines: IndexMap::new(),
+            production_names: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn events() {
+        let spec = sum_spec();
+        let input = [
+            terminal(Kind::StartBlock, "", 0, false),
+            terminal(Kind::Ident, "a", 0, false),
+            terminal(Kind::Add, "+", 2, false),
+            terminal(Kind::Ident, "b", 4, false),
+            terminal(Kind::EOI, "", 5, false),
+        ];
+        let describe = |event: Event| match event {
+            Event::Token(terminal) => format!("{:?}", terminal.kind),
+            Event::Node {
+                production_id,
+                args,
+            } => format!("node {production_id}/{args}"),
+            Event::Error(error) => error.message,
+        };
+        let ctx = Context::new(&spec);
+        let mut events = Vec::new();
+        assert!(parse_events(&input, &ctx, |e| events.push(describe(e))));
+        assert_eq!(
+            events,
+            [
+                "StartBlock",
+                "Ident",
+                "node 1/1",
+                "Add",
+                "Ident",
+                "node 2/3",
+                "EOI",
+                "node 0/3",
+            ]
+        );
+
+        // the same grammar builds the tree
+        let (tree, errors) = parse(&input, &ctx);
+        assert!(tree.is_some());
+        assert!(errors.is_empty());
+
+        let input = [
+            terminal(Kind::StartBlock, "", 0, false),
+            terminal(Kind::Ident, "a", 0, false),
+            terminal(Kind::Ident, "b", 2, false),
+            terminal(Kind::EOI, "", 3, false),
+        ];
+        let mut events = Vec::new();
+        assert!(!parse_events(&input, &ctx, |e| events.push(describe(e))));
+        assert_eq!(events, ["StartBlock", "Ident", "Unexpected 'b'"]);
+    }
+
+    /// Root ::= STARTBLOCK Stmts EOI
+    /// Stmts ::= Stmt | Stmts ';' Stmt
+    /// Stmt ::= SELECT Expr
+    /// Expr ::= ICONST
+    ///
+    /// As in the generated tables, `Expr` is reduced on `select`, which can
+    /// follow an expression elsewhere in the grammar.
+    fn statements_spec() -> Spec {
+        let reduce = |production_id, non_term: &str, cnt| {
+            Action::Reduce(Reduce {
+                production_id,
+                non_term: non_term.into(),
+                cnt,
+            })
+        };
+        let select = Kind::Keyword(Keyword("select"));
+        Spec {
+            actions: vec![
+                IndexMap::from([(Kind::StartBlock, Action::Shift(1))]),
+                IndexMap::from([(select, Action::Shift(2))]),
+                IndexMap::from([(Kind::IntConst, Action::Shift(3))]),
+                IndexMap::from([
+                    (Kind::EOI, reduce(4, "Expr", 1)),
+                    (Kind::Semicolon, reduce(4, "Expr", 1)),
+                    (select, reduce(4, "Expr", 1)),
+                ]),
+                IndexMap::from([
+                    (Kind::EOI, Action::Shift(6)),
+                    (Kind::Semicolon, Action::Shift(7)),
+                ]),
+                IndexMap::from([
+                    (Kind::EOI, reduce(1, "Stmts", 1)),
+                    (Kind::Semicolon, reduce(1, "Stmts", 1)),
+                ]),
+                IndexMap::from([(Kind::EOI, reduce(0, "Root", 3))]),
+                IndexMap::from([(select, Action::Shift(2))]),
+                IndexMap::from([
+                    (Kind::EOI, reduce(2, "Stmts", 3)),
+                    (Kind::Semicolon, reduce(2, "Stmts", 3)),
+                ]),
+                IndexMap::from([(Kind::EOI, Action::Shift(10))]),
+                IndexMap::new(),
+                IndexMap::from([
+                    (Kind::EOI, reduce(3, "Stmt", 2)),
+                    (Kind::Semicolon, reduce(3, "Stmt", 2)),
+                ]),
+            ],
+            goto: vec![
+                IndexMap::from([("Root".into(), 9)]),
+                IndexMap::from([("Stmts".into(), 4), ("Stmt".into(), 5)]),
+                IndexMap::from([("Expr".into(), 11)]),
+                IndexMap::new(),
+                IndexMap::new(),
+                IndexMap::new(),
+                IndexMap::new(),
+                IndexMap::from([("Stmt".into(), 8)]),
+                IndexMap::new(),
+                IndexMap::new(),
+                IndexMap::new(),
+                IndexMap::new(),
+            ],
+            inlines: IndexMap::new(),
+            production_names: ["Root", "Stmts", "Stmts", "Stmt", "Expr"]
+                .iter()
+                .map(|n| (n.to_string(), String::new()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn missing_semicolon() {
+        let spec = statements_spec();
+        let source = "select 1 select 2";
+        let select = Kind::Keyword(Keyword("select"));
+        let input = [
+            terminal(Kind::StartBlock, "", 0, false),
+            terminal(select, "select", 0, false),
+            terminal(Kind::IntConst, "1", 7, false),
+            terminal(select, "select", 9, false),
+            terminal(Kind::IntConst, "2", 16, false),
+            terminal(Kind::EOI, "", 17, false),
+        ];
+        let (_, errors) = parse(&input, &Context::new(&spec));
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].kind, Some(ErrorKind::MissingSemicolon));
+        let diagnostic = Diagnostic::from(errors[0].clone());
+        assert_eq!(diagnostic.suggestions.len(), 1);
+        assert_eq!(
+            diagnostic.suggestions[0].apply(source),
+            "select 1; select 2"
+        );
+    }
+
+    #[test]
+    fn limits() {
+        let spec = sum_spec();
+        let input = [
+            terminal(Kind::StartBlock, "", 0, false),
+            terminal(Kind::Ident, "a", 0, false),
+            terminal(Kind::Add, "+", 2, false),
+            terminal(Kind::Ident, "b", 4, false),
+            terminal(Kind::Add, "+", 6, false),
+            terminal(Kind::Ident, "c", 8, false),
+            terminal(Kind::EOI, "", 9, false),
+        ];
+        let parse_with = |options: ParserOptions| {
+            let ctx = Context::new(&spec).with_options(&options);
+            let (node, errors) = parse(&input, &ctx);
+            (node.is_some(), errors)
+        };
+
+        let (ok, errors) = parse_with(ParserOptions {
+            max_nodes: Some(20),
+            max_depth: Some(5),
+            ..ParserOptions::default()
+        });
+        assert!(ok && errors.is_empty());
+
+        let (ok, errors) = parse_with(ParserOptions {
+            max_nodes: Some(6),
+            ..ParserOptions::default()
+        });
+        assert!(!ok);
+        assert_eq!(
+            errors[0].message,
+            "query is too large: 7 nodes, at most 6 are allowed"
+        );
+        assert_eq!(errors[0].span, Span { start: 6, end: 7 });
+
+        let (ok, errors) = parse_with(ParserOptions {
+            max_depth: Some(3),
+            ..ParserOptions::default()
+        });
+        assert!(!ok);
+        assert_eq!(errors.len(), 1);
+
+        let error = LimitError::QueryTooDeep {
+            depth: 4,
+            max: 3,
+            span: Span { start: 8, end: 9 },
+        };
+        let diagnostic = Diagnostic::from(error.clone());
+        assert_eq!(diagnostic.code.as_deref(), Some("query-too-deep"));
+        assert_eq!(diagnostic.message, error.to_string());
+    }
+
+    #[test]
+    fn cache() {
+        fn assert_sync<T: Sync>() {}
+        assert_sync::<ParserCache>();
+
+        let spec = sum_spec();
+        let cache = ParserCache::new(2);
+        let mut parser = Parser::new(Context::new(&spec));
+        let first = cache.parse(&mut parser, Kind::StartBlock, "a + b");
+        assert!(first.ast.is_some() && first.errors.is_empty());
+        let again = cache.parse(&mut parser, Kind::StartBlock, "a + b");
+        assert!(Arc::ptr_eq(&first, &again));
+
+        cache.parse(&mut parser, Kind::StartBlock, "a");
+        cache.parse(&mut parser, Kind::StartBlock, "b");
+        assert_eq!(cache.len(), 2);
+        // the least recently used result was evicted
+        let evicted = cache.parse(&mut parser, Kind::StartBlock, "a + b");
+        assert!(!Arc::ptr_eq(&first, &evicted));
+
+        // results of other limits are not shared
+        let options = ParserOptions {
+            max_nodes: Some(1),
+            ..ParserOptions::default()
+        };
+        let mut limited = Parser::new(Context::new(&spec)).with_options(&options);
+        let too_large = cache.parse(&mut limited, Kind::StartBlock, "a + b");
+        assert!(too_large.ast.is_none());
+
+        let cancel = CancelToken::new();
+        cancel.cancel();
+        let mut parser = Parser::new(Context::new(&spec).with_cancel(cancel));
+        cache.clear();
+        let cancelled = cache.parse(&mut parser, Kind::StartBlock, "a");
+        assert_eq!(cancelled.errors[0].kind, Some(ErrorKind::Cancelled));
+        assert!(cache.is_empty());
+    }
+}
@@ -0,0 +1,196 @@
+use std::borrow::Cow;
+
+use crate::position::Span;
+use crate::tokenizer::{Kind, Value};
+
+use super::cst::CSTNode;
+
+/// A node of an [Ast]
+#[derive(Debug, Clone, PartialEq)]
+pub enum AstNode<'a> {
+    Empty,
+    Terminal {
+        kind: Kind,
+        text: Cow<'a, str>,
+        value: Option<Value>,
+        span: Span,
+        is_placeholder: bool,
+    },
+    /// The production is followed by its `args` (each with its descendants)
+    Production {
+        id: usize,
+        args: usize,
+        span: Option<Span>,
+    },
+}
+
+/// A parsed tree that does not borrow the arena of the
+/// [Context](super::Context)
+///
+/// Nodes are stored in pre-order, like in a [CSTBuffer](super::CSTBuffer).
+/// Texts of terminals borrow from the source, so building the tree copies
+/// little but keeps the source alive. [Ast::into_owned] copies the texts into
+/// an [AstOwned], which can be cached after the source is dropped.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ast<'a> {
+    nodes: Vec<AstNode<'a>>,
+}
+
+/// An [Ast] that owns the texts of its terminals
+pub type AstOwned = Ast<'static>;
+
+impl<'a> Ast<'a> {
+    /// Converts the tree of `root`, parsed from `source`
+    ///
+    /// Texts of terminals that differ from their span of the source (e.g.
+    /// injected by error recovery) are copied.
+    pub fn new(root: CSTNode<'_>, source: &'a str) -> Ast<'a> {
+        let mut nodes = Vec::new();
+        // an explicit stack, since the trees can be very deep
+        let mut stack = vec![root];
+        while let Some(node) = stack.pop() {
+            nodes.push(match node {
+                CSTNode::Empty => AstNode::Empty,
+                CSTNode::Terminal(terminal) => {
+                    let span = terminal.span;
+                    let text = source
+                        .get(span.start as usize..span.end as usize)
+                        .filter(|text| *text == terminal.text)
+                        .map_or_else(|| Cow::Owned(terminal.text.clone()), Cow::Borrowed);
+                    AstNode::Terminal {
+                        kind: terminal.kind,
+                        text,
+                        value: terminal.value.clone(),
+                        span,
+                        is_placeholder: terminal.is_placeholder(),
+                    }
+                }
+                CSTNode::Production(prod) => {
+                    stack.extend(prod.args.iter().rev());
+                    AstNode::Production {
+                        id: prod.id,
+                        args: prod.args.len(),
+                        span: prod.span,
+                    }
+                }
+            });
+        }
+        Ast { nodes }
+    }
+
+    /// Nodes in pre-order, the root first
+    pub fn nodes(&self) -> &[AstNode<'a>] {
+        &self.nodes
+    }
+
+    /// Copies the texts of terminals that borrow from the source
+    pub fn into_owned(self) -> AstOwned {
+        let nodes = self.nodes.into_iter().map(|node| match node {
+            AstNode::Empty => AstNode::Empty,
+            AstNode::Terminal {
+                kind,
+                text,
+                value,
+                span,
+                is_placeholder,
+            } => AstNode::Terminal {
+                kind,
+                text: Cow::Owned(text.into_owned()),
+                value,
+                span,
+                is_placeholder,
+            },
+            AstNode::Production { id, args, span } => AstNode::Production { id, args, span },
+        });
+        Ast {
+            nodes: nodes.collect(),
+        }
+    }
+
+    /// A tree borrowing the texts of this one, e.g. to pass an [AstOwned]
+    /// to code that takes an [Ast]
+    pub fn borrowed(&self) -> Ast<'_> {
+        self.map_texts(Cow::Borrowed)
+    }
+
+    fn map_texts<'b, 'c>(&'b self, f: impl Fn(&'b str) -> Cow<'c, str>) -> Ast<'c> {
+        let nodes = self.nodes.iter().map(|node| match node {
+            AstNode::Empty => AstNode::Empty,
+            AstNode::Terminal {
+                kind,
+                text,
+                value,
+                span,
+                is_placeholder,
+            } => AstNode::Terminal {
+                kind: *kind,
+                text: f(text.as_ref()),
+                value: value.clone(),
+                span: *span,
+                is_placeholder: *is_placeholder,
+            },
+            AstNode::Production { id, args, span } => AstNode::Production {
+                id: *id,
+                args: *args,
+                span: *span,
+            },
+        });
+        Ast {
+            nodes: nodes.collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::borrow::Cow;
+
+    use super::{Ast, AstNode, AstOwned};
+    use crate::parser::cst::{CSTNode, Production};
+    use crate::position::Span;
+    use crate::tokenizer::{terminal, Kind};
+
+    #[test]
+    fn owned() {
+        let source = String::from("a b");
+        let (a, b, missing) = (
+            terminal(Kind::Ident, "a", 0, false),
+            terminal(Kind::Ident, "b", 2, false),
+            terminal(Kind::Ident, "c", 3, false),
+        );
+        let args = [
+            CSTNode::Terminal(&a),
+            CSTNode::Terminal(&b),
+            CSTNode::Terminal(&missing),
+        ];
+        let root = CSTNode::Production(Production {
+            id: 3,
+            args: &args,
+            span: Some(Span { start: 0, end: 3 }),
+            inlined_ids: None,
+        });
+        let ast = Ast::new(root, &source);
+        let texts: Vec<_> = ast
+            .nodes()
+            .iter()
+            .filter_map(|node| match node {
+                AstNode::Terminal { text, .. } => Some(text),
+                _ => None,
+            })
+            .collect();
+        assert!(matches!(
+            texts[..],
+            [Cow::Borrowed("a"), Cow::Borrowed("b"), Cow::Owned(_)]
+        ));
+
+        let owned: AstOwned = ast.clone().into_owned();
+        assert_eq!(owned, ast);
+        drop(ast);
+        drop(source);
+        assert_eq!(owned.borrowed(), owned);
+        assert!(matches!(
+            owned.nodes()[0],
+            AstNode::Production { id: 3, args: 3, .. }
+        ));
+    }
+}
@@ -0,0 +1,73 @@
+use append_only_vec::AppendOnlyVec;
+
+use super::{parse, CSTNode, Context, ParseMode, Terminal};
+use crate::options::ParserOptions;
+use crate::soft_keywords::demote_soft_terminals;
+use crate::tokenizer::{Error, Kind, Tokenizer};
+
+/// Tokenizes and parses many sources, reusing the allocations
+///
+/// The arena holding the parser stack and the buffer of terminals are kept
+/// between calls of [Parser::parse], so after a few parses of similar size
+/// parsing allocates little besides the errors. The parser is [Send] (but not
+/// [Sync]), so a server can keep one per worker thread.
+pub struct Parser<'s> {
+    ctx: Context<'s>,
+    options: ParserOptions,
+    terminals: Vec<Terminal>,
+}
+
+impl<'s> Parser<'s> {
+    /// Mode, cancellation and deadline of `ctx` apply to every parse
+    pub fn new(ctx: Context<'s>) -> Self {
+        Parser {
+            ctx,
+            options: ParserOptions::default(),
+            terminals: Vec::new(),
+        }
+    }
+
+    /// Limits of the options apply to both tokenizing and parsing
+    pub fn with_options(mut self, options: &ParserOptions) -> Self {
+        self.options = *options;
+        self.ctx.options = *options;
+        self
+    }
+
+    /// The mode, the limits of tokenizing and the limits of the tree, which
+    /// the results of parsing depend on
+    pub(super) fn config(&self) -> (ParseMode, ParserOptions, ParserOptions) {
+        (self.ctx.mode, self.options, self.ctx.options)
+    }
+
+    /// Parses `source` as the grammar of the `start` token (i.e.
+    /// [Kind::StartBlock]). Tokenizer errors are returned without a node.
+    ///
+    /// The node borrows the parser, so it must be dropped before the next
+    /// parse.
+    pub fn parse(&mut self, start: Kind, source: &str) -> (Option<CSTNode<'_>>, Vec<Error>) {
+        self.ctx.arena.reset();
+        self.ctx.terminal_arena = AppendOnlyVec::new();
+        self.terminals.clear();
+
+        self.terminals.push(Terminal {
+            kind: start,
+            text: String::new(),
+            value: None,
+            span: Default::default(),
+            is_placeholder: false,
+        });
+        let tokens = Tokenizer::new(source)
+            .with_options(&self.options)
+            .validated_values()
+            .with_eof();
+        for token in tokens {
+            match token {
+                Ok(token) => self.terminals.push(Terminal::from_token(token)),
+                Err(e) => return (None, vec![e]),
+            }
+        }
+        demote_soft_terminals(&mut self.terminals);
+        parse(&self.terminals, &self.ctx)
+    }
+}
@@ -0,0 +1,230 @@
+use indexmap::{IndexMap, IndexSet};
+
+use super::spec::{get_token_kind, Action, Reduce, Spec, SpecSerializable};
+use crate::tokenizer::Kind;
+
+/// First bytes of a snapshot, the last one is the version of the format
+pub const SNAPSHOT_MAGIC: &[u8; 8] = b"EQLSPEC\x01";
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum SnapshotError {
+    #[error("not a grammar snapshot, or of another version")]
+    BadMagic,
+    #[error("grammar snapshot is truncated")]
+    Truncated,
+    #[error("grammar snapshot has an invalid name at offset {0}")]
+    InvalidName(usize),
+    #[error("grammar snapshot has an out of range number at offset {0}")]
+    InvalidNumber(usize),
+}
+
+/// Serializes the tables for [read_snapshot], e.g. at build time
+///
+/// Names of tokens and non-terminals are stored once, and all numbers are
+/// 32-bit little-endian, so reading the snapshot is mostly a single pass
+/// over the bytes.
+pub fn write_snapshot(spec: &SpecSerializable) -> Vec<u8> {
+    let mut names = IndexSet::new();
+    let mut intern = |name: &str| names.insert_full(name.to_string()).0;
+
+    let mut numbers = vec![spec.actions.len()];
+    for actions in &spec.actions {
+        numbers.push(actions.len());
+        for (token, action) in actions {
+            numbers.push(intern(token));
+            match action {
+                Action::Shift(next) => numbers.extend([0, *next]),
+                Action::Reduce(reduce) => numbers.extend([
+                    1,
+                    reduce.production_id,
+                    intern(&reduce.non_term),
+                    reduce.cnt,
+                ]),
+            }
+        }
+    }
+    numbers.push(spec.goto.len());
+    for goto in &spec.goto {
+        numbers.push(goto.len());
+        for (non_term, next) in goto {
+            numbers.extend([intern(non_term), *next]);
+        }
+    }
+    numbers.push(spec.inlines.len());
+    for (production_id, position) in &spec.inlines {
+        numbers.extend([*production_id, *position as usize]);
+    }
+    numbers.push(spec.production_names.len());
+    for (non_term, production) in &spec.production_names {
+        numbers.extend([intern(non_term), intern(production)]);
+    }
+    let mut body = Vec::new();
+    for number in numbers {
+        push_u32(&mut body, number);
+    }
+
+    let mut buf = SNAPSHOT_MAGIC.to_vec();
+    push_u32(&mut buf, names.len());
+    for name in &names {
+        push_u32(&mut buf, name.len());
+        buf.extend_from_slice(name.as_bytes());
+    }
+    buf.extend(body);
+    buf
+}
+
+fn push_u32(buf: &mut Vec<u8>, number: usize) {
+    let number = u32::try_from(number).expect("grammar tables fit 32-bit numbers");
+    buf.extend_from_slice(&number.to_le_bytes());
+}
+
+/// Builds the tables from a snapshot of [write_snapshot]
+///
+/// Only the tables are allocated, `bytes` are never copied, so they can be
+/// a memory-mapped file.
+pub fn read_snapshot(bytes: &[u8]) -> Result<Spec, SnapshotError> {
+    let rest = bytes
+        .strip_prefix(SNAPSHOT_MAGIC)
+        .ok_or(SnapshotError::BadMagic)?;
+    let mut reader = Reader {
+        bytes: rest,
+        pos: 0,
+        names: Vec::new(),
+        kinds: Vec::new(),
+    };
+    for _ in 0..reader.number()? {
+        let len = reader.number()?;
+        let offset = SNAPSHOT_MAGIC.len() + reader.pos;
+        let name = rest
+            .get(reader.pos..reader.pos + len)
+            .ok_or(SnapshotError::Truncated)?;
+        let name = std::str::from_utf8(name).map_err(|_| SnapshotError::InvalidName(offset))?;
+        reader.names.push(name);
+        reader.pos += len;
+    }
+    reader.kinds = vec![None; reader.names.len()];
+
+    let mut actions = Vec::new();
+    for _ in 0..reader.number()? {
+        let mut state = IndexMap::new();
+        for _ in 0..reader.number()? {
+            let kind = reader.kind()?;
+            let action = match reader.number()? {
+                0 => Action::Shift(reader.number()?),
+                _ => Action::Reduce(Reduce {
+                    production_id: reader.number()?,
+                    non_term: reader.name()?.to_string(),
+                    cnt: reader.number()?,
+                }),
+            };
+            state.insert(kind, action);
+        }
+        actions.push(state);
+    }
+    let mut goto = Vec::new();
+    for _ in 0..reader.number()? {
+        let mut state = IndexMap::new();
+        for _ in 0..reader.number()? {
+            state.insert(reader.name()?.to_string(), reader.number()?);
+        }
+        goto.push(state);
+    }
+    let mut inlines = IndexMap::new();
+    for _ in 0..reader.number()? {
+        let production_id = reader.number()?;
+        inlines.insert(production_id, reader.byte()?);
+    }
+    let mut production_names = Vec::new();
+    for _ in 0..reader.number()? {
+        production_names.push((reader.name()?.to_string(), reader.name()?.to_string()));
+    }
+    Ok(Spec {
+        actions,
+        goto,
+        inlines,
+        production_names,
+    })
+}
+
+struct Reader<'b> {
+    bytes: &'b [u8],
+    pos: usize,
+    names: Vec<&'b str>,
+    /// Kinds of the names used as tokens, looked up once
+    kinds: Vec<Option<Kind>>,
+}
+
+impl<'b> Reader<'b> {
+    fn number(&mut self) -> Result<usize, SnapshotError> {
+        let bytes = self
+            .bytes
+            .get(self.pos..self.pos + 4)
+            .ok_or(SnapshotError::Truncated)?;
+        self.pos += 4;
+        Ok(u32::from_le_bytes(bytes.try_into().unwrap()) as usize)
+    }
+
+    fn byte(&mut self) -> Result<u8, SnapshotError> {
+        let offset = SNAPSHOT_MAGIC.len() + self.pos;
+        u8::try_from(self.number()?).map_err(|_| SnapshotError::InvalidNumber(offset))
+    }
+
+    fn name(&mut self) -> Result<&'b str, SnapshotError> {
+        let offset = SNAPSHOT_MAGIC.len() + self.pos;
+        let idx = self.number()?;
+        self.names
+            .get(idx)
+            .copied()
+            .ok_or(SnapshotError::InvalidName(offset))
+    }
+
+    fn kind(&mut self) -> Result<Kind, SnapshotError> {
+        let offset = SNAPSHOT_MAGIC.len() + self.pos;
+        let idx = self.number()?;
+        let name = self
+            .names
+            .get(idx)
+            .ok_or(SnapshotError::InvalidName(offset))?;
+        Ok(*self.kinds[idx].get_or_insert_with(|| get_token_kind(name)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{read_snapshot, write_snapshot, SnapshotError};
+    use crate::parser::{Action, Reduce, Spec, SpecSerializable};
+
+    #[test]
+    fn roundtrip() {
+        let reduce = Action::Reduce(Reduce {
+            production_id: 1,
+            non_term: "Expr".into(),
+            cnt: 1,
+        });
+        let serializable = || SpecSerializable {
+            actions: vec![
+                vec![("IDENT".into(), Action::Shift(1))],
+                vec![("EOI".into(), reduce.clone()), ("+".into(), reduce.clone())],
+            ],
+            goto: vec![vec![("Expr".into(), 2)], vec![]],
+            inlines: vec![(1, 0)],
+            production_names: vec![("Expr".into(), "reduce_IDENT".into())],
+        };
+        let bytes = write_snapshot(&serializable());
+        let spec = read_snapshot(&bytes).unwrap();
+        let expected = Spec::from(serializable());
+        assert_eq!(spec.actions, expected.actions);
+        assert_eq!(spec.goto, expected.goto);
+        assert_eq!(spec.inlines, expected.inlines);
+        assert_eq!(spec.production_names, expected.production_names);
+
+        assert_eq!(
+            read_snapshot(&bytes[..bytes.len() - 1]).err(),
+            Some(SnapshotError::Truncated)
+        );
+        assert_eq!(
+            read_snapshot(b"\x80\x02}q").err(),
+            Some(SnapshotError::BadMagic)
+        );
+    }
+}
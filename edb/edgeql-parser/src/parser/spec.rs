@@ -9,14 +9,14 @@ pub struct Spec {
     pub production_names: Vec<(String, String)>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Action {
     Shift(usize),
     Reduce(Reduce),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Reduce {
     /// Index of the production in the associated production array
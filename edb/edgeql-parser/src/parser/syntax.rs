@@ -0,0 +1,286 @@
+//! The CST as [rowan] green and red trees
+//!
+//! This allows using the syntax node APIs of rust-analyzer-style tooling
+//! (traversal, structured editing) on EdgeQL. The tree is lossless: the text
+//! not covered by terminals of the CST (whitespace, comments and tokens
+//! skipped by error recovery) is kept in [SyntaxKind::Trivia] tokens, so the
+//! text of the root is the original source.
+
+use rowan::{GreenNode, GreenNodeBuilder, Language};
+
+use super::CSTNode;
+use crate::tokenizer::Kind;
+
+/// Token kinds, except keywords, in the order of their raw kinds
+const TOKEN_KINDS: &[Kind] = {
+    use Kind::*;
+    &[
+        Assign,
+        SubAssign,
+        AddAssign,
+        Arrow,
+        Coalesce,
+        Namespace,
+        BackwardLink,
+        OptionalLink,
+        FloorDiv,
+        Concat,
+        GreaterEq,
+        LessEq,
+        NotEq,
+        NotDistinctFrom,
+        DistinctFrom,
+        Comma,
+        OpenParen,
+        CloseParen,
+        OpenBracket,
+        CloseBracket,
+        OpenBrace,
+        CloseBrace,
+        Dot,
+        Semicolon,
+        Colon,
+        Add,
+        Sub,
+        DoubleSplat,
+        Mul,
+        Div,
+        Modulo,
+        Pow,
+        Less,
+        Greater,
+        Eq,
+        Ampersand,
+        Pipe,
+        At,
+        Parameter,
+        ParameterAndType,
+        DecimalConst,
+        FloatConst,
+        IntConst,
+        BigIntConst,
+        BinStr,
+        Str,
+        StrInterpStart,
+        StrInterpCont,
+        StrInterpEnd,
+        BacktickName,
+        Substitution,
+        Ident,
+        EOI,
+        Epsilon,
+        StartBlock,
+        StartExtension,
+        StartFragment,
+        StartMigration,
+        StartSDLDocument,
+    ]
+};
+
+/// Kind of a rowan node or token
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SyntaxKind {
+    /// Node wrapping the whole tree, including the leading and trailing trivia
+    Root,
+    /// Source text between terminals
+    Trivia,
+    /// Any keyword, the text tells which one
+    Keyword,
+    /// Terminal of other kind, index into the list of token kinds
+    Token(u16),
+    /// Production with this id in [Spec::production_names](super::Spec)
+    Production(u16),
+}
+
+/// The [Language] of EdgeQL trees
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum EdgeQL {}
+
+pub type SyntaxNode = rowan::SyntaxNode<EdgeQL>;
+pub type SyntaxToken = rowan::SyntaxToken<EdgeQL>;
+pub type SyntaxElement = rowan::SyntaxElement<EdgeQL>;
+
+const FIRST_TOKEN: u16 = 3;
+const FIRST_PRODUCTION: u16 = FIRST_TOKEN + TOKEN_KINDS.len() as u16;
+
+impl SyntaxKind {
+    pub fn from_token(kind: Kind) -> SyntaxKind {
+        match kind {
+            Kind::Keyword(_) => SyntaxKind::Keyword,
+            _ => {
+                let index = TOKEN_KINDS.iter().position(|k| *k == kind).unwrap();
+                SyntaxKind::Token(index as u16)
+            }
+        }
+    }
+
+    /// The token kind of a terminal, keywords have no kind without the text
+    pub fn token_kind(self) -> Option<Kind> {
+        match self {
+            SyntaxKind::Token(index) => Some(TOKEN_KINDS[index as usize]),
+            _ => None,
+        }
+    }
+
+    pub fn production_id(self) -> Option<usize> {
+        match self {
+            SyntaxKind::Production(id) => Some(id as usize),
+            _ => None,
+        }
+    }
+}
+
+impl Language for EdgeQL {
+    type Kind = SyntaxKind;
+
+    fn kind_from_raw(raw: rowan::SyntaxKind) -> SyntaxKind {
+        match raw.0 {
+            0 => SyntaxKind::Root,
+            1 => SyntaxKind::Trivia,
+            2 => SyntaxKind::Keyword,
+            n if n < FIRST_PRODUCTION => SyntaxKind::Token(n - FIRST_TOKEN),
+            n => SyntaxKind::Production(n - FIRST_PRODUCTION),
+        }
+    }
+
+    fn kind_to_raw(kind: SyntaxKind) -> rowan::SyntaxKind {
+        rowan::SyntaxKind(match kind {
+            SyntaxKind::Root => 0,
+            SyntaxKind::Trivia => 1,
+            SyntaxKind::Keyword => 2,
+            SyntaxKind::Token(index) => FIRST_TOKEN + index,
+            SyntaxKind::Production(id) => FIRST_PRODUCTION + id,
+        })
+    }
+}
+
+/// Builds the green tree of `root`, which was parsed from `source`
+///
+/// Placeholder terminals injected by error recovery become empty tokens.
+pub fn green_tree(source: &str, root: CSTNode) -> GreenNode {
+    let mut builder = Builder {
+        source,
+        offset: 0,
+        inner: GreenNodeBuilder::new(),
+    };
+    builder.start_node(SyntaxKind::Root);
+    builder.node(root);
+    builder.trivia(source.len());
+    builder.inner.finish_node();
+    builder.inner.finish()
+}
+
+/// Builds the red tree of `root`, see [green_tree]
+pub fn syntax_tree(source: &str, root: CSTNode) -> SyntaxNode {
+    SyntaxNode::new_root(green_tree(source, root))
+}
+
+struct Builder<'s> {
+    source: &'s str,
+    offset: usize,
+    inner: GreenNodeBuilder<'static>,
+}
+
+impl Builder<'_> {
+    fn start_node(&mut self, kind: SyntaxKind) {
+        self.inner.start_node(EdgeQL::kind_to_raw(kind));
+    }
+
+    fn token(&mut self, kind: SyntaxKind, text: &str) {
+        self.inner.token(EdgeQL::kind_to_raw(kind), text);
+    }
+
+    fn trivia(&mut self, end: usize) {
+        if end > self.offset {
+            let text = &self.source[self.offset..end];
+            self.token(SyntaxKind::Trivia, text);
+            self.offset = end;
+        }
+    }
+
+    fn node(&mut self, node: CSTNode) {
+        match node {
+            CSTNode::Empty => {}
+            CSTNode::Terminal(terminal) => {
+                let kind = SyntaxKind::from_token(terminal.kind);
+                if terminal.is_placeholder {
+                    self.token(kind, "");
+                    return;
+                }
+                let start = terminal.span.start as usize;
+                let end = terminal.span.end as usize;
+                self.trivia(start);
+                let text = self.source.get(self.offset..end).unwrap_or_default();
+                self.token(kind, text);
+                self.offset = self.offset.max(end);
+            }
+            CSTNode::Production(production) => {
+                self.start_node(SyntaxKind::Production(production.id as u16));
+                for arg in production.args {
+                    self.node(*arg);
+                }
+                self.inner.finish_node();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{syntax_tree, SyntaxKind};
+    use crate::keywords::Keyword;
+    use crate::parser::cst::{CSTNode, Production};
+    use crate::tokenizer::{terminal, Kind};
+
+    #[test]
+    fn lossless() {
+        let source = "select  a # comment\n;";
+        let select = terminal(Kind::Keyword(Keyword("select")), "select", 0, false);
+        let a = terminal(Kind::Ident, "a", 8, false);
+        let semicolon = terminal(Kind::Semicolon, ";", 20, false);
+        let inner = [CSTNode::Terminal(&select), CSTNode::Terminal(&a)];
+        let args = [
+            CSTNode::Production(Production {
+                id: 4,
+                args: &inner,
+                span: None,
+                inlined_ids: None,
+            }),
+            CSTNode::Empty,
+            CSTNode::Terminal(&semicolon),
+        ];
+        let root = CSTNode::Production(Production {
+            id: 1,
+            args: &args,
+            span: None,
+            inlined_ids: None,
+        });
+
+        let tree = syntax_tree(source, root);
+        assert_eq!(tree.kind(), SyntaxKind::Root);
+        assert_eq!(tree.text().to_string(), source);
+
+        let stmt = tree.first_child().unwrap();
+        assert_eq!(stmt.kind().production_id(), Some(1));
+        let expr = stmt.first_child().unwrap();
+        assert_eq!(expr.kind(), SyntaxKind::Production(4));
+        assert_eq!(expr.text().to_string(), "select  a");
+
+        let tokens: Vec<_> = tree
+            .descendants_with_tokens()
+            .filter_map(|e| e.into_token())
+            .map(|t| (t.kind(), t.text().to_string()))
+            .collect();
+        assert_eq!(
+            tokens,
+            [
+                (SyntaxKind::Keyword, "select".into()),
+                (SyntaxKind::Trivia, "  ".into()),
+                (SyntaxKind::from_token(Kind::Ident), "a".into()),
+                (SyntaxKind::Trivia, " # comment\n".into()),
+                (SyntaxKind::from_token(Kind::Semicolon), ";".into()),
+            ]
+        );
+        assert_eq!(tokens[4].0.token_kind(), Some(Kind::Semicolon));
+    }
+}
@@ -0,0 +1,170 @@
+use crate::position::Span;
+
+use super::cst::CSTNode;
+
+/// Identifier of a node in a [Tree]
+///
+/// Nodes are numbered in pre-order starting from the root, so the id of a
+/// node is the same every time the same input is parsed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NodeId(usize);
+
+/// Index of a CST with parent pointers, for analyses that need to navigate
+/// from a node upwards
+#[derive(Debug)]
+pub struct Tree<'a> {
+    nodes: Vec<Node<'a>>,
+}
+
+#[derive(Debug)]
+struct Node<'a> {
+    node: CSTNode<'a>,
+    parent: Option<NodeId>,
+    /// Span of the node, or the span of its children for productions
+    /// without one
+    span: Option<Span>,
+}
+
+impl NodeId {
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+impl<'a> Tree<'a> {
+    pub fn new(root: CSTNode<'a>) -> Tree<'a> {
+        let mut nodes = Vec::new();
+        // an explicit stack, since the trees can be very deep
+        let mut stack = vec![(root, None)];
+        while let Some((node, parent)) = stack.pop() {
+            let id = NodeId(nodes.len());
+            let span = match node {
+                CSTNode::Empty => None,
+                CSTNode::Terminal(terminal) => Some(terminal.span),
+                CSTNode::Production(prod) => {
+                    stack.extend(prod.args.iter().rev().map(|arg| (*arg, Some(id))));
+                    prod.span
+                }
+            };
+            nodes.push(Node { node, parent, span });
+        }
+        // children come after their parents
+        for idx in (0..nodes.len()).rev() {
+            let (Some(span), Some(parent)) = (nodes[idx].span, nodes[idx].parent) else {
+                continue;
+            };
+            let parent = &mut nodes[parent.0];
+            if matches!(parent.node, CSTNode::Production(p) if p.span.is_none()) {
+                parent.span = Some(parent.span.map_or(span, |s| s.extend(&span)));
+            }
+        }
+        Tree { nodes }
+    }
+
+    pub fn root(&self) -> NodeId {
+        NodeId(0)
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn node(&self, id: NodeId) -> CSTNode<'a> {
+        self.nodes[id.0].node
+    }
+
+    pub fn span(&self, id: NodeId) -> Option<Span> {
+        self.nodes[id.0].span
+    }
+
+    pub fn parent_of(&self, id: NodeId) -> Option<NodeId> {
+        self.nodes[id.0].parent
+    }
+
+    /// Parents of the node, closest first
+    pub fn ancestors(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        std::iter::successors(self.parent_of(id), |id| self.parent_of(*id))
+    }
+
+    /// The innermost node containing the offset. Use [Tree::ancestors] to
+    /// get the enclosing nodes.
+    pub fn ancestor_at(&self, offset: u64) -> Option<NodeId> {
+        // spans of siblings do not overlap, so the last node in pre-order is
+        // the innermost one
+        self.nodes
+            .iter()
+            .rposition(|n| n.span.is_some_and(|s| s.start <= offset && offset < s.end))
+            .map(NodeId)
+    }
+
+    /// The innermost node whose span contains the whole `span`
+    pub fn covering(&self, span: Span) -> Option<NodeId> {
+        self.nodes
+            .iter()
+            .rposition(|n| {
+                n.span
+                    .is_some_and(|s| s.start <= span.start && span.end <= s.end)
+            })
+            .map(NodeId)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{NodeId, Tree};
+    use crate::parser::cst::{CSTNode, Production};
+    use crate::position::Span;
+    use crate::tokenizer::{terminal, Kind};
+
+    #[test]
+    fn parents() {
+        // (a (b c)) with the inner production missing a span
+        let (a, b, c) = (
+            terminal(Kind::Ident, "a", 0, false),
+            terminal(Kind::Ident, "b", 2, false),
+            terminal(Kind::Ident, "c", 4, false),
+        );
+        let inner = [CSTNode::Terminal(&b), CSTNode::Empty, CSTNode::Terminal(&c)];
+        let args = [
+            CSTNode::Terminal(&a),
+            CSTNode::Production(Production {
+                id: 7,
+                args: &inner,
+                span: None,
+                inlined_ids: None,
+            }),
+        ];
+        let root = CSTNode::Production(Production {
+            id: 3,
+            args: &args,
+            span: Some(Span { start: 0, end: 5 }),
+            inlined_ids: None,
+        });
+        let tree = Tree::new(root);
+        assert_eq!(tree.len(), 6);
+        assert_eq!(tree.parent_of(tree.root()), None);
+        assert_eq!(tree.parent_of(NodeId(1)), Some(NodeId(0)));
+        assert_eq!(tree.parent_of(NodeId(5)), Some(NodeId(2)));
+        assert_eq!(tree.span(NodeId(2)), Some(Span { start: 2, end: 5 }));
+
+        let c = tree.ancestor_at(4).unwrap();
+        assert_eq!(c, NodeId(5));
+        assert!(matches!(tree.node(c), CSTNode::Terminal(t) if t.text == "c"));
+        assert_eq!(
+            tree.ancestors(c).collect::<Vec<_>>(),
+            [NodeId(2), NodeId(0)]
+        );
+        // whitespace between tokens
+        assert_eq!(tree.ancestor_at(3), Some(NodeId(2)));
+        assert_eq!(tree.ancestor_at(1), Some(NodeId(0)));
+        assert_eq!(tree.ancestor_at(5), None);
+
+        assert_eq!(tree.covering(Span { start: 2, end: 5 }), Some(NodeId(2)));
+        assert_eq!(tree.covering(Span { start: 1, end: 3 }), Some(NodeId(0)));
+        assert_eq!(tree.covering(Span { start: 4, end: 6 }), None);
+    }
+}
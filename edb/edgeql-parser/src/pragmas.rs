@@ -0,0 +1,245 @@
+//! Pragmas in comments
+//!
+//! Comments starting with `edgeql:` are instructions for tools, which the
+//! server ignores like any other comment:
+//!
+//! ```text
+//! # edgeql: name active_users
+//! # edgeql: lint-disable unused-alias, single-use-alias
+//! select User filter .active;
+//! ```
+//!
+//! [parse_pragmas] attaches every pragma to the statement of the token after
+//! it, so a pragma may be both before a statement and inside of it.
+//! [suppress_lints] drops warnings disabled for their statement, and
+//! [AnnotatedStatement::name] names a query, e.g. for metrics and logs.
+use std::ops::Range;
+
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::messages::is_code;
+use crate::position::Span;
+use crate::tokenizer::{Error, Kind, Tokenizer};
+
+const PREFIX: &str = "edgeql:";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PragmaKind {
+    /// `# edgeql: name <name>`
+    Name(String),
+    /// `# edgeql: lint-disable <code>, ...`
+    LintDisable(Vec<String>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pragma {
+    pub kind: PragmaKind,
+    /// Span of the comment
+    pub span: Span,
+}
+
+/// A statement with the pragmas attached to it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnnotatedStatement {
+    /// Span of the statement, including the `;`
+    pub span: Span,
+    pub pragmas: Vec<Pragma>,
+}
+
+impl AnnotatedStatement {
+    /// The name of the last `name` pragma
+    pub fn name(&self) -> Option<&str> {
+        self.pragmas.iter().rev().find_map(|p| match &p.kind {
+            PragmaKind::Name(name) => Some(name.as_str()),
+            _ => None,
+        })
+    }
+
+    pub fn is_lint_disabled(&self, code: &str) -> bool {
+        self.pragmas.iter().any(|p| match &p.kind {
+            PragmaKind::LintDisable(codes) => codes.iter().any(|c| c == code),
+            _ => false,
+        })
+    }
+}
+
+/// Finds the statements of the script with their pragmas, fails if the
+/// script cannot be tokenized, a pragma is invalid or is not followed by a
+/// statement
+pub fn parse_pragmas(text: &str) -> Result<Vec<AnnotatedStatement>, Error> {
+    let mut statements = Vec::new();
+    let mut current: Option<AnnotatedStatement> = None;
+    let mut pending = Vec::new();
+    let mut depth = 0usize;
+    let mut offset = 0;
+    for token in Tokenizer::new(text) {
+        let token = token?;
+        pending.extend(comment_pragmas(text, offset..token.span.start as usize)?);
+        offset = token.span.end as usize;
+        let statement = current.get_or_insert_with(|| AnnotatedStatement {
+            span: token.span,
+            pragmas: Vec::new(),
+        });
+        statement.span = statement.span.extend(&token.span);
+        statement.pragmas.append(&mut pending);
+        match token.kind {
+            Kind::OpenParen | Kind::OpenBracket | Kind::OpenBrace => depth += 1,
+            Kind::CloseParen | Kind::CloseBracket | Kind::CloseBrace => {
+                depth = depth.saturating_sub(1);
+            }
+            Kind::Semicolon if depth == 0 => statements.extend(current.take()),
+            _ => {}
+        }
+    }
+    let trailing = comment_pragmas(text, offset..text.len())?;
+    if let Some(pragma) = trailing.first() {
+        return Err(Error::new("pragma is not followed by a statement").with_span(pragma.span));
+    }
+    statements.extend(current);
+    Ok(statements)
+}
+
+/// Removes warnings whose code is disabled by a pragma of the statement
+/// they are in
+///
+/// Every warning of the checks of this crate has a code, errors are never
+/// removed.
+pub fn suppress_lints(statements: &[AnnotatedStatement], diagnostics: &mut Vec<Diagnostic>) {
+    diagnostics.retain(|d| match (d.severity, &d.code) {
+        (Severity::Warning, Some(code)) => !statements.iter().any(|s| {
+            s.span.start <= d.span.start && d.span.end <= s.span.end && s.is_lint_disabled(code)
+        }),
+        _ => true,
+    });
+}
+
+/// Pragmas of the comments in the `range` of the text, which is whitespace
+/// and comments between two tokens
+fn comment_pragmas(text: &str, range: Range<usize>) -> Result<Vec<Pragma>, Error> {
+    let mut pragmas = Vec::new();
+    let mut line_start = range.start;
+    for line in text[range].split_inclusive('\n') {
+        let hash = line.find('#').map(|idx| line_start + idx);
+        line_start += line.len();
+        let Some(hash) = hash else {
+            continue;
+        };
+        let comment = text[hash + 1..line_start].trim_end();
+        let Some(directive) = comment.trim_start().strip_prefix(PREFIX) else {
+            continue;
+        };
+        let span = Span {
+            start: hash as u64,
+            end: (hash + 1 + comment.len()) as u64,
+        };
+        let kind = pragma_kind(directive.trim()).map_err(|e| Error::new(e).with_span(span))?;
+        pragmas.push(Pragma { kind, span });
+    }
+    Ok(pragmas)
+}
+
+fn pragma_kind(directive: &str) -> Result<PragmaKind, String> {
+    let (name, args) = directive
+        .split_once(char::is_whitespace)
+        .unwrap_or((directive, ""));
+    let args = args.trim();
+    match name {
+        "name" => {
+            let valid = args
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'));
+            if args.is_empty() || !valid {
+                return Err(format!("invalid query name '{args}'"));
+            }
+            Ok(PragmaKind::Name(args.into()))
+        }
+        "lint-disable" => {
+            let codes: Vec<String> = args
+                .split(',')
+                .map(str::trim)
+                .filter(|code| !code.is_empty())
+                .map(Into::into)
+                .collect();
+            if codes.is_empty() {
+                return Err("expected codes of the lints to disable".into());
+            }
+            if let Some(code) = codes.iter().find(|c| !is_code(c)) {
+                return Err(format!("unknown lint '{code}'"));
+            }
+            Ok(PragmaKind::LintDisable(codes))
+        }
+        _ => Err(format!("unknown pragma '{name}'")),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_pragmas, suppress_lints, PragmaKind};
+    use crate::detached::analyze;
+    use crate::lint::with_bindings;
+
+    #[test]
+    fn statements() {
+        let text = "# edgeql: name unused\n\
+                    # edgeql: lint-disable unused-alias\n\
+                    with a := 1 select 2;\n\
+                    with b := 2 # edgeql: lint-disable single-use-alias\n\
+                    select b;\n\
+                    # not a pragma\n\
+                    with c := 3 select 4";
+        let statements = parse_pragmas(text).unwrap();
+        assert_eq!(statements.len(), 3);
+        assert_eq!(statements[0].name(), Some("unused"));
+        assert_eq!(statements[1].name(), None);
+        assert_eq!(
+            statements[1].pragmas[0].kind,
+            PragmaKind::LintDisable(vec!["single-use-alias".into()])
+        );
+        assert!(statements[2].pragmas.is_empty());
+
+        let mut diagnostics = with_bindings(text).unwrap();
+        suppress_lints(&statements, &mut diagnostics);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].message, "alias `c` is never used");
+    }
+
+    #[test]
+    fn detached() {
+        let text = "# edgeql: lint-disable useless-detached\n\
+                    select detached User;\n\
+                    select detached Post;";
+        let statements = parse_pragmas(text).unwrap();
+        let mut diagnostics = analyze(text).unwrap().warnings;
+        assert_eq!(diagnostics.len(), 2);
+        suppress_lints(&statements, &mut diagnostics);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].span.start,
+            text.find("detached Post").unwrap() as u64
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let error = |text: &str| parse_pragmas(text).unwrap_err().message;
+        assert_eq!(
+            error("# edgeql: lint-disable no-such-lint\nselect 1"),
+            "unknown lint 'no-such-lint'"
+        );
+        assert_eq!(
+            error("# edgeql: lint-disable unused-alias.suggestion\nselect 1"),
+            "unknown lint 'unused-alias.suggestion'"
+        );
+        assert_eq!(
+            error("# edgeql: name a b\nselect 1"),
+            "invalid query name 'a b'"
+        );
+        assert_eq!(
+            error("#edgeql: title x\nselect 1"),
+            "unknown pragma 'title'"
+        );
+        assert_eq!(
+            error("select 1; # edgeql: name one"),
+            "pragma is not followed by a statement"
+        );
+    }
+}
@@ -1,5 +1,10 @@
+use std::io::{self, Read};
+use std::ops::Range;
+
 use memchr::memmem::find;
 
+use crate::keywords::{self, Keyword};
+
 #[derive(Debug, PartialEq)]
 pub struct Continuation {
     position: usize,
@@ -204,3 +209,248 @@ pub fn is_empty(text: &str) -> bool {
         }
     }
 }
+
+/// Result of [`is_complete`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompletionStatus {
+    /// All statements are terminated and can be executed
+    Complete,
+    /// Last statement is unterminated: missing semicolon, unclosed string,
+    /// dollar-quote or bracket
+    NeedsContinuation,
+    /// Only whitespace, comments and semicolons
+    Empty,
+}
+
+/// Returns whether REPL input can be sent, or a continuation prompt should
+/// be shown instead
+pub fn is_complete(source: &str) -> CompletionStatus {
+    if is_empty(source) {
+        return CompletionStatus::Empty;
+    }
+    let mut rest = source;
+    while !is_empty(rest) {
+        match next_statement(rest, None) {
+            Ok((_, pos)) => rest = &rest[pos..],
+            Err(_) => return CompletionStatus::NeedsContinuation,
+        }
+    }
+    CompletionStatus::Complete
+}
+
+/// Kind of the next statement of the REPL input, see [`next_statement`]
+#[derive(Debug, PartialEq, Eq)]
+pub enum Statement<'a> {
+    /// EdgeQL statement, including trailing semicolon
+    Query(&'a str),
+    /// Backslash command, like `\d default::User`
+    Command(Command<'a>),
+}
+
+/// Backslash command of the REPL, terminated by the end of line
+#[derive(Debug, PartialEq, Eq)]
+pub struct Command<'a> {
+    /// Name without the backslash: `d`, `l`, `set`
+    pub name: &'a str,
+    /// Whitespace separated arguments, with surrounding quotes stripped
+    pub args: Vec<&'a str>,
+}
+
+/// Returns the next statement and position after it
+///
+/// Unlike `full_statement`, this also recognizes backslash commands which
+/// are terminated by newline (or the end of the input) rather than by a
+/// semicolon. Leading whitespace and comments are skipped.
+pub fn next_statement(
+    text: &str,
+    continuation: Option<Continuation>,
+) -> Result<(Statement, usize), Continuation> {
+    let start = if continuation.is_some() {
+        0
+    } else {
+        skip_insignificant(text)
+    };
+    if continuation.is_none() && text[start..].starts_with('\\') {
+        let end = text[start..]
+            .find('\n')
+            .map(|idx| start + idx + 1)
+            .unwrap_or(text.len());
+        let command = parse_command(text[start + 1..end].trim_end());
+        return Ok((Statement::Command(command), end));
+    }
+    let end = full_statement(text.as_bytes(), continuation)?;
+    Ok((Statement::Query(text[start..end].trim_start()), end))
+}
+
+/// Parses command text after the backslash
+pub fn parse_command(text: &str) -> Command {
+    let (name, mut rest) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+    let mut args = Vec::new();
+    loop {
+        rest = rest.trim_start();
+        let Some(first) = rest.chars().next() else {
+            break;
+        };
+        if first == '\'' || first == '"' {
+            let (arg, tail) = rest[1..].split_once(first).unwrap_or((&rest[1..], ""));
+            args.push(arg);
+            rest = tail;
+        } else {
+            let (arg, tail) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+            args.push(arg);
+            rest = tail;
+        }
+    }
+    Command { name, args }
+}
+
+fn skip_insignificant(text: &str) -> usize {
+    let mut iter = text.char_indices().peekable();
+    while let Some(&(idx, c)) = iter.peek() {
+        match c {
+            '\u{feff}' | '\r' | '\t' | '\n' | ' ' => {
+                iter.next();
+            }
+            '#' => {
+                for (_, c) in iter.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            _ => return idx,
+        }
+    }
+    text.len()
+}
+
+/// Statement found by [`Splitter`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SplitStatement {
+    /// Byte range in the input, without leading whitespace and comments and
+    /// including the semicolon (if any)
+    pub range: Range<u64>,
+    /// First keyword of the statement (`insert`, `create`, `configure`),
+    /// `None` if the statement starts with something else
+    pub kind: Option<Keyword>,
+    pub text: String,
+}
+
+/// Splits a stream of EdgeQL statements, e.g. a dump script of any size
+///
+/// Statements are found by [`full_statement`], so the splitter does not
+/// validate them. Only the statement being read is kept in memory, and the
+/// input is read in chunks that grow with the statement, so very long
+/// statements (i.e. dollar-quoted data) are still scanned in linear time.
+/// The last statement may lack a semicolon.
+pub struct Splitter<R> {
+    reader: R,
+    buf: Vec<u8>,
+    /// Start of the next statement in `buf`
+    pos: usize,
+    /// Offset of `buf[0]` in the input
+    offset: u64,
+    continuation: Option<Continuation>,
+    eof: bool,
+    max_statement_len: Option<usize>,
+}
+
+const SPLITTER_CHUNK: usize = 64 * 1024;
+
+impl<R: Read> Splitter<R> {
+    pub fn new(reader: R) -> Self {
+        Splitter {
+            reader,
+            buf: Vec::new(),
+            pos: 0,
+            offset: 0,
+            continuation: None,
+            eof: false,
+            max_statement_len: None,
+        }
+    }
+
+    /// Fails with [`io::ErrorKind::InvalidData`] instead of buffering a
+    /// statement longer than `max` bytes
+    pub fn with_max_statement_len(mut self, max: usize) -> Self {
+        self.max_statement_len = Some(max);
+        self
+    }
+
+    fn take(&mut self, end: usize) -> io::Result<Option<SplitStatement>> {
+        let start = self.offset + self.pos as u64;
+        let text = std::str::from_utf8(&self.buf[self.pos..self.pos + end]).map_err(|e| {
+            let offset = start + e.valid_up_to() as u64;
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid utf-8 at byte {offset}"),
+            )
+        })?;
+        self.pos += end;
+        if is_empty(text) {
+            return Ok(None);
+        }
+        let skip = skip_insignificant(text);
+        let text = &text[skip..];
+        let word_len = text
+            .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+            .unwrap_or(text.len());
+        Ok(Some(SplitStatement {
+            range: start + skip as u64..start + end as u64,
+            kind: keywords::lookup_all(&text[..word_len].to_ascii_lowercase()),
+            text: text.to_string(),
+        }))
+    }
+
+    fn read_more(&mut self) -> io::Result<()> {
+        // drop the statements that were returned already
+        self.buf.drain(..self.pos);
+        self.offset += self.pos as u64;
+        self.pos = 0;
+
+        let len = self.buf.len();
+        if let Some(max) = self.max_statement_len.filter(|&max| len > max) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "statement at byte {} is longer than {max} bytes",
+                    self.offset
+                ),
+            ));
+        }
+        let chunk = len.max(SPLITTER_CHUNK) as u64;
+        let read = (&mut self.reader).take(chunk).read_to_end(&mut self.buf)?;
+        self.eof = read == 0;
+        Ok(())
+    }
+}
+
+impl<R: Read> Iterator for Splitter<R> {
+    type Item = io::Result<SplitStatement>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.pos < self.buf.len() {
+                match full_statement(&self.buf[self.pos..], self.continuation.take()) {
+                    Ok(end) => match self.take(end) {
+                        Ok(Some(statement)) => return Some(Ok(statement)),
+                        Ok(None) => continue,
+                        Err(e) => return Some(Err(e)),
+                    },
+                    Err(continuation) => self.continuation = Some(continuation),
+                }
+            }
+            if self.eof {
+                self.continuation = None;
+                let end = self.buf.len() - self.pos;
+                return match self.take(end) {
+                    Ok(statement) => statement.map(Ok),
+                    Err(e) => Some(Err(e)),
+                };
+            }
+            if let Err(e) = self.read_more() {
+                return Some(Err(e));
+            }
+        }
+    }
+}
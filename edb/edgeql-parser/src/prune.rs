@@ -0,0 +1,236 @@
+//! Narrowing of shapes to the fields a client needs
+//!
+//! [prune] keeps only the elements of the main shape whose paths are
+//! requested, e.g. `name` and `friends.name`, which is what a GraphQL-like
+//! gateway needs to avoid over-fetching:
+//!
+//! ```text
+//! select User { name, email, n := count(.friends), friends: { name, email } }
+//! select User { name, friends: { name } }
+//! ```
+//!
+//! Link properties are requested as `@name` and backlinks as `<name`. A path
+//! ending at a link with a shape keeps the whole nested shape. Computed
+//! elements used in `filter` or `order by` of their shape are kept even if
+//! they are not requested. Splats are replaced by the requested names they
+//! would have covered: `*` by properties, `**` also by links.
+//!
+//! Shapes are re-emitted on a single line, comments in them are removed.
+use crate::schema::token_name;
+use crate::shape::{statement, Shape, ShapeElement};
+use crate::tokenizer::{Error, Kind, Token, Tokenizer};
+
+/// Requested paths as a tree
+#[derive(Debug, Default)]
+struct Fields {
+    children: Vec<(String, Fields)>,
+}
+
+impl Fields {
+    fn new<'a>(paths: impl IntoIterator<Item = &'a str>) -> Fields {
+        let mut root = Fields::default();
+        for path in paths {
+            let mut node = &mut root;
+            for step in path.split('.').map(str::trim).filter(|s| !s.is_empty()) {
+                let idx = match node.children.iter().position(|(name, _)| name == step) {
+                    Some(idx) => idx,
+                    None => {
+                        node.children.push((step.to_string(), Fields::default()));
+                        node.children.len() - 1
+                    }
+                };
+                node = &mut node.children[idx].1;
+            }
+        }
+        root
+    }
+
+    fn get(&self, name: &str) -> Option<&Fields> {
+        self.children
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, fields)| fields)
+    }
+
+    fn render(&self, buf: &mut String) {
+        buf.push_str("{ ");
+        for (i, (name, fields)) in self.children.iter().enumerate() {
+            if i > 0 {
+                buf.push_str(", ");
+            }
+            render_field(buf, name, fields);
+        }
+        buf.push_str(" }");
+    }
+}
+
+/// Keeps only the requested `paths` in the shape of the main select
+pub fn prune<'a>(query: &str, paths: impl IntoIterator<Item = &'a str>) -> Result<String, Error> {
+    let tokens = Tokenizer::new(query)
+        .validated_values()
+        .map(|t| t.map(|t| t.cloned()))
+        .collect::<Result<Vec<_>, _>>()?;
+    let shape = statement(&tokens)?
+        .filter(|s| s.keyword == "select")
+        .and_then(|s| s.shape)
+        .ok_or_else(|| Error::new("Query does not select a shape"))?;
+    let fields = Fields::new(paths);
+    let after = tokens
+        .iter()
+        .skip_while(|t| t.span.start < shape.span.end)
+        .cloned()
+        .collect::<Vec<_>>();
+
+    let mut result = String::with_capacity(query.len());
+    result.push_str(&query[..shape.span.start as usize]);
+    render_shape(&mut result, query, &shape, &fields, &after);
+    result.push_str(&query[shape.span.end as usize..]);
+    Ok(result)
+}
+
+fn render_shape(buf: &mut String, query: &str, shape: &Shape, fields: &Fields, clauses: &[Token]) {
+    let mut elements = Vec::new();
+    let mut splat = None;
+    for el in &shape.elements {
+        if el.is_splat() {
+            splat = Some(&el.name[..]);
+            continue;
+        }
+        let requested = fields.get(&key(el));
+        if requested.is_none() && !is_referenced(clauses, el) {
+            continue;
+        }
+        let mut text = String::new();
+        match (&el.shape, requested) {
+            (Some(nested), Some(nested_fields)) if !nested_fields.children.is_empty() => {
+                let nested_clauses = el.clauses.as_ref().map_or(&[][..], |c| &c.tokens[..]);
+                text.push_str(&query[el.span.start as usize..nested.span.start as usize]);
+                render_shape(&mut text, query, nested, nested_fields, nested_clauses);
+                text.push_str(&query[nested.span.end as usize..el.span.end as usize]);
+            }
+            _ => text.push_str(&query[el.span.start as usize..el.span.end as usize]),
+        }
+        elements.push(text);
+    }
+    if let Some(splat) = splat {
+        for (name, nested) in &fields.children {
+            let covered = shape.elements.iter().any(|el| key(el) == *name);
+            let is_property = nested.children.is_empty() && !name.starts_with('<');
+            if !covered && (is_property || splat == "**") {
+                let mut text = String::new();
+                render_field(&mut text, name, nested);
+                elements.push(text);
+            }
+        }
+    }
+    if elements.is_empty() {
+        elements.push("id".into());
+    }
+    buf.push_str("{ ");
+    buf.push_str(&elements.join(", "));
+    buf.push_str(" }");
+}
+
+fn render_field(buf: &mut String, name: &str, fields: &Fields) {
+    buf.push_str(name);
+    if !fields.children.is_empty() {
+        buf.push_str(": ");
+        fields.render(buf);
+    }
+}
+
+/// Name of the element as written in the requested paths
+fn key(el: &ShapeElement) -> String {
+    if el.link_property {
+        format!("@{}", el.name)
+    } else if el.backlink {
+        format!("<{}", el.name)
+    } else {
+        el.name.clone()
+    }
+}
+
+/// Whether the element is computed and `.name` (or `@name`) is used in the
+/// clauses of its shape, other elements are accessible without the shape
+fn is_referenced(clauses: &[Token], el: &ShapeElement) -> bool {
+    if el.expr.is_none() || el.backlink {
+        return false;
+    }
+    let prefix = if el.link_property {
+        Kind::At
+    } else {
+        Kind::Dot
+    };
+    clauses.windows(2).any(|pair| {
+        pair[0].kind == prefix
+            && matches!(pair[1].kind, Kind::Ident | Kind::BacktickName)
+            && token_name(&pair[1]) == el.name
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::prune;
+
+    #[test]
+    fn narrow() {
+        let query = "
+            select User {
+                name,
+                email,  # not needed
+                n := count(.friends),
+                friends: { name, email, @since } filter .active,
+            } filter .name != '';";
+        assert_eq!(
+            prune(query, ["name", "friends.name", "friends.@since"]).unwrap(),
+            "
+            select User { name, friends: { name, @since } filter .active } \
+            filter .name != '';"
+        );
+        // whole nested shape
+        assert_eq!(
+            prune(query, ["friends"]).unwrap(),
+            "
+            select User { friends: { name, email, @since } filter .active } \
+            filter .name != '';"
+        );
+        assert_eq!(
+            prune(query, [] as [&str; 0]).unwrap(),
+            "
+            select User { id } filter .name != '';"
+        );
+    }
+
+    #[test]
+    fn referenced() {
+        assert_eq!(
+            prune(
+                "select User { name, n := count(.friends) } order by .n",
+                ["name"]
+            )
+            .unwrap(),
+            "select User { name, n := count(.friends) } order by .n"
+        );
+        assert_eq!(
+            prune(
+                "select User { friends: { name, rank := 1 } order by .rank } limit 1",
+                ["friends.name"]
+            )
+            .unwrap(),
+            "select User { friends: { name, rank := 1 } order by .rank } limit 1"
+        );
+    }
+
+    #[test]
+    fn splats() {
+        assert_eq!(
+            prune("select User { *, n := 1 }", ["name", "n", "friends.name"]).unwrap(),
+            "select User { n := 1, name }"
+        );
+        assert_eq!(
+            prune("select User { ** }", ["name", "friends.name"]).unwrap(),
+            "select User { name, friends: { name } }"
+        );
+        assert!(prune("select 1", ["name"]).is_err());
+    }
+}
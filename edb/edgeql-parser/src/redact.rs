@@ -0,0 +1,98 @@
+//! Replacement of literal values with placeholders
+//!
+//! [redact] makes a query safe to log or attach to a bug report: every
+//! literal is replaced by a placeholder of the same type, so the query still
+//! parses and type checks the same way, but contains no data:
+//!
+//! ```text
+//! select User filter .email = 'alice@example.com' and .age > 42
+//! select User filter .email = '***' and .age > 0
+//! ```
+//!
+//! Strings right after a cast (`<datetime>'2024-01-01'`) become `'...'`, so
+//! that the cast is visibly kept. Names, parameters and formatting are kept
+//! as is, comments are removed as they may contain data too.
+use crate::tokenizer::{Error, Kind, Token, Tokenizer};
+
+/// Replaces literals with placeholders and removes comments, fails if the
+/// source cannot be tokenized
+pub fn redact(source: &str) -> Result<String, Error> {
+    let mut result = String::with_capacity(source.len());
+    let mut offset = 0;
+    let mut prev: Option<Token> = None;
+    for token in Tokenizer::new(source) {
+        let token = token?;
+        push_whitespace(&mut result, &source[offset..token.span.start as usize]);
+        result.push_str(placeholder(&token, prev.as_ref()).unwrap_or(&token.text));
+        offset = token.span.end as usize;
+        prev = Some(token);
+    }
+    push_whitespace(&mut result, &source[offset..]);
+    Ok(result)
+}
+
+fn placeholder(token: &Token, prev: Option<&Token>) -> Option<&'static str> {
+    let prev = prev.map(|t| t.kind);
+    let text = match token.kind {
+        Kind::Str if prev == Some(Kind::Greater) => "'...'",
+        Kind::Str => "'***'",
+        Kind::BinStr => "b'***'",
+        // `.0` is an access to the tuple element rather than a value
+        Kind::IntConst if prev == Some(Kind::Dot) => return None,
+        Kind::IntConst => "0",
+        Kind::FloatConst => "0.0",
+        Kind::BigIntConst => "0n",
+        Kind::DecimalConst => "0.0n",
+        Kind::StrInterpStart => "'***\\(",
+        Kind::StrInterpCont => ")***\\(",
+        Kind::StrInterpEnd => ")***'",
+        _ => return None,
+    };
+    Some(text)
+}
+
+/// Pushes the text between two tokens without comments
+fn push_whitespace(result: &mut String, gap: &str) {
+    let mut in_comment = false;
+    for c in gap.chars() {
+        match c {
+            '#' => in_comment = true,
+            '\n' => {
+                in_comment = false;
+                result.push(c);
+            }
+            _ if !in_comment => result.push(c),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::redact;
+
+    #[test]
+    fn literals() {
+        assert_eq!(
+            redact(
+                "select User { name } # only alice\n\
+                 filter .email = 'alice@example.com' and .age > 42\n\
+                 and .born < <datetime>\"1990-01-01T00:00:00Z\" limit 10;"
+            )
+            .unwrap(),
+            "select User { name } \n\
+             filter .email = '***' and .age > 0\n\
+             and .born < <datetime>'...' limit 0;"
+        );
+        assert_eq!(
+            redact("select (1.5, 2n, 3.5n, -7, b'\\x00', r'a\\b', $$x$$)").unwrap(),
+            "select (0.0, 0n, 0.0n, -0, b'***', '***', '***')"
+        );
+        // tuple access and parameters are not data
+        assert_eq!(
+            redact("select (<str>$name, ('a', 1)).1.0").unwrap(),
+            "select (<str>$name, ('***', 0)).1.0"
+        );
+        assert!(redact("select 'oops").is_err());
+    }
+}
@@ -0,0 +1,213 @@
+//! Checks of required pointers in `insert` and `update` statements
+//!
+//! [check_required] reports inserts that do not set a required pointer
+//! without a default, and inserts and updates that set a required pointer
+//! to an empty set, which otherwise only fail when the query runs.
+//!
+//! All `insert` and `update` statements of the query are checked, including
+//! nested ones, if their type is known to the [Resolver]. Pointers set by
+//! inherited defaults or triggers on the server are not known, so a pointer
+//! is only reported if the resolver says it has no default.
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::keywords::Keyword;
+use crate::position::Span;
+use crate::resolver::{Pointer, Resolver, TypeKind};
+use crate::schema::{is_name_like, token_name};
+use crate::shape::{parse_shape, statement, ShapeElement};
+use crate::tokenizer::{Error, Kind, Token, Tokenizer};
+
+/// Reports required pointers that inserts and updates leave empty, fails if
+/// the query cannot be tokenized or has an unclosed shape
+pub fn check_required(query: &str, resolver: &dyn Resolver) -> Result<Vec<Diagnostic>, Error> {
+    let tokens = Tokenizer::new(query)
+        .validated_values()
+        .map(|t| t.map(|t| t.cloned()))
+        .collect::<Result<Vec<_>, _>>()?;
+    let module = statement(&tokens)?
+        .and_then(|s| s.module)
+        .unwrap_or_else(|| "default".into());
+    let mut errors = Vec::new();
+    for pos in 0..tokens.len() {
+        let verb = match tokens[pos].kind {
+            Kind::Keyword(Keyword(kw @ ("insert" | "update"))) => kw,
+            _ => continue,
+        };
+        let Some((name, span, end)) = read_name(&tokens, pos + 1) else {
+            continue;
+        };
+        let Some(type_name) = resolver.resolve(&name, &module) else {
+            continue;
+        };
+        if resolver.type_kind(&type_name) != Some(TypeKind::Object) {
+            continue;
+        }
+        let shape_start = if verb == "insert" {
+            Some(end).filter(|&p| tokens.get(p).map(|t| t.kind) == Some(Kind::OpenBrace))
+        } else {
+            find_set(&tokens, end)
+        };
+        let shape = match shape_start {
+            Some(start) => Some(parse_shape(&tokens, start)?.0),
+            None => None,
+        };
+        let elements = shape.as_ref().map_or(&[][..], |s| &s.elements[..]);
+        let pointers = resolver.pointers(&type_name);
+        let required = pointers.iter().filter(|p| p.required && !p.computed);
+
+        for pointer in required {
+            let set = elements
+                .iter()
+                .find(|e| !e.link_property && !e.backlink && e.name == pointer.name);
+            match set {
+                Some(element) if is_empty_set(element) => {
+                    errors.push(missing(pointer, &type_name, element.span));
+                }
+                None if verb == "insert" && !pointer.has_default => {
+                    errors.push(missing(pointer, &type_name, span).with_hint());
+                }
+                _ => {}
+            }
+        }
+    }
+    Ok(errors)
+}
+
+fn missing(pointer: &Pointer, type_name: &str, span: Span) -> Diagnostic {
+    let kind = if pointer.is_link { "link" } else { "property" };
+    Diagnostic::with_code(
+        Severity::Error,
+        "missing-required",
+        &[
+            ("kind", kind.into()),
+            ("name", pointer.name.clone()),
+            ("type", type_name.into()),
+        ],
+        span,
+    )
+}
+
+fn is_empty_set(element: &ShapeElement) -> bool {
+    element.expr.as_ref().is_some_and(|expr| {
+        matches!(
+            &expr.tokens[..],
+            [open, close] if open.kind == Kind::OpenBrace && close.kind == Kind::CloseBrace
+        )
+    })
+}
+
+/// Reads a possibly qualified type name, returns it with its span and the
+/// index of the token after it
+fn read_name(tokens: &[Token], start: usize) -> Option<(String, Span, usize)> {
+    let first = tokens.get(start).filter(|t| is_name_like(Some(t.kind)))?;
+    let mut name = token_name(first).to_string();
+    let mut span = first.span;
+    let mut pos = start + 1;
+    while tokens.get(pos).map(|t| t.kind) == Some(Kind::Namespace)
+        && is_name_like(tokens.get(pos + 1).map(|t| t.kind))
+    {
+        name.push_str("::");
+        name.push_str(token_name(&tokens[pos + 1]));
+        span = span.extend(&tokens[pos + 1].span);
+        pos += 2;
+    }
+    Some((name, span, pos))
+}
+
+/// Index of the brace after `set` of the update statement, the search
+/// stops at the end of the statement
+fn find_set(tokens: &[Token], start: usize) -> Option<usize> {
+    let mut depth = 0usize;
+    for (pos, tok) in tokens.iter().enumerate().skip(start) {
+        match tok.kind {
+            Kind::OpenParen | Kind::OpenBracket | Kind::OpenBrace => depth += 1,
+            Kind::CloseParen | Kind::CloseBracket | Kind::CloseBrace => {
+                depth = depth.checked_sub(1)?;
+            }
+            Kind::Semicolon => return None,
+            Kind::Keyword(Keyword("set")) if depth == 0 => {
+                return Some(pos + 1)
+                    .filter(|&p| tokens.get(p).map(|t| t.kind) == Some(Kind::OpenBrace));
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::check_required;
+    use crate::resolver::SchemaResolver;
+    use crate::schema::parse;
+
+    fn errors(query: &str) -> Vec<String> {
+        let schema = parse(
+            "
+            abstract type Named {
+                required name: str;
+            }
+            type User extending Named {
+                required status: str {
+                    default := 'active';
+                };
+                required team: Team;
+                required slug := str_lower(.name);
+                nick: str;
+            }
+            type Team extending Named;
+            ",
+        )
+        .unwrap();
+        let resolver = SchemaResolver::new(&schema);
+        check_required(query, &resolver)
+            .unwrap()
+            .into_iter()
+            .map(|e| {
+                let span = e.span;
+                format!(
+                    "{} @{}",
+                    e.message,
+                    &query[span.start as usize..span.end as usize]
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn insert() {
+        assert_eq!(
+            errors("insert User { name := 'a', nick := 'b' }"),
+            ["missing value for required link 'team' of object type 'default::User' @User"]
+        );
+        assert!(
+            errors("insert User { name := 'a', team := (insert Team { name := 't' }) }").is_empty()
+        );
+        assert_eq!(
+            errors("with module default insert User { name := 'a', team := (insert Team) }"),
+            [
+                "missing value for required property 'name' of object type 'default::Team' \
+                 @Team"
+            ]
+        );
+        assert_eq!(
+            errors("insert default::User { name := {}, status := {}, team := {} }"),
+            [
+                "missing value for required property 'status' of object type \
+                 'default::User' @status := {}",
+                "missing value for required link 'team' of object type 'default::User' \
+                 @team := {}",
+                "missing value for required property 'name' of object type 'default::User' \
+                 @name := {}",
+            ]
+        );
+    }
+
+    #[test]
+    fn update() {
+        assert!(errors("update User filter .name = 'a' set { nick := 'b' }").is_empty());
+        assert_eq!(
+            errors("update User filter .name in {'a', 'b'} set { name := {}, nick := {} }"),
+            ["missing value for required property 'name' of object type 'default::User' @name := {}"]
+        );
+    }
+}
@@ -0,0 +1,435 @@
+//! Access to schema information needed by analyses of queries
+//!
+//! The [Resolver] trait is implemented by tools that have a schema at hand
+//! (e.g. fetched using [crate::codegen::introspection] queries).
+//! [SchemaResolver] implements it for an SDL document.
+use std::collections::HashMap;
+
+use crate::schema::qualify::qualify;
+use crate::schema::{DeclKind, Declaration, Document, TypeRef};
+
+/// Scalar types of the standard library
+pub const STD_SCALARS: &[&str] = &[
+    "std::str",
+    "std::bool",
+    "std::int16",
+    "std::int32",
+    "std::int64",
+    "std::float32",
+    "std::float64",
+    "std::bigint",
+    "std::decimal",
+    "std::uuid",
+    "std::bytes",
+    "std::json",
+    "std::datetime",
+    "std::duration",
+    "std::sequence",
+    "cal::local_datetime",
+    "cal::local_date",
+    "cal::local_time",
+    "cal::relative_duration",
+    "cal::date_duration",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TypeKind {
+    Object,
+    Scalar,
+    /// Enum scalar with its values
+    Enum(Vec<String>),
+}
+
+/// Fully-qualified name of a type with its parameters, e.g. `array<std::str>`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeName {
+    pub name: String,
+    pub args: Vec<TypeName>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pointer {
+    pub name: String,
+    pub is_link: bool,
+    /// Unknown for pointers computed without an explicit type
+    pub target: Option<TypeName>,
+    pub multi: bool,
+    pub required: bool,
+    pub has_default: bool,
+    pub computed: bool,
+    /// Link properties
+    pub properties: Vec<Pointer>,
+}
+
+pub trait Resolver {
+    /// Kind of the type with the fully-qualified `name`, if it exists
+    fn type_kind(&self, name: &str) -> Option<TypeKind>;
+
+    /// Pointers of an object type, including the inherited ones
+    fn pointers(&self, type_name: &str) -> Vec<Pointer>;
+
+    /// Base type of a scalar type
+    fn scalar_base(&self, _name: &str) -> Option<String> {
+        None
+    }
+
+    /// All fully-qualified names of the types known to the resolver
+    fn type_names(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Fully-qualified names of the base types, including the indirect ones
+    fn ancestors(&self, _name: &str) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Resolves a type name as it is written inside of the `module`
+    fn resolve(&self, name: &str, module: &str) -> Option<String> {
+        if name.contains("::") {
+            return self.type_kind(name).map(|_| name.to_string());
+        }
+        [format!("{module}::{name}"), format!("std::{name}")]
+            .into_iter()
+            .find(|full| self.type_kind(full).is_some())
+    }
+
+    fn pointer(&self, type_name: &str, pointer: &str) -> Option<Pointer> {
+        self.pointers(type_name)
+            .into_iter()
+            .find(|p| p.name == pointer)
+    }
+
+    /// Known types with names close to the unknown `name`, see
+    /// [closest_names]
+    fn suggest_types(&self, name: &str) -> Vec<String> {
+        let names = self.type_names();
+        closest_names(name, names.iter().map(|n| &n[..]))
+    }
+
+    /// Pointers of the type with names close to the unknown `pointer`
+    fn suggest_pointers(&self, type_name: &str, pointer: &str) -> Vec<String> {
+        let pointers = self.pointers(type_name);
+        closest_names(pointer, pointers.iter().map(|p| &p.name[..]))
+    }
+}
+
+/// Names among `candidates` that are close to `name`, best first
+///
+/// Same as the "did you mean" of the server: names are compared without
+/// their module, only the ones at Levenshtein distance below 3 are kept,
+/// sorted by the distance, then names starting with `name` go first. At most
+/// 3 names are returned.
+pub fn closest_names<'a>(name: &str, candidates: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let local = local_name(name);
+    let mut closest: Vec<_> = candidates
+        .into_iter()
+        .map(|c| {
+            (
+                levenshtein(local, local_name(c)),
+                !local_name(c).starts_with(local),
+                c,
+            )
+        })
+        .filter(|(distance, _, _)| *distance < 3)
+        .collect();
+    closest.sort();
+    closest.dedup();
+    closest
+        .into_iter()
+        .take(3)
+        .map(|(_, _, c)| c.to_string())
+        .collect()
+}
+
+/// Hint of an error about an unknown name, `None` if there are no
+/// suggestions
+pub fn suggestion_hint(names: &[String]) -> Option<String> {
+    match names {
+        [] => None,
+        [name] => Some(format!("did you mean '{name}'?")),
+        _ => Some(format!("did you mean one of these: {}?", names.join(", "))),
+    }
+}
+
+fn local_name(name: &str) -> &str {
+    name.rsplit("::").next().unwrap_or(name)
+}
+
+/// Number of single char insertions, deletions and substitutions needed to
+/// turn `a` into `b`
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut prev = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = prev + usize::from(ca != *cb);
+            prev = row[j + 1];
+            row[j + 1] = substitution.min(prev + 1).min(row[j] + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// Resolver over types of an SDL document
+#[derive(Debug)]
+pub struct SchemaResolver {
+    types: HashMap<String, Declaration>,
+}
+
+impl SchemaResolver {
+    pub fn new(doc: &Document) -> SchemaResolver {
+        fn collect(decls: &[Declaration], types: &mut HashMap<String, Declaration>) {
+            for decl in decls {
+                match decl.kind {
+                    DeclKind::Module => collect(&decl.body, types),
+                    DeclKind::ObjectType | DeclKind::ScalarType => {
+                        if let Some(name) = decl.name_text() {
+                            types.insert(qualified(name), decl.clone());
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        let mut types = HashMap::new();
+        collect(&qualify(doc).declarations, &mut types);
+        SchemaResolver { types }
+    }
+
+    fn collect_pointers(&self, decl: &Declaration, result: &mut Vec<Pointer>, depth: usize) {
+        // guards against inheritance cycles
+        if depth > self.types.len() {
+            return;
+        }
+        for ptr in decl.body.iter().filter(|d| d.is_pointer()) {
+            let pointer = self.pointer_info(ptr);
+            if !result.iter().any(|p| p.name == pointer.name) {
+                result.push(pointer);
+            }
+        }
+        for base in &decl.extending {
+            if let Some(base) = self.types.get(&qualified(&base.name.text)) {
+                self.collect_pointers(base, result, depth + 1);
+            }
+        }
+    }
+
+    fn pointer_info(&self, ptr: &Declaration) -> Pointer {
+        let target = match &ptr.target[..] {
+            [target] => Some(self.type_name(target)),
+            _ => None,
+        };
+        let is_link = match ptr.kind {
+            DeclKind::Link => true,
+            DeclKind::Property => false,
+            _ => target
+                .as_ref()
+                .is_some_and(|t| self.type_kind(&t.name) == Some(TypeKind::Object)),
+        };
+        let has_default = ptr
+            .body
+            .iter()
+            .any(|d| d.kind == DeclKind::Field && d.name_text() == Some("default"));
+        Pointer {
+            name: ptr.name_text().unwrap_or_default().to_string(),
+            is_link,
+            target,
+            multi: ptr.is_multi(),
+            required: ptr.has_modifier("required"),
+            has_default,
+            computed: ptr.expr.is_some(),
+            properties: ptr
+                .body
+                .iter()
+                .filter(|d| d.is_pointer())
+                .map(|d| self.pointer_info(d))
+                .collect(),
+        }
+    }
+
+    fn type_name(&self, ty: &TypeRef) -> TypeName {
+        let text = &ty.name.text;
+        let name = if text.contains("::") {
+            text.clone()
+        } else if self.types.contains_key(&qualified(text)) {
+            qualified(text)
+        } else if STD_SCALARS.contains(&&format!("std::{text}")[..]) {
+            format!("std::{text}")
+        } else {
+            text.clone()
+        };
+        TypeName {
+            name,
+            args: ty.args.iter().map(|a| self.type_name(a)).collect(),
+        }
+    }
+}
+
+/// Names of types outside of modules belong to `default`
+fn qualified(name: &str) -> String {
+    if name.contains("::") {
+        name.to_string()
+    } else {
+        format!("default::{name}")
+    }
+}
+
+impl Resolver for SchemaResolver {
+    fn type_kind(&self, name: &str) -> Option<TypeKind> {
+        if STD_SCALARS.contains(&name) {
+            return Some(TypeKind::Scalar);
+        }
+        let decl = self.types.get(name)?;
+        if decl.kind == DeclKind::ObjectType {
+            return Some(TypeKind::Object);
+        }
+        match decl
+            .extending
+            .iter()
+            .find(|b| b.name.text == "enum" || b.name.text == "std::enum")
+        {
+            Some(values) => Some(TypeKind::Enum(
+                values.args.iter().map(|v| v.name.text.clone()).collect(),
+            )),
+            None => Some(TypeKind::Scalar),
+        }
+    }
+
+    fn pointers(&self, type_name: &str) -> Vec<Pointer> {
+        let Some(decl) = self.types.get(type_name) else {
+            return Vec::new();
+        };
+        if decl.kind != DeclKind::ObjectType {
+            return Vec::new();
+        }
+        let mut result = vec![Pointer {
+            name: "id".into(),
+            is_link: false,
+            target: Some(TypeName {
+                name: "std::uuid".into(),
+                args: Vec::new(),
+            }),
+            multi: false,
+            required: true,
+            has_default: true,
+            computed: false,
+            properties: Vec::new(),
+        }];
+        self.collect_pointers(decl, &mut result, 0);
+        result
+    }
+
+    fn scalar_base(&self, name: &str) -> Option<String> {
+        let decl = self.types.get(name)?;
+        let base = decl.extending.first()?;
+        Some(self.type_name(base).name)
+    }
+
+    fn type_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.types.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    fn ancestors(&self, name: &str) -> Vec<String> {
+        let mut result = Vec::new();
+        let mut queue = vec![name.to_string()];
+        while let Some(name) = queue.pop() {
+            let Some(decl) = self.types.get(&name) else {
+                continue;
+            };
+            for base in &decl.extending {
+                let base = qualified(&base.name.text);
+                if !result.contains(&base) {
+                    result.push(base.clone());
+                    queue.push(base);
+                }
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{closest_names, levenshtein, suggestion_hint, Resolver, SchemaResolver, TypeKind};
+    use crate::schema::parse;
+
+    #[test]
+    fn schema() {
+        let doc = parse(
+            "
+            module default {
+                abstract type Named {
+                    required name: str;
+                }
+                type User extending Named {
+                    multi friends: User {
+                        since: datetime;
+                    };
+                    required status: Status {
+                        default := Status.Active;
+                    };
+                    name_len := len(.name);
+                }
+                scalar type Status extending enum<Active, Inactive>;
+            }
+            ",
+        )
+        .unwrap();
+        let resolver = SchemaResolver::new(&doc);
+        assert_eq!(
+            resolver.resolve("User", "default").unwrap(),
+            "default::User"
+        );
+        assert_eq!(resolver.resolve("str", "default").unwrap(), "std::str");
+        assert_eq!(resolver.resolve("Profile", "default"), None);
+        assert_eq!(
+            resolver.type_kind("default::Status"),
+            Some(TypeKind::Enum(vec!["Active".into(), "Inactive".into()]))
+        );
+
+        let pointers = resolver.pointers("default::User");
+        let names: Vec<_> = pointers.iter().map(|p| &p.name[..]).collect();
+        assert_eq!(names, ["id", "friends", "status", "name_len", "name"]);
+        let friends = &pointers[1];
+        assert!(friends.is_link && friends.multi && !friends.required);
+        assert_eq!(friends.properties[0].name, "since");
+        assert_eq!(
+            friends.properties[0].target.as_ref().unwrap().name,
+            "std::datetime"
+        );
+        assert!(pointers[2].has_default && pointers[2].required);
+        assert!(pointers[3].computed && pointers[3].target.is_none());
+
+        assert_eq!(resolver.ancestors("default::User"), ["default::Named"]);
+        assert_eq!(resolver.suggest_types("Usr"), ["default::User"]);
+        assert_eq!(resolver.suggest_pointers("default::User", "nme"), ["name"]);
+    }
+
+    #[test]
+    fn suggestions() {
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+        assert_eq!(levenshtein("", "abc"), 3);
+        assert_eq!(levenshtein("name", "name"), 0);
+
+        let names = ["name_len", "nam", "names", "title", "default::named"];
+        assert_eq!(
+            closest_names("name", names),
+            ["default::named", "names", "nam"]
+        );
+        assert_eq!(closest_names("xyz", names), [] as [String; 0]);
+
+        assert_eq!(suggestion_hint(&[]), None);
+        assert_eq!(
+            suggestion_hint(&["name".into()]).unwrap(),
+            "did you mean 'name'?"
+        );
+        assert_eq!(
+            suggestion_hint(&["names".into(), "nam".into()]).unwrap(),
+            "did you mean one of these: names, nam?"
+        );
+    }
+}
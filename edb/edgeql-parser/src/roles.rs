@@ -0,0 +1,488 @@
+//! Role DDL
+//!
+//! [parse_roles] reads `create role`, `alter role` and `drop role` statements
+//! of a script into [RoleStatement]s, so that tools managing roles can read
+//! and check the DDL they apply. Other statements of the script are skipped.
+//!
+//! With the `future-syntax` feature, `grant <permission>, ... to <role>` and
+//! `revoke <permission>, ... from <role>` are parsed as well. They are the
+//! proposed shorthands of altering the `permissions` of a role. The grammar
+//! does not accept them yet, so the server rejects scripts using them.
+//!
+//! Statements are read from tokens, independently of the grammar, which
+//! remains the one deciding whether the DDL is valid: this module checks
+//! the parts of role statements tools care about and may accept input the
+//! server rejects.
+use crate::keywords::Keyword;
+use crate::position::Span;
+use crate::schema::{is_name_like, token_name, Expr, Name};
+use crate::tokenizer::{Error, Kind, Token, Tokenizer};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoleField {
+    /// Name of the field, e.g. `password`
+    pub name: String,
+    pub name_span: Span,
+    pub value: Expr,
+}
+
+/// Position of a base in `extending` of `alter role`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Position {
+    First,
+    Last,
+    Before(Name),
+    After(Name),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AlterRoleCommand {
+    /// `rename to <name>`
+    Rename { to: Name },
+    /// `set <field> := <expr>`
+    Set(RoleField),
+    /// `reset <field>`
+    Reset { field: String, span: Span },
+    /// `extending <name>, ... [first | last | before <name> | after <name>]`
+    Extending {
+        bases: Vec<Name>,
+        position: Option<Position>,
+    },
+    /// `drop extending <name>, ...`
+    DropExtending { bases: Vec<Name> },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RoleStatementKind {
+    /// `create [superuser] role <name> [extending <name>, ...]
+    /// [if not exists] [{ set <field> := <expr>; ... }]`
+    Create {
+        superuser: bool,
+        extending: Vec<Name>,
+        if_not_exists: bool,
+        fields: Vec<RoleField>,
+    },
+    /// `alter role <name> <command>` or `alter role <name> { <command>; ... }`
+    Alter { commands: Vec<AlterRoleCommand> },
+    /// `drop role <name>`
+    Drop,
+    /// `grant <permission>, ... to <name>`
+    #[cfg(feature = "future-syntax")]
+    Grant { permissions: Vec<Name> },
+    /// `revoke <permission>, ... from <name>`
+    #[cfg(feature = "future-syntax")]
+    Revoke { permissions: Vec<Name> },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoleStatement {
+    pub kind: RoleStatementKind,
+    /// Name of the role the statement applies to
+    pub role: Name,
+    /// Span of the statement, without the `;`
+    pub span: Span,
+}
+
+/// Parses role statements of a script, skipping other statements
+pub fn parse_roles(text: &str) -> Result<Vec<RoleStatement>, Error> {
+    let tokens = Tokenizer::new(text)
+        .validated_values()
+        .map(|t| t.map(|t| t.cloned()))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut parser = Parser {
+        tokens,
+        pos: 0,
+        eof: Span {
+            start: text.len() as u64,
+            end: text.len() as u64,
+        },
+    };
+    let mut statements = Vec::new();
+    while parser.pos < parser.tokens.len() {
+        if parser.kind(0) == Some(Kind::Semicolon) {
+            parser.pos += 1;
+            continue;
+        }
+        if let Some(statement) = parser.statement()? {
+            statements.push(statement);
+        }
+    }
+    Ok(statements)
+}
+
+struct Parser {
+    tokens: Vec<Token<'static>>,
+    pos: usize,
+    eof: Span,
+}
+
+impl Parser {
+    fn kind(&self, n: usize) -> Option<Kind> {
+        self.tokens.get(self.pos + n).map(|t| t.kind)
+    }
+
+    fn keyword(&self, n: usize) -> Option<&'static str> {
+        match self.kind(n) {
+            Some(Kind::Keyword(Keyword(kw))) => Some(kw),
+            _ => None,
+        }
+    }
+
+    fn span(&self) -> Span {
+        self.tokens.get(self.pos).map_or(self.eof, |t| t.span)
+    }
+
+    fn expected(&self, what: &str) -> Error {
+        Error::new(format!("Expected {what}")).with_span(self.span())
+    }
+
+    fn unexpected(&self) -> Error {
+        match self.tokens.get(self.pos) {
+            Some(tok) => Error::new(format!("Unexpected '{}'", tok.text)).with_span(tok.span),
+            None => Error::new("Unexpected end of input").with_span(self.eof),
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), Error> {
+        if self.keyword(0) != Some(keyword) {
+            return Err(self.expected(&format!("'{keyword}'")));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn statement(&mut self) -> Result<Option<RoleStatement>, Error> {
+        let start = self.pos;
+        let (role, kind) = match (self.keyword(0), self.keyword(1), self.keyword(2)) {
+            (Some("create"), Some("role"), _) => {
+                self.pos += 2;
+                self.create(false)?
+            }
+            (Some("create"), Some("superuser"), Some("role")) => {
+                self.pos += 3;
+                self.create(true)?
+            }
+            (Some("alter"), Some("role"), _) => {
+                self.pos += 2;
+                let role = self.short_name()?;
+                let commands = if self.kind(0) == Some(Kind::OpenBrace) {
+                    self.block(Parser::alter_command)?
+                } else {
+                    vec![self.alter_command()?]
+                };
+                (role, RoleStatementKind::Alter { commands })
+            }
+            (Some("drop"), Some("role"), _) => {
+                self.pos += 2;
+                (self.short_name()?, RoleStatementKind::Drop)
+            }
+            #[cfg(feature = "future-syntax")]
+            (Some("grant"), _, _) => {
+                self.pos += 1;
+                let permissions = self.names(Parser::name)?;
+                self.expect_keyword("to")?;
+                (self.short_name()?, RoleStatementKind::Grant { permissions })
+            }
+            #[cfg(feature = "future-syntax")]
+            (Some("revoke"), _, _) => {
+                self.pos += 1;
+                let permissions = self.names(Parser::name)?;
+                self.expect_keyword("from")?;
+                (
+                    self.short_name()?,
+                    RoleStatementKind::Revoke { permissions },
+                )
+            }
+            _ => {
+                self.skip_statement();
+                return Ok(None);
+            }
+        };
+        if !matches!(self.kind(0), None | Some(Kind::Semicolon)) {
+            return Err(self.unexpected());
+        }
+        let span = self.tokens[start]
+            .span
+            .extend(&self.tokens[self.pos - 1].span);
+        Ok(Some(RoleStatement { kind, role, span }))
+    }
+
+    fn create(&mut self, superuser: bool) -> Result<(Name, RoleStatementKind), Error> {
+        let role = self.short_name()?;
+        let mut extending = Vec::new();
+        if self.keyword(0) == Some("extending") {
+            self.pos += 1;
+            extending = self.names(Parser::short_name)?;
+        }
+        let mut if_not_exists = false;
+        if self.keyword(0) == Some("if") {
+            self.pos += 1;
+            self.expect_keyword("not")?;
+            self.expect_keyword("exists")?;
+            if_not_exists = true;
+        }
+        let mut fields = Vec::new();
+        if self.kind(0) == Some(Kind::OpenBrace) {
+            fields = self.block(|p| {
+                p.expect_keyword("set")?;
+                p.field()
+            })?;
+        }
+        let kind = RoleStatementKind::Create {
+            superuser,
+            extending,
+            if_not_exists,
+            fields,
+        };
+        Ok((role, kind))
+    }
+
+    fn alter_command(&mut self) -> Result<AlterRoleCommand, Error> {
+        let command = match (self.keyword(0), self.keyword(1)) {
+            (Some("rename"), Some("to")) => {
+                self.pos += 2;
+                AlterRoleCommand::Rename {
+                    to: self.short_name()?,
+                }
+            }
+            (Some("set"), _) => {
+                self.pos += 1;
+                AlterRoleCommand::Set(self.field()?)
+            }
+            (Some("reset"), _) => {
+                self.pos += 1;
+                let field = self.short_name()?;
+                AlterRoleCommand::Reset {
+                    field: field.text,
+                    span: field.span,
+                }
+            }
+            (Some("extending"), _) => {
+                self.pos += 1;
+                let bases = self.names(Parser::short_name)?;
+                let position = match self.keyword(0) {
+                    Some("first") => Some(Position::First),
+                    Some("last") => Some(Position::Last),
+                    Some("before") => {
+                        self.pos += 1;
+                        Some(Position::Before(self.short_name()?))
+                    }
+                    Some("after") => {
+                        self.pos += 1;
+                        Some(Position::After(self.short_name()?))
+                    }
+                    _ => None,
+                };
+                if matches!(position, Some(Position::First | Position::Last)) {
+                    self.pos += 1;
+                }
+                AlterRoleCommand::Extending { bases, position }
+            }
+            (Some("drop"), Some("extending")) => {
+                self.pos += 2;
+                AlterRoleCommand::DropExtending {
+                    bases: self.names(Parser::short_name)?,
+                }
+            }
+            _ => return Err(self.unexpected()),
+        };
+        Ok(command)
+    }
+
+    /// Reads `<field> := <expr>` after `set`
+    fn field(&mut self) -> Result<RoleField, Error> {
+        let name = self.short_name()?;
+        if self.kind(0) != Some(Kind::Assign) {
+            return Err(self.expected("':='"));
+        }
+        self.pos += 1;
+        Ok(RoleField {
+            name: name.text,
+            name_span: name.span,
+            value: self.expr()?,
+        })
+    }
+
+    /// Reads commands in braces separated by `;`
+    fn block<T>(
+        &mut self,
+        mut command: impl FnMut(&mut Self) -> Result<T, Error>,
+    ) -> Result<Vec<T>, Error> {
+        self.pos += 1;
+        let mut commands = Vec::new();
+        loop {
+            match self.kind(0) {
+                Some(Kind::CloseBrace) => {
+                    self.pos += 1;
+                    return Ok(commands);
+                }
+                Some(Kind::Semicolon) => self.pos += 1,
+                None => return Err(self.expected("'}'")),
+                _ => {
+                    commands.push(command(self)?);
+                    if !matches!(self.kind(0), Some(Kind::Semicolon | Kind::CloseBrace)) {
+                        return Err(self.unexpected());
+                    }
+                }
+            }
+        }
+    }
+
+    /// Reads tokens up to `;` or a closing bracket that is not in the
+    /// expression
+    fn expr(&mut self) -> Result<Expr, Error> {
+        let start = self.pos;
+        let mut depth = 0usize;
+        while let Some(tok) = self.tokens.get(self.pos) {
+            match tok.kind {
+                Kind::OpenParen | Kind::OpenBracket | Kind::OpenBrace => depth += 1,
+                Kind::CloseParen | Kind::CloseBracket | Kind::CloseBrace if depth == 0 => break,
+                Kind::CloseParen | Kind::CloseBracket | Kind::CloseBrace => depth -= 1,
+                Kind::Semicolon if depth == 0 => break,
+                _ => {}
+            }
+            self.pos += 1;
+        }
+        let tokens = &self.tokens[start..self.pos];
+        let (Some(first), Some(last)) = (tokens.first(), tokens.last()) else {
+            return Err(self.expected("an expression"));
+        };
+        Ok(Expr {
+            span: first.span.extend(&last.span),
+            tokens: tokens.to_vec(),
+        })
+    }
+
+    fn skip_statement(&mut self) {
+        let mut depth = 0usize;
+        while let Some(tok) = self.tokens.get(self.pos) {
+            self.pos += 1;
+            match tok.kind {
+                Kind::OpenParen | Kind::OpenBracket | Kind::OpenBrace => depth += 1,
+                Kind::CloseParen | Kind::CloseBracket | Kind::CloseBrace => {
+                    depth = depth.saturating_sub(1)
+                }
+                Kind::Semicolon if depth == 0 => break,
+                _ => {}
+            }
+        }
+    }
+
+    /// Reads an unqualified name, like the name of a role
+    fn short_name(&mut self) -> Result<Name, Error> {
+        if !is_name_like(self.kind(0)) {
+            return Err(self.expected("a name"));
+        }
+        let tok = &self.tokens[self.pos];
+        self.pos += 1;
+        Ok(Name {
+            text: token_name(tok).to_string(),
+            span: tok.span,
+        })
+    }
+
+    /// Reads a possibly qualified name, like the name of a permission
+    #[cfg_attr(not(feature = "future-syntax"), allow(dead_code))]
+    fn name(&mut self) -> Result<Name, Error> {
+        let mut name = self.short_name()?;
+        while self.kind(0) == Some(Kind::Namespace) && is_name_like(self.kind(1)) {
+            let part = &self.tokens[self.pos + 1];
+            name.text.push_str("::");
+            name.text.push_str(token_name(part));
+            name.span = name.span.extend(&part.span);
+            self.pos += 2;
+        }
+        Ok(name)
+    }
+
+    fn names(&mut self, name: fn(&mut Self) -> Result<Name, Error>) -> Result<Vec<Name>, Error> {
+        let mut names = vec![name(self)?];
+        while self.kind(0) == Some(Kind::Comma) {
+            self.pos += 1;
+            names.push(name(self)?);
+        }
+        Ok(names)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_roles, AlterRoleCommand, Position, RoleStatementKind};
+
+    #[test]
+    fn statements() {
+        let text = "
+            create superuser role admin extending base if not exists {
+                set password := 'secret';
+            };
+            select 1;
+            alter role admin {
+                rename to root;
+                extending reader, writer before base;
+                reset password;
+            };
+            alter role guest set permissions := {sys::perm::data_modification};
+            drop role guest;
+        ";
+        let statements = parse_roles(text).unwrap();
+        assert_eq!(statements.len(), 4);
+        let src = |span: crate::position::Span| &text[span.start as usize..span.end as usize];
+
+        let RoleStatementKind::Create {
+            superuser,
+            extending,
+            if_not_exists,
+            fields,
+        } = &statements[0].kind
+        else {
+            panic!("{:?}", statements[0]);
+        };
+        assert!(*superuser && *if_not_exists);
+        assert_eq!(extending[0].text, "base");
+        assert_eq!(fields[0].name, "password");
+        assert_eq!(src(fields[0].value.span), "'secret'");
+
+        let RoleStatementKind::Alter { commands } = &statements[1].kind else {
+            panic!("{:?}", statements[1]);
+        };
+        assert_eq!(statements[1].role.text, "admin");
+        assert!(matches!(&commands[0], AlterRoleCommand::Rename { to } if to.text == "root"));
+        let AlterRoleCommand::Extending { bases, position } = &commands[1] else {
+            panic!("{:?}", commands[1]);
+        };
+        assert_eq!(bases.len(), 2);
+        assert!(matches!(position, Some(Position::Before(name)) if name.text == "base"));
+
+        let RoleStatementKind::Alter { commands } = &statements[2].kind else {
+            panic!("{:?}", statements[2]);
+        };
+        let AlterRoleCommand::Set(field) = &commands[0] else {
+            panic!("{:?}", commands[0]);
+        };
+        assert_eq!(src(field.value.span), "{sys::perm::data_modification}");
+        assert_eq!(statements[3].kind, RoleStatementKind::Drop);
+        assert_eq!(src(statements[3].span), "drop role guest");
+    }
+
+    #[test]
+    fn errors() {
+        let error = |text: &str| parse_roles(text).unwrap_err().message;
+        assert_eq!(
+            error("create role admin { set password 'x' }"),
+            "Expected ':='"
+        );
+        assert_eq!(
+            error("alter role admin { drop owned }"),
+            "Unexpected 'drop'"
+        );
+        assert_eq!(error("drop role"), "Expected a name");
+        if cfg!(feature = "future-syntax") {
+            let statements = parse_roles("grant sys::perm::data_modification to app").unwrap();
+            assert_eq!(statements[0].role.text, "app");
+        } else {
+            assert!(parse_roles("grant data_modification to app")
+                .unwrap()
+                .is_empty());
+        }
+    }
+}
@@ -0,0 +1,311 @@
+//! Injection of row-level filters into queries
+//!
+//! [inject_filters] adds predicates, like `.tenant_id = global
+//! current_tenant` or `not .deleted`, to the main `select` and to every
+//! nested shape over the types they are configured for (and their
+//! subtypes):
+//!
+//! ```text
+//! select User { name, posts: { title } } filter .name = 'x'
+//! select User { name, posts: { title } filter (.tenant = global t) }
+//!     filter (.name = 'x') and (.tenant = global t)
+//! ```
+//!
+//! Types of links are taken from the [Resolver]. Only shapes are rewritten:
+//! subqueries in computed elements and in `with` bindings are kept as is, so
+//! this is a convenience for client-side rules, access policies are the way
+//! to enforce them.
+use crate::expr;
+use crate::pagination::{find_clauses, Clauses};
+use crate::position::Span;
+use crate::resolver::Resolver;
+use crate::shape::{statement, Shape};
+use crate::tokenizer::{Error, Kind, Token, Tokenizer};
+
+/// Predicate applied to objects of a type
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RowFilter {
+    type_name: String,
+    predicate: String,
+}
+
+impl RowFilter {
+    /// Filters objects of the fully-qualified type `type_name` and its
+    /// subtypes, the `predicate` is checked with [expr::check]
+    pub fn new(type_name: &str, predicate: &str) -> Result<RowFilter, expr::Error> {
+        expr::check(predicate)?;
+        Ok(RowFilter {
+            type_name: type_name.to_string(),
+            predicate: predicate.trim().to_string(),
+        })
+    }
+}
+
+/// Adds the predicates of `filters` to the selects over their types
+pub fn inject_filters(
+    query: &str,
+    filters: &[RowFilter],
+    resolver: &dyn Resolver,
+) -> Result<String, Error> {
+    let tokens = Tokenizer::new(query)
+        .validated_values()
+        .map(|t| t.map(|t| t.cloned()))
+        .collect::<Result<Vec<_>, _>>()?;
+    let Some(stmt) = statement(&tokens)? else {
+        return Ok(query.to_string());
+    };
+    let mut injector = Injector {
+        query,
+        filters,
+        resolver,
+        module: stmt.module.as_deref().unwrap_or("default"),
+        edits: Vec::new(),
+    };
+    let subject = stmt
+        .subject
+        .as_ref()
+        .filter(|_| stmt.keyword == "select")
+        .and_then(|s| resolver.resolve(s, injector.module));
+    if let (Some(type_name), Some(subject_span)) = (subject, stmt.subject_span) {
+        let start = tokens
+            .iter()
+            .position(|t| t.span.start == stmt.span.start)
+            .unwrap();
+        let (clauses, end) = find_clauses(&tokens, start + 1);
+        // `select User.friends` or `select User + 1` select something else
+        let subject_end = stmt.shape.as_ref().map_or(subject_span.end, |s| s.span.end);
+        let next = tokens[..end]
+            .iter()
+            .position(|t| t.span.start >= subject_end)
+            .unwrap_or(end);
+        let first_clause = [
+            &clauses.filter,
+            &clauses.order_by,
+            &clauses.offset,
+            &clauses.limit,
+        ]
+        .into_iter()
+        .flatten()
+        .map(|r| r.start)
+        .min()
+        .unwrap_or(end);
+        if next == first_clause {
+            if let Some(predicate) = injector.predicate(&type_name) {
+                injector.add_predicate(&tokens, &clauses, end, subject_end, predicate);
+            }
+            if let Some(shape) = &stmt.shape {
+                injector.shape(&type_name, shape);
+            }
+        }
+    }
+
+    let mut edits = injector.edits;
+    edits.sort_by_key(|(span, _)| span.start);
+    let mut result = String::with_capacity(query.len());
+    let mut pos = 0;
+    for (span, text) in edits {
+        result.push_str(&query[pos..span.start as usize]);
+        result.push_str(&text);
+        pos = span.end as usize;
+    }
+    result.push_str(&query[pos..]);
+    Ok(result)
+}
+
+struct Injector<'a> {
+    query: &'a str,
+    filters: &'a [RowFilter],
+    resolver: &'a dyn Resolver,
+    module: &'a str,
+    /// Replacements of spans of the query, insertions have empty spans
+    edits: Vec<(Span, String)>,
+}
+
+impl Injector<'_> {
+    fn predicate(&self, type_name: &str) -> Option<String> {
+        let ancestors = self.resolver.ancestors(type_name);
+        let predicates = self
+            .filters
+            .iter()
+            .filter(|f| f.type_name == type_name || ancestors.contains(&f.type_name))
+            .map(|f| format!("({})", f.predicate))
+            .collect::<Vec<_>>();
+        (!predicates.is_empty()).then(|| predicates.join(" and "))
+    }
+
+    fn shape(&mut self, type_name: &str, shape: &Shape) {
+        for el in &shape.elements {
+            let Some(nested) = &el.shape else {
+                continue;
+            };
+            if el.link_property || el.expr.is_some() {
+                continue;
+            }
+            let target = if el.backlink {
+                el.type_filter
+                    .as_ref()
+                    .and_then(|t| self.resolver.resolve(t, self.module))
+            } else {
+                let source = match &el.type_filter {
+                    Some(filter) => self.resolver.resolve(filter, self.module),
+                    None => Some(type_name.to_string()),
+                };
+                source
+                    .and_then(|s| self.resolver.pointer(&s, &el.name))
+                    .filter(|p| p.is_link)
+                    .and_then(|p| p.target)
+                    .map(|t| t.name)
+            };
+            let Some(target) = target else {
+                continue;
+            };
+            if let Some(predicate) = self.predicate(&target) {
+                let tokens = el.clauses.as_ref().map_or(&[][..], |c| &c.tokens[..]);
+                let (clauses, end) = find_clauses(tokens, 0);
+                self.add_predicate(tokens, &clauses, end, nested.span.end, predicate);
+            }
+            self.shape(&target, nested);
+        }
+    }
+
+    /// Adds the predicate to the `filter` of the clauses, `end` is the index
+    /// after the last token of the clauses and `subject_end` is the offset to
+    /// append the filter at if there are no tokens
+    fn add_predicate(
+        &mut self,
+        tokens: &[Token],
+        clauses: &Clauses,
+        end: usize,
+        subject_end: u64,
+        predicate: String,
+    ) {
+        if let Some(filter) = clauses.filter.as_ref().filter(|r| r.len() > 1) {
+            let span = tokens[filter.start + 1]
+                .span
+                .extend(&tokens[filter.end - 1].span);
+            let body = &self.query[span.start as usize..span.end as usize];
+            self.edits.push((span, format!("({body}) and {predicate}")));
+            return;
+        }
+        let next = [&clauses.order_by, &clauses.offset, &clauses.limit]
+            .into_iter()
+            .flatten()
+            .map(|r| r.start)
+            .min();
+        let edit = match (next, clauses.filter.as_ref()) {
+            (_, Some(filter)) => (tokens[filter.start].span, format!("filter {predicate}")),
+            (Some(next), None) => (
+                empty_span(tokens[next].span.start),
+                format!("filter {predicate} "),
+            ),
+            (None, None) => {
+                let offset = match end.checked_sub(1) {
+                    Some(last) if tokens[last].kind != Kind::Semicolon => tokens[last].span.end,
+                    _ => subject_end,
+                };
+                (empty_span(offset), format!(" filter {predicate}"))
+            }
+        };
+        self.edits.push(edit);
+    }
+}
+
+fn empty_span(offset: u64) -> Span {
+    Span {
+        start: offset,
+        end: offset,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{inject_filters, RowFilter};
+    use crate::resolver::SchemaResolver;
+    use crate::schema::parse;
+
+    fn inject(query: &str) -> String {
+        let resolver = SchemaResolver::new(
+            &parse(
+                "
+                module default {
+                    abstract type Owned {
+                        required tenant: str;
+                    }
+                    type User extending Owned {
+                        required name: str;
+                        multi friends: User;
+                        multi tags: Tag;
+                    }
+                    type Post extending Owned {
+                        required author: User;
+                        deleted: bool;
+                    }
+                    type Tag {
+                        name: str;
+                    }
+                }
+                ",
+            )
+            .unwrap(),
+        );
+        let filters = [
+            RowFilter::new("default::Owned", ".tenant = global t").unwrap(),
+            RowFilter::new("default::Post", "not .deleted").unwrap(),
+        ];
+        inject_filters(query, &filters, &resolver).unwrap()
+    }
+
+    #[test]
+    fn top_level() {
+        assert_eq!(
+            inject("select User"),
+            "select User filter (.tenant = global t)"
+        );
+        assert_eq!(
+            inject("select User filter .name = 'x' order by .name;"),
+            "select User filter (.name = 'x') and (.tenant = global t) order by .name;"
+        );
+        assert_eq!(
+            inject("with module default select Post { id } limit 1"),
+            "with module default select Post { id } \
+             filter (.tenant = global t) and (not .deleted) limit 1"
+        );
+        // not a select of the type itself
+        assert_eq!(inject("select User.friends"), "select User.friends");
+        assert_eq!(inject("select Tag"), "select Tag");
+        assert_eq!(
+            inject("insert User { name := 'x' }"),
+            "insert User { name := 'x' }"
+        );
+    }
+
+    #[test]
+    fn nested() {
+        assert_eq!(
+            inject(
+                "select Tag { name, \
+                 <tags[is User]: { name, friends: { name } order by .name } }"
+            ),
+            "select Tag { name, \
+             <tags[is User]: { name, friends: { name } filter (.tenant = global t) \
+             order by .name } filter (.tenant = global t) }"
+        );
+        assert_eq!(
+            inject("select Post { author: { tags: { name } } filter .name != '' }"),
+            "select Post { author: { tags: { name } } \
+             filter (.name != '') and (.tenant = global t) } \
+             filter (.tenant = global t) and (not .deleted)"
+        );
+        // computed elements are kept as is
+        assert_eq!(
+            inject("select Tag { users := (select User) }"),
+            "select Tag { users := (select User) }"
+        );
+    }
+
+    #[test]
+    fn invalid_predicate() {
+        assert!(RowFilter::new("default::User", "true; delete User").is_err());
+        assert!(RowFilter::new("default::User", "").is_err());
+    }
+}
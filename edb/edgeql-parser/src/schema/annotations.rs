@@ -0,0 +1,218 @@
+//! Extraction of annotation values
+//!
+//! Collects `annotation name := '...'` of every definition of a document, so
+//! that documentation can be generated right from the schema files.
+use crate::position::Span;
+use crate::schema::{DeclKind, Declaration, Document, Expr, Verb};
+use crate::tokenizer::{Kind, Value};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnnotationKind {
+    /// `std::title`
+    Title,
+    /// `std::description`
+    Description,
+    /// `std::deprecated`
+    Deprecated,
+    /// Annotation declared in the user schema
+    Custom,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Annotation {
+    /// Name as written, e.g. `title` or `default::version`
+    pub name: String,
+    pub kind: AnnotationKind,
+    /// Value of the annotation if it is a string literal or a concatenation
+    /// of string literals, `None` for other expressions
+    pub value: Option<String>,
+    pub expr: Expr,
+    /// Whole `annotation ... := ...` declaration
+    pub span: Span,
+}
+
+/// Annotations attached to a single definition
+#[derive(Debug)]
+pub struct Annotated<'a> {
+    /// Name of the definition, module-qualified if the definition is inside
+    /// of a module block; nested definitions are separated by a dot, e.g.
+    /// `default::User.name`
+    pub name: String,
+    pub decl: &'a Declaration,
+    pub annotations: Vec<Annotation>,
+}
+
+/// Returns annotations of every definition that has any, in the order of
+/// the source. Nested definitions (pointers, constraints on them) are
+/// listed after their parent.
+///
+/// Declarations without a name, like `index on (...)`, are skipped.
+pub fn annotations(doc: &Document) -> Vec<Annotated<'_>> {
+    let mut result = Vec::new();
+    collect(&doc.declarations, None, false, &mut result);
+    result
+}
+
+impl AnnotationKind {
+    fn from_name(name: &str) -> AnnotationKind {
+        match name.strip_prefix("std::").unwrap_or(name) {
+            "title" => AnnotationKind::Title,
+            "description" => AnnotationKind::Description,
+            "deprecated" => AnnotationKind::Deprecated,
+            _ => AnnotationKind::Custom,
+        }
+    }
+}
+
+impl Annotated<'_> {
+    pub fn get(&self, kind: AnnotationKind) -> Option<&Annotation> {
+        self.annotations.iter().find(|a| a.kind == kind)
+    }
+
+    pub fn title(&self) -> Option<&str> {
+        self.get(AnnotationKind::Title)?.value.as_deref()
+    }
+
+    pub fn description(&self) -> Option<&str> {
+        self.get(AnnotationKind::Description)?.value.as_deref()
+    }
+}
+
+fn collect<'a>(
+    decls: &'a [Declaration],
+    prefix: Option<&str>,
+    nested: bool,
+    result: &mut Vec<Annotated<'a>>,
+) {
+    for decl in decls {
+        if decl.kind == DeclKind::Annotation && decl.expr.is_some() {
+            continue;
+        }
+        let Some(name) = decl.name_text() else {
+            continue;
+        };
+        let full_name = match prefix {
+            Some(parent) if nested => format!("{parent}.{name}"),
+            Some(module) if !name.contains("::") => format!("{module}::{name}"),
+            _ => name.to_string(),
+        };
+        if decl.kind == DeclKind::Module {
+            collect(&decl.body, Some(&full_name), false, result);
+            continue;
+        }
+        let annotations: Vec<_> = decl.body.iter().filter_map(annotation).collect();
+        if !annotations.is_empty() {
+            result.push(Annotated {
+                name: full_name.clone(),
+                decl,
+                annotations,
+            });
+        }
+        collect(&decl.body, Some(&full_name), true, result);
+    }
+}
+
+fn annotation(decl: &Declaration) -> Option<Annotation> {
+    if decl.kind != DeclKind::Annotation || decl.verb == Some(Verb::Drop) {
+        return None;
+    }
+    let name = decl.name_text()?;
+    let expr = decl.expr.as_ref()?;
+    Some(Annotation {
+        name: name.to_string(),
+        kind: AnnotationKind::from_name(name),
+        value: string_value(expr),
+        expr: expr.clone(),
+        span: decl.span,
+    })
+}
+
+/// Value of `'a'`, `('a' ++ 'b')` and alike
+fn string_value(expr: &Expr) -> Option<String> {
+    let mut tokens = &expr.tokens[..];
+    while let [first, inner @ .., last] = tokens {
+        if first.kind != Kind::OpenParen || last.kind != Kind::CloseParen {
+            break;
+        }
+        tokens = inner;
+    }
+    let mut value = String::new();
+    for (idx, token) in tokens.iter().enumerate() {
+        match (idx % 2, token.kind, &token.value) {
+            (0, Kind::Str, Some(Value::String(s))) => value.push_str(s),
+            (1, Kind::Concat, _) => {}
+            _ => return None,
+        }
+    }
+    if tokens.len() % 2 == 0 {
+        return None;
+    }
+    Some(value)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{annotations, AnnotationKind};
+    use crate::schema::parse;
+
+    #[test]
+    fn values() {
+        let text = r#"
+            module default {
+                abstract annotation version;
+                type User {
+                    annotation title := 'User';
+                    annotation description := ("A person " ++ r'\o/');
+                    annotation default::version := '1';
+                    required name: str {
+                        annotation std::deprecated := 'use `login`';
+                        constraint exclusive {
+                            annotation description := 'unique';
+                        }
+                    };
+                    index on (.name) {
+                        annotation title := 'skipped';
+                    }
+                }
+                scalar type Age extending int64 {
+                    annotation title := 'Age: ' ++ <str>18;
+                }
+            }
+            type Plain;
+        "#;
+        let doc = parse(text).unwrap();
+        let result = annotations(&doc);
+        let names: Vec<_> = result.iter().map(|a| &a.name[..]).collect();
+        assert_eq!(
+            names,
+            [
+                "default::User",
+                "default::User.name",
+                "default::User.name.exclusive",
+                "default::Age",
+            ]
+        );
+
+        let user = &result[0];
+        assert_eq!(user.title(), Some("User"));
+        assert_eq!(user.description(), Some("A person \\o/"));
+        let version = &user.annotations[2];
+        assert_eq!(version.name, "default::version");
+        assert_eq!(version.kind, AnnotationKind::Custom);
+        assert_eq!(version.value.as_deref(), Some("1"));
+        assert_eq!(
+            &text[version.span.start as usize..version.span.end as usize],
+            "annotation default::version := '1';"
+        );
+
+        let name = &result[1];
+        assert_eq!(name.annotations[0].kind, AnnotationKind::Deprecated);
+        assert_eq!(name.annotations[0].value.as_deref(), Some("use `login`"));
+        assert_eq!(result[2].description(), Some("unique"));
+
+        // not a string literal
+        let age = &result[3].annotations[0];
+        assert_eq!(age.value, None);
+        assert_eq!(age.expr.tokens.len(), 6);
+    }
+}
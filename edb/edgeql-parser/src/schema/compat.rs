@@ -0,0 +1,280 @@
+//! Rewriting of SDL for older server versions
+//!
+//! [downgrade] rewrites constructs that have an older equivalent (pointers
+//! declared without the `link` or `property` keyword) and reports the ones
+//! that have none (e.g. triggers for servers before 3.0). Everything else
+//! is kept as written, including comments and formatting.
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::schema::qualify::qualify;
+use crate::schema::{parse, DeclKind, Declaration, Document};
+use crate::tokenizer::Error;
+
+/// Major and minor version of the server
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Version {
+    pub major: u16,
+    pub minor: u16,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("invalid server version {0:?}, expected `major` or `major.minor`")]
+pub struct VersionError(String);
+
+/// Result of [downgrade]
+#[derive(Debug)]
+pub struct Downgraded {
+    /// Rewritten schema
+    pub text: String,
+    /// Constructs the target version does not support and that could not be
+    /// rewritten
+    pub errors: Vec<Error>,
+}
+
+/// Extensions that are not available in all versions
+const EXTENSIONS: &[(&str, Version)] = &[
+    ("pgvector", Version::new(3, 0)),
+    ("auth", Version::new(4, 0)),
+    ("ai", Version::new(5, 0)),
+];
+
+/// Future behaviors, `using future` itself is available since 3.0
+const FUTURES: &[(&str, Version)] = &[
+    ("nonrecursive_access_policies", Version::new(3, 0)),
+    ("simple_scoping", Version::new(6, 0)),
+    ("warn_old_scoping", Version::new(6, 0)),
+];
+
+const SHORT_POINTERS: Version = Version::new(3, 0);
+
+impl Version {
+    pub const fn new(major: u16, minor: u16) -> Version {
+        Version { major, minor }
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+impl FromStr for Version {
+    type Err = VersionError;
+
+    fn from_str(s: &str) -> Result<Version, VersionError> {
+        let error = || VersionError(s.to_string());
+        let (major, minor) = s.split_once('.').unwrap_or((s, "0"));
+        Ok(Version {
+            major: major.parse().map_err(|_| error())?,
+            minor: minor.parse().map_err(|_| error())?,
+        })
+    }
+}
+
+/// Rewrites the schema so that it can be applied to a server of `target`
+/// version, fails if the text cannot be parsed
+pub fn downgrade(text: &str, target: Version) -> Result<Downgraded, Error> {
+    let doc = qualify(&parse(text)?);
+    let object_types = object_types(&doc);
+
+    let mut inserts = Vec::new();
+    let mut errors = Vec::new();
+    let mut unsupported = |since: Version, message: String, decl: &Declaration| {
+        if since > target {
+            let mut error = Error::new(message).with_span(decl.span);
+            error.hint = Some(format!(
+                "requires server version {since}, target is {target}"
+            ));
+            errors.push(error);
+        }
+    };
+    doc.walk(|decl, parents| match decl.kind {
+        DeclKind::Pointer if target < SHORT_POINTERS => {
+            let Some(name) = &decl.name else {
+                return;
+            };
+            if decl.target.is_empty() {
+                unsupported(
+                    SHORT_POINTERS,
+                    format!(
+                        "computed `{}` must be declared as either a link or a property",
+                        name.text
+                    ),
+                    decl,
+                );
+                return;
+            }
+            let is_link = decl
+                .target
+                .iter()
+                .any(|t| object_types.contains(&t.name.text[..]));
+            let keyword = if is_link { "link " } else { "property " };
+            inserts.push((name.span.start as usize, keyword));
+        }
+        DeclKind::Module if parents.iter().any(|p| p.kind == DeclKind::Module) => {
+            unsupported(
+                Version::new(3, 0),
+                "nested modules are not supported".into(),
+                decl,
+            );
+        }
+        DeclKind::Trigger => {
+            unsupported(
+                Version::new(3, 0),
+                "triggers are not supported".into(),
+                decl,
+            );
+            if target >= Version::new(3, 0) && decl.clause("when").is_some() {
+                unsupported(
+                    Version::new(4, 0),
+                    "conditional triggers (`when`) are not supported".into(),
+                    decl,
+                );
+            }
+        }
+        DeclKind::Rewrite => {
+            unsupported(
+                Version::new(3, 0),
+                "mutation rewrites are not supported".into(),
+                decl,
+            );
+        }
+        DeclKind::Extension => {
+            let Some(name) = decl.name_text() else {
+                return;
+            };
+            if let Some((_, since)) = EXTENSIONS.iter().find(|(ext, _)| *ext == name) {
+                unsupported(*since, format!("extension `{name}` is not available"), decl);
+            }
+        }
+        DeclKind::Future => {
+            let Some(name) = decl.name_text() else {
+                return;
+            };
+            let since = FUTURES
+                .iter()
+                .find(|(future, _)| *future == name)
+                .map_or(Version::new(3, 0), |(_, since)| *since);
+            unsupported(since, format!("future `{name}` is not available"), decl);
+        }
+        _ => {}
+    });
+
+    let mut result = text.to_string();
+    inserts.sort_by(|a, b| b.0.cmp(&a.0));
+    for (offset, keyword) in inserts {
+        result.insert_str(offset, keyword);
+    }
+    Ok(Downgraded {
+        text: result,
+        errors,
+    })
+}
+
+fn object_types(doc: &Document) -> HashSet<String> {
+    let mut types = HashSet::new();
+    doc.walk(|decl, _| {
+        if decl.kind == DeclKind::ObjectType {
+            types.extend(decl.name_text().map(String::from));
+        }
+    });
+    types
+}
+
+#[cfg(test)]
+mod test {
+    use super::{downgrade, Version};
+
+    #[test]
+    fn version() {
+        assert_eq!("3".parse::<Version>().unwrap(), Version::new(3, 0));
+        assert_eq!("4.1".parse::<Version>().unwrap(), Version::new(4, 1));
+        assert!("4.x".parse::<Version>().is_err());
+        assert!(Version::new(2, 9) < Version::new(3, 0));
+    }
+
+    #[test]
+    fn short_pointers() {
+        let text = "
+            module default {
+                type User {
+                    required name: str;
+                    multi friends: User {
+                        since: datetime;
+                    };
+                    # keeps comments
+                    property age: int64;
+                    tags: array<str>;
+                }
+            }
+        ";
+        let result = downgrade(text, Version::new(2, 0)).unwrap();
+        assert!(result.errors.is_empty());
+        assert_eq!(
+            result.text,
+            "
+            module default {
+                type User {
+                    required property name: str;
+                    multi link friends: User {
+                        property since: datetime;
+                    };
+                    # keeps comments
+                    property age: int64;
+                    property tags: array<str>;
+                }
+            }
+        "
+        );
+        // supported as is
+        assert_eq!(downgrade(text, Version::new(3, 0)).unwrap().text, text);
+    }
+
+    #[test]
+    fn incompatible() {
+        let text = "
+            using extension auth;
+            using future nonrecursive_access_policies;
+            module default {
+                module nested {}
+                type User {
+                    name: str {
+                        rewrite insert using ('x');
+                    };
+                    upper := str_upper(.name);
+                    trigger log after insert for each when (true) do (select 1);
+                }
+            }
+        ";
+        let messages = |version| {
+            downgrade(text, version)
+                .unwrap()
+                .errors
+                .into_iter()
+                .map(|e| e.message)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            messages(Version::new(2, 0)),
+            [
+                "extension `auth` is not available",
+                "future `nonrecursive_access_policies` is not available",
+                "nested modules are not supported",
+                "mutation rewrites are not supported",
+                "computed `upper` must be declared as either a link or a property",
+                "triggers are not supported",
+            ]
+        );
+        assert_eq!(
+            messages(Version::new(3, 1)),
+            [
+                "extension `auth` is not available",
+                "conditional triggers (`when`) are not supported",
+            ]
+        );
+        assert!(messages(Version::new(4, 0)).is_empty());
+    }
+}
@@ -0,0 +1,286 @@
+//! Local checks of constraint declarations
+//!
+//! Catches mistakes the server would reject when applying the schema:
+//!
+//! * `__subject__` used outside of constraints, indexes and rewrites
+//! * constraint expressions calling functions that are not immutable, or
+//!   referring to globals
+//! * `constraint expression` without an `on (...)` clause
+//! * `delegated` used anywhere but on constraints of abstract types
+use std::collections::HashMap;
+
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::keywords::Keyword;
+use crate::position::Span;
+use crate::schema::qualify::qualify;
+use crate::schema::{token_name, DeclKind, Declaration, Document, Expr};
+use crate::signature_help::function_name_start;
+use crate::tokenizer::{Kind, Token, Value};
+
+/// Functions of the standard library that are not immutable
+const NOT_IMMUTABLE: &[(&str, &str)] = &[
+    ("datetime_current", "volatile"),
+    ("datetime_of_statement", "stable"),
+    ("datetime_of_transaction", "stable"),
+    ("random", "volatile"),
+    ("sequence_next", "volatile"),
+    ("sequence_reset", "volatile"),
+    ("uuid_generate_v1mc", "volatile"),
+    ("uuid_generate_v4", "volatile"),
+    ("sys::get_current_branch", "stable"),
+    ("sys::get_current_database", "stable"),
+    ("sys::get_version", "stable"),
+    ("sys::get_version_as_str", "stable"),
+];
+
+/// Returns errors in constraint declarations of the document
+pub fn check(doc: &Document) -> Vec<Diagnostic> {
+    let doc = qualify(doc);
+    let mut volatility = HashMap::new();
+    collect_functions(&doc.declarations, &mut volatility);
+
+    let mut errors = Vec::new();
+    doc.walk(|decl, parents| {
+        let in_constraint = decl.kind == DeclKind::Constraint
+            || parents.iter().any(|p| p.kind == DeclKind::Constraint);
+        let subject_allowed = in_constraint
+            || matches!(decl.kind, DeclKind::Index | DeclKind::Rewrite)
+            || parents
+                .iter()
+                .any(|p| matches!(p.kind, DeclKind::Index | DeclKind::Rewrite));
+        for expr in expressions(decl) {
+            if !subject_allowed {
+                check_subject(expr, &mut errors);
+            }
+            if in_constraint {
+                check_immutable(expr, &volatility, &mut errors);
+            }
+        }
+        if decl.kind == DeclKind::Constraint {
+            check_constraint(decl, parents, &mut errors);
+        } else if let Some(modifier) = decl.modifiers.iter().find(|m| m.keyword == "delegated") {
+            errors.push(error("delegated-not-constraint", &[], modifier.span));
+        }
+    });
+    errors
+}
+
+fn expressions(decl: &Declaration) -> impl Iterator<Item = &Expr> {
+    decl.expr
+        .iter()
+        .chain(&decl.args)
+        .chain(decl.clauses.iter().map(|c| &c.expr))
+}
+
+/// Collects declared volatility of functions that are not immutable
+fn collect_functions(decls: &[Declaration], volatility: &mut HashMap<String, String>) {
+    for decl in decls {
+        match decl.kind {
+            DeclKind::Module => collect_functions(&decl.body, volatility),
+            DeclKind::Function => {
+                let Some(name) = decl.name_text() else {
+                    continue;
+                };
+                let declared = decl
+                    .body
+                    .iter()
+                    .filter(|d| d.name_text() == Some("volatility"))
+                    .find_map(|d| match &d.expr.as_ref()?.tokens[..] {
+                        [Token {
+                            value: Some(Value::String(value)),
+                            kind: Kind::Str,
+                            ..
+                        }] => Some(value.to_lowercase()),
+                        _ => None,
+                    });
+                if let Some(declared) = declared.filter(|v| v != "immutable") {
+                    volatility.insert(name.to_string(), declared);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn check_subject(expr: &Expr, errors: &mut Vec<Diagnostic>) {
+    for tok in &expr.tokens {
+        if tok.kind == Kind::Keyword(Keyword("__subject__")) {
+            errors.push(error("misplaced-subject", &[], tok.span));
+        }
+    }
+}
+
+fn check_immutable(
+    expr: &Expr,
+    volatility: &HashMap<String, String>,
+    errors: &mut Vec<Diagnostic>,
+) {
+    let tokens = &expr.tokens;
+    for (idx, tok) in tokens.iter().enumerate() {
+        if tok.kind == Kind::Keyword(Keyword("global")) {
+            errors.push(error("global-in-constraint", &[], tok.span).with_hint());
+            continue;
+        }
+        if tok.kind != Kind::OpenParen {
+            continue;
+        }
+        let Some(start) = function_name_start(tokens, idx) else {
+            continue;
+        };
+        let name = tokens[start..idx]
+            .iter()
+            .map(|t| match t.kind {
+                Kind::Namespace => "::",
+                _ => token_name(t),
+            })
+            .collect::<String>();
+        let short = name.strip_prefix("std::").unwrap_or(&name);
+        let found = NOT_IMMUTABLE
+            .iter()
+            .find(|(f, _)| *f == short)
+            .map(|(_, v)| *v)
+            .or_else(|| volatility.get(&name).map(|v| &v[..]));
+        if let Some(found) = found {
+            let span = tokens[start].span.extend(&tokens[idx - 1].span);
+            let args = [("name", name.clone()), ("volatility", found.into())];
+            errors.push(error("mutable-function-in-constraint", &args, span).with_hint());
+        }
+    }
+}
+
+fn check_constraint(decl: &Declaration, parents: &[&Declaration], errors: &mut Vec<Diagnostic>) {
+    let concrete = !decl.has_modifier("abstract");
+    if concrete && decl.name_text() == Some("expression") && decl.clause("on").is_none() {
+        let span = decl.name.as_ref().map_or(decl.span, |n| n.span);
+        errors.push(error("expression-without-on", &[], span));
+    }
+    let Some(delegated) = decl.modifiers.iter().find(|m| m.keyword == "delegated") else {
+        return;
+    };
+    let object_type = parents
+        .iter()
+        .rev()
+        .find(|p| p.kind == DeclKind::ObjectType);
+    let code = if !concrete {
+        "abstract-delegated"
+    } else if object_type.is_some_and(|t| t.has_modifier("abstract")) {
+        return;
+    } else {
+        "delegated-in-concrete-type"
+    };
+    errors.push(error(code, &[], delegated.span));
+}
+
+fn error(code: &str, args: &[(&str, String)], span: Span) -> Diagnostic {
+    Diagnostic::with_code(Severity::Error, code, args, span)
+}
+
+#[cfg(test)]
+mod test {
+    use super::check;
+    use crate::schema::parse;
+
+    fn errors(text: &str) -> Vec<String> {
+        check(&parse(text).unwrap())
+            .into_iter()
+            .map(|e| {
+                format!(
+                    "{}: {}",
+                    e.message,
+                    &text[e.span.start as usize..e.span.end as usize]
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn subject() {
+        assert!(errors(
+            "
+            abstract constraint positive {
+                using (__subject__ > 0);
+            }
+            type User {
+                age: int64 { constraint expression on (__subject__ < 200) };
+                index on (__subject__.age);
+                rewrite insert using (__subject__.age ?? 0);
+            }
+            "
+        )
+        .is_empty());
+        assert_eq!(
+            errors("type User { age: int64; property x := __subject__.age; }"),
+            ["`__subject__` can only be used in constraints, indexes and rewrites: __subject__"]
+        );
+    }
+
+    #[test]
+    fn immutable() {
+        assert_eq!(
+            errors(
+                "
+                module default {
+                    function now() -> datetime {
+                        volatility := 'Stable';
+                        using (datetime_of_statement());
+                    }
+                    function double(x: int64) -> int64 using (x * 2);
+                    type Event {
+                        at: datetime {
+                            constraint expression on (__subject__ < std::datetime_current());
+                            constraint max_value(now());
+                        };
+                        size: int64 {
+                            constraint expression on (double(__subject__) < 10);
+                            constraint expression on (__subject__ < global limit);
+                        };
+                    }
+                }
+                "
+            ),
+            [
+                "function `std::datetime_current` is volatile \
+                 and cannot be used in constraints: std::datetime_current",
+                "function `default::now` is stable and cannot be used in constraints: now",
+                "globals cannot be used in constraints: global",
+            ]
+        );
+    }
+
+    #[test]
+    fn expression_on() {
+        assert_eq!(
+            errors("type User { name: str { constraint expression; } }"),
+            ["`constraint expression` requires an `on (...)` clause: expression"]
+        );
+    }
+
+    #[test]
+    fn delegated() {
+        assert!(errors(
+            "
+            abstract type Named {
+                required name: str { delegated constraint exclusive; };
+                delegated constraint exclusive on (.name);
+            }
+            "
+        )
+        .is_empty());
+        assert_eq!(
+            errors(
+                "
+                type User {
+                    required name: str { delegated constraint exclusive; };
+                    delegated property x: str;
+                }
+                abstract delegated constraint c;
+                "
+            ),
+            [
+                "delegated constraints are only valid in abstract types: delegated",
+                "`delegated` is only valid for constraints: delegated",
+                "abstract constraints cannot be delegated: delegated",
+            ]
+        );
+    }
+}
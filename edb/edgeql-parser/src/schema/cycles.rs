@@ -0,0 +1,380 @@
+//! Detection of definitions that depend on themselves
+//!
+//! Finds inheritance cycles (`type A extending B; type B extending A;`),
+//! computed pointers referring to each other inside of an object type and
+//! aliases or computed globals defined in terms of each other.
+use std::collections::VecDeque;
+
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::position::Span;
+use crate::schema::deps::{DependencyGraph, DependencyKind};
+use crate::schema::{is_name_like, token_name, DeclKind, Declaration, Document};
+use crate::tokenizer::{Kind, Token};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CycleKind {
+    Inheritance,
+    ComputedPointer,
+    ComputedDefinition,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cycle {
+    pub kind: CycleKind,
+    /// Every step of the cycle. The last step refers back to the first one.
+    pub path: Vec<CycleStep>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CycleStep {
+    pub name: String,
+    /// Span of the reference to the name of the next step
+    pub span: Span,
+}
+
+impl Cycle {
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        let code = match self.kind {
+            CycleKind::Inheritance => "recursive-inheritance",
+            CycleKind::ComputedPointer => "recursive-computed-pointer",
+            CycleKind::ComputedDefinition => "recursive-definition",
+        };
+        let mut path: Vec<&str> = self.path.iter().map(|s| &s.name[..]).collect();
+        path.push(&self.path[0].name);
+        Diagnostic::with_code(
+            Severity::Error,
+            code,
+            &[
+                ("name", self.path[0].name.clone()),
+                ("path", path.join(" -> ")),
+            ],
+            self.path[0].span,
+        )
+        .with_details()
+    }
+}
+
+/// Returns all cycles in the document
+pub fn find_cycles(doc: &Document) -> Vec<Cycle> {
+    let graph = DependencyGraph::build(doc);
+    let mut cycles = Vec::new();
+
+    let inheritance = adjacency(&graph, |dep_kind, _| dep_kind == DependencyKind::Extends);
+    collect_cycles(&inheritance, CycleKind::Inheritance, &mut cycles, |i| {
+        graph.definitions()[i].name.clone()
+    });
+
+    let computed = adjacency(&graph, |dep_kind, target| {
+        dep_kind != DependencyKind::Extends
+            && matches!(target.kind, DeclKind::Alias | DeclKind::Global)
+    });
+    let computed: Vec<_> = computed
+        .into_iter()
+        .enumerate()
+        .map(|(i, edges)| {
+            let src = graph.definitions()[i].decl;
+            if matches!(src.kind, DeclKind::Alias | DeclKind::Global) {
+                edges
+            } else {
+                Vec::new()
+            }
+        })
+        .collect();
+    collect_cycles(&computed, CycleKind::ComputedDefinition, &mut cycles, |i| {
+        graph.definitions()[i].name.clone()
+    });
+
+    for def in graph.definitions() {
+        if def.decl.kind == DeclKind::ObjectType {
+            pointer_cycles(&def.name, def.decl, &mut cycles);
+        }
+    }
+    cycles
+}
+
+/// Returns errors for all cycles in the document
+pub fn check(doc: &Document) -> Vec<Diagnostic> {
+    find_cycles(doc).iter().map(Cycle::to_diagnostic).collect()
+}
+
+type Adjacency = Vec<Vec<(usize, Span)>>;
+
+fn adjacency(
+    graph: &DependencyGraph,
+    filter: impl Fn(DependencyKind, &Declaration) -> bool,
+) -> Adjacency {
+    (0..graph.definitions().len())
+        .map(|i| {
+            graph
+                .dependencies(i)
+                .iter()
+                .filter(|d| filter(d.kind, graph.definitions()[d.target].decl))
+                .map(|d| (d.target, d.span))
+                .collect()
+        })
+        .collect()
+}
+
+fn pointer_cycles(type_name: &str, decl: &Declaration, cycles: &mut Vec<Cycle>) {
+    let pointers: Vec<&Declaration> = decl.body.iter().filter(|d| d.is_pointer()).collect();
+    let find = |name: &str| pointers.iter().position(|p| p.name_text() == Some(name));
+
+    let adj: Adjacency = pointers
+        .iter()
+        .map(|ptr| {
+            let Some(expr) = &ptr.expr else {
+                return Vec::new();
+            };
+            own_pointer_refs(&expr.tokens)
+                .into_iter()
+                .filter_map(|(name, span)| Some((find(name)?, span)))
+                .collect()
+        })
+        .collect();
+    collect_cycles(&adj, CycleKind::ComputedPointer, cycles, |i| {
+        format!(
+            "{type_name}.{}",
+            pointers[i].name_text().unwrap_or_default()
+        )
+    });
+}
+
+/// Finds references to pointers of the enclosing type: `.name` that
+/// starts a path or `__source__.name`.
+fn own_pointer_refs<'t>(tokens: &'t [Token]) -> Vec<(&'t str, Span)> {
+    let mut refs = Vec::new();
+    for (i, tok) in tokens.iter().enumerate() {
+        if tok.kind != Kind::Dot {
+            continue;
+        }
+        let starts_path = match i.checked_sub(1).map(|p| &tokens[p]) {
+            None => true,
+            Some(prev) => match prev.kind {
+                Kind::Keyword(kw) => kw.0 == "__source__" || kw.is_reserved(),
+                Kind::Ident
+                | Kind::CloseParen
+                | Kind::CloseBracket
+                | Kind::CloseBrace
+                | Kind::IntConst
+                | Kind::Str
+                | Kind::Parameter => false,
+                _ => true,
+            },
+        };
+        if !starts_path {
+            continue;
+        }
+        if let Some(next) = tokens.get(i + 1) {
+            if is_name_like(Some(next.kind)) {
+                refs.push((token_name(next), next.span));
+            }
+        }
+    }
+    refs
+}
+
+fn collect_cycles(
+    adj: &Adjacency,
+    kind: CycleKind,
+    cycles: &mut Vec<Cycle>,
+    name: impl Fn(usize) -> String,
+) {
+    for component in strongly_connected(adj) {
+        let start = component[0];
+        let is_cycle = component.len() > 1 || adj[start].iter().any(|(t, _)| *t == start);
+        if !is_cycle {
+            continue;
+        }
+        let path = shortest_cycle(adj, start, &component);
+        cycles.push(Cycle {
+            kind,
+            path: path
+                .into_iter()
+                .map(|(node, span)| CycleStep {
+                    name: name(node),
+                    span,
+                })
+                .collect(),
+        });
+    }
+}
+
+/// Tarjan's algorithm. Components are sorted by their smallest node, and
+/// nodes in each component are sorted too.
+fn strongly_connected(adj: &Adjacency) -> Vec<Vec<usize>> {
+    struct State<'a> {
+        adj: &'a Adjacency,
+        index: Vec<Option<usize>>,
+        lowlink: Vec<usize>,
+        on_stack: Vec<bool>,
+        stack: Vec<usize>,
+        next_index: usize,
+        components: Vec<Vec<usize>>,
+    }
+
+    fn visit(st: &mut State, v: usize) {
+        st.index[v] = Some(st.next_index);
+        st.lowlink[v] = st.next_index;
+        st.next_index += 1;
+        st.stack.push(v);
+        st.on_stack[v] = true;
+
+        for &(w, _) in &st.adj[v] {
+            match st.index[w] {
+                None => {
+                    visit(st, w);
+                    st.lowlink[v] = st.lowlink[v].min(st.lowlink[w]);
+                }
+                Some(w_index) if st.on_stack[w] => {
+                    st.lowlink[v] = st.lowlink[v].min(w_index);
+                }
+                Some(_) => {}
+            }
+        }
+
+        if Some(st.lowlink[v]) == st.index[v] {
+            let mut component = Vec::new();
+            loop {
+                let w = st.stack.pop().unwrap();
+                st.on_stack[w] = false;
+                component.push(w);
+                if w == v {
+                    break;
+                }
+            }
+            component.sort();
+            st.components.push(component);
+        }
+    }
+
+    let count = adj.len();
+    let mut st = State {
+        adj,
+        index: vec![None; count],
+        lowlink: vec![0; count],
+        on_stack: vec![false; count],
+        stack: Vec::new(),
+        next_index: 0,
+        components: Vec::new(),
+    };
+    for v in 0..count {
+        if st.index[v].is_none() {
+            visit(&mut st, v);
+        }
+    }
+    st.components.sort_by_key(|c| c[0]);
+    st.components
+}
+
+/// Breadth-first search for the shortest path from `start` back to itself,
+/// staying within the component.
+fn shortest_cycle(adj: &Adjacency, start: usize, component: &[usize]) -> Vec<(usize, Span)> {
+    let mut came_from: Vec<Option<(usize, Span)>> = vec![None; adj.len()];
+    let mut queue = VecDeque::from([start]);
+    let mut last = None;
+    'search: while let Some(v) = queue.pop_front() {
+        for &(w, span) in &adj[v] {
+            if component.binary_search(&w).is_err() {
+                continue;
+            }
+            if w == start {
+                last = Some((v, span));
+                break 'search;
+            }
+            if came_from[w].is_none() {
+                came_from[w] = Some((v, span));
+                queue.push_back(w);
+            }
+        }
+    }
+
+    // walk back from the node that refers to `start`
+    let (mut node, mut span) = last.expect("component is a cycle");
+    let mut path = Vec::new();
+    loop {
+        path.push((node, span));
+        if node == start {
+            break;
+        }
+        let (prev, prev_span) = came_from[node].unwrap();
+        node = prev;
+        span = prev_span;
+    }
+    path.reverse();
+    path
+}
+
+#[cfg(test)]
+mod test {
+    use super::{check, find_cycles, CycleKind};
+    use crate::schema::parse;
+
+    fn cycles(text: &str) -> Vec<(CycleKind, Vec<String>)> {
+        let doc = parse(text).unwrap();
+        find_cycles(&doc)
+            .into_iter()
+            .map(|c| (c.kind, c.path.into_iter().map(|s| s.name).collect()))
+            .collect()
+    }
+
+    #[test]
+    fn inheritance() {
+        assert_eq!(
+            cycles("type A extending C; type B extending A; type C extending B;"),
+            [(
+                CycleKind::Inheritance,
+                vec!["A".into(), "C".into(), "B".into()]
+            )]
+        );
+        assert_eq!(
+            cycles("type A extending A;"),
+            [(CycleKind::Inheritance, vec!["A".into()])]
+        );
+        assert!(cycles("type A; type B extending A; type C extending A, B;").is_empty());
+    }
+
+    #[test]
+    fn computed_pointers() {
+        assert_eq!(
+            cycles(
+                "
+                type User {
+                    name: str;
+                    property a := .b ++ .name;
+                    property b := (select __source__.a);
+                    property c := .name;
+                }
+                "
+            ),
+            [(
+                CycleKind::ComputedPointer,
+                vec!["User.a".into(), "User.b".into()]
+            )]
+        );
+        assert!(cycles("type User { property a := .friend.a; link friend: User; }").is_empty());
+    }
+
+    #[test]
+    fn aliases() {
+        assert_eq!(
+            cycles("alias A := B { x }; alias B := (select A); type C { link a := A; }"),
+            [(CycleKind::ComputedDefinition, vec!["A".into(), "B".into()])]
+        );
+    }
+
+    #[test]
+    fn error() {
+        let text = "type A extending B;\ntype B extending A;";
+        let errors = check(&parse(text).unwrap());
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].message, "A is defined recursively");
+        assert_eq!(
+            errors[0].details.as_deref(),
+            Some("inheritance cycle: A -> B -> A")
+        );
+        assert_eq!(
+            &text[errors[0].span.start as usize..errors[0].span.end as usize],
+            "B"
+        );
+    }
+}
@@ -0,0 +1,359 @@
+//! Dependency graph between schema definitions
+//!
+//! Used to find out in which order definitions must be created, e.g. when
+//! generating DDL from SDL.
+use std::collections::{BTreeSet, HashMap};
+
+use crate::position::Span;
+use crate::schema::{is_name_like, token_name, DeclKind, Declaration, Document, Expr, TypeRef};
+use crate::tokenizer::{Kind, Token};
+
+/// A top-level schema definition, which is a node of the graph
+#[derive(Debug)]
+pub struct Definition<'a> {
+    /// Fully-qualified name, if definition is inside of a module block.
+    pub name: String,
+    pub module: Option<String>,
+    pub decl: &'a Declaration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DependencyKind {
+    /// Definition is listed in `extending`
+    Extends,
+    /// Target of a pointer, alias or function (including nested pointers)
+    Target,
+    /// Abstract constraint or annotation used by name
+    Uses,
+    /// Referenced in a computed, default or constraint expression
+    Expression,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Dependency {
+    /// Index of the definition depended upon
+    pub target: usize,
+    pub kind: DependencyKind,
+    /// Where the reference is in the source
+    pub span: Span,
+}
+
+#[derive(Debug)]
+pub struct DependencyGraph<'a> {
+    definitions: Vec<Definition<'a>>,
+    dependencies: Vec<Vec<Dependency>>,
+    index: HashMap<String, usize>,
+}
+
+/// Definitions which could not be ordered because they depend on each other
+#[derive(Debug, thiserror::Error)]
+#[error("dependency cycle between {}", .names.join(", "))]
+pub struct CycleError {
+    pub names: Vec<String>,
+}
+
+impl<'a> DependencyGraph<'a> {
+    pub fn build(doc: &'a Document) -> DependencyGraph<'a> {
+        let mut graph = DependencyGraph {
+            definitions: Vec::new(),
+            dependencies: Vec::new(),
+            index: HashMap::new(),
+        };
+        graph.collect(&doc.declarations, None);
+
+        for i in 0..graph.definitions.len() {
+            let mut refs = Vec::new();
+            collect_references(graph.definitions[i].decl, true, &mut refs);
+
+            let module = graph.definitions[i].module.as_deref();
+            let mut deps: Vec<Dependency> = Vec::new();
+            for (name, kind, span) in refs {
+                let Some(target) = graph.resolve(&name, module) else {
+                    continue;
+                };
+                // pointers to own type are not a dependency
+                if target == i && kind != DependencyKind::Extends {
+                    continue;
+                }
+                if !deps.iter().any(|d| d.target == target && d.kind == kind) {
+                    deps.push(Dependency { target, kind, span });
+                }
+            }
+            graph.dependencies.push(deps);
+        }
+        graph
+    }
+
+    fn collect(&mut self, decls: &'a [Declaration], module: Option<&str>) {
+        for decl in decls {
+            let Some(name) = decl.name_text() else {
+                continue;
+            };
+            let full_name = match module {
+                Some(module) if !name.contains("::") => format!("{module}::{name}"),
+                _ => name.to_string(),
+            };
+            if decl.kind == DeclKind::Module {
+                self.collect(&decl.body, Some(&full_name));
+            } else if decl.kind.is_schema_object() {
+                self.index
+                    .entry(full_name.clone())
+                    .or_insert(self.definitions.len());
+                self.definitions.push(Definition {
+                    name: full_name,
+                    module: module.map(|m| m.to_string()),
+                    decl,
+                });
+            }
+        }
+    }
+
+    /// Resolves a name as it is written inside of the `module`
+    pub fn resolve(&self, name: &str, module: Option<&str>) -> Option<usize> {
+        if let Some(module) = module {
+            if let Some(idx) = self.index.get(&format!("{module}::{name}")) {
+                return Some(*idx);
+            }
+        }
+        self.index.get(name).copied()
+    }
+
+    pub fn definitions(&self) -> &[Definition<'a>] {
+        &self.definitions
+    }
+
+    pub fn find(&self, name: &str) -> Option<&Definition<'a>> {
+        self.index.get(name).map(|idx| &self.definitions[*idx])
+    }
+
+    /// Direct dependencies of the definition with index `idx`
+    pub fn dependencies(&self, idx: usize) -> &[Dependency] {
+        &self.dependencies[idx]
+    }
+
+    /// Returns definitions ordered so that every definition comes after all
+    /// of its dependencies. Definitions that are independent of each other
+    /// keep their order from the source.
+    pub fn topological_order(&self) -> Result<Vec<&Definition<'a>>, CycleError> {
+        self.topological_order_by(|_| true)
+    }
+
+    /// Same as [DependencyGraph::topological_order], but only considers
+    /// dependencies for which `filter` returns true.
+    pub fn topological_order_by(
+        &self,
+        filter: impl Fn(&Dependency) -> bool,
+    ) -> Result<Vec<&Definition<'a>>, CycleError> {
+        let count = self.definitions.len();
+        let mut pending = vec![0usize; count];
+        let mut dependants = vec![Vec::new(); count];
+        for (idx, deps) in self.dependencies.iter().enumerate() {
+            for dep in deps.iter().filter(|d| filter(d)) {
+                pending[idx] += 1;
+                dependants[dep.target].push(idx);
+            }
+        }
+
+        let mut ready: BTreeSet<usize> = (0..count).filter(|i| pending[*i] == 0).collect();
+        let mut result = Vec::with_capacity(count);
+        while let Some(idx) = ready.pop_first() {
+            result.push(&self.definitions[idx]);
+            for &dependant in &dependants[idx] {
+                pending[dependant] -= 1;
+                if pending[dependant] == 0 {
+                    ready.insert(dependant);
+                }
+            }
+        }
+
+        if result.len() < count {
+            let names = (0..count)
+                .filter(|i| pending[*i] > 0)
+                .map(|i| self.definitions[i].name.clone())
+                .collect();
+            return Err(CycleError { names });
+        }
+        Ok(result)
+    }
+}
+
+fn collect_references(
+    decl: &Declaration,
+    top_level: bool,
+    refs: &mut Vec<(String, DependencyKind, Span)>,
+) {
+    use DependencyKind::*;
+
+    // inside of a type, `extending` of a pointer refers to abstract pointers
+    for ty in &decl.extending {
+        type_references(ty, Extends, refs);
+    }
+    for ty in &decl.target {
+        type_references(ty, Target, refs);
+    }
+    if !top_level && matches!(decl.kind, DeclKind::Constraint | DeclKind::Annotation) {
+        if let Some(name) = &decl.name {
+            refs.push((name.text.clone(), Uses, name.span));
+        }
+    }
+    let exprs = decl
+        .expr
+        .iter()
+        .chain(decl.args.iter())
+        .chain(decl.clauses.iter().map(|c| &c.expr));
+    for expr in exprs {
+        expr_references(expr, refs);
+    }
+    for child in &decl.body {
+        collect_references(child, false, refs);
+    }
+}
+
+fn type_references(
+    ty: &TypeRef,
+    kind: DependencyKind,
+    refs: &mut Vec<(String, DependencyKind, Span)>,
+) {
+    refs.push((ty.name.text.clone(), kind, ty.name.span));
+    for arg in &ty.args {
+        type_references(arg, kind, refs);
+    }
+}
+
+fn expr_references(expr: &Expr, refs: &mut Vec<(String, DependencyKind, Span)>) {
+    for (name, span) in expr_names(&expr.tokens) {
+        refs.push((name, DependencyKind::Expression, span));
+    }
+}
+
+/// Finds all possibly qualified names in expression tokens, which are not
+/// path steps (`.name`), shape elements (`name := `) or parameter names
+/// (`name: type`).
+pub(crate) fn expr_names(tokens: &[Token]) -> Vec<(String, Span)> {
+    let mut names = Vec::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        let tok = &tokens[i];
+        let after_step = i > 0
+            && matches!(
+                tokens[i - 1].kind,
+                Kind::Dot | Kind::BackwardLink | Kind::OptionalLink | Kind::At
+            );
+        if !is_name_like(Some(tok.kind)) || after_step {
+            i += 1;
+            continue;
+        }
+
+        let mut name = token_name(tok).to_string();
+        let mut span = tok.span;
+        i += 1;
+        while i + 1 < tokens.len()
+            && tokens[i].kind == Kind::Namespace
+            && is_name_like(Some(tokens[i + 1].kind))
+        {
+            name.push_str("::");
+            name.push_str(token_name(&tokens[i + 1]));
+            span = span.extend(&tokens[i + 1].span);
+            i += 2;
+        }
+        let is_label = matches!(
+            tokens.get(i).map(|t| t.kind),
+            Some(Kind::Assign | Kind::Colon)
+        );
+        if !is_label {
+            names.push((name, span));
+        }
+    }
+    names
+}
+
+#[cfg(test)]
+mod test {
+    use super::{DependencyGraph, DependencyKind};
+    use crate::schema::parse;
+
+    fn order(text: &str) -> Vec<String> {
+        let doc = parse(text).unwrap();
+        let graph = DependencyGraph::build(&doc);
+        graph
+            .topological_order()
+            .unwrap()
+            .into_iter()
+            .map(|d| d.name.clone())
+            .collect()
+    }
+
+    #[test]
+    fn extending() {
+        assert_eq!(
+            order(
+                "
+                type B extending A;
+                type C extending B, A;
+                abstract type A;
+                "
+            ),
+            ["A", "B", "C"]
+        );
+    }
+
+    #[test]
+    fn modules() {
+        assert_eq!(
+            order(
+                "
+                module default {
+                    type User {
+                        required link profile -> other::Profile;
+                    }
+                }
+                module other {
+                    type Profile {
+                        property name: Tag;
+                    }
+                    scalar type Tag extending str;
+                }
+                "
+            ),
+            ["other::Tag", "other::Profile", "default::User"]
+        );
+    }
+
+    #[test]
+    fn expressions() {
+        let doc = parse(
+            "
+            type Post {
+                multi link authors := (select User filter .posts = Post);
+                constraint exclusive on (.title);
+            }
+            type User {
+                property posts := .<author[is Post];
+                index on (.name);
+            }
+            alias Admins := User { name };
+            ",
+        )
+        .unwrap();
+        let graph = DependencyGraph::build(&doc);
+        let post = &graph.dependencies(0);
+        assert_eq!(post.len(), 1);
+        assert_eq!(post[0].kind, DependencyKind::Expression);
+        assert_eq!(graph.definitions()[post[0].target].name, "User");
+
+        assert!(graph.topological_order().is_err());
+        let order = graph
+            .topological_order_by(|d| d.kind != DependencyKind::Expression)
+            .unwrap();
+        let names: Vec<_> = order.iter().map(|d| &d.name[..]).collect();
+        assert_eq!(names, ["Post", "User", "Admins"]);
+    }
+
+    #[test]
+    fn cycle() {
+        let doc = parse("type A extending B; type B extending A; type C;").unwrap();
+        let graph = DependencyGraph::build(&doc);
+        let err = graph.topological_order().unwrap_err();
+        assert_eq!(err.to_string(), "dependency cycle between A, B");
+    }
+}
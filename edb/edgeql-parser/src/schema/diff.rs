@@ -0,0 +1,380 @@
+//! Structural differences between two versions of a schema
+//!
+//! [diff_ast] matches declarations of both documents by their path (like
+//! `default::User.name`), so formatting and ordering do not matter. Names
+//! are module-qualified first, so `type User` in `module default` and
+//! `type default::User` are the same declaration. Module blocks are only
+//! containers and are not reported themselves.
+//!
+//! A declaration that disappeared from one place and appeared in another
+//! one with the same kind and name, like a type moved to another module or
+//! a property moved to another type, is reported as [ChangeKind::Moved].
+//! Only the outermost added or removed declaration is reported, not every
+//! declaration inside of it.
+use std::collections::HashMap;
+
+use crate::position::Span;
+use crate::schema::qualify::qualify;
+use crate::schema::{DeclKind, Declaration, Document, Expr, TypeRef};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    /// The declaration itself changed (e.g. target type or modifiers), but
+    /// not necessarily its body
+    Changed,
+    /// The declaration has a different parent, a move may be followed by
+    /// a [ChangeKind::Changed] of the same declaration
+    Moved,
+}
+
+#[derive(Debug, Clone)]
+pub struct Change {
+    pub kind: ChangeKind,
+    /// Path of the declaration in the new document, or in the old one for
+    /// [ChangeKind::Removed]
+    pub path: String,
+    /// Path in the old document for [ChangeKind::Moved]
+    pub old_path: Option<String>,
+    /// Span of the declaration in the old text
+    pub old: Option<Span>,
+    /// Span of the declaration in the new text
+    pub new: Option<Span>,
+}
+
+struct Node {
+    path: String,
+    parent: Option<usize>,
+    /// Path relative to the parent
+    segment: String,
+    /// Kind and unqualified name, for move detection
+    short: (&'static str, String),
+    fingerprint: String,
+    span: Span,
+}
+
+/// Returns changes turning the old document into the new one
+pub fn diff_ast(old: &Document, new: &Document) -> Vec<Change> {
+    let old = flatten(old);
+    let new = flatten(new);
+    let old_by_path: HashMap<&str, usize> = old
+        .iter()
+        .enumerate()
+        .map(|(idx, n)| (&n.path[..], idx))
+        .collect();
+
+    // index of the matching node in `old` by index in `new`
+    let mut matches: Vec<Option<usize>> = vec![None; new.len()];
+    let mut matched_old = vec![false; old.len()];
+    let mut moved = vec![false; new.len()];
+    // new nodes are in pre-order, so parents are matched first
+    for idx in 0..new.len() {
+        let node = &new[idx];
+        let parent_match = node.parent.map(|p| matches[p]);
+        let expected = match parent_match {
+            Some(None) => continue,
+            Some(Some(parent)) => {
+                let candidate = join(Some(&old[parent].path), &node.segment);
+                old_by_path.get(&candidate[..]).copied()
+            }
+            None => old_by_path.get(&node.path[..]).copied(),
+        };
+        let found = expected.filter(|o| !matched_old[*o]).or_else(|| {
+            // the root of an unmatched subtree, try to find where it was
+            (0..old.len()).find(|&o| {
+                !matched_old[o]
+                    && old[o].short == node.short
+                    && !new.iter().any(|n| n.path == old[o].path)
+            })
+        });
+        if let Some(o) = found {
+            moved[idx] = expected != Some(o);
+            matches[idx] = Some(o);
+            matched_old[o] = true;
+        }
+    }
+
+    let mut changes = Vec::new();
+    for (idx, node) in new.iter().enumerate() {
+        let parent_matched = node.parent.is_none_or(|p| matches[p].is_some());
+        match matches[idx] {
+            None if parent_matched => changes.push(Change {
+                kind: ChangeKind::Added,
+                path: node.path.clone(),
+                old_path: None,
+                old: None,
+                new: Some(node.span),
+            }),
+            None => {}
+            Some(o) => {
+                let change = |kind| Change {
+                    kind,
+                    path: node.path.clone(),
+                    old_path: (kind == ChangeKind::Moved).then(|| old[o].path.clone()),
+                    old: Some(old[o].span),
+                    new: Some(node.span),
+                };
+                if moved[idx] {
+                    changes.push(change(ChangeKind::Moved));
+                }
+                if old[o].fingerprint != node.fingerprint {
+                    changes.push(change(ChangeKind::Changed));
+                }
+            }
+        }
+    }
+    for (idx, node) in old.iter().enumerate() {
+        let parent_matched = node.parent.is_none_or(|p| matched_old[p]);
+        if !matched_old[idx] && parent_matched {
+            changes.push(Change {
+                kind: ChangeKind::Removed,
+                path: node.path.clone(),
+                old_path: None,
+                old: Some(node.span),
+                new: None,
+            });
+        }
+    }
+    changes
+}
+
+/// Declarations of the document in pre-order
+fn flatten(doc: &Document) -> Vec<Node> {
+    fn visit(decls: &[Declaration], parent: Option<usize>, nodes: &mut Vec<Node>) {
+        let mut seen: HashMap<String, usize> = HashMap::new();
+        for decl in decls {
+            if decl.kind == DeclKind::Module {
+                visit(&decl.body, parent, nodes);
+                continue;
+            }
+            let label = label(decl.kind);
+            let name = decl.name_text().unwrap_or_default();
+            let mut segment = match decl.kind {
+                DeclKind::Link | DeclKind::Property | DeclKind::Pointer | DeclKind::Field => {
+                    name.to_string()
+                }
+                DeclKind::Function => format!("{label} {name}({})", text(decl.args.as_ref())),
+                _ if decl.name.is_none() => format!("{label} {}", head(decl)),
+                _ => format!("{label} {name}"),
+            };
+            // e.g. two constraints of the same name with different arguments
+            let count = seen.entry(segment.clone()).or_default();
+            *count += 1;
+            if *count > 1 {
+                segment = format!("{segment}#{count}");
+            }
+            let short_name = name.rsplit("::").next().unwrap_or_default();
+            let absolute = parent.is_none() && name.contains("::");
+            let path = match decl.kind {
+                DeclKind::Function if absolute => format!("{name}({})", text(decl.args.as_ref())),
+                _ if absolute => name.to_string(),
+                _ => join(parent.map(|p| &nodes[p].path[..]), &segment),
+            };
+            let idx = nodes.len();
+            nodes.push(Node {
+                path,
+                parent,
+                segment,
+                short: (label, short_name.to_string()),
+                fingerprint: head(decl),
+                span: decl.span,
+            });
+            visit(&decl.body, Some(idx), nodes);
+        }
+    }
+    // spans are kept by qualification
+    let mut nodes = Vec::new();
+    visit(&qualify(doc).declarations, None, &mut nodes);
+    nodes
+}
+
+fn join(parent: Option<&str>, segment: &str) -> String {
+    match parent {
+        Some(parent) => format!("{parent}.{segment}"),
+        None => segment.to_string(),
+    }
+}
+
+fn label(kind: DeclKind) -> &'static str {
+    use DeclKind::*;
+
+    match kind {
+        Module => "module",
+        ObjectType => "type",
+        ScalarType => "scalar type",
+        Link | Property | Pointer => "pointer",
+        Constraint => "constraint",
+        Index => "index",
+        Annotation => "annotation",
+        Alias => "alias",
+        Function => "function",
+        Global => "global",
+        Extension => "extension",
+        Future => "future",
+        AccessPolicy => "access policy",
+        Trigger => "trigger",
+        Rewrite => "rewrite",
+        Permission => "permission",
+        Field => "field",
+        Subcommand => "subcommand",
+        Other => "other",
+    }
+}
+
+/// Text of the declaration without its kind, name and body, used to detect
+/// changes. The kind is left out so that a pointer in short syntax is the
+/// same as a `property` or `link`.
+fn head(decl: &Declaration) -> String {
+    let mut parts: Vec<String> = decl.modifiers.iter().map(|m| m.keyword.into()).collect();
+    parts.extend(decl.cardinality.map(|c| c.as_str().into()));
+    if !decl.extending.is_empty() {
+        parts.push(format!("extending {}", types(&decl.extending, ", ")));
+    }
+    if !decl.target.is_empty() {
+        parts.push(format!("-> {}", types(&decl.target, " | ")));
+    }
+    if decl.args.is_some() {
+        parts.push(format!("({})", text(decl.args.as_ref())));
+    }
+    if decl.expr.is_some() {
+        parts.push(format!(":= {}", text(decl.expr.as_ref())));
+    }
+    for clause in &decl.clauses {
+        parts.push(format!("{} {}", clause.keyword, text(Some(&clause.expr))));
+    }
+    parts.join(" ")
+}
+
+fn types(types: &[TypeRef], separator: &str) -> String {
+    types
+        .iter()
+        .map(|t| {
+            if t.args.is_empty() {
+                t.name.text.clone()
+            } else {
+                format!("{}<{}>", t.name.text, self::types(&t.args, ", "))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+/// Tokens of the expression, separated by a space where the source had
+/// whitespace
+fn text(expr: Option<&Expr>) -> String {
+    let mut result = String::new();
+    let mut prev_end = None;
+    for tok in expr.iter().flat_map(|e| &e.tokens) {
+        if prev_end.is_some_and(|end| end < tok.span.start) {
+            result.push(' ');
+        }
+        result.push_str(&tok.text);
+        prev_end = Some(tok.span.end);
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::{diff_ast, ChangeKind};
+    use crate::schema::parse;
+
+    fn changes(old: &str, new: &str) -> Vec<String> {
+        diff_ast(&parse(old).unwrap(), &parse(new).unwrap())
+            .into_iter()
+            .map(|c| match c.old_path {
+                Some(old_path) => format!("{:?} {old_path} -> {}", c.kind, c.path),
+                None => format!("{:?} {}", c.kind, c.path),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn unchanged() {
+        assert!(changes(
+            "module default { type User { required name: str; index on (.name); } }",
+            "type default::User {\n  index on (.name);\n  required property name: str;\n}",
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn changes_of_declarations() {
+        assert_eq!(
+            changes(
+                "
+                module default {
+                    type User {
+                        required name: str;
+                        age: int32;
+                        nick: str { constraint max_len_value(10); };
+                    }
+                    type Post;
+                }
+                ",
+                "
+                module default {
+                    type User {
+                        required name: str;
+                        age: int64;
+                        email: str { constraint exclusive; };
+                        nick: str { constraint max_len_value(20); };
+                    }
+                    scalar type Color extending enum<Red, Green>;
+                }
+                "
+            ),
+            [
+                "Changed default::User.age",
+                "Added default::User.email",
+                "Changed default::User.nick.constraint max_len_value",
+                "Added default::Color",
+                "Removed default::Post",
+            ]
+        );
+    }
+
+    #[test]
+    fn moves() {
+        let result = diff_ast(
+            &parse(
+                "
+                module default {
+                    type User { name: str; tags: array<str>; }
+                    type Post { title: str; }
+                }
+                ",
+            )
+            .unwrap(),
+            &parse(
+                "
+                module default {
+                    type Post { title: str; tags: array<str>; }
+                }
+                module auth {
+                    type User { required name: str; }
+                }
+                ",
+            )
+            .unwrap(),
+        );
+        let kinds: Vec<_> = result
+            .iter()
+            .map(|c| (c.kind, c.old_path.as_deref(), &c.path[..]))
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                (
+                    ChangeKind::Moved,
+                    Some("default::User.tags"),
+                    "default::Post.tags"
+                ),
+                (ChangeKind::Moved, Some("default::User"), "auth::User"),
+                (ChangeKind::Changed, None, "auth::User.name"),
+            ]
+        );
+        assert!(result[0].old.is_some() && result[0].new.is_some());
+    }
+}
@@ -0,0 +1,140 @@
+//! Enum scalar types
+//!
+//! `scalar type Color extending enum<Red, Green>` is parsed as a scalar type
+//! extending a type named `enum`, with the members as parameters of the
+//! type. [EnumType] gives them as members with their spans, and [check]
+//! reports empty and duplicate members, which the server rejects.
+//!
+//! Both work on the declaration read by [crate::schema::parse], not on the
+//! AST of the SDL grammar. [check] is a lint for editors and build scripts,
+//! the server reports the same problems when the schema is applied.
+use std::collections::HashSet;
+
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::position::Span;
+use crate::schema::{DeclKind, Declaration, Document, TypeRef};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnumMember {
+    /// Unquoted name, empty for a missing member like in `enum<A,,B>`
+    pub name: String,
+    pub span: Span,
+    /// Whether the member is a plain name, e.g. not `a::b` or `a<b>`
+    pub is_plain: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnumType {
+    pub members: Vec<EnumMember>,
+    /// Span of `enum<...>`
+    pub span: Span,
+}
+
+impl EnumType {
+    /// Enum type extended by a scalar type declaration
+    pub fn from_declaration(decl: &Declaration) -> Option<EnumType> {
+        if decl.kind != DeclKind::ScalarType {
+            return None;
+        }
+        decl.extending.iter().find_map(EnumType::from_type)
+    }
+
+    /// Enum type of an `enum<...>` reference
+    pub fn from_type(ty: &TypeRef) -> Option<EnumType> {
+        if !matches!(&ty.name.text[..], "enum" | "std::enum") {
+            return None;
+        }
+        let members = ty
+            .args
+            .iter()
+            .map(|arg| EnumMember {
+                name: arg.name.text.clone(),
+                span: arg.span,
+                is_plain: arg.args.is_empty() && !arg.name.text.contains("::"),
+            })
+            .collect();
+        Some(EnumType {
+            members,
+            span: ty.span,
+        })
+    }
+
+    /// Names of the members in order, including the empty ones
+    pub fn names(&self) -> Vec<&str> {
+        self.members.iter().map(|m| &m.name[..]).collect()
+    }
+}
+
+/// Returns errors of enum members: empty, duplicate and qualified ones
+pub fn check(doc: &Document) -> Vec<Diagnostic> {
+    let mut errors = Vec::new();
+    doc.walk(|decl, _| {
+        let Some(enum_type) = EnumType::from_declaration(decl) else {
+            return;
+        };
+        let mut seen = HashSet::new();
+        for member in &enum_type.members {
+            let code = if member.name.is_empty() {
+                "missing-enum-member"
+            } else if !member.is_plain {
+                "invalid-enum-member"
+            } else if !seen.insert(&member.name[..]) {
+                "duplicate-enum-member"
+            } else {
+                continue;
+            };
+            errors.push(Diagnostic::with_code(
+                Severity::Error,
+                code,
+                &[("name", member.name.clone())],
+                member.span,
+            ));
+        }
+    });
+    errors
+}
+
+#[cfg(test)]
+mod test {
+    use super::{check, EnumType};
+    use crate::schema::parse;
+
+    #[test]
+    fn members() {
+        let text = "scalar type Color extending enum<Red, `Dark Green`, Blue,>;";
+        let doc = parse(text).unwrap();
+        let color = EnumType::from_declaration(&doc.declarations[0]).unwrap();
+        assert_eq!(color.names(), ["Red", "Dark Green", "Blue"]);
+        let span = color.members[1].span;
+        assert_eq!(
+            &text[span.start as usize..span.end as usize],
+            "`Dark Green`"
+        );
+        assert!(check(&doc).is_empty());
+    }
+
+    #[test]
+    fn errors() {
+        let text = "
+            module default {
+                scalar type Color extending enum<Red, Green, Red, , a::B>;
+                scalar type Empty extending enum<>;
+            }
+        ";
+        let errors = check(&parse(text).unwrap());
+        let errors: Vec<_> = errors
+            .iter()
+            .map(|e| (&e.message[..], e.span.start as usize))
+            .collect();
+        let at = |member: &str, nth: usize| text.match_indices(member).nth(nth).unwrap().0;
+        assert_eq!(
+            errors,
+            [
+                ("duplicate enum member 'Red'", at("Red", 1)),
+                ("enum member is missing", at(", a::B", 0)),
+                ("invalid enum member 'a::B'", at("a::B", 0)),
+                ("enum member is missing", at("<>", 0) + 1),
+            ]
+        );
+    }
+}
@@ -0,0 +1,148 @@
+//! Extensions and future behaviors required by a schema
+//!
+//! Lists `using extension ...` and `using future ...` declarations (and
+//! their DDL forms `create extension ...`, `create future ...`), so that
+//! deployment tooling can check that the server supports them before
+//! applying the schema.
+use crate::position::Span;
+use crate::schema::{DeclKind, Declaration, Document, Verb};
+use crate::tokenizer::{Kind, Value};
+
+/// `using extension name version '1.0'`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Extension {
+    pub name: String,
+    /// Requested version, `None` if any version is fine
+    pub version: Option<String>,
+    pub span: Span,
+}
+
+/// `using future name`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Future {
+    pub name: String,
+    pub span: Span,
+}
+
+/// Everything the schema needs from the server, in the order of the source
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Requirements {
+    pub extensions: Vec<Extension>,
+    pub futures: Vec<Future>,
+}
+
+impl Extension {
+    /// Reads an extension declaration, returns `None` for other kinds of
+    /// declarations and for `drop extension`
+    pub fn from_declaration(decl: &Declaration) -> Option<Extension> {
+        if decl.kind != DeclKind::Extension || decl.verb == Some(Verb::Drop) {
+            return None;
+        }
+        let version = decl
+            .clause("version")
+            .and_then(|expr| match &expr.tokens[..] {
+                [tok] if tok.kind == Kind::Str => match &tok.value {
+                    Some(Value::String(s)) => Some(s.clone()),
+                    _ => None,
+                },
+                _ => None,
+            });
+        Some(Extension {
+            name: decl.name_text()?.to_string(),
+            version,
+            span: decl.span,
+        })
+    }
+}
+
+impl Future {
+    /// Reads a future declaration, returns `None` for other kinds of
+    /// declarations and for `drop future`
+    pub fn from_declaration(decl: &Declaration) -> Option<Future> {
+        if decl.kind != DeclKind::Future || decl.verb == Some(Verb::Drop) {
+            return None;
+        }
+        Some(Future {
+            name: decl.name_text()?.to_string(),
+            span: decl.span,
+        })
+    }
+}
+
+impl Requirements {
+    pub fn extension(&self, name: &str) -> Option<&Extension> {
+        self.extensions.iter().find(|e| e.name == name)
+    }
+
+    pub fn has_future(&self, name: &str) -> bool {
+        self.futures.iter().any(|f| f.name == name)
+    }
+}
+
+/// Returns extensions and futures used by the document. Repeated
+/// declarations are only listed once.
+pub fn requirements(doc: &Document) -> Requirements {
+    let mut result = Requirements::default();
+    doc.walk(|decl, _| {
+        if let Some(ext) = Extension::from_declaration(decl) {
+            if result.extension(&ext.name).is_none() {
+                result.extensions.push(ext);
+            }
+        } else if let Some(future) = Future::from_declaration(decl) {
+            if !result.has_future(&future.name) {
+                result.futures.push(future);
+            }
+        }
+    });
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::requirements;
+    use crate::schema::parse;
+
+    #[test]
+    fn sdl() {
+        let text = "
+            using extension pgvector version '0.5';
+            using extension auth;
+            using future nonrecursive_access_policies;
+            using extension auth;
+            module default {
+                type Item { embedding: ext::pgvector::vector; }
+            }
+        ";
+        let req = requirements(&parse(text).unwrap());
+        let extensions: Vec<_> = req
+            .extensions
+            .iter()
+            .map(|e| (&e.name[..], e.version.as_deref()))
+            .collect();
+        assert_eq!(extensions, [("pgvector", Some("0.5")), ("auth", None)]);
+        assert_eq!(
+            &text[req.extensions[0].span.start as usize..req.extensions[0].span.end as usize],
+            "using extension pgvector version '0.5';"
+        );
+        assert!(req.has_future("nonrecursive_access_policies"));
+        assert_eq!(req.futures.len(), 1);
+    }
+
+    #[test]
+    fn ddl() {
+        let req = requirements(
+            &parse(
+                "create extension pgvector version '0.5';\n\
+                 drop extension auth;\n\
+                 create future simple_scoping;",
+            )
+            .unwrap(),
+        );
+        assert_eq!(
+            req.extension("pgvector").unwrap().version.as_deref(),
+            Some("0.5")
+        );
+        assert_eq!(req.extension("auth"), None);
+        assert!(req.has_future("simple_scoping"));
+    }
+}
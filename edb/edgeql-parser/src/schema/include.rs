@@ -0,0 +1,287 @@
+//! Schemas split into several files (experimental)
+//!
+//! A schema file may include other files using comment directives at its
+//! very beginning, before any declaration:
+//!
+//! ```text
+//! # include "common.esdl"
+//! # include "auth/users.esdl"
+//!
+//! type Post { author: User; }
+//! ```
+//!
+//! Paths are relative to the including file. Directives are comments, so
+//! the server and older tools simply ignore them. Each file is loaded once
+//! even if it is included several times; include cycles are an error.
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+use crate::schema::{parse, Document};
+use crate::tokenizer::Error;
+
+/// A loaded schema file
+#[derive(Debug)]
+pub struct SourceFile {
+    pub path: PathBuf,
+    pub text: String,
+    /// Spans of the document refer to `text`
+    pub document: Document,
+    /// Normalized paths of the files included directly
+    pub includes: Vec<PathBuf>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum IncludeError {
+    #[error("cannot read {}: {source}", path.display())]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("{}: {}", path.display(), error.message)]
+    Syntax { path: PathBuf, error: Error },
+    #[error("include cycle: {}", display_paths(paths))]
+    Cycle {
+        /// Files of the cycle, the last one includes the first one
+        paths: Vec<PathBuf>,
+    },
+}
+
+/// Reads file contents, implemented for closures so that files can come from
+/// somewhere else than the file system
+pub trait Loader {
+    fn load(&self, path: &Path) -> io::Result<String>;
+}
+
+impl<F: Fn(&Path) -> io::Result<String>> Loader for F {
+    fn load(&self, path: &Path) -> io::Result<String> {
+        self(path)
+    }
+}
+
+/// Loads the file and every file it includes (recursively) from the file
+/// system, see [load_with]
+pub fn load(root: &Path) -> Result<Vec<SourceFile>, IncludeError> {
+    load_with(root, &|path: &Path| fs::read_to_string(path))
+}
+
+/// Loads the file and everything it includes. Files are ordered so that
+/// included files come before the files including them, the root is the
+/// last one.
+pub fn load_with(root: &Path, loader: &dyn Loader) -> Result<Vec<SourceFile>, IncludeError> {
+    let mut state = State {
+        loader,
+        stack: Vec::new(),
+        done: HashSet::new(),
+        files: Vec::new(),
+    };
+    state.visit(normalize(root))?;
+    Ok(state.files)
+}
+
+/// Paths of the files included by the text, as written in the directives
+pub fn includes(text: &str) -> Vec<&str> {
+    let mut result = Vec::new();
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let Some(comment) = line.strip_prefix('#') else {
+            break;
+        };
+        let Some(rest) = comment.trim_start().strip_prefix("include") else {
+            continue;
+        };
+        let rest = rest.trim();
+        let quoted = ['"', '\''].iter().find_map(|&q| {
+            rest.strip_prefix(q)
+                .and_then(|r| r.strip_suffix(q))
+                .filter(|r| !r.is_empty() && !r.contains(q))
+        });
+        if let Some(path) = quoted {
+            result.push(path);
+        }
+    }
+    result
+}
+
+/// Concatenates declarations of all files, spans of the declarations refer
+/// to the text of their own file
+pub fn merge(files: &[SourceFile]) -> Document {
+    Document {
+        declarations: files
+            .iter()
+            .flat_map(|f| f.document.declarations.iter().cloned())
+            .collect(),
+    }
+}
+
+struct State<'a> {
+    loader: &'a dyn Loader,
+    /// Files being loaded, each includes the next one
+    stack: Vec<PathBuf>,
+    done: HashSet<PathBuf>,
+    files: Vec<SourceFile>,
+}
+
+impl State<'_> {
+    fn visit(&mut self, path: PathBuf) -> Result<(), IncludeError> {
+        if self.done.contains(&path) {
+            return Ok(());
+        }
+        if let Some(pos) = self.stack.iter().position(|p| *p == path) {
+            return Err(IncludeError::Cycle {
+                paths: self.stack[pos..].to_vec(),
+            });
+        }
+        let text = self.loader.load(&path).map_err(|source| IncludeError::Io {
+            path: path.clone(),
+            source,
+        })?;
+        let document = parse(&text).map_err(|error| IncludeError::Syntax {
+            path: path.clone(),
+            error,
+        })?;
+        let dir = path.parent().unwrap_or(Path::new(""));
+        let includes: Vec<_> = includes(&text)
+            .into_iter()
+            .map(|inc| normalize(&dir.join(inc)))
+            .collect();
+
+        self.stack.push(path.clone());
+        for include in &includes {
+            self.visit(include.clone())?;
+        }
+        self.stack.pop();
+
+        self.done.insert(path.clone());
+        self.files.push(SourceFile {
+            path,
+            text,
+            document,
+            includes,
+        });
+        Ok(())
+    }
+}
+
+/// Removes `.` and `..` components without touching the file system
+fn normalize(path: &Path) -> PathBuf {
+    let mut result = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir
+                if matches!(result.components().next_back(), Some(Component::Normal(_))) =>
+            {
+                result.pop();
+            }
+            other => result.push(other),
+        }
+    }
+    result
+}
+
+fn display_paths(paths: &[PathBuf]) -> String {
+    paths
+        .iter()
+        .chain(paths.first())
+        .map(|p| p.display().to_string())
+        .collect::<Vec<_>>()
+        .join(" -> ")
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+    use std::io;
+    use std::path::{Path, PathBuf};
+
+    use super::{includes, load_with, merge, IncludeError};
+
+    fn loader(files: &[(&str, &str)]) -> impl Fn(&Path) -> io::Result<String> {
+        let files: HashMap<PathBuf, String> = files
+            .iter()
+            .map(|(p, t)| (PathBuf::from(p), t.to_string()))
+            .collect();
+        move |path: &Path| {
+            files
+                .get(path)
+                .cloned()
+                .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "not found"))
+        }
+    }
+
+    #[test]
+    fn directives() {
+        assert_eq!(
+            includes(
+                "\n# include \"a.esdl\"\n  #include 'b/c.esdl'\n# other\n\
+                 type A;\n# include \"d.esdl\""
+            ),
+            ["a.esdl", "b/c.esdl"]
+        );
+        assert!(includes("# include a.esdl\n# include \"\"").is_empty());
+    }
+
+    #[test]
+    fn order() {
+        let load = loader(&[
+            (
+                "schema/main.esdl",
+                "# include \"users/user.esdl\"\n# include 'common.esdl'\n\
+                 type Post { author: User; }",
+            ),
+            (
+                "schema/users/user.esdl",
+                "# include \"../common.esdl\"\ntype User extending Named;",
+            ),
+            ("schema/common.esdl", "abstract type Named { name: str; }"),
+        ]);
+        let files = load_with(Path::new("schema/./main.esdl"), &load).unwrap();
+        let paths: Vec<_> = files.iter().map(|f| f.path.to_str().unwrap()).collect();
+        assert_eq!(
+            paths,
+            [
+                "schema/common.esdl",
+                "schema/users/user.esdl",
+                "schema/main.esdl"
+            ]
+        );
+        assert_eq!(
+            files[2].includes,
+            [
+                PathBuf::from("schema/users/user.esdl"),
+                PathBuf::from("schema/common.esdl")
+            ]
+        );
+        let names: Vec<_> = merge(&files)
+            .declarations
+            .iter()
+            .map(|d| d.name_text().unwrap().to_string())
+            .collect();
+        assert_eq!(names, ["Named", "User", "Post"]);
+    }
+
+    #[test]
+    fn errors() {
+        let load = loader(&[
+            ("a.esdl", "# include \"b.esdl\"\ntype A;"),
+            ("b.esdl", "# include \"a.esdl\"\ntype B;"),
+            ("c.esdl", "# include \"missing.esdl\""),
+            ("d.esdl", "type D {"),
+        ]);
+        let err = load_with(Path::new("a.esdl"), &load).unwrap_err();
+        assert!(matches!(err, IncludeError::Cycle { .. }));
+        assert_eq!(err.to_string(), "include cycle: a.esdl -> b.esdl -> a.esdl");
+
+        let err = load_with(Path::new("c.esdl"), &load).unwrap_err();
+        assert_eq!(err.to_string(), "cannot read missing.esdl: not found");
+
+        let err = load_with(Path::new("d.esdl"), &load).unwrap_err();
+        assert!(matches!(err, IncludeError::Syntax { .. }));
+    }
+}
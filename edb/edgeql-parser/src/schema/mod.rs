@@ -0,0 +1,880 @@
+//! Structural model of SDL and DDL documents
+//!
+//! This is not a full parser: it only recognizes the shape of declarations
+//! (kind, modifiers, name, `extending` list, target type, computed
+//! expression and nested body), which is enough for tooling that needs to
+//! reason about a schema without the full grammar. Expressions are kept as
+//! raw token lists, and so are the arguments and clauses of index
+//! definitions (`on`, `except`): the model accepts any of them, only the
+//! grammar checks what is allowed.
+mod annotations;
+pub mod compat;
+pub mod constraints;
+pub mod cycles;
+pub mod deps;
+pub mod diff;
+pub mod enums;
+pub mod extensions;
+pub mod include;
+pub mod modifiers;
+pub mod qualify;
+mod subcommands;
+
+pub use annotations::{annotations, Annotated, Annotation, AnnotationKind};
+pub use subcommands::Subcommand;
+
+use crate::keywords::Keyword;
+use crate::position::Span;
+use crate::tokenizer::{Error, Kind, Token, Tokenizer};
+
+/// A parsed SDL or DDL document
+#[derive(Debug, Clone, Default)]
+pub struct Document {
+    pub declarations: Vec<Declaration>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Declaration {
+    /// `create`, `alter` or `drop` for DDL statements
+    pub verb: Option<Verb>,
+    pub modifiers: Vec<Modifier>,
+    /// `single` or `multi`, only allowed on pointers and globals
+    pub cardinality: Option<Cardinality>,
+    pub kind: DeclKind,
+    pub name: Option<Name>,
+    pub extending: Vec<TypeRef>,
+    /// Target type of a pointer, alias or function (several for `A | B`)
+    pub target: Vec<TypeRef>,
+    /// Expression after `:=`
+    pub expr: Option<Expr>,
+    /// Contents of the parenthesis following the name (function parameters,
+    /// constraint arguments)
+    pub args: Option<Expr>,
+    /// Clauses introduced by a keyword, like `on (...)` or `using (...)`
+    pub clauses: Vec<Clause>,
+    pub body: Vec<Declaration>,
+    /// Sub-command of an `alter` statement, set for [DeclKind::Subcommand]
+    pub subcommand: Option<Subcommand>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verb {
+    Create,
+    Alter,
+    Drop,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DeclKind {
+    Module,
+    ObjectType,
+    ScalarType,
+    Link,
+    Property,
+    /// Pointer declared using short syntax (`name: str`), that does not say
+    /// whether it is a link or a property
+    Pointer,
+    Constraint,
+    Index,
+    Annotation,
+    Alias,
+    Function,
+    Global,
+    Extension,
+    Future,
+    AccessPolicy,
+    Trigger,
+    Rewrite,
+    Permission,
+    /// Field of a declaration, like `default := ...` or `readonly := ...`
+    Field,
+    /// Sub-command of an `alter` statement, like `rename to ...`
+    Subcommand,
+    Other,
+}
+
+/// Cardinality qualifier of a pointer, a global or a computed shape element
+///
+/// Read from the tokens before the name; whether the qualifier is allowed
+/// on the declaration or element is up to the grammar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cardinality {
+    Single,
+    Multi,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Modifier {
+    pub keyword: &'static str,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Name {
+    /// Possibly qualified name, e.g. `default::User`
+    pub text: String,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypeRef {
+    /// Name of the element of a named tuple, e.g. `a` in `tuple<a: str>`
+    pub element_name: Option<Name>,
+    pub name: Name,
+    /// Parameters of a type, e.g. `str` in `array<str>`
+    pub args: Vec<TypeRef>,
+    /// The type without the element name
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expr {
+    pub tokens: Vec<Token<'static>>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Clause {
+    pub keyword: &'static str,
+    pub expr: Expr,
+}
+
+const MODIFIERS: &[&str] = &[
+    "abstract",
+    "required",
+    "optional",
+    "overloaded",
+    "delegated",
+    "final",
+    "deferred",
+];
+
+const CLAUSES: &[&str] = &[
+    "on", "except", "using", "version", "when", "do", "allow", "deny", "after", "for",
+];
+
+const FIELDS: &[&str] = &[
+    "default",
+    "readonly",
+    "errmessage",
+    "expression",
+    "cardinality",
+    "optionality",
+];
+
+/// Parses the structure of an SDL or DDL document
+pub fn parse(text: &str) -> Result<Document, Error> {
+    let tokens = Tokenizer::new(text)
+        .validated_values()
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut reader = Reader { tokens, pos: 0 };
+    let declarations = reader.block(None)?;
+    Ok(Document { declarations })
+}
+
+impl Document {
+    /// Calls `f` for every declaration in the document, depth first.
+    ///
+    /// The second argument contains the parents of the declaration, with
+    /// the outermost one first.
+    pub fn walk<'a>(&'a self, mut f: impl FnMut(&'a Declaration, &[&'a Declaration])) {
+        fn walk_inner<'a>(
+            decls: &'a [Declaration],
+            parents: &mut Vec<&'a Declaration>,
+            f: &mut impl FnMut(&'a Declaration, &[&'a Declaration]),
+        ) {
+            for decl in decls {
+                f(decl, parents);
+                parents.push(decl);
+                walk_inner(&decl.body, parents, f);
+                parents.pop();
+            }
+        }
+        walk_inner(&self.declarations, &mut Vec::new(), &mut f);
+    }
+}
+
+impl Cardinality {
+    pub fn from_keyword(keyword: &str) -> Option<Cardinality> {
+        match keyword {
+            "single" => Some(Cardinality::Single),
+            "multi" => Some(Cardinality::Multi),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Cardinality::Single => "single",
+            Cardinality::Multi => "multi",
+        }
+    }
+}
+
+/// Reads `single` or `multi` from `keyword` into `cardinality`, fails if
+/// the cardinality is already set
+pub(crate) fn read_cardinality(
+    cardinality: &mut Option<(Cardinality, Span)>,
+    keyword: Cardinality,
+    span: Span,
+) -> Result<(), Error> {
+    if let Some((prev, _)) = cardinality {
+        let message = if *prev == keyword {
+            format!("'{}' is specified twice", keyword.as_str())
+        } else {
+            "'single' and 'multi' cannot be used together".to_string()
+        };
+        return Err(Error::new(message).with_span(span));
+    }
+    *cardinality = Some((keyword, span));
+    Ok(())
+}
+
+impl Declaration {
+    pub fn has_modifier(&self, keyword: &str) -> bool {
+        self.modifiers.iter().any(|m| m.keyword == keyword)
+    }
+
+    pub fn is_multi(&self) -> bool {
+        self.cardinality == Some(Cardinality::Multi)
+    }
+
+    pub fn clause(&self, keyword: &str) -> Option<&Expr> {
+        self.clauses
+            .iter()
+            .find(|c| c.keyword == keyword)
+            .map(|c| &c.expr)
+    }
+
+    pub fn name_text(&self) -> Option<&str> {
+        self.name.as_ref().map(|n| &n.text[..])
+    }
+
+    /// Text of the comment lines directly above the declaration (without
+    /// the `#` markers), `text` is the source the document was parsed from
+    pub fn doc_comment(&self, text: &str) -> Option<String> {
+        let start = self.span.start as usize;
+        let line_start = text[..start].rfind('\n').map_or(0, |i| i + 1);
+        if !text[line_start..start].trim().is_empty() {
+            return None;
+        }
+        let mut lines = Vec::new();
+        for line in text[..line_start].lines().rev() {
+            let Some(comment) = line.trim().strip_prefix('#') else {
+                break;
+            };
+            lines.push(comment.strip_prefix(' ').unwrap_or(comment).trim_end());
+        }
+        if lines.is_empty() {
+            return None;
+        }
+        lines.reverse();
+        Some(lines.join("\n"))
+    }
+
+    /// Returns true for pointers (links and properties) of any syntax
+    pub fn is_pointer(&self) -> bool {
+        matches!(
+            self.kind,
+            DeclKind::Link | DeclKind::Property | DeclKind::Pointer
+        )
+    }
+}
+
+impl TypeRef {
+    /// Returns true for `tuple<...>` and `std::tuple<...>`
+    pub fn is_tuple(&self) -> bool {
+        matches!(&self.name.text[..], "tuple" | "std::tuple")
+    }
+
+    /// Names of the elements of a named tuple in the order of the elements,
+    /// `None` for other types, including tuples with unnamed elements
+    pub fn tuple_names(&self) -> Option<Vec<&str>> {
+        if !self.is_tuple() || self.args.is_empty() {
+            return None;
+        }
+        self.args
+            .iter()
+            .map(|arg| arg.element_name.as_ref().map(|n| &n.text[..]))
+            .collect()
+    }
+}
+
+impl DeclKind {
+    /// Kinds of declarations that are schema objects addressable by
+    /// a module-qualified name
+    pub fn is_schema_object(&self) -> bool {
+        use DeclKind::*;
+
+        matches!(
+            self,
+            ObjectType
+                | ScalarType
+                | Link
+                | Property
+                | Constraint
+                | Annotation
+                | Alias
+                | Function
+                | Global
+                | Permission
+        )
+    }
+}
+
+struct Reader<'a> {
+    tokens: Vec<Token<'a>>,
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn peek(&self) -> Option<&Token<'a>> {
+        self.tokens.get(self.pos)
+    }
+
+    fn peek_kind(&self, offset: usize) -> Option<Kind> {
+        self.tokens.get(self.pos + offset).map(|t| t.kind)
+    }
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        let tok = self.tokens.get(self.pos).cloned();
+        if tok.is_some() {
+            self.pos += 1;
+        }
+        tok
+    }
+
+    fn last_end(&self) -> u64 {
+        self.pos
+            .checked_sub(1)
+            .and_then(|p| self.tokens.get(p))
+            .map_or(0, |t| t.span.end)
+    }
+
+    fn eof_span(&self) -> Span {
+        let end = self.tokens.last().map_or(0, |t| t.span.end);
+        Span { start: end, end }
+    }
+
+    fn peek_keyword(&self, offset: usize) -> Option<&'static str> {
+        match self.peek_kind(offset)? {
+            Kind::Keyword(Keyword(kw)) => Some(kw),
+            _ => None,
+        }
+    }
+
+    /// Start of a pointer in short syntax, e.g. `name: str` or `name := ...`
+    fn is_short_pointer(&self, offset: usize) -> bool {
+        is_name_like(self.peek_kind(offset))
+            && matches!(
+                self.peek_kind(offset + 1),
+                Some(Kind::Colon | Kind::Arrow | Kind::Assign)
+            )
+    }
+
+    /// Reads declarations until a closing brace (if `open` is set) or the
+    /// end of input.
+    fn block(&mut self, open: Option<Span>) -> Result<Vec<Declaration>, Error> {
+        let mut decls = Vec::new();
+        loop {
+            match self.peek_kind(0) {
+                None => {
+                    if let Some(open) = open {
+                        return Err(Error::new("Missing '}'").with_span(open));
+                    }
+                    return Ok(decls);
+                }
+                Some(Kind::CloseBrace) => {
+                    let tok = self.next().unwrap();
+                    if open.is_none() {
+                        return Err(Error::new("Unexpected '}'").with_span(tok.span));
+                    }
+                    return Ok(decls);
+                }
+                Some(Kind::Semicolon) => {
+                    self.next();
+                }
+                Some(_) => decls.push(self.declaration()?),
+            }
+        }
+    }
+
+    fn declaration(&mut self) -> Result<Declaration, Error> {
+        let start = self.peek().map_or(0, |t| t.span.start);
+        if let Some(mut decl) = self.subcommand()? {
+            if self.peek_kind(0) == Some(Kind::Semicolon) {
+                self.next();
+                decl.span.end = self.last_end();
+            }
+            return Ok(decl);
+        }
+
+        let verb = match self.peek_keyword(0) {
+            Some("create") => Some(Verb::Create),
+            Some("alter") => Some(Verb::Alter),
+            Some("drop") => Some(Verb::Drop),
+            _ => None,
+        };
+        if verb.is_some() {
+            self.next();
+        }
+
+        let mut modifiers = Vec::new();
+        let mut cardinality = None;
+        while let Some(kw) = self.peek_keyword(0) {
+            let qualifier = Cardinality::from_keyword(kw);
+            if !(MODIFIERS.contains(&kw) || qualifier.is_some()) || self.is_short_pointer(0) {
+                break;
+            }
+            let tok = self.next().unwrap();
+            match qualifier {
+                Some(qualifier) => read_cardinality(&mut cardinality, qualifier, tok.span)?,
+                None => modifiers.push(Modifier {
+                    keyword: kw,
+                    span: tok.span,
+                }),
+            }
+        }
+
+        let kind = self.kind();
+        if let Some((qualifier, span)) = cardinality {
+            use DeclKind::*;
+
+            if !matches!(kind, Link | Property | Pointer | Global) {
+                return Err(Error::new(format!(
+                    "'{}' is only allowed on links, properties and globals",
+                    qualifier.as_str()
+                ))
+                .with_span(span));
+            }
+        }
+        let name = match kind {
+            DeclKind::Rewrite | DeclKind::Other => None,
+            DeclKind::Index if self.peek_keyword(0) == Some("on") => None,
+            _ => self.name(),
+        };
+
+        let mut decl = Declaration {
+            verb,
+            modifiers,
+            cardinality: cardinality.map(|(c, _)| c),
+            kind,
+            name,
+            extending: Vec::new(),
+            target: Vec::new(),
+            expr: None,
+            args: None,
+            clauses: Vec::new(),
+            body: Vec::new(),
+            subcommand: None,
+            span: Span::default(),
+        };
+
+        while let Some(kind) = self.peek_kind(0) {
+            match kind {
+                Kind::Semicolon => {
+                    self.next();
+                    break;
+                }
+                Kind::CloseBrace => break,
+                Kind::OpenBrace => {
+                    let open = self.next().unwrap().span;
+                    decl.body = self.block(Some(open))?;
+                    break;
+                }
+                Kind::Keyword(Keyword("extending")) => {
+                    self.next();
+                    decl.extending = self.type_list()?;
+                }
+                Kind::Arrow | Kind::Colon => {
+                    self.next();
+                    decl.target = self.type_union()?;
+                }
+                Kind::Assign => {
+                    self.next();
+                    decl.expr = Some(self.expr_until_end()?);
+                }
+                Kind::OpenParen if decl.args.is_none() && decl.clauses.is_empty() => {
+                    decl.args = Some(self.group_inner()?);
+                }
+                Kind::Keyword(Keyword(kw)) if CLAUSES.contains(&kw) => {
+                    self.next();
+                    let expr = self.clause_expr()?;
+                    decl.clauses.push(Clause { keyword: kw, expr });
+                }
+                // single sub-command: `alter type User rename to Person`
+                Kind::Keyword(_) if decl.verb == Some(Verb::Alter) => match self.subcommand()? {
+                    Some(subcommand) => decl.body.push(subcommand),
+                    None => self.skip_token_or_group()?,
+                },
+                _ => {
+                    // unknown syntax, skip the token (and the whole group)
+                    self.skip_token_or_group()?;
+                }
+            }
+        }
+        decl.span = Span {
+            start,
+            end: self.last_end(),
+        };
+        Ok(decl)
+    }
+
+    fn kind(&mut self) -> DeclKind {
+        use DeclKind::*;
+
+        if self.is_short_pointer(0) {
+            let is_field = matches!(self.peek_kind(1), Some(Kind::Assign))
+                && self
+                    .peek()
+                    .is_some_and(|t| FIELDS.iter().any(|f| t.text.eq_ignore_ascii_case(f)));
+            return if is_field { Field } else { Pointer };
+        }
+        let (kind, len) = match (self.peek_keyword(0), self.peek_keyword(1)) {
+            (Some("type"), _) => (ObjectType, 1),
+            (Some("scalar"), Some("type")) => (ScalarType, 2),
+            (Some("link"), _) => (Link, 1),
+            (Some("property"), _) => (Property, 1),
+            (Some("constraint"), _) => (Constraint, 1),
+            (Some("index"), _) => (Index, 1),
+            (Some("annotation"), _) => (Annotation, 1),
+            (Some("alias"), _) => (Alias, 1),
+            (Some("function"), _) => (Function, 1),
+            (Some("global"), _) => (Global, 1),
+            (Some("module"), _) => (Module, 1),
+            (Some("using"), Some("extension")) => (Extension, 2),
+            (Some("using"), Some("future")) => (Future, 2),
+            (Some("extension"), _) => (Extension, 1),
+            (Some("future"), _) => (Future, 1),
+            (Some("access"), Some("policy")) => (AccessPolicy, 2),
+            (Some("trigger"), _) => (Trigger, 1),
+            (Some("rewrite"), _) => (Rewrite, 1),
+            (Some("permission"), _) => (Permission, 1),
+            _ => (Other, 0),
+        };
+        for _ in 0..len {
+            self.next();
+        }
+        kind
+    }
+
+    fn name(&mut self) -> Option<Name> {
+        if !is_name_like(self.peek_kind(0)) {
+            return None;
+        }
+        let first = self.next().unwrap();
+        let mut text = token_name(&first).to_string();
+        let mut span = first.span;
+        while self.peek_kind(0) == Some(Kind::Namespace) && is_name_like(self.peek_kind(1)) {
+            self.next();
+            let part = self.next().unwrap();
+            text.push_str("::");
+            text.push_str(token_name(&part));
+            span = span.extend(&part.span);
+        }
+        Some(Name { text, span })
+    }
+
+    fn type_list(&mut self) -> Result<Vec<TypeRef>, Error> {
+        let mut types = vec![self.type_ref()?];
+        while self.peek_kind(0) == Some(Kind::Comma) {
+            self.next();
+            types.push(self.type_ref()?);
+        }
+        Ok(types)
+    }
+
+    fn type_union(&mut self) -> Result<Vec<TypeRef>, Error> {
+        let mut types = vec![self.type_ref()?];
+        while self.peek_kind(0) == Some(Kind::Pipe) {
+            self.next();
+            types.push(self.type_ref()?);
+        }
+        Ok(types)
+    }
+
+    fn type_ref(&mut self) -> Result<TypeRef, Error> {
+        let mut element_name = None;
+        if is_name_like(self.peek_kind(0)) && self.peek_kind(1) == Some(Kind::Colon) {
+            let tok = self.next().unwrap();
+            element_name = Some(Name {
+                text: token_name(&tok).to_string(),
+                span: tok.span,
+            });
+            self.next();
+        }
+        let name = match self.name() {
+            Some(name) => name,
+            None => {
+                let span = self.peek().map_or(self.eof_span(), |t| t.span);
+                return Err(Error::new("Expected a type name").with_span(span));
+            }
+        };
+        let is_enum = matches!(&name.text[..], "enum" | "std::enum");
+        let mut span = name.span;
+        let mut args = Vec::new();
+        if self.peek_kind(0) == Some(Kind::Less) {
+            self.next();
+            loop {
+                match self.peek() {
+                    // trailing comma
+                    Some(tok) if is_enum && tok.kind == Kind::Greater && !args.is_empty() => {}
+                    // empty members are reported by `enums::check`
+                    Some(tok) if is_enum && matches!(tok.kind, Kind::Comma | Kind::Greater) => {
+                        let at = Span {
+                            start: tok.span.start,
+                            end: tok.span.start,
+                        };
+                        args.push(TypeRef {
+                            element_name: None,
+                            name: Name {
+                                text: String::new(),
+                                span: at,
+                            },
+                            args: Vec::new(),
+                            span: at,
+                        });
+                    }
+                    _ => args.push(self.type_ref()?),
+                }
+                match self.next() {
+                    Some(Token {
+                        kind: Kind::Comma, ..
+                    }) => continue,
+                    Some(
+                        tok @ Token {
+                            kind: Kind::Greater,
+                            ..
+                        },
+                    ) => {
+                        span = span.extend(&tok.span);
+                        break;
+                    }
+                    Some(tok) => {
+                        return Err(
+                            Error::new(format!("Unexpected '{}'", tok.text)).with_span(tok.span)
+                        );
+                    }
+                    None => return Err(Error::new("Missing '>'").with_span(self.eof_span())),
+                }
+            }
+        }
+        Ok(TypeRef {
+            element_name,
+            name,
+            args,
+            span,
+        })
+    }
+
+    /// Reads tokens until a `;` or an unmatched `}`
+    fn expr_until_end(&mut self) -> Result<Expr, Error> {
+        let start = self.pos;
+        let mut depth = 0usize;
+        while let Some(kind) = self.peek_kind(0) {
+            match kind {
+                Kind::Semicolon if depth == 0 => break,
+                Kind::CloseBrace | Kind::CloseParen | Kind::CloseBracket if depth == 0 => break,
+                Kind::OpenBrace | Kind::OpenParen | Kind::OpenBracket => depth += 1,
+                Kind::CloseBrace | Kind::CloseParen | Kind::CloseBracket => depth -= 1,
+                _ => {}
+            }
+            self.next();
+        }
+        if depth > 0 {
+            return Err(Error::new("Missing closing bracket").with_span(self.eof_span()));
+        }
+        Ok(self.expr_from(start, self.pos))
+    }
+
+    /// Reads a parenthesized group, returning tokens inside of it
+    fn group_inner(&mut self) -> Result<Expr, Error> {
+        let open = self.peek().map_or(0, |t| t.span.start);
+        let start = self.pos + 1;
+        self.skip_token_or_group()?;
+        let mut expr = self.expr_from(start, self.pos - 1);
+        expr.span = Span {
+            start: open,
+            end: self.last_end(),
+        };
+        Ok(expr)
+    }
+
+    fn clause_expr(&mut self) -> Result<Expr, Error> {
+        if self.peek_kind(0) == Some(Kind::OpenParen) {
+            return self.group_inner();
+        }
+        let start = self.pos;
+        while let Some(kind) = self.peek_kind(0) {
+            match kind {
+                Kind::Semicolon | Kind::OpenBrace | Kind::CloseBrace => break,
+                Kind::Keyword(Keyword(kw)) if CLAUSES.contains(&kw) => break,
+                _ => self.skip_token_or_group()?,
+            }
+        }
+        Ok(self.expr_from(start, self.pos))
+    }
+
+    fn skip_token_or_group(&mut self) -> Result<(), Error> {
+        let Some(first) = self.next() else {
+            return Ok(());
+        };
+        if !matches!(
+            first.kind,
+            Kind::OpenParen | Kind::OpenBracket | Kind::OpenBrace
+        ) {
+            return Ok(());
+        }
+        let mut depth = 1usize;
+        while depth > 0 {
+            match self.next().map(|t| t.kind) {
+                Some(Kind::OpenParen | Kind::OpenBracket | Kind::OpenBrace) => depth += 1,
+                Some(Kind::CloseParen | Kind::CloseBracket | Kind::CloseBrace) => depth -= 1,
+                Some(_) => {}
+                None => {
+                    return Err(Error::new("Missing closing bracket").with_span(first.span));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn expr_from(&self, start: usize, end: usize) -> Expr {
+        let tokens: Vec<_> = self.tokens[start..end]
+            .iter()
+            .map(|t| t.clone().cloned())
+            .collect();
+        let span = match (tokens.first(), tokens.last()) {
+            (Some(first), Some(last)) => first.span.combine(last.span),
+            _ => {
+                let pos = self
+                    .tokens
+                    .get(start)
+                    .map_or(self.last_end(), |t| t.span.start);
+                Span {
+                    start: pos,
+                    end: pos,
+                }
+            }
+        };
+        Expr { tokens, span }
+    }
+}
+
+pub(crate) fn is_name_like(kind: Option<Kind>) -> bool {
+    match kind {
+        Some(Kind::Ident) => true,
+        Some(Kind::Keyword(kw)) => !kw.is_reserved(),
+        _ => false,
+    }
+}
+
+/// Returns unquoted name of an identifier or a keyword token
+pub(crate) fn token_name<'t>(token: &'t Token) -> &'t str {
+    match &token.value {
+        Some(crate::tokenizer::Value::String(s)) => s,
+        _ => &token.text,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse, Cardinality, DeclKind};
+
+    #[test]
+    fn indexes() {
+        let text = "
+            type Foo {
+                index on ((.a, .b)) except (.c);
+                deferred index fts::index on (fts::with_options(.a)) except (.c);
+                deferred index myindex(num := 13, val := 'ab') on ((.a, .b)) {
+                    annotation title := 'Tuple index';
+                };
+            }
+        ";
+        let doc = parse(text).unwrap();
+        let indexes = &doc.declarations[0].body;
+        assert!(indexes.iter().all(|d| d.kind == DeclKind::Index));
+        let src = |span: crate::position::Span| &text[span.start as usize..span.end as usize];
+
+        assert_eq!(indexes[0].name, None);
+        assert_eq!(src(indexes[0].clause("on").unwrap().span), "((.a, .b))");
+        assert_eq!(src(indexes[0].clause("except").unwrap().span), "(.c)");
+
+        assert!(indexes[1].has_modifier("deferred"));
+        assert_eq!(indexes[1].name_text(), Some("fts::index"));
+        assert_eq!(indexes[1].args, None);
+        assert_eq!(
+            src(indexes[1].clause("on").unwrap().span),
+            "(fts::with_options(.a))"
+        );
+
+        assert_eq!(indexes[2].name_text(), Some("myindex"));
+        assert_eq!(
+            src(indexes[2].args.as_ref().unwrap().span),
+            "(num := 13, val := 'ab')"
+        );
+        assert_eq!(indexes[2].clause("except"), None);
+        assert_eq!(indexes[2].body[0].kind, DeclKind::Annotation);
+    }
+
+    #[test]
+    fn cardinality() {
+        let doc = parse(
+            "
+            type User {
+                required multi friends: User;
+                single link best_friend -> User;
+                name: str;
+            }
+            multi global ids := {1, 2};
+            ",
+        )
+        .unwrap();
+        let user = &doc.declarations[0];
+        assert_eq!(user.body[0].cardinality, Some(Cardinality::Multi));
+        assert!(user.body[0].has_modifier("required"));
+        assert!(!user.body[0].has_modifier("multi"));
+        assert_eq!(user.body[1].cardinality, Some(Cardinality::Single));
+        assert_eq!(user.body[2].cardinality, None);
+        assert!(doc.declarations[1].is_multi());
+
+        let err = parse("multi type User;").unwrap_err();
+        assert_eq!(
+            err.message,
+            "'multi' is only allowed on links, properties and globals"
+        );
+        assert_eq!((err.span.start, err.span.end), (0, 5));
+        let err = parse("type User { multi single friends: User; }").unwrap_err();
+        assert_eq!(err.message, "'single' and 'multi' cannot be used together");
+        assert_eq!(err.span.start, 18);
+        let err = parse("type User { multi multi link friends -> User; }").unwrap_err();
+        assert_eq!(err.message, "'multi' is specified twice");
+    }
+
+    #[test]
+    fn tuples() {
+        let doc = parse(
+            "
+            type User {
+                point: tuple<x: float64, `y`: float64>;
+                pair: tuple<str, array<tuple<int64, name: str>>>;
+            }
+            ",
+        )
+        .unwrap();
+        let user = &doc.declarations[0];
+        let point = &user.body[0].target[0];
+        assert!(point.is_tuple());
+        assert_eq!(point.tuple_names(), Some(vec!["x", "y"]));
+        assert_eq!(point.args[0].name.text, "float64");
+
+        let pair = &user.body[1].target[0];
+        assert_eq!(pair.tuple_names(), None);
+        let inner = &pair.args[1].args[0];
+        assert_eq!(inner.args[1].element_name.as_ref().unwrap().text, "name");
+        assert_eq!(inner.tuple_names(), None);
+        assert!(!pair.args[1].is_tuple());
+    }
+}
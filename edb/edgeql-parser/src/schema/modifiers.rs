@@ -0,0 +1,181 @@
+//! Checks of declaration modifiers
+//!
+//! [MODIFIER_KINDS] lists the kinds of declarations each modifier is allowed
+//! on, and [CONFLICTS] the modifiers that cannot be used together. [check]
+//! reports declarations that break them, as well as modifiers used in the
+//! wrong place: `abstract` links, properties, constraints, annotations and
+//! indexes are only declared at the top level of a module, and
+//! `overloaded` pointers only inside of other declarations.
+//!
+//! `single` and `multi` are checked by the parser, and the kinds of
+//! declarations `delegated` is allowed on by [constraints::check].
+//!
+//! [constraints::check]: crate::schema::constraints::check
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::schema::DeclKind::{self, *};
+use crate::schema::{Declaration, Document};
+
+const POINTERS: &[DeclKind] = &[Link, Property, Pointer, Global];
+
+/// Modifiers with the kinds of declarations they are allowed on and the
+/// description of these kinds for error messages
+pub const MODIFIER_KINDS: &[(&str, &[DeclKind], &str)] = &[
+    (
+        "abstract",
+        &[
+            ObjectType, ScalarType, Link, Property, Constraint, Annotation, Index,
+        ],
+        "types, links, properties, constraints, annotations and indexes",
+    ),
+    ("final", &[ScalarType], "scalar types"),
+    (
+        "overloaded",
+        &[Link, Property, Pointer],
+        "links and properties",
+    ),
+    ("delegated", &[Constraint], "constraints"),
+    ("required", POINTERS, "links, properties and globals"),
+    ("optional", POINTERS, "links, properties and globals"),
+    ("deferred", &[Index], "indexes"),
+];
+
+/// Modifiers that cannot be used on the same declaration
+pub const CONFLICTS: &[(&str, &str)] = &[
+    ("abstract", "final"),
+    ("abstract", "overloaded"),
+    ("abstract", "required"),
+    ("abstract", "optional"),
+    ("required", "optional"),
+];
+
+/// Returns true if the modifier is allowed on declarations of the kind
+pub fn is_allowed(modifier: &str, kind: DeclKind) -> bool {
+    MODIFIER_KINDS
+        .iter()
+        .find(|(m, _, _)| *m == modifier)
+        .is_some_and(|(_, kinds, _)| kinds.contains(&kind))
+}
+
+/// Returns errors of modifiers that are not allowed where they are used
+pub fn check(doc: &Document) -> Vec<Diagnostic> {
+    let mut errors = Vec::new();
+    doc.walk(|decl, parents| check_declaration(decl, parents, &mut errors));
+    errors
+}
+
+fn check_declaration(decl: &Declaration, parents: &[&Declaration], errors: &mut Vec<Diagnostic>) {
+    // unknown declarations, like `abstract inheritable annotation`
+    if decl.kind == Other {
+        return;
+    }
+    let top_level = parents.iter().all(|p| p.kind == Module);
+    for (idx, modifier) in decl.modifiers.iter().enumerate() {
+        let keyword = modifier.keyword;
+        let error = |code: &str, args: &[(&str, String)]| {
+            let mut args = args.to_vec();
+            args.push(("keyword", keyword.into()));
+            Diagnostic::with_code(Severity::Error, code, &args, modifier.span)
+        };
+        let previous = &decl.modifiers[..idx];
+        if previous.iter().any(|m| m.keyword == keyword) {
+            errors.push(error("duplicate-modifier", &[]));
+            continue;
+        }
+        let conflict = previous.iter().find(|m| {
+            CONFLICTS.contains(&(m.keyword, keyword)) || CONFLICTS.contains(&(keyword, m.keyword))
+        });
+        if let Some(other) = conflict {
+            errors.push(error(
+                "conflicting-modifiers",
+                &[("other", other.keyword.into())],
+            ));
+            continue;
+        }
+        let Some((_, kinds, description)) = MODIFIER_KINDS.iter().find(|(m, _, _)| *m == keyword)
+        else {
+            continue;
+        };
+        if keyword == "delegated" {
+            continue;
+        }
+        if !kinds.contains(&decl.kind) {
+            errors.push(error(
+                "misplaced-modifier",
+                &[("kinds", description.to_string())],
+            ));
+        } else if keyword == "abstract" && !top_level {
+            errors.push(error("nested-abstract", &[]));
+        } else if keyword == "overloaded" && top_level {
+            errors.push(error("top-level-overloaded", &[]));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{check, is_allowed};
+    use crate::schema::{parse, DeclKind};
+
+    fn errors(text: &str) -> Vec<String> {
+        check(&parse(text).unwrap())
+            .into_iter()
+            .map(|e| {
+                let span = e.span;
+                format!(
+                    "{} @{}",
+                    e.message,
+                    &text[span.start as usize..span.end as usize]
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn allowed() {
+        assert!(is_allowed("required", DeclKind::Global));
+        assert!(!is_allowed("deferred", DeclKind::Constraint));
+        assert!(errors(
+            "
+            abstract type Named {
+                required name: str;
+                deferred index fts::index on (.name);
+            }
+            abstract link friends { strength: float64 }
+            type User extending Named {
+                overloaded required name: str;
+                optional multi link friends: User;
+            }
+            final scalar type Code extending str;
+            "
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn invalid() {
+        assert_eq!(
+            errors(
+                "
+                type User {
+                    required optional name: str;
+                    abstract link friends: User;
+                    required required nick: str;
+                    overloaded index on (.nick);
+                }
+                required type Named;
+                abstract overloaded property name;
+                abstract final scalar type Code extending str;
+                "
+            ),
+            [
+                "'required' and 'optional' cannot be used together @optional",
+                "'abstract' is only allowed on declarations of modules @abstract",
+                "'required' is specified twice @required",
+                "'overloaded' is only allowed on links and properties @overloaded",
+                "'required' is only allowed on links, properties and globals @required",
+                "'abstract' and 'overloaded' cannot be used together @overloaded",
+                "'abstract' and 'final' cannot be used together @final",
+            ]
+        );
+    }
+}
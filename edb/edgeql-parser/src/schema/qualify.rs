@@ -0,0 +1,292 @@
+//! Expansion of module names
+//!
+//! Rewrites every name that refers to a definition of the document into its
+//! fully-qualified form, taking into account `module` blocks, `with module`
+//! and `with x as module y` in expressions. Names which are not defined in
+//! the document (e.g. from the standard library) are left as is.
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+
+use crate::helpers::quote_name;
+use crate::position::Span;
+use crate::schema::deps::DependencyGraph;
+use crate::schema::{is_name_like, token_name, DeclKind, Declaration, Document, Name, TypeRef};
+use crate::tokenizer::{Kind, Token, Value};
+
+/// Returns a copy of the document with all names fully-qualified
+pub fn qualify(doc: &Document) -> Document {
+    let graph = DependencyGraph::build(doc);
+    let known: HashSet<String> = graph.definitions().iter().map(|d| d.name.clone()).collect();
+
+    let mut result = doc.clone();
+    qualify_declarations(&mut result.declarations, None, true, &known);
+    result
+}
+
+/// Rewrites names in the expression tokens, `module` is the one the
+/// expression is written in. Names for which `is_defined` returns false for
+/// the qualified form are kept unchanged.
+///
+/// `with module` and module aliases apply to the rest of the expression.
+pub fn qualify_expr(
+    tokens: &[Token<'static>],
+    module: Option<&str>,
+    is_defined: impl Fn(&str) -> bool,
+) -> Vec<Token<'static>> {
+    let mut scope = Scope {
+        module: module.map(|m| m.to_string()),
+        aliases: HashMap::new(),
+    };
+    let mut result = Vec::with_capacity(tokens.len());
+    let mut i = 0;
+    while i < tokens.len() {
+        let tok = &tokens[i];
+        let prev_kind = i.checked_sub(1).map(|p| tokens[p].kind);
+
+        // with module foo
+        if is_keyword(Some(tok), "module")
+            && (is_keyword(i.checked_sub(1).map(|p| &tokens[p]), "with")
+                || prev_kind == Some(Kind::Comma))
+        {
+            let (name, end) = read_name(tokens, i + 1);
+            if !name.is_empty() {
+                scope.module = Some(name);
+            }
+            result.extend_from_slice(&tokens[i..end]);
+            i = end;
+            continue;
+        }
+
+        let after_step = matches!(
+            prev_kind,
+            Some(Kind::Dot | Kind::BackwardLink | Kind::OptionalLink | Kind::At)
+        );
+        if !is_name_like(Some(tok.kind)) || after_step {
+            result.push(tok.clone());
+            i += 1;
+            continue;
+        }
+
+        let (name, end) = read_name(tokens, i);
+        // with foo as module bar
+        if is_keyword(tokens.get(end), "as") && is_keyword(tokens.get(end + 1), "module") {
+            let (target, target_end) = read_name(tokens, end + 2);
+            if !target.is_empty() {
+                scope.aliases.insert(name, target);
+            }
+            result.extend_from_slice(&tokens[i..target_end]);
+            i = target_end;
+            continue;
+        }
+
+        let is_label = matches!(
+            tokens.get(end).map(|t| t.kind),
+            Some(Kind::Assign | Kind::Colon)
+        );
+        match scope.resolve(&name, &is_defined) {
+            Some(full) if !is_label => {
+                let span = tokens[i].span.extend(&tokens[end - 1].span);
+                push_name(&mut result, &full, span);
+            }
+            _ => result.extend_from_slice(&tokens[i..end]),
+        }
+        i = end;
+    }
+    result
+}
+
+struct Scope {
+    module: Option<String>,
+    aliases: HashMap<String, String>,
+}
+
+impl Scope {
+    fn resolve(&self, name: &str, is_defined: impl Fn(&str) -> bool) -> Option<String> {
+        if let Some((first, rest)) = name.split_once("::") {
+            return self
+                .aliases
+                .get(first)
+                .map(|module| format!("{module}::{rest}"));
+        }
+        let module = self.module.as_ref()?;
+        let full = format!("{module}::{name}");
+        is_defined(&full).then_some(full)
+    }
+}
+
+fn qualify_declarations(
+    decls: &mut [Declaration],
+    module: Option<&str>,
+    top_level: bool,
+    known: &HashSet<String>,
+) {
+    let is_defined = |name: &str| known.contains(name);
+    let scope = Scope {
+        module: module.map(|m| m.to_string()),
+        aliases: HashMap::new(),
+    };
+    for decl in decls {
+        if decl.kind == DeclKind::Module {
+            let Some(name) = &mut decl.name else {
+                continue;
+            };
+            if let Some(module) = module {
+                name.text = format!("{module}::{}", name.text);
+            }
+            let full = name.text.clone();
+            qualify_declarations(&mut decl.body, Some(&full), true, known);
+            continue;
+        }
+
+        if let Some(name) = &mut decl.name {
+            if top_level && decl.kind.is_schema_object() {
+                if let (Some(module), false) = (module, name.text.contains("::")) {
+                    name.text = format!("{module}::{}", name.text);
+                }
+            } else if matches!(decl.kind, DeclKind::Constraint | DeclKind::Annotation) {
+                qualify_name(name, &scope, &is_defined);
+            }
+        }
+        for ty in decl.extending.iter_mut().chain(decl.target.iter_mut()) {
+            qualify_type(ty, &scope, &is_defined);
+        }
+        let exprs = decl
+            .expr
+            .iter_mut()
+            .chain(decl.args.iter_mut())
+            .chain(decl.clauses.iter_mut().map(|c| &mut c.expr));
+        for expr in exprs {
+            expr.tokens = qualify_expr(&expr.tokens, module, is_defined);
+        }
+        qualify_declarations(&mut decl.body, module, false, known);
+    }
+}
+
+fn qualify_type(ty: &mut TypeRef, scope: &Scope, is_defined: &impl Fn(&str) -> bool) {
+    qualify_name(&mut ty.name, scope, is_defined);
+    for arg in &mut ty.args {
+        qualify_type(arg, scope, is_defined);
+    }
+}
+
+fn qualify_name(name: &mut Name, scope: &Scope, is_defined: &impl Fn(&str) -> bool) {
+    if let Some(full) = scope.resolve(&name.text, is_defined) {
+        name.text = full;
+    }
+}
+
+fn is_keyword(token: Option<&Token>, keyword: &str) -> bool {
+    matches!(token.map(|t| t.kind), Some(Kind::Keyword(kw)) if kw.0 == keyword)
+}
+
+/// Reads a possibly qualified name starting at `start`, returns the name and
+/// the index of the token after it
+fn read_name(tokens: &[Token], start: usize) -> (String, usize) {
+    let mut name = String::new();
+    let mut i = start;
+    if !is_name_like(tokens.get(i).map(|t| t.kind)) {
+        return (name, i);
+    }
+    name.push_str(token_name(&tokens[i]));
+    i += 1;
+    while i + 1 < tokens.len()
+        && tokens[i].kind == Kind::Namespace
+        && is_name_like(Some(tokens[i + 1].kind))
+    {
+        name.push_str("::");
+        name.push_str(token_name(&tokens[i + 1]));
+        i += 2;
+    }
+    (name, i)
+}
+
+fn push_name(tokens: &mut Vec<Token<'static>>, name: &str, span: Span) {
+    for (idx, part) in name.split("::").enumerate() {
+        if idx > 0 {
+            tokens.push(Token {
+                kind: Kind::Namespace,
+                text: Cow::Borrowed("::"),
+                value: None,
+                span,
+            });
+        }
+        tokens.push(Token {
+            kind: Kind::Ident,
+            text: Cow::Owned(quote_name(part).into_owned()),
+            value: Some(Value::String(part.to_string())),
+            span,
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{qualify, qualify_expr};
+    use crate::schema::{parse, Declaration};
+    use crate::tokenizer::{tokens, Token};
+
+    fn text(tokens: &[Token]) -> String {
+        let texts: Vec<_> = tokens.iter().map(|t| &t.text[..]).collect();
+        texts.join(" ")
+    }
+
+    fn expr(decl: &Declaration) -> String {
+        text(&decl.expr.as_ref().unwrap().tokens)
+    }
+
+    #[test]
+    fn module_blocks() {
+        let doc = parse(
+            "
+            module default {
+                type User extending Named {
+                    link profile: other::Profile;
+                    property nick := (select Named filter .name = 'x').name;
+                }
+                abstract type Named;
+            }
+            module other {
+                type Profile {
+                    link user: default::User;
+                    property str_len: str;
+                }
+            }
+            ",
+        )
+        .unwrap();
+        let doc = qualify(&doc);
+        let user = &doc.declarations[0].body[0];
+        assert_eq!(user.name_text(), Some("default::User"));
+        assert_eq!(user.extending[0].name.text, "default::Named");
+        assert_eq!(user.body[0].target[0].name.text, "other::Profile");
+        assert_eq!(
+            expr(&user.body[1]),
+            "( select default :: Named filter . name = 'x' ) . name"
+        );
+        let profile = &doc.declarations[1].body[0];
+        assert_eq!(profile.name_text(), Some("other::Profile"));
+        assert_eq!(profile.body[1].target[0].name.text, "str");
+    }
+
+    #[test]
+    fn with_module() {
+        let defined = |name: &str| matches!(name, "foo::User" | "default::User");
+        assert_eq!(
+            text(&qualify_expr(
+                &tokens("with module foo, b as module bar select (User, b::Post, count(User))"),
+                Some("default"),
+                defined,
+            )),
+            "with module foo , b as module bar \
+             select ( foo :: User , bar :: Post , count ( foo :: User ) )"
+        );
+        assert_eq!(
+            text(&qualify_expr(
+                &tokens("select User { User := 1 }"),
+                Some("default"),
+                defined,
+            )),
+            "select default :: User { User := 1 }"
+        );
+    }
+}
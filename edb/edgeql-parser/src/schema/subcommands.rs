@@ -0,0 +1,189 @@
+//! Sub-commands of `alter` statements
+//!
+//! Bodies of `alter type`, `alter link` and `alter property` (and the
+//! statements themselves, like `alter type User rename to Person`) contain
+//! commands that are not declarations. The ones that change the pointer or
+//! the type itself are parsed into a [Subcommand], other ones are kept as
+//! declarations of kind [DeclKind::Other].
+//!
+//! Like the rest of [crate::schema], this reads the structure of the
+//! tokens and does not replace the DDL grammar: a sub-command it does not
+//! know is kept as a [DeclKind::Other] declaration rather than rejected.
+use crate::position::Span;
+use crate::schema::{Cardinality, DeclKind, Declaration, Expr, Name, Reader, TypeRef};
+use crate::tokenizer::{Error, Kind};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Subcommand {
+    /// `set type <type> [using (<expr>)]`
+    SetType {
+        target: TypeRef,
+        using: Option<Expr>,
+    },
+    /// `rename to <name>`
+    Rename { to: Name },
+    /// `set owned`
+    SetOwned,
+    /// `drop owned`
+    DropOwned,
+    /// `set required [using (<expr>)]`
+    SetRequired { using: Option<Expr> },
+    /// `set optional`
+    SetOptional,
+    /// `set single [using (<expr>)]` or `set multi`
+    SetCardinality {
+        cardinality: Cardinality,
+        using: Option<Expr>,
+    },
+    /// `reset <field>`, like `reset optionality` or `reset default`
+    Reset { field: String },
+}
+
+impl Reader<'_> {
+    /// Reads a sub-command, except for the `;` after it, returns `None`
+    /// without reading anything if the tokens do not start one
+    pub(super) fn subcommand(&mut self) -> Result<Option<Declaration>, Error> {
+        let start = self.peek().map_or(0, |t| t.span.start);
+        let subcommand = match (self.peek_keyword(0), self.peek_keyword(1)) {
+            (Some("set"), Some("type")) => {
+                self.pos += 2;
+                let target = self.type_ref()?;
+                Subcommand::SetType {
+                    target,
+                    using: self.using()?,
+                }
+            }
+            (Some("rename"), Some("to")) => {
+                self.pos += 2;
+                let Some(to) = self.name() else {
+                    let span = self.peek().map_or(self.eof_span(), |t| t.span);
+                    return Err(Error::new("Expected a name").with_span(span));
+                };
+                Subcommand::Rename { to }
+            }
+            (Some("set"), Some("owned")) => {
+                self.pos += 2;
+                Subcommand::SetOwned
+            }
+            (Some("drop"), Some("owned")) => {
+                self.pos += 2;
+                Subcommand::DropOwned
+            }
+            (Some("set"), Some("required")) => {
+                self.pos += 2;
+                Subcommand::SetRequired {
+                    using: self.using()?,
+                }
+            }
+            (Some("set"), Some("optional")) => {
+                self.pos += 2;
+                Subcommand::SetOptional
+            }
+            (Some("set"), Some(kw @ ("single" | "multi"))) => {
+                self.pos += 2;
+                Subcommand::SetCardinality {
+                    cardinality: Cardinality::from_keyword(kw).unwrap(),
+                    using: self.using()?,
+                }
+            }
+            (Some("reset"), Some(field)) => {
+                self.pos += 2;
+                Subcommand::Reset {
+                    field: field.to_string(),
+                }
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(Declaration {
+            verb: None,
+            modifiers: Vec::new(),
+            cardinality: None,
+            kind: DeclKind::Subcommand,
+            name: None,
+            extending: Vec::new(),
+            target: Vec::new(),
+            expr: None,
+            args: None,
+            clauses: Vec::new(),
+            body: Vec::new(),
+            subcommand: Some(subcommand),
+            span: Span {
+                start,
+                end: self.last_end(),
+            },
+        }))
+    }
+
+    fn using(&mut self) -> Result<Option<Expr>, Error> {
+        if self.peek_keyword(0) != Some("using") {
+            return Ok(None);
+        }
+        self.next();
+        if self.peek_kind(0) != Some(Kind::OpenParen) {
+            let span = self.peek().map_or(self.eof_span(), |t| t.span);
+            return Err(Error::new("Expected '('").with_span(span));
+        }
+        self.group_inner().map(Some)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Subcommand;
+    use crate::schema::{parse, Cardinality, DeclKind};
+
+    #[test]
+    fn alter() {
+        let text = "
+            alter type User {
+                alter property name {
+                    set type str using (<str>.name);
+                    set required using ('unknown');
+                    reset default;
+                    drop owned;
+                };
+                alter link friends set multi;
+                rename to Person;
+            };
+            alter property title rename to label;
+        ";
+        let doc = parse(text).unwrap();
+        let user = &doc.declarations[0].body;
+        let name = &user[0].body;
+        assert!(name.iter().all(|d| d.kind == DeclKind::Subcommand));
+        let src = |span: crate::position::Span| &text[span.start as usize..span.end as usize];
+        let Some(Subcommand::SetType { target, using }) = &name[0].subcommand else {
+            panic!("{:?}", name[0]);
+        };
+        assert_eq!(target.name.text, "str");
+        assert_eq!(src(using.as_ref().unwrap().span), "(<str>.name)");
+        assert!(matches!(
+            &name[1].subcommand,
+            Some(Subcommand::SetRequired { using: Some(_) })
+        ));
+        assert_eq!(
+            name[2].subcommand,
+            Some(Subcommand::Reset {
+                field: "default".into()
+            })
+        );
+        assert_eq!(name[3].subcommand, Some(Subcommand::DropOwned));
+        assert_eq!(src(name[3].span), "drop owned;");
+
+        assert_eq!(
+            user[1].body[0].subcommand,
+            Some(Subcommand::SetCardinality {
+                cardinality: Cardinality::Multi,
+                using: None
+            })
+        );
+        let Some(Subcommand::Rename { to }) = &user[2].subcommand else {
+            panic!("{:?}", user[2]);
+        };
+        assert_eq!(to.text, "Person");
+        let Some(Subcommand::Rename { to }) = &doc.declarations[1].body[0].subcommand else {
+            panic!("{:?}", doc.declarations[1]);
+        };
+        assert_eq!(to.text, "label");
+    }
+}
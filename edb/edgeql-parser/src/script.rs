@@ -0,0 +1,292 @@
+//! Script container with per-statement checksums, for backup and migration
+//! tooling
+//!
+//! The container is still a valid EdgeQL script, all metadata is in
+//! comments:
+//!
+//! ```text
+//! #! edgeql-script 1
+//! #! server-version: 6.0
+//! select 1;
+//! #! sha256: <checksum of the statement>
+//! #! end: 1
+//! ```
+//!
+//! Every statement is followed by its checksum and the trailer has the number
+//! of statements, so [ScriptReader] detects both truncation and corruption.
+use std::io::{self, Write};
+
+use sha2::digest::Digest;
+
+use crate::preparser::full_statement;
+
+const HEADER: &str = "#! edgeql-script ";
+const VERSION: &str = "1";
+const SERVER_VERSION: &str = "#! server-version: ";
+const CHECKSUM: &str = "#! sha256: ";
+const END: &str = "#! end: ";
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ScriptError {
+    #[error("not an EdgeQL script container: missing header")]
+    MissingHeader,
+    #[error("unsupported script container version {0}")]
+    UnsupportedVersion(String),
+    #[error("missing server version")]
+    MissingServerVersion,
+    #[error("statement {index} at byte {offset} has no checksum")]
+    MissingChecksum { index: u64, offset: usize },
+    #[error("statement {index} at byte {offset} is corrupted: checksum mismatch")]
+    ChecksumMismatch { index: u64, offset: usize },
+    #[error("script is truncated after {statements} statements")]
+    Truncated { statements: u64 },
+    #[error("script has {found} statements, but {expected} are expected")]
+    CountMismatch { found: u64, expected: u64 },
+    #[error("unexpected data after the end of script at byte {offset}")]
+    TrailingData { offset: usize },
+}
+
+/// Writes statements into a script container
+pub struct ScriptWriter<W: Write> {
+    out: W,
+    statements: u64,
+}
+
+/// Reads statements of a script container, verifying their checksums
+///
+/// Iteration stops after the first error.
+pub struct ScriptReader<'a> {
+    text: &'a str,
+    pos: usize,
+    server_version: &'a str,
+    statements: u64,
+    done: bool,
+}
+
+fn checksum(statement: &str) -> String {
+    let hash = base32::encode(
+        base32::Alphabet::Rfc4648 { padding: false },
+        &sha2::Sha256::digest(statement.as_bytes()),
+    );
+    hash.to_ascii_lowercase()
+}
+
+fn invalid_input(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+impl<W: Write> ScriptWriter<W> {
+    /// Writes the header with the version of the server the script is for
+    pub fn new(mut out: W, server_version: &str) -> io::Result<Self> {
+        if server_version.contains(['\n', '\r']) {
+            return Err(invalid_input("server version must be a single line"));
+        }
+        writeln!(out, "{HEADER}{VERSION}\n{SERVER_VERSION}{server_version}")?;
+        Ok(ScriptWriter { out, statements: 0 })
+    }
+
+    /// Writes a single statement, which must be terminated by a semicolon
+    pub fn write_statement(&mut self, statement: &str) -> io::Result<()> {
+        let statement = statement.trim();
+        if full_statement(statement.as_bytes(), None) != Ok(statement.len()) {
+            return Err(invalid_input(
+                "expected a single statement terminated by a semicolon",
+            ));
+        }
+        let checksum = checksum(statement);
+        writeln!(self.out, "{statement}\n{CHECKSUM}{checksum}")?;
+        self.statements += 1;
+        Ok(())
+    }
+
+    /// Writes the trailer and returns the output
+    pub fn finish(mut self) -> io::Result<W> {
+        writeln!(self.out, "{END}{}", self.statements)?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+impl<'a> ScriptReader<'a> {
+    /// Reads the header of the script
+    pub fn new(text: &'a str) -> Result<Self, ScriptError> {
+        let mut reader = ScriptReader {
+            text,
+            pos: 0,
+            server_version: "",
+            statements: 0,
+            done: false,
+        };
+        let version = reader
+            .line()
+            .and_then(|line| line.strip_prefix(HEADER))
+            .ok_or(ScriptError::MissingHeader)?;
+        if version != VERSION {
+            return Err(ScriptError::UnsupportedVersion(version.into()));
+        }
+        reader.server_version = reader
+            .line()
+            .and_then(|line| line.strip_prefix(SERVER_VERSION))
+            .ok_or(ScriptError::MissingServerVersion)?;
+        Ok(reader)
+    }
+
+    pub fn server_version(&self) -> &'a str {
+        self.server_version
+    }
+
+    /// Next line without the line break, `None` if it is not terminated
+    fn line(&mut self) -> Option<&'a str> {
+        let rest = &self.text[self.pos..];
+        let len = rest.find('\n')?;
+        self.pos += len + 1;
+        Some(&rest[..len])
+    }
+
+    fn read_statement(&mut self) -> Result<Option<&'a str>, ScriptError> {
+        let truncated = ScriptError::Truncated {
+            statements: self.statements,
+        };
+        let rest = &self.text[self.pos..];
+        if rest.starts_with(END) {
+            let expected = self
+                .line()
+                .and_then(|line| line[END.len()..].parse().ok())
+                .ok_or(truncated)?;
+            if expected != self.statements {
+                return Err(ScriptError::CountMismatch {
+                    found: self.statements,
+                    expected,
+                });
+            }
+            if !self.text[self.pos..].trim().is_empty() {
+                return Err(ScriptError::TrailingData { offset: self.pos });
+            }
+            return Ok(None);
+        }
+
+        let offset = self.pos;
+        let index = self.statements;
+        let end = full_statement(rest.as_bytes(), None).map_err(|_| truncated.clone())?;
+        let statement = &rest[..end];
+        self.pos += end;
+        match self.line() {
+            Some("") => {}
+            Some(_) => return Err(ScriptError::MissingChecksum { index, offset }),
+            None => return Err(truncated),
+        }
+        let expected = match self.line() {
+            Some(line) => line
+                .strip_prefix(CHECKSUM)
+                .ok_or(ScriptError::MissingChecksum { index, offset })?,
+            None => return Err(truncated),
+        };
+        if expected != checksum(statement) {
+            return Err(ScriptError::ChecksumMismatch { index, offset });
+        }
+        self.statements += 1;
+        Ok(Some(statement))
+    }
+}
+
+impl<'a> Iterator for ScriptReader<'a> {
+    type Item = Result<&'a str, ScriptError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let result = self.read_statement().transpose();
+        self.done = !matches!(result, Some(Ok(_)));
+        result
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ScriptError, ScriptReader, ScriptWriter};
+
+    fn script() -> String {
+        let mut writer = ScriptWriter::new(Vec::new(), "6.0+abc").unwrap();
+        writer.write_statement("create type User;").unwrap();
+        writer
+            .write_statement("  insert User { note := '\n#! end: 0\n;' };\n")
+            .unwrap();
+        writer.write_statement("select 1;").unwrap();
+        String::from_utf8(writer.finish().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn roundtrip() {
+        let text = script();
+        let reader = ScriptReader::new(&text).unwrap();
+        assert_eq!(reader.server_version(), "6.0+abc");
+        let statements = reader.collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(
+            statements,
+            [
+                "create type User;",
+                "insert User { note := '\n#! end: 0\n;' };",
+                "select 1;",
+            ]
+        );
+
+        let mut writer = ScriptWriter::new(Vec::new(), "6.0").unwrap();
+        assert!(writer.write_statement("select 1; select 2;").is_err());
+        assert!(writer.write_statement("select 1").is_err());
+        assert!(ScriptWriter::new(Vec::new(), "6.0\nselect 1;").is_err());
+    }
+
+    #[test]
+    fn truncated() {
+        let text = script();
+        for len in 0..text.len() {
+            let result = ScriptReader::new(&text[..len])
+                .and_then(|reader| reader.collect::<Result<Vec<_>, _>>());
+            assert!(result.is_err(), "truncated at {len}: {result:?}");
+        }
+        let result = ScriptReader::new(&text[..text.len() - 16])
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>();
+        assert_eq!(result, Err(ScriptError::Truncated { statements: 2 }));
+    }
+
+    #[test]
+    fn corrupted() {
+        let text = script().replace("select 1;", "select 2;");
+        let mut reader = ScriptReader::new(&text).unwrap();
+        assert!(reader.next().unwrap().is_ok());
+        assert!(reader.next().unwrap().is_ok());
+        assert_eq!(
+            reader.next(),
+            Some(Err(ScriptError::ChecksumMismatch {
+                index: 2,
+                offset: 232,
+            }))
+        );
+        assert_eq!(reader.next(), None);
+
+        let text = script().replace("#! end: 3", "#! end: 4");
+        let result = ScriptReader::new(&text)
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>();
+        assert_eq!(
+            result,
+            Err(ScriptError::CountMismatch {
+                found: 3,
+                expected: 4
+            })
+        );
+
+        let text = script() + "drop type User;\n";
+        let result = ScriptReader::new(&text)
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>();
+        assert_eq!(result, Err(ScriptError::TrailingData { offset: 316 }));
+
+        assert_eq!(
+            ScriptReader::new("#! edgeql-script 2\n").err(),
+            Some(ScriptError::UnsupportedVersion("2".into()))
+        );
+    }
+}
@@ -0,0 +1,250 @@
+//! Session module aliases
+//!
+//! `set module`, `set alias ... as module`, `reset module` and `reset alias`
+//! change how names of the following statements of a session are resolved.
+//! [parse_session] reads them into [SessionStatement]s and [AliasTable]
+//! keeps the resulting default module and aliases, so that a REPL can
+//! resolve names of the next queries (e.g. for completions) the way the
+//! server does.
+//!
+//! Only these statements are recognized, from their tokens; the grammar
+//! still parses and validates them when they are sent to the server.
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+use crate::keywords::Keyword;
+use crate::position::Span;
+use crate::resolver::Resolver;
+use crate::schema::{is_name_like, token_name, Name};
+use crate::tokenizer::{Error, Kind, Token, Tokenizer};
+
+/// Module of the names that are not qualified, unless set otherwise
+pub const DEFAULT_MODULE: &str = "default";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SessionCommand {
+    /// `set module <module>`
+    SetModule { module: Name },
+    /// `set alias <alias> as module <module>`
+    SetAlias { alias: Name, module: Name },
+    /// `reset module`
+    ResetModule,
+    /// `reset alias <alias>`
+    ResetAlias { alias: Name },
+    /// `reset alias *`
+    ResetAllAliases,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionStatement {
+    pub command: SessionCommand,
+    /// Span of the statement, without the `;`
+    pub span: Span,
+}
+
+/// Default module and module aliases of a session
+#[derive(Debug, Clone, PartialEq)]
+pub struct AliasTable {
+    pub module: String,
+    /// Module of each alias
+    pub aliases: BTreeMap<String, String>,
+}
+
+impl Default for AliasTable {
+    fn default() -> AliasTable {
+        AliasTable {
+            module: DEFAULT_MODULE.into(),
+            aliases: BTreeMap::new(),
+        }
+    }
+}
+
+impl AliasTable {
+    pub fn apply(&mut self, command: &SessionCommand) {
+        match command {
+            SessionCommand::SetModule { module } => self.module = module.text.clone(),
+            SessionCommand::SetAlias { alias, module } => {
+                self.aliases.insert(alias.text.clone(), module.text.clone());
+            }
+            SessionCommand::ResetModule => self.module = DEFAULT_MODULE.into(),
+            SessionCommand::ResetAlias { alias } => {
+                self.aliases.remove(&alias.text);
+            }
+            SessionCommand::ResetAllAliases => self.aliases.clear(),
+        }
+    }
+
+    /// Applies session statements of the script, fails if the script cannot
+    /// be tokenized or has an invalid session statement, in which case
+    /// the table is not changed
+    pub fn apply_script(&mut self, text: &str) -> Result<(), Error> {
+        for statement in parse_session(text)? {
+            self.apply(&statement.command);
+        }
+        Ok(())
+    }
+
+    /// Replaces an alias at the start of the name by its module, e.g.
+    /// `s::len` by `std::len` after `set alias s as module std`
+    pub fn expand<'a>(&self, name: &'a str) -> Cow<'a, str> {
+        match name.split_once("::") {
+            Some((first, rest)) => match self.aliases.get(first) {
+                Some(module) => format!("{module}::{rest}").into(),
+                None => name.into(),
+            },
+            None => name.into(),
+        }
+    }
+
+    /// Resolves a type name as written in a query of the session
+    pub fn resolve_type(&self, name: &str, resolver: &dyn Resolver) -> Option<String> {
+        resolver.resolve(&self.expand(name), &self.module)
+    }
+}
+
+/// Parses session statements of a script, skipping other statements
+pub fn parse_session(text: &str) -> Result<Vec<SessionStatement>, Error> {
+    let tokens = Tokenizer::new(text)
+        .validated_values()
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut statements = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (idx, tok) in tokens.iter().enumerate() {
+        match tok.kind {
+            Kind::OpenParen | Kind::OpenBracket | Kind::OpenBrace => depth += 1,
+            Kind::CloseParen | Kind::CloseBracket | Kind::CloseBrace => {
+                depth = depth.saturating_sub(1);
+            }
+            Kind::Semicolon if depth == 0 => {
+                statements.extend(statement(&tokens[start..idx], tok.span)?);
+                start = idx + 1;
+            }
+            _ => {}
+        }
+    }
+    let eof = Span {
+        start: text.len() as u64,
+        end: text.len() as u64,
+    };
+    statements.extend(statement(&tokens[start..], eof)?);
+    Ok(statements)
+}
+
+/// Parses a statement if it is a session one, `end` is the span of the `;`
+/// or of the end of the input
+fn statement(tokens: &[Token], end: Span) -> Result<Option<SessionStatement>, Error> {
+    let keyword = |idx: usize| match tokens.get(idx).map(|t| t.kind) {
+        Some(Kind::Keyword(Keyword(kw))) => Some(kw),
+        _ => None,
+    };
+    let span_at = |idx: usize| tokens.get(idx).map_or(end, |t| t.span);
+    let expected =
+        |what: &str, idx: usize| Error::new(format!("Expected {what}")).with_span(span_at(idx));
+    let (command, len) = match (keyword(0), keyword(1)) {
+        (Some("set"), Some("module")) => {
+            let (module, len) =
+                module_name(tokens, 2).ok_or_else(|| expected("a module name", 2))?;
+            (SessionCommand::SetModule { module }, len)
+        }
+        (Some("set"), Some("alias")) => {
+            let alias = short_name(tokens, 2).ok_or_else(|| expected("an alias", 2))?;
+            if keyword(3) != Some("as") {
+                return Err(expected("'as'", 3));
+            }
+            if keyword(4) != Some("module") {
+                return Err(expected("'module'", 4));
+            }
+            let (module, len) =
+                module_name(tokens, 5).ok_or_else(|| expected("a module name", 5))?;
+            (SessionCommand::SetAlias { alias, module }, len)
+        }
+        (Some("reset"), Some("module")) => (SessionCommand::ResetModule, 2),
+        (Some("reset"), Some("alias")) if tokens.get(2).map(|t| t.kind) == Some(Kind::Mul) => {
+            (SessionCommand::ResetAllAliases, 3)
+        }
+        (Some("reset"), Some("alias")) => {
+            let alias = short_name(tokens, 2).ok_or_else(|| expected("an alias or '*'", 2))?;
+            (SessionCommand::ResetAlias { alias }, 3)
+        }
+        _ => return Ok(None),
+    };
+    if let Some(tok) = tokens.get(len) {
+        return Err(Error::new(format!("Unexpected '{}'", tok.text)).with_span(tok.span));
+    }
+    Ok(Some(SessionStatement {
+        command,
+        span: tokens[0].span.extend(&tokens[len - 1].span),
+    }))
+}
+
+fn short_name(tokens: &[Token], idx: usize) -> Option<Name> {
+    let tok = tokens.get(idx)?;
+    is_name_like(Some(tok.kind)).then(|| Name {
+        text: token_name(tok).to_string(),
+        span: tok.span,
+    })
+}
+
+/// Reads a possibly qualified module name, returns it with the index of the
+/// token after it
+fn module_name(tokens: &[Token], idx: usize) -> Option<(Name, usize)> {
+    let mut name = short_name(tokens, idx)?;
+    let mut end = idx + 1;
+    while tokens.get(end).map(|t| t.kind) == Some(Kind::Namespace) {
+        let part = short_name(tokens, end + 1)?;
+        name.text.push_str("::");
+        name.text.push_str(&part.text);
+        name.span = name.span.extend(&part.span);
+        end += 2;
+    }
+    Some((name, end))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_session, AliasTable, SessionCommand};
+    use crate::resolver::SchemaResolver;
+    use crate::schema::parse;
+
+    #[test]
+    fn aliases() {
+        let mut table = AliasTable::default();
+        table
+            .apply_script(
+                "set module app; select User; set alias s as module std; \
+                 set alias c as module std::cal; reset alias s",
+            )
+            .unwrap();
+        assert_eq!(table.module, "app");
+        assert_eq!(table.aliases.len(), 1);
+        assert_eq!(table.expand("c::local_date"), "std::cal::local_date");
+        assert_eq!(table.expand("s::str"), "s::str");
+
+        let schema = parse("module app { type User; }").unwrap();
+        let resolver = SchemaResolver::new(&schema);
+        assert_eq!(
+            table.resolve_type("User", &resolver).as_deref(),
+            Some("app::User")
+        );
+
+        table.apply_script("reset module; reset alias *;").unwrap();
+        assert_eq!(table, AliasTable::default());
+    }
+
+    #[test]
+    fn statements() {
+        let text = "set alias s as module std";
+        let statements = parse_session(text).unwrap();
+        let SessionCommand::SetAlias { alias, module } = &statements[0].command else {
+            panic!("{:?}", statements[0]);
+        };
+        assert_eq!((&alias.text[..], &module.text[..]), ("s", "std"));
+        assert_eq!(statements[0].span.end as usize, text.len());
+
+        let error = |text: &str| parse_session(text).unwrap_err().message;
+        assert_eq!(error("set alias s module std"), "Expected 'as'");
+        assert_eq!(error("set module std::"), "Expected a module name");
+        assert_eq!(error("reset alias s, t"), "Unexpected ','");
+    }
+}
@@ -0,0 +1,684 @@
+//! Shapes of queries, read directly from tokens
+//!
+//! Recognizes `Type { element, nested: { ... }, computed := expr }` and the
+//! main statement of a query, which is enough for tools that need to know
+//! what a query returns without the full grammar.
+//!
+//! This is a reading of the tokens for such tools, not a second parser:
+//! the grammar is what decides whether a query is valid. Statements are
+//! only followed as far as their shape (including `for` loops and
+//! cardinality modifiers of elements), anything else is kept as tokens.
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::position::Span;
+use crate::schema::{is_name_like, read_cardinality, token_name, Cardinality, Expr};
+use crate::tokenizer::{Error, Kind, Token, Tokenizer};
+
+const ELEMENT_MODIFIERS: &[&str] = &["required", "optional"];
+
+const STATEMENTS: &[&str] = &["select", "insert", "update", "delete", "group", "for"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Shape {
+    pub elements: Vec<ShapeElement>,
+    /// Span of the braces
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShapeElement {
+    /// `required` or `optional` before the name
+    pub modifiers: Vec<&'static str>,
+    /// `single` or `multi` before the name, only allowed on computed
+    /// elements
+    pub cardinality: Option<Cardinality>,
+    /// Type in `[is Type].name`
+    pub type_filter: Option<String>,
+    /// Element is a link property (`@name`)
+    pub link_property: bool,
+    /// Element is a backlink (`<name`)
+    pub backlink: bool,
+    /// Pointer name, `*` or `**` for splats
+    pub name: String,
+    pub name_span: Span,
+    /// Expression after `:=`
+    pub expr: Option<Expr>,
+    pub shape: Option<Shape>,
+    /// Tokens after the nested shape, like `filter` or `order by`
+    pub clauses: Option<Expr>,
+    pub span: Span,
+}
+
+/// Main statement of a query, ignoring the `with` block
+#[derive(Debug, Clone, PartialEq)]
+pub struct Statement {
+    /// `select`, `insert`, etc.
+    pub keyword: &'static str,
+    /// Module set by `with module`
+    pub module: Option<String>,
+    /// Type name (possibly qualified) if the statement starts with one
+    pub subject: Option<String>,
+    pub subject_span: Option<Span>,
+    pub shape: Option<Shape>,
+    /// Iterator and body of a `for` statement, which has no subject
+    pub for_loop: Option<ForLoop>,
+    pub span: Span,
+}
+
+/// `for [optional] alias in iterator [union] body`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ForLoop {
+    /// `for optional`, the body is evaluated once if the iterator is empty
+    pub optional: bool,
+    pub alias: String,
+    pub alias_span: Span,
+    pub iterator: Expr,
+    /// `union` is written before the body, it may be omitted before a
+    /// statement
+    pub union: bool,
+    pub body: Expr,
+    /// Loop of the body, if the body is a `for` statement (possibly in
+    /// parentheses)
+    pub nested: Option<Box<ForLoop>>,
+}
+
+impl ShapeElement {
+    pub fn has_modifier(&self, modifier: &str) -> bool {
+        self.modifiers.contains(&modifier)
+    }
+
+    pub fn is_multi(&self) -> bool {
+        self.cardinality == Some(Cardinality::Multi)
+    }
+
+    pub fn is_splat(&self) -> bool {
+        self.name == "*" || self.name == "**"
+    }
+
+    /// Name as written, with `@` of link properties and `<` of backlinks
+    pub fn display_name(&self) -> String {
+        let prefix = match (self.link_property, self.backlink) {
+            (true, _) => "@",
+            (_, true) => "<",
+            _ => "",
+        };
+        format!("{prefix}{}", self.name)
+    }
+
+    fn is_same_pointer(&self, other: &ShapeElement) -> bool {
+        !self.is_splat()
+            && self.name == other.name
+            && self.link_property == other.link_property
+            && self.backlink == other.backlink
+            && self.type_filter == other.type_filter
+    }
+}
+
+impl Shape {
+    /// Errors for pointers that are selected or assigned more than once in
+    /// the shape or in its nested shapes, which the server rejects
+    ///
+    /// The primary span is the repeated element, a label points to the first
+    /// one.
+    pub fn duplicates(&self) -> Vec<Diagnostic> {
+        let mut errors = Vec::new();
+        for (idx, element) in self.elements.iter().enumerate() {
+            if let Some(first) = self.elements[..idx]
+                .iter()
+                .find(|e| e.is_same_pointer(element))
+            {
+                let code = match (first.expr.is_some(), element.expr.is_some()) {
+                    (false, false) => "duplicate-selection",
+                    (true, true) => "duplicate-assignment",
+                    (false, true) => "selected-and-assigned",
+                    (true, false) => "assigned-and-selected",
+                };
+                let error = Diagnostic::with_code(
+                    Severity::Error,
+                    code,
+                    &[("name", element.display_name())],
+                    element.name_span,
+                )
+                .with_label(first.name_span);
+                errors.push(error);
+            }
+            if let Some(nested) = &element.shape {
+                errors.extend(nested.duplicates());
+            }
+        }
+        errors
+    }
+}
+
+/// Tokenizes the query and finds its main statement
+pub fn parse_statement(text: &str) -> Result<Option<Statement>, Error> {
+    let tokens = Tokenizer::new(text)
+        .validated_values()
+        .map(|t| t.map(|t| t.cloned()))
+        .collect::<Result<Vec<_>, _>>()?;
+    statement(&tokens)
+}
+
+/// Finds the main statement in the tokens of a query
+pub fn statement(tokens: &[Token<'static>]) -> Result<Option<Statement>, Error> {
+    let mut module = None;
+    let mut pos = 0;
+    if keyword(tokens.get(pos)) == Some("with") {
+        pos += 1;
+        // skip bindings until the statement at the top level
+        let mut depth = 0usize;
+        let mut after_assign = false;
+        while let Some(tok) = tokens.get(pos) {
+            match tok.kind {
+                Kind::OpenParen | Kind::OpenBracket | Kind::OpenBrace => depth += 1,
+                Kind::CloseParen | Kind::CloseBracket | Kind::CloseBrace => {
+                    depth = depth.saturating_sub(1)
+                }
+                _ => {}
+            }
+            let kw = keyword(Some(tok));
+            if depth == 0 && !after_assign && kw.is_some_and(|k| STATEMENTS.contains(&k)) {
+                break;
+            }
+            if depth == 0 && kw == Some("module") {
+                if let Some(name) = tokens.get(pos + 1).filter(|t| is_name_like(Some(t.kind))) {
+                    module = Some(token_name(name).to_string());
+                }
+            }
+            after_assign = tok.kind == Kind::Assign;
+            pos += 1;
+        }
+    }
+    let Some(start) = tokens.get(pos) else {
+        return Ok(None);
+    };
+    let Some(keyword) = keyword(Some(start)).filter(|k| STATEMENTS.contains(k)) else {
+        return Ok(None);
+    };
+    pos += 1;
+
+    let mut subject = None;
+    let mut subject_span = None;
+    let mut for_loop = None;
+    if keyword == "for" {
+        for_loop = parse_for(tokens, pos - 1, statement_end(tokens, pos));
+    } else if is_name_like(tokens.get(pos).map(|t| t.kind)) {
+        let (name, span, end) = read_name(tokens, pos);
+        subject = Some(name);
+        subject_span = Some(span);
+        pos = end;
+    }
+    let mut shape = None;
+    if subject.is_some() && tokens.get(pos).map(|t| t.kind) == Some(Kind::OpenBrace) {
+        let (parsed, _) = parse_shape(tokens, pos)?;
+        shape = Some(parsed);
+    }
+    let end = tokens
+        .iter()
+        .rev()
+        .find(|t| t.kind != Kind::Semicolon)
+        .map_or(start.span, |t| t.span);
+    Ok(Some(Statement {
+        keyword,
+        module,
+        subject,
+        subject_span,
+        shape,
+        for_loop,
+        span: start.span.extend(&end),
+    }))
+}
+
+/// Reads a `for` loop, `tokens[start]` must be the `for` keyword and `end`
+/// the index after the last token of the statement. Returns `None` if the
+/// loop is incomplete.
+fn parse_for(tokens: &[Token<'static>], start: usize, end: usize) -> Option<ForLoop> {
+    let mut pos = start + 1;
+    let optional = keyword(tokens.get(pos)) == Some("optional");
+    if optional {
+        pos += 1;
+    }
+    let alias_tok = tokens
+        .get(pos)
+        .filter(|t| is_name_like(Some(t.kind)) || t.kind == Kind::BacktickName)?;
+    if keyword(tokens.get(pos + 1)) != Some("in") {
+        return None;
+    }
+    pos += 2;
+
+    let iterator_start = pos;
+    let mut depth = 0usize;
+    while pos < end {
+        match tokens[pos].kind {
+            Kind::OpenParen | Kind::OpenBracket | Kind::OpenBrace => depth += 1,
+            Kind::CloseParen | Kind::CloseBracket | Kind::CloseBrace => {
+                depth = depth.saturating_sub(1)
+            }
+            _ if depth > 0 => {}
+            _ => {
+                let kw = keyword(tokens.get(pos));
+                if kw == Some("union") || kw.is_some_and(|k| k == "with" || STATEMENTS.contains(&k))
+                {
+                    break;
+                }
+            }
+        }
+        pos += 1;
+    }
+    if pos == iterator_start || pos == end {
+        return None;
+    }
+    let iterator = expr_from(tokens, iterator_start, pos);
+    let union = keyword(tokens.get(pos)) == Some("union");
+    if union {
+        pos += 1;
+    }
+    if pos == end {
+        return None;
+    }
+
+    // `(for ...)` or `for ...`
+    let (inner_start, inner_end) = match closing_paren(tokens, pos) {
+        Some(close) if close + 1 == end => (pos + 1, close),
+        _ => (pos, end),
+    };
+    let nested = Some(inner_start)
+        .filter(|&p| keyword(tokens.get(p)) == Some("for"))
+        .and_then(|p| parse_for(tokens, p, inner_end))
+        .map(Box::new);
+    Some(ForLoop {
+        optional,
+        alias: token_name(alias_tok).to_string(),
+        alias_span: alias_tok.span,
+        iterator,
+        union,
+        body: expr_from(tokens, pos, end),
+        nested,
+    })
+}
+
+/// Index after the last token of the statement containing `start`: the
+/// semicolon at the top level or the end of the tokens
+fn statement_end(tokens: &[Token], start: usize) -> usize {
+    let mut depth = 0usize;
+    for (pos, tok) in tokens.iter().enumerate().skip(start) {
+        match tok.kind {
+            Kind::OpenParen | Kind::OpenBracket | Kind::OpenBrace => depth += 1,
+            Kind::CloseParen | Kind::CloseBracket | Kind::CloseBrace => {
+                depth = depth.saturating_sub(1)
+            }
+            Kind::Semicolon if depth == 0 => return pos,
+            _ => {}
+        }
+    }
+    tokens.len()
+}
+
+/// Index of the parenthesis closing the one at `open`, `None` if the token
+/// is not an opening parenthesis or it is not closed
+fn closing_paren(tokens: &[Token], open: usize) -> Option<usize> {
+    if kind(tokens, open) != Some(Kind::OpenParen) {
+        return None;
+    }
+    let mut depth = 0usize;
+    for (pos, tok) in tokens.iter().enumerate().skip(open) {
+        match tok.kind {
+            Kind::OpenParen | Kind::OpenBracket | Kind::OpenBrace => depth += 1,
+            Kind::CloseParen | Kind::CloseBracket | Kind::CloseBrace => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(pos);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Reads a shape, `tokens[start]` must be the opening brace. Returns the
+/// shape and the index of the token after the closing brace.
+pub fn parse_shape(tokens: &[Token<'static>], start: usize) -> Result<(Shape, usize), Error> {
+    debug_assert_eq!(tokens[start].kind, Kind::OpenBrace);
+    let mut elements = Vec::new();
+    let mut pos = start + 1;
+    loop {
+        match tokens.get(pos).map(|t| t.kind) {
+            None => return Err(Error::new("Missing '}'").with_span(tokens[start].span)),
+            Some(Kind::CloseBrace) => {
+                let span = tokens[start].span.extend(&tokens[pos].span);
+                return Ok((Shape { elements, span }, pos + 1));
+            }
+            Some(Kind::Comma) => pos += 1,
+            Some(_) => {
+                let (element, end) = parse_element(tokens, pos)?;
+                elements.extend(element);
+                pos = end;
+            }
+        }
+    }
+}
+
+fn parse_element(
+    tokens: &[Token<'static>],
+    start: usize,
+) -> Result<(Option<ShapeElement>, usize), Error> {
+    let mut pos = start;
+    let mut modifiers = Vec::new();
+    let mut cardinality = None;
+    while let Some(kw) = keyword(tokens.get(pos)) {
+        // `single` is reserved, so it is not a name
+        let next = tokens.get(pos + 1);
+        let before_qualifier =
+            keyword(next).is_some_and(|k| Cardinality::from_keyword(k).is_some());
+        if !is_element_start(next) && !before_qualifier {
+            break;
+        }
+        if let Some(qualifier) = Cardinality::from_keyword(kw) {
+            read_cardinality(&mut cardinality, qualifier, tokens[pos].span)?;
+        } else if let Some(m) = ELEMENT_MODIFIERS.iter().find(|m| **m == kw) {
+            modifiers.push(*m);
+        } else {
+            break;
+        }
+        pos += 1;
+    }
+
+    let mut type_filter = None;
+    if kind(tokens, pos) == Some(Kind::OpenBracket) && keyword(tokens.get(pos + 1)) == Some("is") {
+        let (name, _, end) = read_name(tokens, pos + 2);
+        type_filter = Some(name);
+        pos = end;
+        if kind(tokens, pos) == Some(Kind::CloseBracket) {
+            pos += 1;
+        }
+        if kind(tokens, pos) == Some(Kind::Dot) {
+            pos += 1;
+        }
+    }
+
+    let mut link_property = false;
+    let mut backlink = false;
+    match kind(tokens, pos) {
+        Some(Kind::At) => {
+            link_property = true;
+            pos += 1;
+        }
+        Some(Kind::Less) => {
+            backlink = true;
+            pos += 1;
+        }
+        _ => {}
+    }
+
+    let name_tok = match tokens.get(pos) {
+        Some(tok)
+            if is_name_like(Some(tok.kind))
+                || matches!(tok.kind, Kind::Mul | Kind::DoubleSplat) =>
+        {
+            tok
+        }
+        _ => {
+            // not an element, skip it entirely
+            return Ok((None, skip_to_separator(tokens, start)));
+        }
+    };
+    let name = if is_name_like(Some(name_tok.kind)) {
+        token_name(name_tok).to_string()
+    } else {
+        name_tok.text.to_string()
+    };
+    let name_span = name_tok.span;
+    pos += 1;
+
+    // backlink with a type filter: `<name[is Type]`
+    if kind(tokens, pos) == Some(Kind::OpenBracket) && keyword(tokens.get(pos + 1)) == Some("is") {
+        let (name, _, end) = read_name(tokens, pos + 2);
+        type_filter = Some(name);
+        pos = end;
+        if kind(tokens, pos) == Some(Kind::CloseBracket) {
+            pos += 1;
+        }
+    }
+
+    let mut expr = None;
+    let mut shape = None;
+    match kind(tokens, pos) {
+        Some(Kind::Assign) => {
+            let end = skip_to_separator(tokens, pos + 1);
+            expr = Some(expr_from(tokens, pos + 1, end));
+            pos = end;
+        }
+        Some(Kind::Colon) if kind(tokens, pos + 1) == Some(Kind::OpenBrace) => {
+            let (nested, end) = parse_shape(tokens, pos + 1)?;
+            shape = Some(nested);
+            pos = end;
+        }
+        _ => {}
+    }
+    if let (Some((qualifier, span)), None) = (cardinality, &expr) {
+        return Err(Error::new(format!(
+            "'{}' is only allowed on computed shape elements",
+            qualifier.as_str()
+        ))
+        .with_span(span));
+    }
+
+    let mut clauses = None;
+    let end = skip_to_separator(tokens, pos);
+    if end > pos {
+        clauses = Some(expr_from(tokens, pos, end));
+    }
+    let span = tokens[start].span.extend(&tokens[end - 1].span);
+    let element = ShapeElement {
+        modifiers,
+        cardinality: cardinality.map(|(c, _)| c),
+        type_filter,
+        link_property,
+        backlink,
+        name,
+        name_span,
+        expr,
+        shape,
+        clauses,
+        span,
+    };
+    Ok((Some(element), end))
+}
+
+fn is_element_start(token: Option<&Token>) -> bool {
+    token.is_some_and(|t| {
+        is_name_like(Some(t.kind)) || matches!(t.kind, Kind::At | Kind::OpenBracket | Kind::Less)
+    })
+}
+
+fn kind(tokens: &[Token], pos: usize) -> Option<Kind> {
+    tokens.get(pos).map(|t| t.kind)
+}
+
+fn keyword(token: Option<&Token>) -> Option<&'static str> {
+    match token?.kind {
+        Kind::Keyword(kw) => Some(kw.0),
+        _ => None,
+    }
+}
+
+/// Returns the index of the `,` or `}` ending the current element
+fn skip_to_separator(tokens: &[Token], start: usize) -> usize {
+    let mut depth = 0usize;
+    let mut pos = start;
+    while let Some(tok) = tokens.get(pos) {
+        match tok.kind {
+            Kind::OpenParen | Kind::OpenBracket | Kind::OpenBrace => depth += 1,
+            Kind::CloseParen | Kind::CloseBracket => depth = depth.saturating_sub(1),
+            Kind::CloseBrace if depth == 0 => break,
+            Kind::CloseBrace => depth -= 1,
+            Kind::Comma if depth == 0 => break,
+            _ => {}
+        }
+        pos += 1;
+    }
+    pos
+}
+
+fn read_name(tokens: &[Token], start: usize) -> (String, Span, usize) {
+    let mut pos = start;
+    let mut name = String::new();
+    let mut span = tokens
+        .get(pos)
+        .map_or(Span { start: 0, end: 0 }, |t| t.span);
+    if let Some(tok) = tokens.get(pos).filter(|t| is_name_like(Some(t.kind))) {
+        name.push_str(token_name(tok));
+        pos += 1;
+    }
+    while kind(tokens, pos) == Some(Kind::Namespace) && is_name_like(kind(tokens, pos + 1)) {
+        name.push_str("::");
+        name.push_str(token_name(&tokens[pos + 1]));
+        span = span.extend(&tokens[pos + 1].span);
+        pos += 2;
+    }
+    (name, span, pos)
+}
+
+fn expr_from(tokens: &[Token<'static>], start: usize, end: usize) -> Expr {
+    let tokens = tokens[start..end].to_vec();
+    let span = match (tokens.first(), tokens.last()) {
+        (Some(first), Some(last)) => first.span.extend(&last.span),
+        _ => Span { start: 0, end: 0 },
+    };
+    Expr { tokens, span }
+}
+
+#[cfg(test)]
+mod test {
+    use super::parse_statement;
+    use crate::schema::Cardinality;
+
+    #[test]
+    fn select() {
+        let stmt = parse_statement(
+            "
+            with module test, x := (select 1)
+            select User {
+                name,
+                required single nick := .name ++ '!',
+                friends: { name, @since } filter .name != 'x' order by .name,
+                [is Admin].level,
+                <author[is Post]: { title },
+                *
+            } filter .name = 'y';
+            ",
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(stmt.keyword, "select");
+        assert_eq!(stmt.module.as_deref(), Some("test"));
+        assert_eq!(stmt.subject.as_deref(), Some("User"));
+
+        let shape = stmt.shape.unwrap();
+        let names: Vec<_> = shape.elements.iter().map(|e| &e.name[..]).collect();
+        assert_eq!(names, ["name", "nick", "friends", "level", "author", "*"]);
+        let nick = &shape.elements[1];
+        assert!(nick.has_modifier("required"));
+        assert_eq!(nick.cardinality, Some(Cardinality::Single));
+        assert_eq!(nick.expr.as_ref().unwrap().tokens.len(), 4);
+
+        let friends = &shape.elements[2];
+        assert_eq!(friends.cardinality, None);
+        let nested = friends.shape.as_ref().unwrap();
+        assert!(nested.elements[1].link_property);
+        assert_eq!(friends.clauses.as_ref().unwrap().tokens[0].text, "filter");
+
+        assert_eq!(shape.elements[3].type_filter.as_deref(), Some("Admin"));
+        assert!(shape.elements[4].backlink);
+        assert_eq!(shape.elements[4].type_filter.as_deref(), Some("Post"));
+        assert!(shape.elements[5].is_splat());
+    }
+
+    #[test]
+    fn errors() {
+        let err = parse_statement("select User { name").unwrap_err();
+        assert_eq!(err.message, "Missing '}'");
+        assert!(parse_statement("1 + 2").unwrap().is_none());
+        let stmt = parse_statement("insert User { name := 'x' }")
+            .unwrap()
+            .unwrap();
+        assert_eq!(stmt.keyword, "insert");
+        assert_eq!(stmt.shape.unwrap().elements[0].name, "name");
+
+        let err = parse_statement("select User { multi friends: { name } }").unwrap_err();
+        assert_eq!(
+            err.message,
+            "'multi' is only allowed on computed shape elements"
+        );
+        assert_eq!(err.span.start, 14);
+        let stmt = parse_statement("select User { multi ids := .friends.id }")
+            .unwrap()
+            .unwrap();
+        assert!(stmt.shape.unwrap().elements[0].is_multi());
+    }
+
+    #[test]
+    fn for_loops() {
+        let stmt = parse_statement(
+            "for x in {(1, 'a'), (2, 'b')} union (\
+               for optional y in {(select User filter .name = x.1)} \
+               union (select (x.0, y))\
+             );",
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(stmt.keyword, "for");
+        assert_eq!(stmt.subject, None);
+        let outer = stmt.for_loop.unwrap();
+        assert!(!outer.optional && outer.union);
+        assert_eq!(outer.alias, "x");
+        assert_eq!(outer.iterator.tokens.len(), 13);
+        assert_eq!(outer.body.tokens.last().unwrap().text, ")");
+        let inner = outer.nested.unwrap();
+        assert!(inner.optional && inner.union);
+        assert_eq!(inner.alias, "y");
+        assert_eq!(inner.iterator.tokens[1].text, "(");
+        assert_eq!(inner.body.tokens.len(), 10);
+        assert!(inner.nested.is_none());
+
+        let stmt = parse_statement("for x in 1 with y := x insert Foo { y := y }")
+            .unwrap()
+            .unwrap();
+        let for_loop = stmt.for_loop.unwrap();
+        assert!(!for_loop.union);
+        assert_eq!(for_loop.iterator.tokens.len(), 1);
+        assert_eq!(for_loop.body.tokens[0].text, "with");
+        assert!(stmt.shape.is_none());
+
+        let stmt = parse_statement("for x in {1} union (for z in {x} union z)")
+            .unwrap()
+            .unwrap();
+        assert_eq!(stmt.for_loop.unwrap().nested.unwrap().alias, "z");
+    }
+
+    #[test]
+    fn duplicates() {
+        let text = "select User { name, @name, *, *, friends: { name, name }, \
+                    name := .nick, [is Admin].name }";
+        let shape = parse_statement(text).unwrap().unwrap().shape.unwrap();
+        let errors = shape.duplicates();
+        let described: Vec<_> = errors
+            .iter()
+            .map(|e| (&e.message[..], e.span.start, e.labels[0].span.start))
+            .collect();
+        assert_eq!(
+            described,
+            [
+                ("`name` is selected twice", 50, 44),
+                ("`name` is both selected and assigned", 58, 14),
+            ]
+        );
+        assert_eq!(errors[1].labels[0].message, "first selected here");
+
+        let stmt = parse_statement("insert User { name := 'a', name := 'b' }")
+            .unwrap()
+            .unwrap();
+        let errors = stmt.shape.unwrap().duplicates();
+        assert_eq!(errors[0].message, "`name` is assigned twice");
+    }
+}
@@ -0,0 +1,219 @@
+//! Signature help: the function call enclosing a cursor position
+//!
+//! Works on tokens of possibly incomplete input, so it is usable while the
+//! call is still being typed: `select len(`.
+use crate::keywords::Keyword;
+use crate::position::Span;
+use crate::schema::{is_name_like, token_name};
+use crate::tokenizer::{Kind, Token, Tokenizer};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignatureHelp {
+    /// Function name as written in the call, e.g. `len` or `std::len`
+    pub function: String,
+    pub name_span: Span,
+    /// Index of the argument the cursor is in
+    pub active_argument: usize,
+    pub arguments: Vec<Argument>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Argument {
+    /// Name of a named argument: `f(x := 1)`
+    pub name: Option<String>,
+    /// Span of the argument, empty span between commas for a missing one
+    pub span: Span,
+}
+
+/// Returns the innermost function call containing byte `offset` of the
+/// `source`
+pub fn signature_help_at(source: &str, offset: u64) -> Option<SignatureHelp> {
+    let tokens: Vec<_> = Tokenizer::new(source)
+        .validated_values()
+        .map_while(Result::ok)
+        .collect();
+
+    // innermost unclosed paren before the cursor
+    let mut stack = Vec::new();
+    for (idx, tok) in tokens.iter().enumerate() {
+        if tok.span.end > offset || (tok.span.start == offset && is_close(tok.kind)) {
+            break;
+        }
+        match tok.kind {
+            Kind::OpenParen | Kind::OpenBracket | Kind::OpenBrace => stack.push(idx),
+            Kind::CloseParen | Kind::CloseBracket | Kind::CloseBrace => {
+                stack.pop();
+            }
+            _ => {}
+        }
+    }
+    let open = stack
+        .into_iter()
+        .rev()
+        .find(|&idx| tokens[idx].kind == Kind::OpenParen)?;
+    let name_start = function_name_start(&tokens, open)?;
+
+    let mut function = String::new();
+    for tok in &tokens[name_start..open] {
+        match tok.kind {
+            Kind::Namespace => function.push_str("::"),
+            _ => function.push_str(token_name(tok)),
+        }
+    }
+    let name_span = tokens[name_start].span.extend(&tokens[open - 1].span);
+
+    let arguments = read_arguments(&tokens, open, source.len() as u64);
+    let active_argument = arguments
+        .iter()
+        .take_while(|arg| arg.span.end < offset)
+        .count()
+        .min(arguments.len().saturating_sub(1));
+
+    Some(SignatureHelp {
+        function,
+        name_span,
+        active_argument,
+        arguments,
+    })
+}
+
+fn is_close(kind: Kind) -> bool {
+    matches!(
+        kind,
+        Kind::CloseParen | Kind::CloseBracket | Kind::CloseBrace
+    )
+}
+
+/// Returns the index of the first token of the (possibly qualified)
+/// function name before the paren at `open`
+pub(crate) fn function_name_start(tokens: &[Token], open: usize) -> Option<usize> {
+    let mut start = open.checked_sub(1)?;
+    if !is_name_like(Some(tokens[start].kind)) {
+        return None;
+    }
+    while start >= 2
+        && tokens[start - 1].kind == Kind::Namespace
+        && is_name_like(Some(tokens[start - 2].kind))
+    {
+        start -= 2;
+    }
+    // not a call, but a definition or a link: `function f(`, `.f(`
+    if start > 0
+        && matches!(
+            tokens[start - 1].kind,
+            Kind::Dot | Kind::At | Kind::Keyword(Keyword("function"))
+        )
+    {
+        return None;
+    }
+    Some(start)
+}
+
+/// Splits call arguments at top level commas, up to the closing paren or
+/// the end of the input
+pub(crate) fn read_arguments(tokens: &[Token], open: usize, end_of_input: u64) -> Vec<Argument> {
+    let mut arguments = Vec::new();
+    let mut depth = 0usize;
+    let mut current: Vec<&Token> = Vec::new();
+    let mut separator_end = tokens[open].span.end;
+    let mut end = end_of_input;
+    for tok in &tokens[open + 1..] {
+        match tok.kind {
+            Kind::OpenParen | Kind::OpenBracket | Kind::OpenBrace => depth += 1,
+            Kind::CloseParen | Kind::CloseBracket | Kind::CloseBrace if depth == 0 => {
+                end = tok.span.start;
+                break;
+            }
+            Kind::CloseParen | Kind::CloseBracket | Kind::CloseBrace => depth -= 1,
+            Kind::Comma if depth == 0 => {
+                arguments.push(argument(&current, separator_end, tok.span.start));
+                current.clear();
+                separator_end = tok.span.end;
+                continue;
+            }
+            Kind::Semicolon if depth == 0 => {
+                end = tok.span.start;
+                break;
+            }
+            _ => {}
+        }
+        current.push(tok);
+    }
+    if !current.is_empty() || !arguments.is_empty() || end > separator_end {
+        arguments.push(argument(&current, separator_end, end));
+    }
+    arguments
+}
+
+fn argument(tokens: &[&Token], start: u64, end: u64) -> Argument {
+    let span = match (tokens.first(), tokens.last()) {
+        (Some(first), Some(last)) => first.span.extend(&last.span),
+        // cover the whitespace between commas, so the cursor is inside
+        _ => Span { start, end },
+    };
+    let name = match tokens {
+        [name, assign, ..] if assign.kind == Kind::Assign && is_name_like(Some(name.kind)) => {
+            Some(token_name(name).to_string())
+        }
+        _ => None,
+    };
+    Argument { name, span }
+}
+
+#[cfg(test)]
+mod test {
+    use super::signature_help_at;
+
+    fn help_at_cursor(text: &str) -> Option<super::SignatureHelp> {
+        let offset = text.find('|').unwrap();
+        let text = format!("{}{}", &text[..offset], &text[offset + 1..]);
+        signature_help_at(&text, offset as u64)
+    }
+
+    #[test]
+    fn incomplete() {
+        let help = help_at_cursor("select std::len(|").unwrap();
+        assert_eq!(help.function, "std::len");
+        assert_eq!(help.active_argument, 0);
+        assert!(help.arguments.is_empty());
+
+        let help = help_at_cursor("select math::mean({1, 2}, |").unwrap();
+        assert_eq!(help.function, "math::mean");
+        assert_eq!(help.active_argument, 1);
+        assert_eq!(help.arguments.len(), 2);
+    }
+
+    #[test]
+    fn arguments() {
+        let text = "select to_str(<int64>$x, fmt := a(|), 'c')";
+        let help = help_at_cursor(text).unwrap();
+        assert_eq!(help.function, "a");
+
+        let text = "select to_str(<int64>$x, fmt := |'FM999', 'c')";
+        let help = help_at_cursor(text).unwrap();
+        assert_eq!(help.function, "to_str");
+        assert_eq!(help.active_argument, 1);
+        assert_eq!(help.arguments.len(), 3);
+        assert_eq!(help.arguments[0].name, None);
+        assert_eq!(help.arguments[1].name.as_deref(), Some("fmt"));
+        let span = help.arguments[2].span;
+        let source = text.replace('|', "");
+        assert_eq!(&source[span.start as usize..span.end as usize], "'c'");
+    }
+
+    #[test]
+    fn no_call() {
+        assert_eq!(help_at_cursor("select (1, |2)"), None);
+        assert_eq!(help_at_cursor("select len(1)|"), None);
+        assert_eq!(help_at_cursor("create function f(|"), None);
+        assert_eq!(
+            help_at_cursor("select User { x := f(|) }")
+                .unwrap()
+                .function,
+            "f"
+        );
+        // set literal inside of the argument
+        let help = help_at_cursor("select f({ 1, |2 })").unwrap();
+        assert_eq!(help.active_argument, 0);
+    }
+}
@@ -0,0 +1,177 @@
+//! Soft keywords
+//!
+//! Some unreserved keywords are only keywords in one or two productions of
+//! the grammar, like `applied` in `create applied migration` and `orphan` in
+//! `delete target if orphan`. [SOFT_KEYWORDS] lists the contexts they are
+//! keywords in, everywhere else they are names.
+//!
+//! [demote_soft_keywords] turns soft keywords outside of their contexts into
+//! identifiers, so that consumers of tokens treat them the same as other
+//! names. The parser does the same with [demote_soft_terminals] before
+//! parsing, so the grammar only sees a soft keyword as a keyword in the
+//! productions listed here, even once it is promoted to a reserved keyword.
+//! [check_soft_keywords] warns about soft keywords used as names,
+//! which break if the keyword is promoted to a reserved one, and suggests
+//! quoting them.
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::keywords::Keyword;
+use crate::parser::Terminal;
+use crate::tokenizer::{Kind, Token};
+
+/// Tokens around a soft keyword where it is a keyword
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Context {
+    /// Keywords right before the soft keyword
+    pub before: &'static [&'static str],
+    /// Keywords right after the soft keyword
+    pub after: &'static [&'static str],
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SoftKeyword {
+    pub keyword: &'static str,
+    pub contexts: &'static [Context],
+}
+
+pub const SOFT_KEYWORDS: &[SoftKeyword] = &[
+    SoftKeyword {
+        keyword: "applied",
+        contexts: &[Context {
+            before: &["create"],
+            after: &["migration"],
+        }],
+    },
+    SoftKeyword {
+        keyword: "orphan",
+        contexts: &[Context {
+            before: &["delete", "target", "if"],
+            after: &[],
+        }],
+    },
+];
+
+/// The soft keyword with the name, in lowercase
+pub fn soft_keyword(name: &str) -> Option<&'static SoftKeyword> {
+    SOFT_KEYWORDS.iter().find(|k| k.keyword == name)
+}
+
+impl Context {
+    /// Whether the token at `pos` is surrounded by the context
+    pub fn matches(&self, tokens: &[Token], pos: usize) -> bool {
+        self.matches_kinds(tokens.len(), |i| tokens[i].kind, pos)
+    }
+
+    fn matches_kinds(&self, len: usize, kind: impl Fn(usize) -> Kind, pos: usize) -> bool {
+        let Some(start) = pos.checked_sub(self.before.len()) else {
+            return false;
+        };
+        let before = (start..pos).zip(self.before);
+        let after = (pos + 1..len).zip(self.after);
+        len > pos + self.after.len()
+            && before
+                .chain(after)
+                .all(|(i, word)| kind(i) == Kind::Keyword(Keyword(*word)))
+    }
+}
+
+/// Returns the soft keyword at `pos`, if the kind there is one, with
+/// whether it is used as a keyword there
+fn soft_keyword_at(
+    len: usize,
+    kind: impl Fn(usize) -> Kind + Copy,
+    pos: usize,
+) -> Option<(&'static SoftKeyword, bool)> {
+    let Kind::Keyword(Keyword(name)) = kind(pos) else {
+        return None;
+    };
+    let soft = soft_keyword(name)?;
+    let is_keyword = soft
+        .contexts
+        .iter()
+        .any(|c| c.matches_kinds(len, kind, pos));
+    Some((soft, is_keyword))
+}
+
+/// Positions of the soft keywords that are not in one of their contexts
+fn demoted(len: usize, kind: impl Fn(usize) -> Kind + Copy) -> Vec<usize> {
+    (0..len)
+        .filter(|&pos| matches!(soft_keyword_at(len, kind, pos), Some((_, false))))
+        .collect()
+}
+
+/// Turns soft keywords that are not in one of their contexts into
+/// identifiers
+pub fn demote_soft_keywords(tokens: &mut [Token]) {
+    for pos in demoted(tokens.len(), |i| tokens[i].kind) {
+        tokens[pos].kind = Kind::Ident;
+    }
+}
+
+/// Same as [demote_soft_keywords], for the terminals given to the parser
+pub fn demote_soft_terminals(terminals: &mut [Terminal]) {
+    for pos in demoted(terminals.len(), |i| terminals[i].kind) {
+        terminals[pos].kind = Kind::Ident;
+    }
+}
+
+/// Warns about soft keywords used as names
+pub fn check_soft_keywords(tokens: &[Token]) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    for (pos, tok) in tokens.iter().enumerate() {
+        let Some((soft, false)) = soft_keyword_at(tokens.len(), |i| tokens[i].kind, pos) else {
+            continue;
+        };
+        let warning = Diagnostic::with_code(
+            Severity::Warning,
+            "soft-keyword",
+            &[("keyword", soft.keyword.into())],
+            tok.span,
+        )
+        .with_suggestion(tok.span, format!("`{}`", tok.text));
+        diagnostics.push(warning);
+    }
+    diagnostics
+}
+
+#[cfg(test)]
+mod test {
+    use super::{check_soft_keywords, demote_soft_keywords, demote_soft_terminals};
+    use crate::parser::Terminal;
+    use crate::tokenizer::{tokens, Kind};
+
+    #[test]
+    fn contexts() {
+        let mut toks = tokens(
+            "create applied migration m1 { \
+                alter type User { alter link tags { on source delete delete target if orphan } }; \
+             }; \
+             select Migration { applied, orphan := false }",
+        );
+        let mut terminals: Vec<_> = toks.iter().cloned().map(Terminal::from_token).collect();
+        demote_soft_keywords(&mut toks);
+        let kinds: Vec<_> = toks
+            .iter()
+            .filter(|t| matches!(&t.text[..], "applied" | "orphan"))
+            .map(|t| t.kind == Kind::Ident)
+            .collect();
+        assert_eq!(kinds, [false, false, true, true]);
+
+        demote_soft_terminals(&mut terminals);
+        let demoted: Vec<_> = toks.iter().map(|t| t.kind).collect();
+        let kinds: Vec<_> = terminals.iter().map(|t| t.kind).collect();
+        assert_eq!(kinds, demoted);
+    }
+
+    #[test]
+    fn warnings() {
+        let query = "select User { orphan } filter .Orphan";
+        let diagnostics = check_soft_keywords(&tokens(query));
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].code.as_deref(), Some("soft-keyword"));
+        assert_eq!(
+            diagnostics[1].suggestions[0].apply(query),
+            "select User { orphan } filter .`Orphan`"
+        );
+        assert!(check_soft_keywords(&tokens("select `orphan`")).is_empty());
+    }
+}
@@ -0,0 +1,319 @@
+//! Query templates with safe substitution
+//!
+//! Holes in a template are written as `\(name)` tokens. A hole can be
+//! filled only with a [Fragment], and every fragment renders to
+//! a self-contained piece of the query: literals are quoted, names are
+//! backtick-quoted when needed and expressions are checked and
+//! parenthesized. So no value can escape its hole and change the rest of the
+//! query, whatever the value is.
+//!
+//! ```
+//! use edgeql_parser::template::{Fragment, Template};
+//!
+//! let template = Template::parse(r"select \(ty) filter .name = \(name)").unwrap();
+//! let query = template
+//!     .render([
+//!         ("ty", Fragment::name("User").unwrap()),
+//!         ("name", Fragment::str("x'; delete User; select '")),
+//!     ])
+//!     .unwrap();
+//! assert_eq!(query, r#"select User filter .name = "x'; delete User; select '""#);
+//! ```
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use crate::expr;
+use crate::helpers::{quote_name, quote_string};
+use crate::position::Span;
+use crate::tokenizer::{self, Kind, Tokenizer};
+
+#[derive(Debug, thiserror::Error)]
+pub enum TemplateError {
+    #[error("{0}")]
+    Tokenizer(tokenizer::Error),
+    #[error("invalid expression: {0}")]
+    Expression(#[from] expr::Error),
+    #[error("substitutions are not allowed in fragments")]
+    NestedSubstitution,
+    #[error("{0:?} is not a valid name")]
+    InvalidName(String),
+    #[error("no value for \\({0})")]
+    MissingValue(String),
+    #[error("template has no \\({0})")]
+    UnknownHole(String),
+}
+
+/// A template with `\(name)` holes
+#[derive(Debug, Clone)]
+pub struct Template {
+    source: String,
+    /// Holes in the order of appearance, a name may occur multiple times
+    holes: Vec<Hole>,
+}
+
+#[derive(Debug, Clone)]
+struct Hole {
+    name: String,
+    span: Span,
+    /// Whether the hole is directly attached to the previous or the next
+    /// token, so the value must be separated by a space
+    attached_before: bool,
+    attached_after: bool,
+}
+
+/// A value for a hole of a [Template]
+///
+/// Fragments can only be created by the constructors which guarantee that
+/// the text is a single, complete piece of the query.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fragment {
+    text: String,
+}
+
+impl Template {
+    /// Parses the template, fails if it cannot be tokenized
+    pub fn parse(source: &str) -> Result<Template, TemplateError> {
+        let mut holes: Vec<Hole> = Vec::new();
+        let mut prev_end = None;
+        for token in Tokenizer::new(source) {
+            let token = token.map_err(TemplateError::Tokenizer)?;
+            if let Some(hole) = holes.last_mut() {
+                if hole.span.end == token.span.start {
+                    hole.attached_after = true;
+                }
+            }
+            if token.kind == Kind::Substitution {
+                holes.push(Hole {
+                    name: token.text[2..token.text.len() - 1].to_string(),
+                    span: token.span,
+                    attached_before: prev_end == Some(token.span.start),
+                    attached_after: false,
+                });
+            }
+            prev_end = Some(token.span.end);
+        }
+        Ok(Template {
+            source: source.to_string(),
+            holes,
+        })
+    }
+
+    /// Names of the holes, each name is returned once
+    pub fn holes(&self) -> impl Iterator<Item = &str> {
+        let mut seen = Vec::new();
+        self.holes.iter().filter_map(move |hole| {
+            if seen.contains(&&hole.name) {
+                return None;
+            }
+            seen.push(&hole.name);
+            Some(&hole.name[..])
+        })
+    }
+
+    /// Fills all the holes, every hole must have a value and every value
+    /// must have a hole
+    pub fn render<'a>(
+        &self,
+        values: impl IntoIterator<Item = (&'a str, Fragment)>,
+    ) -> Result<String, TemplateError> {
+        let values = values.into_iter().collect::<HashMap<_, _>>();
+        if let Some(name) = values
+            .keys()
+            .find(|name| !self.holes.iter().any(|h| h.name == **name))
+        {
+            return Err(TemplateError::UnknownHole(name.to_string()));
+        }
+        let mut text = String::with_capacity(self.source.len());
+        let mut pos = 0;
+        for hole in &self.holes {
+            let value = values
+                .get(&hole.name[..])
+                .ok_or_else(|| TemplateError::MissingValue(hole.name.clone()))?;
+            text.push_str(&self.source[pos..hole.span.start as usize]);
+            if hole.attached_before {
+                text.push(' ');
+            }
+            text.push_str(&value.text);
+            if hole.attached_after {
+                text.push(' ');
+            }
+            pos = hole.span.end as usize;
+        }
+        text.push_str(&self.source[pos..]);
+        Ok(text)
+    }
+}
+
+impl Fragment {
+    /// An expression, e.g. a rendered sub-template
+    ///
+    /// The expression is checked with [expr::check], comments are removed
+    /// and the result is parenthesized.
+    pub fn expr(text: &str) -> Result<Fragment, TemplateError> {
+        expr::check(text)?;
+        let mut tokens = Vec::new();
+        for token in Tokenizer::new(text) {
+            let token = token.map_err(TemplateError::Tokenizer)?;
+            if token.kind == Kind::Substitution {
+                return Err(TemplateError::NestedSubstitution);
+            }
+            tokens.push(token.text);
+        }
+        Ok(Fragment {
+            text: format!("({})", tokens.join(" ")),
+        })
+    }
+
+    /// A name of an object, a property or a module, backtick-quoted if
+    /// needed
+    pub fn name(name: &str) -> Result<Fragment, TemplateError> {
+        let mut text = quote_name(name).into_owned();
+        if text.starts_with(|c: char| c.is_ascii_digit()) {
+            text = format!("`{text}`");
+        }
+        let mut tokens = Tokenizer::new(&text);
+        match (tokens.next(), tokens.next()) {
+            (Some(Ok(token)), None) if matches!(token.kind, Kind::Ident | Kind::BacktickName) => {
+                Ok(Fragment { text })
+            }
+            _ => Err(TemplateError::InvalidName(name.to_string())),
+        }
+    }
+
+    pub fn str(value: &str) -> Fragment {
+        Fragment {
+            text: quote_string(value),
+        }
+    }
+
+    pub fn bytes(value: &[u8]) -> Fragment {
+        let mut text = String::with_capacity(value.len() + 3);
+        text.push_str("b'");
+        for &b in value {
+            match b {
+                b'\'' | b'\\' => {
+                    text.push('\\');
+                    text.push(b as char);
+                }
+                b' '..=b'~' => text.push(b as char),
+                _ => write!(text, "\\x{b:02x}").unwrap(),
+            }
+        }
+        text.push('\'');
+        Fragment { text }
+    }
+
+    pub fn int(value: i64) -> Fragment {
+        Fragment {
+            text: if value < 0 {
+                // `-1` is negation of a literal, which overflows for `i64::MIN`
+                format!("(<int64>'{value}')")
+            } else {
+                format!("{value}")
+            },
+        }
+    }
+
+    pub fn float(value: f64) -> Fragment {
+        Fragment {
+            text: if value.is_finite() && value.is_sign_positive() {
+                format!("{value:?}")
+            } else {
+                format!("(<float64>'{value}')")
+            },
+        }
+    }
+
+    pub fn bool(value: bool) -> Fragment {
+        Fragment {
+            text: value.to_string(),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Fragment, Template, TemplateError};
+
+    fn render(template: &str, values: Vec<(&str, Fragment)>) -> String {
+        Template::parse(template).unwrap().render(values).unwrap()
+    }
+
+    #[test]
+    fn substitution() {
+        let template = Template::parse(r"select \(a) + \(b) + \(a)").unwrap();
+        assert_eq!(template.holes().collect::<Vec<_>>(), ["a", "b"]);
+        assert_eq!(
+            template
+                .render([("a", Fragment::int(1)), ("b", Fragment::int(-2))])
+                .unwrap(),
+            "select 1 + (<int64>'-2') + 1"
+        );
+        assert!(matches!(
+            template.render([("a", Fragment::int(1))]),
+            Err(TemplateError::MissingValue(name)) if name == "b"
+        ));
+        assert!(matches!(
+            template.render([
+                ("a", Fragment::int(1)),
+                ("b", Fragment::int(2)),
+                ("c", Fragment::int(3)),
+            ]),
+            Err(TemplateError::UnknownHole(name)) if name == "c"
+        ));
+        assert_eq!(
+            render(r"select r\(x)", vec![("x", Fragment::str("a\\b"))]),
+            r#"select r "a\\b""#
+        );
+    }
+
+    #[test]
+    fn literals() {
+        let cases = [
+            (Fragment::str("it's \"q\""), r#""it's \"q\"""#),
+            (Fragment::float(-0.0), "(<float64>'-0')"),
+            (Fragment::bytes(b"a'\\\x00\xff"), r"b'a\'\\\x00\xff'"),
+            (Fragment::int(i64::MIN), "(<int64>'-9223372036854775808')"),
+            (Fragment::float(1.0), "1.0"),
+            (Fragment::float(-0.5), "(<float64>'-0.5')"),
+            (Fragment::float(f64::NAN), "(<float64>'NaN')"),
+            (Fragment::bool(true), "true"),
+        ];
+        for (fragment, text) in cases {
+            assert_eq!(fragment.as_str(), text);
+        }
+    }
+
+    #[test]
+    fn names() {
+        assert_eq!(Fragment::name("User").unwrap().as_str(), "User");
+        assert_eq!(Fragment::name("select").unwrap().as_str(), "`select`");
+        assert_eq!(Fragment::name("1st").unwrap().as_str(), "`1st`");
+        assert_eq!(
+            Fragment::name("a` + 1; #").unwrap().as_str(),
+            "`a`` + 1; #`"
+        );
+        assert!(Fragment::name("").is_err());
+        assert!(Fragment::name("__type__").is_err());
+        assert!(Fragment::name("std::str").is_err());
+    }
+
+    #[test]
+    fn expressions() {
+        let sub = render(r"\(a) ++ 'x'", vec![("a", Fragment::str("y"))]);
+        assert_eq!(
+            render(r"select \(e)", vec![("e", Fragment::expr(&sub).unwrap())]),
+            r#"select ("y" ++ 'x')"#
+        );
+        // comments cannot swallow the closing parenthesis
+        assert_eq!(Fragment::expr("1 # comment").unwrap().as_str(), "(1)");
+        assert!(Fragment::expr("1; delete User").is_err());
+        assert!(Fragment::expr("1) union (2").is_err());
+        assert!(Fragment::expr("'unclosed").is_err());
+        assert!(Fragment::expr(r"\(x)").is_err());
+    }
+}
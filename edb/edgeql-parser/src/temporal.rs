@@ -0,0 +1,383 @@
+//! Format checks of date, time and duration literals
+//!
+//! [check_literals] reports string literals cast to date, time and duration
+//! types (like `<datetime>'2024-13-01T00:00:00Z'`) that the server would
+//! reject. Only the format and the ranges of the fields are checked, so a
+//! literal that passes may still fail on the server, e.g. if it is out of
+//! the range of the type.
+//!
+//! Date and time types accept ISO 8601, with a `T` or a space between the
+//! date and the time. Durations accept ISO 8601 (`PT1H30M`), units (`1 hour
+//! 30 minutes`, `1h 30m`) and `HH:MM:SS`.
+use crate::casts::{is_cast_start, read_cast, CastType};
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::position::Span;
+use crate::resolver::Resolver;
+use crate::tokenizer::{Kind, Token, Value};
+
+const TIME_UNITS: &[&str] = &[
+    "us",
+    "microsecond",
+    "microseconds",
+    "ms",
+    "millisecond",
+    "milliseconds",
+    "s",
+    "second",
+    "seconds",
+    "m",
+    "minute",
+    "minutes",
+    "h",
+    "hour",
+    "hours",
+];
+
+const DATE_UNITS: &[&str] = &[
+    "d", "day", "days", "week", "weeks", "month", "months", "year", "years",
+];
+
+/// Reports string literals cast to date, time and duration types that have
+/// an invalid format
+pub fn check_literals(tokens: &[Token], resolver: &dyn Resolver) -> Vec<Diagnostic> {
+    let mut errors = Vec::new();
+    for pos in 0..tokens.len() {
+        if !is_cast_start(tokens, pos) {
+            continue;
+        }
+        let Some((CastType::Scalar(name), operand)) = read_cast(tokens, pos, resolver) else {
+            continue;
+        };
+        let Some(Token {
+            kind: Kind::Str,
+            value: Some(Value::String(text)),
+            span,
+            ..
+        }) = tokens.get(operand)
+        else {
+            continue;
+        };
+        if is_valid(&name, text) == Some(false) {
+            let span = Span {
+                start: tokens[pos].span.start,
+                end: span.end,
+            };
+            let mut error = Diagnostic::with_code(
+                Severity::Error,
+                "invalid-temporal",
+                &[("type", name.clone()), ("text", text.clone())],
+                span,
+            );
+            if let Some(example) = example(&name) {
+                error = error.with_arg("example", example).with_hint();
+            }
+            errors.push(error);
+        }
+    }
+    errors
+}
+
+/// Checks the format of a value of a date, time or duration type, returns
+/// `None` for other types
+pub fn is_valid(type_name: &str, text: &str) -> Option<bool> {
+    let mut cursor = Cursor {
+        text: text.as_bytes(),
+        pos: 0,
+    };
+    let valid = match type_name {
+        "std::datetime" => cursor.datetime() && cursor.timezone() && cursor.is_end(),
+        "cal::local_datetime" => cursor.datetime() && cursor.is_end(),
+        "cal::local_date" => cursor.date() && cursor.is_end(),
+        "cal::local_time" => cursor.time() && cursor.is_end(),
+        "std::duration" => is_duration(text, TIME_UNITS, &[]),
+        "cal::relative_duration" => is_duration(text, TIME_UNITS, DATE_UNITS),
+        "cal::date_duration" => is_duration(text, &[], DATE_UNITS),
+        _ => return None,
+    };
+    Some(valid)
+}
+
+fn example(type_name: &str) -> Option<&'static str> {
+    Some(match type_name {
+        "std::datetime" => "2024-12-27T23:59:59-07:00",
+        "cal::local_datetime" => "2024-12-27T23:59:59",
+        "cal::local_date" => "2024-12-27",
+        "cal::local_time" => "23:59:59",
+        "std::duration" => "1 hour 30 minutes",
+        "cal::relative_duration" => "1 year 2 days",
+        "cal::date_duration" => "1 month 2 days",
+        _ => return None,
+    })
+}
+
+fn is_leap_year(year: u32) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+struct Cursor<'a> {
+    text: &'a [u8],
+    pos: usize,
+}
+
+impl Cursor<'_> {
+    fn is_end(&self) -> bool {
+        self.pos == self.text.len()
+    }
+
+    fn eat(&mut self, ch: u8) -> bool {
+        if self.text.get(self.pos) == Some(&ch) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Reads exactly `n` digits
+    fn digits(&mut self, n: usize) -> Option<u32> {
+        let digits = self.text.get(self.pos..self.pos + n)?;
+        if !digits.iter().all(u8::is_ascii_digit) {
+            return None;
+        }
+        self.pos += n;
+        Some(
+            digits
+                .iter()
+                .fold(0, |acc, d| acc * 10 + u32::from(d - b'0')),
+        )
+    }
+
+    /// Reads one or more digits, returns false if there are none
+    fn number(&mut self) -> bool {
+        let start = self.pos;
+        while self.text.get(self.pos).is_some_and(u8::is_ascii_digit) {
+            self.pos += 1;
+        }
+        self.pos > start
+    }
+
+    /// Reads an optional fraction: `.` followed by digits
+    fn fraction(&mut self) -> bool {
+        !self.eat(b'.') || self.number()
+    }
+
+    fn date(&mut self) -> bool {
+        let (Some(year), true, Some(month), true, Some(day)) = (
+            self.digits(4),
+            self.eat(b'-'),
+            self.digits(2),
+            self.eat(b'-'),
+            self.digits(2),
+        ) else {
+            return false;
+        };
+        let days = match month {
+            1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+            4 | 6 | 9 | 11 => 30,
+            2 if is_leap_year(year) => 29,
+            2 => 28,
+            _ => return false,
+        };
+        (1..=days).contains(&day)
+    }
+
+    /// `HH:MM`, with optional seconds and a fraction of them
+    fn time(&mut self) -> bool {
+        let (Some(hours), true, Some(minutes)) = (self.digits(2), self.eat(b':'), self.digits(2))
+        else {
+            return false;
+        };
+        if hours > 23 || minutes > 59 {
+            return false;
+        }
+        if !self.eat(b':') {
+            return true;
+        }
+        self.digits(2).is_some_and(|seconds| seconds <= 59) && self.fraction()
+    }
+
+    fn datetime(&mut self) -> bool {
+        self.date() && (self.eat(b'T') || self.eat(b't') || self.eat(b' ')) && self.time()
+    }
+
+    /// `Z`, `+HH`, `+HHMM` or `+HH:MM`, or the same with `-`
+    fn timezone(&mut self) -> bool {
+        if self.eat(b'Z') || self.eat(b'z') {
+            return true;
+        }
+        if !self.eat(b'+') && !self.eat(b'-') {
+            return false;
+        }
+        if !self.digits(2).is_some_and(|hours| hours <= 23) {
+            return false;
+        }
+        if self.is_end() {
+            return true;
+        }
+        self.eat(b':');
+        self.digits(2).is_some_and(|minutes| minutes <= 59)
+    }
+}
+
+fn is_duration(text: &str, time_units: &[&str], date_units: &[&str]) -> bool {
+    let text = text.trim();
+    if let Some(iso) = text.strip_prefix('P') {
+        return is_iso_duration(iso, !time_units.is_empty(), !date_units.is_empty());
+    }
+    if text.contains(':') {
+        let text = text.strip_prefix(['+', '-']).unwrap_or(text);
+        let mut cursor = Cursor {
+            text: text.as_bytes(),
+            pos: 0,
+        };
+        let valid = cursor.number()
+            && cursor.eat(b':')
+            && cursor.digits(2).is_some_and(|m| m <= 59)
+            && (!cursor.eat(b':')
+                || (cursor.digits(2).is_some_and(|s| s <= 59) && cursor.fraction()));
+        return !time_units.is_empty() && valid && cursor.is_end();
+    }
+    let mut cursor = Cursor {
+        text: text.as_bytes(),
+        pos: 0,
+    };
+    let mut parts = 0;
+    while !cursor.is_end() {
+        let _ = cursor.eat(b'+') || cursor.eat(b'-');
+        if !cursor.number() || !cursor.fraction() {
+            return false;
+        }
+        while cursor.eat(b' ') {}
+        let start = cursor.pos;
+        while cursor
+            .text
+            .get(cursor.pos)
+            .is_some_and(u8::is_ascii_alphabetic)
+        {
+            cursor.pos += 1;
+        }
+        let unit = text[start..cursor.pos].to_ascii_lowercase();
+        if !time_units.contains(&&unit[..]) && !date_units.contains(&&unit[..]) {
+            return false;
+        }
+        while cursor.eat(b' ') {}
+        parts += 1;
+    }
+    parts > 0
+}
+
+/// ISO 8601 duration after the `P`, like `1Y2M3DT4H5M6.5S`
+fn is_iso_duration(text: &str, time: bool, date: bool) -> bool {
+    let (date_part, time_part) = match text.split_once('T') {
+        Some((date_part, time_part)) => (date_part, Some(time_part)),
+        None => (text, None),
+    };
+    let components = |part: &str, designators: &[u8]| -> Option<usize> {
+        let mut cursor = Cursor {
+            text: part.as_bytes(),
+            pos: 0,
+        };
+        let mut next = 0;
+        let mut count = 0;
+        while !cursor.is_end() {
+            if !cursor.number() || !cursor.fraction() {
+                return None;
+            }
+            let designator = cursor.text.get(cursor.pos)?;
+            next += designators[next..].iter().position(|d| d == designator)? + 1;
+            cursor.pos += 1;
+            count += 1;
+        }
+        Some(count)
+    };
+    let Some(date_count) = components(date_part, b"YMWD") else {
+        return false;
+    };
+    let time_count = match time_part {
+        Some(part) => match components(part, b"HMS") {
+            Some(count) if count > 0 => count,
+            _ => return false,
+        },
+        None => 0,
+    };
+    (date || date_count == 0) && (time || time_count == 0) && date_count + time_count > 0
+}
+
+#[cfg(test)]
+mod test {
+    use super::{check_literals, is_valid};
+    use crate::resolver::SchemaResolver;
+    use crate::schema::parse;
+    use crate::tokenizer::tokens;
+
+    fn errors(query: &str) -> Vec<String> {
+        let schema = parse("scalar type Created extending datetime;").unwrap();
+        let resolver = SchemaResolver::new(&schema);
+        let tokens = tokens(query);
+        check_literals(&tokens, &resolver)
+            .into_iter()
+            .map(|e| {
+                let span = e.span;
+                format!(
+                    "{} @{}",
+                    e.message,
+                    &query[span.start as usize..span.end as usize]
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn formats() {
+        let valid = [
+            ("std::datetime", "2024-02-29T12:30:00Z"),
+            ("std::datetime", "2024-01-01 00:00:00.123456+02:00"),
+            ("std::datetime", "2024-01-01T00:00-0700"),
+            ("cal::local_datetime", "2024-01-01T23:59:59"),
+            ("cal::local_date", "2023-12-31"),
+            ("cal::local_time", "23:59"),
+            ("std::duration", "48h 45m"),
+            ("std::duration", "1 hour -2.5 minutes"),
+            ("std::duration", "PT1H30M"),
+            ("std::duration", "-01:30:00"),
+            ("cal::relative_duration", "P1Y2DT3H"),
+            ("cal::relative_duration", "1 year 2 days 3 hours"),
+            ("cal::date_duration", "2 weeks"),
+        ];
+        for (ty, text) in valid {
+            assert_eq!(is_valid(ty, text), Some(true), "{ty} {text}");
+        }
+        let invalid = [
+            ("std::datetime", "2023-02-29T00:00:00Z"),
+            ("std::datetime", "2024-01-01T00:00:00"),
+            ("cal::local_datetime", "2024-01-01T00:00:00Z"),
+            ("cal::local_date", "2023-1-1"),
+            ("cal::local_time", "24:00"),
+            ("std::duration", "1 day"),
+            ("std::duration", "P1D"),
+            ("std::duration", "PT1M2H"),
+            ("std::duration", "hour"),
+            ("cal::date_duration", "1 hour"),
+        ];
+        for (ty, text) in invalid {
+            assert_eq!(is_valid(ty, text), Some(false), "{ty} {text}");
+        }
+        assert_eq!(is_valid("std::str", ""), None);
+    }
+
+    #[test]
+    fn literals() {
+        assert_eq!(
+            errors(
+                "select (<datetime>'2024-01-01T00:00:00Z', <optional Created>'yesterday', \
+                 <cal::local_date>'2024-02-30', <str>'x', <duration>$d, 'a' < 'b')"
+            ),
+            [
+                "invalid input syntax for type std::datetime: 'yesterday' \
+                 @<optional Created>'yesterday'",
+                "invalid input syntax for type cal::local_date: '2024-02-30' \
+                 @<cal::local_date>'2024-02-30'",
+            ]
+        );
+    }
+}
@@ -0,0 +1,152 @@
+//! Token streams with substitutions
+//!
+//! [TokenStream] is a query as a list of validated tokens.
+//! [TokenStream::substitute] replaces identifiers with other streams, like
+//! a macro expansion, which allows simple templating of queries on the
+//! client:
+//!
+//! ```
+//! use std::collections::HashMap;
+//! use edgeql_parser::token_stream::TokenStream;
+//!
+//! let query = TokenStream::parse("select User filter .age > MIN_AGE").unwrap();
+//! let min_age = TokenStream::parse("18 + 3").unwrap();
+//! let query = query.substitute(&HashMap::from([("MIN_AGE", min_age)])).unwrap();
+//! assert_eq!(query.to_string(), "select User filter . age > ( 18 + 3 )");
+//! ```
+//!
+//! Unlike [template](crate::template), there are no dedicated holes: every
+//! identifier with a name in the map is replaced, except for path steps
+//! (`.name`, `@name`) and parts of qualified names. Quoted names (`` `x` ``)
+//! are never replaced.
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::expr;
+use crate::tokenizer::{self, Kind, Token, Tokenizer};
+
+#[derive(Debug, thiserror::Error)]
+pub enum SubstituteError {
+    #[error("{0}")]
+    Tokenizer(tokenizer::Error),
+    #[error("invalid expression for {name}: {error}")]
+    Expression { name: String, error: expr::Error },
+}
+
+/// Validated tokens of a query or an expression, without comments
+#[derive(Debug, Clone)]
+pub struct TokenStream {
+    tokens: Vec<Token<'static>>,
+}
+
+impl TokenStream {
+    /// Tokenizes and validates the text
+    pub fn parse(text: &str) -> Result<TokenStream, tokenizer::Error> {
+        let tokens = Tokenizer::new(text)
+            .validated_values()
+            .map(|t| t.map(|t| t.cloned()))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(TokenStream { tokens })
+    }
+
+    pub fn tokens(&self) -> &[Token<'static>] {
+        &self.tokens
+    }
+
+    /// Replaces identifiers with the streams of the map
+    ///
+    /// Every stream must be a single expression (see [expr::check]), and
+    /// is parenthesized unless it is a single token. The result is
+    /// tokenized again, so spans of its tokens refer to its
+    /// [to_string](ToString::to_string).
+    pub fn substitute(
+        &self,
+        map: &HashMap<&str, TokenStream>,
+    ) -> Result<TokenStream, SubstituteError> {
+        for (name, stream) in map {
+            expr::check(&stream.to_string()).map_err(|error| SubstituteError::Expression {
+                name: name.to_string(),
+                error,
+            })?;
+        }
+        let mut text = String::new();
+        for (idx, tok) in self.tokens.iter().enumerate() {
+            if !text.is_empty() {
+                text.push(' ');
+            }
+            let replacement = map.get(&tok.text[..]).filter(|_| self.is_replaceable(idx));
+            match replacement {
+                Some(stream) if stream.tokens.len() == 1 => text.push_str(&stream.to_string()),
+                Some(stream) => text.push_str(&format!("( {stream} )")),
+                None => text.push_str(&tok.text),
+            }
+        }
+        TokenStream::parse(&text).map_err(SubstituteError::Tokenizer)
+    }
+
+    fn is_replaceable(&self, idx: usize) -> bool {
+        let kind = |idx: Option<usize>| idx.and_then(|i| self.tokens.get(i)).map(|t| t.kind);
+        kind(Some(idx)) == Some(Kind::Ident)
+            && !matches!(
+                kind(idx.checked_sub(1)),
+                Some(
+                    Kind::Dot
+                        | Kind::BackwardLink
+                        | Kind::OptionalLink
+                        | Kind::At
+                        | Kind::Namespace
+                )
+            )
+            && kind(Some(idx + 1)) != Some(Kind::Namespace)
+    }
+}
+
+impl fmt::Display for TokenStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (idx, tok) in self.tokens.iter().enumerate() {
+            if idx > 0 {
+                f.write_str(" ")?;
+            }
+            f.write_str(&tok.text)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::{SubstituteError, TokenStream};
+
+    fn stream(text: &str) -> TokenStream {
+        TokenStream::parse(text).unwrap()
+    }
+
+    #[test]
+    fn substitute() {
+        let query = stream(
+            "select Item { NAME, total := .price * RATE } \
+             filter .RATE > RATE and default::RATE = `RATE` # RATE",
+        );
+        let map = HashMap::from([("RATE", stream("1.5 * discount")), ("NAME", stream("name"))]);
+        let result = query.substitute(&map).unwrap();
+        assert_eq!(
+            result.to_string(),
+            "select Item { name , total := . price * ( 1.5 * discount ) } \
+             filter . RATE > ( 1.5 * discount ) and default :: RATE = `RATE`"
+        );
+        let span = result.tokens()[1].span;
+        assert_eq!((span.start, span.end), (7, 11));
+    }
+
+    #[test]
+    fn invalid() {
+        let query = stream("select X");
+        let map = HashMap::from([("X", stream("1; delete User"))]);
+        assert!(matches!(
+            query.substitute(&map),
+            Err(SubstituteError::Expression { name, .. }) if name == "X"
+        ));
+    }
+}
@@ -5,7 +5,10 @@ use std::str::CharIndices;
 use bigdecimal::BigDecimal;
 use memchr::memmem::find;
 
+use crate::experimental;
+use crate::helpers::unicode_escape;
 use crate::keywords::{self, Keyword};
+use crate::options::{LimitError, ParserOptions};
 use crate::position::{Pos, Span};
 use crate::validation::Validator;
 
@@ -21,6 +24,9 @@ pub struct Token<'a> {
     /// Parsed during validation.
     pub value: Option<Value>,
 
+    /// Defaults to an empty span when deserializing tokens serialized
+    /// without spans, see [SerializeOptions]
+    #[cfg_attr(feature = "serde", serde(default))]
     pub span: Span,
 }
 
@@ -43,6 +49,20 @@ pub struct Error {
     pub span: Span,
     pub hint: Option<String>,
     pub details: Option<String>,
+    pub kind: Option<ErrorKind>,
+}
+
+/// Errors that callers handle differently from the others
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// A token is not terminated before the end of the buffer, see
+    /// [Tokenizer::incomplete]
+    Unterminated,
+    /// Parsing was aborted, see
+    /// [Context::with_cancel](crate::parser::Context::with_cancel)
+    Cancelled,
+    /// A `;` is missing between two statements, the span is where it goes
+    MissingSemicolon,
 }
 
 impl Error {
@@ -52,9 +72,19 @@ impl Error {
             span: Span::default(),
             hint: None,
             details: None,
+            kind: None,
         }
     }
 
+    fn unterminated<S: ToString>(message: S) -> Self {
+        Error::new(message).with_kind(ErrorKind::Unterminated)
+    }
+
+    pub fn with_kind(mut self, kind: ErrorKind) -> Self {
+        self.kind = Some(kind);
+        self
+    }
+
     pub fn with_span(mut self, span: Span) -> Self {
         self.span = span;
         self
@@ -116,7 +146,7 @@ pub enum Kind {
     IntConst,
     BigIntConst,
     BinStr, // b"xx", b'xx'
-    Str,    // "xx", 'xx', r"xx", r'xx', $$xx$$
+    Str,    // "xx", 'xx', r"xx", r'xx', $$xx$$, """xx""", '''xx'''
 
     StrInterpStart, // "xx\(, 'xx\(
     StrInterpCont,  // )xx\(
@@ -162,6 +192,26 @@ pub struct Tokenizer<'a> {
     // paren when there are no open parens *and* we are inside a
     // string inerpolation, we close it.
     open_parens: usize,
+    // Whether `using sql { ... }` is lexed as a single string token, see
+    // [Tokenizer::with_sql_blocks]
+    sql_blocks: bool,
+    after_using: bool,
+    before_sql_block: bool,
+    // See [Tokenizer::with_options]
+    options: ParserOptions,
+    limit_error: Option<LimitError>,
+    // See [Tokenizer::with_at_eof]
+    at_eof: bool,
+    incomplete: Option<Incomplete>,
+}
+
+/// Token cut off by the end of the buffer, see [Tokenizer::with_at_eof]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Incomplete {
+    /// From the start of the token to the end of the buffer
+    pub span: Span,
+    /// Error reported for the token if the input is complete
+    pub message: String,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -169,6 +219,8 @@ pub struct Checkpoint {
     position: Pos,
     off: usize,
     dot: bool,
+    after_using: bool,
+    before_sql_block: bool,
 }
 
 impl<'a> Iterator for Tokenizer<'a> {
@@ -190,7 +242,7 @@ impl<'a> Iterator for Tokenizer<'a> {
                 })
                 .map_err(|e| {
                     let end = self.position.offset;
-                    e.with_span(Span { start, end })
+                    e.default_span_to(Span { start, end })
                 }),
         )
     }
@@ -213,6 +265,13 @@ impl<'a> Tokenizer<'a> {
             keyword_buf: String::with_capacity(MAX_KEYWORD_LENGTH),
             str_interp_stack: Vec::new(),
             open_parens: 0,
+            sql_blocks: false,
+            after_using: false,
+            before_sql_block: false,
+            options: ParserOptions::default(),
+            limit_error: None,
+            at_eof: true,
+            incomplete: None,
         };
         me.skip_whitespace();
         me
@@ -232,11 +291,60 @@ impl<'a> Tokenizer<'a> {
             // XXX: If we are in the middle of an interpolated string we will have trouble
             str_interp_stack: Vec::new(),
             open_parens: 0,
+            sql_blocks: false,
+            after_using: false,
+            before_sql_block: false,
+            options: ParserOptions::default(),
+            limit_error: None,
+            at_eof: true,
+            incomplete: None,
         };
         me.skip_whitespace();
         me
     }
 
+    /// Lexes the SQL of `using sql { ... }` as a single [Kind::Str] token
+    /// covering the braces, instead of tokenizing it as EdgeQL. The body is
+    /// scanned for SQL quotes (`'...'`, `"..."`, `$tag$...$tag$`) and
+    /// comments, so a brace inside of them does not end the block. The value
+    /// of the token is the text between the braces, so the block can be
+    /// used wherever a string with SQL is expected.
+    pub fn with_sql_blocks(mut self) -> Self {
+        self.sql_blocks = true;
+        self
+    }
+
+    /// Applies the limits of `options`: when the input or a token is too
+    /// long, an error is yielded instead of the token, and the typed error
+    /// is available via [Tokenizer::limit_error].
+    pub fn with_options(mut self, options: &ParserOptions) -> Self {
+        self.options = *options;
+        self
+    }
+
+    /// The limit of [ParserOptions] exceeded by the last error, if any
+    pub fn limit_error(&self) -> Option<&LimitError> {
+        self.limit_error.as_ref()
+    }
+
+    /// Whether the end of the buffer is the end of the input, the default.
+    ///
+    /// With `false` the input is streamed: a string, a quoted name or an
+    /// SQL block that is unterminated at the end of the buffer is not an
+    /// error, instead the tokens end before it and the cut off token is
+    /// available via [Tokenizer::incomplete]. Tokenizing again once more
+    /// input is available continues from the start of that token.
+    pub fn with_at_eof(mut self, at_eof: bool) -> Self {
+        self.at_eof = at_eof;
+        self
+    }
+
+    /// The token at the end of the buffer that needs more input, if any,
+    /// see [Tokenizer::with_at_eof]
+    pub fn incomplete(&self) -> Option<&Incomplete> {
+        self.incomplete.as_ref()
+    }
+
     pub fn validated_values(self) -> Validator<'a> {
         Validator::new(self)
     }
@@ -246,6 +354,8 @@ impl<'a> Tokenizer<'a> {
             position: self.position,
             off: self.off,
             dot: self.dot,
+            after_using: self.after_using,
+            before_sql_block: self.before_sql_block,
         }
     }
 
@@ -253,6 +363,8 @@ impl<'a> Tokenizer<'a> {
         self.position = checkpoint.position;
         self.off = checkpoint.off;
         self.dot = checkpoint.dot;
+        self.after_using = checkpoint.after_using;
+        self.before_sql_block = checkpoint.before_sql_block;
     }
 
     pub fn current_pos(&self) -> Pos {
@@ -268,16 +380,43 @@ impl<'a> Tokenizer<'a> {
             if at == self.off {
                 self.off = off;
                 self.position = next;
+                self.track_sql_block(tok);
                 return Some(Ok((tok, end)));
             }
         }
 
+        if let Err(e) = self.options.check_input(self.buf) {
+            self.limit_error = Some(e.clone());
+            return Some(Err(e.into()));
+        }
+
         let old_pos = self.off;
         let (kind, len) = match self.peek_token()? {
             Ok(x) => x,
+            Err(e) if !self.at_eof && e.kind == Some(ErrorKind::Unterminated) => {
+                let start = self.position.offset;
+                self.incomplete = Some(Incomplete {
+                    span: Span {
+                        start,
+                        end: start + (self.buf.len() - self.off) as u64,
+                    },
+                    message: e.message,
+                });
+                return None;
+            }
             Err(e) => return Some(Err(e)),
         };
 
+        let start = self.position.offset;
+        let span = Span {
+            start,
+            end: start + len as u64,
+        };
+        if let Err(e) = self.options.check_token(span) {
+            self.limit_error = Some(e.clone());
+            return Some(Err(e.into()));
+        }
+
         match kind {
             StrInterpStart => {
                 let start = self.buf[self.off..].chars().next()?;
@@ -306,16 +445,29 @@ impl<'a> Tokenizer<'a> {
 
         self.skip_whitespace();
         let token = TokenStub { kind, text: value };
+        self.track_sql_block(token);
         // This is for quick reset on token back
         self.next_state = Some((old_pos, token, self.off, end, self.position));
         Some(Ok((token, end)))
     }
 
+    fn track_sql_block(&mut self, token: TokenStub) {
+        if self.sql_blocks {
+            self.before_sql_block = self.after_using
+                && token.kind == Kind::Ident
+                && token.text.eq_ignore_ascii_case("sql");
+            self.after_using = token.kind == Kind::Keyword(Keyword("using"));
+        }
+    }
+
     fn peek_token(&mut self) -> Option<Result<(Kind, usize), Error>> {
         let tail = &self.buf[self.off..];
         let mut iter = tail.char_indices();
 
         let (_, cur_char) = iter.next()?;
+        if let Some(result) = experimental::peek_token(self, tail) {
+            return Some(result);
+        }
         Some(self.peek_token_inner(cur_char, tail, &mut iter))
     }
 
@@ -433,7 +585,7 @@ impl<'a> Tokenizer<'a> {
                     }
                     check_prohibited(c, false)?;
                 }
-                Err(Error::new("unterminated backtick name"))
+                Err(Error::unterminated("unterminated backtick name"))
             }
             '=' => Ok((Eq, 1)),
             ',' => Ok((Comma, 1)),
@@ -446,6 +598,7 @@ impl<'a> Tokenizer<'a> {
             },
             '[' => Ok((OpenBracket, 1)),
             ']' => Ok((CloseBracket, 1)),
+            '{' if self.before_sql_block => self.parse_sql_block(),
             '{' => Ok((OpenBrace, 1)),
             '}' => Ok((CloseBrace, 1)),
             ';' => Ok((Semicolon, 1)),
@@ -540,7 +693,9 @@ impl<'a> Tokenizer<'a> {
                                 }
                                 return Ok((Str, 2 + end + 2));
                             } else {
-                                return Err(Error::new("unterminated string started with $$"));
+                                return Err(Error::unterminated(
+                                    "unterminated string started with $$",
+                                ));
                             }
                         }
                         '`' => {
@@ -578,7 +733,7 @@ impl<'a> Tokenizer<'a> {
                                 }
                                 check_prohibited(c, false)?;
                             }
-                            return Err(Error::new("unterminated backtick argument"));
+                            return Err(Error::unterminated("unterminated backtick argument"));
                         }
                         '0'..='9' => {}
                         c if c.is_alphabetic() || c == '_' => {
@@ -609,7 +764,7 @@ impl<'a> Tokenizer<'a> {
                                 }
                                 return Ok((Str, msize + end + msize));
                             } else {
-                                return Err(Error::new(format_args!(
+                                return Err(Error::unterminated(format_args!(
                                     "unterminated string started with {marker:?}"
                                 )));
                             }
@@ -650,7 +805,7 @@ impl<'a> Tokenizer<'a> {
                                 ));
                             }
                             None => {
-                                return Err(Error::new("unclosed \\(name) token"));
+                                return Err(Error::unterminated("unclosed \\(name) token"));
                             }
                         }
                     };
@@ -698,6 +853,9 @@ impl<'a> Tokenizer<'a> {
                 match c {
                     '\\' if !raw => match iter.next() {
                         Some((idx, '(')) => return Ok((Kind::StrInterpStart, quote_off + idx + 1)),
+                        Some((_, 'u' | 'U')) => {
+                            self.check_unicode_escape(self.off + quote_off + idx)?
+                        }
                         // skip any next char, even quote
                         Some((_, _)) => continue,
                         None => break,
@@ -707,11 +865,130 @@ impl<'a> Tokenizer<'a> {
                 }
             }
         }
-        Err(Error::new(format_args!(
+        Err(Error::unterminated(format_args!(
             "unterminated string, quoted by `{open_quote}`"
         )))
     }
 
+    /// Heredoc strings are raw and may span multiple lines, their value has
+    /// the indentation stripped (see [crate::helpers::dedent])
+    #[cfg(feature = "future-syntax")]
+    pub(crate) fn parse_heredoc(&self, delim: &str) -> Result<(Kind, usize), Error> {
+        let body = &self.buf[self.off + delim.len()..];
+        let Some(end) = find(body.as_bytes(), delim.as_bytes()) else {
+            return Err(Error::unterminated(format_args!(
+                "unterminated string, quoted by `{delim}`"
+            )));
+        };
+        for c in body[..end].chars() {
+            check_prohibited(c, false)?;
+        }
+        Ok((Kind::Str, delim.len() * 2 + end))
+    }
+
+    /// Finds the brace closing the SQL block, skipping SQL strings, quoted
+    /// identifiers and comments
+    fn parse_sql_block(&self) -> Result<(Kind, usize), Error> {
+        let body = &self.buf[self.off..];
+        let bytes = body.as_bytes();
+        let mut brackets = Vec::new();
+        let mut idx = 0;
+        while idx < bytes.len() {
+            let c = bytes[idx];
+            match c {
+                b'(' | b'[' | b'{' => brackets.push(c),
+                b')' | b']' | b'}' => {
+                    let open = match c {
+                        b')' => b'(',
+                        b']' => b'[',
+                        _ => b'{',
+                    };
+                    if brackets.pop() != Some(open) {
+                        return Err(Error::new(format_args!(
+                            "unbalanced `{}` in SQL block",
+                            c as char
+                        )));
+                    }
+                    if brackets.is_empty() {
+                        return Ok((Kind::Str, idx + 1));
+                    }
+                }
+                b'\'' | b'"' => {
+                    // a doubled quote is an escaped one
+                    let mut end = idx + 1;
+                    loop {
+                        match bytes[end..].iter().position(|b| *b == c) {
+                            Some(pos) if bytes.get(end + pos + 1) == Some(&c) => end += pos + 2,
+                            Some(pos) => {
+                                end += pos;
+                                break;
+                            }
+                            None => {
+                                return Err(Error::unterminated(format_args!(
+                                    "unterminated string in SQL block, quoted by `{}`",
+                                    c as char
+                                )))
+                            }
+                        }
+                    }
+                    idx = end;
+                }
+                b'$' if !bytes[..idx]
+                    .last()
+                    .is_some_and(|b| b.is_ascii_alphanumeric() || *b == b'_') =>
+                {
+                    // dollar-quoted string, `$1` is a parameter, not a tag
+                    let tag_len = bytes[idx + 1..]
+                        .iter()
+                        .take_while(|b| b.is_ascii_alphanumeric() || **b == b'_')
+                        .count();
+                    let is_tag = bytes.get(idx + tag_len + 1) == Some(&b'$')
+                        && !bytes[idx + 1].is_ascii_digit();
+                    if is_tag {
+                        let tag = &body[idx..idx + tag_len + 2];
+                        let start = idx + tag.len();
+                        let Some(end) = find(&bytes[start..], tag.as_bytes()) else {
+                            return Err(Error::unterminated(format_args!(
+                                "unterminated string in SQL block, quoted by `{tag}`"
+                            )));
+                        };
+                        idx = start + end + tag.len() - 1;
+                    }
+                }
+                b'-' if bytes.get(idx + 1) == Some(&b'-') => {
+                    match bytes[idx..].iter().position(|b| *b == b'\n') {
+                        Some(pos) => idx += pos,
+                        None => break,
+                    }
+                }
+                b'/' if bytes.get(idx + 1) == Some(&b'*') => {
+                    // comments nest in SQL
+                    let mut depth = 0;
+                    loop {
+                        if bytes[idx..].starts_with(b"/*") {
+                            depth += 1;
+                            idx += 2;
+                        } else if bytes[idx..].starts_with(b"*/") {
+                            depth -= 1;
+                            idx += 2;
+                            if depth == 0 {
+                                break;
+                            }
+                        } else if idx < bytes.len() {
+                            idx += 1;
+                        } else {
+                            return Err(Error::unterminated("unterminated comment in SQL block"));
+                        }
+                    }
+                    continue;
+                }
+                _ => {}
+            }
+            idx += 1;
+        }
+        Err(Error::unterminated("unterminated SQL block"))
+    }
+
     fn parse_string_interp_cont(&self, end: &str) -> Result<(Kind, usize), Error> {
         let quote_off = 1;
         let mut iter = self.buf[self.off + quote_off..].char_indices();
@@ -720,6 +997,9 @@ impl<'a> Tokenizer<'a> {
             match c {
                 '\\' => match iter.next() {
                     Some((idx, '(')) => return Ok((Kind::StrInterpCont, quote_off + idx + 1)),
+                    Some((_, 'u' | 'U')) => {
+                        self.check_unicode_escape(self.off + quote_off + idx)?
+                    }
                     // skip any next char, even quote
                     Some((_, _)) => continue,
                     None => break,
@@ -730,11 +1010,35 @@ impl<'a> Tokenizer<'a> {
                 _ => check_prohibited(c, true)?,
             }
         }
-        Err(Error::new(format_args!(
+        Err(Error::unterminated(format_args!(
             "unterminated string with interpolations, quoted by `{end}`",
         )))
     }
 
+    /// Checks the code point of the `\u` or `\U` escape at offset `off`
+    ///
+    /// Only escapes with enough hex digits are checked, other malformed
+    /// escapes are reported when the value of the string is validated.
+    fn check_unicode_escape(&self, off: usize) -> Result<(), Error> {
+        let digits = if self.buf[off + 1..].starts_with('u') {
+            4
+        } else {
+            8
+        };
+        let hex = match self.buf.get(off + 2..off + 2 + digits) {
+            Some(hex) if hex.bytes().all(|b| b.is_ascii_hexdigit()) => hex,
+            _ => return Ok(()),
+        };
+        let code = u32::from_str_radix(hex, 16).expect("hex digits");
+        unicode_escape(code).map(|_| ()).map_err(|e| {
+            let start = self.position.offset + (off - self.off) as u64;
+            Error::new(e).with_span(Span {
+                start,
+                end: start + 2 + digits as u64,
+            })
+        })
+    }
+
     fn parse_number(&mut self) -> Result<(Kind, usize), Error> {
         #[derive(PartialEq, PartialOrd)]
         enum Break {
@@ -1004,6 +1308,37 @@ impl Token<'_> {
     }
 }
 
+/// Tokens of a text that must be valid, for tests of checks working on
+/// tokens
+#[cfg(test)]
+pub(crate) fn tokens(text: &str) -> Vec<Token<'static>> {
+    Tokenizer::new(text)
+        .validated_values()
+        .map(|t| t.unwrap().cloned())
+        .collect()
+}
+
+/// Terminal with the text at the offset `start`, for tests of the parser
+/// and of the trees it builds
+#[cfg(test)]
+pub(crate) fn terminal(
+    kind: Kind,
+    text: &str,
+    start: u64,
+    is_placeholder: bool,
+) -> crate::parser::Terminal {
+    crate::parser::Terminal {
+        kind,
+        text: text.into(),
+        value: None,
+        span: Span {
+            start,
+            end: start + text.len() as u64,
+        },
+        is_placeholder,
+    }
+}
+
 fn check_prohibited(c: char, escape: bool) -> Result<(), Error> {
     match c {
         '\0' if escape => Err(Error::new("character U+0000 is not allowed")),
@@ -1065,6 +1400,273 @@ where
     deserializer.deserialize_str(Visitor)
 }
 
+/// Fields of [SerializedToken] that are not always needed
+#[cfg(feature = "serde")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SerializeOptions {
+    /// Include the `span` of the token
+    pub spans: bool,
+    /// Include the `category` of keywords, see [Keyword::category]
+    pub categories: bool,
+}
+
+/// Token serialized as a map of `kind`, `text` and `value`, with `span`
+/// and `category` depending on the [SerializeOptions]
+///
+/// The result deserializes as a [Token], the category is ignored as it is
+/// known from the keyword.
+#[cfg(feature = "serde")]
+#[derive(Debug, Clone, Copy)]
+pub struct SerializedToken<'t> {
+    token: &'t Token<'t>,
+    options: SerializeOptions,
+}
+
+#[cfg(feature = "serde")]
+impl SerializeOptions {
+    pub fn token<'t>(self, token: &'t Token<'t>) -> SerializedToken<'t> {
+        SerializedToken {
+            token,
+            options: self,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for SerializedToken<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeMap;
+
+        let token = self.token;
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("kind", &token.kind)?;
+        map.serialize_entry("text", &token.text)?;
+        map.serialize_entry("value", &token.value)?;
+        if self.options.spans {
+            map.serialize_entry("span", &token.span)?;
+        }
+        if let (true, Kind::Keyword(keyword)) = (self.options.categories, token.kind) {
+            map.serialize_entry("category", &keyword.category())?;
+        }
+        map.end()
+    }
+}
+
+/// [Kind::id] of all keywords
+pub const KEYWORD_ID: u16 = 51;
+
+impl Kind {
+    /// Stable numeric identifier of the kind, for binary formats and FFI
+    ///
+    /// Identifiers never change and are never reused: new kinds get new
+    /// numbers and removed kinds leave gaps. All keywords share
+    /// [KEYWORD_ID], a keyword itself is identified by its text.
+    pub const fn id(&self) -> u16 {
+        use Kind::*;
+
+        match self {
+            Assign => 0,
+            SubAssign => 1,
+            AddAssign => 2,
+            Arrow => 3,
+            Coalesce => 4,
+            Namespace => 5,
+            BackwardLink => 6,
+            OptionalLink => 7,
+            FloorDiv => 8,
+            Concat => 9,
+            GreaterEq => 10,
+            LessEq => 11,
+            NotEq => 12,
+            NotDistinctFrom => 13,
+            DistinctFrom => 14,
+            Comma => 15,
+            OpenParen => 16,
+            CloseParen => 17,
+            OpenBracket => 18,
+            CloseBracket => 19,
+            OpenBrace => 20,
+            CloseBrace => 21,
+            Dot => 22,
+            Semicolon => 23,
+            Colon => 24,
+            Add => 25,
+            Sub => 26,
+            DoubleSplat => 27,
+            Mul => 28,
+            Div => 29,
+            Modulo => 30,
+            Pow => 31,
+            Less => 32,
+            Greater => 33,
+            Eq => 34,
+            Ampersand => 35,
+            Pipe => 36,
+            At => 37,
+            Parameter => 38,
+            ParameterAndType => 39,
+            DecimalConst => 40,
+            FloatConst => 41,
+            IntConst => 42,
+            BigIntConst => 43,
+            BinStr => 44,
+            Str => 45,
+            StrInterpStart => 46,
+            StrInterpCont => 47,
+            StrInterpEnd => 48,
+            BacktickName => 49,
+            Substitution => 50,
+            Kind::Keyword(_) => KEYWORD_ID,
+            Ident => 52,
+            EOI => 53,
+            Epsilon => 54,
+            StartBlock => 55,
+            StartExtension => 56,
+            StartFragment => 57,
+            StartMigration => 58,
+            StartSDLDocument => 59,
+        }
+    }
+
+    /// Kind with the [Kind::id], `None` for unknown identifiers and for
+    /// [KEYWORD_ID], as keywords are looked up by text with
+    /// [keywords::lookup_all]
+    pub fn from_id(id: u16) -> Option<Kind> {
+        use Kind::*;
+
+        Some(match id {
+            0 => Assign,
+            1 => SubAssign,
+            2 => AddAssign,
+            3 => Arrow,
+            4 => Coalesce,
+            5 => Namespace,
+            6 => BackwardLink,
+            7 => OptionalLink,
+            8 => FloorDiv,
+            9 => Concat,
+            10 => GreaterEq,
+            11 => LessEq,
+            12 => NotEq,
+            13 => NotDistinctFrom,
+            14 => DistinctFrom,
+            15 => Comma,
+            16 => OpenParen,
+            17 => CloseParen,
+            18 => OpenBracket,
+            19 => CloseBracket,
+            20 => OpenBrace,
+            21 => CloseBrace,
+            22 => Dot,
+            23 => Semicolon,
+            24 => Colon,
+            25 => Add,
+            26 => Sub,
+            27 => DoubleSplat,
+            28 => Mul,
+            29 => Div,
+            30 => Modulo,
+            31 => Pow,
+            32 => Less,
+            33 => Greater,
+            34 => Eq,
+            35 => Ampersand,
+            36 => Pipe,
+            37 => At,
+            38 => Parameter,
+            39 => ParameterAndType,
+            40 => DecimalConst,
+            41 => FloatConst,
+            42 => IntConst,
+            43 => BigIntConst,
+            44 => BinStr,
+            45 => Str,
+            46 => StrInterpStart,
+            47 => StrInterpCont,
+            48 => StrInterpEnd,
+            49 => BacktickName,
+            50 => Substitution,
+            52 => Ident,
+            53 => EOI,
+            54 => Epsilon,
+            55 => StartBlock,
+            56 => StartExtension,
+            57 => StartFragment,
+            58 => StartMigration,
+            59 => StartSDLDocument,
+            _ => return None,
+        })
+    }
+}
+
+// Fails to compile if an identifier changes, which would break data
+// serialized by older versions
+const _: () = {
+    assert!(KEYWORD_ID == 51);
+    assert!(Kind::Assign.id() == 0);
+    assert!(Kind::SubAssign.id() == 1);
+    assert!(Kind::AddAssign.id() == 2);
+    assert!(Kind::Arrow.id() == 3);
+    assert!(Kind::Coalesce.id() == 4);
+    assert!(Kind::Namespace.id() == 5);
+    assert!(Kind::BackwardLink.id() == 6);
+    assert!(Kind::OptionalLink.id() == 7);
+    assert!(Kind::FloorDiv.id() == 8);
+    assert!(Kind::Concat.id() == 9);
+    assert!(Kind::GreaterEq.id() == 10);
+    assert!(Kind::LessEq.id() == 11);
+    assert!(Kind::NotEq.id() == 12);
+    assert!(Kind::NotDistinctFrom.id() == 13);
+    assert!(Kind::DistinctFrom.id() == 14);
+    assert!(Kind::Comma.id() == 15);
+    assert!(Kind::OpenParen.id() == 16);
+    assert!(Kind::CloseParen.id() == 17);
+    assert!(Kind::OpenBracket.id() == 18);
+    assert!(Kind::CloseBracket.id() == 19);
+    assert!(Kind::OpenBrace.id() == 20);
+    assert!(Kind::CloseBrace.id() == 21);
+    assert!(Kind::Dot.id() == 22);
+    assert!(Kind::Semicolon.id() == 23);
+    assert!(Kind::Colon.id() == 24);
+    assert!(Kind::Add.id() == 25);
+    assert!(Kind::Sub.id() == 26);
+    assert!(Kind::DoubleSplat.id() == 27);
+    assert!(Kind::Mul.id() == 28);
+    assert!(Kind::Div.id() == 29);
+    assert!(Kind::Modulo.id() == 30);
+    assert!(Kind::Pow.id() == 31);
+    assert!(Kind::Less.id() == 32);
+    assert!(Kind::Greater.id() == 33);
+    assert!(Kind::Eq.id() == 34);
+    assert!(Kind::Ampersand.id() == 35);
+    assert!(Kind::Pipe.id() == 36);
+    assert!(Kind::At.id() == 37);
+    assert!(Kind::Parameter.id() == 38);
+    assert!(Kind::ParameterAndType.id() == 39);
+    assert!(Kind::DecimalConst.id() == 40);
+    assert!(Kind::FloatConst.id() == 41);
+    assert!(Kind::IntConst.id() == 42);
+    assert!(Kind::BigIntConst.id() == 43);
+    assert!(Kind::BinStr.id() == 44);
+    assert!(Kind::Str.id() == 45);
+    assert!(Kind::StrInterpStart.id() == 46);
+    assert!(Kind::StrInterpCont.id() == 47);
+    assert!(Kind::StrInterpEnd.id() == 48);
+    assert!(Kind::BacktickName.id() == 49);
+    assert!(Kind::Substitution.id() == 50);
+    assert!(Kind::Ident.id() == 52);
+    assert!(Kind::EOI.id() == 53);
+    assert!(Kind::Epsilon.id() == 54);
+    assert!(Kind::StartBlock.id() == 55);
+    assert!(Kind::StartExtension.id() == 56);
+    assert!(Kind::StartFragment.id() == 57);
+    assert!(Kind::StartMigration.id() == 58);
+    assert!(Kind::StartSDLDocument.id() == 59);
+};
+
 impl Kind {
     pub fn text(&self) -> Option<&'static str> {
         use Kind::*;
@@ -1134,3 +1736,164 @@ impl Kind {
         })
     }
 }
+
+/// Short description and documentation page of a token, for hover and help
+/// texts. Keywords are described by [Keyword::docs].
+pub fn token_docs(kind: Kind) -> Option<keywords::Docs> {
+    use Kind::*;
+
+    let (description, page) = match kind {
+        Assign => (
+            "assigns a value to a pointer in a shape",
+            "reference/reference/edgeql/shapes",
+        ),
+        SubAssign => (
+            "removes elements from a multi link or property in `update`",
+            "reference/reference/edgeql/update",
+        ),
+        AddAssign => (
+            "adds elements to a multi link or property in `update`",
+            "reference/reference/edgeql/update",
+        ),
+        Arrow => (
+            "declares the return type of a function",
+            "reference/datamodel/functions",
+        ),
+        Coalesce => (
+            "coalescing: the left set, or the right one if the left is empty",
+            "reference/stdlib/set",
+        ),
+        Namespace => (
+            "separates a module name and a name in it",
+            "reference/datamodel/modules",
+        ),
+        BackwardLink => (
+            "follows a link backwards, from the target to the source",
+            "reference/reference/edgeql/paths",
+        ),
+        OptionalLink => (
+            "follows a link, producing empty set instead of an error",
+            "reference/reference/edgeql/paths",
+        ),
+        FloorDiv => ("floor division", "reference/stdlib/numbers"),
+        Concat => (
+            "concatenates strings, arrays or bytes",
+            "reference/stdlib/string",
+        ),
+        GreaterEq => ("greater than or equal", "reference/stdlib/generic"),
+        LessEq => ("less than or equal", "reference/stdlib/generic"),
+        NotEq => ("not equal", "reference/stdlib/generic"),
+        NotDistinctFrom => (
+            "equal, treating empty sets as equal to each other",
+            "reference/stdlib/generic",
+        ),
+        DistinctFrom => (
+            "not equal, treating empty sets as equal to each other",
+            "reference/stdlib/generic",
+        ),
+        Comma => (
+            "separates elements of a list",
+            "reference/reference/edgeql/lexical",
+        ),
+        OpenParen => (
+            "groups an expression, starts a tuple or arguments of a call",
+            "reference/stdlib/tuple",
+        ),
+        CloseParen => (
+            "closes a group, a tuple or arguments of a call",
+            "reference/stdlib/tuple",
+        ),
+        OpenBracket => (
+            "starts an array, an index, a slice or a type filter `[is Type]`",
+            "reference/stdlib/array",
+        ),
+        CloseBracket => (
+            "closes an array, an index, a slice or a type filter",
+            "reference/stdlib/array",
+        ),
+        OpenBrace => (
+            "starts a shape, a set or a block",
+            "reference/reference/edgeql/shapes",
+        ),
+        CloseBrace => (
+            "closes a shape, a set or a block",
+            "reference/reference/edgeql/shapes",
+        ),
+        Dot => (
+            "follows a link or a property",
+            "reference/reference/edgeql/paths",
+        ),
+        Semicolon => (
+            "terminates a statement",
+            "reference/reference/edgeql/lexical",
+        ),
+        Colon => (
+            "separates bounds of a slice, or a name and a type",
+            "reference/stdlib/array",
+        ),
+        Add => ("addition", "reference/stdlib/numbers"),
+        Sub => ("subtraction or negation", "reference/stdlib/numbers"),
+        DoubleSplat => (
+            "includes all pointers of a type and its links in a shape",
+            "reference/reference/edgeql/shapes",
+        ),
+        Mul => (
+            "multiplication, or all pointers of a type in a shape",
+            "reference/stdlib/numbers",
+        ),
+        Div => ("division", "reference/stdlib/numbers"),
+        Modulo => ("remainder of a division", "reference/stdlib/numbers"),
+        Pow => ("power", "reference/stdlib/numbers"),
+        Less => (
+            "less than, or starts a cast `<type>`",
+            "reference/stdlib/generic",
+        ),
+        Greater => (
+            "greater than, or ends a cast `<type>`",
+            "reference/stdlib/generic",
+        ),
+        Eq => ("equal", "reference/stdlib/generic"),
+        Ampersand => ("intersection of object types", "reference/stdlib/type"),
+        Pipe => ("union of object types", "reference/stdlib/type"),
+        At => ("refers to a link property", "reference/datamodel/linkprops"),
+        Parameter => (
+            "query parameter, given when the query is run",
+            "reference/edgeql/parameters",
+        ),
+        ParameterAndType => ("query parameter with a type", "reference/edgeql/parameters"),
+        DecimalConst => (
+            "arbitrary precision decimal constant",
+            "reference/stdlib/numbers",
+        ),
+        FloatConst => ("floating point constant", "reference/stdlib/numbers"),
+        IntConst => ("integer constant", "reference/stdlib/numbers"),
+        BigIntConst => (
+            "arbitrary precision integer constant",
+            "reference/stdlib/numbers",
+        ),
+        BinStr => ("bytes constant", "reference/stdlib/bytes"),
+        Str => ("string constant", "reference/stdlib/string"),
+        StrInterpStart => (
+            "start of a string with interpolated expressions",
+            "reference/reference/edgeql/lexical",
+        ),
+        StrInterpCont => (
+            "part of a string between interpolated expressions",
+            "reference/reference/edgeql/lexical",
+        ),
+        StrInterpEnd => (
+            "end of a string with interpolated expressions",
+            "reference/reference/edgeql/lexical",
+        ),
+        BacktickName => ("quoted identifier", "reference/reference/edgeql/lexical"),
+        Substitution => (
+            "substitution of a name in a template",
+            "reference/reference/edgeql/lexical",
+        ),
+        Ident => ("identifier", "reference/reference/edgeql/lexical"),
+        Keyword(keyword) => return keyword.docs().copied(),
+        EOI | Epsilon | StartBlock | StartExtension | StartFragment | StartMigration
+        | StartSDLDocument => return None,
+    };
+    Some(keywords::Docs::new(description, page))
+}
@@ -0,0 +1,348 @@
+//! Tree-sitter grammar for highlighting EdgeQL and its conformance check
+//!
+//! [grammar_js] generates a lexical tree-sitter grammar (tokens only, no
+//! statement structure) from the keyword tables and token kinds of the
+//! tokenizer, and [HIGHLIGHTS] maps its nodes to highlight captures. Editor
+//! plugins that ship the generated files stay in sync with the parser by
+//! regenerating them.
+//!
+//! [check_highlights] compares captures produced by a plugin (i.e. the output
+//! of `tree-sitter highlight`) with the captures of the canonical tokenizer,
+//! see [highlight].
+use std::fmt::Write;
+
+use crate::keywords::{
+    CURRENT_RESERVED_KEYWORDS, FUTURE_RESERVED_KEYWORDS, PARTIAL_RESERVED_KEYWORDS,
+    UNRESERVED_KEYWORDS,
+};
+use crate::position::Span;
+use crate::tokenizer::{Error, Kind, Tokenizer};
+
+/// Highlight queries (`highlights.scm`) for the grammar of [grammar_js]
+pub const HIGHLIGHTS: &str = "\
+(comment) @comment
+(keyword) @keyword
+(operator) @operator
+(delimiter) @punctuation.delimiter
+(bracket) @punctuation.bracket
+(string) @string
+(number) @number
+(parameter) @variable.parameter
+";
+
+/// A capture of a plugin that does not match the canonical tokenizer
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mismatch {
+    pub span: Span,
+    /// Capture of the canonical tokenizer, `None` for uncaptured tokens
+    pub expected: Option<&'static str>,
+    /// Capture of the plugin, `None` if there was none at the span
+    pub found: Option<String>,
+}
+
+/// Highlight capture of a token kind, as named in [HIGHLIGHTS]
+pub fn capture(kind: Kind) -> Option<&'static str> {
+    use Kind::*;
+
+    Some(match kind {
+        Keyword(_) => "keyword",
+        Assign | SubAssign | AddAssign | Arrow | Coalesce | FloorDiv | Concat | GreaterEq
+        | LessEq | NotEq | NotDistinctFrom | DistinctFrom | Add | Sub | DoubleSplat | Mul | Div
+        | Modulo | Pow | Less | Greater | Eq | Ampersand | Pipe => "operator",
+        Comma | Dot | Semicolon | Colon | Namespace | BackwardLink | OptionalLink | At => {
+            "punctuation.delimiter"
+        }
+        OpenParen | CloseParen | OpenBracket | CloseBracket | OpenBrace | CloseBrace => {
+            "punctuation.bracket"
+        }
+        Str | BinStr | StrInterpStart | StrInterpCont | StrInterpEnd => "string",
+        DecimalConst | FloatConst | IntConst | BigIntConst => "number",
+        Parameter | ParameterAndType => "variable.parameter",
+        Ident | BacktickName | Substitution | EOI | Epsilon | StartBlock | StartExtension
+        | StartFragment | StartMigration | StartSDLDocument => return None,
+    })
+}
+
+/// Captures of the tokens of `source`, fails if the source cannot be
+/// tokenized
+pub fn highlight(source: &str) -> Result<Vec<(Span, &'static str)>, Error> {
+    let mut captures = Vec::new();
+    for token in Tokenizer::new(source) {
+        let token = token?;
+        if let Some(capture) = capture(token.kind) {
+            captures.push((token.span, capture));
+        }
+    }
+    Ok(captures)
+}
+
+/// Compares `captures` of a plugin with the captures of [highlight]
+///
+/// Every token must have the same capture at exactly the same span. Captures
+/// between tokens (i.e. comments) are not checked.
+pub fn check_highlights(source: &str, captures: &[(Span, String)]) -> Result<Vec<Mismatch>, Error> {
+    let mut mismatches = Vec::new();
+    for token in Tokenizer::new(source) {
+        let token = token?;
+        let expected = capture(token.kind);
+        let found = captures
+            .iter()
+            .find(|(span, _)| *span == token.span)
+            .map(|(_, c)| c.clone());
+        if expected != found.as_deref() {
+            mismatches.push(Mismatch {
+                span: token.span,
+                expected,
+                found,
+            });
+        }
+    }
+    Ok(mismatches)
+}
+
+/// Generates `grammar.js` of the tree-sitter grammar
+pub fn grammar_js() -> String {
+    let mut keywords: Vec<&str> = [
+        &UNRESERVED_KEYWORDS,
+        &PARTIAL_RESERVED_KEYWORDS,
+        &FUTURE_RESERVED_KEYWORDS,
+        &CURRENT_RESERVED_KEYWORDS,
+    ]
+    .into_iter()
+    .flat_map(|set| set.iter().copied())
+    .collect();
+    keywords.sort_unstable();
+
+    let mut buf = String::new();
+    buf.push_str(
+        "// Generated by edgeql_parser::tree_sitter::grammar_js, do not edit\n\
+         \n\
+         // keywords are case insensitive\n\
+         const kw = (word) =>\n  \
+           alias(new RegExp(word.replace(/[a-z]/g, (c) => `[${c}${c.toUpperCase()}]`)), word);\n\
+         \n\
+         module.exports = grammar({\n  \
+           name: 'edgeql',\n  \
+           extras: ($) => [/\\s/, $.comment],\n  \
+           rules: {\n    \
+             source_file: ($) => repeat($._token),\n    \
+             _token: ($) =>\n      \
+               choice($.keyword, $.operator, $.delimiter, $.bracket, $.string, $.number,\n        \
+               $.parameter, $.identifier),\n",
+    );
+    // keywords must precede identifiers, so they win on same length
+    push_rule(
+        &mut buf,
+        "keyword",
+        keywords.iter().map(|k| format!("kw('{k}')")),
+    );
+    push_rule(&mut buf, "operator", punctuation("operator"));
+    push_rule(&mut buf, "delimiter", punctuation("punctuation.delimiter"));
+    push_rule(&mut buf, "bracket", punctuation("punctuation.bracket"));
+    push_rule(
+        &mut buf,
+        "string",
+        [
+            r#"/[rb]?'([^'\\]|\\.)*'/"#,
+            r#"/[rb]?"([^"\\]|\\.)*"/"#,
+            r#"/'''([^']|'[^']|''[^'])*'''/"#,
+            r#"/"""([^"]|"[^"]|""[^"])*"""/"#,
+            r#"/\$\$([^$]|\$[^$])*\$\$/"#,
+        ]
+        .map(String::from),
+    );
+    push_rule(
+        &mut buf,
+        "number",
+        [r"/\d[\d_]*(\.\d[\d_]*)?([eE][+-]?\d+)?n?/".to_string()],
+    );
+    push_rule(
+        &mut buf,
+        "parameter",
+        [r"/\$[A-Za-z_0-9]+/", r"/\$`[^`]+`/"].map(String::from),
+    );
+    push_rule(
+        &mut buf,
+        "identifier",
+        [r"/[A-Za-z_][A-Za-z_0-9]*/", r"/`([^`]|``)+`/"].map(String::from),
+    );
+    buf.push_str("    comment: ($) => /#.*/,\n  },\n});\n");
+    buf
+}
+
+/// Texts of token kinds with the given capture, longest first
+fn punctuation(capture_name: &str) -> Vec<String> {
+    use Kind::*;
+
+    let kinds = [
+        Assign,
+        SubAssign,
+        AddAssign,
+        Arrow,
+        Coalesce,
+        Namespace,
+        BackwardLink,
+        OptionalLink,
+        FloorDiv,
+        Concat,
+        GreaterEq,
+        LessEq,
+        NotEq,
+        NotDistinctFrom,
+        DistinctFrom,
+        Comma,
+        OpenParen,
+        CloseParen,
+        OpenBracket,
+        CloseBracket,
+        OpenBrace,
+        CloseBrace,
+        Dot,
+        Semicolon,
+        Colon,
+        Add,
+        Sub,
+        DoubleSplat,
+        Mul,
+        Div,
+        Modulo,
+        Pow,
+        Less,
+        Greater,
+        Eq,
+        Ampersand,
+        Pipe,
+        At,
+    ];
+    let mut texts: Vec<&str> = kinds
+        .into_iter()
+        .filter(|k| capture(*k) == Some(capture_name))
+        .filter_map(|k| k.text())
+        .collect();
+    texts.sort_by_key(|t| std::cmp::Reverse(t.len()));
+    texts.into_iter().map(|t| format!("'{t}'")).collect()
+}
+
+fn push_rule(buf: &mut String, name: &str, alternatives: impl IntoIterator<Item = String>) {
+    write!(buf, "    {name}: ($) =>\n      choice(").unwrap();
+    for (i, alternative) in alternatives.into_iter().enumerate() {
+        if i > 0 {
+            buf.push_str(", ");
+        }
+        buf.push_str(&alternative);
+    }
+    buf.push_str("),\n");
+}
+
+#[cfg(test)]
+mod test {
+    use super::{capture, check_highlights, grammar_js, highlight, Mismatch, HIGHLIGHTS};
+    use crate::keywords::CURRENT_RESERVED_KEYWORDS;
+    use crate::position::Span;
+    use crate::tokenizer::{Kind, Tokenizer};
+
+    #[test]
+    fn grammar() {
+        let grammar = grammar_js();
+        for keyword in CURRENT_RESERVED_KEYWORDS.iter() {
+            assert!(grammar.contains(&format!("kw('{keyword}')")));
+        }
+        assert!(grammar.contains("'?!=', "));
+        assert!(grammar.contains("'.?>', "));
+
+        // every node of the grammar has a capture
+        for node in [
+            "operator",
+            "delimiter",
+            "bracket",
+            "string",
+            "number",
+            "parameter",
+        ] {
+            assert!(grammar.contains(&format!("    {node}: ($) =>")));
+            assert!(HIGHLIGHTS.contains(&format!("({node}) @")));
+        }
+    }
+
+    /// Alternatives of the `name` rule of the generated grammar
+    fn rule<'a>(grammar: &'a str, name: &str) -> &'a str {
+        let header = format!("    {name}: ($) =>\n      choice(");
+        let start = grammar.find(&header).unwrap() + header.len();
+        let len = grammar[start..].find("),\n").unwrap();
+        &grammar[start..start + len]
+    }
+
+    #[test]
+    fn conformance() {
+        // a representative query, with every kind of punctuation
+        let source = "\
+            WITH module default, x := (SELECT User.<friends[IS Person] LIMIT 1) \
+            SELECT x { name, @since, friends: { name }, tags := .tags ?? [] } \
+            FILTER .age >= <int64>$age AND .age <= 99 AND .name != 'x' \
+            AND .a ?= 1 AND .b ?!= 2 AND .c > 3 AND .d < 4 AND .e = -5 \
+            AND (1 + 2 * 3 / 4 // 5 % 6 ^ 7) > 0 AND x.?>y ++ 'z' != '' \
+            ORDER BY .name; UPDATE X SET { a += 1, b -= 2 }; \
+            SELECT { x := 1 } ?? {**x} ?? (y -> z) ?? (a | b) ?? (a & b); \
+            SELECT std::len('a');";
+        let grammar = grammar_js();
+        for token in Tokenizer::new(source) {
+            let token = token.unwrap();
+            let Some(expected) = capture(token.kind) else {
+                continue;
+            };
+            let (node, pattern) = match token.kind {
+                Kind::Keyword(keyword) => ("keyword", format!("kw('{}')", keyword.0)),
+                _ if expected == "operator" => ("operator", format!("'{}'", token.text)),
+                _ if expected.starts_with("punctuation.") => (
+                    &expected["punctuation.".len()..],
+                    format!("'{}'", token.text),
+                ),
+                _ => continue,
+            };
+            let alternatives: Vec<&str> = rule(&grammar, node).split(", ").collect();
+            assert!(
+                alternatives.contains(&pattern.as_str()),
+                "{:?} is not matched by the {node} rule",
+                token.text
+            );
+            assert!(HIGHLIGHTS.contains(&format!("({node}) @{expected}\n")));
+        }
+    }
+
+    #[test]
+    fn check() {
+        let source = "SELECT User { name } filter .age >= <int64>$age";
+        let captures = highlight(source).unwrap();
+        assert_eq!(captures[0], (Span { start: 0, end: 6 }, "keyword"));
+        assert_eq!(
+            captures[1],
+            (Span { start: 12, end: 13 }, "punctuation.bracket")
+        );
+        assert_eq!(captures.last().unwrap().1, "variable.parameter");
+
+        let mut plugin: Vec<_> = captures
+            .iter()
+            .map(|(span, c)| (*span, c.to_string()))
+            .collect();
+        assert!(check_highlights(source, &plugin).unwrap().is_empty());
+
+        // `filter` highlighted as a name, `User` as a keyword
+        plugin.retain(|(span, _)| span.start != 21);
+        plugin.push((Span { start: 7, end: 11 }, "keyword".into()));
+        assert_eq!(
+            check_highlights(source, &plugin).unwrap(),
+            [
+                Mismatch {
+                    span: Span { start: 7, end: 11 },
+                    expected: None,
+                    found: Some("keyword".into()),
+                },
+                Mismatch {
+                    span: Span { start: 21, end: 27 },
+                    expected: Some("keyword"),
+                    found: None,
+                },
+            ]
+        );
+    }
+}
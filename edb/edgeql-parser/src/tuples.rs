@@ -0,0 +1,197 @@
+//! Tuple literals of queries
+//!
+//! [tuple_literals] finds tuples like `(1, 'a')` and named tuples like
+//! `(id := 1, name := 'a')` in a query, with the names and the order of
+//! their elements, so that bindings can generate matching tuples or records
+//! of the host language. Element names of tuple types (`tuple<id: int64>`)
+//! are in [TypeRef::tuple_names](crate::schema::TypeRef::tuple_names).
+//!
+//! Parentheses are a tuple if they are empty, contain a top-level comma, or
+//! a single named element. Arguments of function calls and of `by` of
+//! `group`, and parenthesized statements are not tuples.
+use crate::keywords::Keyword;
+use crate::position::Span;
+use crate::schema::{is_name_like, token_name};
+use crate::tokenizer::{Error, Kind, Token, Tokenizer};
+
+/// Keywords of statements that may have top-level commas in parentheses
+const STATEMENTS: &[&str] = &[
+    "select", "insert", "update", "delete", "group", "for", "with",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TupleElement {
+    /// Name of the element of a named tuple
+    pub name: Option<String>,
+    pub name_span: Option<Span>,
+    /// The value of the element, without the name
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TupleLiteral {
+    /// Elements in the order they are written
+    pub elements: Vec<TupleElement>,
+    /// From the opening to the closing parenthesis
+    pub span: Span,
+}
+
+impl TupleLiteral {
+    /// Returns true if the tuple has elements and all of them are named
+    pub fn is_named(&self) -> bool {
+        !self.elements.is_empty() && self.elements.iter().all(|e| e.name.is_some())
+    }
+
+    /// Names of the elements in order, `None` if the tuple is not named
+    pub fn names(&self) -> Option<Vec<&str>> {
+        if self.elements.is_empty() {
+            return None;
+        }
+        self.elements.iter().map(|e| e.name.as_deref()).collect()
+    }
+}
+
+struct Open {
+    /// Index of the tuple in the result, `None` for other brackets
+    slot: Option<usize>,
+    /// Index of the opening token
+    token: usize,
+    commas: Vec<usize>,
+}
+
+/// Returns tuple literals of the query ordered by the opening parenthesis,
+/// fails if the query cannot be tokenized
+pub fn tuple_literals(text: &str) -> Result<Vec<TupleLiteral>, Error> {
+    let tokens = Tokenizer::new(text)
+        .validated_values()
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut result = Vec::new();
+    let mut open: Vec<Open> = Vec::new();
+    for (idx, tok) in tokens.iter().enumerate() {
+        match tok.kind {
+            Kind::OpenParen | Kind::OpenBracket | Kind::OpenBrace => {
+                let slot = if tok.kind == Kind::OpenParen && !is_call(&tokens, idx) {
+                    result.push(None);
+                    Some(result.len() - 1)
+                } else {
+                    None
+                };
+                open.push(Open {
+                    slot,
+                    token: idx,
+                    commas: Vec::new(),
+                });
+            }
+            Kind::Comma => {
+                if let Some(parent) = open.last_mut() {
+                    parent.commas.push(idx);
+                }
+            }
+            Kind::CloseParen | Kind::CloseBracket | Kind::CloseBrace => {
+                let Some(Open {
+                    slot: Some(slot),
+                    token,
+                    commas,
+                }) = open.pop()
+                else {
+                    continue;
+                };
+                result[slot] = tuple(&tokens, token, idx, &commas);
+            }
+            _ => {}
+        }
+    }
+    Ok(result.into_iter().flatten().collect())
+}
+
+/// Whether the parenthesis at `idx` opens arguments of a call or the
+/// grouping sets after `by`
+fn is_call(tokens: &[Token], idx: usize) -> bool {
+    let Some(prev) = idx.checked_sub(1).map(|i| &tokens[i]) else {
+        return false;
+    };
+    match prev.kind {
+        Kind::Keyword(Keyword("by")) => true,
+        Kind::Keyword(kw) => !kw.is_reserved(),
+        Kind::Ident | Kind::BacktickName => true,
+        _ => false,
+    }
+}
+
+fn tuple(tokens: &[Token], open: usize, close: usize, commas: &[usize]) -> Option<TupleLiteral> {
+    let span = tokens[open].span.extend(&tokens[close].span);
+    if open + 1 == close {
+        return Some(TupleLiteral {
+            elements: Vec::new(),
+            span,
+        });
+    }
+    if let Kind::Keyword(Keyword(kw)) = tokens[open + 1].kind {
+        if STATEMENTS.contains(&kw) {
+            return None;
+        }
+    }
+    let mut bounds = vec![open];
+    bounds.extend(commas);
+    if commas.last() != Some(&(close - 1)) {
+        bounds.push(close);
+    }
+    let elements = bounds
+        .windows(2)
+        .filter(|w| w[0] + 1 < w[1])
+        .map(|w| element(tokens, w[0] + 1, w[1] - 1))
+        .collect::<Vec<_>>();
+    if commas.is_empty() && elements[0].name.is_none() {
+        return None;
+    }
+    Some(TupleLiteral { elements, span })
+}
+
+/// Element from `first` to `last` token inclusive
+fn element(tokens: &[Token], first: usize, last: usize) -> TupleElement {
+    let named = first < last
+        && is_name_like(Some(tokens[first].kind))
+        && tokens[first + 1].kind == Kind::Assign
+        && first + 1 < last;
+    if named {
+        TupleElement {
+            name: Some(token_name(&tokens[first]).to_string()),
+            name_span: Some(tokens[first].span),
+            span: tokens[first + 2].span.extend(&tokens[last].span),
+        }
+    } else {
+        TupleElement {
+            name: None,
+            name_span: None,
+            span: tokens[first].span.extend(&tokens[last].span),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::tuple_literals;
+
+    #[test]
+    fn literals() {
+        let text = "select (a := 1, `b` := (2, 'x'), c := count(User)) \
+                    filter .x in (1,) and f(1, 2) = () and (.y) = (select 1)";
+        let tuples = tuple_literals(text).unwrap();
+        assert_eq!(tuples.len(), 4);
+        assert!(tuples[0].is_named());
+        assert_eq!(tuples[0].names(), Some(vec!["a", "b", "c"]));
+        let span = tuples[0].elements[2].span;
+        assert_eq!(&text[span.start as usize..span.end as usize], "count(User)");
+        assert_eq!(tuples[1].names(), None);
+        assert_eq!(tuples[1].elements.len(), 2);
+        assert_eq!(tuples[2].elements.len(), 1);
+        assert!(tuples[3].elements.is_empty());
+        assert!(!tuples[3].is_named());
+
+        let tuples = tuple_literals("select (x := 1)").unwrap();
+        assert_eq!(tuples[0].names(), Some(vec!["x"]));
+        assert!(tuple_literals("group User using a := .x by (a, .y)")
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,177 @@
+//! Format checks of UUID literals
+//!
+//! [check_uuids] reports string literals cast to `uuid` that the server
+//! would reject, and suggests the canonical form (lowercase, with dashes)
+//! for the ones that are accepted but written differently, like
+//! `<uuid>'A0EEBC99-9C0B-4EF8-BB6D-6BB9BD380A11'`.
+//!
+//! The accepted forms are the ones of PostgreSQL: 32 hex digits of either
+//! case, optionally in braces, with a dash after any group of four digits.
+use crate::casts::{is_cast_start, read_cast, CastType};
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::resolver::Resolver;
+use crate::tokenizer::{Kind, Token, Value};
+
+/// Reason of a UUID literal being invalid
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum UuidError {
+    #[error("invalid character {ch:?} at position {offset}")]
+    InvalidCharacter { ch: char, offset: usize },
+    #[error("expected 32 hex digits, found {0}")]
+    WrongLength(usize),
+    #[error("a dash is only allowed after a group of four hex digits")]
+    MisplacedDash { offset: usize },
+    #[error("unbalanced braces")]
+    UnbalancedBraces,
+}
+
+/// Parses the text of a UUID, returns its canonical form, like
+/// `a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11`
+pub fn parse_uuid(text: &str) -> Result<String, UuidError> {
+    let inner = match (text.strip_prefix('{'), text.strip_suffix('}')) {
+        (Some(_), Some(_)) if text.len() >= 2 => &text[1..text.len() - 1],
+        (None, None) => text,
+        _ => return Err(UuidError::UnbalancedBraces),
+    };
+    let offset = text.len() - inner.len() - usize::from(text.ends_with('}'));
+    let mut digits = String::with_capacity(32);
+    let mut prev_dash = true;
+    for (idx, ch) in inner.char_indices() {
+        match ch {
+            '-' if prev_dash || digits.len() % 4 != 0 || digits.len() >= 32 => {
+                return Err(UuidError::MisplacedDash {
+                    offset: offset + idx,
+                });
+            }
+            '-' => prev_dash = true,
+            _ if ch.is_ascii_hexdigit() => {
+                digits.push(ch.to_ascii_lowercase());
+                prev_dash = false;
+            }
+            _ => {
+                return Err(UuidError::InvalidCharacter {
+                    ch,
+                    offset: offset + idx,
+                });
+            }
+        }
+    }
+    if digits.len() != 32 {
+        return Err(UuidError::WrongLength(digits.len()));
+    }
+    for idx in [20, 16, 12, 8] {
+        digits.insert(idx, '-');
+    }
+    Ok(digits)
+}
+
+/// Reports string literals cast to `uuid` that are invalid, or valid but
+/// not in the canonical form
+pub fn check_uuids(tokens: &[Token], resolver: &dyn Resolver) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    for pos in 0..tokens.len() {
+        if !is_cast_start(tokens, pos) {
+            continue;
+        }
+        let Some((CastType::Scalar(name), operand)) = read_cast(tokens, pos, resolver) else {
+            continue;
+        };
+        if name != "std::uuid" {
+            continue;
+        }
+        let Some(
+            tok @ Token {
+                kind: Kind::Str,
+                value: Some(Value::String(text)),
+                ..
+            },
+        ) = tokens.get(operand)
+        else {
+            continue;
+        };
+        match parse_uuid(text) {
+            Ok(canonical) if canonical == *text => {}
+            Ok(canonical) => {
+                let warning =
+                    Diagnostic::with_code(Severity::Warning, "non-canonical-uuid", &[], tok.span)
+                        .with_suggestion(tok.span, format!("'{canonical}'"));
+                diagnostics.push(warning);
+            }
+            Err(err) => {
+                diagnostics.push(Diagnostic::with_code(
+                    Severity::Error,
+                    "invalid-uuid",
+                    &[("error", err.to_string())],
+                    tok.span,
+                ));
+            }
+        }
+    }
+    diagnostics
+}
+
+#[cfg(test)]
+mod test {
+    use super::{check_uuids, parse_uuid, UuidError};
+    use crate::resolver::SchemaResolver;
+    use crate::schema::parse;
+    use crate::tokenizer::tokens;
+
+    const CANONICAL: &str = "a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11";
+
+    #[test]
+    fn formats() {
+        for text in [
+            CANONICAL,
+            "A0EEBC99-9C0B-4EF8-BB6D-6BB9BD380A11",
+            "{a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11}",
+            "a0eebc999c0b4ef8bb6d6bb9bd380a11",
+            "a0ee-bc99-9c0b-4ef8-bb6d-6bb9-bd38-0a11",
+        ] {
+            assert_eq!(parse_uuid(text).as_deref(), Ok(CANONICAL), "{text}");
+        }
+        assert_eq!(
+            parse_uuid("a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a1"),
+            Err(UuidError::WrongLength(31))
+        );
+        assert_eq!(
+            parse_uuid("a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a1g"),
+            Err(UuidError::InvalidCharacter {
+                ch: 'g',
+                offset: 35
+            })
+        );
+        assert_eq!(
+            parse_uuid("a0eebc9-99c0b-4ef8-bb6d-6bb9bd380a11"),
+            Err(UuidError::MisplacedDash { offset: 7 })
+        );
+        assert_eq!(
+            parse_uuid("{a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11"),
+            Err(UuidError::UnbalancedBraces)
+        );
+    }
+
+    #[test]
+    fn casts() {
+        let schema = parse("scalar type Key extending uuid;").unwrap();
+        let resolver = SchemaResolver::new(&schema);
+        let query = "select (<uuid>'a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11', \
+                     <Key>'A0EEBC999C0B4EF8BB6D6BB9BD380A11', <optional uuid>'a0eebc99', \
+                     <str>'x', <uuid>$id)";
+        let tokens = tokens(query);
+        let diagnostics = check_uuids(&tokens, &resolver);
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].code.as_deref(), Some("non-canonical-uuid"));
+        assert_eq!(
+            diagnostics[0].suggestions[0].apply(query),
+            query.replace(
+                "'A0EEBC999C0B4EF8BB6D6BB9BD380A11'",
+                &format!("'{CANONICAL}'")
+            )
+        );
+        assert_eq!(
+            diagnostics[1].message,
+            "invalid UUID: expected 32 hex digits, found 8"
+        );
+    }
+}
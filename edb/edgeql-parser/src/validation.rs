@@ -29,7 +29,10 @@ impl<'a> Iterator for Validator<'a> {
 
         token.value = match parse_value(&token) {
             Ok(x) => x,
-            Err(e) => return Some(Err(Error::new(e).with_span(token.span))),
+            Err(e) => {
+                let span = escape_span(&token).unwrap_or(token.span);
+                return Some(Err(Error::new(e).with_span(span)));
+            }
         };
 
         if let Some(keyword) = self.combine_multi_word_keywords(&token) {
@@ -203,7 +206,10 @@ pub fn parse_value(token: &Token) -> Result<Option<Value>, String> {
                 .map(|x| Some(Value::BigInt(x.to_str_radix(16))));
         }
         BinStr => {
-            return unquote_bytes(text).map(Value::Bytes).map(Some);
+            return unquote_bytes(text)
+                .map(Value::Bytes)
+                .map(Some)
+                .map_err(|e| e.to_string());
         }
 
         Str | StrInterpStart | StrInterpEnd | StrInterpCont => {
@@ -217,6 +223,24 @@ pub fn parse_value(token: &Token) -> Result<Option<Value>, String> {
     Ok(Some(Value::String(string_value)))
 }
 
+/// Span of the invalid escape sequence that makes a string or bytes literal
+/// invalid
+fn escape_span(token: &Token) -> Option<Span> {
+    use Kind::*;
+
+    let range = match token.kind {
+        Str | StrInterpStart | StrInterpEnd | StrInterpCont => {
+            unquote_string(&token.text).err()?.escape_range()?
+        }
+        BinStr => unquote_bytes(&token.text).err()?.escape_range()?,
+        _ => return None,
+    };
+    Some(Span {
+        start: token.span.start + range.start as u64,
+        end: token.span.start + range.end as u64,
+    })
+}
+
 fn remap_kind(kind: Kind) -> Kind {
     match kind {
         Kind::BacktickName => Kind::Ident,
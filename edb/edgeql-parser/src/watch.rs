@@ -0,0 +1,224 @@
+//! Validation of a schema directory as its files change
+//!
+//! [SchemaDir] keeps the last known contents of every `.esdl` file of the
+//! directory and reparses only files that have actually changed. [watch]
+//! drives it with file system notifications, yielding diagnostics of every
+//! changed file.
+use std::collections::{BTreeMap, VecDeque};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver};
+
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::diagnostics::Diagnostic;
+use crate::schema::{constraints, cycles, enums, modifiers, parse};
+
+pub const SCHEMA_EXTENSION: &str = "esdl";
+
+/// Diagnostics of a single schema file
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileDiagnostics {
+    pub path: PathBuf,
+    /// Contents of the file the diagnostics refer to, `None` if the file
+    /// was removed
+    pub source: Option<String>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Last checked state of the schema files of a directory
+#[derive(Debug)]
+pub struct SchemaDir {
+    root: PathBuf,
+    files: BTreeMap<PathBuf, String>,
+}
+
+/// Diagnostics of a single schema file: syntax errors, cycles, invalid
+/// constraints, enum members and modifiers
+pub fn check_file(source: &str) -> Vec<Diagnostic> {
+    match parse(source) {
+        Ok(doc) => cycles::check(&doc)
+            .into_iter()
+            .chain(constraints::check(&doc))
+            .chain(enums::check(&doc))
+            .chain(modifiers::check(&doc))
+            .collect(),
+        Err(err) => vec![err.into()],
+    }
+}
+
+fn is_schema_file(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == SCHEMA_EXTENSION)
+}
+
+impl SchemaDir {
+    pub fn new(root: impl Into<PathBuf>) -> SchemaDir {
+        SchemaDir {
+            root: root.into(),
+            files: BTreeMap::new(),
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Checks all schema files of the directory (recursively), ordered by
+    /// path
+    pub fn scan(&mut self) -> io::Result<Vec<FileDiagnostics>> {
+        let mut paths = Vec::new();
+        let mut dirs = vec![self.root.clone()];
+        while let Some(dir) = dirs.pop() {
+            for entry in fs::read_dir(&dir)? {
+                let path = entry?.path();
+                if path.is_dir() {
+                    dirs.push(path);
+                } else if is_schema_file(&path) {
+                    paths.push(path);
+                }
+            }
+        }
+        paths.sort();
+        Ok(paths.iter().filter_map(|path| self.update(path)).collect())
+    }
+
+    /// Rechecks a file after it was changed, created or removed. Returns
+    /// `None` for files that are not schema files and for files whose
+    /// contents did not change since the last check.
+    pub fn update(&mut self, path: &Path) -> Option<FileDiagnostics> {
+        if !is_schema_file(path) {
+            return None;
+        }
+        let Ok(source) = fs::read_to_string(path) else {
+            // removed, or not readable anymore
+            self.files.remove(path)?;
+            return Some(FileDiagnostics {
+                path: path.to_path_buf(),
+                source: None,
+                diagnostics: Vec::new(),
+            });
+        };
+        if self.files.get(path) == Some(&source) {
+            return None;
+        }
+        let diagnostics = check_file(&source);
+        self.files.insert(path.to_path_buf(), source.clone());
+        Some(FileDiagnostics {
+            path: path.to_path_buf(),
+            source: Some(source),
+            diagnostics,
+        })
+    }
+
+    /// Paths of all schema files known to be in the directory
+    pub fn files(&self) -> impl Iterator<Item = &Path> {
+        self.files.keys().map(|p| p.as_path())
+    }
+}
+
+/// Stream of diagnostics of a watched schema directory, see [watch]
+pub struct Watch {
+    dir: SchemaDir,
+    events: Receiver<notify::Result<Event>>,
+    pending: VecDeque<FileDiagnostics>,
+    // stops watching when dropped
+    _watcher: RecommendedWatcher,
+}
+
+/// Starts watching the schema directory
+///
+/// The returned iterator first yields diagnostics of every schema file of
+/// the directory, then blocks waiting for changes and yields diagnostics of
+/// changed files (with `source: None` for removed ones). The iterator ends
+/// when the watcher stops.
+pub fn watch(root: impl Into<PathBuf>) -> notify::Result<Watch> {
+    let mut dir = SchemaDir::new(root);
+    let (tx, events) = channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    watcher.watch(dir.root(), RecursiveMode::Recursive)?;
+    // scan after the watcher is started, so that no change is missed
+    let pending = dir.scan().map_err(|e| notify::Error {
+        kind: notify::ErrorKind::Io(e),
+        paths: vec![dir.root().to_path_buf()],
+    })?;
+    Ok(Watch {
+        dir,
+        events,
+        pending: pending.into(),
+        _watcher: watcher,
+    })
+}
+
+impl Iterator for Watch {
+    type Item = notify::Result<FileDiagnostics>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(diagnostics) = self.pending.pop_front() {
+                return Some(Ok(diagnostics));
+            }
+            let event = match self.events.recv().ok()? {
+                Ok(event) => event,
+                Err(e) => return Some(Err(e)),
+            };
+            if matches!(event.kind, EventKind::Access(_)) {
+                continue;
+            }
+            for path in &event.paths {
+                self.pending.extend(self.dir.update(path));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+    use std::path::PathBuf;
+
+    use super::SchemaDir;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("edgeql-parser-watch-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("nested")).unwrap();
+        dir
+    }
+
+    #[test]
+    fn incremental() {
+        let root = temp_dir("incremental");
+        let good = root.join("default.esdl");
+        let bad = root.join("nested").join("cycle.esdl");
+        fs::write(&good, "type User { required name: str; }").unwrap();
+        fs::write(&bad, "type A extending B; type B extending A;").unwrap();
+        fs::write(root.join("notes.txt"), "not a schema {").unwrap();
+
+        let mut dir = SchemaDir::new(&root);
+        let result = dir.scan().unwrap();
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].path, good);
+        assert!(result[0].diagnostics.is_empty());
+        assert_eq!(result[1].path, bad);
+        assert_eq!(result[1].diagnostics.len(), 1);
+
+        // unchanged files are not reparsed
+        assert_eq!(dir.update(&good), None);
+        assert_eq!(dir.update(&root.join("notes.txt")), None);
+
+        fs::write(&good, "type User {").unwrap();
+        let changed = dir.update(&good).unwrap();
+        assert_eq!(changed.diagnostics.len(), 1);
+
+        fs::remove_file(&bad).unwrap();
+        let removed = dir.update(&bad).unwrap();
+        assert_eq!(removed.source, None);
+        assert!(removed.diagnostics.is_empty());
+        assert_eq!(dir.update(&bad), None);
+        assert_eq!(dir.files().collect::<Vec<_>>(), vec![good.as_path()]);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -1,4 +1,5 @@
-use edgeql_parser::preparser::{full_statement, is_empty};
+use edgeql_parser::preparser::{full_statement, is_complete, is_empty, next_statement};
+use edgeql_parser::preparser::{Command, CompletionStatus, Splitter, Statement};
 
 fn test_statement(data: &[u8], len: usize) {
     for i in 0..len - 1 {
@@ -161,3 +162,148 @@ fn empty() {
     assert!(!is_empty("    '#c"));
     assert!(!is_empty("ab\n#cd"));
 }
+
+#[test]
+fn backslash_commands() {
+    let text = "  # list\n\\d+ default::User 'a b'\nselect 1;\\l";
+    let (stmt, pos) = next_statement(text, None).unwrap();
+    assert_eq!(
+        stmt,
+        Statement::Command(Command {
+            name: "d+",
+            args: vec!["default::User", "a b"],
+        })
+    );
+    let text = &text[pos..];
+    let (stmt, pos) = next_statement(text, None).unwrap();
+    assert_eq!(stmt, Statement::Query("select 1;"));
+    let (stmt, _) = next_statement(&text[pos..], None).unwrap();
+    assert_eq!(
+        stmt,
+        Statement::Command(Command {
+            name: "l",
+            args: vec![],
+        })
+    );
+}
+
+#[test]
+fn backslash_in_query() {
+    let (stmt, _) = next_statement("select '\\d';", None).unwrap();
+    assert_eq!(stmt, Statement::Query("select '\\d';"));
+    let (stmt, _) = next_statement("\\set limit   100\n", None).unwrap();
+    let Statement::Command(cmd) = stmt else {
+        panic!("not a command");
+    };
+    assert_eq!((cmd.name, cmd.args), ("set", vec!["limit", "100"]));
+}
+
+#[test]
+fn completion() {
+    use CompletionStatus::*;
+
+    assert_eq!(is_complete(""), Empty);
+    assert_eq!(is_complete("  # comment\n;"), Empty);
+    assert_eq!(is_complete("select 1;"), Complete);
+    assert_eq!(is_complete("select 1; # done"), Complete);
+    assert_eq!(is_complete("select 1; select 2;\n"), Complete);
+    assert_eq!(is_complete("\\d User"), Complete);
+    assert_eq!(is_complete("select 1"), NeedsContinuation);
+    assert_eq!(is_complete("select 1; select"), NeedsContinuation);
+    assert_eq!(is_complete("select 'a;"), NeedsContinuation);
+    assert_eq!(is_complete("select User { name;"), NeedsContinuation);
+    assert_eq!(is_complete("select User { x := { 1 } };"), Complete);
+    assert_eq!(
+        is_complete("create function f() -> str using $$ ;"),
+        NeedsContinuation
+    );
+    assert_eq!(is_complete("select $a$ ; $a$;"), Complete);
+    assert_eq!(is_complete("select $a$ $$; $a$"), NeedsContinuation);
+}
+
+/// Reader returning at most a few bytes per call
+struct Trickle<'a> {
+    data: &'a [u8],
+    calls: usize,
+}
+
+impl std::io::Read for Trickle<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.calls += 1;
+        let len = buf.len().min(self.data.len()).min(self.calls % 7 + 1);
+        buf[..len].copy_from_slice(&self.data[..len]);
+        self.data = &self.data[len..];
+        Ok(len)
+    }
+}
+
+#[test]
+fn splitter() {
+    let mut script = String::new();
+    let mut expected = Vec::new();
+    for i in 0..20_000 {
+        script.push_str(match i % 3 {
+            0 => "\n",
+            1 => " # comment; with semicolon\n",
+            _ => ";\n",
+        });
+        let statement = match i % 5 {
+            0 => format!("insert User {{ name := 'user{i};' }};"),
+            1 => format!("create type T{i} {{ create property p -> str; }};"),
+            2 => format!("select $$ {i}; $$;"),
+            3 => format!("CONFIGURE INSTANCE SET x := {i};"),
+            _ => format!("with a := {{ {i} }} select (a, \"{{\");"),
+        };
+        let start = script.len() as u64;
+        script.push_str(&statement);
+        expected.push((start..script.len() as u64, statement));
+    }
+    script.push_str(" select 'no semicolon'\n");
+    expected.push((
+        script.len() as u64 - 22..script.len() as u64,
+        "select 'no semicolon'\n".into(),
+    ));
+
+    let statements = Splitter::new(script.as_bytes())
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(statements.len(), expected.len());
+    for (statement, (range, text)) in statements.iter().zip(&expected) {
+        assert_eq!(&statement.range, range);
+        assert_eq!(&statement.text, text);
+        assert_eq!(&script[range.start as usize..range.end as usize], text);
+    }
+    let kinds: Vec<_> = statements[..5].iter().map(|s| s.kind.unwrap().0).collect();
+    assert_eq!(kinds, ["insert", "create", "select", "configure", "with"]);
+
+    let trickle = Trickle {
+        data: script.as_bytes(),
+        calls: 0,
+    };
+    let trickled = Splitter::new(trickle)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(trickled, statements);
+}
+
+#[test]
+fn splitter_long_statement() {
+    let data = "x".repeat(8 << 20);
+    let script = format!("insert Blob {{ data := $$ {data}; $$ }}; select 1;");
+    let statements = Splitter::new(script.as_bytes())
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(statements.len(), 2);
+    assert_eq!(statements[0].text.len(), data.len() + 32);
+    assert_eq!(statements[1].text, "select 1;");
+
+    let err = Splitter::new(script.as_bytes())
+        .with_max_statement_len(1 << 20)
+        .find_map(Result::err)
+        .unwrap();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert_eq!(
+        err.to_string(),
+        "statement at byte 0 is longer than 1048576 bytes"
+    );
+}
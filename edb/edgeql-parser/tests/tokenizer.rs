@@ -870,6 +870,96 @@ fn strings() {
     );
 }
 
+#[test]
+fn string_escape_spans() {
+    use edgeql_parser::position::Span;
+
+    let results: Vec<_> = Tokenizer::new(r#"select "a\qb" ++ 'ok\u12' ++ 'fine'"#)
+        .validated_values()
+        .collect();
+    assert_eq!(results.len(), 6);
+    let err = results[1].as_ref().unwrap_err();
+    assert_eq!(
+        err.message,
+        "invalid string literal: invalid escape sequence '\\q'"
+    );
+    assert_eq!(err.span, Span { start: 9, end: 11 });
+    let err = results[3].as_ref().unwrap_err();
+    assert_eq!(
+        err.message,
+        "invalid string literal: invalid escape sequence '\\u12'"
+    );
+    assert_eq!(err.span, Span { start: 20, end: 24 });
+    assert_eq!(results[4].as_ref().unwrap().kind, Concat);
+    assert_eq!(results[5].as_ref().unwrap().text, "'fine'");
+}
+
+#[test]
+fn unicode_escapes() {
+    use edgeql_parser::position::Span;
+
+    assert_eq!(
+        tok_str(r#"'\uD7FF' "\U0010FFFF" r'\uD800'"#),
+        [r#"'\uD7FF'"#, r#""\U0010FFFF""#, r#"r'\uD800'"#]
+    );
+    assert_eq!(
+        tok_err(r#"'\uD800'"#),
+        "invalid string literal: invalid escape sequence, \
+         U+D800 is a surrogate code point"
+    );
+    assert_eq!(
+        tok_err(r#""\U0000dfff""#),
+        "invalid string literal: invalid escape sequence, \
+         U+DFFF is a surrogate code point"
+    );
+    assert_eq!(
+        tok_err(r#"'\U00110000'"#),
+        "invalid string literal: invalid escape sequence, \
+         U+110000 is beyond the maximum code point U+10FFFF"
+    );
+    assert_eq!(
+        tok_err(r#""\UFFFFFFFF""#),
+        "invalid string literal: invalid escape sequence, \
+         U+FFFFFFFF is beyond the maximum code point U+10FFFF"
+    );
+
+    let err = Tokenizer::new(r#"select 'a\\\uDBFF'"#)
+        .nth(1)
+        .unwrap()
+        .unwrap_err();
+    assert_eq!(err.span, Span { start: 11, end: 17 });
+}
+
+#[test]
+fn bytes_values() {
+    use edgeql_parser::position::Span;
+    use edgeql_parser::tokenizer::Value;
+
+    let values: Vec<_> = Tokenizer::new(r#"b'\x00a\xFF\n' rb"\x00" b'\'\\'"#)
+        .validated_values()
+        .map(|t| t.unwrap().value)
+        .collect();
+    assert_eq!(
+        values,
+        [
+            Some(Value::Bytes(b"\x00a\xff\n".to_vec())),
+            Some(Value::Bytes(br"\x00".to_vec())),
+            Some(Value::Bytes(br"'\".to_vec())),
+        ]
+    );
+
+    let err = Tokenizer::new(r"select b'abc\xZZ'")
+        .validated_values()
+        .nth(1)
+        .unwrap()
+        .unwrap_err();
+    assert_eq!(
+        err.message,
+        "invalid bytes literal: invalid escape sequence '\\xZZ'"
+    );
+    assert_eq!(err.span, Span { start: 12, end: 16 });
+}
+
 #[test]
 fn string_prohibited_chars() {
     assert_eq!(
@@ -1096,3 +1186,251 @@ fn test_substitution() {
     );
     assert_eq!(tok_err("SELECT \\(some_name"), "unclosed \\(name) token");
 }
+
+#[cfg(feature = "future-syntax")]
+#[test]
+fn heredoc_strings() {
+    use edgeql_parser::tokenizer::Value;
+
+    let text = "\"\"\"\n    Long\n      text with 'quotes' and \\n\n\n    \"\"\"";
+    assert_eq!(tok_str(&format!("SELECT {text};")), ["SELECT", text, ";"]);
+    assert_eq!(
+        tok_typ(&format!("SELECT {text};")),
+        [keyword("select"), Str, Semicolon]
+    );
+    let token = Tokenizer::new(text)
+        .validated_values()
+        .next()
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        token.value,
+        Some(Value::String("Long\n  text with 'quotes' and \\n\n".into()))
+    );
+
+    assert_eq!(tok_str("'''a \"\"\" b'''"), ["'''a \"\"\" b'''"]);
+    assert_eq!(tok_str("'' ''"), ["''", "''"]);
+    assert_eq!(
+        tok_err("select \"\"\"\n  text\"\""),
+        "unterminated string, quoted by `\"\"\"`"
+    );
+}
+
+#[test]
+fn sql_blocks() {
+    use edgeql_parser::tokenizer::Value;
+
+    fn sql_tokens(s: &str) -> Result<Vec<(Kind, String)>, String> {
+        Tokenizer::new(s)
+            .with_sql_blocks()
+            .validated_values()
+            .map(|t| t.map(|t| (t.kind, t.text.to_string())))
+            .collect::<Result<_, _>>()
+            .map_err(|e| e.message)
+    }
+
+    let block = "{ SELECT '}' || \"a}\" || $q$ } $q$, $1 -- }\n/* { /* } */ */ FROM t(x) }";
+    let text = format!("create function f() -> int64 using sql {block}; select 1");
+    let tokens = sql_tokens(&text).unwrap();
+    assert_eq!(tokens[7], (keyword("using"), "using".into()));
+    assert_eq!(tokens[9], (Str, block.into()));
+    assert_eq!(tokens[10].0, Semicolon);
+    assert_eq!(tokens[12].1, "1");
+
+    let token = Tokenizer::new("USING SQL {select 'it''s'}")
+        .with_sql_blocks()
+        .validated_values()
+        .nth(2)
+        .unwrap()
+        .unwrap();
+    assert_eq!(token.value, Some(Value::String("select 'it''s'".into())));
+
+    // only right after `using sql`, a shape stays a shape
+    assert_eq!(sql_tokens("select sql { x }").unwrap().len(), 5);
+    assert_eq!(tok_typ("using sql {}")[2], OpenBrace);
+    assert_eq!(
+        sql_tokens("using sql { select '}").unwrap_err(),
+        "unterminated string in SQL block, quoted by `'`"
+    );
+    assert_eq!(
+        sql_tokens("using sql { select $x$ }").unwrap_err(),
+        "unterminated string in SQL block, quoted by `$x$`"
+    );
+    assert_eq!(
+        sql_tokens("using sql { select (1] }").unwrap_err(),
+        "unbalanced `]` in SQL block"
+    );
+    assert_eq!(
+        sql_tokens("using sql { select 1").unwrap_err(),
+        "unterminated SQL block"
+    );
+}
+
+#[test]
+fn limits() {
+    use edgeql_parser::options::{LimitError, ParserOptions};
+    use edgeql_parser::position::Span;
+
+    let options = ParserOptions {
+        max_input_len: Some(32),
+        max_token_len: Some(8),
+        ..ParserOptions::default()
+    };
+    let tokens = Tokenizer::new("select 'abcdef'")
+        .with_options(&options)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(tokens.len(), 2);
+
+    let mut s = Tokenizer::new("select 'abcdefgh'").with_options(&options);
+    assert_eq!(s.next().unwrap().unwrap().text, "select");
+    let err = s.next().unwrap().unwrap_err();
+    assert_eq!(
+        err.message,
+        "token is too long: 10 bytes, at most 8 are allowed"
+    );
+    assert_eq!(err.span, Span { start: 7, end: 17 });
+    assert_eq!(
+        s.limit_error(),
+        Some(&LimitError::TokenTooLong {
+            len: 10,
+            max: 8,
+            span: Span { start: 7, end: 17 },
+        })
+    );
+
+    let text = format!("select '{}", "x".repeat(64));
+    let mut s = Tokenizer::new(&text).with_options(&options);
+    assert!(s.next().unwrap().is_err());
+    assert_eq!(
+        s.limit_error(),
+        Some(&LimitError::InputTooLong { len: 72, max: 32 })
+    );
+    assert_eq!(
+        options.check_input(&text),
+        Err(LimitError::InputTooLong { len: 72, max: 32 })
+    );
+}
+
+#[test]
+fn docs() {
+    use edgeql_parser::tokenizer::token_docs;
+
+    let all = ":= -= += -> ?? :: .< .?> // ++ >= <= != ?= ?!= , ( ) [ ] { } . ; : \
+               + - ** * / % ^ < > = & | @ $x 1.5n 1.5 1 1n b'x' 'x' `x` x \\(x) select";
+    let kinds = tok_typ(all);
+    assert_eq!(kinds.len(), 49);
+    for kind in kinds {
+        let docs = token_docs(kind).unwrap_or_else(|| panic!("no docs for {kind:?}"));
+        assert!(!docs.description.is_empty());
+    }
+    assert_eq!(
+        token_docs(Coalesce).unwrap().url(),
+        "https://docs.geldata.com/reference/stdlib/set"
+    );
+    assert_eq!(token_docs(EOI), None);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serialize_options() {
+    use edgeql_parser::tokenizer::{SerializeOptions, Token};
+    use serde_json::json;
+
+    let tokens = Tokenizer::new("select x")
+        .validated_values()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let plain = SerializeOptions::default();
+    assert_eq!(
+        serde_json::to_value(plain.token(&tokens[0])).unwrap(),
+        json!({"kind": {"Keyword": "select"}, "text": "select", "value": null})
+    );
+    let full = SerializeOptions {
+        spans: true,
+        categories: true,
+    };
+    assert_eq!(
+        serde_json::to_value(full.token(&tokens[0])).unwrap(),
+        json!({
+            "kind": {"Keyword": "select"}, "text": "select", "value": null,
+            "span": {"start": 0, "end": 6}, "category": "reserved",
+        })
+    );
+    // categories are only added to keywords
+    let ident = serde_json::to_value(full.token(&tokens[1])).unwrap();
+    assert_eq!(ident["span"], json!({"start": 7, "end": 8}));
+    assert_eq!(ident.get("category"), None);
+
+    let json = serde_json::to_string(&plain.token(&tokens[1])).unwrap();
+    let token: Token = serde_json::from_str(&json).unwrap();
+    assert_eq!(token.kind, Ident);
+    assert_eq!(token.span.end, 0);
+    let json = serde_json::to_string(&full.token(&tokens[0])).unwrap();
+    let token: Token = serde_json::from_str(&json).unwrap();
+    assert_eq!(token.kind, keyword("select"));
+    assert_eq!(token.span.end, 6);
+}
+
+#[test]
+fn kind_ids() {
+    use edgeql_parser::tokenizer::KEYWORD_ID;
+
+    assert_eq!(Assign.id(), 0);
+    assert_eq!(keyword("select").id(), KEYWORD_ID);
+    assert_eq!(Kind::from_id(KEYWORD_ID), None);
+    assert_eq!(Kind::from_id(1000), None);
+    for id in (0..100).filter(|&id| id != KEYWORD_ID) {
+        if let Some(kind) = Kind::from_id(id) {
+            assert_eq!(kind.id(), id);
+        }
+    }
+    assert_eq!(Kind::from_id(StartSDLDocument.id()), Some(StartSDLDocument));
+}
+
+#[test]
+fn partial_input() {
+    fn partial(s: &str) -> (Vec<String>, Option<(u64, u64, String)>) {
+        let mut tokenizer = Tokenizer::new(s).with_at_eof(false).validated_values();
+        let tokens = tokenizer
+            .by_ref()
+            .map(|t| t.unwrap().text.to_string())
+            .collect();
+        let incomplete = tokenizer
+            .inner
+            .incomplete()
+            .map(|i| (i.span.start, i.span.end, i.message.clone()));
+        (tokens, incomplete)
+    }
+
+    assert_eq!(
+        partial("select 'abc"),
+        (
+            vec!["select".into()],
+            Some((7, 11, "unterminated string, quoted by `'`".into()))
+        )
+    );
+    assert_eq!(
+        partial("select `na"),
+        (
+            vec!["select".into()],
+            Some((7, 10, "unterminated backtick name".into()))
+        )
+    );
+    assert_eq!(
+        partial("select $$ab"),
+        (
+            vec!["select".into()],
+            Some((7, 11, "unterminated string started with $$".into()))
+        )
+    );
+    assert_eq!(
+        partial("select 'abc'"),
+        (vec!["select".into(), "'abc'".into()], None)
+    );
+    assert_eq!(tok_err("select 'abc"), "unterminated string, quoted by `'`");
+    // other errors are reported as usual
+    let mut tokenizer = Tokenizer::new("select ~").with_at_eof(false);
+    assert!(tokenizer.nth(1).unwrap().is_err());
+    assert_eq!(tokenizer.incomplete(), None);
+}
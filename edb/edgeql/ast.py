@@ -473,6 +473,26 @@ class Set(Expr):
     elements: list[Expr]
 
 
+class EmptySet(Set):
+    """An empty set constructor `{}`.
+
+    The parser keeps a cast written right before it (`<str>{}`) in `type`
+    instead of wrapping the set into a TypeCast.  Empty sets built by the
+    compiler are plain `Set(elements=[])`.
+    """
+
+    type: typing.Optional[TypeExpr] = None
+
+    def as_cast(self) -> Expr:
+        if self.type is None:
+            return self
+        return TypeCast(
+            expr=Set(elements=[], span=self.span),
+            type=self.type,
+            span=self.span,
+        )
+
+
 # Statements
 #
 
@@ -330,6 +330,8 @@ class EdgeQLSourceGenerator(codegen.SourceGenerator):
         self._visit_aliases(node)
 
         self._write_keywords('FOR ')
+        if node.optional:
+            self._write_keywords('OPTIONAL ')
         self.write(ident_to_str(node.iterator_alias))
         self._write_keywords(' IN ')
         self.visit(node.iterator)
@@ -531,6 +533,13 @@ class EdgeQLSourceGenerator(codegen.SourceGenerator):
         self.visit_list(node.elements, newlines=False)
         self.write('}')
 
+    def visit_EmptySet(self, node: qlast.EmptySet) -> None:
+        if node.type is not None:
+            self.write('<')
+            self.visit(node.type)
+            self.write('>')
+        self.write('{}')
+
     def visit_Array(self, node: qlast.Array) -> None:
         self.write('[')
         self.visit_list(node.elements, newlines=False)
@@ -570,6 +579,9 @@ class EdgeQLSourceGenerator(codegen.SourceGenerator):
                         qlast.QueryParameter,
                         qlast.FunctionParameter,
                     ),
+                ) and not (
+                    # a typed empty set is a cast, which binds looser
+                    isinstance(e, qlast.EmptySet) and e.type is not None
                 ):
                     self.visit(e)
                 else:
@@ -86,6 +86,8 @@ def ensure_ql_select(expr: qlast.Expr) -> qlast.SelectQuery:
 
 
 def is_ql_empty_set(expr: qlast.Expr) -> bool:
+    if isinstance(expr, qlast.EmptySet):
+        return expr.type is None
     return isinstance(expr, qlast.Set) and len(expr.elements) == 0
 
 
@@ -247,6 +247,15 @@ def compile_Set(expr: qlast.Set, *, ctx: context.ContextLevel) -> irast.Set:
         )
 
 
+@dispatch.compile.register(qlast.EmptySet)
+def compile_EmptySet(
+    expr: qlast.EmptySet, *, ctx: context.ContextLevel
+) -> irast.Set:
+    if expr.type is not None:
+        return dispatch.compile(expr.as_cast(), ctx=ctx)
+    return compile_Set(expr, ctx=ctx)
+
+
 @dispatch.compile.register(qlast.Constant)
 def compile_Constant(
     expr: qlast.Constant, *, ctx: context.ContextLevel
@@ -1194,7 +1203,10 @@ def compile_type_check_op(
 def flatten_set(expr: qlast.Set) -> list[qlast.Expr]:
     elements = []
     for el in expr.elements:
-        if isinstance(el, qlast.Set):
+        if isinstance(el, qlast.EmptySet) and el.type is not None:
+            # a typed empty set still contributes its type to the union
+            elements.append(el)
+        elif isinstance(el, qlast.Set):
             elements.extend(flatten_set(el))
         else:
             elements.append(el)
@@ -1388,6 +1388,13 @@ class BaseAtomicExpr(Nonterm):
         self.val = qlast.Path(steps=[kids[0].val], partial=True)
 
 
+def _cast(expr, typ):
+    # An empty set keeps its cast, so that `<str>{}` is a typed EmptySet
+    if isinstance(expr, qlast.EmptySet) and expr.type is None:
+        return qlast.EmptySet(elements=[], type=typ)
+    return qlast.TypeCast(expr=expr, type=typ, cardinality_mod=None)
+
+
 class Expr(Nonterm):
     val: qlast.Expr
     # BaseAtomicExpr
@@ -1562,11 +1569,7 @@ class Expr(Nonterm):
     @parsing.precedence(precedence.P_TYPECAST)
     def reduce_LANGBRACKET_FullTypeExpr_RANGBRACKET_Expr(
             self, *kids):
-        self.val = qlast.TypeCast(
-            expr=kids[3].val,
-            type=kids[1].val,
-            cardinality_mod=None,
-        )
+        self.val = _cast(kids[3].val, kids[1].val)
 
     @parsing.precedence(precedence.P_TYPECAST)
     def reduce_LANGBRACKET_OPTIONAL_FullTypeExpr_RANGBRACKET_Expr(
@@ -1693,7 +1696,10 @@ class NamedTupleElementList(ListNonterm, element=NamedTupleElement,
 
 class Set(Nonterm):
     def reduce_LBRACE_OptExprList_RBRACE(self, *kids):
-        self.val = qlast.Set(elements=kids[1].val)
+        if kids[1].val:
+            self.val = qlast.Set(elements=kids[1].val)
+        else:
+            self.val = qlast.EmptySet(elements=[])
 
 
 class Collection(Nonterm):
@@ -1870,11 +1876,7 @@ class AtomicExpr(Nonterm):
     @parsing.precedence(precedence.P_TYPECAST)
     def reduce_LANGBRACKET_FullTypeExpr_RANGBRACKET_AtomicExpr(
             self, *kids):
-        self.val = qlast.TypeCast(
-            expr=kids[3].val,
-            type=kids[1].val,
-            cardinality_mod=None,
-        )
+        self.val = _cast(kids[3].val, kids[1].val)
 
 
 # Duplication of Path above, but with BasicExpr at the root
@@ -48,7 +48,7 @@ class Source:
     def __init__(
         self,
         text: str,
-        tokens: list[ql_parser.OpaqueToken],
+        tokens: ql_parser.TokenBuffer | list[ql_parser.OpaqueToken],
         serialized: bytes,
     ) -> None:
         self._cache_key = hashlib.blake2b(serialized).digest()
@@ -65,7 +65,7 @@ class Source:
     def variables(self) -> dict[str, Any]:
         return {}
 
-    def tokens(self) -> list[ql_parser.OpaqueToken]:
+    def tokens(self) -> ql_parser.TokenBuffer | list[ql_parser.OpaqueToken]:
         return self._tokens
 
     def first_extra(self) -> Optional[int]:
@@ -89,7 +89,7 @@ class Source:
     @staticmethod
     def from_string(text: str) -> Source:
         result = _tokenize(text)
-        assert isinstance(result.out, list)
+        assert isinstance(result.out, ql_parser.TokenBuffer)
         return Source(text=text, tokens=result.out, serialized=result.pack())
 
     def __repr__(self):
@@ -189,7 +189,7 @@ def line_col_to_source_point(
 
 
 def _tokenize(eql: str) -> ql_parser.ParserResult:
-    result = ql_parser.tokenize(eql)
+    result = ql_parser.tokenize_buffer(eql)
 
     if len(result.errors) > 0:
         # TODO: emit multiple errors
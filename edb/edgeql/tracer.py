@@ -689,6 +689,12 @@ def trace_Set(node: qlast.Set, *, ctx: TracerContext) -> None:
         trace(el, ctx=ctx)
 
 
+@trace.register
+def trace_EmptySet(node: qlast.EmptySet, *, ctx: TracerContext) -> None:
+    if node.type is not None:
+        trace(node.as_cast(), ctx=ctx)
+
+
 @trace.register
 def trace_Tuple(node: qlast.Tuple, *, ctx: TracerContext) -> None:
     for el in node.elements:
@@ -674,6 +674,13 @@ def elab_Set(qle: qlast.Set):
     return MultiSetExpr(expr=[elab(e) for e in qle.elements])
 
 
+@elab.register(qlast.EmptySet)
+def elab_EmptySet(qle: qlast.EmptySet):
+    if qle.type is not None:
+        return elab(qle.as_cast())
+    return elab_Set(qle)
+
+
 def elab_aliases(
     aliases: Optional[Sequence[qlast.Alias]],
     tail_expr: Expr,
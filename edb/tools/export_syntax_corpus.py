@@ -0,0 +1,98 @@
+#
+# This source file is part of the EdgeDB open source project.
+#
+# Copyright 2024-present MagicStack Inc. and the EdgeDB authors.
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#     http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+#
+
+
+from __future__ import annotations
+
+import importlib
+import inspect
+import json
+import sys
+
+import click
+
+from edb.testbase import lang as tb
+from edb.tools.edb import edbcommands
+
+
+DEFAULT_MODULES = (
+    'tests.test_edgeql_syntax',
+    'tests.test_schema_syntax',
+)
+
+
+def iter_cases(module_name):
+    module = importlib.import_module(module_name)
+    for _, cls in inspect.getmembers(module, inspect.isclass):
+        if (
+            not issubclass(cls, tb.BaseSyntaxTest)
+            or cls.__module__ != module.__name__
+        ):
+            continue
+        try:
+            start = cls.get_grammar_token().__name__[2:]
+        except NotImplementedError:
+            continue
+
+        for name, meth in sorted(vars(cls).items()):
+            if not name.startswith('test_') or meth.__doc__ is None:
+                continue
+
+            # same as tb.DocTestMeta
+            doc = meth.__doc__
+            source, _, output = doc.partition('\n% OK %')
+            if not output:
+                source, _, _ = doc.partition('\n% ERROR %')
+
+            spec = getattr(meth, 'test_spec', {})
+            error = None
+            if 'must_fail' in spec:
+                args, _ = spec['must_fail']
+                if len(args) > 1:
+                    error = args[1]
+
+            yield {
+                'name': f'{cls.__name__}.{name}',
+                'start': start,
+                'source': source,
+                'accept': 'must_fail' not in spec,
+                'error': error,
+            }
+
+
+@edbcommands.command('export-syntax-corpus')
+@click.option('-o', '--output', type=click.File('w'), default='-',
+              help='file to write the corpus to, defaults to stdout')
+@click.argument('modules', nargs=-1, metavar='MODULE...')
+def export_syntax_corpus(output, modules):
+    """Export the syntax tests as a corpus of JSON lines.
+
+    The corpus can be replayed against the Rust parser with
+    `edgeql-difftest` to find cases the two parsers disagree on.
+
+    MODULE - test modules to export, defaults to the EdgeQL and SDL
+    syntax tests
+    """
+
+    count = 0
+    for module in modules or DEFAULT_MODULES:
+        for case in iter_cases(module):
+            output.write(json.dumps(case) + '\n')
+            count += 1
+
+    print(f'exported {count} cases', file=sys.stderr)
@@ -46,6 +46,7 @@ def main() -> None:
 
             //! Abstract Syntax Tree for EdgeQL
             #![allow(non_camel_case_types)]
+            #![cfg(never)] // TODO: migrate cpython-rust to pyo3
 
             use indexmap::IndexMap;
 
@@ -230,6 +231,21 @@ def translate_type(
         if typ._name == 'Dict':
             return f'IndexMap<{params[0]}, {params[1]}>'
 
+    if typing.get_origin(typ) is list:
+        return f'Vec<{params[0]}>'
+
+    if typing.get_origin(typ) is dict:
+        return f'IndexMap<{params[0]}, {params[1]}>'
+
+    if typing.get_origin(typ) is tuple:
+        if len(params) > 1 and params[1] == 'Ellipsis':
+            return f'Vec<{params[0]}>'
+        else:
+            return '(' + ', '.join(params) + ')'
+
+    if typing.get_origin(typ) is typing.Literal:
+        return 'String'
+
     if not hasattr(typ, '__name__'):
         return str(typ)
 
@@ -1186,6 +1186,7 @@ setuptools.setup(
             path="edb/edgeql-parser/edgeql-parser-python/Cargo.toml",
             features=["python_extension"],
             binding=setuptools_rust.Binding.PyO3,
+            py_limited_api=True,
         ),
         setuptools_rust.RustExtension(
             "edb._graphql_rewrite",
@@ -17,14 +17,18 @@
 #
 
 
+import json
 import re
+import struct
 import unittest  # NOQA
 
 from edb import errors
 
 from edb.testbase import lang as tb
+from edb.edgeql import ast as qlast
 from edb.edgeql import generate_source as edgeql_to_source
 from edb.edgeql import tokenizer
+from edb.edgeql import parser as qlparser
 from edb.edgeql.parser import grammar as qlgrammar
 from edb.tools import test
 
@@ -1933,6 +1937,125 @@ aa';
         };
         """
 
+    @tb.must_fail(errors.EdgeQLSyntaxError,
+                  "Unexpected '1'", line=4, col=17)
+    def test_edgeql_syntax_shape_recovery_01(self):
+        """
+        select Foo {
+            bar,
+            baz 1 2,
+            boo,
+        };
+        """
+
+    def _parse_result(self, source):
+        qlparser.preload_spec()
+        source = tokenizer.Source.from_string(source)
+        result, _ = qlparser.rust_parser.parse('STARTBLOCK', source.tokens())
+        return result
+
+    def test_edgeql_syntax_shape_recovery_02(self):
+        result = self._parse_result(
+            """
+            select Foo {
+                bar,
+                baz := 1 2,
+                sub: { a, b 1 c, e },
+                boo,
+                qux := .a + * .b,
+                last,
+            };
+            """
+        )
+        # one error per invalid element
+        self.assertEqual(len(result.errors), 3)
+        self.assertEqual(result.errors[0][0], "Unexpected '2'")
+        self.assertIsNotNone(result.out)
+
+    def test_edgeql_syntax_shape_recovery_03(self):
+        # the rest of the statement is still parsed after a recovered shape
+        result = self._parse_result(
+            """
+            select Foo { bar baz, boo } filter .bar = 1 order by .boo;
+            select Bar { a, b };
+            """
+        )
+        self.assertEqual(len(result.errors), 1)
+        self.assertIsNotNone(result.out)
+
+    @tb.must_fail(errors.EdgeQLSyntaxError,
+                  "Missing ';'",
+                  hint="statements must be separated by semicolons, "
+                       "insert ';' before keyword 'SELECT'",
+                  line=2, col=24)
+    def test_edgeql_syntax_missing_semicolon_01(self):
+        """
+        create type Foo
+        select Foo;
+        """
+
+    def test_edgeql_syntax_missing_semicolon_02(self):
+        result = self._parse_result(
+            """
+            select User { name } filter .name = 'x'
+            insert User { name := 'y' }
+            delete User;
+            select 1;
+            """
+        )
+        self.assertEqual(
+            [message for message, *_ in result.errors],
+            ["Missing ';'", "Missing ';'"],
+        )
+        self.assertIsNotNone(result.out)
+
+    def test_edgeql_syntax_cst_buffer_01(self):
+        qlparser.preload_spec()
+        source = tokenizer.Source.from_string("select (1, 2")
+        result, _ = qlparser.rust_parser.parse_to_buffer(
+            'STARTBLOCK', source.tokens())
+        self.assertEqual(len(result.errors), 1)
+
+        buffer, terminals = result.out
+        words = struct.unpack(f'<{len(buffer) // 8}Q', buffer)
+        # the root is a production: tag, id, number of args, start, end
+        self.assertEqual(words[0], 3)
+        self.assertIn('select', [t.text for t in terminals])
+        # the missing paren is injected by error recovery
+        self.assertEqual(len([t for t in terminals if t.is_placeholder]), 1)
+
+    def test_edgeql_syntax_token_buffer_01(self):
+        tokens = tokenizer.Source.from_string("select 1 + 22").tokens()
+        # (start, end) of every token, including the end of input
+        self.assertEqual(
+            memoryview(tokens).tolist(),
+            [[0, 6], [7, 8], [9, 10], [11, 13], [13, 13]],
+        )
+        self.assertEqual(len(tokens), 5)
+        self.assertEqual(tokens[-2].span_start(), 11)
+        self.assertEqual(len(tokens[1:3]), 2)
+        self.assertFalse(tokens[0].is_ident())
+
+    def test_edgeql_syntax_tolerant_01(self):
+        qlparser.preload_spec()
+        source = tokenizer.Source.from_string("select User { name, friends: {")
+        result, _ = qlparser.rust_parser.parse(
+            'STARTBLOCK', source.tokens(), tolerant=True)
+        self.assertEqual(result.errors, [])
+        self.assertIsNotNone(result.out)
+
+    def test_edgeql_syntax_render_errors_01(self):
+        text = "select 1 2;"
+        result = self._parse_result(text)
+        rendered = qlparser.rust_parser.render_errors(
+            text, 'query.edgeql', result.errors)
+        self.assertIn("--> query.edgeql:1:10", rendered)
+        as_json = json.loads(qlparser.rust_parser.render_errors(
+            text, 'query.edgeql', result.errors, json=True))
+        self.assertEqual(as_json['version'], 1)
+        [diagnostic] = as_json['diagnostics']
+        self.assertEqual(diagnostic['spans'][0]['start']['column'], 10)
+
     def test_edgeql_syntax_struct_01(self):
         """
         SELECT (
@@ -3230,6 +3353,15 @@ aa';
         SELECT (((1 INTERSECT 2) EXCEPT (3 INTERSECT 4)) UNION 5);
         """
 
+    def test_edgeql_syntax_set_14(self):
+        """
+        SELECT <str>{};
+        SELECT {1, <int64>{}, {}};
+        SELECT (<Foo>{}).bar;
+        SELECT <optional str>{};
+        SELECT <int64><str>{};
+        """
+
     def test_edgeql_syntax_insert_01(self):
         """
         INSERT Foo;
@@ -3714,6 +3846,27 @@ aa';
         INSERT Foo { y := y };
         """
 
+    def test_edgeql_syntax_optionalfor_01(self):
+        """
+        FOR OPTIONAL x IN {(SELECT User FILTER (.name = 'a'))}
+        UNION (INSERT Log { target := x });
+        """
+
+    def test_edgeql_syntax_optionalfor_02(self):
+        """
+        FOR OPTIONAL x IN {1}
+        INSERT Foo { x := x };
+        """
+
+    def test_edgeql_syntax_nestedfor_01(self):
+        """
+        FOR x IN {(1, 'a'), (2, 'b')}
+        UNION (
+            FOR OPTIONAL y IN {(SELECT User FILTER (.name = x.1))}
+            UNION (SELECT (x.0, y))
+        );
+        """
+
     def test_edgeql_syntax_coalesce_01(self):
         """
         SELECT (a ?? x);
@@ -6098,6 +6251,28 @@ aa';
         };
         """
 
+    def test_edgeql_syntax_ddl_index_14(self):
+        """
+        CREATE TYPE Foo {
+            CREATE INDEX ON ((.a, .b));
+            CREATE INDEX ON ((.a, .b)) EXCEPT (.c);
+            CREATE DEFERRED INDEX ON ((.a, .b)) EXCEPT (.c);
+            CREATE DEFERRED INDEX fts::index ON (.a) EXCEPT (.c);
+            CREATE DEFERRED INDEX myindex1(num := 13, val := 'ab')
+                ON ((.a, .b)) EXCEPT (.c);
+        };
+        """
+
+    def test_edgeql_syntax_ddl_index_15(self):
+        """
+        ALTER TYPE Foo {
+            ALTER INDEX ON ((.a, .b)) EXCEPT (.c) SET DEFERRED;
+            ALTER INDEX myindex1(num := 13) ON ((.a, .b)) EXCEPT (.c)
+                DROP DEFERRED;
+            DROP INDEX fts::index ON (.a) EXCEPT (.c);
+        };
+        """
+
     def test_edgeql_syntax_ddl_global_01(self):
         """
         CREATE GLOBAL Foo := (SELECT User);
@@ -6695,3 +6870,52 @@ class TestEdgeQLTokenSerialization(unittest.TestCase):
         self.assertEqual(src1.extra_counts(), src2.extra_counts())
         self.assertEqual(src1.extra_blobs(), src2.extra_blobs())
         self.assertIs(src1.serialize(), src2.serialize())
+
+
+class TestEdgeQLGrammar(unittest.TestCase):
+    def test_edgeql_grammar_conflicts(self):
+        # spec_to_json keeps only the first action of every token, so
+        # conflicts must be caught here, before the tables are serialized.
+        from edb.common import parsing
+
+        spec = parsing.load_parser_spec(qlgrammar.start)
+
+        conflicts = []
+        for state, st_actions in enumerate(spec.actions()):
+            for tok, acts in st_actions.items():
+                if len(acts) < 2:
+                    continue
+
+                described = []
+                shifts = 0
+                for act in acts:
+                    if 'ShiftAction' in str(type(act)):
+                        shifts += 1
+                        described.append(f'shift to state {act.nextState}')
+                    else:
+                        described.append(f'reduce {act.production}')
+
+                kind = 'shift/reduce' if shifts else 'reduce/reduce'
+                conflicts.append(
+                    f'state {state}: {kind} conflict on {tok}: '
+                    + ', '.join(described)
+                )
+
+        if conflicts:
+            self.fail(
+                f'{len(conflicts)} conflicts in the grammar:\n'
+                + '\n'.join(conflicts)
+            )
+
+    def test_edgeql_grammar_empty_set(self):
+        qlparser.preload_spec()
+        query = qlparser.parse_query('select {1, <str>{}, {}}')
+
+        assert isinstance(query, qlast.SelectQuery)
+        one, typed, untyped = query.result.elements
+        self.assertIs(type(query.result), qlast.Set)
+        self.assertIs(type(one), qlast.Constant)
+        self.assertIsInstance(typed, qlast.EmptySet)
+        self.assertEqual(typed.type.maintype.name, 'str')
+        self.assertIsInstance(untyped, qlast.EmptySet)
+        self.assertIsNone(untyped.type)
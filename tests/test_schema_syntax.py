@@ -869,6 +869,31 @@ class TestEdgeSchemaParser(SchemaSyntaxTest):
         };
         """
 
+    def test_eschema_syntax_index_11(self):
+        """
+        module test {
+            type Foo {
+                property a: str;
+                property b: str;
+                property c: bool;
+
+                index on ((.a, .b));
+                index on ((.a, .b)) except (.c);
+                deferred index on ((.a, .b)) except (.c);
+                deferred index fts::index on (
+                    fts::with_options(.a, language := fts::Language.eng)
+                ) except (.c);
+                deferred index myindex1(num := 13, val := 'ab')
+                    on ((.a, .b)) except (.c) {
+                    annotation title := 'Tuple index';
+                };
+                index myindex2(num := 13) on ((.a, .b)) except (.c) {
+                    annotation title := 'Tuple index';
+                };
+            };
+        };
+        """
+
     def test_eschema_syntax_ws_01(self):
         """
         module test {